@@ -1,6 +1,6 @@
-use criterion::{BenchmarkId, Criterion};
+use criterion::{BatchSize, BenchmarkId, Criterion};
 use orderbook_rs::OrderBook;
-use pricelevel::{Id, Side, TimeInForce};
+use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
 use std::hint::black_box;
 
 /// Register all benchmarks for adding orders to an order book
@@ -88,3 +88,60 @@ pub fn register_benchmarks(c: &mut Criterion) {
 
     group.finish();
 }
+
+/// A non-crossing ladder of `count` resting orders over 1,000 levels per side.
+fn resting_orders(count: u64) -> Vec<OrderType<()>> {
+    (0..count)
+        .map(|i| {
+            let offset = u128::from(i % 1_000);
+            let (price, side) = if i % 2 == 0 {
+                (10_000 - offset, Side::Buy)
+            } else {
+                (10_001 + offset, Side::Sell)
+            };
+            OrderType::Standard {
+                id: Id::new_uuid(),
+                price: Price::new(price),
+                quantity: Quantity::new(10),
+                side,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(i),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            }
+        })
+        .collect()
+}
+
+/// Register benchmarks comparing bulk loading with sequential adds
+pub fn register_bulk_load_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("OrderBook - Bulk Load");
+    group.sample_size(10);
+    let orders = resting_orders(100_000);
+
+    group.bench_function("sequential_add_100k", |b| {
+        b.iter_batched(
+            || orders.clone(),
+            |orders| {
+                let order_book: OrderBook = OrderBook::new("TEST-SYMBOL");
+                for order in orders {
+                    let _ = black_box(order_book.add_order(order));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("bulk_load_100k", |b| {
+        b.iter_batched(
+            || orders.clone(),
+            |orders| {
+                let order_book: OrderBook = OrderBook::new("TEST-SYMBOL");
+                let _ = black_box(order_book.bulk_load(orders, true));
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
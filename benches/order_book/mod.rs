@@ -10,6 +10,7 @@ pub mod update_orders;
 // Import common benchmarks into the main bench group
 pub fn register_benchmarks(c: &mut criterion::Criterion) {
     add_orders::register_benchmarks(c);
+    add_orders::register_bulk_load_benchmarks(c);
     match_orders::register_benchmarks(c);
     update_orders::register_benchmarks(c);
     mixed_operations::register_benchmarks(c);
@@ -17,4 +18,6 @@ pub fn register_benchmarks(c: &mut criterion::Criterion) {
     mass_cancel::register_benchmarks(c);
     snapshot::register_benchmarks(c);
     replay::register_benchmarks(c);
+    replay::register_batch_benchmarks(c);
+    replay::register_compact_benchmarks(c);
 }
@@ -115,5 +115,148 @@ pub fn register_benchmarks(c: &mut Criterion) {
         );
     }
 
+    // ─── Cold replay: mmap vs. load into memory ────────────────────
+    #[cfg(feature = "journal")]
+    cold_replay(&mut group);
+
+    group.finish();
+}
+
+/// Compare replaying a persisted journal through [`MmapJournal`] with first
+/// loading it into an [`InMemoryJournal`].
+#[cfg(feature = "journal")]
+fn cold_replay(group: &mut criterion::BenchmarkGroup<'_, criterion::measurement::WallTime>) {
+    use orderbook_rs::orderbook::sequencer::{FileJournal, MmapJournal};
+
+    for &event_count in &[1_000, 10_000] {
+        let dir = tempfile::tempdir().expect("tempdir");
+        {
+            let file_journal: FileJournal<()> = FileJournal::open(dir.path()).expect("open");
+            let source = make_journal(event_count);
+            for entry in source.read_from(0).expect("read") {
+                let entry = entry.expect("entry");
+                file_journal.append(&entry.event).expect("append");
+            }
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("cold_replay_mmap", event_count),
+            &event_count,
+            |b, _| {
+                b.iter(|| {
+                    let journal: MmapJournal<()> =
+                        MmapJournal::open(dir.path()).expect("open mmap");
+                    let _ = black_box(
+                        ReplayEngine::<()>::replay_from(&journal, 0, "BENCH")
+                            .expect("replay must succeed"),
+                    );
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("cold_replay_in_memory", event_count),
+            &event_count,
+            |b, _| {
+                b.iter(|| {
+                    let file_journal: FileJournal<()> =
+                        FileJournal::open(dir.path()).expect("open");
+                    let journal = InMemoryJournal::<()>::new();
+                    for entry in file_journal.read_from(0).expect("read") {
+                        let _ = journal.append(&entry.expect("entry").event);
+                    }
+                    let _ = black_box(
+                        ReplayEngine::<()>::replay_from(&journal, 0, "BENCH")
+                            .expect("replay must succeed"),
+                    );
+                });
+            },
+        );
+    }
+}
+
+/// Compare executing queued commands one at a time with batched execution.
+pub fn register_batch_benchmarks(c: &mut Criterion) {
+    use orderbook_rs::OrderBook;
+    use orderbook_rs::orderbook::sequencer::BatchExecutor;
+    use std::collections::VecDeque;
+
+    let mut group = c.benchmark_group("OrderBook - Batch Execution");
+    let commands: Vec<SequencerCommand<()>> = make_journal(10_000)
+        .read_from(0)
+        .expect("read")
+        .map(|entry| entry.expect("entry").event.command)
+        .collect();
+
+    for &batch_size in &[1, 16, 256] {
+        group.bench_with_input(
+            BenchmarkId::new("execute_batch", batch_size),
+            &batch_size,
+            |b, &size| {
+                b.iter_with_setup(
+                    || {
+                        let pending: VecDeque<_> = commands.iter().cloned().collect();
+                        (
+                            OrderBook::<()>::new("BENCH"),
+                            BatchExecutor::new(size),
+                            pending,
+                        )
+                    },
+                    |(book, mut executor, mut pending)| {
+                        while !pending.is_empty() {
+                            black_box(executor.execute_batch(&book, &mut pending));
+                        }
+                    },
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Benchmark converting 1M full events to their compact form.
+///
+/// Half the events carry a trade result. Counting each result's heap
+/// allocations, the full events take about 544 MiB and the compact ones
+/// 259 MiB.
+pub fn register_compact_benchmarks(c: &mut Criterion) {
+    use orderbook_rs::OrderBook;
+    use orderbook_rs::orderbook::sequencer::{CompactEvent, execute_command};
+
+    const EVENT_COUNT: usize = 1_000_000;
+
+    // Resting adds interleaved with market orders that trade, so half the
+    // full events carry a trade result
+    let book: OrderBook<()> = OrderBook::new("BENCH");
+    let events: Vec<SequencerEvent<()>> = (0..EVENT_COUNT)
+        .map(|i| {
+            let command = if i % 2 == 0 {
+                make_add_event(i as u64, Id::new_uuid(), 1000, 10, Side::Sell).command
+            } else {
+                SequencerCommand::MarketOrder {
+                    id: Id::new_uuid(),
+                    quantity: 10,
+                    side: Side::Buy,
+                }
+            };
+            let result = execute_command(&book, &command);
+            SequencerEvent {
+                sequence_num: i as u64,
+                timestamp_ns: i as u64,
+                command,
+                result,
+            }
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("Journal - Compact Events");
+    group.sample_size(10);
+    group.bench_function("compact_1m_events", |b| {
+        b.iter(|| {
+            let compact: Vec<CompactEvent<()>> = events.iter().map(CompactEvent::from).collect();
+            black_box(compact)
+        });
+    });
     group.finish();
 }
@@ -259,34 +259,68 @@ mod utils;
 
 #[cfg(feature = "bincode")]
 pub use orderbook::BincodeEventSerializer;
-#[cfg(feature = "journal")]
-pub use orderbook::FileJournal;
 #[cfg(feature = "nats")]
 pub use orderbook::NatsTradePublisher;
+pub use orderbook::arrival::{ArrivalOrder, QueuePlace};
+pub use orderbook::auction::{AuctionResult, TradingState};
+pub use orderbook::crossed_check::CrossedBookListener;
+pub use orderbook::external_bbo::ExternalBboPolicy;
+pub use orderbook::fee_ledger::FeeRounding;
+pub use orderbook::fixed_price::FixedPrice;
+pub use orderbook::iceberg_refill::IcebergRefillPolicy;
 pub use orderbook::implied_volatility::{
     BlackScholes, IVConfig, IVError, IVParams, IVQuality, IVResult, OptionType, PriceSource,
     SolverConfig,
 };
 pub use orderbook::iterators::LevelInfo;
+pub use orderbook::level_changes::LevelChangeSet;
+pub use orderbook::lifetime_counters::LifetimeCounters;
 pub use orderbook::manager::{BookManager, BookManagerStd, BookManagerTokio};
 pub use orderbook::market_impact::{MarketImpact, OrderSimulation};
+pub use orderbook::match_observer::{MatchObserver, MatchOrderKind};
+pub use orderbook::matching::{
+    IncomingOrder, LevelMatch, MatchConfig, StpCancelledMaker, match_against_levels,
+};
+pub use orderbook::modifications::{
+    AddOrderResult, AddOutcome, CancelOutcome, RemainderOutcome, RemainderPolicy,
+};
+pub use orderbook::modify_cross::ModifyCrossPolicy;
 pub use orderbook::order_state::{
     CancelReason, OrderStateListener, OrderStateTracker, OrderStatus,
 };
+pub use orderbook::parked::{MarketOrderEmptyBookPolicy, ParkedMarketOrder};
+pub use orderbook::pro_rata::{ProRataRemainderRule, allocate_pro_rata};
+pub use orderbook::published::SnapshotPublishPolicy;
+pub use orderbook::reduce_only::ReduceOnlyPolicy;
+pub use orderbook::reference_price::ReferencePricePolicy;
 pub use orderbook::sequencer::{
-    InMemoryJournal, Journal, JournalEntry, JournalError, JournalReadIter, ReplayEngine,
-    ReplayError, SequencerCommand, SequencerEvent, SequencerResult, snapshots_match,
+    BatchExecutor, BookRegistry, CompactEvent, CompactJournal, CompactResult, ConfigChange,
+    ConflatedDeltas, DeltaConflator, DispatchOutcome, EventListeners, InMemoryJournal, Journal,
+    JournalEntry, JournalError, JournalReadIter, LevelDifference, ListenerId, ListenerPanicPolicy,
+    ReplayEngine, ReplayError, ReplayedEvent, RetentionPolicy, SequencerCommand, SequencerEvent,
+    SequencerListener, SequencerMetrics, SequencerResult, SnapshotComparison, SymbolCommand,
+    SymbolEvent, contingent_events, execute_command, snapshot_diff, snapshots_match,
 };
 pub use orderbook::serialization::{EventSerializer, JsonEventSerializer, SerializationError};
-pub use orderbook::snapshot::{EnrichedSnapshot, MetricFlags};
+pub use orderbook::simulation::FillSimulation;
+pub use orderbook::snapshot::{
+    AggregatedLevel, AggregatedSnapshot, ColumnarSnapshot, EnrichedSnapshot, MetricFlags,
+    PackageDelta, Quote, RestoreScope, SnapshotOptions,
+};
 pub use orderbook::statistics::{DepthStats, DistributionBin};
-pub use orderbook::stp::STPMode;
-pub use orderbook::trade::{TradeListener, TradeResult};
+pub use orderbook::stp::{STPMakerScope, STPMode, STPTriggered};
+pub use orderbook::tick_rounding::{RoundingMode, TickRounding};
+pub use orderbook::timestamp_policy::TimestampPolicy;
+pub use orderbook::trade::{AveragePrice, TradeFill, TradeListener, TradeResult, TradeSummary};
+pub use orderbook::trailing_stop::{PendingTrailingStop, TrailAmount, TrailingStopStatus};
 #[cfg(feature = "nats")]
 pub use orderbook::{BookChangeBatch, BookChangeEntry, NatsBookChangePublisher};
 pub use orderbook::{
-    FeeSchedule, ManagerError, MassCancelResult, OrderBook, OrderBookError, OrderBookSnapshot,
+    FeeSchedule, FeeTier, ManagerError, MassCancelResult, OrderBook, OrderBookBuilder,
+    OrderBookError, OrderBookSnapshot, OrderRole,
 };
+#[cfg(feature = "journal")]
+pub use orderbook::{FileJournal, MmapJournal};
 pub use utils::current_time_millis;
 
 /// Legacy type alias for `OrderBook<()>` to maintain backward compatibility.
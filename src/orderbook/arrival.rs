@@ -0,0 +1,173 @@
+//! Arrival order of resting orders within their price levels.
+//!
+//! A price level matches its orders first in, first out, but pricelevel
+//! only exposes them sorted by timestamp. That differs from the queue when
+//! orders arrive out of timestamp order, and cannot separate orders added
+//! in the same millisecond. [`ArrivalOrder`] numbers each order as it joins
+//! the back of its level's queue, so the book can report the queue the
+//! engine really matches: [`OrderBook::queue_position`](crate::OrderBook::queue_position),
+//! [`OrderBook::orders_in_match_order`](crate::OrderBook::orders_in_match_order)
+//! and the self-trade prevention scans of
+//! [`match_against_levels`](crate::orderbook::matching::match_against_levels)
+//! read it.
+//!
+//! The numbering mirrors pricelevel's queue, which keeps an order's place
+//! for as long as the order has an entry in it. Every time an order is
+//! pushed onto its level it gains an entry at the back: when it rests, when
+//! its quantity changes, and when a trade leaves it partially filled or
+//! refills its visible slice. Its place is its oldest entry. A trade takes
+//! that entry, so a partially filled order falls back to its next entry,
+//! while a quantity change, which adds an entry behind the oldest, keeps
+//! its place. Orders added concurrently from several threads are numbered
+//! in an unspecified relative order.
+
+use super::book::OrderBook;
+use dashmap::DashMap;
+use pricelevel::{Id, OrderType, PriceLevel};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The queue entries of a resting order, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct QueuePlace(VecDeque<u64>);
+
+/// Queue positions of resting orders, in the order they joined the back of
+/// their level's queue.
+#[derive(Debug, Default)]
+pub struct ArrivalOrder {
+    entries: DashMap<Id, QueuePlace>,
+    next: AtomicU64,
+}
+
+impl ArrivalOrder {
+    /// Create an empty arrival order.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `order_id` was pushed onto the back of its level's queue.
+    pub fn push_back(&self, order_id: Id) {
+        let entry = self.next.fetch_add(1, Ordering::Relaxed);
+        self.entries.entry(order_id).or_default().0.push_back(entry);
+    }
+
+    /// Record that a trade took the oldest entry of `order_id` and pushed
+    /// the order, still resting, back onto its level.
+    pub fn requeue(&self, order_id: Id) {
+        if let Some(mut place) = self.entries.get_mut(&order_id) {
+            place.0.pop_front();
+        }
+        self.push_back(order_id);
+    }
+
+    /// Returns the position `order_id` holds, if it is tracked. Positions
+    /// only compare within a level; they are not dense.
+    #[must_use]
+    pub fn position(&self, order_id: Id) -> Option<u64> {
+        self.entries
+            .get(&order_id)
+            .and_then(|place| place.0.front().copied())
+    }
+
+    /// Stop tracking `order_id`, returning the place it held.
+    pub fn remove(&self, order_id: Id) -> Option<QueuePlace> {
+        self.entries.remove(&order_id).map(|(_, place)| place)
+    }
+
+    /// Give `order_id` back a `place` it held before, ahead of any entry it
+    /// gained since, as pricelevel does for an order removed from a level
+    /// and pushed onto it again.
+    pub fn restore(&self, order_id: Id, place: QueuePlace) {
+        let mut current = self.entries.entry(order_id).or_default();
+        let mut entries = place.0;
+        entries.append(&mut current.0);
+        current.0 = entries;
+    }
+
+    /// Stop tracking every order.
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    /// Sort the orders of one level into queue order. Untracked orders go
+    /// last, in the order given.
+    pub fn sort(&self, orders: &mut [Arc<OrderType<()>>]) {
+        orders.sort_by_cached_key(|order| self.position(order.id()).unwrap_or(u64::MAX));
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns the orders resting at `level` in the order the engine
+    /// matches them, front of the queue first.
+    pub(super) fn queue_orders(&self, level: &PriceLevel) -> Vec<Arc<OrderType<()>>> {
+        let mut orders = level.snapshot_orders();
+        self.arrivals.sort(&mut orders);
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn order(id: Id, timestamp: u64) -> Arc<OrderType<()>> {
+        Arc::new(OrderType::Standard {
+            id,
+            price: Price::new(100),
+            quantity: Quantity::new(1),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+    }
+
+    #[test]
+    fn test_sort_follows_arrival_not_timestamp() {
+        let arrivals = ArrivalOrder::new();
+        let (late, early, untracked) = (Id::new_uuid(), Id::new_uuid(), Id::new_uuid());
+        arrivals.push_back(late);
+        arrivals.push_back(early);
+
+        let mut orders = vec![order(untracked, 0), order(early, 2), order(late, 3)];
+        arrivals.sort(&mut orders);
+        let ids: Vec<Id> = orders.iter().map(|order| order.id()).collect();
+        assert_eq!(ids, vec![late, early, untracked]);
+    }
+
+    #[test]
+    fn test_place_is_the_oldest_entry() {
+        let arrivals = ArrivalOrder::new();
+        let (first, second, third) = (Id::new_uuid(), Id::new_uuid(), Id::new_uuid());
+        arrivals.push_back(first);
+        arrivals.push_back(second);
+        let mut orders = vec![order(first, 1), order(second, 2), order(third, 3)];
+
+        // A quantity change keeps the place, and leaves an entry behind
+        // the second order
+        arrivals.push_back(first);
+        arrivals.push_back(third);
+        arrivals.sort(&mut orders);
+        assert_eq!(orders[0].id(), first);
+
+        // A trade takes the oldest entry, falling back to the next one
+        arrivals.requeue(first);
+        arrivals.sort(&mut orders);
+        let ids: Vec<Id> = orders.iter().map(|order| order.id()).collect();
+        assert_eq!(ids, vec![second, first, third]);
+
+        let place = arrivals.remove(second).unwrap();
+        assert_eq!(arrivals.position(second), None);
+        arrivals.push_back(second);
+        arrivals.restore(second, place);
+        arrivals.sort(&mut orders);
+        assert_eq!(orders[0].id(), second);
+    }
+}
@@ -0,0 +1,338 @@
+//! Auction accumulation and uncross.
+//!
+//! In [`TradingState::Auction`] incoming limit orders rest without
+//! continuous matching, so bids and asks are allowed to cross. Cancels and
+//! modifications remain allowed. [`OrderBook::run_auction_uncross`] then
+//! executes all crossing interest at a single clearing price and returns
+//! the book to [`TradingState::Continuous`].
+
+use super::book::{OrderBook, level_quantity};
+use super::error::OrderBookError;
+use super::matching::IncomingOrder;
+use pricelevel::{Hash32, Id, Price, Quantity, Side, Trade};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::sync::atomic::Ordering;
+use tracing::trace;
+
+/// Trading state of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum TradingState {
+    /// Incoming orders match continuously against resting liquidity (default).
+    #[default]
+    Continuous,
+    /// Incoming limit orders rest without matching until
+    /// [`OrderBook::run_auction_uncross`] is called. Market, IOC and FOK
+    /// orders are rejected.
+    Auction,
+}
+
+impl std::fmt::Display for TradingState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradingState::Continuous => write!(f, "Continuous"),
+            TradingState::Auction => write!(f, "Auction"),
+        }
+    }
+}
+
+/// Outcome of an auction uncross.
+#[derive(Debug, Clone)]
+pub struct AuctionResult {
+    /// The single price all auction trades executed at, or `None` if the
+    /// book was not crossed.
+    pub clearing_price: Option<u128>,
+    /// Total quantity executed at the clearing price.
+    pub executed_quantity: u64,
+    /// Trades executed during the uncross. The buy order is reported as the
+    /// taker and the sell order as the maker.
+    pub trades: Vec<Trade>,
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the trading state of the book.
+    pub fn set_trading_state(&self, state: TradingState) {
+        self.trading_state.store(state);
+        trace!("Order book {}: Trading state set to {}", self.symbol, state);
+    }
+
+    /// Returns the current trading state.
+    #[must_use]
+    pub fn trading_state(&self) -> TradingState {
+        self.trading_state.load()
+    }
+
+    /// Returns `true` while the book is accumulating orders for an auction.
+    #[must_use]
+    pub fn is_auction(&self) -> bool {
+        self.trading_state.load() == TradingState::Auction
+    }
+
+    /// Returns the price that would clear the auction and the quantity that
+    /// would execute there, or `None` if the book is not crossed.
+    ///
+    /// The clearing price maximises executed volume. Ties are broken by the
+    /// smallest imbalance between buy and sell volume, then by proximity to
+    /// the active reference price (if any), then by the lowest price.
+    #[must_use]
+    pub fn indicative_clearing_price(&self) -> Option<(u128, u64)> {
+        let bids: Vec<(u128, u64)> = self
+            .bids
+            .iter()
+            .map(|e| (*e.key(), level_quantity(e.value())))
+            .collect();
+        let asks: Vec<(u128, u64)> = self
+            .asks
+            .iter()
+            .map(|e| (*e.key(), level_quantity(e.value())))
+            .collect();
+        let reference = self.reference_price();
+
+        let mut best: Option<(u128, u64)> = None;
+        let mut best_key = None;
+        for &price in bids.iter().chain(asks.iter()).map(|(p, _)| p) {
+            let buy: u64 = bids
+                .iter()
+                .filter(|(p, _)| *p >= price)
+                .fold(0u64, |acc, (_, q)| acc.saturating_add(*q));
+            let sell: u64 = asks
+                .iter()
+                .filter(|(p, _)| *p <= price)
+                .fold(0u64, |acc, (_, q)| acc.saturating_add(*q));
+            let volume = buy.min(sell);
+            if volume == 0 {
+                continue;
+            }
+
+            let key = (
+                volume,
+                Reverse(buy.abs_diff(sell)),
+                Reverse(reference.map_or(0, |r| r.abs_diff(price))),
+                Reverse(price),
+            );
+            if best_key.is_none_or(|best| key > best) {
+                best_key = Some(key);
+                best = Some((price, volume));
+            }
+        }
+
+        best
+    }
+
+    /// Execute the auction: match all crossing interest at a single
+    /// clearing price and return to [`TradingState::Continuous`].
+    ///
+    /// Orders are filled in price-time priority on each side. Self-trade
+    /// prevention is not applied during the uncross. Trades are returned in
+    /// the [`AuctionResult`] and are not sent to the trade listener. Once
+    /// the book is continuous again, the contingent orders of filled orders
+    /// are submitted and trailing stops observe the clearing price.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`] if the book is not in
+    /// [`TradingState::Auction`].
+    pub fn run_auction_uncross(&self) -> Result<AuctionResult, OrderBookError> {
+        let _write = self.write_scope();
+        if !self.is_auction() {
+            return Err(OrderBookError::InvalidOperation {
+                message: "auction uncross requires the Auction trading state".to_string(),
+            });
+        }
+
+        let Some((clearing_price, volume)) = self.indicative_clearing_price() else {
+            self.set_trading_state(TradingState::Continuous);
+            return Ok(AuctionResult {
+                clearing_price: None,
+                executed_quantity: 0,
+                trades: Vec::new(),
+            });
+        };
+
+        // Consume `volume` from each side at or through the clearing price.
+        // The engine reports the resting orders as makers, which gives the
+        // per-order fills in priority order for each side.
+        let (sell_fills, _) = self.match_resting_orders(
+            &IncomingOrder {
+                order_id: Id::from_uuid(self.transaction_id_generator.next()),
+                side: Side::Buy,
+                quantity: volume,
+                limit_price: Some(clearing_price),
+                user_id: Hash32::zero(),
+                interact_hidden: true,
+            },
+            false,
+        )?;
+        let (buy_fills, _) = self.match_resting_orders(
+            &IncomingOrder {
+                order_id: Id::from_uuid(self.transaction_id_generator.next()),
+                side: Side::Sell,
+                quantity: volume,
+                limit_price: Some(clearing_price),
+                user_id: Hash32::zero(),
+                interact_hidden: true,
+            },
+            false,
+        )?;
+
+        let mut sells: Vec<(Id, u64)> = sell_fills
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|t| (t.maker_order_id(), t.quantity().as_u64()))
+            .collect();
+        let mut buys: Vec<(Id, u64)> = buy_fills
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|t| (t.maker_order_id(), t.quantity().as_u64()))
+            .collect();
+
+        // Pair buy and sell fills in priority order at the clearing price
+        let mut trades = Vec::new();
+        let (mut b, mut s) = (0, 0);
+        let mut executed_quantity = 0u64;
+        while b < buys.len() && s < sells.len() {
+            let quantity = buys[b].1.min(sells[s].1);
+            trades.push(Trade::new(
+                Id::from_uuid(self.transaction_id_generator.next()),
+                buys[b].0,
+                sells[s].0,
+                Price::new(clearing_price),
+                Quantity::new(quantity),
+                Side::Buy,
+            ));
+            executed_quantity = executed_quantity.saturating_add(quantity);
+            buys[b].1 -= quantity;
+            sells[s].1 -= quantity;
+            if buys[b].1 == 0 {
+                b += 1;
+            }
+            if sells[s].1 == 0 {
+                s += 1;
+            }
+        }
+
+        self.last_trade_price.store(clearing_price);
+        self.has_traded.store(true, Ordering::Relaxed);
+        self.record_session_volume(executed_quantity, clearing_price);
+        self.set_trading_state(TradingState::Continuous);
+
+        trace!(
+            "Order book {}: Auction uncrossed {} at {}",
+            self.symbol, executed_quantity, clearing_price
+        );
+        self.submit_triggered_contingents(&sell_fills);
+        self.submit_triggered_contingents(&buy_fills);
+        self.observe_trade_prices(&[clearing_price]);
+
+        Ok(AuctionResult {
+            clearing_price: Some(clearing_price),
+            executed_quantity,
+            trades,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::TimeInForce;
+
+    #[test]
+    fn test_crossing_orders_do_not_execute_during_auction() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_trading_state(TradingState::Auction);
+
+        let buy = Id::new_uuid();
+        let sell = Id::new_uuid();
+        book.add_limit_order(buy, 105, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(sell, 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // Crossed book, no execution
+        assert_eq!(book.best_bid(), Some(105));
+        assert_eq!(book.best_ask(), Some(100));
+        assert!(book.get_order(buy).is_some());
+        assert!(book.get_order(sell).is_some());
+        assert_eq!(book.last_trade_price(), None);
+
+        // Cancels remain allowed
+        let extra = Id::new_uuid();
+        book.add_limit_order(extra, 90, 1, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert!(book.cancel_order(extra).unwrap().is_some());
+
+        // Immediate orders are rejected
+        assert!(
+            book.submit_market_order(Id::new_uuid(), 1, Side::Buy)
+                .is_err()
+        );
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 105, 1, Side::Buy, TimeInForce::Ioc, None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_uncross_matches_at_clearing_price() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_trading_state(TradingState::Auction);
+
+        let b1 = Id::new_uuid();
+        let b2 = Id::new_uuid();
+        let s1 = Id::new_uuid();
+        let s2 = Id::new_uuid();
+        book.add_limit_order(b1, 103, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(b2, 101, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(s1, 100, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(s2, 102, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // At 102: buy 10 (>=102), sell 15 (<=102) -> 10
+        // At 101: buy 20, sell 5 -> 5; at 103: buy 10, sell 15 -> 10 (imbalance 5 both)
+        // Tie between 102 and 103 on volume and imbalance -> lowest price
+        assert_eq!(book.indicative_clearing_price(), Some((102, 10)));
+
+        let result = book.run_auction_uncross().unwrap();
+        assert_eq!(result.clearing_price, Some(102));
+        assert_eq!(result.executed_quantity, 10);
+        assert!(result.trades.iter().all(|t| t.price().as_u128() == 102));
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].taker_order_id(), b1);
+        assert_eq!(result.trades[0].maker_order_id(), s1);
+        assert_eq!(result.trades[1].maker_order_id(), s2);
+
+        assert_eq!(book.trading_state(), TradingState::Continuous);
+        assert_eq!(book.last_trade_price(), Some(102));
+        assert!(book.get_order(b1).is_none());
+        assert!(book.get_order(s1).is_none());
+        // Book is no longer crossed
+        assert_eq!(book.best_bid(), Some(101));
+        assert_eq!(book.best_ask(), Some(102));
+        assert_eq!(book.get_order(s2).unwrap().visible_quantity(), 5);
+    }
+
+    #[test]
+    fn test_uncross_without_cross_and_outside_auction() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert!(book.run_auction_uncross().is_err());
+
+        book.set_trading_state(TradingState::Auction);
+        book.add_limit_order(Id::new_uuid(), 99, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let result = book.run_auction_uncross().unwrap();
+        assert_eq!(result.clearing_price, None);
+        assert_eq!(result.executed_quantity, 0);
+        assert_eq!(book.trading_state(), TradingState::Continuous);
+    }
+}
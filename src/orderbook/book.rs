@@ -1,29 +1,56 @@
 //! Core OrderBook implementation for managing price levels and orders
 
+use super::arrival::ArrivalOrder;
 use super::cache::PriceLevelCache;
+use super::contingent::TriggeredContingent;
+use super::crossed_check::CrossedBookListener;
 use super::error::OrderBookError;
-use super::fees::FeeSchedule;
+use super::fee_ledger::FeeRounding;
+use super::fees::{FeeSchedule, FeeTier, OrderRole};
+use super::fill_probability::TapeFill;
+use super::iceberg_refill::IcebergRefillPolicy;
 use super::iterators::{LevelInfo, LevelsInRange, LevelsUntilDepth, LevelsWithCumulativeDepth};
+use super::level_changes::LevelChangeRecorder;
+use super::lifetime_counters::LifetimeCounterCells;
 use super::market_impact::{MarketImpact, OrderSimulation};
-use super::snapshot::{EnrichedSnapshot, MetricFlags, OrderBookSnapshot, OrderBookSnapshotPackage};
+use super::match_observer::{MatchObserver, MatchOrderKind};
+use super::matching::IncomingOrder;
+use super::modify_cross::ModifyCrossPolicy;
+use super::parked::{MarketOrderEmptyBookPolicy, ParkedMarketOrder};
+use super::published::SnapshotPublisher;
+use super::reduce_only::ReduceOnlyPolicy;
+use super::snapshot::{
+    AggregatedLevel, AggregatedSnapshot, ColumnarSnapshot, EnrichedSnapshot, MetricFlags,
+    OrderBookSnapshot, OrderBookSnapshotPackage, Quote, RestoreScope, SnapshotOptions,
+    without_hidden,
+};
 use super::statistics::{DepthStats, DistributionBin};
+use super::timestamp_policy::TimestampPolicy;
+use super::trailing_stop::PendingTrailingStop;
+use crate::orderbook::auction::TradingState;
 use crate::orderbook::book_change_event::PriceLevelChangedListener;
+use crate::orderbook::external_bbo::ExternalBboPolicy;
+use crate::orderbook::reference_price::ReferencePricePolicy;
 #[cfg(feature = "special_orders")]
 use crate::orderbook::repricing::SpecialOrderTracker;
-use crate::orderbook::stp::STPMode;
+use crate::orderbook::stp::{STPMakerScope, STPMode};
 use crate::orderbook::trade::{TradeListener, TradeResult};
 use crate::utils::current_time_millis;
 use crossbeam::atomic::AtomicCell;
+use crossbeam::queue::SegQueue;
 use crossbeam_skiplist::SkipMap;
 use dashmap::DashMap;
 #[cfg(feature = "special_orders")]
 use pricelevel::OrderUpdate;
-use pricelevel::{Hash32, Id, MatchResult, OrderType, PriceLevel, Side, UuidGenerator};
+use pricelevel::{
+    Hash32, Id, MatchResult, OrderType, PriceLevel, PriceLevelSnapshot, Side, UuidGenerator,
+};
 use serde::Serialize;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::marker::PhantomData;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::ThreadId;
 use tracing::trace;
 use uuid::Uuid;
 
@@ -31,6 +58,17 @@ use uuid::Uuid;
 /// One basis point = 0.01% = 0.0001
 const DEFAULT_BASIS_POINTS_MULTIPLIER: f64 = 10_000.0;
 
+/// Total (visible + hidden) quantity of a level, saturating at `u64::MAX`.
+///
+/// Used by display and statistics aggregations, where a saturated value is
+/// preferable to an overflowing level silently counting as empty.
+#[inline]
+pub(crate) fn level_quantity(level: &PriceLevel) -> u64 {
+    level
+        .visible_quantity()
+        .saturating_add(level.hidden_quantity())
+}
+
 /// The OrderBook manages a collection of price levels for both bid and ask sides.
 /// It supports adding, cancelling, and matching orders with lock-free operations where possible.
 pub struct OrderBook<T = ()> {
@@ -58,6 +96,12 @@ pub struct OrderBook<T = ()> {
     /// to enable O(1) user-based mass cancellation.
     pub(super) user_orders: DashMap<Hash32, Vec<Id>>,
 
+    /// A concurrent map from order ID to the caller's `extra_fields` payload.
+    /// Price levels store orders as `OrderType<()>`, so the custom data for
+    /// resting orders is kept here and re-attached on lookup. Zero-sized
+    /// payloads such as `()` are never stored.
+    pub(super) order_extra_fields: DashMap<Id, T>,
+
     /// Generator for unique transaction IDs
     pub(super) transaction_id_generator: UuidGenerator,
 
@@ -93,9 +137,11 @@ pub struct OrderBook<T = ()> {
     #[cfg(feature = "special_orders")]
     pub(super) special_order_tracker: SpecialOrderTracker,
 
-    /// Minimum price increment for orders. When set, order prices must be
-    /// exact multiples of this value. `None` disables validation (default).
-    pub(super) tick_size: Option<u128>,
+    /// Minimum price increment per price band as `(price_threshold,
+    /// tick_size)`, sorted by threshold. When set, order prices must be
+    /// exact multiples of the tick of their band. Empty disables validation
+    /// (default).
+    pub(super) tick_schedule: Vec<(u128, u128)>,
 
     /// Minimum quantity increment for orders. When set, order quantities must be
     /// exact multiples of this value. `None` disables validation (default).
@@ -114,6 +160,10 @@ pub struct OrderBook<T = ()> {
     /// to prevent self-trades. Default is `STPMode::None` (disabled).
     pub(super) stp_mode: STPMode,
 
+    /// Which same-user makers `STPMode::CancelMaker` cancels at a matched
+    /// level. Default is `STPMakerScope::AllAtLevel`.
+    pub(super) stp_maker_scope: STPMakerScope,
+
     /// Fee schedule for calculating trading fees. When None, no fees are applied.
     /// Fees are calculated during trade execution and can be configured per orderbook.
     pub(super) fee_schedule: Option<FeeSchedule>,
@@ -122,6 +172,148 @@ pub struct OrderBook<T = ()> {
     /// When `Some`, every order transition (Open, PartiallyFilled, Filled,
     /// Cancelled, Rejected) is recorded. When `None`, zero overhead.
     pub(super) order_state_tracker: Option<super::order_state::OrderStateTracker>,
+
+    /// Manually set reference price, used when the reference price policy
+    /// is `Manual`. Only meaningful when `has_manual_reference_price` is set.
+    pub(super) manual_reference_price: AtomicCell<u128>,
+
+    /// Flag indicating if a manual reference price has been set
+    pub(super) has_manual_reference_price: AtomicBool,
+
+    /// Which price is used as the active reference for price bands and
+    /// stop triggers. Default is `ReferencePricePolicy::Manual`.
+    pub(super) reference_price_policy: ReferencePricePolicy,
+
+    /// Maximum allowed distance of an order price from the active reference
+    /// price, in basis points. `None` disables the band (default).
+    pub(super) price_band_bps: Option<u32>,
+
+    /// Optional observer notified at the start and end of each match.
+    /// `None` by default, in which case matching incurs no observer overhead.
+    pub(super) match_observer: Option<Arc<dyn MatchObserver>>,
+
+    /// External reference BBO as `(bid, ask)` for locked/crossed market
+    /// prevention. Independent of the book's own best prices.
+    pub(super) external_bbo: AtomicCell<Option<(u128, u128)>>,
+
+    /// Policy applied to orders that would lock or cross `external_bbo`.
+    pub(super) external_bbo_policy: ExternalBboPolicy,
+
+    /// Trading state. In `Auction` incoming orders rest without matching
+    /// until the book is uncrossed.
+    pub(super) trading_state: AtomicCell<TradingState>,
+
+    /// Maximum resting quantity per user and side; see
+    /// [`set_user_limit`](Self::set_user_limit).
+    pub(super) user_limits: DashMap<Hash32, u64>,
+
+    /// Number of decimals of the integer price scale; an integer price `p`
+    /// is the decimal price `p / 10^price_decimals`.
+    pub(super) price_decimals: u32,
+
+    /// Per-side rounding of off-tick prices on entry; `None` rejects them.
+    pub(super) tick_rounding: Option<super::tick_rounding::TickRounding>,
+
+    /// Executed quantity since the last [`reset_session`](Self::reset_session).
+    pub(super) session_volume: AtomicU64,
+
+    /// Executed notional (price × quantity) since the last
+    /// [`reset_session`](Self::reset_session).
+    pub(super) session_turnover: AtomicCell<u128>,
+
+    /// Minimum spread, in ticks, a new resting order may leave; see
+    /// [`set_min_spread_ticks`](Self::set_min_spread_ticks).
+    pub(super) min_spread_ticks: Option<u32>,
+
+    /// Most recent fills, oldest first, bounded by
+    /// [`TRADE_TAPE_CAPACITY`](super::fill_probability::TRADE_TAPE_CAPACITY).
+    /// Feeds [`fill_probability`](Self::fill_probability).
+    pub(super) trade_tape: Mutex<VecDeque<TapeFill>>,
+
+    /// Orders waiting to be submitted when the keyed resting order fully
+    /// fills. See [`add_contingent`](Self::add_contingent).
+    pub(super) contingent_orders: DashMap<Id, Vec<OrderType<T>>>,
+
+    /// Contingent orders submitted since the last
+    /// [`take_triggered_contingents`](Self::take_triggered_contingents).
+    pub(super) triggered_contingents: SegQueue<TriggeredContingent<T>>,
+
+    /// Triggered orders waiting, per thread, for the outermost operation in
+    /// progress on that thread to complete, with the id of their trigger.
+    pub(super) deferred_submissions: DashMap<ThreadId, VecDeque<(Id, OrderType<T>)>>,
+
+    /// Snapshot published to lock-free readers, see [`super::published`].
+    pub(super) snapshot_publisher: SnapshotPublisher,
+
+    /// How market orders against an empty opposite side are handled.
+    pub(super) market_order_empty_book_policy: MarketOrderEmptyBookPolicy,
+
+    /// Market orders parked until the opposite side has liquidity.
+    pub(super) parked_market_orders: Mutex<VecDeque<ParkedMarketOrder>>,
+
+    /// Handling of client timestamps on order entry.
+    pub(super) timestamp_policy: TimestampPolicy,
+
+    /// Timestamp of the last order accepted for matching, in milliseconds.
+    pub(super) last_order_timestamp: AtomicU64,
+
+    /// Levels touched while an operation is recorded.
+    pub(super) level_change_recorder: LevelChangeRecorder,
+
+    /// How fractional fees are rounded as they accrue to the fee ledger.
+    pub(super) fee_rounding: FeeRounding,
+
+    /// Fees accrued per user in 1/10,000 of a price unit; see
+    /// [`fees_owed`](Self::fees_owed).
+    pub(super) fee_ledger: DashMap<Hash32, i128>,
+
+    /// How modifications that would cross the book are handled.
+    pub(super) modify_cross_policy: ModifyCrossPolicy,
+
+    /// Trailing stops held off the book until they fire, in the order they
+    /// were added.
+    pub(super) trailing_stops: Mutex<Vec<PendingTrailingStop<T>>>,
+
+    /// Minimum time in nanoseconds an order must rest before a user cancel
+    /// is accepted. `None` allows immediate cancels (default).
+    pub(super) min_rest_time_ns: Option<u64>,
+
+    /// Clock in milliseconds the minimum rest time is measured against.
+    pub(super) min_rest_clock: fn() -> u64,
+
+    /// Whether a reduce-only order larger than the quantity it can reduce
+    /// is rejected or capped.
+    pub(super) reduce_only_policy: ReduceOnlyPolicy,
+
+    /// Maximum number of orders one price level may hold. `None` leaves
+    /// levels unbounded (default).
+    pub(super) max_orders_per_level: Option<usize>,
+
+    /// Tolerance in basis points by which an aggressive order may cross
+    /// the opposite best price. `None` disables the guard (default).
+    pub(super) marketable_guard_bps: Option<u32>,
+
+    /// Volume tiers of the fee ledger, in ascending `min_volume` order.
+    pub(super) fee_tiers: Vec<FeeTier>,
+
+    /// Quantity each user has traded this session while fees accrue, for
+    /// fee tier selection.
+    pub(super) user_session_volume: DashMap<Hash32, u64>,
+
+    /// Orders counted by outcome since the book was created.
+    pub(super) lifetime_counters: LifetimeCounterCells,
+
+    /// Where refilled iceberg and reserve orders rest in their level's queue.
+    pub(super) iceberg_refill_policy: IcebergRefillPolicy,
+
+    /// Number of checks that found the book crossed outside an auction.
+    pub(super) crossed_incidents: AtomicU64,
+
+    /// Called when a check finds the book crossed.
+    pub(super) crossed_book_listener: Option<CrossedBookListener>,
+
+    /// Queue position of each resting order within its level.
+    pub(super) arrivals: ArrivalOrder,
 }
 
 impl<T> Serialize for OrderBook<T>
@@ -216,7 +408,7 @@ where
                 user_id: *user_id,
                 timestamp: *timestamp,
                 time_in_force: *time_in_force,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::IcebergOrder {
                 id,
@@ -237,7 +429,7 @@ where
                 user_id: *user_id,
                 timestamp: *timestamp,
                 time_in_force: *time_in_force,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::PostOnly {
                 id,
@@ -256,7 +448,7 @@ where
                 user_id: *user_id,
                 timestamp: *timestamp,
                 time_in_force: *time_in_force,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::TrailingStop {
                 id,
@@ -279,7 +471,7 @@ where
                 time_in_force: *time_in_force,
                 trail_amount: *trail_amount,
                 last_reference_price: *last_reference_price,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::PeggedOrder {
                 id,
@@ -302,7 +494,7 @@ where
                 time_in_force: *time_in_force,
                 reference_price_offset: *reference_price_offset,
                 reference_price_type: *reference_price_type,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::MarketToLimit {
                 id,
@@ -321,7 +513,7 @@ where
                 user_id: *user_id,
                 timestamp: *timestamp,
                 time_in_force: *time_in_force,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
             OrderType::ReserveOrder {
                 id,
@@ -348,7 +540,7 @@ where
                 replenish_threshold: *replenish_threshold,
                 replenish_amount: *replenish_amount,
                 auto_replenish: *auto_replenish,
-                extra_fields: T::default(),
+                extra_fields: self.extra_fields_for(*id),
             },
         }
     }
@@ -363,6 +555,7 @@ where
             asks: SkipMap::new(),
             order_locations: DashMap::new(),
             user_orders: DashMap::new(),
+            order_extra_fields: DashMap::new(),
             transaction_id_generator: UuidGenerator::new(namespace),
             next_order_id: AtomicU64::new(1),
             last_trade_price: AtomicCell::new(0),
@@ -375,13 +568,54 @@ where
             price_level_changed_listener: None,
             #[cfg(feature = "special_orders")]
             special_order_tracker: SpecialOrderTracker::new(),
-            tick_size: None,
+            tick_schedule: Vec::new(),
             lot_size: None,
             min_order_size: None,
             max_order_size: None,
             stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
             fee_schedule: None,
             order_state_tracker: None,
+            manual_reference_price: AtomicCell::new(0),
+            has_manual_reference_price: AtomicBool::new(false),
+            reference_price_policy: ReferencePricePolicy::Manual,
+            price_band_bps: None,
+            match_observer: None,
+            external_bbo: AtomicCell::new(None),
+            external_bbo_policy: ExternalBboPolicy::Off,
+            trading_state: AtomicCell::new(TradingState::Continuous),
+            user_limits: DashMap::new(),
+            price_decimals: 0,
+            tick_rounding: None,
+            session_volume: AtomicU64::new(0),
+            session_turnover: AtomicCell::new(0),
+            min_spread_ticks: None,
+            trade_tape: Mutex::new(VecDeque::new()),
+            contingent_orders: DashMap::new(),
+            triggered_contingents: SegQueue::new(),
+            deferred_submissions: DashMap::new(),
+            snapshot_publisher: SnapshotPublisher::new(symbol),
+            market_order_empty_book_policy: MarketOrderEmptyBookPolicy::default(),
+            parked_market_orders: Mutex::new(VecDeque::new()),
+            timestamp_policy: TimestampPolicy::default(),
+            last_order_timestamp: AtomicU64::new(0),
+            level_change_recorder: LevelChangeRecorder::default(),
+            fee_rounding: FeeRounding::default(),
+            fee_ledger: DashMap::new(),
+            modify_cross_policy: ModifyCrossPolicy::default(),
+            trailing_stops: Mutex::new(Vec::new()),
+            min_rest_time_ns: None,
+            min_rest_clock: current_time_millis,
+            reduce_only_policy: ReduceOnlyPolicy::default(),
+            max_orders_per_level: None,
+            marketable_guard_bps: None,
+            fee_tiers: Vec::new(),
+            user_session_volume: DashMap::new(),
+            lifetime_counters: LifetimeCounterCells::default(),
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            crossed_incidents: AtomicU64::new(0),
+            crossed_book_listener: None,
+            arrivals: ArrivalOrder::new(),
         }
     }
 
@@ -399,7 +633,7 @@ where
     /// A new `OrderBook` instance with tick size validation enabled
     pub fn with_tick_size(symbol: &str, tick_size: u128) -> Self {
         let mut book = Self::new(symbol);
-        book.tick_size = Some(tick_size);
+        book.set_tick_size(tick_size);
         book
     }
 
@@ -417,7 +651,7 @@ where
     /// A new `OrderBook` instance with lot size validation enabled
     pub fn with_lot_size(symbol: &str, lot_size: u64) -> Self {
         let mut book = Self::new(symbol);
-        book.lot_size = Some(lot_size);
+        book.set_lot_size(lot_size);
         book
     }
 
@@ -431,6 +665,7 @@ where
             asks: SkipMap::new(),
             order_locations: DashMap::new(),
             user_orders: DashMap::new(),
+            order_extra_fields: DashMap::new(),
             transaction_id_generator: UuidGenerator::new(namespace),
             next_order_id: AtomicU64::new(1),
             last_trade_price: AtomicCell::new(0),
@@ -443,13 +678,54 @@ where
             price_level_changed_listener: None,
             #[cfg(feature = "special_orders")]
             special_order_tracker: SpecialOrderTracker::new(),
-            tick_size: None,
+            tick_schedule: Vec::new(),
             lot_size: None,
             min_order_size: None,
             max_order_size: None,
             stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
             fee_schedule: None,
             order_state_tracker: None,
+            manual_reference_price: AtomicCell::new(0),
+            has_manual_reference_price: AtomicBool::new(false),
+            reference_price_policy: ReferencePricePolicy::Manual,
+            price_band_bps: None,
+            match_observer: None,
+            external_bbo: AtomicCell::new(None),
+            external_bbo_policy: ExternalBboPolicy::Off,
+            trading_state: AtomicCell::new(TradingState::Continuous),
+            user_limits: DashMap::new(),
+            price_decimals: 0,
+            tick_rounding: None,
+            session_volume: AtomicU64::new(0),
+            session_turnover: AtomicCell::new(0),
+            min_spread_ticks: None,
+            trade_tape: Mutex::new(VecDeque::new()),
+            contingent_orders: DashMap::new(),
+            triggered_contingents: SegQueue::new(),
+            deferred_submissions: DashMap::new(),
+            snapshot_publisher: SnapshotPublisher::new(symbol),
+            market_order_empty_book_policy: MarketOrderEmptyBookPolicy::default(),
+            parked_market_orders: Mutex::new(VecDeque::new()),
+            timestamp_policy: TimestampPolicy::default(),
+            last_order_timestamp: AtomicU64::new(0),
+            level_change_recorder: LevelChangeRecorder::default(),
+            fee_rounding: FeeRounding::default(),
+            fee_ledger: DashMap::new(),
+            modify_cross_policy: ModifyCrossPolicy::default(),
+            trailing_stops: Mutex::new(Vec::new()),
+            min_rest_time_ns: None,
+            min_rest_clock: current_time_millis,
+            reduce_only_policy: ReduceOnlyPolicy::default(),
+            max_orders_per_level: None,
+            marketable_guard_bps: None,
+            fee_tiers: Vec::new(),
+            user_session_volume: DashMap::new(),
+            lifetime_counters: LifetimeCounterCells::default(),
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            crossed_incidents: AtomicU64::new(0),
+            crossed_book_listener: None,
+            arrivals: ArrivalOrder::new(),
         }
     }
 
@@ -475,6 +751,7 @@ where
             asks: SkipMap::new(),
             order_locations: DashMap::new(),
             user_orders: DashMap::new(),
+            order_extra_fields: DashMap::new(),
             transaction_id_generator: UuidGenerator::new(namespace),
             next_order_id: AtomicU64::new(1),
             last_trade_price: AtomicCell::new(0),
@@ -487,13 +764,54 @@ where
             price_level_changed_listener: Some(book_changed_listener),
             #[cfg(feature = "special_orders")]
             special_order_tracker: SpecialOrderTracker::new(),
-            tick_size: None,
+            tick_schedule: Vec::new(),
             lot_size: None,
             min_order_size: None,
             max_order_size: None,
             stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
             fee_schedule: None,
             order_state_tracker: None,
+            manual_reference_price: AtomicCell::new(0),
+            has_manual_reference_price: AtomicBool::new(false),
+            reference_price_policy: ReferencePricePolicy::Manual,
+            price_band_bps: None,
+            match_observer: None,
+            external_bbo: AtomicCell::new(None),
+            external_bbo_policy: ExternalBboPolicy::Off,
+            trading_state: AtomicCell::new(TradingState::Continuous),
+            user_limits: DashMap::new(),
+            price_decimals: 0,
+            tick_rounding: None,
+            session_volume: AtomicU64::new(0),
+            session_turnover: AtomicCell::new(0),
+            min_spread_ticks: None,
+            trade_tape: Mutex::new(VecDeque::new()),
+            contingent_orders: DashMap::new(),
+            triggered_contingents: SegQueue::new(),
+            deferred_submissions: DashMap::new(),
+            snapshot_publisher: SnapshotPublisher::new(symbol),
+            market_order_empty_book_policy: MarketOrderEmptyBookPolicy::default(),
+            parked_market_orders: Mutex::new(VecDeque::new()),
+            timestamp_policy: TimestampPolicy::default(),
+            last_order_timestamp: AtomicU64::new(0),
+            level_change_recorder: LevelChangeRecorder::default(),
+            fee_rounding: FeeRounding::default(),
+            fee_ledger: DashMap::new(),
+            modify_cross_policy: ModifyCrossPolicy::default(),
+            trailing_stops: Mutex::new(Vec::new()),
+            min_rest_time_ns: None,
+            min_rest_clock: current_time_millis,
+            reduce_only_policy: ReduceOnlyPolicy::default(),
+            max_orders_per_level: None,
+            marketable_guard_bps: None,
+            fee_tiers: Vec::new(),
+            user_session_volume: DashMap::new(),
+            lifetime_counters: LifetimeCounterCells::default(),
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            crossed_incidents: AtomicU64::new(0),
+            crossed_book_listener: None,
+            arrivals: ArrivalOrder::new(),
         }
     }
 
@@ -561,18 +879,33 @@ where
     /// For example, with `tick_size = 100`, prices 100, 200, 300 are valid
     /// but 150 is rejected with `OrderBookError::InvalidTickSize`.
     ///
+    /// This replaces any tick schedule with a single band covering every
+    /// price; see [`set_tick_schedule`](Self::set_tick_schedule).
+    ///
     /// # Arguments
-    /// - `tick_size`: Minimum price increment. Must be > 0
+    /// - `tick_size`: Minimum price increment. `0` disables tick size
+    ///   validation, as [`clear_tick_size`](Self::clear_tick_size) does
     pub fn set_tick_size(&mut self, tick_size: u128) {
-        self.tick_size = Some(tick_size);
+        if tick_size == 0 {
+            self.clear_tick_size();
+            return;
+        }
+        self.tick_schedule = vec![(0, tick_size)];
+    }
+
+    /// Disable tick size validation, removing any tick schedule.
+    pub fn clear_tick_size(&mut self) {
+        self.tick_schedule.clear();
     }
 
     /// Returns the configured tick size, if any.
     ///
     /// `None` means tick size validation is disabled (all prices accepted).
+    /// With a tiered schedule this is the tick of the lowest band; use
+    /// [`tick_size_at`](Self::tick_size_at) for the tick of a given price.
     #[must_use]
     pub fn tick_size(&self) -> Option<u128> {
-        self.tick_size
+        self.tick_schedule.first().map(|&(_, tick)| tick)
     }
 
     /// Set the minimum quantity increment for orders.
@@ -582,9 +915,15 @@ where
     /// individually. Rejection returns `OrderBookError::InvalidLotSize`.
     ///
     /// # Arguments
-    /// - `lot_size`: Minimum quantity increment. Must be > 0
+    /// - `lot_size`: Minimum quantity increment. `0` disables lot size
+    ///   validation, as [`clear_lot_size`](Self::clear_lot_size) does
     pub fn set_lot_size(&mut self, lot_size: u64) {
-        self.lot_size = Some(lot_size);
+        self.lot_size = (lot_size > 0).then_some(lot_size);
+    }
+
+    /// Disable lot size validation.
+    pub fn clear_lot_size(&mut self) {
+        self.lot_size = None;
     }
 
     /// Returns the configured lot size, if any.
@@ -618,6 +957,16 @@ where
         self.max_order_size = Some(size);
     }
 
+    /// Remove the minimum order size.
+    pub fn clear_min_order_size(&mut self) {
+        self.min_order_size = None;
+    }
+
+    /// Remove the maximum order size.
+    pub fn clear_max_order_size(&mut self) {
+        self.max_order_size = None;
+    }
+
     /// Returns the configured minimum order size, if any.
     ///
     /// `None` means no minimum size validation (default).
@@ -657,6 +1006,23 @@ where
         self.stp_mode
     }
 
+    /// Set which same-user makers [`STPMode::CancelMaker`] cancels at a
+    /// matched price level.
+    pub fn set_stp_maker_scope(&mut self, scope: STPMakerScope) {
+        trace!(
+            "Order book {}: Setting STP maker scope to {}",
+            self.symbol, scope
+        );
+        self.stp_maker_scope = scope;
+    }
+
+    /// Returns which same-user makers [`STPMode::CancelMaker`] cancels at a
+    /// matched price level.
+    #[must_use]
+    pub fn stp_maker_scope(&self) -> STPMakerScope {
+        self.stp_maker_scope
+    }
+
     /// Set an order state tracker for explicit lifecycle tracking.
     ///
     /// When set, every order transition (Open, PartiallyFilled, Filled,
@@ -801,6 +1167,26 @@ where
         best_price
     }
 
+    /// Get the best bid price and the total visible quantity resting there,
+    /// if any. Cheaper than a snapshot for a one-level read.
+    #[must_use]
+    pub fn best_bid_size(&self) -> Option<(u128, u64)> {
+        let price = self.best_bid()?;
+        self.bids
+            .get(&price)
+            .map(|entry| (price, entry.value().visible_quantity()))
+    }
+
+    /// Get the best ask price and the total visible quantity resting there,
+    /// if any. Cheaper than a snapshot for a one-level read.
+    #[must_use]
+    pub fn best_ask_size(&self) -> Option<(u128, u64)> {
+        let price = self.best_ask()?;
+        self.asks
+            .get(&price)
+            .map(|entry| (price, entry.value().visible_quantity()))
+    }
+
     /// Get the mid price (average of best bid and best ask)
     pub fn mid_price(&self) -> Option<f64> {
         match (
@@ -832,6 +1218,42 @@ where
         }
     }
 
+    /// Classifies an incoming limit order as maker or taker against the current BBO
+    ///
+    /// A buy at or above the best ask, or a sell at or below the best bid, would
+    /// take liquidity. Anything else would rest. When the opposite side is empty
+    /// the order is always classified as a maker. This is a pure read and does
+    /// not modify the book.
+    ///
+    /// # Arguments
+    /// - `side`: The side of the incoming order
+    /// - `price`: The limit price of the incoming order
+    ///
+    /// # Returns
+    /// [`OrderRole::Taker`] if the order would cross the opposite best price,
+    /// otherwise [`OrderRole::Maker`].
+    ///
+    /// # Examples
+    /// ```
+    /// use orderbook_rs::{OrderBook, OrderRole};
+    /// use pricelevel::{Id, Side, TimeInForce};
+    ///
+    /// let book = OrderBook::<()>::new("BTC/USD");
+    /// book.add_limit_order(Id::new(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(book.classify_order(Side::Buy, 100), OrderRole::Taker);
+    /// assert_eq!(book.classify_order(Side::Buy, 99), OrderRole::Maker);
+    /// ```
+    #[must_use]
+    pub fn classify_order(&self, side: Side, price: u128) -> OrderRole {
+        if self.will_cross_market(price, side) {
+            OrderRole::Taker
+        } else {
+            OrderRole::Maker
+        }
+    }
+
     /// Finds the price where cumulative depth reaches the target quantity
     ///
     /// # Arguments
@@ -879,7 +1301,7 @@ where
         for entry in iter {
             let price = *entry.key();
             let price_level = entry.value();
-            cumulative = cumulative.saturating_add(price_level.total_quantity().unwrap_or(0));
+            cumulative = cumulative.saturating_add(level_quantity(price_level));
 
             if cumulative >= target_depth {
                 return Some(price);
@@ -935,7 +1357,7 @@ where
         for entry in iter {
             let price = *entry.key();
             let price_level = entry.value();
-            cumulative = cumulative.saturating_add(price_level.total_quantity().unwrap_or(0));
+            cumulative = cumulative.saturating_add(level_quantity(price_level));
 
             if cumulative >= target_depth {
                 return Some((price, cumulative));
@@ -997,12 +1419,67 @@ where
             }
 
             let price_level = entry.value();
-            total = total.saturating_add(price_level.total_quantity().unwrap_or(0));
+            total = total.saturating_add(level_quantity(price_level));
         }
 
         total
     }
 
+    /// Returns the levels from the top of `side` until their cumulative
+    /// visible quantity reaches `target_quantity`
+    ///
+    /// Unlike [`levels_until_depth`](Self::levels_until_depth), only visible
+    /// quantity counts, so levels holding nothing but hidden quantity are
+    /// skipped. The level that reaches or straddles the target is included.
+    ///
+    /// # Arguments
+    /// - `side`: The side of the order book (Buy for bids, Sell for asks)
+    /// - `target_quantity`: Cumulative visible quantity to cover
+    ///
+    /// # Returns
+    /// `(price, visible_quantity)` pairs in price-priority order. Returns all
+    /// visible levels if the target exceeds the visible liquidity.
+    ///
+    /// # Examples
+    /// ```
+    /// use orderbook_rs::OrderBook;
+    /// use pricelevel::{Id, Side, TimeInForce};
+    ///
+    /// let book = OrderBook::<()>::new("BTC/USD");
+    /// let _ = book.add_limit_order(Id::new(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    /// let _ = book.add_limit_order(Id::new(), 99, 20, Side::Buy, TimeInForce::Gtc, None);
+    ///
+    /// assert_eq!(book.depth_until_quantity(Side::Buy, 15), vec![(100, 10), (99, 20)]);
+    /// ```
+    #[must_use]
+    pub fn depth_until_quantity(&self, side: Side, target_quantity: u64) -> Vec<(u128, u64)> {
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+
+        // Iterate in price-priority order
+        let iter: Box<dyn Iterator<Item = _>> = match side {
+            Side::Buy => Box::new(price_levels.iter().rev()), // Highest to lowest
+            Side::Sell => Box::new(price_levels.iter()),      // Lowest to highest
+        };
+
+        let mut levels = Vec::new();
+        let mut cumulative = 0u64;
+        for entry in iter {
+            let quantity = entry.value().visible_quantity();
+            if quantity == 0 {
+                continue;
+            }
+            levels.push((*entry.key(), quantity));
+            cumulative = cumulative.saturating_add(quantity);
+            if cumulative >= target_quantity {
+                break;
+            }
+        }
+        levels
+    }
+
     /// Returns the absolute spread (ask - bid) in price units
     ///
     /// This is an alias for `spread()` provided for API consistency.
@@ -1107,6 +1584,13 @@ where
     /// ```
     #[must_use]
     pub fn vwap(&self, quantity: u64, side: Side) -> Option<f64> {
+        let (total_cost, total_filled) = self.vwap_totals(quantity, side)?;
+        Some(total_cost as f64 / total_filled as f64)
+    }
+
+    /// Total notional and quantity of filling `quantity` on `side`, or
+    /// `None` if the quantity is zero or liquidity is insufficient.
+    pub(super) fn vwap_totals(&self, quantity: u64, side: Side) -> Option<(u128, u64)> {
         if quantity == 0 {
             return None;
         }
@@ -1139,7 +1623,7 @@ where
 
             let price = *entry.key();
             let price_level = entry.value();
-            let available = price_level.total_quantity().unwrap_or(0);
+            let available = level_quantity(price_level);
 
             if available == 0 {
                 continue;
@@ -1152,7 +1636,7 @@ where
         }
 
         if total_filled == quantity {
-            Some(total_cost as f64 / total_filled as f64)
+            Some((total_cost, total_filled))
         } else {
             None // Insufficient liquidity
         }
@@ -1189,18 +1673,8 @@ where
         let best_ask_price = self.best_ask()?;
 
         // Get volumes at best levels
-        let bid_volume = self
-            .bids
-            .get(&best_bid_price)?
-            .value()
-            .total_quantity()
-            .unwrap_or(0);
-        let ask_volume = self
-            .asks
-            .get(&best_ask_price)?
-            .value()
-            .total_quantity()
-            .unwrap_or(0);
+        let bid_volume = level_quantity(self.bids.get(&best_bid_price)?.value());
+        let ask_volume = level_quantity(self.asks.get(&best_ask_price)?.value());
 
         let total_volume = bid_volume.saturating_add(ask_volume);
 
@@ -1351,7 +1825,7 @@ where
 
             let price = *entry.key();
             let price_level = entry.value();
-            let available = price_level.total_quantity().unwrap_or(0);
+            let available = level_quantity(price_level);
 
             if available == 0 {
                 continue;
@@ -1463,7 +1937,7 @@ where
 
             let price = *entry.key();
             let price_level = entry.value();
-            let available = price_level.total_quantity().unwrap_or(0);
+            let available = level_quantity(price_level);
 
             if available == 0 {
                 continue;
@@ -1490,6 +1964,169 @@ where
         }
     }
 
+    /// Computes the cost-to-fill curve of a market order on `side`
+    ///
+    /// Walks the opposite side in price priority like
+    /// [`simulate_market_order`](Self::simulate_market_order) and returns
+    /// one point per level consumed: the cumulative quantity filled and the
+    /// cumulative notional paid (or received) up to and including that
+    /// level. The last point stops at `max_quantity` if the book holds that
+    /// much. Hidden quantity counts, as it would fill.
+    ///
+    /// # Arguments
+    /// - `side`: The side of the order (Buy = sweep asks, Sell = sweep bids)
+    /// - `max_quantity`: The largest cumulative quantity to include
+    ///
+    /// # Returns
+    /// `Some(points)`, empty if nothing can fill, or `None` if the
+    /// cumulative cost overflows `u128`.
+    ///
+    /// # Examples
+    /// ```
+    /// use orderbook_rs::OrderBook;
+    /// use pricelevel::{Id, Side, TimeInForce};
+    ///
+    /// let book = OrderBook::<()>::new("BTC/USD");
+    /// let _ = book.add_limit_order(Id::new(), 100, 10, Side::Sell, TimeInForce::Gtc, None);
+    /// let _ = book.add_limit_order(Id::new(), 105, 15, Side::Sell, TimeInForce::Gtc, None);
+    ///
+    /// let curve = book.cost_curve(Side::Buy, 20).unwrap();
+    /// assert_eq!(curve, vec![(10, 1_000), (20, 2_050)]);
+    /// ```
+    #[must_use]
+    pub fn cost_curve(&self, side: Side, max_quantity: u64) -> Option<Vec<(u64, u128)>> {
+        let price_levels = match side {
+            Side::Buy => &self.asks,
+            Side::Sell => &self.bids,
+        };
+        let iter: Box<dyn Iterator<Item = _>> = match side {
+            Side::Buy => Box::new(price_levels.iter()),
+            Side::Sell => Box::new(price_levels.iter().rev()),
+        };
+
+        let mut curve = Vec::new();
+        let mut filled = 0u64;
+        let mut cost = 0u128;
+        for entry in iter {
+            if filled >= max_quantity {
+                break;
+            }
+            let available = level_quantity(entry.value());
+            if available == 0 {
+                continue;
+            }
+
+            let fill_qty = available.min(max_quantity - filled);
+            cost = entry
+                .key()
+                .checked_mul(u128::from(fill_qty))
+                .and_then(|notional| cost.checked_add(notional))?;
+            filled += fill_qty;
+            curve.push((filled, cost));
+        }
+        Some(curve)
+    }
+
+    /// Calculates the total notional (price × quantity) resting on one side
+    ///
+    /// Sums `price × visible_quantity` over every price level on the side.
+    /// Arithmetic is checked; on overflow the result saturates at
+    /// `u128::MAX` instead of wrapping.
+    ///
+    /// # Arguments
+    /// - `side`: The side to analyze (Buy for bids, Sell for asks)
+    ///
+    /// # Returns
+    /// Total visible notional on the side, or 0 if the side is empty.
+    ///
+    /// # Performance
+    /// O(N) where N is the number of price levels on the side.
+    ///
+    /// # Examples
+    /// ```
+    /// use orderbook_rs::OrderBook;
+    /// use pricelevel::{Id, Side, TimeInForce};
+    ///
+    /// let book = OrderBook::<()>::new("BTC/USD");
+    /// let _ = book.add_limit_order(Id::new(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    /// let _ = book.add_limit_order(Id::new(), 99, 20, Side::Buy, TimeInForce::Gtc, None);
+    ///
+    /// assert_eq!(book.resting_notional(Side::Buy), 100 * 10 + 99 * 20);
+    /// ```
+    #[must_use]
+    pub fn resting_notional(&self, side: Side) -> u128 {
+        self.checked_resting_notional(side, false)
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Calculates the total notional resting on one side, including the hidden
+    /// quantity of iceberg and reserve orders
+    ///
+    /// Same as [`resting_notional`](Self::resting_notional) but uses
+    /// `visible + hidden` quantity per level. Saturates at `u128::MAX` on
+    /// overflow.
+    #[must_use]
+    pub fn resting_notional_with_hidden(&self, side: Side) -> u128 {
+        self.checked_resting_notional(side, true)
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Calculates the total notional resting on one side with overflow detection
+    ///
+    /// # Arguments
+    /// - `side`: The side to analyze (Buy for bids, Sell for asks)
+    /// - `include_hidden`: Whether to include hidden quantity
+    ///
+    /// # Returns
+    /// `Some(notional)`, or `None` if the sum overflows `u128`.
+    #[must_use]
+    pub fn checked_resting_notional(&self, side: Side, include_hidden: bool) -> Option<u128> {
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+
+        price_levels.iter().try_fold(0u128, |total, entry| {
+            let level = entry.value();
+            let quantity = if include_hidden {
+                u128::from(level.visible_quantity()) + u128::from(level.hidden_quantity())
+            } else {
+                u128::from(level.visible_quantity())
+            };
+            entry
+                .key()
+                .checked_mul(quantity)
+                .and_then(|notional| total.checked_add(notional))
+        })
+    }
+
+    /// Returns the exact total quantity (visible + hidden) resting on one side.
+    ///
+    /// Depth and statistics helpers such as
+    /// [`buy_sell_pressure`](Self::buy_sell_pressure) saturate at `u64::MAX`;
+    /// this method reports overflow instead.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::QuantityOverflow`] if the total of any level,
+    /// or of the whole side, does not fit in a `u64`.
+    pub fn checked_total_quantity(&self, side: Side) -> Result<u64, OrderBookError> {
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+
+        price_levels
+            .iter()
+            .try_fold(0u64, |total, entry| {
+                entry
+                    .value()
+                    .total_quantity()
+                    .ok()
+                    .and_then(|quantity| total.checked_add(quantity))
+            })
+            .ok_or(OrderBookError::QuantityOverflow { side })
+    }
+
     /// Calculates available liquidity within a specific price range
     ///
     /// Sums up the total quantity available at price levels that fall
@@ -1549,7 +2186,7 @@ where
             }
 
             let price_level = entry.value();
-            let quantity = price_level.total_quantity().unwrap_or(0);
+            let quantity = level_quantity(price_level);
             total_liquidity = total_liquidity.saturating_add(quantity);
         }
 
@@ -1707,18 +2344,15 @@ where
 
         // For bids: iterate from highest to lowest (reverse)
         // For asks: iterate from lowest to highest (forward)
-        let mut current_position = 1;
-
         let iter: Box<dyn Iterator<Item = _>> = match side {
             Side::Buy => Box::new(price_levels.iter().rev()),
             Side::Sell => Box::new(price_levels.iter()),
         };
 
-        for entry in iter {
+        for (current_position, entry) in (1..).zip(iter) {
             if current_position == position {
                 return Some(*entry.key());
             }
-            current_position += 1;
         }
 
         None
@@ -1790,7 +2424,7 @@ where
 
         for entry in iter {
             let price = *entry.key();
-            let quantity = entry.value().total_quantity().unwrap_or(0);
+            let quantity = level_quantity(entry.value());
             cumulative_depth = cumulative_depth.saturating_add(quantity);
 
             if cumulative_depth >= target_depth {
@@ -2072,6 +2706,70 @@ where
         None
     }
 
+    /// Returns the zero-based position of a resting order within its price
+    /// level under price-time priority, or `None` if the order is not
+    /// resting.
+    ///
+    /// Position is the number of orders ahead of it in the level's queue,
+    /// as tracked by [`ArrivalOrder`], so orders added within the same
+    /// millisecond still rank by arrival. The position shrinks as orders
+    /// ahead are cancelled or filled, and a partial fill sends the order to
+    /// the back.
+    ///
+    /// # Performance
+    /// O(N) where N is the number of orders at the order's price level.
+    #[must_use]
+    pub fn queue_position(&self, order_id: Id) -> Option<usize> {
+        let (price, side) = *self.order_locations.get(&order_id)?;
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let entry = price_levels.get(&price)?;
+
+        let arrival = |id| self.arrivals.position(id).unwrap_or(u64::MAX);
+        let target = arrival(order_id);
+        let mut found = false;
+        let mut ahead = 0;
+        for order in entry.value().iter_orders() {
+            if order.id() == order_id {
+                found = true;
+            } else if arrival(order.id()) < target {
+                ahead += 1;
+            }
+        }
+        found.then_some(ahead)
+    }
+
+    /// Returns the resting orders of `side` in the order an incoming order
+    /// of the opposite side would match them.
+    ///
+    /// Levels are visited best price first and, within a level, orders are
+    /// yielded in the engine's queue order, as tracked by
+    /// [`ArrivalOrder`]: the order in which they joined the back of the
+    /// level, whatever their timestamps. Each level is read when the
+    /// iterator reaches it, so concurrent changes to later levels are
+    /// reflected.
+    ///
+    /// # Performance
+    /// O(N log N) per level visited, where N is the number of orders at that
+    /// level.
+    pub fn orders_in_match_order(
+        &self,
+        side: Side,
+    ) -> impl Iterator<Item = Arc<OrderType<T>>> + '_ {
+        let levels: Box<dyn Iterator<Item = Arc<PriceLevel>> + '_> = match side {
+            Side::Buy => Box::new(self.bids.iter().rev().map(|e| Arc::clone(e.value()))),
+            Side::Sell => Box::new(self.asks.iter().map(|e| Arc::clone(e.value()))),
+        };
+
+        levels.flat_map(move |level| {
+            self.queue_orders(&level)
+                .into_iter()
+                .map(move |order| Arc::new(self.convert_from_unit_type(&order)))
+        })
+    }
+
     /// Match a market order against the book.
     ///
     /// This is a convenience wrapper that bypasses STP (uses `Hash32::zero()`).
@@ -2107,13 +2805,61 @@ where
         quantity: u64,
         side: Side,
         user_id: Hash32,
+    ) -> Result<MatchResult, OrderBookError> {
+        self.match_market_order_with_hidden(order_id, quantity, side, user_id, true)
+    }
+
+    /// Match a market order as [`Self::match_market_order_with_user`] does,
+    /// taking only displayed quantity unless `interact_hidden` is set.
+    pub(super) fn match_market_order_with_hidden(
+        &self,
+        order_id: Id,
+        quantity: u64,
+        side: Side,
+        user_id: Hash32,
+        interact_hidden: bool,
     ) -> Result<MatchResult, OrderBookError> {
         trace!(
             "Order book {}: Matching market order {} for {} at side {:?}",
             self.symbol, order_id, quantity, side
         );
+        if self.is_auction() {
+            return Err(OrderBookError::InvalidOperation {
+                message: "market orders are not accepted during an auction".to_string(),
+            });
+        }
+        if self.market_order_empty_book_policy == MarketOrderEmptyBookPolicy::ParkAsPending {
+            let opposite_empty = match side {
+                Side::Buy => self.asks.is_empty(),
+                Side::Sell => self.bids.is_empty(),
+            };
+            if opposite_empty {
+                return Ok(self.park_market_order(
+                    order_id,
+                    quantity,
+                    side,
+                    user_id,
+                    interact_hidden,
+                ));
+            }
+        }
+        let incoming = IncomingOrder {
+            order_id,
+            side,
+            quantity,
+            limit_price: None,
+            user_id,
+            interact_hidden,
+        };
+        let mut stp = None;
         let match_result =
-            OrderBook::<T>::match_order_with_user(self, order_id, side, quantity, None, user_id)?;
+            self.observe_match(order_id, MatchOrderKind::Market, side, quantity, || {
+                self.match_incoming_reporting_stp(&incoming)
+                    .map(|(match_result, triggered)| {
+                        stp = triggered;
+                        match_result
+                    })
+            })?;
 
         // Trigger trade listener if there are transactions
         if !match_result.trades().as_vec().is_empty()
@@ -2123,7 +2869,8 @@ where
                 self.symbol.clone(),
                 match_result.clone(),
                 self.fee_schedule,
-            );
+            )
+            .with_stp(stp);
             listener(&trade_result);
         }
 
@@ -2181,14 +2928,15 @@ where
             "Order book {}: Matching limit order {} for {} at side {:?} with limit price {}",
             self.symbol, order_id, quantity, side, limit_price
         );
-        let match_result = OrderBook::<T>::match_order_with_user(
-            self,
-            order_id,
-            side,
-            quantity,
-            Some(limit_price),
-            user_id,
-        )?;
+        let mut stp = None;
+        let match_result =
+            self.observe_match(order_id, MatchOrderKind::Standard, side, quantity, || {
+                self.match_order_reporting_stp(order_id, side, quantity, Some(limit_price), user_id)
+                    .map(|(match_result, triggered)| {
+                        stp = triggered;
+                        match_result
+                    })
+            })?;
 
         // Trigger trade listener if there are transactions
         if !match_result.trades().as_vec().is_empty()
@@ -2198,7 +2946,8 @@ where
                 self.symbol.clone(),
                 match_result.clone(),
                 self.fee_schedule,
-            );
+            )
+            .with_stp(stp);
             listener(&trade_result);
         }
 
@@ -2206,49 +2955,157 @@ where
     }
 
     /// Create a snapshot of the current order book state
+    ///
+    /// `depth` limits the number of levels on each side, so up to
+    /// `2 * depth` levels are included. Hidden quantity is included; see
+    /// [`create_snapshot_with`](Self::create_snapshot_with) for asymmetric
+    /// depths or public depth only.
     pub fn create_snapshot(&self, depth: usize) -> OrderBookSnapshot {
-        // Get all bid prices and sort them in descending order
-        let mut bid_prices: Vec<u128> = self.bids.iter().map(|item| *item.key()).collect();
-        bid_prices.sort_by(|a, b| b.cmp(a)); // Descending order
-        bid_prices.truncate(depth);
+        self.create_snapshot_with(SnapshotOptions::depth(depth))
+    }
 
-        // Get all ask prices and sort them in ascending order
-        let mut ask_prices: Vec<u128> = self.asks.iter().map(|item| *item.key()).collect();
-        ask_prices.sort(); // Ascending order
-        ask_prices.truncate(depth);
+    /// Create a snapshot of the current order book state with separate
+    /// limits per side and optional hidden quantity.
+    ///
+    /// # Performance
+    /// O(B + A) in the number of included bid and ask levels, plus the
+    /// orders they contain.
+    #[must_use]
+    pub fn create_snapshot_with(&self, options: SnapshotOptions) -> OrderBookSnapshot {
+        // Orders are listed in queue order, which a restore rebuilds
+        let level_snapshot = |level: &Arc<PriceLevel>| {
+            let snapshot = PriceLevelSnapshot::with_orders(level.price(), self.queue_orders(level))
+                .unwrap_or_else(|_| level.snapshot());
+            if options.include_hidden {
+                snapshot
+            } else {
+                without_hidden(snapshot)
+            }
+        };
 
-        let mut bid_levels = Vec::with_capacity(bid_prices.len());
-        let mut ask_levels = Vec::with_capacity(ask_prices.len());
+        let bids = self
+            .bids
+            .iter()
+            .rev()
+            .take(options.max_bid_levels)
+            .map(|entry| level_snapshot(entry.value()))
+            .collect();
+        let asks = self
+            .asks
+            .iter()
+            .take(options.max_ask_levels)
+            .map(|entry| level_snapshot(entry.value()))
+            .collect();
 
-        // Create snapshots for each bid level
-        for price in bid_prices {
-            if let Some(entry) = self.bids.get(&price) {
-                bid_levels.push(entry.value().snapshot());
-            }
+        OrderBookSnapshot {
+            symbol: self.symbol.clone(),
+            timestamp: current_time_millis(),
+            bids,
+            asks,
         }
+    }
 
-        // Create snapshots for each ask level
-        for price in ask_prices {
-            if let Some(entry) = self.asks.get(&price) {
-                ask_levels.push(entry.value().snapshot());
-            }
+    /// Create a columnar (struct-of-arrays) snapshot of the top `depth` levels
+    ///
+    /// Produces the same levels as [`create_snapshot`](Self::create_snapshot)
+    /// in the same best-first order, but as parallel price and quantity
+    /// vectors per side instead of per-level snapshots.
+    ///
+    /// # Arguments
+    /// - `depth`: Maximum number of levels to include per side
+    ///
+    /// # Performance
+    /// O(D) per side where D is `depth`. No per-order data is copied.
+    #[must_use]
+    pub fn snapshot_columnar(&self, depth: usize) -> ColumnarSnapshot {
+        let bid_len = depth.min(self.bids.len());
+        let ask_len = depth.min(self.asks.len());
+
+        let mut bid_prices = Vec::with_capacity(bid_len);
+        let mut bid_qtys = Vec::with_capacity(bid_len);
+        for entry in self.bids.iter().rev().take(depth) {
+            bid_prices.push(*entry.key());
+            bid_qtys.push(entry.value().visible_quantity());
         }
 
-        OrderBookSnapshot {
+        let mut ask_prices = Vec::with_capacity(ask_len);
+        let mut ask_qtys = Vec::with_capacity(ask_len);
+        for entry in self.asks.iter().take(depth) {
+            ask_prices.push(*entry.key());
+            ask_qtys.push(entry.value().visible_quantity());
+        }
+
+        ColumnarSnapshot {
+            symbol: self.symbol.clone(),
+            timestamp: current_time_millis(),
+            bid_prices,
+            bid_qtys,
+            ask_prices,
+            ask_qtys,
+        }
+    }
+
+    /// Returns the best `depth` levels per side as price/size pairs.
+    ///
+    /// Each entry aggregates the visible quantity of all orders at that
+    /// price. Bids are ordered highest first and asks lowest first.
+    ///
+    /// # Performance
+    /// O(D) per side where D is `depth`.
+    #[must_use]
+    pub fn quote(&self, depth: usize) -> Quote {
+        Quote {
+            bids: self
+                .bids
+                .iter()
+                .rev()
+                .take(depth)
+                .map(|entry| (*entry.key(), entry.value().visible_quantity()))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .take(depth)
+                .map(|entry| (*entry.key(), entry.value().visible_quantity()))
+                .collect(),
+        }
+    }
+
+    /// Create a snapshot of the best `depth` levels per side holding only
+    /// the price, visible quantity and order count of each level.
+    ///
+    /// Suitable for market-data distribution, not for recovery; see
+    /// [`AggregatedSnapshot`].
+    ///
+    /// # Performance
+    /// O(D) per side where D is `depth`. No per-order data is copied.
+    #[must_use]
+    pub fn create_aggregated_snapshot(&self, depth: usize) -> AggregatedSnapshot {
+        let level =
+            |entry: crossbeam_skiplist::map::Entry<'_, u128, Arc<PriceLevel>>| AggregatedLevel {
+                price: *entry.key(),
+                quantity: entry.value().visible_quantity(),
+                order_count: entry.value().order_count(),
+            };
+        AggregatedSnapshot {
             symbol: self.symbol.clone(),
             timestamp: current_time_millis(),
-            bids: bid_levels,
-            asks: ask_levels,
+            bids: self.bids.iter().rev().take(depth).map(level).collect(),
+            asks: self.asks.iter().take(depth).map(level).collect(),
         }
     }
 
     /// Create a checksum-protected snapshot package of the entire book.
     ///
     /// The returned package includes the book's configuration fields
-    /// (`fee_schedule`, `stp_mode`, `tick_size`, `lot_size`,
-    /// `min_order_size`, `max_order_size`) so that
+    /// (`fee_schedule`, `stp_mode`, `tick_size` and `tick_schedule`, `lot_size`,
+    /// `min_order_size`, `max_order_size`), the fee ledger, the lifetime
+    /// counters, the trading state, the last trade price, the pending
+    /// trailing stops, contingent and parked market orders, and the session
+    /// totals so that
     /// [`restore_from_snapshot_package`](Self::restore_from_snapshot_package)
-    /// can fully reconstruct the book's state.
+    /// can fully reconstruct the book's state. Pending orders keep only
+    /// their unit extra fields, like the resting orders.
     pub fn create_snapshot_package(
         &self,
         depth: usize,
@@ -2257,10 +3114,36 @@ where
         let mut package = OrderBookSnapshotPackage::new(snapshot)?;
         package.fee_schedule = self.fee_schedule;
         package.stp_mode = self.stp_mode;
-        package.tick_size = self.tick_size;
+        package.tick_size = self.tick_size();
+        package.tick_schedule = self.tick_schedule.clone();
         package.lot_size = self.lot_size;
         package.min_order_size = self.min_order_size;
         package.max_order_size = self.max_order_size;
+        package.fee_rounding = self.fee_rounding;
+        package.fee_ledger = self.raw_fee_ledger();
+        package.lifetime_counters = self.lifetime_counters();
+        package.trading_state = self.trading_state();
+        package.last_trade_price = self.last_trade_price();
+        package.trailing_stops = self.raw_trailing_stops();
+        package.contingent_orders = self.raw_contingent_orders();
+        package.parked_market_orders = self.parked_market_orders();
+        package.session_volume = self.session_volume();
+        package.session_turnover = self.session_turnover();
+        package.user_session_volume = self.raw_user_session_volume();
+        package.update_chain_hash()?;
+        Ok(package)
+    }
+
+    /// Create a snapshot package chained after `previous`, the last
+    /// checkpoint of a tamper-evident chain; see
+    /// [`OrderBookSnapshotPackage::verify_chain`].
+    pub fn create_chained_snapshot_package(
+        &self,
+        depth: usize,
+        previous: &OrderBookSnapshotPackage,
+    ) -> Result<OrderBookSnapshotPackage, OrderBookError> {
+        let mut package = self.create_snapshot_package(depth)?;
+        package.chain_after(previous)?;
         Ok(package)
     }
 
@@ -2272,34 +3155,155 @@ where
     /// Restore the book state from a checksum-validated snapshot package.
     ///
     /// This restores both the order data and the configuration fields
-    /// (`fee_schedule`, `stp_mode`, `tick_size`, `lot_size`,
-    /// `min_order_size`, `max_order_size`) that were captured by
-    /// [`create_snapshot_package`](Self::create_snapshot_package).
+    /// (`fee_schedule`, `stp_mode`, `tick_size` and `tick_schedule`, `lot_size`,
+    /// `min_order_size`, `max_order_size`), the fee ledger, the lifetime
+    /// counters and the session totals that were captured by
+    /// [`create_snapshot_package`](Self::create_snapshot_package), as
+    /// [`RestoreScope::Full`] does.
+    ///
+    /// The book takes the package's trading state. A package taken during
+    /// an auction may describe a crossed book, which restores still in the
+    /// auction. It also takes the last trade price and the pending trailing
+    /// stops, contingent and parked market orders, whose orders get default
+    /// extra fields. Contingent orders whose trigger is not among the
+    /// restored orders are dropped. The trade tape starts empty.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::ChecksumMismatch`] if the package fails
+    /// validation, and [`OrderBookError::InvalidSnapshot`] if it describes a
+    /// crossed or locked book (best bid >= best ask) outside an auction. In
+    /// both cases the book is left unchanged.
     pub fn restore_from_snapshot_package(
         &mut self,
         package: OrderBookSnapshotPackage,
     ) -> Result<(), OrderBookError> {
-        // Extract config before consuming the package via into_snapshot().
+        self.restore_from_snapshot_package_with(package, RestoreScope::Full)
+    }
+
+    /// Restore the book state from a checksum-validated snapshot package,
+    /// taking what `scope` selects.
+    ///
+    /// [`RestoreScope::OrdersOnly`] replaces the resting orders and keeps the
+    /// book's current configuration, fee ledger and session totals, e.g.
+    /// after fee schedules changed since the package was taken. The trading
+    /// state, the last trade price and the pending off-book orders go with
+    /// the orders and are restored under either scope.
+    /// [`RestoreScope::Full`] also restores the package's configuration, fee
+    /// ledger, lifetime counters and session totals, as
+    /// [`restore_from_snapshot_package`](Self::restore_from_snapshot_package)
+    /// does.
+    ///
+    /// # Errors
+    /// As [`restore_from_snapshot_package`](Self::restore_from_snapshot_package);
+    /// the book is left unchanged on error.
+    pub fn restore_from_snapshot_package_with(
+        &mut self,
+        mut package: OrderBookSnapshotPackage,
+        scope: RestoreScope,
+    ) -> Result<(), OrderBookError> {
+        // Validate the whole package before taking its fields apart
+        package.validate()?;
         let fee_schedule = package.fee_schedule;
         let stp_mode = package.stp_mode;
-        let tick_size = package.tick_size;
+        // Packages from before tick schedules only carry the tick size
+        let tick_schedule = match std::mem::take(&mut package.tick_schedule) {
+            schedule if schedule.is_empty() => package
+                .tick_size
+                .map(|tick| vec![(0, tick)])
+                .unwrap_or_default(),
+            schedule => schedule,
+        };
         let lot_size = package.lot_size;
         let min_order_size = package.min_order_size;
         let max_order_size = package.max_order_size;
+        let fee_rounding = package.fee_rounding;
+        let fee_ledger = std::mem::take(&mut package.fee_ledger);
+        let lifetime_counters = package.lifetime_counters;
+        let trading_state = package.trading_state;
+        let last_trade_price = package.last_trade_price;
+        let trailing_stops = std::mem::take(&mut package.trailing_stops);
+        let contingent_orders = std::mem::take(&mut package.contingent_orders);
+        let parked_market_orders = std::mem::take(&mut package.parked_market_orders);
+        let session_volume = package.session_volume;
+        let session_turnover = package.session_turnover;
+        let user_session_volume = std::mem::take(&mut package.user_session_volume);
+
+        let snapshot = package.snapshot;
+        let best_bid = snapshot.bids.iter().map(|level| level.price()).max();
+        let best_ask = snapshot.asks.iter().map(|level| level.price()).min();
+        if trading_state != TradingState::Auction
+            && let (Some(bid), Some(ask)) = (best_bid, best_ask)
+            && bid >= ask
+        {
+            return Err(OrderBookError::InvalidSnapshot {
+                message: format!("crossed book: best bid {bid} >= best ask {ask}"),
+            });
+        }
 
-        self.restore_from_snapshot(package.into_snapshot()?)?;
+        self.restore_from_snapshot(snapshot)?;
+        self.set_trading_state(trading_state);
+        if let Some(price) = last_trade_price {
+            self.last_trade_price.store(price);
+            self.has_traded.store(true, Ordering::Relaxed);
+        }
+        self.restore_trailing_stops(trailing_stops);
+        self.restore_contingent_orders(contingent_orders);
+        self.restore_parked_market_orders(parked_market_orders);
+        if scope == RestoreScope::OrdersOnly {
+            return Ok(());
+        }
 
         // Apply configuration that was captured in the package.
         self.fee_schedule = fee_schedule;
         self.stp_mode = stp_mode;
-        self.tick_size = tick_size;
+        self.tick_schedule = tick_schedule;
         self.lot_size = lot_size;
         self.min_order_size = min_order_size;
         self.max_order_size = max_order_size;
+        self.fee_rounding = fee_rounding;
+        self.restore_fee_ledger(fee_ledger);
+        self.lifetime_counters.store(lifetime_counters);
+        self.restore_session_totals(session_volume, session_turnover, user_session_volume);
 
         Ok(())
     }
 
+    /// Restore the book state from a checksum-validated snapshot package,
+    /// deriving each restored order's `extra_fields` with `map`.
+    ///
+    /// Packages carry orders with unit extra fields, so a package taken
+    /// from a book with another (or no) metadata type restores as-is and
+    /// `map` supplies the metadata of this book's type for every resting
+    /// order, e.g. a default or a value looked up by order id. `map` is not
+    /// called when `T` is zero-sized, as nothing is stored. Otherwise this
+    /// behaves as
+    /// [`restore_from_snapshot_package`](Self::restore_from_snapshot_package).
+    ///
+    /// # Errors
+    /// As [`restore_from_snapshot_package`](Self::restore_from_snapshot_package);
+    /// the book is left unchanged and `map` is not called on error.
+    pub fn restore_from_snapshot_package_mapped<F>(
+        &mut self,
+        package: OrderBookSnapshotPackage,
+        mut map: F,
+    ) -> Result<(), OrderBookError>
+    where
+        F: FnMut(&OrderType<()>) -> T,
+    {
+        self.restore_from_snapshot_package(package)?;
+        if std::mem::size_of::<T>() == 0 {
+            return Ok(());
+        }
+        for levels in [&self.bids, &self.asks] {
+            for entry in levels.iter() {
+                for order in entry.value().iter_orders() {
+                    self.order_extra_fields.insert(order.id(), map(&order));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Restore the book state from a JSON payload containing a checksum-protected snapshot package.
     ///
     /// This restores both order data and configuration fields.
@@ -2310,7 +3314,13 @@ where
     }
 
     /// Restore the book state from a snapshot, without checksum validation.
+    ///
+    /// A snapshot only holds the resting orders. Everything tied to the
+    /// orders it replaces is cleared: the last trade price, the trade tape,
+    /// and the pending trailing stops, contingent and parked market orders.
+    /// Configuration, the fee ledger and the session totals are kept.
     pub fn restore_from_snapshot(&self, snapshot: OrderBookSnapshot) -> Result<(), OrderBookError> {
+        let _write = self.write_scope();
         if snapshot.symbol != self.symbol {
             return Err(OrderBookError::InvalidOperation {
                 message: format!(
@@ -2330,12 +3340,36 @@ where
             drop(entry);
         }
         self.order_locations.clear();
+        self.arrivals.clear();
         self.user_orders.clear();
+        self.order_extra_fields.clear();
+        #[cfg(feature = "special_orders")]
+        self.special_order_tracker.clear();
         self.has_traded.store(false, Ordering::Relaxed);
         self.last_trade_price.store(0);
+        self.trade_tape
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.trailing_stops
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.contingent_orders.clear();
+        self.parked_market_orders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
         self.has_market_close.store(false, Ordering::Relaxed);
         self.market_close_timestamp.store(0, Ordering::Relaxed);
 
+        // A level's queue is rebuilt in the order its snapshot lists
+        for level_snapshot in snapshot.bids.iter().chain(&snapshot.asks) {
+            for order in level_snapshot.orders() {
+                self.arrivals.push_back(order.id());
+            }
+        }
+
         for level_snapshot in snapshot.bids {
             let price = level_snapshot.price();
             let price_level = PriceLevel::from_snapshot(level_snapshot)
@@ -2352,13 +3386,16 @@ where
             self.asks.insert(price, arc_level);
         }
 
-        // Rebuild order location and user_orders maps
+        // Rebuild order location and user_orders maps, and re-register
+        // pegged orders and trailing stops for re-pricing
         for item in self.bids.iter() {
             let price = *item.key();
             let level = item.value();
             for order in level.iter_orders() {
                 self.order_locations.insert(order.id(), (price, Side::Buy));
                 self.track_user_order(order.user_id(), order.id());
+                #[cfg(feature = "special_orders")]
+                self.track_special_order(&order);
             }
         }
 
@@ -2368,6 +3405,8 @@ where
             for order in level.iter_orders() {
                 self.order_locations.insert(order.id(), (price, Side::Sell));
                 self.track_user_order(order.user_id(), order.id());
+                #[cfg(feature = "special_orders")]
+                self.track_special_order(&order);
             }
         }
 
@@ -2501,14 +3540,14 @@ where
         for item in self.bids.iter() {
             let price = *item.key();
             let price_level = item.value();
-            bid_volumes.insert(price, price_level.total_quantity().unwrap_or(0));
+            bid_volumes.insert(price, level_quantity(price_level));
         }
 
         // Calculate ask volumes
         for item in self.asks.iter() {
             let price = *item.key();
             let price_level = item.value();
-            ask_volumes.insert(price, price_level.total_quantity().unwrap_or(0));
+            ask_volumes.insert(price, level_quantity(price_level));
         }
 
         (bid_volumes, ask_volumes)
@@ -2629,7 +3668,7 @@ where
             }
 
             let price = *entry.key();
-            let quantity = entry.value().total_quantity().unwrap_or(0);
+            let quantity = level_quantity(entry.value());
 
             if quantity == 0 {
                 continue;
@@ -2703,17 +3742,13 @@ where
     /// ```
     #[must_use]
     pub fn buy_sell_pressure(&self) -> (u64, u64) {
-        let buy_pressure: u64 = self
-            .bids
-            .iter()
-            .map(|entry| entry.value().total_quantity().unwrap_or(0))
-            .sum();
+        let buy_pressure: u64 = self.bids.iter().fold(0u64, |acc, entry| {
+            acc.saturating_add(level_quantity(entry.value()))
+        });
 
-        let sell_pressure: u64 = self
-            .asks
-            .iter()
-            .map(|entry| entry.value().total_quantity().unwrap_or(0))
-            .sum();
+        let sell_pressure: u64 = self.asks.iter().fold(0u64, |acc, entry| {
+            acc.saturating_add(level_quantity(entry.value()))
+        });
 
         (buy_pressure, sell_pressure)
     }
@@ -2847,7 +3882,7 @@ where
         // Fill bins with data
         for entry in price_levels.iter() {
             let price = *entry.key();
-            let quantity = entry.value().total_quantity().unwrap_or(0);
+            let quantity = level_quantity(entry.value());
 
             if quantity == 0 {
                 continue;
@@ -2867,6 +3902,48 @@ where
 
         distribution
     }
+
+    /// Counts resting orders by age, where an order's age is `now_ts` minus
+    /// its stored timestamp, in the same unit as the timestamps.
+    ///
+    /// `buckets` are ascending upper bounds. Entry `i` of the result counts
+    /// the orders with an age of at least `buckets[i - 1]` (or zero) and
+    /// below `buckets[i]`, so an age exactly on a bound falls into the next
+    /// bucket. A final entry counts the orders at least as old as the last
+    /// bound, so the result has `buckets.len() + 1` entries. Timestamps
+    /// after `now_ts` count as age zero.
+    ///
+    /// Only meaningful if orders carry their entry time as timestamp.
+    ///
+    /// # Performance
+    /// O(N log B) where N is the number of resting orders and B the number
+    /// of buckets.
+    ///
+    /// # Examples
+    /// ```
+    /// use orderbook_rs::OrderBook;
+    /// use pricelevel::{Id, Side, TimeInForce};
+    ///
+    /// let book = OrderBook::<()>::new("BTC/USD");
+    /// let _ = book.add_limit_order(Id::new(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    ///
+    /// let now = orderbook_rs::current_time_millis();
+    /// let histogram = book.order_age_histogram(now + 5_000, &[1_000, 60_000]);
+    /// assert_eq!(histogram, vec![0, 1, 0]);
+    /// ```
+    #[must_use]
+    pub fn order_age_histogram(&self, now_ts: u64, buckets: &[u64]) -> Vec<usize> {
+        let mut histogram = vec![0usize; buckets.len() + 1];
+        for levels in [&self.bids, &self.asks] {
+            for entry in levels.iter() {
+                for order in entry.value().iter_orders() {
+                    let age = now_ts.saturating_sub(order.timestamp());
+                    histogram[buckets.partition_point(|&bound| bound <= age)] += 1;
+                }
+            }
+        }
+        histogram
+    }
 }
 
 // Implementation of RepricingOperations trait for OrderBook
@@ -2922,7 +3999,7 @@ where
                         order_id,
                         new_price: pricelevel::Price::new(new_price),
                     };
-                    if self.update_order(update).is_ok() {
+                    if self.apply_order_update(update).is_ok() {
                         repriced_count += 1;
                         trace!(
                             "Re-priced pegged order {} from {} to {}",
@@ -2982,7 +4059,7 @@ where
                             order_id,
                             new_price: pricelevel::Price::new(new_stop_price),
                         };
-                        if self.update_order(update).is_ok() {
+                        if self.apply_order_update(update).is_ok() {
                             repriced_count += 1;
                             trace!(
                                 "Re-priced trailing stop {} from {} to {} (ref: {} -> {})",
@@ -0,0 +1,237 @@
+//! Builder for configured order books.
+//!
+//! [`OrderBookBuilder`] collects the validation and matching configuration
+//! of a book in one chained expression and applies it in
+//! [`build`](OrderBookBuilder::build), instead of a sequence of setter calls
+//! on a mutable book.
+
+use super::book::OrderBook;
+use super::fees::FeeSchedule;
+use super::reference_price::ReferencePricePolicy;
+use super::stp::STPMode;
+use super::tick_rounding::TickRounding;
+
+/// Chained configuration for an [`OrderBook`].
+///
+/// Options that are not set keep the defaults of [`OrderBook::new`].
+///
+/// # Example
+///
+/// ```
+/// use orderbook_rs::{FeeSchedule, OrderBook, OrderBookBuilder, STPMode};
+///
+/// let book: OrderBook<()> = OrderBookBuilder::new()
+///     .tick_size(5)
+///     .lot_size(10)
+///     .fee_schedule(FeeSchedule::new(-2, 5))
+///     .stp_mode(STPMode::CancelTaker)
+///     .build("BTC/USD");
+///
+/// assert_eq!(book.tick_size(), Some(5));
+/// assert_eq!(book.stp_mode(), STPMode::CancelTaker);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OrderBookBuilder {
+    tick_size: Option<u128>,
+    tick_rounding: Option<TickRounding>,
+    lot_size: Option<u64>,
+    fee_schedule: Option<FeeSchedule>,
+    stp_mode: STPMode,
+    min_order_size: Option<u64>,
+    max_order_size: Option<u64>,
+    price_band_bps: Option<u32>,
+    price_decimals: Option<u32>,
+    min_spread_ticks: Option<u32>,
+    reference_price_policy: Option<ReferencePricePolicy>,
+}
+
+impl OrderBookBuilder {
+    /// Create a builder with every option unset.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum price increment (see [`OrderBook::set_tick_size`]).
+    #[must_use]
+    pub fn tick_size(mut self, tick_size: u128) -> Self {
+        self.tick_size = Some(tick_size);
+        self
+    }
+
+    /// Round off-tick prices on entry instead of rejecting them
+    /// (see [`OrderBook::set_tick_rounding`]).
+    #[must_use]
+    pub fn tick_rounding(mut self, rounding: TickRounding) -> Self {
+        self.tick_rounding = Some(rounding);
+        self
+    }
+
+    /// Set the minimum quantity increment (see [`OrderBook::set_lot_size`]).
+    #[must_use]
+    pub fn lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = Some(lot_size);
+        self
+    }
+
+    /// Set the fee schedule (see [`OrderBook::set_fee_schedule`]).
+    #[must_use]
+    pub fn fee_schedule(mut self, fee_schedule: FeeSchedule) -> Self {
+        self.fee_schedule = Some(fee_schedule);
+        self
+    }
+
+    /// Set the Self-Trade Prevention mode (see [`OrderBook::set_stp_mode`]).
+    #[must_use]
+    pub fn stp_mode(mut self, mode: STPMode) -> Self {
+        self.stp_mode = mode;
+        self
+    }
+
+    /// Set the minimum order size (see [`OrderBook::set_min_order_size`]).
+    #[must_use]
+    pub fn min_order_size(mut self, size: u64) -> Self {
+        self.min_order_size = Some(size);
+        self
+    }
+
+    /// Set the maximum order size (see [`OrderBook::set_max_order_size`]).
+    #[must_use]
+    pub fn max_order_size(mut self, size: u64) -> Self {
+        self.max_order_size = Some(size);
+        self
+    }
+
+    /// Set the price band around the reference price, in basis points
+    /// (see [`OrderBook::set_price_band_bps`]).
+    #[must_use]
+    pub fn price_band(mut self, bps: u32) -> Self {
+        self.price_band_bps = Some(bps);
+        self
+    }
+
+    /// Set the number of decimals of the integer price scale
+    /// (see [`OrderBook::set_price_decimals`]).
+    #[must_use]
+    pub fn price_decimals(mut self, price_decimals: u32) -> Self {
+        self.price_decimals = Some(price_decimals);
+        self
+    }
+
+    /// Set the minimum spread, in ticks, a new resting order may leave
+    /// (see [`OrderBook::set_min_spread_ticks`]).
+    #[must_use]
+    pub fn min_spread_ticks(mut self, ticks: u32) -> Self {
+        self.min_spread_ticks = Some(ticks);
+        self
+    }
+
+    /// Set how the reference price for price bands is derived
+    /// (see [`OrderBook::set_reference_price_policy`]).
+    #[must_use]
+    pub fn reference_price_policy(mut self, policy: ReferencePricePolicy) -> Self {
+        self.reference_price_policy = Some(policy);
+        self
+    }
+
+    /// Build an empty order book for `symbol` with the configured options.
+    #[must_use]
+    pub fn build<T>(self, symbol: &str) -> OrderBook<T>
+    where
+        T: Clone + Send + Sync + Default + 'static,
+    {
+        let mut book = OrderBook::new(symbol);
+        if let Some(tick_size) = self.tick_size {
+            book.set_tick_size(tick_size);
+        }
+        book.set_tick_rounding(self.tick_rounding);
+        if let Some(lot_size) = self.lot_size {
+            book.set_lot_size(lot_size);
+        }
+        book.set_fee_schedule(self.fee_schedule);
+        book.set_stp_mode(self.stp_mode);
+        if let Some(size) = self.min_order_size {
+            book.set_min_order_size(size);
+        }
+        if let Some(size) = self.max_order_size {
+            book.set_max_order_size(size);
+        }
+        book.set_price_band_bps(self.price_band_bps);
+        if let Some(price_decimals) = self.price_decimals {
+            book.set_price_decimals(price_decimals);
+        }
+        book.set_min_spread_ticks(self.min_spread_ticks);
+        if let Some(policy) = self.reference_price_policy {
+            book.set_reference_price_policy(policy);
+        }
+        book
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns a builder for a configured order book.
+    #[must_use]
+    pub fn builder() -> OrderBookBuilder {
+        OrderBookBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_options_are_reflected_by_getters() {
+        let fees = FeeSchedule::new(-2, 5);
+        let book: OrderBook<()> = OrderBook::<()>::builder()
+            .tick_size(5)
+            .tick_rounding(TickRounding::default())
+            .lot_size(10)
+            .fee_schedule(fees)
+            .stp_mode(STPMode::CancelBoth)
+            .min_order_size(10)
+            .max_order_size(1_000)
+            .price_band(500)
+            .price_decimals(2)
+            .min_spread_ticks(3)
+            .reference_price_policy(ReferencePricePolicy::Manual)
+            .build("BTC/USD");
+
+        assert_eq!(book.symbol(), "BTC/USD");
+        assert_eq!(book.tick_size(), Some(5));
+        assert_eq!(book.tick_rounding(), Some(TickRounding::default()));
+        assert_eq!(book.lot_size(), Some(10));
+        assert_eq!(book.fee_schedule(), Some(fees));
+        assert_eq!(book.stp_mode(), STPMode::CancelBoth);
+        assert_eq!(book.min_order_size(), Some(10));
+        assert_eq!(book.max_order_size(), Some(1_000));
+        assert_eq!(book.price_band_bps(), Some(500));
+        assert_eq!(book.price_decimals(), 2);
+        assert_eq!(book.min_spread_ticks(), Some(3));
+        assert_eq!(book.reference_price_policy(), ReferencePricePolicy::Manual);
+    }
+
+    #[test]
+    fn test_default_builder_matches_new() {
+        let built: OrderBook<()> = OrderBookBuilder::new().build("TEST");
+        let plain: OrderBook<()> = OrderBook::new("TEST");
+
+        assert_eq!(built.tick_size(), plain.tick_size());
+        assert_eq!(built.tick_rounding(), plain.tick_rounding());
+        assert_eq!(built.lot_size(), plain.lot_size());
+        assert_eq!(built.fee_schedule(), plain.fee_schedule());
+        assert_eq!(built.stp_mode(), plain.stp_mode());
+        assert_eq!(built.min_order_size(), plain.min_order_size());
+        assert_eq!(built.max_order_size(), plain.max_order_size());
+        assert_eq!(built.price_band_bps(), plain.price_band_bps());
+        assert_eq!(built.price_decimals(), plain.price_decimals());
+        assert_eq!(built.min_spread_ticks(), plain.min_spread_ticks());
+        assert_eq!(
+            built.reference_price_policy(),
+            plain.reference_price_policy()
+        );
+    }
+}
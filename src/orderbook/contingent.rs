@@ -0,0 +1,393 @@
+//! Orders submitted automatically when another order fully fills.
+//!
+//! [`OrderBook::add_contingent`] registers an order to be submitted as soon
+//! as a resting trigger order is completely filled, e.g. a hedge or a take
+//! profit that should only exist once the entry is done. The contingent
+//! order goes through the regular add path once the operation whose fill
+//! completed the trigger is done, after the incoming order's remainder has
+//! rested, so it trades with that remainder rather than resting beside it.
+//! The submission is recorded as a [`TriggeredContingent`] so a command
+//! loop can journal it as its own event. Cancelling the trigger drops its
+//! contingent orders, while an amend keeps them waiting on the amended
+//! order. Snapshot packages carry the orders still waiting, and a plain
+//! [`OrderBook::restore_from_snapshot`] drops them.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::modifications::{AddOrderResult, RemainderPolicy};
+use pricelevel::{Id, MatchResult, OrderType};
+use tracing::trace;
+
+/// A contingent order submitted because its trigger order filled.
+#[derive(Debug)]
+pub struct TriggeredContingent<T> {
+    /// The order whose fill triggered the submission.
+    pub trigger_order_id: Id,
+    /// The submitted contingent order.
+    pub order: OrderType<T>,
+    /// Outcome of adding the contingent order to the book.
+    pub outcome: Result<AddOrderResult<T>, OrderBookError>,
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Register `order` to be submitted when the resting order
+    /// `trigger_order_id` fully fills.
+    ///
+    /// Several orders may wait on the same trigger; they are submitted in
+    /// registration order. A partial fill of the trigger does not submit
+    /// anything. A trigger filled by an auction uncross submits its orders
+    /// once the book is continuous again.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::OrderNotFound`] if the trigger order is not
+    /// resting in the book.
+    pub fn add_contingent(
+        &self,
+        trigger_order_id: Id,
+        order: OrderType<T>,
+    ) -> Result<(), OrderBookError> {
+        if !self.order_locations.contains_key(&trigger_order_id) {
+            return Err(OrderBookError::OrderNotFound(trigger_order_id.to_string()));
+        }
+        trace!(
+            "Order book {}: Order {} waits on fill of {}",
+            self.symbol,
+            order.id(),
+            trigger_order_id
+        );
+        self.contingent_orders
+            .entry(trigger_order_id)
+            .or_default()
+            .push(order);
+        Ok(())
+    }
+
+    /// Returns the orders waiting on `trigger_order_id`, in registration
+    /// order.
+    #[must_use]
+    pub fn contingent_orders(&self, trigger_order_id: Id) -> Vec<OrderType<T>> {
+        self.contingent_orders
+            .get(&trigger_order_id)
+            .map(|orders| orders.clone())
+            .unwrap_or_default()
+    }
+
+    /// Remove and return the orders waiting on `trigger_order_id`.
+    pub fn remove_contingent(&self, trigger_order_id: Id) -> Vec<OrderType<T>> {
+        self.contingent_orders
+            .remove(&trigger_order_id)
+            .map(|(_, orders)| orders)
+            .unwrap_or_default()
+    }
+
+    /// Returns the waiting orders by trigger with unit extra fields, for
+    /// snapshots.
+    pub(super) fn raw_contingent_orders(&self) -> Vec<(Id, Vec<OrderType<()>>)> {
+        self.contingent_orders
+            .iter()
+            .map(|entry| {
+                let orders = entry
+                    .value()
+                    .iter()
+                    .map(|order| self.convert_to_unit_type(order))
+                    .collect();
+                (*entry.key(), orders)
+            })
+            .collect()
+    }
+
+    /// Replace the waiting orders with orders taken by
+    /// [`raw_contingent_orders`](Self::raw_contingent_orders). Orders whose
+    /// trigger is not resting are dropped, and the rest get default extra
+    /// fields.
+    pub(super) fn restore_contingent_orders(&self, raw: Vec<(Id, Vec<OrderType<()>>)>) {
+        self.contingent_orders.clear();
+        for (trigger_order_id, orders) in raw {
+            if !self.order_locations.contains_key(&trigger_order_id) {
+                continue;
+            }
+            let orders = orders
+                .iter()
+                .map(|order| self.convert_from_unit_type(order))
+                .collect();
+            self.contingent_orders.insert(trigger_order_id, orders);
+        }
+    }
+
+    /// Drain the contingent orders submitted since the last call, in
+    /// submission order.
+    ///
+    /// A command loop calls this after each command to journal the
+    /// submissions; see
+    /// [`contingent_events`](crate::orderbook::sequencer::contingent_events).
+    pub fn take_triggered_contingents(&self) -> Vec<TriggeredContingent<T>> {
+        std::iter::from_fn(|| self.triggered_contingents.pop()).collect()
+    }
+
+    /// Submit the contingent orders of every maker that `match_result`
+    /// filled completely.
+    pub(super) fn submit_triggered_contingents(&self, match_result: &MatchResult) {
+        if self.contingent_orders.is_empty() {
+            return;
+        }
+        for trigger_order_id in match_result.filled_order_ids() {
+            if let Some((_, orders)) = self.contingent_orders.remove(trigger_order_id) {
+                self.submit_contingents(*trigger_order_id, orders);
+            }
+        }
+    }
+
+    /// Submit `orders`, the contingent orders of `trigger_order_id`, which
+    /// just filled completely.
+    pub(super) fn submit_contingents(&self, trigger_order_id: Id, orders: Vec<OrderType<T>>) {
+        for order in orders {
+            trace!(
+                "Order book {}: Fill of {} submits contingent order {}",
+                self.symbol,
+                trigger_order_id,
+                order.id()
+            );
+            self.submit_triggered(trigger_order_id, order);
+        }
+    }
+
+    /// Submit `order`, triggered by `trigger_order_id`, once the operation
+    /// in progress on this thread has completed, or now if there is none.
+    ///
+    /// A trigger fires while the order that traded with it is still
+    /// matching; adding the triggered order then would let it rest before
+    /// that order's remainder and lock or cross the book.
+    pub(super) fn submit_triggered(&self, trigger_order_id: Id, order: OrderType<T>) {
+        if self.write_in_progress() {
+            self.deferred_submissions
+                .entry(std::thread::current().id())
+                .or_default()
+                .push_back((trigger_order_id, order));
+            return;
+        }
+        self.submit_now(trigger_order_id, order);
+    }
+
+    /// Submit the orders this thread's completed operation triggered, in
+    /// trigger order, including those their own fills trigger.
+    pub(super) fn submit_deferred(&self) {
+        let thread = std::thread::current().id();
+        loop {
+            let next = self
+                .deferred_submissions
+                .get_mut(&thread)
+                .and_then(|mut queue| queue.pop_front());
+            let Some((trigger_order_id, order)) = next else {
+                break;
+            };
+            self.submit_now(trigger_order_id, order);
+        }
+        self.deferred_submissions
+            .remove_if(&thread, |_, queue| queue.is_empty());
+    }
+
+    /// Add `order` and record the submission for
+    /// [`take_triggered_contingents`](Self::take_triggered_contingents).
+    fn submit_now(&self, trigger_order_id: Id, order: OrderType<T>) {
+        let outcome = self.add_order_with_remainder(order.clone(), RemainderPolicy::Rest);
+        self.triggered_contingents.push(TriggeredContingent {
+            trigger_order_id,
+            order,
+            outcome,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, OrderUpdate, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn standard(price: u128, quantity: u64, side: Side) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    #[test]
+    fn test_full_fill_of_trigger_submits_contingent() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        let take_profit = standard(110, 10, Side::Sell);
+        book.add_contingent(trigger.id(), take_profit).unwrap();
+        assert_eq!(book.contingent_orders(trigger.id()).len(), 1);
+
+        // A partial fill does not trigger
+        book.submit_market_order(Id::new_uuid(), 4, Side::Sell)
+            .unwrap();
+        assert!(book.get_order(take_profit.id()).is_none());
+        assert!(book.take_triggered_contingents().is_empty());
+
+        book.submit_market_order(Id::new_uuid(), 6, Side::Sell)
+            .unwrap();
+        assert!(book.get_order(take_profit.id()).is_some());
+        assert_eq!(book.best_ask(), Some(110));
+
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, trigger.id());
+        assert_eq!(triggered[0].order.id(), take_profit.id());
+        assert!(triggered[0].outcome.is_ok());
+        assert!(book.contingent_orders(trigger.id()).is_empty());
+        assert!(book.take_triggered_contingents().is_empty());
+    }
+
+    #[test]
+    fn test_contingent_waits_for_the_incoming_remainder_to_rest() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        let contingent = standard(100, 10, Side::Buy);
+        book.add_contingent(trigger.id(), contingent).unwrap();
+
+        // The sell fills the trigger and rests 5, which the contingent buy
+        // then trades with instead of locking the book
+        let sell = standard(100, 15, Side::Sell);
+        book.add_order(sell).unwrap();
+        assert!(!book.check_crossed());
+        assert!(book.get_order(sell.id()).is_none());
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(book.best_bid(), Some(100));
+        assert_eq!(
+            book.get_order(contingent.id()).unwrap().visible_quantity(),
+            5
+        );
+
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        let outcome = triggered[0].outcome.as_ref().unwrap();
+        assert_eq!(outcome.match_result.filled_order_ids(), &[sell.id()]);
+    }
+
+    #[test]
+    fn test_auction_uncross_submits_contingents_of_filled_triggers() {
+        use crate::orderbook::TradingState;
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_trading_state(TradingState::Auction);
+        let trigger = standard(105, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        book.add_order(standard(100, 10, Side::Sell)).unwrap();
+        let take_profit = standard(120, 10, Side::Sell);
+        book.add_contingent(trigger.id(), take_profit).unwrap();
+
+        book.run_auction_uncross().unwrap();
+        assert!(book.get_order(trigger.id()).is_none());
+        assert!(book.contingent_orders(trigger.id()).is_empty());
+        assert_eq!(book.best_ask(), Some(120));
+
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].order.id(), take_profit.id());
+        assert!(triggered[0].outcome.is_ok());
+        let package = book.create_snapshot_package(10).unwrap();
+        assert!(package.contingent_orders.is_empty());
+    }
+
+    #[test]
+    fn test_cancelling_trigger_removes_contingency() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        book.add_contingent(trigger.id(), standard(110, 10, Side::Sell))
+            .unwrap();
+
+        book.cancel_order(trigger.id()).unwrap();
+        assert!(book.contingent_orders(trigger.id()).is_empty());
+        assert_eq!(book.best_ask(), None);
+
+        // Cancelling through an update drops them as well
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        book.add_contingent(trigger.id(), standard(110, 10, Side::Sell))
+            .unwrap();
+        book.update_order(OrderUpdate::Cancel {
+            order_id: trigger.id(),
+        })
+        .unwrap();
+        assert!(book.contingent_orders(trigger.id()).is_empty());
+    }
+
+    #[test]
+    fn test_amended_trigger_keeps_contingency() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        let take_profit = standard(110, 10, Side::Sell);
+        book.add_contingent(trigger.id(), take_profit).unwrap();
+
+        for update in [
+            OrderUpdate::UpdatePrice {
+                order_id: trigger.id(),
+                new_price: Price::new(101),
+            },
+            OrderUpdate::UpdatePriceAndQuantity {
+                order_id: trigger.id(),
+                new_price: Price::new(102),
+                new_quantity: Quantity::new(5),
+            },
+            OrderUpdate::Replace {
+                order_id: trigger.id(),
+                price: Price::new(100),
+                quantity: Quantity::new(5),
+                side: Side::Buy,
+            },
+        ] {
+            book.update_order(update).unwrap();
+            assert_eq!(book.contingent_orders(trigger.id()), vec![take_profit]);
+        }
+
+        book.submit_market_order(Id::new_uuid(), 5, Side::Sell)
+            .unwrap();
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, trigger.id());
+    }
+
+    #[test]
+    fn test_amend_that_fills_trigger_submits_contingent() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let trigger = standard(100, 10, Side::Buy);
+        book.add_order(trigger).unwrap();
+        let take_profit = standard(110, 10, Side::Sell);
+        book.add_contingent(trigger.id(), take_profit).unwrap();
+        book.add_order(standard(105, 10, Side::Sell)).unwrap();
+
+        // Repricing through the ask fills the trigger completely
+        book.update_order(OrderUpdate::UpdatePrice {
+            order_id: trigger.id(),
+            new_price: Price::new(105),
+        })
+        .unwrap();
+        assert!(book.get_order(trigger.id()).is_none());
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].order.id(), take_profit.id());
+        assert!(book.get_order(take_profit.id()).is_some());
+    }
+
+    #[test]
+    fn test_unknown_trigger_is_rejected() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert!(matches!(
+            book.add_contingent(Id::new_uuid(), standard(110, 10, Side::Sell)),
+            Err(OrderBookError::OrderNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+//! Detection of a crossed book after each command.
+//!
+//! Matching removes every crossing with the opposite side before an order
+//! rests, so outside an auction the best bid must stay below the best ask.
+//! A book found crossed points to a matching bug. Rather than fail the
+//! command, [`execute_command`](crate::orderbook::sequencer::execute_command)
+//! calls [`OrderBook::check_crossed`] after every command, which records the
+//! incident in a sticky [`OrderBook::ever_crossed`] flag and a count, and
+//! calls the [`CrossedBookListener`] if one is set, for alerting. Callers
+//! driving the book directly can call the check themselves.
+//!
+//! A locked book, best bid equal to best ask, counts as crossed. Every check
+//! that finds the book crossed is counted, so a book left crossed is
+//! counted once per command until it uncrosses.
+
+use super::book::OrderBook;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use tracing::error;
+
+/// Callback invoked with `(best_bid, best_ask)` when a check finds the book
+/// crossed.
+pub type CrossedBookListener = Arc<dyn Fn(u128, u128) + Send + Sync>;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the listener called when a check finds the book crossed.
+    pub fn set_crossed_book_listener(&mut self, listener: CrossedBookListener) {
+        self.crossed_book_listener = Some(listener);
+    }
+
+    /// Remove the crossed book listener.
+    pub fn remove_crossed_book_listener(&mut self) {
+        self.crossed_book_listener = None;
+    }
+
+    /// Returns `true` if a check has ever found the book crossed.
+    #[must_use]
+    pub fn ever_crossed(&self) -> bool {
+        self.crossed_incidents() > 0
+    }
+
+    /// Returns the number of checks that found the book crossed.
+    #[must_use]
+    pub fn crossed_incidents(&self) -> u64 {
+        self.crossed_incidents.load(Ordering::Relaxed)
+    }
+
+    /// Check that the best bid is below the best ask, and record an
+    /// incident if it is not. Crossing is expected during an auction, which
+    /// is never reported.
+    ///
+    /// Returns `true` if the book is crossed outside an auction.
+    pub fn check_crossed(&self) -> bool {
+        if self.is_auction() {
+            return false;
+        }
+        let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) else {
+            return false;
+        };
+        if bid < ask {
+            return false;
+        }
+        error!(
+            "Order book {}: Book is crossed: best bid {} >= best ask {}",
+            self.symbol, bid, ask
+        );
+        self.crossed_incidents.fetch_add(1, Ordering::Relaxed);
+        if let Some(listener) = &self.crossed_book_listener {
+            listener(bid, ask);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::sequencer::{SequencerCommand, execute_command};
+    use pricelevel::{
+        Hash32, Id, OrderType, Price, PriceLevel, Quantity, Side, TimeInForce, TimestampMs,
+    };
+    use std::sync::atomic::AtomicU64;
+
+    fn limit(price: u128, side: Side) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(10),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    /// Rest a bid at `price` without matching it, as a matching bug would.
+    fn force_bid(book: &OrderBook<()>, price: u128) {
+        let level = Arc::new(PriceLevel::new(price));
+        level.add_order(limit(price, Side::Buy));
+        book.bids.insert(price, level);
+        book.cache.invalidate();
+    }
+
+    #[test]
+    fn test_normal_operation_never_crosses() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for (price, side) in [
+            (101, Side::Sell),
+            (103, Side::Sell),
+            (99, Side::Buy),
+            (102, Side::Buy),
+        ] {
+            execute_command(&book, &SequencerCommand::AddOrder(limit(price, side)));
+        }
+        assert_eq!((book.best_bid(), book.best_ask()), (Some(99), Some(103)));
+        assert!(!book.check_crossed());
+        assert!(!book.ever_crossed());
+        assert_eq!(book.crossed_incidents(), 0);
+    }
+
+    #[test]
+    fn test_forced_cross_is_recorded_and_reported() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        let alerts = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&alerts);
+        book.set_crossed_book_listener(Arc::new(move |bid, ask| {
+            assert_eq!((bid, ask), (101, 100));
+            counter.fetch_add(1, Ordering::Relaxed);
+        }));
+        execute_command(&book, &SequencerCommand::AddOrder(limit(100, Side::Sell)));
+        assert!(!book.ever_crossed());
+
+        force_bid(&book, 101);
+        execute_command(&book, &SequencerCommand::CancelOrder(Id::new_uuid()));
+        assert!(book.ever_crossed());
+        assert_eq!(book.crossed_incidents(), 1);
+        assert_eq!(alerts.load(Ordering::Relaxed), 1);
+
+        // The flag stays set once the book uncrosses
+        book.bids.remove(&101);
+        book.cache.invalidate();
+        assert!(!book.check_crossed());
+        assert!(book.ever_crossed());
+        assert_eq!(book.crossed_incidents(), 1);
+    }
+}
@@ -1,6 +1,6 @@
 //! Order book error types
 
-use pricelevel::{PriceLevelError, Side};
+use pricelevel::{Hash32, PriceLevelError, Side};
 use std::fmt;
 
 /// Errors that can occur within the OrderBook
@@ -107,6 +107,161 @@ pub enum OrderBookError {
         user_id: pricelevel::Hash32,
     },
 
+    /// Order price falls outside the configured price band around the
+    /// active reference price
+    PriceOutsideBand {
+        /// The order price that failed validation
+        price: u128,
+        /// The active reference price
+        reference: u128,
+        /// Lowest accepted price (inclusive)
+        lower: u128,
+        /// Highest accepted price (inclusive)
+        upper: u128,
+    },
+
+    /// Order would lock or cross the external reference BBO.
+    LocksExternalBbo {
+        /// The order price that failed validation
+        price: u128,
+        /// The side of the order
+        side: Side,
+        /// External best bid
+        external_bid: u128,
+        /// External best ask
+        external_ask: u128,
+    },
+
+    /// A quantity total that must be exact overflowed `u64`.
+    QuantityOverflow {
+        /// The side whose total overflowed
+        side: Side,
+    },
+
+    /// Snapshot describes a book state that cannot exist.
+    InvalidSnapshot {
+        /// Description of the inconsistency
+        message: String,
+    },
+
+    /// Adding the order would push the user's resting quantity on a side
+    /// over the configured limit.
+    UserLimitExceeded {
+        /// The user whose limit would be exceeded
+        user_id: Hash32,
+        /// The side of the order
+        side: Side,
+        /// Maximum resting quantity allowed for the user on this side
+        limit: u64,
+        /// Quantity the user already has resting on this side
+        resting: u64,
+        /// Quantity of the rejected order
+        requested: u64,
+    },
+
+    /// Decimal price cannot be represented as an integer price with the
+    /// configured number of price decimals
+    InvalidDecimalPrice {
+        /// The decimal price that failed conversion
+        value: f64,
+        /// The configured number of price decimals
+        price_decimals: u32,
+    },
+
+    /// Resting order would narrow the spread below the configured minimum
+    SpreadTooTight {
+        /// The order price
+        price: u128,
+        /// The order side
+        side: Side,
+        /// The best price on the opposite side
+        opposite_price: u128,
+        /// The minimum spread in price units
+        min_spread: u128,
+    },
+
+    /// Cancel requested by a user who does not own the order
+    Unauthorized {
+        /// The order the cancel was addressed to
+        order_id: pricelevel::Id,
+        /// The user that requested the cancel
+        user_id: Hash32,
+    },
+
+    /// An order's timestamp is earlier than the last accepted order's under
+    /// [`TimestampPolicy::RejectOutOfOrder`](super::timestamp_policy::TimestampPolicy::RejectOutOfOrder).
+    TimestampOutOfOrder {
+        /// The rejected order
+        order_id: pricelevel::Id,
+        /// The order's timestamp in milliseconds
+        timestamp: u64,
+        /// Timestamp of the last accepted order in milliseconds
+        last_timestamp: u64,
+    },
+
+    /// Compare-and-cancel found a remaining quantity other than the expected one
+    QuantityChanged {
+        /// The order the cancel was addressed to
+        order_id: pricelevel::Id,
+        /// The remaining quantity the caller last saw
+        expected: u64,
+        /// The order's remaining quantity in the book
+        actual: u64,
+    },
+
+    /// Cancel requested before the order rested for the minimum rest time
+    /// set with [`OrderBook::set_min_rest_time_ns`](super::OrderBook::set_min_rest_time_ns)
+    MinRestTimeNotMet {
+        /// Nanoseconds the order has rested
+        elapsed: u64,
+        /// Minimum rest time in nanoseconds
+        required: u64,
+    },
+
+    /// Reduce-only order would add to the user's exposure: its quantity
+    /// exceeds what the user has resting on the opposite side
+    ReduceOnlyExceeded {
+        /// The user of the order
+        user_id: Hash32,
+        /// The side of the order
+        side: Side,
+        /// Quantity of the rejected order
+        requested: u64,
+        /// Quantity the user has resting on the opposite side
+        reducible: u64,
+    },
+
+    /// Order rejected because its price level already holds the maximum
+    /// number of orders set with
+    /// [`OrderBook::set_max_orders_per_level`](super::OrderBook::set_max_orders_per_level)
+    LevelFull {
+        /// The price of the full level
+        price: u128,
+    },
+
+    /// Aggressive order rejected as a likely fat finger: it crosses the
+    /// opposite best price by more than the tolerance set with
+    /// [`OrderBook::set_marketable_guard`](super::OrderBook::set_marketable_guard)
+    ExcessiveCross {
+        /// The order price that failed validation
+        price: u128,
+        /// The side of the order
+        side: Side,
+        /// The best price on the opposite side
+        opposite_price: u128,
+        /// The furthest price the order may cross to (inclusive)
+        limit: u128,
+    },
+
+    /// Command submitted for an expected sequence number it was not
+    /// assigned: the sequence advanced since the client observed it
+    SequenceConflict {
+        /// The sequence number the client expected the command to receive
+        expected: u64,
+        /// The sequence number the command was assigned
+        actual: u64,
+    },
+
     /// Failed to publish a trade event to NATS JetStream.
     #[cfg(feature = "nats")]
     NatsPublishError {
@@ -197,6 +352,126 @@ impl fmt::Display for OrderBookError {
                     "self-trade prevented ({mode}): taker {taker_order_id}, user {user_id}"
                 )
             }
+            OrderBookError::PriceOutsideBand {
+                price,
+                reference,
+                lower,
+                upper,
+            } => {
+                write!(
+                    f,
+                    "price {price} outside band [{lower}, {upper}] around reference {reference}"
+                )
+            }
+            OrderBookError::LocksExternalBbo {
+                price,
+                side,
+                external_bid,
+                external_ask,
+            } => {
+                write!(
+                    f,
+                    "{side} order at price {price} would lock or cross external BBO {external_bid}/{external_ask}"
+                )
+            }
+            OrderBookError::QuantityOverflow { side } => {
+                write!(f, "total quantity on {side} side overflows u64")
+            }
+            OrderBookError::InvalidSnapshot { message } => {
+                write!(f, "invalid snapshot: {message}")
+            }
+            OrderBookError::UserLimitExceeded {
+                user_id,
+                side,
+                limit,
+                resting,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "user {user_id} {side} resting quantity {resting} plus {requested} exceeds limit {limit}"
+                )
+            }
+            OrderBookError::InvalidDecimalPrice {
+                value,
+                price_decimals,
+            } => {
+                write!(
+                    f,
+                    "invalid decimal price: {value} cannot be represented with {price_decimals} price decimals"
+                )
+            }
+            OrderBookError::SpreadTooTight {
+                price,
+                side,
+                opposite_price,
+                min_spread,
+            } => {
+                write!(
+                    f,
+                    "spread too tight: {side} at {price} against {opposite_price} is below the minimum spread {min_spread}"
+                )
+            }
+            OrderBookError::Unauthorized { order_id, user_id } => {
+                write!(
+                    f,
+                    "unauthorized: user {user_id} does not own order {order_id}"
+                )
+            }
+            OrderBookError::TimestampOutOfOrder {
+                order_id,
+                timestamp,
+                last_timestamp,
+            } => {
+                write!(
+                    f,
+                    "order {order_id} timestamp {timestamp} is before last accepted timestamp {last_timestamp}"
+                )
+            }
+            OrderBookError::QuantityChanged {
+                order_id,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "quantity changed: order {order_id} has {actual} remaining, expected {expected}"
+                )
+            }
+            OrderBookError::MinRestTimeNotMet { elapsed, required } => {
+                write!(
+                    f,
+                    "minimum rest time not met: order rested {elapsed} ns of the required {required} ns"
+                )
+            }
+            OrderBookError::ReduceOnlyExceeded {
+                user_id,
+                side,
+                requested,
+                reducible,
+            } => {
+                write!(
+                    f,
+                    "reduce-only {side} order of {requested} for user {user_id} exceeds the {reducible} it could reduce"
+                )
+            }
+            OrderBookError::LevelFull { price } => {
+                write!(f, "price level {price} is full")
+            }
+            OrderBookError::ExcessiveCross {
+                price,
+                side,
+                opposite_price,
+                limit,
+            } => {
+                write!(
+                    f,
+                    "{side} order at {price} crosses best opposite price {opposite_price} beyond limit {limit}"
+                )
+            }
+            OrderBookError::SequenceConflict { expected, actual } => {
+                write!(f, "sequence conflict: expected {expected}, actual {actual}")
+            }
             #[cfg(feature = "nats")]
             OrderBookError::NatsPublishError { message } => {
                 write!(f, "nats publish error: {message}")
@@ -329,6 +604,122 @@ impl Clone for OrderBookError {
                 taker_order_id: *taker_order_id,
                 user_id: *user_id,
             },
+            OrderBookError::PriceOutsideBand {
+                price,
+                reference,
+                lower,
+                upper,
+            } => OrderBookError::PriceOutsideBand {
+                price: *price,
+                reference: *reference,
+                lower: *lower,
+                upper: *upper,
+            },
+            OrderBookError::LocksExternalBbo {
+                price,
+                side,
+                external_bid,
+                external_ask,
+            } => OrderBookError::LocksExternalBbo {
+                price: *price,
+                side: *side,
+                external_bid: *external_bid,
+                external_ask: *external_ask,
+            },
+            OrderBookError::QuantityOverflow { side } => {
+                OrderBookError::QuantityOverflow { side: *side }
+            }
+            OrderBookError::InvalidSnapshot { message } => OrderBookError::InvalidSnapshot {
+                message: message.clone(),
+            },
+            OrderBookError::UserLimitExceeded {
+                user_id,
+                side,
+                limit,
+                resting,
+                requested,
+            } => OrderBookError::UserLimitExceeded {
+                user_id: *user_id,
+                side: *side,
+                limit: *limit,
+                resting: *resting,
+                requested: *requested,
+            },
+            OrderBookError::InvalidDecimalPrice {
+                value,
+                price_decimals,
+            } => OrderBookError::InvalidDecimalPrice {
+                value: *value,
+                price_decimals: *price_decimals,
+            },
+            OrderBookError::SpreadTooTight {
+                price,
+                side,
+                opposite_price,
+                min_spread,
+            } => OrderBookError::SpreadTooTight {
+                price: *price,
+                side: *side,
+                opposite_price: *opposite_price,
+                min_spread: *min_spread,
+            },
+            OrderBookError::Unauthorized { order_id, user_id } => OrderBookError::Unauthorized {
+                order_id: *order_id,
+                user_id: *user_id,
+            },
+            OrderBookError::TimestampOutOfOrder {
+                order_id,
+                timestamp,
+                last_timestamp,
+            } => OrderBookError::TimestampOutOfOrder {
+                order_id: *order_id,
+                timestamp: *timestamp,
+                last_timestamp: *last_timestamp,
+            },
+            OrderBookError::QuantityChanged {
+                order_id,
+                expected,
+                actual,
+            } => OrderBookError::QuantityChanged {
+                order_id: *order_id,
+                expected: *expected,
+                actual: *actual,
+            },
+            OrderBookError::MinRestTimeNotMet { elapsed, required } => {
+                OrderBookError::MinRestTimeNotMet {
+                    elapsed: *elapsed,
+                    required: *required,
+                }
+            }
+            OrderBookError::ReduceOnlyExceeded {
+                user_id,
+                side,
+                requested,
+                reducible,
+            } => OrderBookError::ReduceOnlyExceeded {
+                user_id: *user_id,
+                side: *side,
+                requested: *requested,
+                reducible: *reducible,
+            },
+            OrderBookError::LevelFull { price } => OrderBookError::LevelFull { price: *price },
+            OrderBookError::ExcessiveCross {
+                price,
+                side,
+                opposite_price,
+                limit,
+            } => OrderBookError::ExcessiveCross {
+                price: *price,
+                side: *side,
+                opposite_price: *opposite_price,
+                limit: *limit,
+            },
+            OrderBookError::SequenceConflict { expected, actual } => {
+                OrderBookError::SequenceConflict {
+                    expected: *expected,
+                    actual: *actual,
+                }
+            }
             #[cfg(feature = "nats")]
             OrderBookError::NatsPublishError { message } => OrderBookError::NatsPublishError {
                 message: message.clone(),
@@ -366,7 +757,7 @@ impl std::error::Error for ManagerError {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pricelevel::{Hash32, Id};
+    use pricelevel::Id;
 
     #[test]
     fn test_clone_order_not_found() {
@@ -590,4 +981,272 @@ mod tests {
             }) if expected == "hash1" && actual == "hash2"
         ));
     }
+
+    #[test]
+    fn test_clone_price_outside_band() {
+        let error = OrderBookError::PriceOutsideBand {
+            price: 120,
+            reference: 100,
+            lower: 90,
+            upper: 110,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::PriceOutsideBand {
+                price: 120,
+                reference: 100,
+                lower: 90,
+                upper: 110
+            }
+        ));
+        assert!(error.to_string().contains("outside band"));
+    }
+
+    #[test]
+    fn test_clone_locks_external_bbo() {
+        let error = OrderBookError::LocksExternalBbo {
+            price: 105,
+            side: Side::Buy,
+            external_bid: 100,
+            external_ask: 105,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::LocksExternalBbo {
+                price: 105,
+                side: Side::Buy,
+                external_bid: 100,
+                external_ask: 105
+            }
+        ));
+        assert!(error.to_string().contains("external BBO 100/105"));
+    }
+
+    #[test]
+    fn test_quantity_overflow_clone() {
+        let error = OrderBookError::QuantityOverflow { side: Side::Sell };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::QuantityOverflow { side: Side::Sell }
+        ));
+        assert!(error.to_string().contains("overflows u64"));
+    }
+
+    #[test]
+    fn test_invalid_snapshot_clone() {
+        let error = OrderBookError::InvalidSnapshot {
+            message: "crossed".to_string(),
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::InvalidSnapshot { ref message } if message == "crossed"
+        ));
+        assert_eq!(error.to_string(), "invalid snapshot: crossed");
+    }
+
+    #[test]
+    fn test_user_limit_exceeded_clone() {
+        let error = OrderBookError::UserLimitExceeded {
+            user_id: Hash32::zero(),
+            side: Side::Buy,
+            limit: 100,
+            resting: 80,
+            requested: 30,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::UserLimitExceeded {
+                limit: 100,
+                resting: 80,
+                requested: 30,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("exceeds limit 100"));
+    }
+
+    #[test]
+    fn test_invalid_decimal_price_clone() {
+        let error = OrderBookError::InvalidDecimalPrice {
+            value: 1.005,
+            price_decimals: 2,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::InvalidDecimalPrice {
+                price_decimals: 2,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("2 price decimals"));
+    }
+
+    #[test]
+    fn test_spread_too_tight_clone() {
+        let error = OrderBookError::SpreadTooTight {
+            price: 104,
+            side: Side::Buy,
+            opposite_price: 105,
+            min_spread: 2,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::SpreadTooTight {
+                price: 104,
+                opposite_price: 105,
+                min_spread: 2,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("minimum spread 2"));
+    }
+
+    #[test]
+    fn test_unauthorized_clone() {
+        let order_id = Id::new_uuid();
+        let error = OrderBookError::Unauthorized {
+            order_id,
+            user_id: Hash32::new([7; 32]),
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::Unauthorized { order_id: id, .. } if id == order_id
+        ));
+        assert!(error.to_string().contains("does not own order"));
+    }
+
+    #[test]
+    fn test_timestamp_out_of_order_clone() {
+        let error = OrderBookError::TimestampOutOfOrder {
+            order_id: Id::new_uuid(),
+            timestamp: 1,
+            last_timestamp: 2,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::TimestampOutOfOrder {
+                timestamp: 1,
+                last_timestamp: 2,
+                ..
+            }
+        ));
+        assert!(
+            error
+                .to_string()
+                .contains("before last accepted timestamp 2")
+        );
+    }
+
+    #[test]
+    fn test_quantity_changed_clone() {
+        let error = OrderBookError::QuantityChanged {
+            order_id: Id::new_uuid(),
+            expected: 10,
+            actual: 6,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::QuantityChanged {
+                expected: 10,
+                actual: 6,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("has 6 remaining, expected 10"));
+    }
+
+    #[test]
+    fn test_min_rest_time_not_met_clone() {
+        let error = OrderBookError::MinRestTimeNotMet {
+            elapsed: 1_000,
+            required: 5_000,
+        };
+        let cloned = error.clone();
+        assert!(matches!(
+            cloned,
+            OrderBookError::MinRestTimeNotMet {
+                elapsed: 1_000,
+                required: 5_000,
+            }
+        ));
+        assert!(
+            error
+                .to_string()
+                .contains("rested 1000 ns of the required 5000 ns")
+        );
+    }
+
+    #[test]
+    fn test_reduce_only_exceeded_display_and_clone() {
+        let error = OrderBookError::ReduceOnlyExceeded {
+            user_id: Hash32::zero(),
+            side: Side::Sell,
+            requested: 10,
+            reducible: 4,
+        };
+        assert!(matches!(
+            error.clone(),
+            OrderBookError::ReduceOnlyExceeded {
+                requested: 10,
+                reducible: 4,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("exceeds the 4 it could reduce"));
+    }
+
+    #[test]
+    fn test_level_full_display_and_clone() {
+        let error = OrderBookError::LevelFull { price: 100 };
+        assert!(matches!(
+            error.clone(),
+            OrderBookError::LevelFull { price: 100 }
+        ));
+        assert_eq!(error.to_string(), "price level 100 is full");
+    }
+
+    #[test]
+    fn test_excessive_cross_display_and_clone() {
+        let error = OrderBookError::ExcessiveCross {
+            price: 120,
+            side: Side::Buy,
+            opposite_price: 100,
+            limit: 105,
+        };
+        assert!(matches!(
+            error.clone(),
+            OrderBookError::ExcessiveCross {
+                price: 120,
+                limit: 105,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("beyond limit 105"));
+    }
+
+    #[test]
+    fn test_sequence_conflict_display_and_clone() {
+        let error = OrderBookError::SequenceConflict {
+            expected: 3,
+            actual: 5,
+        };
+        assert!(matches!(
+            error.clone(),
+            OrderBookError::SequenceConflict {
+                expected: 3,
+                actual: 5
+            }
+        ));
+        assert_eq!(error.to_string(), "sequence conflict: expected 3, actual 5");
+    }
 }
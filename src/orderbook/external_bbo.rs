@@ -0,0 +1,228 @@
+//! Locked/crossed market prevention against an external reference BBO.
+//!
+//! When this book is one of several venues for the same instrument, an
+//! order must sometimes not post at a price that locks or crosses the best
+//! bid/offer observed elsewhere. The external BBO is independent of the
+//! book's own best prices and is set with [`OrderBook::set_external_bbo`];
+//! it can be journaled through
+//! [`SequencerCommand::SetExternalBbo`](crate::orderbook::sequencer::SequencerCommand::SetExternalBbo)
+//! so that replay reproduces the same validation.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::tick_rounding::RoundingMode;
+use pricelevel::{OrderType, Side};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// How orders that would lock or cross the external BBO are handled.
+///
+/// A buy locks the external quote when its price is at or above the
+/// external ask; a sell when its price is at or below the external bid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ExternalBboPolicy {
+    /// Ignore the external BBO (default).
+    #[default]
+    Off,
+    /// Reject the order with [`OrderBookError::LocksExternalBbo`].
+    Reject,
+    /// Reprice the order one tick away from the external quote: a buy to
+    /// the highest tick below the external ask, a sell to the lowest tick
+    /// above the external bid.
+    Reprice,
+}
+
+impl std::fmt::Display for ExternalBboPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternalBboPolicy::Off => write!(f, "Off"),
+            ExternalBboPolicy::Reject => write!(f, "Reject"),
+            ExternalBboPolicy::Reprice => write!(f, "Reprice"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set or clear the external reference BBO as `(bid, ask)`.
+    pub fn set_external_bbo(&self, bbo: Option<(u128, u128)>) {
+        self.external_bbo.store(bbo);
+        trace!("Order book {}: Set external BBO to {:?}", self.symbol, bbo);
+    }
+
+    /// Returns the external reference BBO as `(bid, ask)`, if set.
+    #[must_use]
+    pub fn external_bbo(&self) -> Option<(u128, u128)> {
+        self.external_bbo.load()
+    }
+
+    /// Set the policy applied to orders that would lock or cross the
+    /// external BBO.
+    pub fn set_external_bbo_policy(&mut self, policy: ExternalBboPolicy) {
+        self.external_bbo_policy = policy;
+    }
+
+    /// Returns the external BBO policy.
+    #[must_use]
+    pub fn external_bbo_policy(&self) -> ExternalBboPolicy {
+        self.external_bbo_policy
+    }
+
+    /// Returns `true` if an order at `price` on `side` would lock or cross
+    /// the external BBO. Always `false` when no external BBO is set.
+    #[must_use]
+    pub fn would_lock_external_bbo(&self, price: u128, side: Side) -> bool {
+        match self.external_bbo() {
+            Some((_, ask)) if side == Side::Buy => price >= ask,
+            Some((bid, _)) if side == Side::Sell => price <= bid,
+            _ => false,
+        }
+    }
+
+    /// Apply the external BBO policy to an incoming order, repricing it in
+    /// place when the policy is [`ExternalBboPolicy::Reprice`].
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::LocksExternalBbo`] when the policy is
+    /// `Reject`, or when it is `Reprice` and no valid price exists on the
+    /// passive side of the external quote.
+    pub(super) fn apply_external_bbo_policy(
+        &self,
+        order: &mut OrderType<T>,
+    ) -> Result<(), OrderBookError> {
+        if self.external_bbo_policy == ExternalBboPolicy::Off {
+            return Ok(());
+        }
+        let Some((external_bid, external_ask)) = self.external_bbo() else {
+            return Ok(());
+        };
+        let price = order.price().as_u128();
+        let side = order.side();
+        if !self.would_lock_external_bbo(price, side) {
+            return Ok(());
+        }
+
+        let error = OrderBookError::LocksExternalBbo {
+            price,
+            side,
+            external_bid,
+            external_ask,
+        };
+        let new_price = match self.external_bbo_policy {
+            ExternalBboPolicy::Reprice => match side {
+                Side::Buy => external_ask
+                    .checked_sub(1)
+                    .map(|p| self.round_to_tick(p, RoundingMode::Down))
+                    .filter(|&p| p > 0),
+                Side::Sell => external_bid
+                    .checked_add(1)
+                    .map(|p| self.round_to_tick(p, RoundingMode::Up))
+                    .filter(|&p| p > external_bid),
+            },
+            _ => None,
+        };
+
+        let Some(new_price) = new_price else {
+            return self.reject(order.id(), error);
+        };
+
+        trace!(
+            "Order book {}: Repricing order {} from {} to {} to avoid locking external BBO",
+            self.symbol,
+            order.id(),
+            price,
+            new_price
+        );
+        Self::set_order_price(order, new_price);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    #[test]
+    fn test_posting_at_external_ask_rejected_under_policy() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_external_bbo(Some((100, 105)));
+        book.set_external_bbo_policy(ExternalBboPolicy::Reject);
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 105, 1, Side::Buy, TimeInForce::Gtc, None)
+            .expect_err("locks external ask");
+        assert!(matches!(
+            err,
+            OrderBookError::LocksExternalBbo {
+                price: 105,
+                side: Side::Buy,
+                external_bid: 100,
+                external_ask: 105
+            }
+        ));
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 99, 1, Side::Sell, TimeInForce::Gtc, None)
+            .expect_err("crosses external bid");
+        assert!(matches!(err, OrderBookError::LocksExternalBbo { .. }));
+
+        // Inside the external spread is fine
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 104, 1, Side::Buy, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 101, 1, Side::Sell, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_posting_at_external_ask_allowed_when_policy_off() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_external_bbo(Some((100, 105)));
+        assert_eq!(book.external_bbo_policy(), ExternalBboPolicy::Off);
+
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 105, 1, Side::Buy, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+        assert_eq!(book.best_bid(), Some(105));
+    }
+
+    #[test]
+    fn test_reprice_moves_order_one_tick_away() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_tick_size(5);
+        book.set_external_bbo(Some((100, 112)));
+        book.set_external_bbo_policy(ExternalBboPolicy::Reprice);
+
+        let buy = book
+            .add_limit_order(Id::new_uuid(), 120, 1, Side::Buy, TimeInForce::Gtc, None)
+            .expect("repriced buy");
+        assert_eq!(buy.price().as_u128(), 110);
+
+        let sell = book
+            .add_limit_order(Id::new_uuid(), 95, 1, Side::Sell, TimeInForce::Gtc, None)
+            .expect("repriced sell");
+        assert_eq!(sell.price().as_u128(), 105);
+    }
+
+    #[test]
+    fn test_clearing_external_bbo_disables_check() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_external_bbo_policy(ExternalBboPolicy::Reject);
+        book.set_external_bbo(Some((100, 105)));
+        assert!(book.would_lock_external_bbo(105, Side::Buy));
+
+        book.set_external_bbo(None);
+        assert!(!book.would_lock_external_bbo(105, Side::Buy));
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 200, 1, Side::Buy, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+    }
+}
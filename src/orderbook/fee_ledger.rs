@@ -0,0 +1,399 @@
+//! Per-user accrual of fees for settlement.
+//!
+//! With a [`FeeSchedule`](crate::orderbook::FeeSchedule) configured, every
+//! continuous fill charges its taker and maker and adds the signed amounts
+//! to the book's fee ledger: positive amounts are fees the user owes,
+//! negative amounts are rebates owed to the user. Auction uncrosses do not
+//! accrue fees.
+//!
+//! A fee in basis points usually leaves a fraction of a price unit.
+//! [`FeeRounding::PerFill`] truncates each fill's fee toward zero, matching
+//! the totals reported in [`TradeResult`](crate::orderbook::trade::TradeResult);
+//! [`FeeRounding::Accumulated`] keeps the exact fractions in the ledger and
+//! only truncates the running total when it is read, so many small fills are
+//! charged what one large fill would be.
+//!
+//! [`OrderBook::set_fee_tiers`] adds volume tiers: each fill is charged at
+//! the rates of the highest tier its user's session volume has reached
+//! before the fill, falling back to the fee schedule below the first tier.
+//! The session volume of a user is the quantity they traded as taker or
+//! maker while fees accrue, until [`OrderBook::reset_session`]. Tiers only
+//! affect the ledger; the fee totals in a `TradeResult` use the schedule.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::fees::{FeeSchedule, FeeTier};
+use pricelevel::{Hash32, Id, MatchResult};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// Ledger amounts are kept in units of 1/10,000 of a price unit, the
+/// resolution of a basis point fee.
+const BPS_SCALE: i128 = 10_000;
+
+/// How fractional fees are rounded when they accrue to the ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum FeeRounding {
+    /// Truncate each fill's fee toward zero (default).
+    #[default]
+    PerFill,
+    /// Keep exact fractions and truncate only the running total.
+    Accumulated,
+}
+
+impl std::fmt::Display for FeeRounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeeRounding::PerFill => write!(f, "PerFill"),
+            FeeRounding::Accumulated => write!(f, "Accumulated"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set how fractional fees are rounded as they accrue.
+    pub fn set_fee_rounding(&mut self, rounding: FeeRounding) {
+        trace!(
+            "Order book {}: Setting fee rounding to {}",
+            self.symbol, rounding
+        );
+        self.fee_rounding = rounding;
+    }
+
+    /// Returns how fractional fees are rounded as they accrue.
+    #[must_use]
+    pub fn fee_rounding(&self) -> FeeRounding {
+        self.fee_rounding
+    }
+
+    /// Set the volume tiers of the fee ledger, in any order. An empty list
+    /// charges every fill at the fee schedule.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`], leaving the tiers
+    /// unchanged, if two tiers share a `min_volume`.
+    pub fn set_fee_tiers(&mut self, mut tiers: Vec<FeeTier>) -> Result<(), OrderBookError> {
+        tiers.sort_unstable_by_key(|tier| tier.min_volume);
+        if let Some(pair) = tiers
+            .windows(2)
+            .find(|pair| pair[0].min_volume == pair[1].min_volume)
+        {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!("two fee tiers start at volume {}", pair[0].min_volume),
+            });
+        }
+        trace!(
+            "Order book {}: Setting fee tiers to {:?}",
+            self.symbol, tiers
+        );
+        self.fee_tiers = tiers;
+        Ok(())
+    }
+
+    /// Returns the volume tiers of the fee ledger in ascending
+    /// `min_volume` order.
+    #[must_use]
+    pub fn fee_tiers(&self) -> &[FeeTier] {
+        &self.fee_tiers
+    }
+
+    /// Returns the quantity `user_id` has traded this session while fees
+    /// accrue.
+    #[must_use]
+    pub fn user_session_volume(&self, user_id: Hash32) -> u64 {
+        self.user_session_volume
+            .get(&user_id)
+            .map_or(0, |volume| *volume)
+    }
+
+    /// Returns the rates the next fill of `user_id` is charged at: those of
+    /// the highest tier the user's session volume has reached, or the fee
+    /// schedule. `None` if no fee schedule is set.
+    #[must_use]
+    pub fn fee_schedule_for(&self, user_id: Hash32) -> Option<FeeSchedule> {
+        let schedule = self.fee_schedule?;
+        let volume = self.user_session_volume(user_id);
+        let reached = self
+            .fee_tiers
+            .partition_point(|tier| tier.min_volume <= volume);
+        Some(
+            reached
+                .checked_sub(1)
+                .map_or(schedule, |tier| self.fee_tiers[tier].schedule()),
+        )
+    }
+
+    /// Returns the fees `user_id` owes, net of rebates, since the last
+    /// [`reset_fees`](Self::reset_fees). Negative values are rebates owed
+    /// to the user.
+    #[must_use]
+    pub fn fees_owed(&self, user_id: Hash32) -> i128 {
+        self.fee_ledger
+            .get(&user_id)
+            .map_or(0, |amount| *amount / BPS_SCALE)
+    }
+
+    /// Returns every user with a ledger entry and the fees they owe.
+    #[must_use]
+    pub fn fee_ledger(&self) -> Vec<(Hash32, i128)> {
+        self.fee_ledger
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value() / BPS_SCALE))
+            .collect()
+    }
+
+    /// Clear the fee ledger, e.g. after settlement.
+    pub fn reset_fees(&self) {
+        trace!("Order book {}: Resetting fee ledger", self.symbol);
+        self.fee_ledger.clear();
+    }
+
+    /// Returns the raw ledger in 1/10,000 price units, for snapshots.
+    pub(super) fn raw_fee_ledger(&self) -> Vec<(Hash32, i128)> {
+        self.fee_ledger
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+
+    /// Replace the ledger with raw amounts taken by
+    /// [`raw_fee_ledger`](Self::raw_fee_ledger).
+    pub(super) fn restore_fee_ledger(&self, ledger: Vec<(Hash32, i128)>) {
+        self.fee_ledger.clear();
+        for (user_id, amount) in ledger {
+            self.fee_ledger.insert(user_id, amount);
+        }
+    }
+
+    /// Returns `true` if fills currently accrue fees.
+    pub(super) fn accrues_fees(&self) -> bool {
+        self.fee_schedule
+            .is_some_and(|schedule| !schedule.is_zero_fee())
+    }
+
+    /// Accrue the fees of every fill in `match_result` to its taker and
+    /// maker. `filled_makers` holds the owners of makers that left the
+    /// book; the owners of partially filled makers are looked up.
+    pub(super) fn accrue_fees(
+        &self,
+        match_result: &MatchResult,
+        taker_user_id: Hash32,
+        filled_makers: &[(Id, Hash32)],
+    ) {
+        if self.fee_schedule.is_none() {
+            return;
+        }
+        for trade in match_result.trades().as_vec() {
+            let notional = trade
+                .price()
+                .as_u128()
+                .saturating_mul(u128::from(trade.quantity().as_u64()));
+            let maker_id = trade.maker_order_id();
+            let maker_user_id = filled_makers
+                .iter()
+                .find(|(id, _)| *id == maker_id)
+                .map(|(_, user_id)| *user_id)
+                .or_else(|| self.get_order(maker_id).map(|order| order.user_id()))
+                .unwrap_or_else(Hash32::zero);
+
+            let parties = [(taker_user_id, false), (maker_user_id, true)];
+            for (user_id, is_maker) in parties {
+                let Some(schedule) = self.fee_schedule_for(user_id) else {
+                    continue;
+                };
+                let amount = match self.fee_rounding {
+                    FeeRounding::PerFill => schedule
+                        .calculate_fee(notional, is_maker)
+                        .saturating_mul(BPS_SCALE),
+                    FeeRounding::Accumulated => {
+                        let bps = if is_maker {
+                            schedule.maker_fee_bps
+                        } else {
+                            schedule.taker_fee_bps
+                        };
+                        i128::try_from(notional)
+                            .unwrap_or(i128::MAX)
+                            .saturating_mul(i128::from(bps))
+                    }
+                };
+                let mut entry = self.fee_ledger.entry(user_id).or_insert(0);
+                *entry = entry.saturating_add(amount);
+            }
+            // Both sides are charged before the fill counts toward a tier
+            for (user_id, _) in parties {
+                let mut volume = self.user_session_volume.entry(user_id).or_insert(0);
+                *volume = volume.saturating_add(trade.quantity().as_u64());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Side, TimeInForce};
+
+    const MAKER: Hash32 = Hash32([1; 32]);
+    const TAKER: Hash32 = Hash32([2; 32]);
+
+    fn book(rounding: FeeRounding) -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        // 2 bps maker rebate, 5 bps taker fee
+        book.set_fee_schedule(Some(FeeSchedule::new(-2, 5)));
+        book.set_fee_rounding(rounding);
+        book
+    }
+
+    fn rest(book: &OrderBook<()>, price: u128, quantity: u64, side: Side) {
+        book.add_limit_order_with_user(
+            Id::new_uuid(),
+            price,
+            quantity,
+            side,
+            TimeInForce::Gtc,
+            MAKER,
+            None,
+        )
+        .unwrap();
+    }
+
+    fn take(book: &OrderBook<()>, price: u128, quantity: u64, side: Side) {
+        book.add_limit_order_with_user(
+            Id::new_uuid(),
+            price,
+            quantity,
+            side,
+            TimeInForce::Ioc,
+            TAKER,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_taker_and_maker_accrue_signed_fees() {
+        let book = book(FeeRounding::default());
+        rest(&book, 10_000, 30, Side::Sell);
+
+        // Notional 10,000 * 10 = 100,000: taker pays 50, maker earns 20
+        take(&book, 10_000, 10, Side::Buy);
+        assert_eq!(book.fees_owed(TAKER), 50);
+        assert_eq!(book.fees_owed(MAKER), -20);
+
+        // The maker fully fills and leaves the book on the second trade
+        take(&book, 10_000, 20, Side::Buy);
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(book.fees_owed(TAKER), 150);
+        assert_eq!(book.fees_owed(MAKER), -60);
+        assert_eq!(book.fee_ledger().len(), 2);
+
+        book.reset_fees();
+        assert_eq!(book.fees_owed(TAKER), 0);
+        assert!(book.fee_ledger().is_empty());
+    }
+
+    #[test]
+    fn test_accumulated_rounding_keeps_fractions() {
+        // Notional 1,000 per fill: the taker fee is 0.5 and the rebate 0.2
+        let per_fill = book(FeeRounding::PerFill);
+        let accumulated = book(FeeRounding::Accumulated);
+        for book in [&per_fill, &accumulated] {
+            rest(book, 1_000, 4, Side::Buy);
+            for _ in 0..4 {
+                take(book, 1_000, 1, Side::Sell);
+            }
+        }
+        assert_eq!(per_fill.fees_owed(TAKER), 0);
+        assert_eq!(per_fill.fees_owed(MAKER), 0);
+        assert_eq!(accumulated.fees_owed(TAKER), 2);
+        assert_eq!(accumulated.fees_owed(MAKER), 0);
+    }
+
+    #[test]
+    fn test_fee_tier_applies_once_volume_threshold_is_reached() {
+        let mut book = book(FeeRounding::default());
+        // From 100 units traded: no maker rebate, 1 bps taker fee
+        book.set_fee_tiers(vec![FeeTier::new(100, 0, 1)]).unwrap();
+        rest(&book, 10_000, 200, Side::Sell);
+
+        // 90 units: notional 900,000 at 5 bps is 450, the rebate 180
+        take(&book, 10_000, 90, Side::Buy);
+        assert_eq!(book.fees_owed(TAKER), 450);
+        assert_eq!(book.fees_owed(MAKER), -180);
+        assert_eq!(book.user_session_volume(TAKER), 90);
+
+        // Still below the tier at 90: 10 units are charged 50 and earn 20
+        take(&book, 10_000, 10, Side::Buy);
+        assert_eq!(book.fees_owed(TAKER), 500);
+        assert_eq!(book.fees_owed(MAKER), -200);
+        assert_eq!(book.fee_schedule_for(TAKER), Some(FeeSchedule::new(0, 1)));
+
+        // At 100 both sides are in the tier: 10 more units cost 10, earn 0
+        take(&book, 10_000, 10, Side::Buy);
+        assert_eq!(book.fees_owed(TAKER), 510);
+        assert_eq!(book.fees_owed(MAKER), -200);
+
+        // A new session starts back at the schedule
+        book.reset_session();
+        assert_eq!(book.user_session_volume(TAKER), 0);
+        assert_eq!(book.fee_schedule_for(TAKER), Some(FeeSchedule::new(-2, 5)));
+    }
+
+    #[test]
+    fn test_fee_tiers_are_sorted_and_unique() {
+        let mut book = book(FeeRounding::default());
+        book.set_fee_tiers(vec![FeeTier::new(1_000, -3, 2), FeeTier::new(100, -2, 4)])
+            .unwrap();
+        let thresholds: Vec<u64> = book.fee_tiers().iter().map(|t| t.min_volume).collect();
+        assert_eq!(thresholds, vec![100, 1_000]);
+
+        assert!(matches!(
+            book.set_fee_tiers(vec![FeeTier::new(100, 0, 1), FeeTier::new(100, 0, 2)]),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+        assert_eq!(book.fee_tiers().len(), 2);
+
+        book.set_fee_schedule(None);
+        assert_eq!(book.fee_schedule_for(TAKER), None);
+    }
+
+    #[test]
+    fn test_fee_ledger_survives_snapshot_restore() {
+        let book = book(FeeRounding::Accumulated);
+        rest(&book, 10_000, 30, Side::Sell);
+        take(&book, 10_000, 10, Side::Buy);
+
+        let package = book.create_snapshot_package(usize::MAX).unwrap();
+        let json = package.to_json().unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_json(&json).unwrap();
+        assert_eq!(restored.fee_rounding(), FeeRounding::Accumulated);
+        assert_eq!(restored.fees_owed(TAKER), 50);
+        assert_eq!(restored.fees_owed(MAKER), -20);
+
+        // Restoring onto the original book keeps its ledger as well
+        let mut book = book;
+        book.restore_from_snapshot_json(&json).unwrap();
+        assert_eq!(book.fees_owed(TAKER), 50);
+    }
+
+    #[test]
+    fn test_fee_ledger_survives_delta_restore() {
+        let book = book(FeeRounding::Accumulated);
+        let base = book.create_snapshot_package(usize::MAX).unwrap();
+        rest(&book, 10_000, 30, Side::Sell);
+        take(&book, 10_000, 10, Side::Buy);
+        let target = book.create_snapshot_package(usize::MAX).unwrap();
+
+        let delta = target.delta_since(&base).unwrap();
+        let package = base.apply_delta(&delta).unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(restored.fee_rounding(), FeeRounding::Accumulated);
+        assert_eq!(restored.fees_owed(TAKER), 50);
+        assert_eq!(restored.fees_owed(MAKER), -20);
+    }
+}
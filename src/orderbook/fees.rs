@@ -175,6 +175,81 @@ impl Default for FeeSchedule {
     }
 }
 
+/// A volume tier of the fee ledger
+///
+/// Once a user's session volume reaches `min_volume`, fills charged to the
+/// fee ledger use the tier's rates instead of the book's [`FeeSchedule`].
+/// See [`OrderBook::set_fee_tiers`](crate::OrderBook::set_fee_tiers).
+///
+/// # Examples
+///
+/// ```
+/// use orderbook_rs::FeeTier;
+///
+/// // From 1,000 units traded: 1 bps maker rebate, 3 bps taker fee
+/// let tier = FeeTier::new(1_000, -1, 3);
+/// assert_eq!(tier.schedule().taker_fee_bps, 3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeTier {
+    /// Session volume, in units, from which the tier applies (inclusive)
+    pub min_volume: u64,
+
+    /// Maker fee in basis points (negative = rebate)
+    pub maker_fee_bps: i32,
+
+    /// Taker fee in basis points
+    pub taker_fee_bps: i32,
+}
+
+impl FeeTier {
+    /// Create a new fee tier
+    #[must_use = "FeeTier does nothing unless used"]
+    pub fn new(min_volume: u64, maker_fee_bps: i32, taker_fee_bps: i32) -> Self {
+        Self {
+            min_volume,
+            maker_fee_bps,
+            taker_fee_bps,
+        }
+    }
+
+    /// Returns the tier's rates as a fee schedule
+    #[must_use]
+    pub fn schedule(&self) -> FeeSchedule {
+        FeeSchedule::new(self.maker_fee_bps, self.taker_fee_bps)
+    }
+}
+
+/// Liquidity role of an order relative to the current book
+///
+/// Makers add resting liquidity and pay the maker fee (or earn a rebate);
+/// takers remove liquidity and pay the taker fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderRole {
+    /// The order would rest in the book
+    Maker,
+    /// The order would cross the opposite best price and take liquidity
+    Taker,
+}
+
+impl OrderRole {
+    /// Returns `true` if this is the maker role
+    #[must_use]
+    #[inline]
+    pub fn is_maker(&self) -> bool {
+        matches!(self, OrderRole::Maker)
+    }
+}
+
+impl std::fmt::Display for OrderRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderRole::Maker => write!(f, "Maker"),
+            OrderRole::Taker => write!(f, "Taker"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
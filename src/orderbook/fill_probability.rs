@@ -0,0 +1,198 @@
+//! Heuristic fill probability of resting orders for smart order routing.
+//!
+//! The book keeps a tape of its most recent fills (up to
+//! [`TRADE_TAPE_CAPACITY`](crate::orderbook::fill_probability::TRADE_TAPE_CAPACITY)).
+//! [`OrderBook::fill_probability`] combines the quantity queued ahead of an
+//! order with the average quantity the tape has traded against makers at the
+//! order's price level per trade, and estimates how likely the level's flow
+//! over the next trades is to reach and fill the order. The estimate only
+//! depends on the book and the tape, so the same inputs always give the
+//! same result.
+//!
+//! Snapshots do not carry the tape. Restoring a snapshot clears it, as its
+//! fills traded against orders the snapshot replaced, so estimates start
+//! over from the restored book's own fills.
+
+use super::book::OrderBook;
+use pricelevel::{Id, MatchResult, Side};
+use std::sync::PoisonError;
+
+/// Maximum number of fills kept on the trade tape. Older fills are dropped
+/// first.
+pub const TRADE_TAPE_CAPACITY: usize = 1_024;
+
+/// A fill recorded on the trade tape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct TapeFill {
+    /// Execution price.
+    pub(super) price: u128,
+    /// Side of the resting maker order.
+    pub(super) maker_side: Side,
+    /// Executed quantity.
+    pub(super) quantity: u64,
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Estimate the probability that a resting order fills completely
+    /// within the next `horizon_trades` fills.
+    ///
+    /// The quantity that must trade at the order's level before it is
+    /// filled is the visible quantity queued ahead of it in its level's
+    /// queue plus its own remaining quantity. The expected flow is the
+    /// average quantity traded per fill against makers at the order's price
+    /// and side on the tape, times `horizon_trades`. The estimate is the
+    /// ratio of expected flow to required quantity, capped at 1.0, and 0.0
+    /// when the tape has no fills at the level.
+    ///
+    /// Returns `None` if the order is not resting in the book. Only fills of
+    /// the continuous matching engine are taped; auction uncrosses are not.
+    ///
+    /// # Performance
+    /// O(N + K) where N is the number of orders at the level and K the
+    /// number of taped fills.
+    #[must_use]
+    pub fn fill_probability(&self, order_id: Id, horizon_trades: u64) -> Option<f64> {
+        let (price, side) = *self.order_locations.get(&order_id)?;
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let orders = self.queue_orders(price_levels.get(&price)?.value());
+        let index = orders.iter().position(|order| order.id() == order_id)?;
+        let order = &orders[index];
+
+        let ahead = orders[..index].iter().fold(0u64, |acc, other| {
+            acc.saturating_add(other.visible_quantity())
+        });
+        let required = ahead
+            .saturating_add(order.visible_quantity())
+            .saturating_add(order.hidden_quantity());
+        if required == 0 {
+            return Some(1.0);
+        }
+
+        let tape = self
+            .trade_tape
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if tape.is_empty() {
+            return Some(0.0);
+        }
+        let level_flow = tape
+            .iter()
+            .filter(|fill| fill.maker_side == side && fill.price == price)
+            .fold(0u64, |acc, fill| acc.saturating_add(fill.quantity));
+        let flow_per_trade = level_flow as f64 / tape.len() as f64;
+
+        Some((flow_per_trade * horizon_trades as f64 / required as f64).min(1.0))
+    }
+
+    /// Append every fill of `match_result` to the trade tape.
+    pub(super) fn record_tape(&self, match_result: &MatchResult) {
+        let trades = match_result.trades().as_vec();
+        if trades.is_empty() {
+            return;
+        }
+        let mut tape = self
+            .trade_tape
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for trade in trades {
+            if tape.len() == TRADE_TAPE_CAPACITY {
+                tape.pop_front();
+            }
+            tape.push_back(TapeFill {
+                price: trade.price().as_u128(),
+                maker_side: trade.maker_side(),
+                quantity: trade.quantity().as_u64(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, OrderType, Price, Quantity, TimeInForce, TimestampMs};
+
+    fn ask(id: Id, quantity: u64, timestamp: u64) -> OrderType<()> {
+        OrderType::Standard {
+            id,
+            price: Price::new(100),
+            quantity: Quantity::new(quantity),
+            side: Side::Sell,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    #[test]
+    fn test_front_of_queue_is_more_likely_to_fill() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        // Tape: two fills of 10 at 100 against asks
+        book.add_order(ask(Id::new_uuid(), 20, 1)).unwrap();
+        for _ in 0..2 {
+            book.submit_market_order(Id::new_uuid(), 10, Side::Buy)
+                .unwrap();
+        }
+
+        let front = Id::new_uuid();
+        let back = Id::new_uuid();
+        book.add_order(ask(front, 10, 2)).unwrap();
+        book.add_order(ask(Id::new_uuid(), 20, 3)).unwrap();
+        book.add_order(ask(back, 10, 4)).unwrap();
+
+        // 10 per trade over 2 trades against 10 and 40 required
+        assert_eq!(book.fill_probability(front, 2), Some(1.0));
+        assert_eq!(book.fill_probability(back, 2), Some(0.5));
+        assert_eq!(book.fill_probability(front, 0), Some(0.0));
+        assert_eq!(
+            book.fill_probability(back, 2),
+            book.fill_probability(back, 2)
+        );
+    }
+
+    #[test]
+    fn test_flow_at_other_levels_does_not_count() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(Id::new_uuid(), 101, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.submit_market_order(Id::new_uuid(), 10, Side::Buy)
+            .unwrap();
+
+        let id = Id::new_uuid();
+        book.add_order(ask(id, 10, 1)).unwrap();
+        assert_eq!(book.fill_probability(id, 100), Some(0.0));
+    }
+
+    #[test]
+    fn test_unknown_order_has_no_estimate() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(book.fill_probability(Id::new_uuid(), 10), None);
+    }
+
+    #[test]
+    fn test_tape_is_bounded() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let count = TRADE_TAPE_CAPACITY + 10;
+        book.add_limit_order(
+            Id::new_uuid(),
+            100,
+            count as u64,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        for _ in 0..count {
+            book.submit_market_order(Id::new_uuid(), 1, Side::Buy)
+                .unwrap();
+        }
+        assert_eq!(book.trade_tape.lock().unwrap().len(), TRADE_TAPE_CAPACITY);
+    }
+}
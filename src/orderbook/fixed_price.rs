@@ -0,0 +1,211 @@
+//! Fixed-point prices for deterministic analytics.
+//!
+//! [`OrderBook::mid_price`] and [`OrderBook::vwap`] return `f64`, which
+//! rounds large prices and is awkward to compare across runs. The `_fixed`
+//! variants compute the same values with integer arithmetic only and
+//! return a [`FixedPrice`]: a price in the book's integer price units with
+//! [`FixedPrice::FRACTION_DIGITS`] extra decimal digits. A mid price is
+//! always exact; a VWAP is rounded half up to the last fractional digit.
+
+use super::book::OrderBook;
+use pricelevel::Side;
+use serde::{Deserialize, Serialize};
+
+/// A price in integer book price units with nine fractional digits.
+///
+/// The raw value is the price multiplied by [`FixedPrice::SCALE`], so
+/// `FixedPrice::from_raw(100_500_000_000)` is the price `100.5` in book
+/// units. Convert to a client decimal price by also applying the book's
+/// [`price_decimals`](OrderBook::price_decimals).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct FixedPrice(u128);
+
+impl FixedPrice {
+    /// Number of fractional decimal digits.
+    pub const FRACTION_DIGITS: u32 = 9;
+
+    /// Raw units per integer price unit.
+    pub const SCALE: u128 = 10u128.pow(Self::FRACTION_DIGITS);
+
+    /// Wrap a raw value already scaled by [`Self::SCALE`].
+    #[must_use]
+    pub const fn from_raw(raw: u128) -> Self {
+        Self(raw)
+    }
+
+    /// Convert an integer book price, or `None` if it does not fit.
+    #[must_use]
+    pub fn from_price(price: u128) -> Option<Self> {
+        price.checked_mul(Self::SCALE).map(Self)
+    }
+
+    /// Returns the raw value scaled by [`Self::SCALE`].
+    #[must_use]
+    pub const fn raw(self) -> u128 {
+        self.0
+    }
+
+    /// Returns the integer part in book price units.
+    #[must_use]
+    pub const fn integer_part(self) -> u128 {
+        self.0 / Self::SCALE
+    }
+
+    /// Returns the fractional part in raw units.
+    #[must_use]
+    pub const fn fractional_part(self) -> u128 {
+        self.0 % Self::SCALE
+    }
+
+    /// Convert to `f64` for display or further floating point analysis.
+    #[must_use]
+    pub fn to_f64(self) -> f64 {
+        self.integer_part() as f64 + self.fractional_part() as f64 / Self::SCALE as f64
+    }
+
+    /// `numerator / denominator` in book price units, rounded half up to
+    /// the last fractional digit, or `None` on overflow or a zero
+    /// denominator.
+    fn ratio(numerator: u128, denominator: u128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let integer = numerator / denominator;
+        let remainder = numerator % denominator;
+        // remainder < denominator, so remainder * SCALE only overflows for
+        // denominators above u128::MAX / SCALE
+        let scaled = remainder.checked_mul(Self::SCALE)?;
+        let fraction =
+            (scaled / denominator) + u128::from(scaled % denominator >= denominator.div_ceil(2));
+        integer
+            .checked_mul(Self::SCALE)?
+            .checked_add(fraction)
+            .map(Self)
+    }
+}
+
+impl std::fmt::Display for FixedPrice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{:0width$}",
+            self.integer_part(),
+            self.fractional_part(),
+            width = Self::FRACTION_DIGITS as usize
+        )
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// The mid price as an exact [`FixedPrice`], or `None` if either side
+    /// is empty.
+    #[must_use]
+    pub fn mid_price_fixed(&self) -> Option<FixedPrice> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        // bid / 2 + ask / 2 plus the halves of their odd units, without
+        // overflowing on bid + ask
+        let halves = (bid % 2) + (ask % 2);
+        let whole = (bid / 2) + (ask / 2) + halves / 2;
+        let fraction = if halves % 2 == 1 {
+            FixedPrice::SCALE / 2
+        } else {
+            0
+        };
+        FixedPrice::from_price(whole)?
+            .raw()
+            .checked_add(fraction)
+            .map(FixedPrice::from_raw)
+    }
+
+    /// The VWAP of filling `quantity` on `side` as a [`FixedPrice`],
+    /// computed like [`vwap`](Self::vwap) but with integer arithmetic and
+    /// rounded half up to the last fractional digit.
+    ///
+    /// Returns `None` if the quantity is zero, liquidity is insufficient or
+    /// the value does not fit.
+    #[must_use]
+    pub fn vwap_fixed(&self, quantity: u64, side: Side) -> Option<FixedPrice> {
+        let (total_cost, total_filled) = self.vwap_totals(quantity, side)?;
+        FixedPrice::ratio(total_cost, u128::from(total_filled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn add(book: &OrderBook<()>, price: u128, quantity: u64, side: Side) {
+        book.add_limit_order(
+            Id::new_uuid(),
+            price,
+            quantity,
+            side,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_mid_price_fixed_is_exact() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(book.mid_price_fixed(), None);
+
+        add(&book, 100, 10, Side::Buy);
+        add(&book, 101, 10, Side::Sell);
+        let mid = book.mid_price_fixed().unwrap();
+        assert_eq!(mid, FixedPrice::from_raw(100_500_000_000));
+        assert_eq!(mid.to_string(), "100.500000000");
+
+        // Far beyond f64's 53-bit mantissa the fixed mid stays exact
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let bid = (1u128 << 80) + 1;
+        add(&book, bid, 1, Side::Buy);
+        add(&book, bid + 2, 1, Side::Sell);
+        let mid = book.mid_price_fixed().unwrap();
+        assert_eq!((mid.integer_part(), mid.fractional_part()), (bid + 1, 0));
+        assert_ne!(book.mid_price().map(|m| m as u128), Some(bid + 1));
+    }
+
+    #[test]
+    fn test_vwap_fixed_matches_hand_computed_values() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        add(&book, 100, 10, Side::Sell);
+        add(&book, 105, 15, Side::Sell);
+
+        // (100 * 10 + 105 * 10) / 20 = 102.5
+        assert_eq!(
+            book.vwap_fixed(20, Side::Buy),
+            Some(FixedPrice::from_raw(102_500_000_000))
+        );
+        // (100 * 10 + 105 * 5) / 15 = 101.666666666... rounds up
+        assert_eq!(
+            book.vwap_fixed(15, Side::Buy),
+            Some(FixedPrice::from_raw(101_666_666_667))
+        );
+        // (100 * 10 + 105 * 2) / 12 = 100.833333333... rounds down
+        assert_eq!(
+            book.vwap_fixed(12, Side::Buy),
+            Some(FixedPrice::from_raw(100_833_333_333))
+        );
+        assert_eq!(book.vwap_fixed(26, Side::Buy), None);
+        assert_eq!(book.vwap_fixed(0, Side::Buy), None);
+        assert_eq!(book.vwap_fixed(1, Side::Sell), None);
+    }
+
+    #[test]
+    fn test_fixed_price_conversions() {
+        let price = FixedPrice::from_price(42).unwrap();
+        assert_eq!(price.raw(), 42 * FixedPrice::SCALE);
+        assert_eq!(price.to_f64(), 42.0);
+        assert_eq!(FixedPrice::from_price(u128::MAX), None);
+        assert!(FixedPrice::from_raw(1) < FixedPrice::from_raw(2));
+    }
+}
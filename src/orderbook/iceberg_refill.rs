@@ -0,0 +1,300 @@
+//! Queue position of iceberg and reserve orders after a refill.
+//!
+//! When the visible slice of an iceberg or auto-replenishing reserve order
+//! is consumed, pricelevel refills it from the hidden quantity and sends the
+//! order to the back of its level's queue. Venues differ on this: some let
+//! the refilled slice keep the order's place in time priority.
+//! [`IcebergRefillPolicy`] selects the behaviour for the whole book.
+//!
+//! Under [`IcebergRefillPolicy::KeepPriority`], a level holding hidden
+//! quantity is matched in place rather than by pricelevel. Its orders are
+//! walked once in queue order (see [`crate::orderbook::arrival`]); each one
+//! traded with is taken off the level and, if it still rests, put back
+//! under its own id, which keeps its entry in the queue. A refilled order
+//! is traded with again while the incoming order lasts, before the orders
+//! behind it, and a partially filled one stays at the front. Walking the
+//! level sorts its orders on every visit, so the policy is opt-in; levels
+//! without hidden quantity are matched as under
+//! [`IcebergRefillPolicy::LosePriority`]. Each order taken off and put back
+//! counts as one removal and one addition in the level's statistics.
+
+use super::arrival::ArrivalOrder;
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{
+    Id, MatchResult, OrderType, OrderUpdate, Price, PriceLevel, Quantity, Trade, UuidGenerator,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::trace;
+
+/// Where a refilled iceberg or reserve order rests in its level's queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum IcebergRefillPolicy {
+    /// The refilled order goes to the back of the queue, behind orders that
+    /// arrived after it (default).
+    #[default]
+    LosePriority,
+    /// The refilled order keeps its place in time priority.
+    KeepPriority,
+}
+
+impl std::fmt::Display for IcebergRefillPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IcebergRefillPolicy::LosePriority => write!(f, "LosePriority"),
+            IcebergRefillPolicy::KeepPriority => write!(f, "KeepPriority"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set where refilled iceberg and reserve orders rest in their level's
+    /// queue.
+    pub fn set_iceberg_refill_policy(&mut self, policy: IcebergRefillPolicy) {
+        trace!(
+            "Order book {}: Setting iceberg refill policy to {}",
+            self.symbol, policy
+        );
+        self.iceberg_refill_policy = policy;
+    }
+
+    /// Returns where refilled iceberg and reserve orders rest in their
+    /// level's queue.
+    #[must_use]
+    pub fn iceberg_refill_policy(&self) -> IcebergRefillPolicy {
+        self.iceberg_refill_policy
+    }
+}
+
+/// Match up to `quantity` for `taker_order_id` against `orders`, resting
+/// at `price_level` and given in queue order, leaving every order traded
+/// with in its place.
+///
+/// A refilled order is traded with again only if `take_refills` is set;
+/// otherwise the walk moves on to the next order. An order cancelled since
+/// `orders` was read is skipped. `arrivals`, if given, records the queue
+/// entry each order put back gains.
+///
+/// # Errors
+/// Returns [`OrderBookError::PriceLevelError`] if the level refuses an
+/// update or a trade exceeds the quantity left to match.
+pub(super) fn match_in_place(
+    price_level: &PriceLevel,
+    orders: Vec<Arc<OrderType<()>>>,
+    quantity: u64,
+    taker_order_id: Id,
+    trade_id_generator: &UuidGenerator,
+    arrivals: Option<&ArrivalOrder>,
+    take_refills: bool,
+) -> Result<MatchResult, OrderBookError> {
+    let mut result = MatchResult::new(taker_order_id, quantity);
+    let mut remaining = quantity;
+    for queued in orders {
+        while remaining > 0 {
+            let Some(maker) = price_level.update_order(OrderUpdate::Cancel {
+                order_id: queued.id(),
+            })?
+            else {
+                break;
+            };
+            let (consumed, updated, _, left) = maker.match_against(remaining);
+            let resting = updated.is_some();
+            if let Some(updated) = updated {
+                price_level.add_order(updated);
+                if let Some(arrivals) = arrivals {
+                    arrivals.push_back(maker.id());
+                }
+            }
+            if consumed == 0 {
+                break;
+            }
+
+            let trade = Trade::new(
+                Id::from_uuid(trade_id_generator.next()),
+                taker_order_id,
+                maker.id(),
+                Price::new(price_level.price()),
+                Quantity::new(consumed),
+                maker.side().opposite(),
+            );
+            result.add_trade(trade)?;
+            if !resting {
+                result.add_filled_order_id(maker.id());
+            }
+            let _ = price_level.stats().record_execution(
+                consumed,
+                maker.price().as_u128(),
+                maker.timestamp(),
+            );
+            remaining = left;
+            if !resting || !take_refills {
+                break;
+            }
+        }
+        if remaining == 0 {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    /// An iceberg ask of 5 visible and 10 hidden at 100, then a standard ask
+    /// of 5 behind it at the same price.
+    fn book(policy: IcebergRefillPolicy) -> (OrderBook<()>, Id, Id) {
+        let mut book = OrderBook::new("TEST");
+        book.set_iceberg_refill_policy(policy);
+        let iceberg = Id::new_uuid();
+        book.add_order(OrderType::IcebergOrder {
+            id: iceberg,
+            price: Price::new(100),
+            visible_quantity: Quantity::new(5),
+            hidden_quantity: Quantity::new(10),
+            side: Side::Sell,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(1),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+        let standard = Id::new_uuid();
+        book.add_order(OrderType::Standard {
+            id: standard,
+            price: Price::new(100),
+            quantity: Quantity::new(5),
+            side: Side::Sell,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(2),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+        (book, iceberg, standard)
+    }
+
+    fn makers(book: &OrderBook<()>, quantity: u64) -> Vec<Id> {
+        let result = book
+            .submit_market_order(Id::new_uuid(), quantity, Side::Buy)
+            .unwrap();
+        result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect()
+    }
+
+    #[test]
+    fn test_refilled_iceberg_loses_priority_by_default() {
+        let (book, iceberg, standard) = book(IcebergRefillPolicy::default());
+        assert_eq!(
+            book.iceberg_refill_policy(),
+            IcebergRefillPolicy::LosePriority
+        );
+        assert_eq!(makers(&book, 5), vec![iceberg]);
+        // The refill went behind the standard order
+        assert_eq!(makers(&book, 5), vec![standard]);
+        assert_eq!(book.get_order(iceberg).unwrap().hidden_quantity(), 5);
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_refilled_iceberg_keeps_priority() {
+        let (book, iceberg, standard) = book(IcebergRefillPolicy::KeepPriority);
+        assert_eq!(makers(&book, 5), vec![iceberg]);
+        assert_eq!(makers(&book, 5), vec![iceberg]);
+        assert_eq!(
+            book.level_order_ids(Side::Sell, 100),
+            vec![iceberg, standard]
+        );
+
+        // Within one sweep the iceberg is matched through every refill
+        // before the order behind it
+        assert_eq!(makers(&book, 8), vec![iceberg, standard]);
+        assert!(book.get_order(iceberg).is_none());
+        assert_eq!(book.get_order(standard).unwrap().visible_quantity(), 2);
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_level_is_matched_in_place() {
+        let (book, iceberg, standard) = book(IcebergRefillPolicy::KeepPriority);
+        let level = Arc::clone(book.asks.get(&100).unwrap().value());
+        assert_eq!(makers(&book, 7), vec![iceberg, iceberg]);
+
+        // The same level keeps its statistics
+        assert!(Arc::ptr_eq(&level, book.asks.get(&100).unwrap().value()));
+        assert_eq!(level.stats().orders_executed(), 2);
+        assert_eq!(level.stats().quantity_executed(), 7);
+        assert_eq!(
+            book.level_order_ids(Side::Sell, 100),
+            vec![iceberg, standard]
+        );
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_lit_only_order_skips_refilled_slice() {
+        let (book, iceberg, standard) = book(IcebergRefillPolicy::KeepPriority);
+        let result = book
+            .submit_market_order_with_hidden(Id::new_uuid(), 20, Side::Buy, Hash32::zero(), false)
+            .unwrap();
+        let makers: Vec<(Id, u64)> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| (trade.maker_order_id(), trade.quantity().as_u64()))
+            .collect();
+        assert_eq!(makers, vec![(iceberg, 5), (standard, 5)]);
+
+        // The refilled iceberg is still at the front
+        assert_eq!(book.level_order_ids(Side::Sell, 100), vec![iceberg]);
+        assert_eq!(book.get_order(iceberg).unwrap().visible_quantity(), 5);
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_adds_are_kept() {
+        let (book, _, _) = book(IcebergRefillPolicy::KeepPriority);
+        let book = Arc::new(book);
+        let adder = {
+            let book = Arc::clone(&book);
+            std::thread::spawn(move || {
+                for _ in 0..200 {
+                    book.add_limit_order(
+                        Id::new_uuid(),
+                        100,
+                        1,
+                        Side::Sell,
+                        TimeInForce::Gtc,
+                        None,
+                    )
+                    .unwrap();
+                }
+            })
+        };
+        let mut filled = 0;
+        for _ in 0..100 {
+            if let Ok(result) = book.submit_market_order(Id::new_uuid(), 2, Side::Buy) {
+                filled += result.executed_quantity().unwrap();
+            }
+        }
+        adder.join().unwrap();
+
+        // 20 resting before, 200 added: none was lost to the matching
+        let resting: u64 = book
+            .orders_in_match_order(Side::Sell)
+            .map(|order| order.visible_quantity() + order.hidden_quantity())
+            .sum();
+        assert_eq!(resting + filled, 220);
+        book.validate_invariants().unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! Structural checks of the book for tests and debugging.
+//!
+//! [`OrderBook::level_order_ids`] lists the orders resting at a price in
+//! queue order, so tests can assert FIFO integrity after complex add,
+//! cancel and fill sequences. [`OrderBook::validate_invariants`] checks that
+//! the price levels and the order index agree with each other.
+//!
+//! pricelevel does not expose the internal queue of a level, only its
+//! orders sorted by timestamp. The queue order is the one the book tracks
+//! in [`ArrivalOrder`](crate::orderbook::ArrivalOrder).
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{Id, Side};
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns the ids of the orders resting at `price` on `side` in queue
+    /// order, front of the queue first. Empty if there is no such level.
+    #[must_use]
+    pub fn level_order_ids(&self, side: Side, price: u128) -> Vec<Id> {
+        let levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        levels.get(&price).map_or_else(Vec::new, |entry| {
+            self.queue_orders(entry.value())
+                .iter()
+                .map(|order| order.id())
+                .collect()
+        })
+    }
+
+    /// Check the internal consistency of the book.
+    ///
+    /// Verifies that:
+    /// - no price level is empty, and every order sits on its level's price
+    ///   and side
+    /// - each level's visible and hidden totals equal the sum of its orders
+    /// - the order index locates every resting order, and nothing else
+    /// - outside an auction, the best bid is below the best ask
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`] describing the first
+    /// violation found.
+    pub fn validate_invariants(&self) -> Result<(), OrderBookError> {
+        let violation = |message: String| Err(OrderBookError::InvalidOperation { message });
+        let mut resting = 0usize;
+
+        for (side, levels) in [(Side::Buy, &self.bids), (Side::Sell, &self.asks)] {
+            for entry in levels.iter() {
+                let price = *entry.key();
+                let level = entry.value();
+                let orders = level.snapshot_orders();
+                if orders.is_empty() {
+                    return violation(format!("{side} level at {price} is empty"));
+                }
+                if level.order_count() != orders.len() {
+                    return violation(format!(
+                        "{side} level at {price} counts {} orders but holds {}",
+                        level.order_count(),
+                        orders.len()
+                    ));
+                }
+
+                let mut visible = 0u64;
+                let mut hidden = 0u64;
+                for order in &orders {
+                    let order_id = order.id();
+                    if order.price().as_u128() != price || order.side() != side {
+                        return violation(format!(
+                            "order {order_id} ({} at {}) rests on the {side} level at {price}",
+                            order.side(),
+                            order.price().as_u128()
+                        ));
+                    }
+                    match self.order_locations.get(&order_id).map(|entry| *entry) {
+                        Some(location) if location == (price, side) => {}
+                        location => {
+                            return violation(format!(
+                                "order {order_id} at {side} {price} is indexed at {location:?}"
+                            ));
+                        }
+                    }
+                    visible = visible.saturating_add(order.visible_quantity());
+                    hidden = hidden.saturating_add(order.hidden_quantity());
+                }
+                if level.visible_quantity() != visible || level.hidden_quantity() != hidden {
+                    return violation(format!(
+                        "{side} level at {price} totals {}/{} visible/hidden but its orders sum to {visible}/{hidden}",
+                        level.visible_quantity(),
+                        level.hidden_quantity()
+                    ));
+                }
+                resting += orders.len();
+            }
+        }
+
+        if self.order_locations.len() != resting {
+            return violation(format!(
+                "order index holds {} entries for {resting} resting orders",
+                self.order_locations.len()
+            ));
+        }
+        if !self.is_auction()
+            && let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask())
+            && bid >= ask
+        {
+            return violation(format!("book is crossed: bid {bid} against ask {ask}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::TimestampPolicy;
+    use pricelevel::{Hash32, OrderType, Price, Quantity, TimeInForce, TimestampMs};
+
+    fn bid(price: u128, quantity: u64, timestamp: u64) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    /// Five bids of 10 at 100, arriving in timestamp order.
+    fn level() -> (OrderBook<()>, Vec<Id>) {
+        let mut book = OrderBook::new("TEST");
+        book.set_timestamp_policy(TimestampPolicy::RejectOutOfOrder);
+        let ids = (1..=5)
+            .map(|timestamp| {
+                let order = bid(100, 10, timestamp);
+                book.add_order(order).unwrap();
+                order.id()
+            })
+            .collect();
+        (book, ids)
+    }
+
+    #[test]
+    fn test_cancel_in_middle_keeps_fifo() {
+        let (book, ids) = level();
+        assert_eq!(book.level_order_ids(Side::Buy, 100), ids);
+        assert!(book.level_order_ids(Side::Buy, 99).is_empty());
+
+        book.cancel_order(ids[2]).unwrap();
+        assert_eq!(
+            book.level_order_ids(Side::Buy, 100),
+            vec![ids[0], ids[1], ids[3], ids[4]]
+        );
+        book.validate_invariants().unwrap();
+
+        // A partial fill sends its order to the back
+        book.submit_market_order(Id::new_uuid(), 14, Side::Sell)
+            .unwrap();
+        assert_eq!(
+            book.level_order_ids(Side::Buy, 100),
+            vec![ids[3], ids[4], ids[1]]
+        );
+        assert_eq!(book.get_order(ids[1]).unwrap().visible_quantity(), 6);
+        book.add_order(bid(100, 10, 6)).unwrap();
+        book.add_order(bid(99, 10, 7)).unwrap();
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_validate_invariants_reports_stale_index() {
+        let (book, ids) = level();
+        book.order_locations.insert(ids[0], (101, Side::Buy));
+        assert!(matches!(
+            book.validate_invariants(),
+            Err(OrderBookError::InvalidOperation { message }) if message.contains("indexed at")
+        ));
+
+        book.order_locations.insert(ids[0], (100, Side::Buy));
+        book.order_locations
+            .insert(Id::new_uuid(), (100, Side::Buy));
+        assert!(matches!(
+            book.validate_invariants(),
+            Err(OrderBookError::InvalidOperation { message }) if message.contains("order index holds")
+        ));
+    }
+}
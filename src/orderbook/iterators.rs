@@ -4,6 +4,7 @@
 //! and structure without unnecessary allocations. All iterators support standard
 //! iterator combinators and can short-circuit early.
 
+use super::book::level_quantity;
 use crossbeam_skiplist::SkipMap;
 use pricelevel::{PriceLevel, Side};
 use std::sync::Arc;
@@ -56,7 +57,7 @@ impl<'a> Iterator for LevelsWithCumulativeDepth<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|entry| {
             let price = *entry.key();
-            let quantity = entry.value().total_quantity().unwrap_or(0);
+            let quantity = level_quantity(entry.value());
             self.cumulative_depth = self.cumulative_depth.saturating_add(quantity);
 
             LevelInfo {
@@ -115,7 +116,7 @@ impl<'a> Iterator for LevelsUntilDepth<'a> {
 
         self.iter.next().map(|entry| {
             let price = *entry.key();
-            let quantity = entry.value().total_quantity().unwrap_or(0);
+            let quantity = level_quantity(entry.value());
             self.cumulative_depth = self.cumulative_depth.saturating_add(quantity);
 
             let level_info = LevelInfo {
@@ -186,7 +187,7 @@ impl<'a> Iterator for LevelsInRange<'a> {
 
             // Check if price is within range
             if price >= self.min_price && price <= self.max_price {
-                let quantity = entry.value().total_quantity().unwrap_or(0);
+                let quantity = level_quantity(entry.value());
 
                 return Some(LevelInfo {
                     price,
@@ -0,0 +1,118 @@
+//! Per-price-level order limits.
+//!
+//! A flood of tiny orders at one price makes every match and cancel at that
+//! level slower. [`OrderBook::set_max_orders_per_level`] caps how many
+//! orders a single price level may hold; an order that would rest at a full
+//! level is rejected, while other prices stay open. Cancels and fills free
+//! capacity immediately. The cap is independent of the number of levels and
+//! of per-user limits.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{OrderType, Side};
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the maximum number of orders one price level may hold. `None`
+    /// leaves levels unbounded (default).
+    ///
+    /// Levels already above the new maximum keep their orders but accept
+    /// no new ones until they drop below it.
+    pub fn set_max_orders_per_level(&mut self, max_orders: Option<usize>) {
+        trace!(
+            "Order book {}: Setting maximum orders per level to {:?}",
+            self.symbol, max_orders
+        );
+        self.max_orders_per_level = max_orders;
+    }
+
+    /// Returns the maximum number of orders one price level may hold.
+    #[must_use]
+    pub fn max_orders_per_level(&self) -> Option<usize> {
+        self.max_orders_per_level
+    }
+
+    /// Reject `order` if the level it would rest at is full.
+    ///
+    /// Immediate orders never rest and are not checked. An order crossing
+    /// the opposite side checks its own side's level at its price, which
+    /// outside an auction does not exist.
+    pub(super) fn check_level_capacity(&self, order: &OrderType<T>) -> Result<(), OrderBookError> {
+        let Some(max_orders) = self.max_orders_per_level else {
+            return Ok(());
+        };
+        if order.is_immediate() {
+            return Ok(());
+        }
+
+        let price = order.price().as_u128();
+        let levels = match order.side() {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let resting = levels
+            .get(&price)
+            .map_or(0, |entry| entry.value().order_count());
+        if resting >= max_orders {
+            return Err(OrderBookError::LevelFull { price });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn add(book: &OrderBook<()>, price: u128, side: Side) -> Result<Id, OrderBookError> {
+        let id = Id::new_uuid();
+        book.add_limit_order(id, price, 1, side, TimeInForce::Gtc, None)
+            .map(|_| id)
+    }
+
+    #[test]
+    fn test_full_level_rejects_adds_at_its_price_only() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_max_orders_per_level(Some(3));
+        assert_eq!(book.max_orders_per_level(), Some(3));
+
+        let ids: Vec<Id> = (0..3)
+            .map(|_| add(&book, 100, Side::Buy).unwrap())
+            .collect();
+        assert!(matches!(
+            add(&book, 100, Side::Buy),
+            Err(OrderBookError::LevelFull { price: 100 })
+        ));
+
+        // Other prices and the other side stay open
+        assert!(add(&book, 99, Side::Buy).is_ok());
+        assert!(add(&book, 101, Side::Sell).is_ok());
+        assert_eq!(book.level_order_ids(Side::Buy, 100).len(), 3);
+
+        // A cancel frees capacity
+        book.cancel_order(ids[1]).unwrap();
+        assert!(add(&book, 100, Side::Buy).is_ok());
+        assert!(add(&book, 100, Side::Buy).is_err());
+    }
+
+    #[test]
+    fn test_fills_free_capacity_and_none_disables_the_cap() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_max_orders_per_level(Some(1));
+        add(&book, 100, Side::Sell).unwrap();
+        assert!(add(&book, 100, Side::Sell).is_err());
+
+        // A crossing order takes the full level without resting there
+        book.submit_market_order(Id::new_uuid(), 1, Side::Buy)
+            .unwrap();
+        add(&book, 100, Side::Sell).unwrap();
+
+        book.set_max_orders_per_level(None);
+        assert!(add(&book, 100, Side::Sell).is_ok());
+        assert_eq!(book.level_order_ids(Side::Sell, 100).len(), 2);
+    }
+}
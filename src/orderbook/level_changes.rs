@@ -0,0 +1,121 @@
+//! Price levels changed by a single operation.
+//!
+//! Incremental market-data publishers need, per applied command, the levels
+//! whose visible quantity changed. [`OrderBook::record_level_changes`] runs
+//! an operation while noting every level it touches, then classifies each
+//! one against the book afterwards, which costs time proportional to the
+//! touched levels rather than to the depth of the book. Levels created by
+//! contingent or parked orders submitted during the operation are included.
+//!
+//! Recording assumes a single writer: changes made by other threads while an
+//! operation is recorded are attributed to it.
+
+use super::book::OrderBook;
+use super::book_change_event::PriceLevelChangedEvent;
+use pricelevel::Side;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The price levels one operation added, updated and removed.
+///
+/// Each level appears at most once, in the order it was first touched, with
+/// its visible quantity after the operation; removed levels carry `0`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelChangeSet {
+    /// Levels that did not exist before the operation.
+    pub added: Vec<PriceLevelChangedEvent>,
+    /// Levels that existed before and after the operation.
+    pub updated: Vec<PriceLevelChangedEvent>,
+    /// Levels that existed before the operation and no longer do.
+    pub removed: Vec<PriceLevelChangedEvent>,
+}
+
+impl LevelChangeSet {
+    /// Returns `true` if no level changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns the number of changed levels.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.added.len() + self.updated.len() + self.removed.len()
+    }
+}
+
+/// Levels touched while recording, as `(side, price, existed_before)`.
+#[derive(Debug, Default)]
+pub(super) struct LevelChangeRecorder {
+    recording: AtomicBool,
+    touched: Mutex<Vec<(Side, u128, bool)>>,
+}
+
+impl LevelChangeRecorder {
+    /// Returns `true` while an operation is being recorded.
+    pub(super) fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Acquire)
+    }
+
+    /// Note a change to the level at `price`; `existed_before` is `false`
+    /// when the change created the level.
+    pub(super) fn touch(&self, side: Side, price: u128, existed_before: bool) {
+        if !self.is_recording() {
+            return;
+        }
+        if let Ok(mut touched) = self.touched.lock() {
+            touched.push((side, price, existed_before));
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Run `operation` and return its output with the levels it changed.
+    ///
+    /// A nested call returns an empty set; its changes are reported by the
+    /// outermost call.
+    pub fn record_level_changes<R>(&self, operation: impl FnOnce() -> R) -> (R, LevelChangeSet) {
+        let recorder = &self.level_change_recorder;
+        if recorder.recording.swap(true, Ordering::AcqRel) {
+            return (operation(), LevelChangeSet::default());
+        }
+        let output = operation();
+        recorder.recording.store(false, Ordering::Release);
+        let touched = recorder
+            .touched
+            .lock()
+            .map(|mut touched| std::mem::take(&mut *touched))
+            .unwrap_or_default();
+
+        let mut changes = LevelChangeSet::default();
+        let mut seen = std::collections::HashSet::new();
+        for (side, price, existed_before) in touched {
+            if !seen.insert((side == Side::Buy, price)) {
+                continue;
+            }
+            let levels = match side {
+                Side::Buy => &self.bids,
+                Side::Sell => &self.asks,
+            };
+            let quantity = levels
+                .get(&price)
+                .map(|level| level.value().visible_quantity());
+            let change = |quantity| PriceLevelChangedEvent {
+                side,
+                price,
+                quantity,
+            };
+            match (existed_before, quantity) {
+                (false, Some(quantity)) => changes.added.push(change(quantity)),
+                (true, Some(quantity)) => changes.updated.push(change(quantity)),
+                (true, None) => changes.removed.push(change(0)),
+                (false, None) => {}
+            }
+        }
+        (output, changes)
+    }
+}
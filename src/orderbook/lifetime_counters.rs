@@ -0,0 +1,242 @@
+//! Lifetime counts of orders by outcome.
+//!
+//! Operational dashboards track how many orders a book has accepted,
+//! cancelled, filled and rejected since it was created, as opposed to the
+//! traded quantity. [`OrderBook::lifetime_counters`] reads the counts; they
+//! are never reset by the book and survive a full snapshot restore, whether
+//! from a package or a package rebuilt from deltas.
+//!
+//! An order counts as added once it passes validation on entry, including
+//! fill-or-kill orders that are then killed for lack of liquidity, and when
+//! it is bulk loaded. Requeueing an order neither adds nor cancels it, nor
+//! does an amend that takes the order off the book and adds it again. Each
+//! resting or incoming order that is completely filled counts as filled
+//! once; an order whose remainder is cancelled after partial fills counts
+//! as cancelled.
+
+use super::book::OrderBook;
+use super::order_state::{CancelReason, OrderStatus};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Orders counted by outcome since the book was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct LifetimeCounters {
+    /// Orders accepted by the book.
+    pub orders_added: u64,
+    /// Orders cancelled, by their owner or by the book.
+    pub orders_cancelled: u64,
+    /// Orders completely filled.
+    pub orders_filled: u64,
+    /// Orders rejected on entry.
+    pub orders_rejected: u64,
+}
+
+/// Atomic storage of [`LifetimeCounters`].
+#[derive(Debug, Default)]
+pub(super) struct LifetimeCounterCells {
+    orders_added: AtomicU64,
+    orders_cancelled: AtomicU64,
+    orders_filled: AtomicU64,
+    orders_rejected: AtomicU64,
+}
+
+impl LifetimeCounterCells {
+    /// Count an order accepted by the book.
+    pub(super) fn record_added(&self) {
+        self.orders_added.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count the terminal outcome an order transitioned to, if `status` is
+    /// one.
+    pub(super) fn record_status(&self, status: &OrderStatus) {
+        let counter = match status {
+            OrderStatus::Filled { .. } => &self.orders_filled,
+            OrderStatus::Rejected { .. } => &self.orders_rejected,
+            OrderStatus::Cancelled { reason, .. } if *reason != CancelReason::Requeued => {
+                &self.orders_cancelled
+            }
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn load(&self) -> LifetimeCounters {
+        LifetimeCounters {
+            orders_added: self.orders_added.load(Ordering::Relaxed),
+            orders_cancelled: self.orders_cancelled.load(Ordering::Relaxed),
+            orders_filled: self.orders_filled.load(Ordering::Relaxed),
+            orders_rejected: self.orders_rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(super) fn store(&self, counters: LifetimeCounters) {
+        self.orders_added
+            .store(counters.orders_added, Ordering::Relaxed);
+        self.orders_cancelled
+            .store(counters.orders_cancelled, Ordering::Relaxed);
+        self.orders_filled
+            .store(counters.orders_filled, Ordering::Relaxed);
+        self.orders_rejected
+            .store(counters.orders_rejected, Ordering::Relaxed);
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns the number of orders added, cancelled, filled and rejected
+    /// since the book was created.
+    #[must_use]
+    pub fn lifetime_counters(&self) -> LifetimeCounters {
+        self.lifetime_counters.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::OrderBookError;
+    use pricelevel::{Id, OrderUpdate, Price, Quantity, Side, TimeInForce};
+
+    #[test]
+    fn test_counters_follow_order_outcomes() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_min_order_size(5);
+        let counters = || book.lifetime_counters();
+        assert_eq!(counters(), LifetimeCounters::default());
+
+        let add = |price, quantity, side| {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                quantity,
+                side,
+                TimeInForce::Gtc,
+                None,
+            )
+        };
+        let resting = add(100, 10, Side::Sell).unwrap().id();
+        add(101, 10, Side::Sell).unwrap();
+        let cancelled = add(99, 10, Side::Buy).unwrap().id();
+        book.cancel_order(cancelled).unwrap();
+
+        // The taker and the maker at 100 are both filled, not cancelled
+        add(100, 10, Side::Buy).unwrap();
+        assert!(book.get_order(resting).is_none());
+        assert_eq!(
+            counters(),
+            LifetimeCounters {
+                orders_added: 4,
+                orders_cancelled: 1,
+                orders_filled: 2,
+                orders_rejected: 0,
+            }
+        );
+
+        // A partially filled taker whose remainder is cancelled
+        assert!(matches!(
+            book.add_limit_order(Id::new_uuid(), 101, 15, Side::Buy, TimeInForce::Ioc, None),
+            Err(OrderBookError::InsufficientLiquidity { .. })
+        ));
+        assert_eq!(counters().orders_filled, 3);
+        assert_eq!(counters().orders_cancelled, 2);
+        assert_eq!(counters().orders_added, 5);
+
+        assert!(add(100, 1, Side::Buy).is_err());
+        assert_eq!(counters().orders_rejected, 1);
+        assert_eq!(counters().orders_added, 5);
+    }
+
+    #[test]
+    fn test_counters_survive_snapshot_restore() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 101, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.cancel_order(id).unwrap();
+        let expected = book.lifetime_counters();
+
+        let json = book.snapshot_to_json(usize::MAX).unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_json(&json).unwrap();
+        // Restored orders are not counted again
+        assert_eq!(restored.lifetime_counters(), expected);
+        assert_eq!(expected.orders_added, 2);
+        assert_eq!(expected.orders_cancelled, 1);
+    }
+
+    #[test]
+    fn test_counters_survive_delta_restore() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let base = book.create_snapshot_package(usize::MAX).unwrap();
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.cancel_order(id).unwrap();
+        let target = book.create_snapshot_package(usize::MAX).unwrap();
+
+        let delta = target.delta_since(&base).unwrap();
+        let package = base.apply_delta(&delta).unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(restored.lifetime_counters(), book.lifetime_counters());
+        assert_eq!(restored.lifetime_counters().orders_cancelled, 1);
+    }
+
+    #[test]
+    fn test_amends_are_not_counted_as_cancel_and_add() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.update_order(OrderUpdate::UpdatePrice {
+            order_id: id,
+            new_price: Price::new(101),
+        })
+        .unwrap();
+        book.update_order(OrderUpdate::UpdatePriceAndQuantity {
+            order_id: id,
+            new_price: Price::new(102),
+            new_quantity: Quantity::new(8),
+        })
+        .unwrap();
+        book.update_order(OrderUpdate::Replace {
+            order_id: id,
+            price: Price::new(103),
+            quantity: Quantity::new(6),
+            side: Side::Buy,
+        })
+        .unwrap();
+        assert_eq!(book.best_bid(), Some(103));
+        assert_eq!(
+            book.lifetime_counters(),
+            LifetimeCounters {
+                orders_added: 1,
+                ..LifetimeCounters::default()
+            }
+        );
+
+        // An amend that crosses and fills the order counts the fill
+        book.add_limit_order(Id::new_uuid(), 110, 6, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.update_order(OrderUpdate::UpdatePrice {
+            order_id: id,
+            new_price: Price::new(110),
+        })
+        .unwrap();
+        assert!(book.get_order(id).is_none());
+        assert_eq!(
+            book.lifetime_counters(),
+            LifetimeCounters {
+                orders_added: 2,
+                orders_cancelled: 0,
+                orders_filled: 2,
+                orders_rejected: 0,
+            }
+        );
+    }
+}
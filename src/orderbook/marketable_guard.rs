@@ -0,0 +1,147 @@
+//! Rejection of orders crossing far beyond the opposite best price.
+//!
+//! A buy priced well above the best ask, or a sell well below the best bid,
+//! is usually a typo rather than intent. With
+//! [`OrderBook::set_marketable_guard`] set, an aggressive limit order may
+//! cross the opposite best price by at most the tolerance; an order priced
+//! beyond it is rejected before it can sweep the book. Unlike the price
+//! band, which is centred on a reference price, the guard moves with the
+//! touch. Orders are accepted while the opposite side is empty, and the
+//! guard does not apply during an auction, where orders rest without
+//! matching.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::Side;
+use tracing::trace;
+
+/// Basis points per whole unit (100%).
+const BASIS_POINTS_PER_UNIT: u128 = 10_000;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the tolerance in basis points by which an aggressive order may
+    /// cross the opposite best price. `None` disables the guard (default).
+    pub fn set_marketable_guard(&mut self, bps: Option<u32>) {
+        trace!(
+            "Order book {}: Setting marketable guard to {:?} bps",
+            self.symbol, bps
+        );
+        self.marketable_guard_bps = bps;
+    }
+
+    /// Returns the marketable guard tolerance in basis points, if any.
+    #[must_use]
+    pub fn marketable_guard(&self) -> Option<u32> {
+        self.marketable_guard_bps
+    }
+
+    /// Reject an order at `price` on `side` that crosses the opposite best
+    /// price by more than the guard's tolerance.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::ExcessiveCross`] when `price` is beyond the
+    /// furthest price the guard allows.
+    pub(super) fn check_marketable_guard(
+        &self,
+        price: u128,
+        side: Side,
+    ) -> Result<(), OrderBookError> {
+        let Some(bps) = self.marketable_guard_bps else {
+            return Ok(());
+        };
+        let opposite = match side {
+            Side::Buy => self.best_ask(),
+            Side::Sell => self.best_bid(),
+        };
+        let Some(opposite_price) = opposite else {
+            return Ok(());
+        };
+
+        let delta = opposite_price
+            .checked_mul(u128::from(bps))
+            .map_or(u128::MAX, |v| v / BASIS_POINTS_PER_UNIT);
+        let (limit, beyond) = match side {
+            Side::Buy => {
+                let limit = opposite_price.saturating_add(delta);
+                (limit, price > limit)
+            }
+            Side::Sell => {
+                let limit = opposite_price.saturating_sub(delta);
+                (limit, price < limit)
+            }
+        };
+        if beyond {
+            return Err(OrderBookError::ExcessiveCross {
+                price,
+                side,
+                opposite_price,
+                limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn add(book: &OrderBook<()>, price: u128, side: Side) -> Result<(), OrderBookError> {
+        book.add_limit_order(Id::new_uuid(), price, 5, side, TimeInForce::Gtc, None)
+            .map(|_| ())
+    }
+
+    /// Bid 990 and ask 1,000 with a 5% guard.
+    fn book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_marketable_guard(Some(500));
+        add(&book, 990, Side::Buy).unwrap();
+        add(&book, 1_000, Side::Sell).unwrap();
+        add(&book, 1_040, Side::Sell).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_deep_crossing_order_is_rejected() {
+        let book = book();
+        assert_eq!(book.marketable_guard(), Some(500));
+        assert!(matches!(
+            add(&book, 1_051, Side::Buy),
+            Err(OrderBookError::ExcessiveCross {
+                side: Side::Buy,
+                opposite_price: 1_000,
+                limit: 1_050,
+                ..
+            })
+        ));
+        // 990 less 5% is 940.5, truncated to a limit of 941
+        assert!(matches!(
+            add(&book, 940, Side::Sell),
+            Err(OrderBookError::ExcessiveCross { limit: 941, .. })
+        ));
+        assert_eq!(book.best_ask(), Some(1_000));
+        assert_eq!(book.best_bid(), Some(990));
+    }
+
+    #[test]
+    fn test_crossing_within_tolerance_executes() {
+        let mut book = book();
+        // Orders exactly on the limit execute against the touch
+        add(&book, 1_050, Side::Buy).unwrap();
+        assert_eq!(book.best_ask(), Some(1_040));
+        add(&book, 941, Side::Sell).unwrap();
+        assert_eq!(book.best_bid(), None);
+
+        // Orders are accepted while the opposite side is empty
+        add(&book, 1, Side::Sell).unwrap();
+        assert!(add(&book, 5_000, Side::Buy).is_err());
+
+        book.set_marketable_guard(None);
+        add(&book, 5_000, Side::Buy).unwrap();
+        assert_eq!(book.best_ask(), Some(1_040));
+    }
+}
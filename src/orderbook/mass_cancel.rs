@@ -126,6 +126,7 @@ where
     /// assert_eq!(book.best_ask(), None);
     /// ```
     pub fn cancel_all_orders(&self) -> MassCancelResult {
+        let _write = self.write_scope();
         self.cache.invalidate();
         trace!("Order book {}: Mass cancel ALL orders (bulk)", self.symbol);
 
@@ -158,6 +159,17 @@ where
             }
         }
 
+        if self.level_change_recorder.is_recording() {
+            for entry in self.bids.iter() {
+                self.level_change_recorder
+                    .touch(Side::Buy, *entry.key(), true);
+            }
+            for entry in self.asks.iter() {
+                self.level_change_recorder
+                    .touch(Side::Sell, *entry.key(), true);
+            }
+        }
+
         // 2b. Track cancellation state for each order
         for &order_id in &cancelled_order_ids {
             let prev_filled = self
@@ -177,7 +189,10 @@ where
 
         // 3. Clear tracking maps
         self.order_locations.clear();
+        self.arrivals.clear();
         self.user_orders.clear();
+        self.order_extra_fields.clear();
+        self.contingent_orders.clear();
 
         // 4. Drain both SkipMaps
         while self.bids.pop_front().is_some() {}
@@ -358,6 +373,7 @@ where
         order_ids: &[Id],
         reason: CancelReason,
     ) -> MassCancelResult {
+        let _write = self.write_scope();
         let mut cancelled_ids = Vec::with_capacity(order_ids.len());
 
         for &order_id in order_ids {
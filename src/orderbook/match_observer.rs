@@ -0,0 +1,238 @@
+//! Optional instrumentation hooks around order matching.
+//!
+//! A [`MatchObserver`] registered with [`OrderBook::set_match_observer`] is
+//! notified when matching of an incoming order starts and ends, together
+//! with the kind of order, the number of fills and the executed quantity.
+//! This is intended for per-order-type latency histograms and similar
+//! measurements. When no observer is set the matching path only pays for a
+//! single `None` check.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{Id, MatchResult, OrderType, Side};
+use std::sync::Arc;
+
+/// Kind of incoming order being matched, as reported to a [`MatchObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchOrderKind {
+    /// Market order (no limit price).
+    Market,
+    /// Standard limit order.
+    Standard,
+    /// Iceberg order.
+    Iceberg,
+    /// Post-only order.
+    PostOnly,
+    /// Trailing stop order.
+    TrailingStop,
+    /// Pegged order.
+    Pegged,
+    /// Market-to-limit order.
+    MarketToLimit,
+    /// Reserve order.
+    Reserve,
+}
+
+impl MatchOrderKind {
+    /// Returns the kind matching the given order variant.
+    #[must_use]
+    pub fn from_order<T>(order: &OrderType<T>) -> Self {
+        match order {
+            OrderType::Standard { .. } => MatchOrderKind::Standard,
+            OrderType::IcebergOrder { .. } => MatchOrderKind::Iceberg,
+            OrderType::PostOnly { .. } => MatchOrderKind::PostOnly,
+            OrderType::TrailingStop { .. } => MatchOrderKind::TrailingStop,
+            OrderType::PeggedOrder { .. } => MatchOrderKind::Pegged,
+            OrderType::MarketToLimit { .. } => MatchOrderKind::MarketToLimit,
+            OrderType::ReserveOrder { .. } => MatchOrderKind::Reserve,
+        }
+    }
+}
+
+impl std::fmt::Display for MatchOrderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchOrderKind::Market => write!(f, "Market"),
+            MatchOrderKind::Standard => write!(f, "Standard"),
+            MatchOrderKind::Iceberg => write!(f, "Iceberg"),
+            MatchOrderKind::PostOnly => write!(f, "PostOnly"),
+            MatchOrderKind::TrailingStop => write!(f, "TrailingStop"),
+            MatchOrderKind::Pegged => write!(f, "Pegged"),
+            MatchOrderKind::MarketToLimit => write!(f, "MarketToLimit"),
+            MatchOrderKind::Reserve => write!(f, "Reserve"),
+        }
+    }
+}
+
+/// Hooks invoked around the matching of an incoming order.
+///
+/// Both methods have empty default implementations so observers only need
+/// to implement what they measure. Observers run synchronously on the
+/// matching thread and should be cheap.
+pub trait MatchObserver: Send + Sync {
+    /// Called before the incoming order is matched.
+    fn on_match_start(&self, _order_id: Id, _kind: MatchOrderKind, _side: Side, _quantity: u64) {}
+
+    /// Called after matching finishes.
+    ///
+    /// `fills` is the number of trades produced and `executed_quantity` the
+    /// total quantity filled. Both are zero when matching returned an error.
+    fn on_match_end(
+        &self,
+        _order_id: Id,
+        _kind: MatchOrderKind,
+        _fills: usize,
+        _executed_quantity: u64,
+    ) {
+    }
+}
+
+impl<O: MatchObserver + ?Sized> MatchObserver for Arc<O> {
+    fn on_match_start(&self, order_id: Id, kind: MatchOrderKind, side: Side, quantity: u64) {
+        (**self).on_match_start(order_id, kind, side, quantity);
+    }
+
+    fn on_match_end(
+        &self,
+        order_id: Id,
+        kind: MatchOrderKind,
+        fills: usize,
+        executed_quantity: u64,
+    ) {
+        (**self).on_match_end(order_id, kind, fills, executed_quantity);
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the match observer for this order book.
+    pub fn set_match_observer(&mut self, observer: impl MatchObserver + 'static) {
+        self.match_observer = Some(Arc::new(observer));
+    }
+
+    /// Remove the match observer from this order book.
+    pub fn remove_match_observer(&mut self) {
+        self.match_observer = None;
+    }
+
+    /// Run `match_fn`, notifying the match observer (if any) before and after.
+    #[inline]
+    pub(super) fn observe_match<F>(
+        &self,
+        order_id: Id,
+        kind: MatchOrderKind,
+        side: Side,
+        quantity: u64,
+        match_fn: F,
+    ) -> Result<MatchResult, OrderBookError>
+    where
+        F: FnOnce() -> Result<MatchResult, OrderBookError>,
+    {
+        let Some(observer) = self.match_observer.as_ref() else {
+            return match_fn();
+        };
+
+        observer.on_match_start(order_id, kind, side, quantity);
+        let result = match_fn();
+        let (fills, executed_quantity) = match &result {
+            Ok(match_result) => (
+                match_result.trades().as_vec().len(),
+                match_result.executed_quantity().unwrap_or(0),
+            ),
+            Err(_) => (0, 0),
+        };
+        observer.on_match_end(order_id, kind, fills, executed_quantity);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::TimeInForce;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        starts: Mutex<Vec<(Id, MatchOrderKind, u64)>>,
+        ends: Mutex<Vec<(Id, MatchOrderKind, usize, u64)>>,
+    }
+
+    impl MatchObserver for Recorder {
+        fn on_match_start(&self, order_id: Id, kind: MatchOrderKind, _side: Side, quantity: u64) {
+            self.starts.lock().unwrap().push((order_id, kind, quantity));
+        }
+
+        fn on_match_end(
+            &self,
+            order_id: Id,
+            kind: MatchOrderKind,
+            fills: usize,
+            executed_quantity: u64,
+        ) {
+            self.ends
+                .lock()
+                .unwrap()
+                .push((order_id, kind, fills, executed_quantity));
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_crossing_market_order() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        let recorder = Arc::new(Recorder::default());
+        book.set_match_observer(Arc::clone(&recorder));
+
+        book.add_limit_order(Id::new_uuid(), 100, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 101, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // Two resting limit orders were matched (against an empty book)
+        assert_eq!(recorder.ends.lock().unwrap().len(), 2);
+        recorder.starts.lock().unwrap().clear();
+        recorder.ends.lock().unwrap().clear();
+
+        let taker = Id::new_uuid();
+        book.submit_market_order(taker, 8, Side::Buy).unwrap();
+
+        assert_eq!(
+            *recorder.starts.lock().unwrap(),
+            vec![(taker, MatchOrderKind::Market, 8)]
+        );
+        assert_eq!(
+            *recorder.ends.lock().unwrap(),
+            vec![(taker, MatchOrderKind::Market, 2, 8)]
+        );
+    }
+
+    #[test]
+    fn test_observer_reports_limit_kind_and_errors() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        let recorder = Arc::new(Recorder::default());
+        book.set_match_observer(Arc::clone(&recorder));
+
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(
+            *recorder.ends.lock().unwrap(),
+            vec![(id, MatchOrderKind::Standard, 0, 0)]
+        );
+
+        // Market order against an empty side fails; observer still sees the end
+        let taker = Id::new_uuid();
+        assert!(book.submit_market_order(taker, 1, Side::Buy).is_err());
+        assert_eq!(
+            recorder.ends.lock().unwrap().last().copied(),
+            Some((taker, MatchOrderKind::Market, 0, 0))
+        );
+
+        book.remove_match_observer();
+        book.submit_market_order(Id::new_uuid(), 1, Side::Sell)
+            .unwrap();
+        assert_eq!(recorder.ends.lock().unwrap().len(), 2);
+    }
+}
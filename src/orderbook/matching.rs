@@ -3,13 +3,26 @@
 //! The matching engine supports Self-Trade Prevention (STP) when configured
 //! via [`crate::STPMode`]. When STP is disabled (`STPMode::None`, the default),
 //! the matching hot path is unchanged with zero overhead.
+//!
+//! The level walk itself is [`match_against_levels`], which works on bare
+//! price levels and can be driven without an [`OrderBook`]; the book's
+//! methods wrap it with order tracking, fees and state updates.
 
+use crate::orderbook::arrival::ArrivalOrder;
+use crate::orderbook::book::level_quantity;
 use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+use crate::orderbook::iceberg_refill::{IcebergRefillPolicy, match_in_place};
 use crate::orderbook::order_state::{CancelReason, OrderStatus};
 use crate::orderbook::pool::MatchingPool;
-use crate::orderbook::stp::{STPAction, check_stp_at_level};
+use crate::orderbook::stp::{
+    STPAction, STPMakerScope, STPMode, STPTriggered, check_stp_at_level, first_same_user_maker,
+};
 use crate::{OrderBook, OrderBookError};
-use pricelevel::{Hash32, Id, MatchResult, OrderUpdate, Side};
+use crossbeam_skiplist::SkipMap;
+use pricelevel::{
+    Hash32, Id, MatchResult, OrderType, OrderUpdate, PriceLevel, Side, UuidGenerator,
+};
+use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
 impl<T> OrderBook<T>
@@ -63,244 +76,153 @@ where
         limit_price: Option<u128>,
         taker_user_id: Hash32,
     ) -> Result<MatchResult, OrderBookError> {
-        self.cache.invalidate();
-        let mut match_result = MatchResult::new(order_id, quantity);
-        let mut remaining_quantity = quantity;
-
-        // Determine if STP checks are needed for this match
-        let stp_active = self.stp_mode.is_enabled() && taker_user_id != Hash32::zero();
-
-        // Choose the appropriate side for matching
-        let match_side = match side {
-            Side::Buy => &self.asks,
-            Side::Sell => &self.bids,
-        };
-
-        // Early exit if the opposite side is empty
-        if match_side.is_empty() {
-            if limit_price.is_none() {
-                return Err(OrderBookError::InsufficientLiquidity {
-                    side,
-                    requested: quantity,
-                    available: 0,
-                });
-            }
-            // remaining_quantity is managed by add_trade(); no manual update needed
-            return Ok(match_result);
-        }
-
-        // Use static memory pool for better performance
-        thread_local! {
-            static MATCHING_POOL: MatchingPool = MatchingPool::new();
-        }
-
-        // Get reusable vectors from pool
-        let (mut filled_orders, mut empty_price_levels) = MATCHING_POOL.with(|pool| {
-            let filled = pool.get_filled_orders_vec();
-            let empty = pool.get_price_vec();
-            (filled, empty)
-        });
-
-        // Track whether STP cancelled the taker
-        let mut stp_taker_cancelled = false;
-
-        // Iterate through prices in optimal order (already sorted by SkipMap)
-        // For buy orders: iterate asks in ascending order (best ask first)
-        // For sell orders: iterate bids in descending order (best bid first)
-        let price_iter: Box<dyn Iterator<Item = _>> = match side {
-            Side::Buy => Box::new(match_side.iter()),
-            Side::Sell => Box::new(match_side.iter().rev()),
-        };
-
-        // Process each price level
-        for entry in price_iter {
-            let price = *entry.key();
-            // Check price limit constraint early
-            if let Some(limit) = limit_price {
-                match side {
-                    Side::Buy if price > limit => break,
-                    Side::Sell if price < limit => break,
-                    _ => {}
-                }
-            }
-
-            // Get price level value from the entry
-            let price_level = entry.value();
-
-            // --- STP pre-processing ---
-            // When STP is active, check for self-trade conflicts before matching.
-            // This is done per-price-level to handle partial fills correctly.
-            if stp_active {
-                let orders: Vec<_> = price_level.iter_orders().collect();
-                let action = check_stp_at_level(&orders, taker_user_id, self.stp_mode);
+        self.match_order_reporting_stp(order_id, side, quantity, limit_price, taker_user_id)
+            .map(|(match_result, _)| match_result)
+    }
 
-                match action {
-                    STPAction::NoConflict => {
-                        // No self-trade at this level; match normally below
-                    }
+    /// Match as [`Self::match_order_with_user`] does and also report what
+    /// self-trade prevention did, if it triggered.
+    pub(super) fn match_order_reporting_stp(
+        &self,
+        order_id: Id,
+        side: Side,
+        quantity: u64,
+        limit_price: Option<u128>,
+        taker_user_id: Hash32,
+    ) -> Result<(MatchResult, Option<STPTriggered>), OrderBookError> {
+        self.match_incoming_reporting_stp(&IncomingOrder {
+            order_id,
+            side,
+            quantity,
+            limit_price,
+            user_id: taker_user_id,
+            interact_hidden: true,
+        })
+    }
 
-                    STPAction::CancelTaker { safe_quantity } => {
-                        // Match up to safe_quantity, then cancel the taker
-                        if safe_quantity > 0 {
-                            let match_qty = remaining_quantity.min(safe_quantity);
-                            let saved_remaining = remaining_quantity;
-                            let price_level_match = price_level.match_order(
-                                match_qty,
-                                order_id,
-                                &self.transaction_id_generator,
-                            );
-                            // Compute actual executed from the sub-match
-                            let executed =
-                                match_qty.saturating_sub(price_level_match.remaining_quantity());
-                            Self::process_level_match(
-                                &mut match_result,
-                                &price_level_match,
-                                &mut filled_orders,
-                                &mut remaining_quantity,
-                                price,
-                                price_level,
-                                side,
-                                &self.last_trade_price,
-                                &self.has_traded,
-                                &self.price_level_changed_listener,
-                                &mut empty_price_levels,
-                            );
-                            // Correct remaining: process_level_match set it to
-                            // sub-match remaining, but we need overall remaining.
-                            remaining_quantity = saved_remaining.saturating_sub(executed);
-                        }
-                        stp_taker_cancelled = true;
-                        break;
-                    }
+    /// Match `incoming` as [`Self::match_order_reporting_stp`] does.
+    pub(super) fn match_incoming_reporting_stp(
+        &self,
+        incoming: &IncomingOrder,
+    ) -> Result<(MatchResult, Option<STPTriggered>), OrderBookError> {
+        let _write = self.write_scope();
+        let (match_result, stp) = self.match_resting_orders(incoming, true)?;
+        self.record_session_trades(&match_result);
+        self.record_tape(&match_result);
+        self.submit_triggered_contingents(&match_result);
+        self.update_trailing_stops(&match_result);
+        Ok((match_result, stp))
+    }
 
-                    STPAction::CancelMaker { maker_order_ids } => {
-                        // Cancel same-user resting orders, then match normally.
-                        // Look up each maker's user_id from the snapshot rather
-                        // than assuming it equals taker_user_id.
-                        for maker_id in &maker_order_ids {
-                            let maker_user_id = orders
-                                .iter()
-                                .find(|o| o.id() == *maker_id)
-                                .map(|o| o.user_id())
-                                .unwrap_or(taker_user_id);
-                            let _ = price_level.update_order(OrderUpdate::Cancel {
-                                order_id: *maker_id,
-                            });
-                            self.order_locations.remove(maker_id);
-                            self.untrack_user_order(maker_user_id, maker_id);
-                        }
-                        // If the level is now empty, mark for removal and continue
-                        if price_level.order_count() == 0 {
-                            empty_price_levels.push(price);
-                            continue;
-                        }
-                        // Fall through to normal matching below
-                    }
+    /// Consume resting liquidity as [`Self::match_order_with_user`] does,
+    /// without counting the fills towards the session totals.
+    ///
+    /// Used where the fills are bookkeeping for trades reported separately,
+    /// such as the one-sided sweeps of an auction uncross. The fills are
+    /// charged to the fee ledger only when `accrue_fees` is set.
+    pub(super) fn match_resting_orders(
+        &self,
+        incoming: &IncomingOrder,
+        accrue_fees: bool,
+    ) -> Result<(MatchResult, Option<STPTriggered>), OrderBookError> {
+        self.cache.invalidate();
 
-                    STPAction::CancelBoth {
-                        safe_quantity,
-                        maker_order_id,
-                    } => {
-                        // Match up to safe_quantity, cancel the maker, then cancel taker
-                        if safe_quantity > 0 {
-                            let match_qty = remaining_quantity.min(safe_quantity);
-                            let saved_remaining = remaining_quantity;
-                            let price_level_match = price_level.match_order(
-                                match_qty,
-                                order_id,
-                                &self.transaction_id_generator,
-                            );
-                            let executed =
-                                match_qty.saturating_sub(price_level_match.remaining_quantity());
-                            Self::process_level_match(
-                                &mut match_result,
-                                &price_level_match,
-                                &mut filled_orders,
-                                &mut remaining_quantity,
-                                price,
-                                price_level,
-                                side,
-                                &self.last_trade_price,
-                                &self.has_traded,
-                                &self.price_level_changed_listener,
-                                &mut empty_price_levels,
-                            );
-                            // Correct remaining: process_level_match set it to
-                            // sub-match remaining, but we need overall remaining.
-                            remaining_quantity = saved_remaining.saturating_sub(executed);
-                        }
-                        // Cancel the maker order — look up its user_id from the
-                        // snapshot rather than assuming it equals taker_user_id.
-                        let maker_user_id = orders
-                            .iter()
-                            .find(|o| o.id() == maker_order_id)
-                            .map(|o| o.user_id())
-                            .unwrap_or(taker_user_id);
-                        let _ = price_level.update_order(OrderUpdate::Cancel {
-                            order_id: maker_order_id,
-                        });
-                        self.order_locations.remove(&maker_order_id);
-                        self.untrack_user_order(maker_user_id, &maker_order_id);
-                        if price_level.order_count() == 0 {
-                            empty_price_levels.push(price);
-                        }
-                        stp_taker_cancelled = true;
-                        break;
-                    }
-                }
+        let notify = |event: PriceLevelChangedEvent| {
+            let (side, price) = (event.side, event.price);
+            if let Some(ref listener) = self.price_level_changed_listener {
+                listener(event);
             }
+            self.level_change_recorder.touch(side, price, true);
+        };
+        let config = MatchConfig {
+            stp_mode: self.stp_mode,
+            stp_maker_scope: self.stp_maker_scope,
+            transaction_id_generator: &self.transaction_id_generator,
+            on_level_change: Some(&notify),
+            iceberg_refill_policy: self.iceberg_refill_policy,
+            arrivals: Some(&self.arrivals),
+        };
+        let level_match = match_against_levels(incoming, &self.bids, &self.asks, &config);
+        self.settle_level_match(incoming, level_match, accrue_fees)
+    }
 
-            // --- Normal matching (no STP conflict or after CancelMaker cleanup) ---
-            let price_level_match = price_level.match_order(
-                remaining_quantity,
-                order_id,
-                &self.transaction_id_generator,
-            );
-
-            Self::process_level_match(
-                &mut match_result,
-                &price_level_match,
-                &mut filled_orders,
-                &mut remaining_quantity,
-                price,
-                price_level,
-                side,
-                &self.last_trade_price,
-                &self.has_traded,
-                &self.price_level_changed_listener,
-                &mut empty_price_levels,
-            );
-
-            // Early exit if order is fully matched
-            if remaining_quantity == 0 {
-                break;
-            }
+    /// Bring the book's order indexes in step with `level_match`, the
+    /// outcome of matching `incoming`, and decide whether it is an error.
+    ///
+    /// A walk that stopped on a failure is settled as far as it went before
+    /// the failure is returned. The contingent orders of makers it filled
+    /// are dropped, as the operation fails.
+    pub(super) fn settle_level_match(
+        &self,
+        incoming: &IncomingOrder,
+        level_match: LevelMatch,
+        accrue_fees: bool,
+    ) -> Result<(MatchResult, Option<STPTriggered>), OrderBookError> {
+        let IncomingOrder {
+            order_id,
+            side,
+            quantity,
+            limit_price,
+            user_id: taker_user_id,
+            ..
+        } = *incoming;
+        let LevelMatch {
+            match_result,
+            stp,
+            cancelled_makers,
+            last_trade_price,
+            failure,
+        } = level_match;
+
+        if let Some(price) = last_trade_price {
+            self.last_trade_price.store(price);
+            self.has_traded.store(true, Ordering::Relaxed);
         }
 
-        // Batch remove empty price levels
-        for price in &empty_price_levels {
-            match_side.remove(price);
+        // Forget the resting orders self-trade prevention cancelled
+        for maker in &cancelled_makers {
+            if self.stp_mode == STPMode::CancelBoth {
+                self.level_change_recorder
+                    .touch(side.opposite(), maker.price, true);
+            }
+            self.order_locations.remove(&maker.order_id);
+            self.arrivals.remove(maker.order_id);
+            self.order_extra_fields.remove(&maker.order_id);
+            self.contingent_orders.remove(&maker.order_id);
+            self.untrack_user_order(maker.user_id, &maker.order_id);
         }
 
-        // Batch remove filled orders from tracking and update state
-        for filled_id in &filled_orders {
+        // Batch remove filled orders from tracking and update state, keeping
+        // their owners while fees accrue
+        let accrue_fees = accrue_fees && self.accrues_fees();
+        let mut filled_owners = Vec::new();
+        for filled_id in match_result.filled_order_ids() {
             // Track the resting order as Filled (quantity unknown here;
             // use 0 as placeholder — the important thing is the terminal state)
             self.track_state(*filled_id, OrderStatus::Filled { filled_quantity: 0 });
             self.order_locations.remove(filled_id);
-            self.untrack_order_by_id(filled_id);
+            self.arrivals.remove(*filled_id);
+            self.order_extra_fields.remove(filled_id);
+            let owner = self.untrack_order_by_id(filled_id);
+            if accrue_fees && let Some(owner) = owner {
+                filled_owners.push((*filled_id, owner));
+            }
+        }
+        if accrue_fees {
+            self.accrue_fees(&match_result, taker_user_id, &filled_owners);
+        }
+        if let Some(failure) = failure {
+            for filled_id in match_result.filled_order_ids() {
+                self.contingent_orders.remove(filled_id);
+            }
+            return Err(failure);
         }
-
-        // Return vectors to pool for reuse
-        MATCHING_POOL.with(|pool| {
-            pool.return_filled_orders_vec(filled_orders);
-            pool.return_price_vec(empty_price_levels);
-        });
 
         // If STP cancelled the taker and no fills occurred at all, return STP error.
         // When partial fills happened (remaining < original quantity), return Ok
         // with the partial result so the caller can see what was executed.
-        if stp_taker_cancelled && remaining_quantity == quantity {
+        let unfilled = match_result.remaining_quantity() == quantity;
+        if unfilled && stp.is_some_and(|triggered| triggered.cancelled_taker) {
             self.track_state(
                 order_id,
                 OrderStatus::Cancelled {
@@ -316,7 +238,7 @@ where
         }
 
         // Check for insufficient liquidity in market orders
-        if limit_price.is_none() && remaining_quantity == quantity {
+        if limit_price.is_none() && unfilled {
             return Err(OrderBookError::InsufficientLiquidity {
                 side,
                 requested: quantity,
@@ -324,67 +246,7 @@ where
             });
         }
 
-        // remaining_quantity is managed by add_trade(); no manual update needed
-        Ok(match_result)
-    }
-
-    /// Processes match results from a single price level, updating the
-    /// aggregate match result and bookkeeping vectors.
-    ///
-    /// Extracted to avoid code duplication between the normal path and
-    /// the STP safe-quantity pre-match path.
-    #[allow(clippy::too_many_arguments)]
-    fn process_level_match(
-        match_result: &mut MatchResult,
-        price_level_match: &MatchResult,
-        filled_orders: &mut Vec<Id>,
-        remaining_quantity: &mut u64,
-        price: u128,
-        price_level: &std::sync::Arc<pricelevel::PriceLevel>,
-        side: Side,
-        last_trade_price: &crossbeam::atomic::AtomicCell<u128>,
-        has_traded: &std::sync::atomic::AtomicBool,
-        price_level_changed_listener: &Option<
-            crate::orderbook::book_change_event::PriceLevelChangedListener,
-        >,
-        empty_price_levels: &mut Vec<u128>,
-    ) {
-        // Process trades if any occurred
-        if !price_level_match.trades().as_vec().is_empty() {
-            // Update last trade price atomically
-            last_trade_price.store(price);
-            has_traded.store(true, Ordering::Relaxed);
-
-            // Add trades to result
-            for trade in price_level_match.trades().as_vec() {
-                // add_trade returns Result in v0.7; ignore error since
-                // pricelevel already validated the quantities during matching
-                let _ = match_result.add_trade(*trade);
-            }
-
-            // Notify price level changes
-            if let Some(listener) = price_level_changed_listener {
-                listener(PriceLevelChangedEvent {
-                    side: side.opposite(),
-                    price: price_level.price(),
-                    quantity: price_level.visible_quantity(),
-                });
-            }
-        }
-
-        // Collect filled orders for batch removal
-        for &filled_order_id in price_level_match.filled_order_ids() {
-            match_result.add_filled_order_id(filled_order_id);
-            filled_orders.push(filled_order_id);
-        }
-
-        // Update remaining quantity
-        *remaining_quantity = price_level_match.remaining_quantity();
-
-        // Check if price level is empty and mark for removal
-        if price_level.order_count() == 0 {
-            empty_price_levels.push(price);
-        }
+        Ok((match_result, stp))
     }
 
     /// Optimized peek match without memory pooling or sorting
@@ -430,7 +292,7 @@ where
 
             // Get available quantity at this level
             let price_level = entry.value();
-            let available_quantity = price_level.total_quantity().unwrap_or(0);
+            let available_quantity = level_quantity(price_level);
             let needed_quantity = quantity.saturating_sub(matched_quantity);
             let quantity_to_match = needed_quantity.min(available_quantity);
             matched_quantity = matched_quantity.saturating_add(quantity_to_match);
@@ -454,3 +316,489 @@ where
         results
     }
 }
+
+/// An incoming order matched by [`match_against_levels`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncomingOrder {
+    /// Identifier of the incoming (taker) order.
+    pub order_id: Id,
+    /// Side of the incoming order; it matches the opposite side.
+    pub side: Side,
+    /// Quantity to match.
+    pub quantity: u64,
+    /// Worst price the order may trade at, `None` for a market order.
+    pub limit_price: Option<u128>,
+    /// Owner of the incoming order, for self-trade prevention.
+    pub user_id: Hash32,
+    /// Whether the order may trade with the hidden quantity of iceberg and
+    /// reserve orders. When `false` it takes only the displayed quantity of
+    /// each level, leaving the rest of the order unfilled.
+    pub interact_hidden: bool,
+}
+
+/// Settings for [`match_against_levels`].
+pub struct MatchConfig<'a> {
+    /// Self-trade prevention applied against resting orders.
+    pub stp_mode: STPMode,
+    /// Which same-user makers [`STPMode::CancelMaker`] cancels at a level.
+    pub stp_maker_scope: STPMakerScope,
+    /// Source of the trade identifiers.
+    pub transaction_id_generator: &'a UuidGenerator,
+    /// Called with the new state of every level that traded, as it trades.
+    pub on_level_change: Option<&'a dyn Fn(PriceLevelChangedEvent)>,
+    /// Where refilled iceberg and reserve orders rest in their level's queue.
+    pub iceberg_refill_policy: IcebergRefillPolicy,
+    /// Queue order of the resting orders, kept in step as makers rejoin the
+    /// back of their level. Without it, self-trade prevention scans levels
+    /// in timestamp order.
+    pub arrivals: Option<&'a ArrivalOrder>,
+}
+
+/// A resting order cancelled by self-trade prevention while matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StpCancelledMaker {
+    /// Identifier of the cancelled order.
+    pub order_id: Id,
+    /// Owner of the cancelled order.
+    pub user_id: Hash32,
+    /// Price of the level the order rested at.
+    pub price: u128,
+}
+
+/// Outcome of [`match_against_levels`].
+#[derive(Debug, Clone)]
+pub struct LevelMatch {
+    /// Trades executed and makers filled, in execution order.
+    pub match_result: MatchResult,
+    /// What self-trade prevention did, if it triggered.
+    pub stp: Option<STPTriggered>,
+    /// Resting orders self-trade prevention removed from their levels.
+    pub cancelled_makers: Vec<StpCancelledMaker>,
+    /// Price of the last level that traded, if any did.
+    pub last_trade_price: Option<u128>,
+    /// Why the walk stopped early, if a level refused an update. The
+    /// trades, fills and cancels above happened before it and stand.
+    pub failure: Option<OrderBookError>,
+}
+
+/// Match `incoming` against the opposite side of `bids` and `asks`.
+///
+/// This is the matching core of [`OrderBook`], free of the book's order
+/// indexes. Levels are walked from the best price until the order fills
+/// or reaches its limit; filled makers and emptied levels are removed from
+/// the levels, and makers cancelled by self-trade prevention are reported.
+/// Keeping any index of resting orders in step, and deciding whether an
+/// unfilled order is an error, is left to the caller.
+///
+/// # Performance Optimization
+/// Uses SkipMap which maintains prices in sorted order automatically.
+/// This eliminates O(N log N) sorting overhead, reducing time complexity
+/// from O(N log N) to O(M log N), where:
+/// - N = total number of price levels
+/// - M = number of price levels actually matched (typically << N)
+///
+/// When `config.stp_mode` is `None` or the incoming order is anonymous,
+/// the STP check is skipped entirely (zero overhead fast path).
+///
+/// If a level matched in place under
+/// [`IcebergRefillPolicy::KeepPriority`] refuses an update, the walk stops
+/// at that level and the [`OrderBookError::PriceLevelError`] is reported in
+/// [`LevelMatch::failure`] together with what the earlier levels executed,
+/// which the caller still has to account for.
+pub fn match_against_levels(
+    incoming: &IncomingOrder,
+    bids: &SkipMap<u128, Arc<PriceLevel>>,
+    asks: &SkipMap<u128, Arc<PriceLevel>>,
+    config: &MatchConfig<'_>,
+) -> LevelMatch {
+    let IncomingOrder {
+        order_id,
+        side,
+        quantity,
+        limit_price,
+        user_id: taker_user_id,
+        interact_hidden,
+    } = *incoming;
+    let mut outcome = LevelMatch {
+        match_result: MatchResult::new(order_id, quantity),
+        stp: None,
+        cancelled_makers: Vec::new(),
+        last_trade_price: None,
+        failure: None,
+    };
+    let mut remaining_quantity = quantity;
+
+    // Determine if STP checks are needed for this match
+    let stp_active = config.stp_mode.is_enabled() && taker_user_id != Hash32::zero();
+
+    // Choose the appropriate side for matching
+    let match_side = match side {
+        Side::Buy => asks,
+        Side::Sell => bids,
+    };
+
+    // Early exit if the opposite side is empty
+    if match_side.is_empty() {
+        return outcome;
+    }
+
+    // Use static memory pool for better performance
+    thread_local! {
+        static MATCHING_POOL: MatchingPool = MatchingPool::new();
+    }
+
+    // Get a reusable vector from the pool
+    let mut empty_price_levels = MATCHING_POOL.with(|pool| pool.get_price_vec());
+
+    // Track whether STP cancelled the taker or any maker
+    let mut stp_taker_cancelled = false;
+    let mut stp_maker_cancelled = false;
+
+    // Iterate through prices in optimal order (already sorted by SkipMap)
+    // For buy orders: iterate asks in ascending order (best ask first)
+    // For sell orders: iterate bids in descending order (best bid first)
+    let price_iter: Box<dyn Iterator<Item = _>> = match side {
+        Side::Buy => Box::new(match_side.iter()),
+        Side::Sell => Box::new(match_side.iter().rev()),
+    };
+
+    // Match at one level, stopping the walk on the first error
+    let mut failure = None;
+    let mut level_match = |price_level: &PriceLevel, quantity: u64| {
+        match_level(price_level, quantity, order_id, interact_hidden, config)
+            .map_err(|err| failure = Some(err))
+            .ok()
+    };
+
+    // Process each price level
+    'levels: for entry in price_iter {
+        let price = *entry.key();
+        // Check price limit constraint early
+        if let Some(limit) = limit_price {
+            match side {
+                Side::Buy if price > limit => break,
+                Side::Sell if price < limit => break,
+                _ => {}
+            }
+        }
+
+        // Get price level value from the entry
+        let price_level = entry.value();
+
+        // What a lit-only order may still take here: pricelevel matches the
+        // displayed quantity of a level before any slice it refills
+        let mut displayed_left = if interact_hidden {
+            u64::MAX
+        } else {
+            price_level.visible_quantity()
+        };
+
+        // --- STP pre-processing ---
+        // When STP is active, check for self-trade conflicts before matching.
+        // This is done per-price-level to handle partial fills correctly.
+        if stp_active {
+            let orders = queue_orders(price_level, config);
+            let action = check_stp_at_level(&orders, taker_user_id, config.stp_mode);
+            // Look up a maker's user_id from the snapshot rather than
+            // assuming it equals taker_user_id
+            let maker_user_id = |maker_id: Id| {
+                orders
+                    .iter()
+                    .find(|o| o.id() == maker_id)
+                    .map(|o| o.user_id())
+                    .unwrap_or(taker_user_id)
+            };
+
+            match action {
+                STPAction::NoConflict => {
+                    // No self-trade at this level; match normally below
+                }
+
+                STPAction::CancelTaker { safe_quantity } => {
+                    // Match up to safe_quantity, then cancel the taker
+                    if safe_quantity > 0 {
+                        let quantity = remaining_quantity.min(safe_quantity).min(displayed_left);
+                        let Some(price_level_match) = level_match(price_level, quantity) else {
+                            break 'levels;
+                        };
+                        process_level_match(
+                            &mut outcome,
+                            &price_level_match,
+                            &mut remaining_quantity,
+                            price,
+                            price_level,
+                            side,
+                            config,
+                            &mut empty_price_levels,
+                        );
+                    }
+                    stp_taker_cancelled = true;
+                    break;
+                }
+
+                STPAction::CancelMaker { .. }
+                    if config.stp_maker_scope == STPMakerScope::HitOnly =>
+                {
+                    // Trade up to each same-user maker the taker reaches,
+                    // cancel it and rescan the level, until the taker is
+                    // filled or no same-user maker is left. The scan follows
+                    // the level's queue order.
+                    let mut level_orders = queue_orders(price_level, config);
+                    while let Some((safe_quantity, maker_id)) =
+                        first_same_user_maker(&level_orders, taker_user_id)
+                    {
+                        if safe_quantity > 0 {
+                            let level_quantity =
+                                remaining_quantity.min(safe_quantity).min(displayed_left);
+                            let held_back = remaining_quantity - level_quantity;
+                            let Some(price_level_match) = level_match(price_level, level_quantity)
+                            else {
+                                break 'levels;
+                            };
+                            process_level_match(
+                                &mut outcome,
+                                &price_level_match,
+                                &mut remaining_quantity,
+                                price,
+                                price_level,
+                                side,
+                                config,
+                                &mut empty_price_levels,
+                            );
+                            displayed_left =
+                                displayed_left.saturating_sub(level_quantity - remaining_quantity);
+                            remaining_quantity += held_back;
+                        }
+                        // A lit-only taker that used up the displayed
+                        // quantity never reaches the maker
+                        if remaining_quantity == 0 || displayed_left == 0 {
+                            break;
+                        }
+                        if let Ok(Some(maker)) =
+                            price_level.update_order(OrderUpdate::Cancel { order_id: maker_id })
+                        {
+                            displayed_left =
+                                displayed_left.saturating_sub(maker.visible_quantity());
+                        }
+                        outcome.cancelled_makers.push(StpCancelledMaker {
+                            order_id: maker_id,
+                            user_id: taker_user_id,
+                            price,
+                        });
+                        stp_maker_cancelled = true;
+                        level_orders = queue_orders(price_level, config);
+                    }
+                    if remaining_quantity == 0 {
+                        break;
+                    }
+                    if price_level.order_count() == 0 {
+                        empty_price_levels.push(price);
+                        continue;
+                    }
+                    // Fall through to normal matching below
+                }
+
+                STPAction::CancelMaker { maker_order_ids } => {
+                    // Cancel same-user resting orders, then match normally.
+                    for maker_id in &maker_order_ids {
+                        let _ = price_level.update_order(OrderUpdate::Cancel {
+                            order_id: *maker_id,
+                        });
+                        outcome.cancelled_makers.push(StpCancelledMaker {
+                            order_id: *maker_id,
+                            user_id: maker_user_id(*maker_id),
+                            price,
+                        });
+                        stp_maker_cancelled = true;
+                    }
+                    if !interact_hidden {
+                        displayed_left = price_level.visible_quantity();
+                    }
+                    // If the level is now empty, mark for removal and continue
+                    if price_level.order_count() == 0 {
+                        empty_price_levels.push(price);
+                        continue;
+                    }
+                    // Fall through to normal matching below
+                }
+
+                STPAction::CancelBoth {
+                    safe_quantity,
+                    maker_order_id,
+                } => {
+                    // Match up to safe_quantity, cancel the maker, then cancel taker
+                    if safe_quantity > 0 {
+                        let quantity = remaining_quantity.min(safe_quantity).min(displayed_left);
+                        let Some(price_level_match) = level_match(price_level, quantity) else {
+                            break 'levels;
+                        };
+                        process_level_match(
+                            &mut outcome,
+                            &price_level_match,
+                            &mut remaining_quantity,
+                            price,
+                            price_level,
+                            side,
+                            config,
+                            &mut empty_price_levels,
+                        );
+                    }
+                    let _ = price_level.update_order(OrderUpdate::Cancel {
+                        order_id: maker_order_id,
+                    });
+                    outcome.cancelled_makers.push(StpCancelledMaker {
+                        order_id: maker_order_id,
+                        user_id: maker_user_id(maker_order_id),
+                        price,
+                    });
+                    stp_maker_cancelled = true;
+                    if price_level.order_count() == 0 {
+                        empty_price_levels.push(price);
+                    }
+                    stp_taker_cancelled = true;
+                    break;
+                }
+            }
+        }
+
+        // --- Normal matching (no STP conflict or after CancelMaker cleanup) ---
+        let level_quantity = remaining_quantity.min(displayed_left);
+        let held_back = remaining_quantity - level_quantity;
+        let Some(price_level_match) = level_match(price_level, level_quantity) else {
+            break;
+        };
+
+        process_level_match(
+            &mut outcome,
+            &price_level_match,
+            &mut remaining_quantity,
+            price,
+            price_level,
+            side,
+            config,
+            &mut empty_price_levels,
+        );
+        remaining_quantity += held_back;
+
+        // Early exit if order is fully matched
+        if remaining_quantity == 0 {
+            break;
+        }
+    }
+
+    // Batch remove empty price levels
+    for price in &empty_price_levels {
+        match_side.remove(price);
+    }
+
+    // Return the vector to the pool for reuse
+    MATCHING_POOL.with(|pool| pool.return_price_vec(empty_price_levels));
+
+    outcome.stp = (stp_taker_cancelled || stp_maker_cancelled).then_some(STPTriggered {
+        mode: config.stp_mode,
+        cancelled_taker: stp_taker_cancelled,
+        cancelled_maker: stp_maker_cancelled,
+    });
+
+    // remaining_quantity is managed by add_trade(); no manual update needed
+    outcome.failure = failure;
+    outcome
+}
+
+/// Processes match results from a single price level, updating the
+/// aggregate match outcome and bookkeeping vectors.
+///
+/// Extracted to avoid code duplication between the normal path and
+/// the STP safe-quantity pre-match path.
+#[allow(clippy::too_many_arguments)]
+fn process_level_match(
+    outcome: &mut LevelMatch,
+    price_level_match: &MatchResult,
+    remaining_quantity: &mut u64,
+    price: u128,
+    price_level: &Arc<PriceLevel>,
+    side: Side,
+    config: &MatchConfig<'_>,
+    empty_price_levels: &mut Vec<u128>,
+) {
+    // Process trades if any occurred
+    if !price_level_match.trades().as_vec().is_empty() {
+        outcome.last_trade_price = Some(price);
+
+        // Add trades to result
+        for trade in price_level_match.trades().as_vec() {
+            // add_trade returns Result in v0.7; ignore error since
+            // pricelevel already validated the quantities during matching
+            let _ = outcome.match_result.add_trade(*trade);
+        }
+
+        // Notify price level changes
+        if let Some(on_level_change) = config.on_level_change {
+            on_level_change(PriceLevelChangedEvent {
+                side: side.opposite(),
+                price: price_level.price(),
+                quantity: price_level.visible_quantity(),
+            });
+        }
+    }
+
+    // Collect filled orders for batch removal
+    for &filled_order_id in price_level_match.filled_order_ids() {
+        outcome.match_result.add_filled_order_id(filled_order_id);
+    }
+
+    // Update remaining quantity
+    *remaining_quantity = price_level_match.remaining_quantity();
+
+    // Check if price level is empty and mark for removal
+    if price_level.order_count() == 0 {
+        empty_price_levels.push(price);
+    }
+}
+
+/// Match up to `quantity` for `order_id` at `price_level` under
+/// `config.iceberg_refill_policy`, keeping `config.arrivals` in step.
+fn match_level(
+    price_level: &PriceLevel,
+    quantity: u64,
+    order_id: Id,
+    interact_hidden: bool,
+    config: &MatchConfig<'_>,
+) -> Result<MatchResult, OrderBookError> {
+    if config.iceberg_refill_policy == IcebergRefillPolicy::KeepPriority
+        && price_level.hidden_quantity() > 0
+    {
+        return match_in_place(
+            price_level,
+            queue_orders(price_level, config),
+            quantity,
+            order_id,
+            config.transaction_id_generator,
+            config.arrivals,
+            interact_hidden,
+        );
+    }
+
+    let price_level_match =
+        price_level.match_order(quantity, order_id, config.transaction_id_generator);
+    // pricelevel pushes a maker left resting back onto the level
+    if let Some(arrivals) = config.arrivals {
+        for trade in price_level_match.trades().as_vec() {
+            let maker_id = trade.maker_order_id();
+            if !price_level_match.filled_order_ids().contains(&maker_id) {
+                arrivals.requeue(maker_id);
+            }
+        }
+    }
+    Ok(price_level_match)
+}
+
+/// Returns the orders resting at `price_level` in queue order, or in
+/// timestamp order when `config` does not track it.
+fn queue_orders(price_level: &PriceLevel, config: &MatchConfig<'_>) -> Vec<Arc<OrderType<()>>> {
+    let mut orders = price_level.snapshot_orders();
+    if let Some(arrivals) = config.arrivals {
+        arrivals.sort(&mut orders);
+    }
+    orders
+}
@@ -0,0 +1,194 @@
+//! Minimum resting time before an order can be cancelled.
+//!
+//! To discourage quote flickering, a venue can require an order to rest for
+//! a minimum time before its owner may cancel it. With
+//! [`OrderBook::set_min_rest_time_ns`] set, [`OrderBook::cancel_order`] and
+//! cancels through [`OrderBook::update_order`] compare the order's stored
+//! timestamp with the book's clock and reject a cancel that comes too early.
+//! Updates that re-add the order (price updates and replaces) take it out of
+//! the book as a cancel does and are held back the same way, so a quote
+//! cannot flicker by repricing; in-place quantity updates are not. Cancels
+//! and reprices the book issues itself, such as expiry, mass cancels,
+//! self-trade prevention and pegged order repricing, are not held back.
+//!
+//! Order timestamps are in milliseconds, so the elapsed time is measured at
+//! millisecond resolution. The check is only as meaningful as the order
+//! timestamps: stamp orders on entry, e.g. through the sequencer, rather
+//! than trusting client clocks.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::Id;
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the minimum time in nanoseconds an order must rest before a
+    /// user cancel is accepted. `None` allows immediate cancels (default).
+    pub fn set_min_rest_time_ns(&mut self, min_rest_time_ns: Option<u64>) {
+        trace!(
+            "Order book {}: Setting minimum rest time to {:?} ns",
+            self.symbol, min_rest_time_ns
+        );
+        self.min_rest_time_ns = min_rest_time_ns;
+    }
+
+    /// Returns the minimum time in nanoseconds an order must rest before a
+    /// user cancel is accepted.
+    #[must_use]
+    pub fn min_rest_time_ns(&self) -> Option<u64> {
+        self.min_rest_time_ns
+    }
+
+    /// Check a user cancel of `order_id` against the minimum rest time.
+    /// Orders that are not resting pass, so the cancel reports them as it
+    /// would without a minimum.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::MinRestTimeNotMet`] if the order has rested
+    /// for less than the minimum rest time.
+    pub(super) fn check_min_rest_time(&self, order_id: Id) -> Result<(), OrderBookError> {
+        let Some(required) = self.min_rest_time_ns else {
+            return Ok(());
+        };
+        let Some(order) = self.get_order(order_id) else {
+            return Ok(());
+        };
+        let elapsed = (self.min_rest_clock)()
+            .saturating_sub(order.timestamp())
+            .saturating_mul(1_000_000);
+        if elapsed < required {
+            trace!(
+                "Order book {}: Rejecting cancel of order {} after {} ns of {} ns",
+                self.symbol, order_id, elapsed, required
+            );
+            return Err(OrderBookError::MinRestTimeNotMet { elapsed, required });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{
+        Hash32, OrderType, OrderUpdate, Price, Quantity, Side, TimeInForce, TimestampMs,
+    };
+
+    const MIN_REST_NS: u64 = 60_000_000_000;
+    const NOW_MS: u64 = 1_000_000;
+
+    fn test_clock() -> u64 {
+        NOW_MS
+    }
+
+    fn book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.min_rest_clock = test_clock;
+        book.set_min_rest_time_ns(Some(MIN_REST_NS));
+        book
+    }
+
+    /// A bid stamped `age_ms` before the book's clock.
+    fn rest(book: &OrderBook<()>, age_ms: u64) -> Id {
+        let id = Id::new_uuid();
+        book.add_order(OrderType::Standard {
+            id,
+            price: Price::new(100),
+            quantity: Quantity::new(10),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(NOW_MS - age_ms),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_cancel_before_min_rest_time_is_rejected() {
+        let book = book();
+        assert_eq!(book.min_rest_time_ns(), Some(MIN_REST_NS));
+        let id = rest(&book, 1_000);
+
+        for attempt in [
+            book.cancel_order(id).map(|_| ()),
+            book.update_order(OrderUpdate::Cancel { order_id: id })
+                .map(|_| ()),
+        ] {
+            assert!(matches!(
+                attempt,
+                Err(OrderBookError::MinRestTimeNotMet {
+                    elapsed: 1_000_000_000,
+                    required: MIN_REST_NS
+                })
+            ));
+        }
+        assert!(book.get_order(id).is_some());
+
+        // Cancels the book issues itself are not held back
+        assert_eq!(book.cancel_all_orders().cancelled_count(), 1);
+    }
+
+    #[test]
+    fn test_cancel_after_min_rest_time_is_accepted() {
+        let mut book = book();
+        let id = rest(&book, 61_000);
+        assert!(book.cancel_order(id).unwrap().is_some());
+
+        let id = rest(&book, 0);
+        book.set_min_rest_time_ns(None);
+        assert!(book.cancel_order(id).unwrap().is_some());
+        // Unknown orders are reported as without a minimum
+        book.set_min_rest_time_ns(Some(MIN_REST_NS));
+        assert!(book.cancel_order(Id::new_uuid()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reprice_before_min_rest_time_is_rejected() {
+        let book = book();
+        let id = rest(&book, 1_000);
+
+        for update in [
+            OrderUpdate::UpdatePrice {
+                order_id: id,
+                new_price: Price::new(90),
+            },
+            OrderUpdate::UpdatePriceAndQuantity {
+                order_id: id,
+                new_price: Price::new(90),
+                new_quantity: Quantity::new(5),
+            },
+            OrderUpdate::Replace {
+                order_id: id,
+                price: Price::new(90),
+                quantity: Quantity::new(5),
+                side: Side::Buy,
+            },
+        ] {
+            assert!(matches!(
+                book.update_order(update),
+                Err(OrderBookError::MinRestTimeNotMet { .. })
+            ));
+        }
+        assert_eq!(book.best_bid(), Some(100));
+
+        // A quantity update keeps the order resting and is allowed
+        book.update_order(OrderUpdate::UpdateQuantity {
+            order_id: id,
+            new_quantity: Quantity::new(5),
+        })
+        .unwrap();
+
+        let id = rest(&book, 61_000);
+        book.update_order(OrderUpdate::UpdatePrice {
+            order_id: id,
+            new_price: Price::new(110),
+        })
+        .unwrap();
+        assert_eq!(book.best_bid(), Some(110));
+    }
+}
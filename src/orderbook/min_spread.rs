@@ -0,0 +1,150 @@
+//! Minimum spread enforcement for thin markets.
+//!
+//! With [`OrderBook::set_min_spread_ticks`] configured, an order that would
+//! rest as the new best price on its side is rejected with
+//! [`OrderBookError::SpreadTooTight`] if it leaves less than the minimum
+//! spread to the opposite best. Orders that cross the opposite best are not
+//! affected: they trade rather than narrow the spread.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::Side;
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set or clear the minimum spread, in ticks, that a new resting order
+    /// may leave to the opposite best price.
+    ///
+    /// One tick is the configured tick size, or 1 when none is set.
+    pub fn set_min_spread_ticks(&mut self, ticks: Option<u32>) {
+        self.min_spread_ticks = ticks;
+        trace!(
+            "Order book {}: Set minimum spread to {:?} ticks",
+            self.symbol, ticks
+        );
+    }
+
+    /// Returns the minimum spread in ticks, if configured.
+    #[must_use]
+    pub fn min_spread_ticks(&self) -> Option<u32> {
+        self.min_spread_ticks
+    }
+
+    /// Reject an order at `price` on `side` that would become the best price
+    /// on its side with less than the minimum spread to the opposite best.
+    pub(super) fn check_min_spread(
+        &self,
+        order_id: pricelevel::Id,
+        price: u128,
+        side: Side,
+    ) -> Result<(), OrderBookError> {
+        let Some(ticks) = self.min_spread_ticks.filter(|&t| t > 0) else {
+            return Ok(());
+        };
+        let tick = self.tick_size_at(price).unwrap_or(1);
+        let min_spread = tick.saturating_mul(u128::from(ticks));
+
+        let (opposite_price, own_best, spread) = match side {
+            Side::Buy => {
+                let Some(ask) = self.best_ask() else {
+                    return Ok(());
+                };
+                (ask, self.best_bid(), ask.checked_sub(price))
+            }
+            Side::Sell => {
+                let Some(bid) = self.best_bid() else {
+                    return Ok(());
+                };
+                (bid, self.best_ask(), price.checked_sub(bid))
+            }
+        };
+
+        // Crossing or locking orders trade instead of resting
+        let Some(spread) = spread.filter(|&s| s > 0) else {
+            return Ok(());
+        };
+        let improves = match (side, own_best) {
+            (_, None) => true,
+            (Side::Buy, Some(bid)) => price > bid,
+            (Side::Sell, Some(ask)) => price < ask,
+        };
+        if !improves || spread >= min_spread {
+            return Ok(());
+        }
+
+        self.reject(
+            order_id,
+            OrderBookError::SpreadTooTight {
+                price,
+                side,
+                opposite_price,
+                min_spread,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_tick_size(5);
+        book.set_min_spread_ticks(Some(2));
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 120, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_order_tightening_spread_below_minimum_is_rejected() {
+        let book = book();
+        assert_eq!(book.min_spread_ticks(), Some(2));
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 115, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OrderBookError::SpreadTooTight {
+                price: 115,
+                side: Side::Buy,
+                opposite_price: 120,
+                min_spread: 10,
+            }
+        ));
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 105, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap_err();
+        assert!(matches!(err, OrderBookError::SpreadTooTight { .. }));
+        assert_eq!(book.best_bid(), Some(100));
+        assert_eq!(book.best_ask(), Some(120));
+    }
+
+    #[test]
+    fn test_order_leaving_sufficient_spread_is_accepted() {
+        let book = book();
+        book.add_limit_order(Id::new_uuid(), 110, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.best_bid(), Some(110));
+
+        // Joining or resting behind the best is unaffected
+        book.add_limit_order(Id::new_uuid(), 110, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 125, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // A crossing order trades instead of narrowing the spread
+        book.add_limit_order(Id::new_uuid(), 120, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.best_ask(), Some(125));
+    }
+}
@@ -1,6 +1,8 @@
 //! OrderBook implementation for managing multiple price levels and order matching.
 
 pub mod book;
+/// Chained configuration of new order books.
+pub mod builder;
 pub mod error;
 /// Implied volatility calculation from order book prices.
 pub mod implied_volatility;
@@ -10,6 +12,8 @@ pub mod iterators;
 pub mod manager;
 /// Market impact simulation and liquidity analysis.
 pub mod market_impact;
+/// Optional instrumentation hooks around order matching.
+pub mod match_observer;
 pub mod matching;
 /// Aggregate statistics for order book analysis.
 pub mod statistics;
@@ -17,6 +21,9 @@ pub mod statistics;
 /// Self-Trade Prevention (STP) types and logic.
 pub mod stp;
 
+/// Auction accumulation state and single-price uncross.
+pub mod auction;
+
 /// Price level change events for real-time order book updates.
 pub mod book_change_event;
 mod cache;
@@ -30,6 +37,9 @@ mod tests;
 /// Enhanced trade result that includes symbol information
 pub mod trade;
 
+/// Locked/crossed market prevention against an external reference BBO.
+pub mod external_bbo;
+
 /// Fee schedule implementation for trading fees
 pub mod fees;
 
@@ -39,6 +49,12 @@ pub mod mass_cancel;
 /// Order state machine for explicit lifecycle tracking.
 pub mod order_state;
 
+/// Deterministic pro-rata allocation with explicit remainder rules.
+pub mod pro_rata;
+
+/// Reference price policy, price bands, and stop trigger reference.
+pub mod reference_price;
+
 /// Pluggable event serialization for NATS publishers and consumers.
 pub mod serialization;
 
@@ -57,32 +73,127 @@ pub mod repricing;
 /// Sequencer subsystem: types, journal trait, and file-based journal.
 pub mod sequencer;
 
+/// Per-user resting quantity limits.
+pub mod user_limits;
+
+/// Conversion between decimal and integer prices.
+pub mod price_scale;
+
+/// Rounding of off-tick prices on order entry.
+pub mod tick_rounding;
+
+/// Cumulative traded volume and turnover since the session open.
+pub mod session;
+
+/// Minimum spread enforcement for new resting orders.
+pub mod min_spread;
+
+/// What-if execution of orders against a scratch copy of the book.
+pub mod simulation;
+
+/// Parallel traversal of price levels (requires the `rayon` feature).
+#[cfg(feature = "rayon")]
+pub mod parallel;
+
+/// Fill probability estimates from queue position and recent trade flow.
+pub mod fill_probability;
+
+/// Arrival order of resting orders within their price levels.
+pub mod arrival;
+/// Bulk loading of a static list of resting orders.
+pub mod bulk_load;
+/// Orders submitted automatically when another order fully fills.
+pub mod contingent;
+/// Detection of a crossed book after each command.
+pub mod crossed_check;
+/// Per-user accrual of fees for settlement.
+pub mod fee_ledger;
+/// Fixed-point prices for deterministic analytics.
+pub mod fixed_price;
+/// Queue position of iceberg and reserve orders after a refill.
+pub mod iceberg_refill;
+/// Structural checks of the book for tests and debugging.
+pub mod invariants;
+/// Per-price-level order limits.
+pub mod level_capacity;
+/// Price levels changed by a single operation.
+pub mod level_changes;
+/// Lifetime counts of orders by outcome.
+pub mod lifetime_counters;
+/// Rejection of orders crossing far beyond the opposite best price.
+pub mod marketable_guard;
+/// Minimum resting time before an order can be cancelled.
+pub mod min_rest_time;
+/// Handling of modifications that reprice an order through the book.
+pub mod modify_cross;
+/// Market orders parked until the opposite side has liquidity.
+pub mod parked;
+/// Lock-free publication of immutable snapshots for readers.
+pub mod published;
+/// Reduce-only orders capped to the quantity they can reduce.
+pub mod reduce_only;
+/// Tick sizes that vary by price band.
+pub mod tick_schedule;
+/// Handling of out-of-band client timestamps on order entry.
+pub mod timestamp_policy;
+/// Off-book trailing stops that follow the reference price.
+pub mod trailing_stop;
+
+pub use arrival::{ArrivalOrder, QueuePlace};
+pub use auction::{AuctionResult, TradingState};
 pub use book::OrderBook;
+pub use builder::OrderBookBuilder;
+pub use contingent::TriggeredContingent;
+pub use crossed_check::CrossedBookListener;
 pub use error::{ManagerError, OrderBookError};
-pub use fees::FeeSchedule;
+pub use external_bbo::ExternalBboPolicy;
+pub use fee_ledger::FeeRounding;
+pub use fees::{FeeSchedule, FeeTier, OrderRole};
+pub use fixed_price::FixedPrice;
+pub use iceberg_refill::IcebergRefillPolicy;
 pub use implied_volatility::{
     BlackScholes, IVConfig, IVError, IVParams, IVQuality, IVResult, OptionType, PriceSource,
     SolverConfig,
 };
 pub use iterators::LevelInfo;
+pub use level_changes::LevelChangeSet;
+pub use lifetime_counters::LifetimeCounters;
 pub use market_impact::{MarketImpact, OrderSimulation};
 pub use mass_cancel::MassCancelResult;
+pub use match_observer::{MatchObserver, MatchOrderKind};
+pub use matching::{
+    IncomingOrder, LevelMatch, MatchConfig, StpCancelledMaker, match_against_levels,
+};
+pub use modifications::{
+    AddOrderResult, AddOutcome, CancelOutcome, RemainderOutcome, RemainderPolicy,
+};
+pub use modify_cross::ModifyCrossPolicy;
 #[cfg(feature = "nats")]
 pub use nats::NatsTradePublisher;
 #[cfg(feature = "nats")]
 pub use nats_book_change::{BookChangeBatch, BookChangeEntry, NatsBookChangePublisher};
 pub use order_state::{CancelReason, OrderStateListener, OrderStateTracker, OrderStatus};
+pub use parked::{MarketOrderEmptyBookPolicy, ParkedMarketOrder};
+pub use pro_rata::{ProRataRemainderRule, allocate_pro_rata};
+pub use published::SnapshotPublishPolicy;
+pub use reduce_only::ReduceOnlyPolicy;
+pub use reference_price::ReferencePricePolicy;
 #[cfg(feature = "special_orders")]
 pub use repricing::{RepricingOperations, RepricingResult, SpecialOrderTracker};
-#[cfg(feature = "journal")]
-pub use sequencer::FileJournal;
 pub use sequencer::journal::{Journal, JournalEntry};
+#[cfg(feature = "journal")]
+pub use sequencer::{FileJournal, MmapJournal};
 pub use sequencer::{JournalError, SequencerCommand, SequencerEvent, SequencerResult};
 #[cfg(feature = "bincode")]
 pub use serialization::BincodeEventSerializer;
 pub use serialization::{EventSerializer, JsonEventSerializer, SerializationError};
+pub use simulation::FillSimulation;
 pub use snapshot::{
-    EnrichedSnapshot, MetricFlags, ORDERBOOK_SNAPSHOT_FORMAT_VERSION, OrderBookSnapshot,
-    OrderBookSnapshotPackage,
+    AggregatedLevel, AggregatedSnapshot, ColumnarSnapshot, EnrichedSnapshot, MetricFlags,
+    ORDERBOOK_SNAPSHOT_FORMAT_VERSION, OrderBookSnapshot, OrderBookSnapshotPackage, PackageDelta,
+    Quote, RestoreScope, SnapshotOptions,
 };
 pub use statistics::{DepthStats, DistributionBin};
+pub use tick_rounding::{RoundingMode, TickRounding};
+pub use timestamp_policy::TimestampPolicy;
+pub use trailing_stop::{PendingTrailingStop, TrailAmount, TrailingStopStatus};
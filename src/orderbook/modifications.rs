@@ -1,12 +1,134 @@
 use crate::orderbook::book::OrderBook;
 use crate::orderbook::book_change_event::PriceLevelChangedEvent;
 use crate::orderbook::error::OrderBookError;
+use crate::orderbook::match_observer::MatchOrderKind;
 use crate::orderbook::order_state::{CancelReason, OrderStatus};
+use crate::orderbook::stp::STPTriggered;
 use crate::orderbook::trade::TradeResult;
-use pricelevel::{Id, OrderType, OrderUpdate, PriceLevel, Quantity, Side};
+use crate::utils::current_time_millis;
+use pricelevel::{
+    Hash32, Id, MatchResult, OrderType, OrderUpdate, PriceLevel, Quantity, Side, TimestampMs, Trade,
+};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::trace;
 
+/// What to do with the unfilled remainder of a limit order that crosses the
+/// book but cannot be fully filled.
+///
+/// This is independent of the order's [`pricelevel::TimeInForce`]: IOC and
+/// FOK orders never rest regardless of the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RemainderPolicy {
+    /// Rest the remainder at the order's limit price (default behaviour).
+    #[default]
+    Rest,
+    /// Cancel the remainder after matching (one-shot behaviour).
+    Cancel,
+}
+
+/// Final disposition of an order's unfilled remainder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemainderOutcome {
+    /// The order was fully filled; nothing remained.
+    Filled,
+    /// The remainder rests in the book.
+    Rested {
+        /// Quantity left resting in the book.
+        quantity: u64,
+    },
+    /// The remainder was cancelled under [`RemainderPolicy::Cancel`].
+    Cancelled {
+        /// Quantity that was cancelled instead of resting.
+        quantity: u64,
+    },
+}
+
+/// Outcome of an idempotent cancel request.
+///
+/// Unlike [`OrderBook::cancel_order`], an order that is no longer in the book
+/// (never existed, already filled, or already cancelled) is reported as
+/// [`CancelOutcome::AlreadyGone`] rather than treated as a failure, so
+/// clients can safely retry cancels.
+#[derive(Debug, Clone)]
+pub enum CancelOutcome<T> {
+    /// The order was resting and has been cancelled.
+    Cancelled(Arc<OrderType<T>>),
+    /// The order was not in the book; nothing was changed.
+    AlreadyGone,
+    /// The cancel failed for a reason other than the order being absent.
+    Error(OrderBookError),
+}
+
+impl<T> CancelOutcome<T> {
+    /// Returns `true` unless the cancel failed with an error.
+    ///
+    /// Both a fresh cancellation and an already-gone order count as success.
+    #[must_use]
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, CancelOutcome::Error(_))
+    }
+}
+
+/// Result of adding an order with an explicit [`RemainderPolicy`].
+#[derive(Debug, Clone)]
+pub struct AddOrderResult<T> {
+    /// The resting order when the remainder rests, otherwise the submitted order.
+    pub order: Arc<OrderType<T>>,
+    /// Trades executed while matching the incoming order.
+    pub match_result: MatchResult,
+    /// What happened to the unfilled remainder.
+    pub remainder: RemainderOutcome,
+    /// What self-trade prevention did while matching, if it triggered.
+    pub stp: Option<STPTriggered>,
+    /// Fills of parked market orders that traded against the order once it
+    /// rested, in execution order; see
+    /// [`MarketOrderEmptyBookPolicy`](super::MarketOrderEmptyBookPolicy).
+    pub parked_fills: Vec<MatchResult>,
+}
+
+/// Disposition of an order added through [`OrderBook::add_order_detailed`].
+///
+/// `filled_quantity + rested_quantity + cancelled_quantity` is the order's
+/// total quantity, so a caller reconciling state can tell a fully matched
+/// order from one whose remainder was cancelled without resting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddOutcome {
+    /// Identifier of the added order.
+    pub order_id: Id,
+    /// Quantity left resting in the book.
+    pub rested_quantity: u64,
+    /// Quantity filled while matching.
+    pub filled_quantity: u64,
+    /// Remainder cancelled instead of resting, e.g. by self-trade prevention.
+    pub cancelled_quantity: u64,
+    /// Trades executed while matching, in execution order.
+    pub fills: Vec<Trade>,
+    /// What self-trade prevention did while matching, if it triggered.
+    pub stp: Option<STPTriggered>,
+}
+
+impl<T> From<AddOrderResult<T>> for AddOutcome {
+    fn from(result: AddOrderResult<T>) -> Self {
+        let fills = result.match_result.trades().as_vec().clone();
+        let filled_quantity = fills.iter().map(|trade| trade.quantity().as_u64()).sum();
+        let (rested_quantity, cancelled_quantity) = match result.remainder {
+            RemainderOutcome::Filled => (0, 0),
+            RemainderOutcome::Rested { quantity } => (quantity, 0),
+            RemainderOutcome::Cancelled { quantity } => (0, quantity),
+        };
+        Self {
+            order_id: result.match_result.order_id(),
+            rested_quantity,
+            filled_quantity,
+            cancelled_quantity,
+            fills,
+            stp: result.stp,
+        }
+    }
+}
+
 /// A trait to abstract quantity access and modification for different order types.
 pub trait OrderQuantity<T = ()> {
     /// Returns the primary quantity used for display or simple matching.
@@ -118,10 +240,53 @@ where
     T: Clone + Send + Sync + Default + 'static,
 {
     /// Update an order's price and/or quantity
+    ///
+    /// An update that reprices the order re-enters it at the new price;
+    /// see [`ModifyCrossPolicy`](super::modify_cross::ModifyCrossPolicy) for
+    /// what happens when that price crosses the book. Cancels and re-adding
+    /// updates are held to the minimum rest time; see
+    /// [`Self::set_min_rest_time_ns`].
     pub fn update_order(
         &self,
         update: OrderUpdate,
     ) -> Result<Option<Arc<OrderType<T>>>, OrderBookError> {
+        self.update_order_with_fills(update)
+            .map(|result| result.map(|result| result.order))
+    }
+
+    /// Update an order as [`Self::update_order`] does and report the fills
+    /// of a repriced order that crossed the book.
+    ///
+    /// Updates that do not reprice the order never trade: a quantity update
+    /// reports the order as rested and a cancel as cancelled, both with an
+    /// empty match result. Returns `None` if the order was not found.
+    ///
+    /// # Errors
+    /// Same as [`Self::update_order`], including
+    /// [`OrderBookError::PriceCrossing`] when a crossing reprice is refused
+    /// under [`ModifyCrossPolicy::Reject`](super::modify_cross::ModifyCrossPolicy::Reject).
+    pub fn update_order_with_fills(
+        &self,
+        update: OrderUpdate,
+    ) -> Result<Option<AddOrderResult<T>>, OrderBookError> {
+        // Re-adding amends take the order out of the book like a cancel
+        if let OrderUpdate::UpdatePrice { order_id, .. }
+        | OrderUpdate::UpdatePriceAndQuantity { order_id, .. }
+        | OrderUpdate::Replace { order_id, .. }
+        | OrderUpdate::Cancel { order_id } = update
+        {
+            self.check_min_rest_time(order_id)?;
+        }
+        self.apply_order_update(update)
+    }
+
+    /// Apply `update` as [`Self::update_order_with_fills`] does, without
+    /// the minimum rest time, for updates the book issues itself.
+    pub(super) fn apply_order_update(
+        &self,
+        update: OrderUpdate,
+    ) -> Result<Option<AddOrderResult<T>>, OrderBookError> {
+        let _write = self.write_scope();
         self.cache.invalidate();
         trace!("Order book {}: Updating order {:?}", self.symbol, update);
         match update {
@@ -148,9 +313,6 @@ where
                         return Ok(None); // Order not found
                     };
 
-                    // Cancel the original order
-                    self.cancel_order(order_id)?;
-
                     // Create a new order with the updated price
                     let mut new_order = original_order;
 
@@ -164,9 +326,9 @@ where
                         OrderType::MarketToLimit { price, .. } => *price = new_price,
                         OrderType::ReserveOrder { price, .. } => *price = new_price,
                     }
+                    self.check_modify_cross(&new_order)?;
 
-                    // Add the updated order
-                    let result = self.add_order(new_order)?;
+                    let result = self.amend_by_readd(order_id, new_order)?;
                     Ok(Some(result))
                 } else {
                     Ok(None) // Order not found
@@ -210,6 +372,8 @@ where
                                     quantity: price_level.visible_quantity(),
                                 })
                             }
+                            self.level_change_recorder.touch(side, price, true);
+                            self.arrivals.push_back(order_id);
                             result = Some(Arc::new(self.convert_from_unit_type(&order)));
                         }
 
@@ -220,11 +384,17 @@ where
                     if is_empty {
                         price_levels.remove(&price);
                         self.order_locations.remove(&order_id);
+                        self.arrivals.remove(order_id);
+                        self.order_extra_fields.remove(&order_id);
+                        self.contingent_orders.remove(&order_id);
                         self.untrack_order_by_id(&order_id);
                     }
 
                     self.cache.invalidate();
-                    Ok(result)
+                    Ok(result.map(|order| {
+                        let quantity = order.total_quantity();
+                        Self::untraded_update(order, RemainderOutcome::Rested { quantity })
+                    }))
                 } else {
                     Ok(None) // Order not found
                 }
@@ -247,9 +417,6 @@ where
                         return Ok(None); // Order not found
                     };
 
-                    // Cancel the original order
-                    self.cancel_order(order_id)?;
-
                     // Create a new order with the updated price and quantity
                     let mut new_order = original_order;
 
@@ -266,9 +433,9 @@ where
 
                     // Update the quantity using the trait method
                     new_order.set_quantity(new_quantity.as_u64());
+                    self.check_modify_cross(&new_order)?;
 
-                    // Add the updated order
-                    let result = self.add_order(new_order)?;
+                    let result = self.amend_by_readd(order_id, new_order)?;
                     Ok(Some(result))
                 } else {
                     Ok(None) // Order not found
@@ -299,6 +466,7 @@ where
                             let price_level = entry.value();
                             let cancel_update = OrderUpdate::Cancel { order_id };
                             let result = price_level.update_order(cancel_update);
+                            self.level_change_recorder.touch(side, price, true);
                             // notify price level changes
                             if let Some(ref listener) = self.price_level_changed_listener
                                 && let Ok(updated_order) = result
@@ -315,6 +483,9 @@ where
 
                         // Remove from order locations tracking
                         self.order_locations.remove(&order_id);
+                        self.arrivals.remove(order_id);
+                        self.order_extra_fields.remove(&order_id);
+                        self.contingent_orders.remove(&order_id);
                         // Remove from user_orders index
                         self.untrack_order_by_id(&order_id);
                     }
@@ -324,7 +495,10 @@ where
                         price_levels.remove(&price);
                     }
 
-                    Ok(result)
+                    Ok(result.map(|order| {
+                        let quantity = order.total_quantity();
+                        Self::untraded_update(order, RemainderOutcome::Cancelled { quantity })
+                    }))
                 } else {
                     Ok(None) // Order not found
                 }
@@ -431,11 +605,9 @@ where
                         }
                     }
 
-                    // Cancel the original order
-                    self.cancel_order(order_id)?;
+                    self.check_modify_cross(&new_order)?;
 
-                    // Add the new order
-                    let result = self.add_order(new_order)?;
+                    let result = self.amend_by_readd(order_id, new_order)?;
                     Ok(Some(result))
                 } else {
                     Ok(None) // Original order not found
@@ -444,14 +616,401 @@ where
         }
     }
 
+    /// Amend resting order `order_id` by cancelling it and adding
+    /// `new_order`, which carries the same id, in its place.
+    ///
+    /// An order that rests again at its old price and side keeps its place
+    /// in the queue while the level lasts, as pricelevel keeps it there.
+    /// Its contingent orders keep waiting on it if it rests again, and are
+    /// submitted if the amend fills it completely. The lifetime counters
+    /// count neither the cancel nor the add, only an outcome of the re-add
+    /// that ends the order.
+    ///
+    /// `new_order` is checked against the tick size, order size, user
+    /// limit, price band and marketable guard before the order is
+    /// cancelled, so such a rejection leaves it untouched. If the re-add
+    /// fails later, the original order is added back with its state and
+    /// contingent orders, at the back of its level.
+    fn amend_by_readd(
+        &self,
+        order_id: Id,
+        new_order: OrderType<T>,
+    ) -> Result<AddOrderResult<T>, OrderBookError> {
+        let original = self.get_order(order_id);
+        if let Some(original) = &original {
+            self.check_amend(original, &new_order)?;
+        }
+        let status = self
+            .order_state_tracker
+            .as_ref()
+            .and_then(|tracker| tracker.get(order_id));
+        let location = self.order_locations.get(&order_id).map(|val| *val);
+        let place = self.arrivals.remove(order_id);
+        let contingents = self.contingent_orders.remove(&order_id);
+        self.cancel_order_counted(order_id, CancelReason::UserRequested, false)?;
+        let level_kept = location.is_some_and(|(price, side)| match side {
+            Side::Buy => self.bids.contains_key(&price),
+            Side::Sell => self.asks.contains_key(&price),
+        });
+        let restore_place = || {
+            if let Some(place) = place
+                && level_kept
+                && self.order_locations.get(&order_id).map(|val| *val) == location
+            {
+                self.arrivals.restore(order_id, place);
+            }
+        };
+
+        let result = match self.add_order_counted(new_order, RemainderPolicy::Rest, false) {
+            Ok(result) => result,
+            Err(error) => {
+                // Put the original back; the book is as it was before the cancel
+                if let Some(original) = original
+                    && self
+                        .add_order_counted(
+                            Arc::unwrap_or_clone(original),
+                            RemainderPolicy::Rest,
+                            false,
+                        )
+                        .is_ok()
+                {
+                    restore_place();
+                    if let Some(status) = status {
+                        self.track_state_uncounted(order_id, status);
+                    }
+                    if let Some((_, orders)) = contingents {
+                        self.contingent_orders.insert(order_id, orders);
+                    }
+                }
+                return Err(error);
+            }
+        };
+        restore_place();
+        if let Some((_, orders)) = contingents {
+            match result.remainder {
+                RemainderOutcome::Rested { .. } => {
+                    self.contingent_orders.insert(order_id, orders);
+                }
+                RemainderOutcome::Filled => self.submit_contingents(order_id, orders),
+                RemainderOutcome::Cancelled { .. } => {}
+            }
+        }
+        Ok(result)
+    }
+
+    /// Run the checks of the add path that have no side effects on
+    /// `new_order`, which is to replace resting order `original`.
+    fn check_amend(
+        &self,
+        original: &OrderType<T>,
+        new_order: &OrderType<T>,
+    ) -> Result<(), OrderBookError> {
+        let price = new_order.price().as_u128();
+        // With tick rounding configured the add moves the price instead
+        if self.tick_rounding.is_none()
+            && let Some(tick_size) = self.tick_size_at(price)
+            && !price.is_multiple_of(tick_size)
+        {
+            return Err(OrderBookError::InvalidTickSize { price, tick_size });
+        }
+        self.check_order_size(new_order)?;
+        self.check_user_limit_replacing(new_order, Some(original))?;
+        self.check_price_band(price)?;
+        if !self.is_auction() {
+            self.check_marketable_guard(price, new_order.side())?;
+        }
+        Ok(())
+    }
+
+    /// Check the quantity of `order` against the lot size and the minimum
+    /// and maximum order size. For iceberg orders, the visible and hidden
+    /// quantities are checked against the lot size individually.
+    fn check_order_size(&self, order: &OrderType<T>) -> Result<(), OrderBookError> {
+        if let Some(lot) = self.lot_size
+            && lot > 0
+        {
+            match order {
+                OrderType::IcebergOrder {
+                    visible_quantity,
+                    hidden_quantity,
+                    ..
+                } => {
+                    if visible_quantity.as_u64() % lot != 0 {
+                        return Err(OrderBookError::InvalidLotSize {
+                            quantity: visible_quantity.as_u64(),
+                            lot_size: lot,
+                        });
+                    }
+                    if hidden_quantity.as_u64() % lot != 0 {
+                        return Err(OrderBookError::InvalidLotSize {
+                            quantity: hidden_quantity.as_u64(),
+                            lot_size: lot,
+                        });
+                    }
+                }
+                _ => {
+                    if order.total_quantity() % lot != 0 {
+                        return Err(OrderBookError::InvalidLotSize {
+                            quantity: order.total_quantity(),
+                            lot_size: lot,
+                        });
+                    }
+                }
+            }
+        }
+
+        let qty = order.total_quantity();
+        if let Some(min) = self.min_order_size
+            && qty < min
+        {
+            return Err(OrderBookError::OrderSizeOutOfRange {
+                quantity: qty,
+                min: Some(min),
+                max: self.max_order_size,
+            });
+        }
+        if let Some(max) = self.max_order_size
+            && qty > max
+        {
+            return Err(OrderBookError::OrderSizeOutOfRange {
+                quantity: qty,
+                min: self.min_order_size,
+                max: Some(max),
+            });
+        }
+        Ok(())
+    }
+
+    /// The result of an update that did not match.
+    fn untraded_update(order: Arc<OrderType<T>>, remainder: RemainderOutcome) -> AddOrderResult<T> {
+        AddOrderResult {
+            match_result: MatchResult::new(order.id(), 0),
+            order,
+            remainder,
+            stp: None,
+            parked_fills: Vec::new(),
+        }
+    }
+
     /// Cancel an order by ID.
     ///
     /// Tracks the cancellation as `CancelReason::UserRequested` in the
     /// order state tracker (if configured).
+    ///
+    /// For iceberg and reserve orders both the visible and the remaining
+    /// hidden quantity are removed, including after a refill, and the order
+    /// is dropped from every index.
     pub fn cancel_order(&self, order_id: Id) -> Result<Option<Arc<OrderType<T>>>, OrderBookError> {
+        self.check_min_rest_time(order_id)?;
         self.cancel_order_with_reason(order_id, CancelReason::UserRequested)
     }
 
+    /// Cancel an order by ID on behalf of `requesting_user`.
+    ///
+    /// Unlike [`cancel_order`](Self::cancel_order), the order must exist and
+    /// be owned by the requesting user, so in a multi-user book one user
+    /// cannot cancel another user's order by guessing its id.
+    ///
+    /// # Errors
+    /// - [`OrderBookError::OrderNotFound`] if the order is not resting in
+    ///   the book
+    /// - [`OrderBookError::Unauthorized`] if the order belongs to a
+    ///   different user
+    pub fn cancel_order_as(
+        &self,
+        order_id: Id,
+        requesting_user: Hash32,
+    ) -> Result<Arc<OrderType<T>>, OrderBookError> {
+        let not_found = || OrderBookError::OrderNotFound(order_id.to_string());
+        let order = self.get_order(order_id).ok_or_else(not_found)?;
+        if order.user_id() != requesting_user {
+            trace!(
+                "Order book {}: Rejecting cancel of order {} by user {}",
+                self.symbol, order_id, requesting_user
+            );
+            return Err(OrderBookError::Unauthorized {
+                order_id,
+                user_id: requesting_user,
+            });
+        }
+        self.cancel_order(order_id)?.ok_or_else(not_found)
+    }
+
+    /// Cancel an order only if it has not traded since the caller last saw
+    /// it (compare-and-cancel).
+    ///
+    /// `expected_remaining` is the order's remaining quantity, visible and
+    /// hidden, as the caller last observed it. Commands run by the sequencer
+    /// are applied one at a time, so the comparison and the cancel see the
+    /// same state and no fill can land in between.
+    ///
+    /// # Errors
+    /// - [`OrderBookError::OrderNotFound`] if the order is not resting in
+    ///   the book, e.g. because it has since filled completely
+    /// - [`OrderBookError::QuantityChanged`] if its remaining quantity is no
+    ///   longer `expected_remaining`; the order is left resting
+    pub fn try_cancel_if_unfilled(
+        &self,
+        order_id: Id,
+        expected_remaining: u64,
+    ) -> Result<Arc<OrderType<T>>, OrderBookError> {
+        let not_found = || OrderBookError::OrderNotFound(order_id.to_string());
+        let order = self.get_order(order_id).ok_or_else(not_found)?;
+        let actual = order
+            .visible_quantity()
+            .saturating_add(order.hidden_quantity());
+        if actual != expected_remaining {
+            trace!(
+                "Order book {}: Keeping order {} with {} remaining, expected {}",
+                self.symbol, order_id, actual, expected_remaining
+            );
+            return Err(OrderBookError::QuantityChanged {
+                order_id,
+                expected: expected_remaining,
+                actual,
+            });
+        }
+        self.cancel_order(order_id)?.ok_or_else(not_found)
+    }
+
+    /// Cancel an order by ID, treating an absent order as success.
+    ///
+    /// Retrying a cancel for an order that has already been cancelled or
+    /// filled returns [`CancelOutcome::AlreadyGone`] instead of an error.
+    /// Genuine failures are returned as [`CancelOutcome::Error`].
+    pub fn cancel_order_idempotent(&self, order_id: Id) -> CancelOutcome<T> {
+        match self.cancel_order(order_id) {
+            Ok(Some(order)) => CancelOutcome::Cancelled(order),
+            Ok(None) | Err(OrderBookError::OrderNotFound(_)) => CancelOutcome::AlreadyGone,
+            Err(e) => CancelOutcome::Error(e),
+        }
+    }
+
+    /// Move a resting order to the back of its price level's queue.
+    ///
+    /// The order is removed and re-inserted in one step with the same
+    /// price, side, user and remaining quantity, a fresh timestamp and a new
+    /// id, losing time priority to every order already resting at that
+    /// price. The new id is returned. A new id is required because the
+    /// level's queue keeps the position of a removed id until it is popped,
+    /// so re-inserting under the same id would keep the original priority.
+    ///
+    /// Unlike a cancel followed by an add, the order is not re-validated or
+    /// re-matched. The order state tracker (if configured) records the old
+    /// id as cancelled with [`CancelReason::Requeued`] and the new id as
+    /// open.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::OrderNotFound`] if the order is not resting
+    /// in the book.
+    pub fn requeue_order(&self, order_id: Id) -> Result<Id, OrderBookError> {
+        let new_id = Id::from_uuid(self.transaction_id_generator.next());
+        self.requeue_order_as(order_id, new_id)?;
+        Ok(new_id)
+    }
+
+    /// Move a resting order to the back of its price level's queue under
+    /// the caller's `new_id`, as [`requeue_order`](Self::requeue_order)
+    /// does with a generated one.
+    ///
+    /// Replay uses this to give a requeued order the id it was journaled
+    /// with, so later commands addressing that id find it.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::OrderNotFound`] if the order is not resting
+    /// in the book, or [`OrderBookError::InvalidOperation`] if `new_id` is
+    /// already resting.
+    pub fn requeue_order_as(&self, order_id: Id, new_id: Id) -> Result<(), OrderBookError> {
+        let _write = self.write_scope();
+        let not_found = || OrderBookError::OrderNotFound(order_id.to_string());
+        if self.order_locations.contains_key(&new_id) {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!("cannot requeue order {order_id} as resting order {new_id}"),
+            });
+        }
+        let (price, side) = self
+            .order_locations
+            .get(&order_id)
+            .map(|val| *val)
+            .ok_or_else(not_found)?;
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let entry = price_levels.get(&price).ok_or_else(not_found)?;
+        let price_level = entry.value();
+
+        let removed = price_level
+            .update_order(OrderUpdate::Cancel { order_id })?
+            .ok_or_else(not_found)?;
+        let mut order = *removed;
+        let now = TimestampMs::new(current_time_millis());
+        match &mut order {
+            OrderType::Standard { id, timestamp, .. }
+            | OrderType::IcebergOrder { id, timestamp, .. }
+            | OrderType::PostOnly { id, timestamp, .. }
+            | OrderType::TrailingStop { id, timestamp, .. }
+            | OrderType::PeggedOrder { id, timestamp, .. }
+            | OrderType::MarketToLimit { id, timestamp, .. }
+            | OrderType::ReserveOrder { id, timestamp, .. } => {
+                *id = new_id;
+                *timestamp = now;
+            }
+        }
+        price_level.add_order(order);
+        self.cache.invalidate();
+
+        // Re-key the book's indexes from the old id to the new one
+        self.order_locations.remove(&order_id);
+        self.order_locations.insert(new_id, (price, side));
+        self.arrivals.remove(order_id);
+        self.arrivals.push_back(new_id);
+        if let Some((_, extra_fields)) = self.order_extra_fields.remove(&order_id) {
+            self.order_extra_fields.insert(new_id, extra_fields);
+        }
+        if let Some((_, orders)) = self.contingent_orders.remove(&order_id) {
+            self.contingent_orders.insert(new_id, orders);
+        }
+        self.untrack_user_order(order.user_id(), &order_id);
+        self.track_user_order(order.user_id(), new_id);
+
+        #[cfg(feature = "special_orders")]
+        match &order {
+            OrderType::PeggedOrder { .. } => {
+                self.special_order_tracker
+                    .unregister_pegged_order(&order_id);
+                self.special_order_tracker.register_pegged_order(new_id);
+            }
+            OrderType::TrailingStop { .. } => {
+                self.special_order_tracker
+                    .unregister_trailing_stop(&order_id);
+                self.special_order_tracker.register_trailing_stop(new_id);
+            }
+            _ => {}
+        }
+
+        let filled_quantity = self
+            .order_state_tracker
+            .as_ref()
+            .and_then(|t| t.get(order_id))
+            .map(|s| s.filled_quantity())
+            .unwrap_or(0);
+        self.track_state(
+            order_id,
+            OrderStatus::Cancelled {
+                filled_quantity,
+                reason: CancelReason::Requeued,
+            },
+        );
+        self.track_state(new_id, OrderStatus::Open);
+
+        trace!(
+            "Order book {}: Requeued order {} as {} at price {}",
+            self.symbol, order_id, new_id, price
+        );
+        Ok(())
+    }
+
     /// Cancel an order by ID with an explicit cancellation reason.
     ///
     /// This is the internal implementation used by both `cancel_order`
@@ -462,6 +1021,20 @@ where
         order_id: Id,
         reason: CancelReason,
     ) -> Result<Option<Arc<OrderType<T>>>, OrderBookError> {
+        self.cancel_order_counted(order_id, reason, true)
+    }
+
+    /// Cancel `order_id` as
+    /// [`cancel_order_with_reason`](Self::cancel_order_with_reason) does.
+    /// Unless `counted`, as for an order taken off by an amend, it does not
+    /// count as cancelled in the lifetime counters.
+    fn cancel_order_counted(
+        &self,
+        order_id: Id,
+        reason: CancelReason,
+        counted: bool,
+    ) -> Result<Option<Arc<OrderType<T>>>, OrderBookError> {
+        let _write = self.write_scope();
         self.cache.invalidate();
         // First, we find the order's location (price and side) without locking
         let location = self.order_locations.get(&order_id).map(|val| *val);
@@ -485,6 +1058,7 @@ where
                 // Try to cancel the order
                 if let Ok(cancelled) = price_level.update_order(update) {
                     result = cancelled;
+                    self.level_change_recorder.touch(side, price, true);
 
                     // notify price level changes
                     if result.is_some()
@@ -512,16 +1086,20 @@ where
                     .and_then(|t| t.get(order_id))
                     .map(|s| s.filled_quantity())
                     .unwrap_or(0);
-                self.track_state(
-                    order_id,
-                    OrderStatus::Cancelled {
-                        filled_quantity: prev_filled,
-                        reason,
-                    },
-                );
+                let status = OrderStatus::Cancelled {
+                    filled_quantity: prev_filled,
+                    reason,
+                };
+                if counted {
+                    self.track_state(order_id, status);
+                } else {
+                    self.track_state_uncounted(order_id, status);
+                }
 
                 // Remove the order from the locations map
                 self.order_locations.remove(&order_id);
+                self.arrivals.remove(order_id);
+                self.contingent_orders.remove(&order_id);
 
                 // Remove the order from the user_orders index
                 self.untrack_user_order(cancelled_order.user_id(), &order_id);
@@ -541,14 +1119,60 @@ where
                 }
             }
 
-            Ok(result.map(|order| Arc::new(self.convert_from_unit_type(&order))))
+            let cancelled = result.map(|order| Arc::new(self.convert_from_unit_type(&order)));
+            self.order_extra_fields.remove(&order_id);
+            Ok(cancelled)
         } else {
             Ok(None)
         }
     }
 
     /// Add a new order to the book, automatically matching it if it's aggressive.
-    pub fn add_order(&self, mut order: OrderType<T>) -> Result<Arc<OrderType<T>>, OrderBookError> {
+    pub fn add_order(&self, order: OrderType<T>) -> Result<Arc<OrderType<T>>, OrderBookError> {
+        self.add_order_with_remainder(order, RemainderPolicy::Rest)
+            .map(|result| result.order)
+    }
+
+    /// Add a new order to the book like [`Self::add_order`] and report how
+    /// much of it filled, rested and was cancelled.
+    ///
+    /// # Errors
+    /// Same as [`Self::add_order`].
+    pub fn add_order_detailed(&self, order: OrderType<T>) -> Result<AddOutcome, OrderBookError> {
+        self.add_order_with_remainder(order, RemainderPolicy::Rest)
+            .map(AddOutcome::from)
+    }
+
+    /// Add a new order to the book, choosing whether an unfilled remainder
+    /// rests or is cancelled after matching.
+    ///
+    /// Validation and matching are identical to [`Self::add_order`]. With
+    /// [`RemainderPolicy::Cancel`] a crossing order that is only partially
+    /// filled (or not filled at all) does not rest; the cancelled quantity is
+    /// reported in [`AddOrderResult::remainder`] instead of an error.
+    ///
+    /// # Errors
+    /// Same as [`Self::add_order`]. IOC and FOK orders keep their
+    /// time-in-force semantics and still return
+    /// [`OrderBookError::InsufficientLiquidity`] for an unfilled remainder.
+    pub fn add_order_with_remainder(
+        &self,
+        order: OrderType<T>,
+        remainder_policy: RemainderPolicy,
+    ) -> Result<AddOrderResult<T>, OrderBookError> {
+        self.add_order_counted(order, remainder_policy, true)
+    }
+
+    /// Add `order` as [`add_order_with_remainder`](Self::add_order_with_remainder)
+    /// does. Unless `counted`, as for an order re-added by an amend, it
+    /// does not count as added in the lifetime counters.
+    fn add_order_counted(
+        &self,
+        mut order: OrderType<T>,
+        remainder_policy: RemainderPolicy,
+        counted: bool,
+    ) -> Result<AddOrderResult<T>, OrderBookError> {
+        let _write = self.write_scope();
         self.cache.invalidate();
 
         trace!(
@@ -574,9 +1198,15 @@ where
             });
         }
 
-        // Tick size validation: reject orders whose price is not a multiple of tick_size
-        if let Some(tick) = self.tick_size
-            && tick > 0
+        // Tick rounding: move off-tick prices to a valid tick when configured
+        self.apply_tick_rounding(&mut order)?;
+
+        // Timestamp policy: clamp or reject out-of-band client timestamps
+        self.apply_timestamp_policy(&mut order)?;
+
+        // Tick size validation: reject orders whose price is not a multiple
+        // of the tick of its price band
+        if let Some(tick) = self.tick_size_at(order.price().as_u128())
             && !order.price().as_u128().is_multiple_of(tick)
         {
             self.track_state(
@@ -595,69 +1225,49 @@ where
             });
         }
 
-        // Lot size validation: reject orders whose quantity is not a multiple of lot_size.
-        // For iceberg orders, validate visible and hidden quantities individually.
-        if let Some(lot) = self.lot_size
-            && lot > 0
-        {
-            match &order {
-                OrderType::IcebergOrder {
-                    visible_quantity,
-                    hidden_quantity,
-                    ..
-                } => {
-                    if visible_quantity.as_u64() % lot != 0 {
-                        return Err(OrderBookError::InvalidLotSize {
-                            quantity: visible_quantity.as_u64(),
-                            lot_size: lot,
-                        });
-                    }
-                    if hidden_quantity.as_u64() % lot != 0 {
-                        return Err(OrderBookError::InvalidLotSize {
-                            quantity: hidden_quantity.as_u64(),
-                            lot_size: lot,
-                        });
-                    }
-                }
-                _ => {
-                    if order.total_quantity() % lot != 0 {
-                        return Err(OrderBookError::InvalidLotSize {
-                            quantity: order.total_quantity(),
-                            lot_size: lot,
-                        });
-                    }
-                }
-            }
-        }
+        // Lot size and min/max order size validation
+        self.reject_if(order.id(), self.check_order_size(&order))?;
 
-        // Min/max order size validation
-        let qty = order.total_quantity();
-        if let Some(min) = self.min_order_size
-            && qty < min
-        {
-            return Err(OrderBookError::OrderSizeOutOfRange {
-                quantity: qty,
-                min: Some(min),
-                max: self.max_order_size,
-            });
-        }
-        if let Some(max) = self.max_order_size
-            && qty > max
-        {
-            return Err(OrderBookError::OrderSizeOutOfRange {
-                quantity: qty,
-                min: self.min_order_size,
-                max: Some(max),
-            });
-        }
+        // Per-user resting quantity limit
+        self.reject_if(order.id(), self.check_user_limit(&order))?;
+
+        // External BBO lock/cross prevention (may reprice the order)
+        self.apply_external_bbo_policy(&mut order)?;
+
+        // Price band validation against the active reference price
+        self.reject_if(order.id(), self.check_price_band(order.price().as_u128()))?;
+
+        // Per-level order limit, at the price after any external BBO repricing
+        self.reject_if(order.id(), self.check_level_capacity(&order))?;
 
         if self.has_expired(&order) {
+            return self.reject(
+                order.id(),
+                OrderBookError::InvalidOperation {
+                    message: "Order has already expired".to_string(),
+                },
+            );
+        }
+
+        // During auction accumulation orders rest without matching, so
+        // immediate orders cannot be honoured and crosses may build up.
+        let in_auction = self.is_auction();
+        if in_auction && order.is_immediate() {
+            self.track_state(
+                order.id(),
+                OrderStatus::Rejected {
+                    reason: "immediate order during auction".to_string(),
+                },
+            );
             return Err(OrderBookError::InvalidOperation {
-                message: "Order has already expired".to_string(),
+                message: "IOC and FOK orders are not accepted during an auction".to_string(),
             });
         }
 
-        if order.is_post_only() && self.will_cross_market(order.price().as_u128(), order.side()) {
+        if !in_auction
+            && order.is_post_only()
+            && self.will_cross_market(order.price().as_u128(), order.side())
+        {
             self.track_state(
                 order.id(),
                 OrderStatus::Rejected {
@@ -675,6 +1285,23 @@ where
             });
         }
 
+        // Fat-finger guard: an aggressive order may only cross so far
+        if !in_auction {
+            self.reject_if(
+                order.id(),
+                self.check_marketable_guard(order.price().as_u128(), order.side()),
+            )?;
+        }
+
+        // Minimum spread: a new best price must keep its distance to the opposite best
+        if !in_auction && !order.is_immediate() {
+            self.check_min_spread(order.id(), order.price().as_u128(), order.side())?;
+        }
+
+        if counted {
+            self.lifetime_counters.record_added();
+        }
+
         // For FOK orders, first check if the entire quantity can be matched without altering the book.
         if order.is_fill_or_kill() {
             let potential_match = self.peek_match(
@@ -698,15 +1325,33 @@ where
             }
         }
 
+        self.record_order_timestamp(&order);
         self.cache.invalidate();
         // Attempt to match the order immediately (with STP user_id propagation)
-        let match_result = self.match_order_with_user(
-            order.id(),
-            order.side(),
-            order.total_quantity(), // Use total quantity for matching
-            Some(order.price().as_u128()),
-            order.user_id(),
-        )?;
+        let mut stp = None;
+        let match_result = if in_auction {
+            MatchResult::new(order.id(), order.total_quantity())
+        } else {
+            self.observe_match(
+                order.id(),
+                MatchOrderKind::from_order(&order),
+                order.side(),
+                order.total_quantity(),
+                || {
+                    self.match_order_reporting_stp(
+                        order.id(),
+                        order.side(),
+                        order.total_quantity(), // Use total quantity for matching
+                        Some(order.price().as_u128()),
+                        order.user_id(),
+                    )
+                    .map(|(match_result, triggered)| {
+                        stp = triggered;
+                        match_result
+                    })
+                },
+            )?
+        };
 
         if !match_result.trades().as_vec().is_empty()
             && let Some(ref listener) = self.trade_listener
@@ -715,7 +1360,8 @@ where
                 self.symbol.clone(),
                 match_result.clone(),
                 self.fee_schedule,
-            );
+            )
+            .with_stp(stp);
             listener(&trade_result) // emit trade events to listener
         }
 
@@ -723,6 +1369,29 @@ where
         let original_qty = order.total_quantity();
         let filled_qty = original_qty.saturating_sub(match_result.remaining_quantity());
 
+        // STP cancelled the taker after some fills: the remainder never rests
+        if stp.is_some_and(|triggered| triggered.cancelled_taker)
+            && match_result.remaining_quantity() > 0
+        {
+            let cancelled_qty = match_result.remaining_quantity();
+            self.track_state(
+                order.id(),
+                OrderStatus::Cancelled {
+                    filled_quantity: filled_qty,
+                    reason: CancelReason::SelfTradePrevention,
+                },
+            );
+            return Ok(AddOrderResult {
+                order: Arc::new(order),
+                match_result,
+                remainder: RemainderOutcome::Cancelled {
+                    quantity: cancelled_qty,
+                },
+                stp,
+                parked_fills: Vec::new(),
+            });
+        }
+
         // If the order was not fully filled, add the remainder to the book
         if match_result.remaining_quantity() > 0 {
             if order.is_immediate() {
@@ -745,6 +1414,27 @@ where
                 });
             }
 
+            // One-shot limit: drop the remainder instead of resting it.
+            if remainder_policy == RemainderPolicy::Cancel {
+                let cancelled_qty = match_result.remaining_quantity();
+                self.track_state(
+                    order.id(),
+                    OrderStatus::Cancelled {
+                        filled_quantity: filled_qty,
+                        reason: CancelReason::InsufficientLiquidity,
+                    },
+                );
+                return Ok(AddOrderResult {
+                    order: Arc::new(order),
+                    match_result,
+                    remainder: RemainderOutcome::Cancelled {
+                        quantity: cancelled_qty,
+                    },
+                    stp,
+                    parked_fills: Vec::new(),
+                });
+            }
+
             // Update the order with the remaining quantity
             // For iceberg orders, only update if there was actual matching (remaining < total)
             if match_result.remaining_quantity() < order.total_quantity() {
@@ -759,6 +1449,8 @@ where
                 Side::Sell => &self.asks,
             };
 
+            let created =
+                self.level_change_recorder.is_recording() && !price_levels.contains_key(&price);
             let price_level = price_levels.get_or_insert(price, Arc::new(PriceLevel::new(price)));
             let level = price_level.value();
 
@@ -773,23 +1465,18 @@ where
                     quantity: level.visible_quantity(),
                 })
             }
+            self.level_change_recorder.touch(side, price, !created);
             self.order_locations
                 .insert(unit_order_arc.id(), (price, side));
+            self.arrivals.push_back(unit_order_arc.id());
+            self.store_extra_fields(&order);
 
             // Track the order in the user_orders index
             self.track_user_order(order.user_id(), unit_order_arc.id());
 
             // Register special orders for re-pricing tracking
             #[cfg(feature = "special_orders")]
-            match &order {
-                OrderType::PeggedOrder { id, .. } => {
-                    self.special_order_tracker.register_pegged_order(*id);
-                }
-                OrderType::TrailingStop { id, .. } => {
-                    self.special_order_tracker.register_trailing_stop(*id);
-                }
-                _ => {}
-            }
+            self.track_special_order(&order);
 
             // Track state: Open (no fills) or PartiallyFilled (some fills, resting)
             if filled_qty > 0 {
@@ -806,7 +1493,17 @@ where
 
             // Convert back to generic type for return
             let generic_order = self.convert_from_unit_type(&unit_order_arc);
-            Ok(Arc::new(generic_order))
+            let rested_qty = match_result.remaining_quantity();
+            let parked_fills = self.execute_parked_market_orders(side);
+            Ok(AddOrderResult {
+                order: Arc::new(generic_order),
+                match_result,
+                remainder: RemainderOutcome::Rested {
+                    quantity: rested_qty,
+                },
+                stp,
+                parked_fills,
+            })
         } else {
             // The order was fully matched
             self.track_state(
@@ -815,7 +1512,13 @@ where
                     filled_quantity: original_qty,
                 },
             );
-            Ok(Arc::new(order))
+            Ok(AddOrderResult {
+                order: Arc::new(order),
+                match_result,
+                remainder: RemainderOutcome::Filled,
+                stp,
+                parked_fills: Vec::new(),
+            })
         }
     }
 }
@@ -0,0 +1,156 @@
+//! Handling of modifications that reprice an order through the book.
+//!
+//! Repricing a resting order re-enters it at the new price. When that price
+//! now crosses the opposite side, [`ModifyCrossPolicy::Match`] (default)
+//! trades the order as a new aggressor, as an add at that price would, and
+//! [`OrderBook::update_order_with_fills`] reports the fills.
+//! [`ModifyCrossPolicy::Reject`] refuses the modification instead and leaves
+//! the original order resting unchanged.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{OrderType, Side};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// What to do with a modification that would make an order cross the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ModifyCrossPolicy {
+    /// Match the repriced order against the opposite side (default).
+    #[default]
+    Match,
+    /// Reject the modification with [`OrderBookError::PriceCrossing`].
+    Reject,
+}
+
+impl std::fmt::Display for ModifyCrossPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModifyCrossPolicy::Match => write!(f, "Match"),
+            ModifyCrossPolicy::Reject => write!(f, "Reject"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set how modifications that would cross the book are handled.
+    pub fn set_modify_cross_policy(&mut self, policy: ModifyCrossPolicy) {
+        trace!(
+            "Order book {}: Setting modify cross policy to {}",
+            self.symbol, policy
+        );
+        self.modify_cross_policy = policy;
+    }
+
+    /// Returns how modifications that would cross the book are handled.
+    #[must_use]
+    pub fn modify_cross_policy(&self) -> ModifyCrossPolicy {
+        self.modify_cross_policy
+    }
+
+    /// Check a repriced order against the modify cross policy before the
+    /// original is replaced.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::PriceCrossing`] under
+    /// [`ModifyCrossPolicy::Reject`] when `order` would cross the opposite
+    /// side.
+    pub(super) fn check_modify_cross(&self, order: &OrderType<T>) -> Result<(), OrderBookError> {
+        if self.modify_cross_policy == ModifyCrossPolicy::Match {
+            return Ok(());
+        }
+        let price = order.price().as_u128();
+        let side = order.side();
+        let opposite = match side {
+            Side::Buy => self.best_ask().filter(|ask| price >= *ask),
+            Side::Sell => self.best_bid().filter(|bid| price <= *bid),
+        };
+        match opposite {
+            Some(opposite_price) => Err(OrderBookError::PriceCrossing {
+                price,
+                side,
+                opposite_price,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::modifications::RemainderOutcome;
+    use pricelevel::{Id, OrderUpdate, Price, TimeInForce};
+
+    /// A bid of 10 at 100 facing an ask of 4 at 102.
+    fn book(policy: ModifyCrossPolicy) -> (OrderBook<()>, Id, Id) {
+        let mut book = OrderBook::new("TEST");
+        book.set_modify_cross_policy(policy);
+        let bid = Id::new_uuid();
+        let ask = Id::new_uuid();
+        book.add_limit_order(bid, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(ask, 102, 4, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        (book, bid, ask)
+    }
+
+    fn reprice(order_id: Id, price: u128) -> OrderUpdate {
+        OrderUpdate::UpdatePrice {
+            order_id,
+            new_price: Price::new(price),
+        }
+    }
+
+    #[test]
+    fn test_match_policy_trades_crossing_modify() {
+        let (book, bid, ask) = book(ModifyCrossPolicy::default());
+        let result = book
+            .update_order_with_fills(reprice(bid, 103))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result.match_result.filled_order_ids(), &[ask]);
+        assert_eq!(result.match_result.executed_quantity().unwrap(), 4);
+        assert_eq!(result.remainder, RemainderOutcome::Rested { quantity: 6 });
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(book.best_bid(), Some(103));
+    }
+
+    #[test]
+    fn test_reject_policy_keeps_original_order() {
+        let (book, bid, ask) = book(ModifyCrossPolicy::Reject);
+        for update in [
+            reprice(bid, 102),
+            OrderUpdate::UpdatePriceAndQuantity {
+                order_id: bid,
+                new_price: Price::new(105),
+                new_quantity: pricelevel::Quantity::new(2),
+            },
+        ] {
+            assert!(matches!(
+                book.update_order(update),
+                Err(OrderBookError::PriceCrossing {
+                    side: Side::Buy,
+                    opposite_price: 102,
+                    ..
+                })
+            ));
+        }
+        let original = book.get_order(bid).unwrap();
+        assert_eq!(original.price().as_u128(), 100);
+        assert_eq!(original.visible_quantity(), 10);
+        assert!(book.get_order(ask).is_some());
+
+        // A modify that stays passive is still allowed
+        let result = book
+            .update_order_with_fills(reprice(bid, 101))
+            .unwrap()
+            .unwrap();
+        assert!(result.match_result.trades().as_vec().is_empty());
+        assert_eq!(book.best_bid(), Some(101));
+    }
+}
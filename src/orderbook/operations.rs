@@ -2,6 +2,8 @@
 
 use super::book::OrderBook;
 use super::error::OrderBookError;
+use super::modifications::{AddOrderResult, RemainderPolicy};
+use super::trade::TradeResult;
 use pricelevel::{
     Hash32, Id, MatchResult, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs,
 };
@@ -86,6 +88,104 @@ where
         self.add_order(order)
     }
 
+    /// Add a limit order with an explicit policy for its unfilled remainder.
+    ///
+    /// With [`RemainderPolicy::Rest`] this behaves like
+    /// [`Self::add_limit_order_with_user`]. With [`RemainderPolicy::Cancel`]
+    /// whatever is not filled immediately is cancelled instead of resting,
+    /// without changing the order's time-in-force. The returned
+    /// [`AddOrderResult`] reports the fills and whether the remainder rested
+    /// or was cancelled.
+    ///
+    /// # Arguments
+    /// * `id` — Unique order identifier.
+    /// * `price` — Limit price.
+    /// * `quantity` — Order quantity.
+    /// * `side` — Buy or Sell.
+    /// * `time_in_force` — Time-in-force policy.
+    /// * `user_id` — Owner identity for STP checks.
+    /// * `remainder_policy` — Whether an unfilled remainder rests or is cancelled.
+    /// * `extra_fields` — Optional application-specific payload.
+    ///
+    /// # Errors
+    /// Same as [`Self::add_limit_order_with_user`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_limit_order_with_remainder(
+        &self,
+        id: Id,
+        price: u128,
+        quantity: u64,
+        side: Side,
+        time_in_force: TimeInForce,
+        user_id: Hash32,
+        remainder_policy: RemainderPolicy,
+        extra_fields: Option<T>,
+    ) -> Result<AddOrderResult<T>, OrderBookError> {
+        let extra_fields: T = extra_fields.unwrap_or_default();
+        let order = OrderType::Standard {
+            id,
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id,
+            timestamp: TimestampMs::new(crate::utils::current_time_millis()),
+            time_in_force,
+            extra_fields,
+        };
+        trace!(
+            "Adding limit order {} {} {} {} {} with remainder policy {:?}",
+            id, price, quantity, side, time_in_force, remainder_policy
+        );
+        self.add_order_with_remainder(order, remainder_policy)
+    }
+
+    /// Submit a marketable limit order that takes liquidity up to `price`
+    /// and cancels whatever does not fill immediately.
+    ///
+    /// This has the semantics of a [`TimeInForce::Ioc`] limit order with a
+    /// simpler signature: nothing rests afterwards, and the returned
+    /// [`TradeResult`] carries the fills with fees from the book's fee
+    /// schedule. Unlike an IOC order passed to [`Self::add_order`], an
+    /// unfilled remainder is not an error; it shows as the remaining
+    /// quantity of the match result.
+    ///
+    /// # Arguments
+    /// * `id` — Unique order identifier.
+    /// * `price` — Worst price the order may trade at.
+    /// * `quantity` — Maximum quantity to take.
+    /// * `side` — Buy or Sell.
+    /// * `user_id` — Owner identity for STP checks.
+    ///
+    /// # Errors
+    /// Same as [`Self::add_limit_order_with_user`].
+    pub fn submit_ioc_limit(
+        &self,
+        id: Id,
+        price: u128,
+        quantity: u64,
+        side: Side,
+        user_id: Hash32,
+    ) -> Result<TradeResult, OrderBookError> {
+        trace!(
+            "Submitting IOC limit order {} {} {} {}",
+            id, price, quantity, side
+        );
+        let result = self.add_limit_order_with_remainder(
+            id,
+            price,
+            quantity,
+            side,
+            TimeInForce::Gtc,
+            user_id,
+            RemainderPolicy::Cancel,
+            None,
+        )?;
+        Ok(
+            TradeResult::with_fees(self.symbol.clone(), result.match_result, self.fee_schedule)
+                .with_stp(result.stp),
+        )
+    }
+
     /// Add an iceberg order to the book.
     ///
     /// This convenience method sets `user_id` to `Hash32::zero()`.  When STP
@@ -274,4 +374,33 @@ where
         );
         OrderBook::<T>::match_market_order_with_user(self, id, quantity, side, user_id)
     }
+
+    /// Submit a market order that trades only with displayed liquidity
+    /// unless `interact_hidden` is set.
+    ///
+    /// With `interact_hidden` false the order takes the visible quantity of
+    /// each level it reaches and never the hidden quantity of iceberg and
+    /// reserve orders, including slices refilled while it matches. Whatever
+    /// the displayed depth cannot fill is left as the remaining quantity of
+    /// the match result. With `interact_hidden` true this is
+    /// [`Self::submit_market_order_with_user`].
+    ///
+    /// # Errors
+    /// Same as [`Self::submit_market_order_with_user`];
+    /// [`OrderBookError::InsufficientLiquidity`] is returned only when no
+    /// quantity at all was filled.
+    pub fn submit_market_order_with_hidden(
+        &self,
+        id: Id,
+        quantity: u64,
+        side: Side,
+        user_id: Hash32,
+        interact_hidden: bool,
+    ) -> Result<MatchResult, OrderBookError> {
+        trace!(
+            "Submitting market order {} {} {} (user: {}, interact hidden: {})",
+            id, quantity, side, user_id, interact_hidden
+        );
+        self.match_market_order_with_hidden(id, quantity, side, user_id, interact_hidden)
+    }
 }
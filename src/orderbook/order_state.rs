@@ -68,6 +68,11 @@ pub enum CancelReason {
     MassCancelByPriceRange,
     /// IOC or FOK order could not be fully filled.
     InsufficientLiquidity,
+    /// Moved to the back of its queue by `requeue_order` under a new id.
+    Requeued,
+    /// Added by a multi-book transaction that was rolled back (see
+    /// `BookRegistry::execute_transaction`).
+    TransactionRolledBack,
 }
 
 impl std::fmt::Display for CancelReason {
@@ -81,6 +86,8 @@ impl std::fmt::Display for CancelReason {
             Self::MassCancelByUser => write!(f, "mass cancel by user"),
             Self::MassCancelByPriceRange => write!(f, "mass cancel by price range"),
             Self::InsufficientLiquidity => write!(f, "insufficient liquidity"),
+            Self::Requeued => write!(f, "requeued"),
+            Self::TransactionRolledBack => write!(f, "transaction rolled back"),
         }
     }
 }
@@ -0,0 +1,84 @@
+//! Parallel traversal of price levels with rayon.
+//!
+//! [`OrderBook::par_levels`] takes a consistent read of one side of the
+//! book into an owned, best-first list of levels and hands it to rayon, so
+//! analytics such as depth curves can be computed in parallel while the
+//! book keeps changing. Requires the `rayon` feature.
+
+use super::book::OrderBook;
+use pricelevel::{PriceLevel, Side};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator};
+use std::sync::Arc;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns a parallel iterator over the price levels of `side`, best
+    /// price first.
+    ///
+    /// The set of levels is collected up front; orders added or removed
+    /// afterwards are visible through the shared level handles, but levels
+    /// created afterwards are not included.
+    ///
+    /// # Performance
+    /// O(N) to collect the N levels of the side, then parallel.
+    #[must_use]
+    pub fn par_levels(&self, side: Side) -> impl IndexedParallelIterator<Item = Arc<PriceLevel>> {
+        let levels: Vec<Arc<PriceLevel>> = match side {
+            Side::Buy => self.bids.iter().rev().map(|e| e.value().clone()).collect(),
+            Side::Sell => self.asks.iter().map(|e| e.value().clone()).collect(),
+        };
+        levels.into_par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+    use rayon::iter::ParallelIterator;
+
+    #[test]
+    fn test_parallel_visible_quantity_matches_serial() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for i in 0..200u64 {
+            let price = 1_000 + u128::from(i % 50);
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                i + 1,
+                Side::Sell,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+            book.add_limit_order(
+                Id::new_uuid(),
+                price - 100,
+                i + 1,
+                Side::Buy,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+
+        for side in [Side::Buy, Side::Sell] {
+            let serial: u64 = book
+                .levels_with_cumulative_depth(side)
+                .map(|level| level.quantity)
+                .sum();
+            let parallel: u64 = book
+                .par_levels(side)
+                .map(|level| level.visible_quantity())
+                .sum();
+            assert_eq!(parallel, serial);
+            assert_eq!(book.par_levels(side).len(), 50);
+        }
+
+        let prices: Vec<u128> = book.par_levels(Side::Buy).map(|l| l.price()).collect();
+        assert_eq!(prices.first(), Some(&949));
+        assert_eq!(prices.last(), Some(&900));
+    }
+}
@@ -0,0 +1,261 @@
+//! Market orders submitted against an empty opposite side.
+//!
+//! By default a market order that finds no liquidity at all is rejected with
+//! [`OrderBookError::InsufficientLiquidity`](crate::orderbook::OrderBookError::InsufficientLiquidity).
+//! Under
+//! [`MarketOrderEmptyBookPolicy::ParkAsPending`] it is parked instead and
+//! matched, in submission order, as soon as an order rests on the opposite
+//! side. A parked order that is only partly filled stays parked with its
+//! remainder until more liquidity arrives. The fills are reported with the
+//! add that brought the liquidity, in
+//! [`AddOrderResult::parked_fills`](crate::orderbook::AddOrderResult::parked_fills).
+//! Parked orders do not rest in the book, so mass cancels leave them in
+//! place; remove them with
+//! [`OrderBook::cancel_parked_market_order`]. Snapshot packages carry
+//! parked orders, and a plain [`OrderBook::restore_from_snapshot`] drops
+//! them.
+
+use super::book::OrderBook;
+use pricelevel::{Hash32, Id, MatchResult, Side};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// How a market order is handled when the opposite side has no orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum MarketOrderEmptyBookPolicy {
+    /// Reject the order with
+    /// [`OrderBookError::InsufficientLiquidity`](crate::orderbook::OrderBookError::InsufficientLiquidity)
+    /// (default).
+    #[default]
+    Reject,
+    /// Park the order until liquidity arrives on the opposite side.
+    ParkAsPending,
+}
+
+impl std::fmt::Display for MarketOrderEmptyBookPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarketOrderEmptyBookPolicy::Reject => write!(f, "Reject"),
+            MarketOrderEmptyBookPolicy::ParkAsPending => write!(f, "ParkAsPending"),
+        }
+    }
+}
+
+/// A market order waiting for liquidity on the opposite side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParkedMarketOrder {
+    /// The market order's identifier.
+    pub order_id: Id,
+    /// The side of the market order.
+    pub side: Side,
+    /// The quantity still to be filled.
+    pub quantity: u64,
+    /// The owner of the order, for self-trade prevention.
+    pub user_id: Hash32,
+    /// Whether the order may trade with hidden quantity; see
+    /// [`OrderBook::submit_market_order_with_hidden`].
+    #[serde(default = "interacts_hidden")]
+    pub interact_hidden: bool,
+}
+
+/// Orders parked before the flag existed traded with hidden quantity.
+fn interacts_hidden() -> bool {
+    true
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set how market orders against an empty opposite side are handled.
+    pub fn set_market_order_empty_book_policy(&mut self, policy: MarketOrderEmptyBookPolicy) {
+        trace!(
+            "Order book {}: Setting market order empty book policy to {}",
+            self.symbol, policy
+        );
+        self.market_order_empty_book_policy = policy;
+    }
+
+    /// Returns how market orders against an empty opposite side are
+    /// handled.
+    #[must_use]
+    pub fn market_order_empty_book_policy(&self) -> MarketOrderEmptyBookPolicy {
+        self.market_order_empty_book_policy
+    }
+
+    /// Returns the parked market orders in execution order.
+    #[must_use]
+    pub fn parked_market_orders(&self) -> Vec<ParkedMarketOrder> {
+        self.parked_market_orders
+            .lock()
+            .map(|parked| parked.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove a parked market order, returning it if it was parked.
+    pub fn cancel_parked_market_order(&self, order_id: Id) -> Option<ParkedMarketOrder> {
+        let mut parked = self.parked_market_orders.lock().ok()?;
+        let index = parked.iter().position(|p| p.order_id == order_id)?;
+        parked.remove(index)
+    }
+
+    /// Replace the parked orders with `parked`, in execution order.
+    pub(super) fn restore_parked_market_orders(&self, parked: Vec<ParkedMarketOrder>) {
+        if let Ok(mut current) = self.parked_market_orders.lock() {
+            *current = parked.into();
+        }
+    }
+
+    /// Park a market order that found no liquidity and return its empty
+    /// match.
+    pub(super) fn park_market_order(
+        &self,
+        order_id: Id,
+        quantity: u64,
+        side: Side,
+        user_id: Hash32,
+        interact_hidden: bool,
+    ) -> MatchResult {
+        trace!(
+            "Order book {}: Parking market order {} for {} at side {:?}",
+            self.symbol, order_id, quantity, side
+        );
+        if let Ok(mut parked) = self.parked_market_orders.lock() {
+            parked.push_back(ParkedMarketOrder {
+                order_id,
+                side,
+                quantity,
+                user_id,
+                interact_hidden,
+            });
+        }
+        MatchResult::new(order_id, quantity)
+    }
+
+    /// Match parked market orders against liquidity that just rested on
+    /// `resting_side`, until they or the liquidity run out. Returns the
+    /// fills, in execution order.
+    pub(super) fn execute_parked_market_orders(&self, resting_side: Side) -> Vec<MatchResult> {
+        let taker_side = resting_side.opposite();
+        let mut fills = Vec::new();
+        loop {
+            let opposite_empty = match resting_side {
+                Side::Buy => self.bids.is_empty(),
+                Side::Sell => self.asks.is_empty(),
+            };
+            if opposite_empty {
+                return fills;
+            }
+            let next = self
+                .parked_market_orders
+                .lock()
+                .ok()
+                .and_then(|mut parked| {
+                    let index = parked.iter().position(|p| p.side == taker_side)?;
+                    parked.remove(index)
+                });
+            let Some(order) = next else {
+                return fills;
+            };
+
+            // A rejection (e.g. by self-trade prevention) drops the order
+            let Ok(match_result) = self.match_market_order_with_hidden(
+                order.order_id,
+                order.quantity,
+                order.side,
+                order.user_id,
+                order.interact_hidden,
+            ) else {
+                continue;
+            };
+            let remaining = match_result.remaining_quantity();
+            if !match_result.trades().as_vec().is_empty() {
+                fills.push(match_result);
+            }
+            if remaining > 0 {
+                // Liquidity ran out: keep the remainder at the front
+                if let Ok(mut parked) = self.parked_market_orders.lock() {
+                    parked.push_front(ParkedMarketOrder {
+                        quantity: remaining,
+                        ..order
+                    });
+                }
+                return fills;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::OrderBookError;
+    use pricelevel::TimeInForce;
+
+    fn parking_book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_market_order_empty_book_policy(MarketOrderEmptyBookPolicy::ParkAsPending);
+        book
+    }
+
+    #[test]
+    fn test_empty_book_market_order_rejected_by_default() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(
+            book.market_order_empty_book_policy(),
+            MarketOrderEmptyBookPolicy::Reject
+        );
+        assert!(matches!(
+            book.submit_market_order(Id::new_uuid(), 10, Side::Buy),
+            Err(OrderBookError::InsufficientLiquidity { available: 0, .. })
+        ));
+        assert!(book.parked_market_orders().is_empty());
+    }
+
+    #[test]
+    fn test_parked_market_order_fills_when_ask_rests() {
+        let book = parking_book();
+        let id = Id::new_uuid();
+        let result = book.submit_market_order(id, 10, Side::Buy).unwrap();
+        assert!(result.trades().as_vec().is_empty());
+        assert_eq!(book.parked_market_orders().len(), 1);
+
+        // A bid does not release a parked buy
+        book.add_limit_order(Id::new_uuid(), 90, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.parked_market_orders()[0].quantity, 10);
+
+        // Partly filled by the first ask, the remainder stays parked
+        book.add_limit_order(Id::new_uuid(), 100, 4, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.best_ask(), None);
+        let parked = book.parked_market_orders();
+        assert_eq!(parked.len(), 1);
+        assert_eq!((parked[0].order_id, parked[0].quantity), (id, 6));
+
+        book.add_limit_order(Id::new_uuid(), 101, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert!(book.parked_market_orders().is_empty());
+        assert_eq!(book.best_ask(), Some(101));
+        assert_eq!(
+            book.get_orders_at_price(101, Side::Sell)[0].visible_quantity(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_cancel_parked_market_order() {
+        let book = parking_book();
+        let id = Id::new_uuid();
+        book.submit_market_order(id, 10, Side::Sell).unwrap();
+        assert_eq!(
+            book.cancel_parked_market_order(id).map(|p| p.quantity),
+            Some(10)
+        );
+        assert!(book.cancel_parked_market_order(id).is_none());
+
+        book.add_limit_order(Id::new_uuid(), 100, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.best_bid(), Some(100));
+    }
+}
@@ -0,0 +1,145 @@
+//! Conversion between decimal client prices and integer book prices.
+//!
+//! The book stores prices as integers scaled by `10^price_decimals`. With
+//! [`OrderBook::set_price_decimals`] set to 2, the decimal price `1234.56`
+//! is the integer price `123456`. [`OrderBook::price_from_decimal`] and
+//! [`OrderBook::price_to_decimal`] centralize that scaling and reject
+//! decimal prices that do not land on the configured tick.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use tracing::trace;
+
+/// Largest distance, in integer price units, between a scaled decimal price
+/// and the nearest integer that is still attributed to floating point
+/// representation error rather than to a sub-unit price.
+const SCALE_TOLERANCE: f64 = 1e-6;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the number of decimals of the integer price scale.
+    ///
+    /// # Arguments
+    /// - `price_decimals`: Decimal places represented by one integer price
+    ///   unit. The default of 0 means integer prices are decimal prices.
+    pub fn set_price_decimals(&mut self, price_decimals: u32) {
+        self.price_decimals = price_decimals;
+        trace!(
+            "Order book {}: Set price decimals to {}",
+            self.symbol, price_decimals
+        );
+    }
+
+    /// Returns the number of decimals of the integer price scale.
+    #[must_use]
+    pub fn price_decimals(&self) -> u32 {
+        self.price_decimals
+    }
+
+    /// Convert a decimal price to the book's integer price.
+    ///
+    /// # Errors
+    /// - [`OrderBookError::InvalidDecimalPrice`] if `value` is negative, not
+    ///   finite, out of range, or has more decimals than `price_decimals`
+    /// - [`OrderBookError::InvalidTickSize`] if the integer price is not a
+    ///   multiple of the configured tick size
+    pub fn price_from_decimal(&self, value: f64) -> Result<u128, OrderBookError> {
+        let invalid = || OrderBookError::InvalidDecimalPrice {
+            value,
+            price_decimals: self.price_decimals,
+        };
+
+        let exponent = i32::try_from(self.price_decimals).map_err(|_| invalid())?;
+        let scaled = value * 10f64.powi(exponent);
+        if !scaled.is_finite() || scaled < 0.0 || scaled >= u128::MAX as f64 {
+            return Err(invalid());
+        }
+
+        let rounded = scaled.round();
+        if (scaled - rounded).abs() > SCALE_TOLERANCE {
+            return Err(invalid());
+        }
+
+        let price = rounded as u128;
+        if let Some(tick_size) = self.tick_size_at(price)
+            && !price.is_multiple_of(tick_size)
+        {
+            return Err(OrderBookError::InvalidTickSize { price, tick_size });
+        }
+        Ok(price)
+    }
+
+    /// Convert an integer book price to its decimal price.
+    ///
+    /// Prices above 2^53 lose precision in the conversion.
+    #[must_use]
+    pub fn price_to_decimal(&self, price: u128) -> f64 {
+        let exponent = i32::try_from(self.price_decimals).unwrap_or(i32::MAX);
+        price as f64 / 10f64.powi(exponent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(price_decimals: u32, tick_size: Option<u128>) -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_price_decimals(price_decimals);
+        if let Some(tick_size) = tick_size {
+            book.set_tick_size(tick_size);
+        }
+        book
+    }
+
+    #[test]
+    fn test_decimal_price_round_trip() {
+        let book = book(2, None);
+        assert_eq!(book.price_decimals(), 2);
+
+        let price = book.price_from_decimal(1234.56).unwrap();
+        assert_eq!(price, 123_456);
+        assert!((book.price_to_decimal(price) - 1234.56).abs() < f64::EPSILON * 1e4);
+
+        // Integer prices are decimal prices by default
+        let plain: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(plain.price_from_decimal(42.0).unwrap(), 42);
+        assert_eq!(plain.price_to_decimal(42), 42.0);
+    }
+
+    #[test]
+    fn test_sub_tick_decimal_is_rejected() {
+        let book = book(2, None);
+        assert!(matches!(
+            book.price_from_decimal(1234.565),
+            Err(OrderBookError::InvalidDecimalPrice {
+                price_decimals: 2,
+                ..
+            })
+        ));
+
+        // With a tick of 0.05, 1234.56 is between ticks
+        let ticked = self::book(2, Some(5));
+        assert!(matches!(
+            ticked.price_from_decimal(1234.56),
+            Err(OrderBookError::InvalidTickSize {
+                price: 123_456,
+                tick_size: 5
+            })
+        ));
+        assert_eq!(ticked.price_from_decimal(1234.55).unwrap(), 123_455);
+    }
+
+    #[test]
+    fn test_invalid_decimal_inputs_are_rejected() {
+        let book = book(2, None);
+        for value in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                book.price_from_decimal(value),
+                Err(OrderBookError::InvalidDecimalPrice { .. })
+            ));
+        }
+    }
+}
@@ -45,6 +45,7 @@ where
         };
 
         // Get or create the price level
+        let created = self.level_change_recorder.is_recording() && !book_side.contains_key(&price);
         let price_level = book_side
             .get_or_insert(price, Arc::new(PriceLevel::new(price)))
             .value()
@@ -62,8 +63,11 @@ where
                 quantity: price_level.visible_quantity(),
             })
         }
+        self.level_change_recorder.touch(side, price, !created);
         // The location is stored as (price, side) for efficient retrieval in cancel_order
         self.order_locations.insert(order_id, (price, side));
+        self.arrivals.push_back(order_id);
+        self.store_extra_fields(&order);
 
         // Track the order in the user_orders index for efficient user-based cancellation
         self.track_user_order(order.user_id(), order_id);
@@ -80,6 +84,22 @@ where
         self.user_orders.entry(user_id).or_default().push(order_id);
     }
 
+    /// Register a pegged order or trailing stop with the re-pricing tracker.
+    /// Other order types are ignored.
+    #[cfg(feature = "special_orders")]
+    #[inline]
+    pub(super) fn track_special_order<U>(&self, order: &OrderType<U>) {
+        match order {
+            OrderType::PeggedOrder { id, .. } => {
+                self.special_order_tracker.register_pegged_order(*id);
+            }
+            OrderType::TrailingStop { id, .. } => {
+                self.special_order_tracker.register_trailing_stop(*id);
+            }
+            _ => {}
+        }
+    }
+
     /// Remove an order from the `user_orders` index.
     ///
     /// If the user's order list becomes empty, the entry is removed entirely.
@@ -105,14 +125,22 @@ where
     /// accessible. The scan is efficient in practice because:
     /// - Each order belongs to exactly one user (early return on first match)
     /// - The number of active users is typically small
-    pub(super) fn untrack_order_by_id(&self, order_id: &pricelevel::Id) {
+    ///
+    /// Returns the user the order was tracked under, if any.
+    pub(super) fn untrack_order_by_id(
+        &self,
+        order_id: &pricelevel::Id,
+    ) -> Option<pricelevel::Hash32> {
+        let mut owner = None;
         let mut user_to_remove = None;
         for mut entry in self.user_orders.iter_mut() {
             let ids = entry.value_mut();
             if let Some(pos) = ids.iter().position(|id| id == order_id) {
                 ids.swap_remove(pos);
-                if ids.is_empty() {
-                    user_to_remove = Some(*entry.key());
+                let emptied = ids.is_empty();
+                owner = Some(*entry.key());
+                if emptied {
+                    user_to_remove = owner;
                 }
                 break;
             }
@@ -120,22 +148,109 @@ where
         if let Some(user_id) = user_to_remove {
             self.user_orders.remove(&user_id);
         }
+        owner
     }
 
-    /// Record an order state transition if a tracker is configured.
+    /// Keep the `extra_fields` of a resting order so lookups can return them.
     ///
-    /// This is a no-op when `order_state_tracker` is `None`.
+    /// Zero-sized payloads carry no data and are skipped entirely.
+    #[inline]
+    pub(super) fn store_extra_fields(&self, order: &OrderType<T>) {
+        if std::mem::size_of::<T>() != 0 {
+            self.order_extra_fields
+                .insert(order.id(), order.extra_fields().clone());
+        }
+    }
+
+    /// Fetch the stored `extra_fields` for an order, falling back to
+    /// `T::default()` for orders that were never stored (e.g. restored from
+    /// a snapshot, which only carries unit payloads).
+    #[inline]
+    pub(super) fn extra_fields_for(&self, order_id: pricelevel::Id) -> T {
+        self.order_extra_fields
+            .get(&order_id)
+            .map(|entry| entry.value().clone())
+            .unwrap_or_default()
+    }
+
+    /// Count an order state transition towards the lifetime counters and
+    /// record it if a tracker is configured.
     #[inline]
     pub(super) fn track_state(
         &self,
         order_id: pricelevel::Id,
         status: super::order_state::OrderStatus,
+    ) {
+        self.lifetime_counters.record_status(&status);
+        self.track_state_uncounted(order_id, status);
+    }
+
+    /// Record a status transition in the order state tracker (if
+    /// configured) without counting it in the lifetime counters.
+    #[inline]
+    pub(super) fn track_state_uncounted(
+        &self,
+        order_id: pricelevel::Id,
+        status: super::order_state::OrderStatus,
     ) {
         if let Some(ref tracker) = self.order_state_tracker {
             tracker.transition(order_id, status);
         }
     }
 
+    /// Track `order_id` as rejected for `error`, and return the error.
+    pub(super) fn reject<R>(
+        &self,
+        order_id: pricelevel::Id,
+        error: OrderBookError,
+    ) -> Result<R, OrderBookError> {
+        self.track_state(
+            order_id,
+            super::order_state::OrderStatus::Rejected {
+                reason: error.to_string(),
+            },
+        );
+        Err(error)
+    }
+
+    /// Pass on the result of a validation `check`, tracking `order_id` as
+    /// rejected if it failed.
+    pub(super) fn reject_if(
+        &self,
+        order_id: pricelevel::Id,
+        check: Result<(), OrderBookError>,
+    ) -> Result<(), OrderBookError> {
+        check.or_else(|error| self.reject(order_id, error))
+    }
+
+    /// Overwrite the limit price of `order` in place.
+    pub(super) fn set_order_price(order: &mut OrderType<T>, new_price: u128) {
+        let new_price = pricelevel::Price::new(new_price);
+        match order {
+            OrderType::Standard { price, .. } => *price = new_price,
+            OrderType::IcebergOrder { price, .. } => *price = new_price,
+            OrderType::PostOnly { price, .. } => *price = new_price,
+            OrderType::TrailingStop { price, .. } => *price = new_price,
+            OrderType::PeggedOrder { price, .. } => *price = new_price,
+            OrderType::MarketToLimit { price, .. } => *price = new_price,
+            OrderType::ReserveOrder { price, .. } => *price = new_price,
+        }
+    }
+
+    /// Replace the timestamp of `order` in place.
+    pub(super) fn set_order_timestamp(order: &mut OrderType<T>, new_timestamp: u64) {
+        let new_timestamp = pricelevel::TimestampMs::new(new_timestamp);
+        match order {
+            OrderType::Standard { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::IcebergOrder { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::PostOnly { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::TrailingStop { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::PeggedOrder { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::MarketToLimit { timestamp, .. } => *timestamp = new_timestamp,
+            OrderType::ReserveOrder { timestamp, .. } => *timestamp = new_timestamp,
+        }
+    }
+
     /// Convert `OrderType<T>` to OrderType<()> for compatibility with current PriceLevel API
     pub fn convert_to_unit_type(&self, order: &OrderType<T>) -> OrderType<()> {
         match order {
@@ -0,0 +1,161 @@
+//! Deterministic pro-rata allocation.
+//!
+//! Pro-rata matching splits an incoming quantity across the resting orders
+//! of a price level in proportion to their size. Integer division leaves a
+//! remainder that must be assigned by an explicit, deterministic rule so
+//! that replaying the same inputs reproduces identical fills.
+//!
+//! [`allocate_pro_rata`] is a pure function: its output depends only on its
+//! arguments, never on wall-clock time, hashing order or thread scheduling.
+
+use serde::{Deserialize, Serialize};
+
+/// Rule for assigning the units left over after proportional distribution.
+///
+/// Leftover units are handed out one at a time, in the order defined by the
+/// rule, to orders that still have unallocated quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ProRataRemainderRule {
+    /// Assign leftover units in time priority (earliest order first). Default.
+    #[default]
+    EarliestFirst,
+    /// Assign leftover units to the largest resting orders first; equal
+    /// sizes are broken by time priority.
+    LargestFirst,
+}
+
+impl std::fmt::Display for ProRataRemainderRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProRataRemainderRule::EarliestFirst => write!(f, "EarliestFirst"),
+            ProRataRemainderRule::LargestFirst => write!(f, "LargestFirst"),
+        }
+    }
+}
+
+/// Split `incoming` across `resting` quantities in proportion to their size.
+///
+/// `resting` must be given in time priority (earliest first); the returned
+/// vector is aligned with it. Each order first receives
+/// `floor(incoming * size / total)`, then the remaining units are assigned
+/// one by one according to `rule`. No order is allocated more than its
+/// resting size, and when `incoming` covers the whole level every order is
+/// filled completely.
+///
+/// # Examples
+///
+/// ```
+/// use orderbook_rs::orderbook::pro_rata::{allocate_pro_rata, ProRataRemainderRule};
+///
+/// // 10 units across 3 equal orders: 3/3/3 plus one leftover unit
+/// let alloc = allocate_pro_rata(10, &[5, 5, 5], ProRataRemainderRule::EarliestFirst);
+/// assert_eq!(alloc, vec![4, 3, 3]);
+/// ```
+#[must_use]
+pub fn allocate_pro_rata(incoming: u64, resting: &[u64], rule: ProRataRemainderRule) -> Vec<u64> {
+    let total: u128 = resting.iter().map(|&q| u128::from(q)).sum();
+    if total == 0 || incoming == 0 {
+        return vec![0; resting.len()];
+    }
+    if u128::from(incoming) >= total {
+        return resting.to_vec();
+    }
+
+    // incoming < total, so every share fits in u64 and is <= its order size
+    let mut allocations: Vec<u64> = resting
+        .iter()
+        .map(|&q| (u128::from(incoming) * u128::from(q) / total) as u64)
+        .collect();
+    let allocated: u64 = allocations.iter().sum();
+    let mut remainder = incoming - allocated;
+
+    let mut order: Vec<usize> = (0..resting.len()).collect();
+    if rule == ProRataRemainderRule::LargestFirst {
+        // Stable sort keeps time priority among equal sizes
+        order.sort_by_key(|&i| std::cmp::Reverse(resting[i]));
+    }
+
+    while remainder > 0 {
+        let mut progressed = false;
+        for &i in &order {
+            if remainder == 0 {
+                break;
+            }
+            if allocations[i] < resting[i] {
+                allocations[i] += 1;
+                remainder -= 1;
+                progressed = true;
+            }
+        }
+        if !progressed {
+            break;
+        }
+    }
+
+    allocations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remainder_of_one_earliest_first() {
+        // 7 * [2, 3, 5] / 10 = [1.4, 2.1, 3.5] -> floor [1, 2, 3], remainder 1
+        let alloc = allocate_pro_rata(7, &[2, 3, 5], ProRataRemainderRule::EarliestFirst);
+        assert_eq!(alloc, vec![2, 2, 3]);
+    }
+
+    #[test]
+    fn test_remainder_of_one_largest_first() {
+        let alloc = allocate_pro_rata(7, &[2, 3, 5], ProRataRemainderRule::LargestFirst);
+        assert_eq!(alloc, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_largest_first_ties_break_by_time() {
+        let alloc = allocate_pro_rata(10, &[5, 5, 5], ProRataRemainderRule::LargestFirst);
+        assert_eq!(alloc, vec![4, 3, 3]);
+    }
+
+    #[test]
+    fn test_allocation_is_reproducible() {
+        let resting = [13, 7, 29, 1, 50];
+        for rule in [
+            ProRataRemainderRule::EarliestFirst,
+            ProRataRemainderRule::LargestFirst,
+        ] {
+            let first = allocate_pro_rata(37, &resting, rule);
+            let bytes = serde_json::to_vec(&first).expect("serialize");
+            for _ in 0..10 {
+                let again = allocate_pro_rata(37, &resting, rule);
+                assert_eq!(serde_json::to_vec(&again).expect("serialize"), bytes);
+            }
+            assert_eq!(first.iter().sum::<u64>(), 37);
+        }
+    }
+
+    #[test]
+    fn test_allocation_bounds() {
+        assert_eq!(
+            allocate_pro_rata(100, &[10, 20], ProRataRemainderRule::EarliestFirst),
+            vec![10, 20]
+        );
+        assert_eq!(
+            allocate_pro_rata(0, &[10, 20], ProRataRemainderRule::EarliestFirst),
+            vec![0, 0]
+        );
+        assert!(allocate_pro_rata(5, &[], ProRataRemainderRule::LargestFirst).is_empty());
+        assert_eq!(
+            allocate_pro_rata(
+                u64::MAX - 1,
+                &[u64::MAX, u64::MAX],
+                ProRataRemainderRule::EarliestFirst
+            )
+            .iter()
+            .map(|&q| u128::from(q))
+            .sum::<u128>(),
+            u128::from(u64::MAX - 1)
+        );
+    }
+}
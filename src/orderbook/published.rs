@@ -0,0 +1,448 @@
+//! Lock-free publication of immutable snapshots for readers.
+//!
+//! [`OrderBook::create_snapshot`] walks the live price levels, so many
+//! readers snapshotting at a high rate compete with the writer for the same
+//! data. When a [`SnapshotPublishPolicy`] is configured, the book instead
+//! publishes an immutable [`OrderBookSnapshot`] at the end of writes, and
+//! readers load the most recent one with
+//! [`OrderBook::latest_published_snapshot`]. Loading clones an
+//! [`Arc`](std::sync::Arc) out of an [`ArcSwap`](arc_swap::ArcSwap): it
+//! takes no lock and never waits for the writer.
+//!
+//! # Staleness
+//! A write is one outermost book operation (add, cancel, update, requeue,
+//! market order, mass cancel, auction uncross or snapshot restore), whether
+//! or not it succeeds; operations
+//! nested inside another one, and the orders it triggers, count as part of
+//! it. With a single writer:
+//!
+//! - [`SnapshotPublishPolicy::EveryWrite`] publishes after every write, so
+//!   the published snapshot lags the book by at most the write in progress.
+//! - [`SnapshotPublishPolicy::EveryWrites`] with `N` lags by at most `N - 1`
+//!   completed writes plus the write in progress.
+//! - [`SnapshotPublishPolicy::EveryMillis`] with `D` publishes at the end of
+//!   the first write at least `D` milliseconds after the previous
+//!   publication. Nothing publishes between writes, so the last changes
+//!   before the book goes quiet stay unpublished until the next write or
+//!   [`OrderBook::publish_snapshot`].
+//!
+//! Each thread counts its own outermost writes, so with concurrent writers a
+//! write publishes at its end even while writes on other threads are in
+//! flight, and the published snapshot may already miss their changes.
+
+use super::book::OrderBook;
+use super::snapshot::OrderBookSnapshot;
+use crate::utils::current_time_millis;
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tracing::trace;
+
+thread_local! {
+    /// Publishers of the books this thread has a write in progress on.
+    static WRITES_IN_PROGRESS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// When the book publishes a snapshot for lock-free readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SnapshotPublishPolicy {
+    /// Never publish automatically (default).
+    #[default]
+    Disabled,
+    /// Publish after every write.
+    EveryWrite,
+    /// Publish after every `N` writes; `0` disables publication.
+    EveryWrites(usize),
+    /// Publish after the first write at least this many milliseconds after
+    /// the previous publication.
+    EveryMillis(u64),
+}
+
+impl std::fmt::Display for SnapshotPublishPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotPublishPolicy::Disabled => write!(f, "Disabled"),
+            SnapshotPublishPolicy::EveryWrite => write!(f, "EveryWrite"),
+            SnapshotPublishPolicy::EveryWrites(writes) => write!(f, "EveryWrites({writes})"),
+            SnapshotPublishPolicy::EveryMillis(millis) => write!(f, "EveryMillis({millis})"),
+        }
+    }
+}
+
+/// Publication state: the current snapshot and the publish cadence.
+pub(super) struct SnapshotPublisher {
+    current: ArcSwap<OrderBookSnapshot>,
+    pub(super) policy: SnapshotPublishPolicy,
+    pending: AtomicUsize,
+    /// Clock reading of the last publication, in milliseconds.
+    last_published_ms: AtomicU64,
+    /// Time source for [`SnapshotPublishPolicy::EveryMillis`].
+    clock: fn() -> u64,
+}
+
+impl SnapshotPublisher {
+    /// Create a publisher holding an empty snapshot of `symbol`.
+    pub(super) fn new(symbol: &str) -> Self {
+        let empty = OrderBookSnapshot {
+            symbol: symbol.to_string(),
+            timestamp: 0,
+            bids: Vec::new(),
+            asks: Vec::new(),
+        };
+        Self {
+            current: ArcSwap::from_pointee(empty),
+            policy: SnapshotPublishPolicy::Disabled,
+            pending: AtomicUsize::new(0),
+            last_published_ms: AtomicU64::new(0),
+            clock: current_time_millis,
+        }
+    }
+
+    /// Returns `true` if the write that just completed should publish.
+    fn write_completed(&self) -> bool {
+        match self.policy {
+            SnapshotPublishPolicy::Disabled | SnapshotPublishPolicy::EveryWrites(0) => false,
+            SnapshotPublishPolicy::EveryWrite => true,
+            SnapshotPublishPolicy::EveryWrites(writes) => {
+                let pending = self.pending.fetch_add(1, Ordering::AcqRel) + 1;
+                if pending >= writes {
+                    self.pending.store(0, Ordering::Release);
+                    true
+                } else {
+                    false
+                }
+            }
+            SnapshotPublishPolicy::EveryMillis(millis) => {
+                let elapsed =
+                    (self.clock)().saturating_sub(self.last_published_ms.load(Ordering::Acquire));
+                elapsed >= millis
+            }
+        }
+    }
+
+    fn load(&self) -> Arc<OrderBookSnapshot> {
+        self.current.load_full()
+    }
+
+    fn store(&self, snapshot: OrderBookSnapshot) {
+        self.last_published_ms
+            .store((self.clock)(), Ordering::Release);
+        self.current.store(Arc::new(snapshot));
+    }
+
+    /// Identifies this publisher among the writes in progress on a thread.
+    fn key(&self) -> usize {
+        std::ptr::from_ref(self) as usize
+    }
+}
+
+/// Marks a book operation in progress on the current thread; the outermost
+/// one counts as a write when dropped.
+pub(super) struct WriteScope<'a, T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    book: &'a OrderBook<T>,
+    outermost: bool,
+}
+
+impl<T> Drop for WriteScope<'_, T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    fn drop(&mut self) {
+        if !self.outermost {
+            return;
+        }
+        // Still inside the write, so the orders it triggered count as part
+        // of it
+        if !std::thread::panicking() {
+            self.book.submit_deferred();
+        }
+        let publisher = &self.book.snapshot_publisher;
+        let key = publisher.key();
+        WRITES_IN_PROGRESS.with_borrow_mut(|writes| writes.retain(|write| *write != key));
+        if publisher.write_completed() {
+            self.book.publish_snapshot();
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set when full-depth snapshots are published for lock-free readers.
+    ///
+    /// The write count and the time window both restart from the change.
+    pub fn set_snapshot_publish_policy(&mut self, policy: SnapshotPublishPolicy) {
+        trace!(
+            "Order book {}: Setting snapshot publish policy to {}",
+            self.symbol, policy
+        );
+        let publisher = &mut self.snapshot_publisher;
+        publisher.policy = policy;
+        publisher.pending.store(0, Ordering::Release);
+        publisher
+            .last_published_ms
+            .store((publisher.clock)(), Ordering::Release);
+    }
+
+    /// Returns when full-depth snapshots are published.
+    #[must_use]
+    pub fn snapshot_publish_policy(&self) -> SnapshotPublishPolicy {
+        self.snapshot_publisher.policy
+    }
+
+    /// Publish a full-depth snapshot every `writes` writes; `0` (the
+    /// default) disables publication.
+    ///
+    /// Shorthand for [`set_snapshot_publish_policy`](Self::set_snapshot_publish_policy)
+    /// with [`SnapshotPublishPolicy::EveryWrites`].
+    pub fn set_snapshot_publish_interval(&mut self, writes: usize) {
+        let policy = match writes {
+            0 => SnapshotPublishPolicy::Disabled,
+            writes => SnapshotPublishPolicy::EveryWrites(writes),
+        };
+        self.set_snapshot_publish_policy(policy);
+    }
+
+    /// Returns the snapshot publish interval in writes: `1` for
+    /// [`SnapshotPublishPolicy::EveryWrite`], `0` if publication is disabled
+    /// or time based.
+    #[must_use]
+    pub fn snapshot_publish_interval(&self) -> usize {
+        match self.snapshot_publisher.policy {
+            SnapshotPublishPolicy::EveryWrite => 1,
+            SnapshotPublishPolicy::EveryWrites(writes) => writes,
+            SnapshotPublishPolicy::Disabled | SnapshotPublishPolicy::EveryMillis(_) => 0,
+        }
+    }
+
+    /// Returns the most recently published snapshot without locking.
+    ///
+    /// The snapshot is empty, with a zero timestamp, until the first
+    /// publication. See the [module docs](self) for the staleness bound.
+    #[must_use]
+    pub fn latest_published_snapshot(&self) -> Arc<OrderBookSnapshot> {
+        self.snapshot_publisher.load()
+    }
+
+    /// Publish a full-depth snapshot of the current state now, regardless of
+    /// the policy.
+    pub fn publish_snapshot(&self) {
+        self.snapshot_publisher
+            .store(self.create_snapshot(usize::MAX));
+    }
+
+    /// Returns `true` if this thread has a book operation in progress.
+    pub(super) fn write_in_progress(&self) -> bool {
+        let key = self.snapshot_publisher.key();
+        WRITES_IN_PROGRESS.with_borrow(|writes| writes.contains(&key))
+    }
+
+    /// Enter a book operation; see [`WriteScope`].
+    pub(super) fn write_scope(&self) -> WriteScope<'_, T> {
+        let key = self.snapshot_publisher.key();
+        let outermost = WRITES_IN_PROGRESS.with_borrow_mut(|writes| {
+            let outermost = !writes.contains(&key);
+            if outermost {
+                writes.push(key);
+            }
+            outermost
+        });
+        WriteScope {
+            book: self,
+            outermost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, Side, TimeInForce};
+    use std::sync::Barrier;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    static TEST_CLOCK_MS: AtomicU64 = AtomicU64::new(0);
+
+    fn test_clock() -> u64 {
+        TEST_CLOCK_MS.load(Ordering::Acquire)
+    }
+
+    fn bid(book: &OrderBook<()>, price: u128, quantity: u64) -> Id {
+        let id = Id::new_uuid();
+        book.add_limit_order(id, price, quantity, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_publication_is_disabled_by_default() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        bid(&book, 100, 10);
+        let published = book.latest_published_snapshot();
+        assert_eq!(published.symbol, "TEST");
+        assert!(published.bids.is_empty());
+
+        book.publish_snapshot();
+        assert_eq!(book.latest_published_snapshot().best_bid(), Some((100, 10)));
+    }
+
+    #[test]
+    fn test_snapshot_published_every_interval_writes() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_snapshot_publish_interval(2);
+        assert_eq!(book.snapshot_publish_interval(), 2);
+
+        let first = bid(&book, 100, 10);
+        assert!(book.latest_published_snapshot().bids.is_empty());
+        bid(&book, 101, 10);
+        assert_eq!(book.latest_published_snapshot().bids.len(), 2);
+
+        // An update is one write even though it cancels and re-adds
+        book.update_order(pricelevel::OrderUpdate::UpdatePrice {
+            order_id: first,
+            new_price: pricelevel::Price::new(99),
+        })
+        .unwrap();
+        assert_eq!(book.latest_published_snapshot().best_bid(), Some((101, 10)));
+        assert_eq!(book.latest_published_snapshot().bids.len(), 2);
+        book.cancel_order(first).unwrap();
+        assert_eq!(book.latest_published_snapshot().bids.len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_readers_never_see_torn_snapshot() {
+        const WRITES: u64 = 400;
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_snapshot_publish_interval(1);
+        let book = Arc::new(book);
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Write `k` rests quantity `k` at price `1_000 + k`, so a consistent
+        // view after `n` writes holds exactly the levels `1..=n`.
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let book = Arc::clone(&book);
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    let mut seen = 0usize;
+                    while !done.load(Ordering::Acquire) {
+                        let snapshot = book.latest_published_snapshot();
+                        let mut levels: Vec<(u128, u64)> = snapshot
+                            .bids
+                            .iter()
+                            .map(|level| (level.price(), level.visible_quantity()))
+                            .collect();
+                        levels.sort_unstable();
+                        for (k, (price, quantity)) in levels.iter().enumerate() {
+                            let k = k as u64 + 1;
+                            assert_eq!((*price, *quantity), (1_000 + u128::from(k), k));
+                        }
+                        assert!(levels.len() >= seen, "published view went backwards");
+                        seen = levels.len();
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for k in 1..=WRITES {
+            bid(&book, 1_000 + u128::from(k), k);
+        }
+        done.store(true, Ordering::Release);
+        for reader in readers {
+            assert!(reader.join().unwrap() <= WRITES as usize);
+        }
+        assert_eq!(book.latest_published_snapshot().bids.len(), WRITES as usize);
+    }
+
+    #[test]
+    fn test_write_in_flight_elsewhere_does_not_hold_back_publication() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_snapshot_publish_policy(SnapshotPublishPolicy::EveryWrite);
+        let (entered, release) = (Barrier::new(2), Barrier::new(2));
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let _write = book.write_scope();
+                entered.wait();
+                release.wait();
+            });
+            entered.wait();
+            bid(&book, 100, 10);
+            assert_eq!(book.latest_published_snapshot().bids.len(), 1);
+            release.wait();
+        });
+    }
+
+    #[test]
+    fn test_every_write_policy_publishes_each_write() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_snapshot_publish_policy(SnapshotPublishPolicy::EveryWrite);
+        assert_eq!(book.snapshot_publish_interval(), 1);
+        for k in 1..=3 {
+            bid(&book, 100 + k, 10);
+            assert_eq!(book.latest_published_snapshot().bids.len(), k as usize);
+        }
+    }
+
+    #[test]
+    fn test_uncross_and_restore_publish() {
+        use crate::orderbook::TradingState;
+
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_snapshot_publish_policy(SnapshotPublishPolicy::EveryWrite);
+        let empty = book.create_snapshot(usize::MAX);
+        book.set_trading_state(TradingState::Auction);
+        bid(&book, 105, 10);
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.latest_published_snapshot().asks.len(), 1);
+
+        book.run_auction_uncross().unwrap();
+        let published = book.latest_published_snapshot();
+        assert!(published.bids.is_empty() && published.asks.is_empty());
+
+        bid(&book, 90, 10);
+        let package = book.create_snapshot_package(usize::MAX).unwrap();
+        book.restore_from_snapshot(empty).unwrap();
+        assert!(book.latest_published_snapshot().bids.is_empty());
+        book.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(book.latest_published_snapshot().best_bid(), Some((90, 10)));
+    }
+
+    #[test]
+    fn test_every_millis_policy_publishes_per_time_window() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.snapshot_publisher.clock = test_clock;
+        TEST_CLOCK_MS.store(1_000, Ordering::Release);
+        book.set_snapshot_publish_policy(SnapshotPublishPolicy::EveryMillis(50));
+        assert_eq!(book.snapshot_publish_interval(), 0);
+        let published = |book: &OrderBook<()>| book.latest_published_snapshot().bids.len();
+
+        // Writes inside the window are held back
+        bid(&book, 100, 10);
+        TEST_CLOCK_MS.store(1_049, Ordering::Release);
+        bid(&book, 101, 10);
+        assert_eq!(published(&book), 0);
+
+        // The first write once the window has elapsed publishes everything
+        TEST_CLOCK_MS.store(1_050, Ordering::Release);
+        bid(&book, 102, 10);
+        assert_eq!(published(&book), 3);
+
+        // The window restarts from that publication
+        TEST_CLOCK_MS.store(1_099, Ordering::Release);
+        bid(&book, 103, 10);
+        assert_eq!(published(&book), 3);
+        TEST_CLOCK_MS.store(1_200, Ordering::Release);
+        assert_eq!(published(&book), 3);
+        bid(&book, 104, 10);
+        assert_eq!(published(&book), 5);
+    }
+}
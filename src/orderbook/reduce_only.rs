@@ -0,0 +1,208 @@
+//! Reduce-only orders.
+//!
+//! A reduce-only order must never increase its user's exposure. The book
+//! does not know positions, so it uses what the user has resting on the
+//! opposite side as a proxy: a reduce-only sell may add at most the
+//! quantity the user has resting on the bid, and vice versa.
+//! [`OrderBook::add_reduce_only_order`] rejects an order when that proxy is
+//! zero; a larger order is rejected or capped to the proxy depending on
+//! the book's [`ReduceOnlyPolicy`].
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::modifications::OrderQuantity;
+use pricelevel::{OrderType, Quantity};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::trace;
+
+/// What happens to a reduce-only order larger than the quantity it can
+/// reduce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ReduceOnlyPolicy {
+    /// Reject the order (default).
+    #[default]
+    Reject,
+    /// Reduce the order's quantity to what it can reduce.
+    Cap,
+}
+
+impl std::fmt::Display for ReduceOnlyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReduceOnlyPolicy::Reject => write!(f, "Reject"),
+            ReduceOnlyPolicy::Cap => write!(f, "Cap"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set what happens to a reduce-only order larger than the quantity it
+    /// can reduce.
+    pub fn set_reduce_only_policy(&mut self, policy: ReduceOnlyPolicy) {
+        trace!(
+            "Order book {}: Setting reduce-only policy to {}",
+            self.symbol, policy
+        );
+        self.reduce_only_policy = policy;
+    }
+
+    /// Returns what happens to a reduce-only order larger than the
+    /// quantity it can reduce.
+    #[must_use]
+    pub fn reduce_only_policy(&self) -> ReduceOnlyPolicy {
+        self.reduce_only_policy
+    }
+
+    /// Add `order` as a reduce-only order.
+    ///
+    /// The order may add at most the total quantity its user has resting on
+    /// the opposite side. Under [`ReduceOnlyPolicy::Cap`] a larger order is
+    /// reduced to that quantity, taking hidden quantity first, and then
+    /// added like [`add_order`](Self::add_order).
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::ReduceOnlyExceeded`] if the user has
+    /// nothing resting on the opposite side, or if the order is larger than
+    /// that under [`ReduceOnlyPolicy::Reject`]. Otherwise returns the
+    /// errors of [`add_order`](Self::add_order).
+    pub fn add_reduce_only_order(
+        &self,
+        mut order: OrderType<T>,
+    ) -> Result<Arc<OrderType<T>>, OrderBookError> {
+        let user_id = order.user_id();
+        let side = order.side();
+        let requested = order.total_quantity();
+        let reducible = self.user_resting_quantity(user_id, side.opposite());
+
+        if requested > reducible {
+            if reducible == 0 || self.reduce_only_policy == ReduceOnlyPolicy::Reject {
+                return Err(OrderBookError::ReduceOnlyExceeded {
+                    user_id,
+                    side,
+                    requested,
+                    reducible,
+                });
+            }
+            trace!(
+                "Order book {}: Capping reduce-only order {} from {} to {}",
+                self.symbol,
+                order.id(),
+                requested,
+                reducible
+            );
+            cap_total_quantity(&mut order, reducible);
+        }
+        self.add_order(order)
+    }
+}
+
+/// Reduce the total quantity of `order` to `total`, taking hidden quantity
+/// before visible quantity.
+fn cap_total_quantity<T>(order: &mut OrderType<T>, total: u64) {
+    match order {
+        OrderType::IcebergOrder {
+            visible_quantity,
+            hidden_quantity,
+            ..
+        } => {
+            let visible = visible_quantity.as_u64().min(total);
+            *visible_quantity = Quantity::new(visible);
+            *hidden_quantity = Quantity::new(total - visible);
+        }
+        _ => order.set_quantity(total),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Id, Price, Side, TimeInForce, TimestampMs};
+
+    const USER: Hash32 = Hash32([7; 32]);
+
+    fn order(price: u128, quantity: u64, side: Side) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: USER,
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    /// A book where the user has 30 resting on the bid.
+    fn book(policy: ReduceOnlyPolicy) -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_reduce_only_policy(policy);
+        book.add_order(order(100, 10, Side::Buy)).unwrap();
+        book.add_order(order(99, 20, Side::Buy)).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_reduce_only_order_is_capped_to_opposing_quantity() {
+        let book = book(ReduceOnlyPolicy::Cap);
+        assert_eq!(book.reduce_only_policy(), ReduceOnlyPolicy::Cap);
+
+        let added = book
+            .add_reduce_only_order(order(110, 50, Side::Sell))
+            .unwrap();
+        assert_eq!(added.total_quantity(), 30);
+        assert_eq!(book.best_ask_size(), Some((110, 30)));
+
+        // An iceberg gives up its hidden quantity first
+        let book = self::book(ReduceOnlyPolicy::Cap);
+        let added = book
+            .add_reduce_only_order(OrderType::IcebergOrder {
+                id: Id::new_uuid(),
+                price: Price::new(110),
+                visible_quantity: Quantity::new(10),
+                hidden_quantity: Quantity::new(40),
+                side: Side::Sell,
+                user_id: USER,
+                timestamp: TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            })
+            .unwrap();
+        assert_eq!(added.visible_quantity(), 10);
+        assert_eq!(added.hidden_quantity(), 20);
+    }
+
+    #[test]
+    fn test_reduce_only_order_adding_exposure_is_rejected() {
+        let book = book(ReduceOnlyPolicy::default());
+
+        // Nothing resting on the ask to reduce
+        assert!(matches!(
+            book.add_reduce_only_order(order(98, 5, Side::Buy)),
+            Err(OrderBookError::ReduceOnlyExceeded {
+                side: Side::Buy,
+                requested: 5,
+                reducible: 0,
+                ..
+            })
+        ));
+        // Larger than the resting bid under the default policy
+        assert!(matches!(
+            book.add_reduce_only_order(order(110, 31, Side::Sell)),
+            Err(OrderBookError::ReduceOnlyExceeded {
+                requested: 31,
+                reducible: 30,
+                ..
+            })
+        ));
+        assert_eq!(book.best_ask(), None);
+
+        book.add_reduce_only_order(order(110, 30, Side::Sell))
+            .unwrap();
+        assert_eq!(book.best_ask_size(), Some((110, 30)));
+    }
+}
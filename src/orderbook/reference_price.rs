@@ -0,0 +1,330 @@
+//! Reference price selection for price bands and stop triggers.
+//!
+//! Price bands and stop triggers need a reference price. Relying only on the
+//! last trade is fragile before the first trade occurs, so the active
+//! reference is chosen by a [`ReferencePricePolicy`]: a manually set price,
+//! the last trade price, or the current mid price.
+//!
+//! Manual reference prices are set with
+//! [`OrderBook::set_reference_price`] and can be journaled through
+//! [`SequencerCommand::SetReferencePrice`](crate::orderbook::sequencer::SequencerCommand::SetReferencePrice)
+//! so that replay reproduces the same band validation.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use tracing::trace;
+
+/// Basis points per unit (1 bps = 0.01%).
+const BASIS_POINTS_PER_UNIT: u128 = 10_000;
+
+/// Source of the active reference price.
+///
+/// # Examples
+///
+/// ```
+/// use orderbook_rs::{OrderBook, ReferencePricePolicy};
+///
+/// let mut book = OrderBook::<()>::new("BTC/USD");
+/// assert_eq!(book.reference_price_policy(), ReferencePricePolicy::Manual);
+///
+/// book.set_reference_price(100);
+/// assert_eq!(book.reference_price(), Some(100));
+///
+/// // Switch to last-trade: no trade yet, so no reference
+/// book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+/// assert_eq!(book.reference_price(), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ReferencePricePolicy {
+    /// Use the price set via [`OrderBook::set_reference_price`] (default).
+    #[default]
+    Manual,
+    /// Use the last trade price.
+    LastTrade,
+    /// Use the mid price (rounded down) of the current best bid and ask.
+    Mid,
+}
+
+impl std::fmt::Display for ReferencePricePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferencePricePolicy::Manual => write!(f, "Manual"),
+            ReferencePricePolicy::LastTrade => write!(f, "LastTrade"),
+            ReferencePricePolicy::Mid => write!(f, "Mid"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the manual reference price.
+    ///
+    /// The value is used as the active reference when the policy is
+    /// [`ReferencePricePolicy::Manual`]; it is stored regardless of the
+    /// current policy. Pending trailing stops are evaluated against the new
+    /// active reference.
+    pub fn set_reference_price(&self, price: u128) {
+        self.manual_reference_price.store(price);
+        self.has_manual_reference_price
+            .store(true, Ordering::SeqCst);
+        trace!(
+            "Order book {}: Set manual reference price to {}",
+            self.symbol, price
+        );
+        self.evaluate_trailing_stops();
+    }
+
+    /// Clear the manual reference price.
+    pub fn clear_reference_price(&self) {
+        self.has_manual_reference_price
+            .store(false, Ordering::SeqCst);
+    }
+
+    /// Returns the manually set reference price, if any, regardless of policy.
+    #[must_use]
+    pub fn manual_reference_price(&self) -> Option<u128> {
+        if self.has_manual_reference_price.load(Ordering::SeqCst) {
+            Some(self.manual_reference_price.load())
+        } else {
+            None
+        }
+    }
+
+    /// Set the policy that selects the active reference price.
+    pub fn set_reference_price_policy(&mut self, policy: ReferencePricePolicy) {
+        self.reference_price_policy = policy;
+    }
+
+    /// Returns the policy that selects the active reference price.
+    #[must_use]
+    pub fn reference_price_policy(&self) -> ReferencePricePolicy {
+        self.reference_price_policy
+    }
+
+    /// Returns the active reference price under the configured policy.
+    ///
+    /// # Returns
+    /// - `Manual`: the price set via [`set_reference_price`](Self::set_reference_price)
+    /// - `LastTrade`: the last trade price
+    /// - `Mid`: `(best_bid + best_ask) / 2`, rounded down
+    ///
+    /// `None` when the selected source is not available.
+    #[must_use]
+    pub fn reference_price(&self) -> Option<u128> {
+        match self.reference_price_policy {
+            ReferencePricePolicy::Manual => self.manual_reference_price(),
+            ReferencePricePolicy::LastTrade => self.last_trade_price(),
+            ReferencePricePolicy::Mid => match (self.best_bid(), self.best_ask()) {
+                (Some(bid), Some(ask)) => Some(bid / 2 + ask / 2 + (bid % 2 + ask % 2) / 2),
+                _ => None,
+            },
+        }
+    }
+
+    /// Set the price band width in basis points around the active reference.
+    ///
+    /// When set, orders priced further than `bps` basis points from the
+    /// active reference price are rejected with
+    /// [`OrderBookError::PriceOutsideBand`]. Orders are accepted while no
+    /// reference price is available. Pass `None` to disable the band.
+    pub fn set_price_band_bps(&mut self, bps: Option<u32>) {
+        self.price_band_bps = bps;
+    }
+
+    /// Returns the configured price band width in basis points, if any.
+    #[must_use]
+    pub fn price_band_bps(&self) -> Option<u32> {
+        self.price_band_bps
+    }
+
+    /// Returns the current inclusive `(lower, upper)` price band, if a band
+    /// is configured and a reference price is available.
+    #[must_use]
+    pub fn price_band(&self) -> Option<(u128, u128)> {
+        let bps = self.price_band_bps?;
+        let reference = self.reference_price()?;
+        let delta = reference
+            .checked_mul(u128::from(bps))
+            .map(|v| v / BASIS_POINTS_PER_UNIT)
+            .unwrap_or(u128::MAX);
+        Some((
+            reference.saturating_sub(delta),
+            reference.saturating_add(delta),
+        ))
+    }
+
+    /// Validate an order price against the price band.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::PriceOutsideBand`] when `price` is outside
+    /// the current band.
+    pub(super) fn check_price_band(&self, price: u128) -> Result<(), OrderBookError> {
+        if let Some((lower, upper)) = self.price_band()
+            && (price < lower || price > upper)
+        {
+            return Err(OrderBookError::PriceOutsideBand {
+                price,
+                reference: self.reference_price().unwrap_or_default(),
+                lower,
+                upper,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a trailing stop order would trigger at the active
+    /// reference price.
+    ///
+    /// Returns `false` for non-stop orders or when no reference price is
+    /// available.
+    #[cfg(feature = "special_orders")]
+    #[must_use]
+    pub fn is_stop_triggered(&self, order: &pricelevel::OrderType<T>) -> bool {
+        use super::repricing::RepricingOperations;
+
+        self.reference_price()
+            .is_some_and(|reference| self.should_trigger_trailing_stop(order, reference))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    #[test]
+    fn test_manual_reference_price() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(book.reference_price(), None);
+
+        book.set_reference_price(1000);
+        assert_eq!(book.reference_price(), Some(1000));
+        assert_eq!(book.manual_reference_price(), Some(1000));
+
+        book.clear_reference_price();
+        assert_eq!(book.reference_price(), None);
+    }
+
+    #[test]
+    fn test_last_trade_policy() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price(500);
+        book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        assert_eq!(book.reference_price(), None);
+
+        book.add_limit_order(Id::new_uuid(), 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .expect("add ask");
+        book.submit_market_order(Id::new_uuid(), 5, Side::Buy)
+            .expect("market buy");
+        assert_eq!(book.reference_price(), Some(1000));
+        // The manual price is still stored but not active
+        assert_eq!(book.manual_reference_price(), Some(500));
+    }
+
+    #[test]
+    fn test_mid_policy() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::Mid);
+
+        book.add_limit_order(Id::new_uuid(), 99, 10, Side::Buy, TimeInForce::Gtc, None)
+            .expect("add bid");
+        assert_eq!(book.reference_price(), None);
+
+        book.add_limit_order(Id::new_uuid(), 102, 10, Side::Sell, TimeInForce::Gtc, None)
+            .expect("add ask");
+        assert_eq!(book.reference_price(), Some(100));
+    }
+
+    #[test]
+    fn test_band_validates_against_manual_reference() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_price_band_bps(Some(1_000)); // 10%
+
+        // No reference yet: band is inactive
+        assert!(book.price_band().is_none());
+        book.add_limit_order(Id::new_uuid(), 5_000, 1, Side::Buy, TimeInForce::Gtc, None)
+            .expect("no reference, accepted");
+        let _ = book.cancel_all_orders();
+
+        book.set_reference_price(1_000);
+        assert_eq!(book.price_band(), Some((900, 1_100)));
+
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 900, 1, Side::Buy, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 1_100, 1, Side::Sell, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 1_101, 1, Side::Sell, TimeInForce::Gtc, None)
+            .expect_err("above band");
+        assert!(matches!(
+            err,
+            OrderBookError::PriceOutsideBand {
+                price: 1_101,
+                reference: 1_000,
+                lower: 900,
+                upper: 1_100
+            }
+        ));
+
+        let err = book
+            .add_limit_order(Id::new_uuid(), 899, 1, Side::Buy, TimeInForce::Gtc, None)
+            .expect_err("below band");
+        assert!(matches!(err, OrderBookError::PriceOutsideBand { .. }));
+
+        // Moving the manual reference moves the band
+        book.set_reference_price(1_200);
+        assert!(
+            book.add_limit_order(Id::new_uuid(), 1_250, 1, Side::Sell, TimeInForce::Gtc, None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_band_extreme_values_saturate() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_price_band_bps(Some(u32::MAX));
+        book.set_reference_price(u128::MAX / 2);
+
+        let (lower, upper) = book.price_band().expect("band");
+        assert_eq!(lower, 0);
+        assert_eq!(upper, u128::MAX);
+    }
+
+    #[cfg(feature = "special_orders")]
+    #[test]
+    fn test_stop_triggers_against_manual_reference() {
+        use pricelevel::{Hash32, OrderType, Price, Quantity, TimestampMs};
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let stop = OrderType::TrailingStop {
+            id: Id::new_uuid(),
+            price: Price::new(95),
+            quantity: Quantity::new(10),
+            side: Side::Sell,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            trail_amount: Quantity::new(5),
+            last_reference_price: Price::new(100),
+            extra_fields: (),
+        };
+
+        // No reference: nothing to trigger against
+        assert!(!book.is_stop_triggered(&stop));
+
+        book.set_reference_price(100);
+        assert!(!book.is_stop_triggered(&stop));
+
+        book.set_reference_price(95);
+        assert!(book.is_stop_triggered(&stop));
+    }
+}
@@ -0,0 +1,407 @@
+//! Batched command execution for throughput.
+//!
+//! Per-command overhead in a command loop is dominated by reading the clock
+//! and dispatching listeners. A [`BatchExecutor`] drains up to
+//! `max_batch_size` queued commands, executes them back to back under a
+//! single timestamp read, and only then hands the events to the listeners.
+//! Every event is still delivered individually and in order, and sequence
+//! numbers and results are identical to executing the commands one at a
+//! time.
+//!
+//! # Latency / throughput tradeoff
+//!
+//! Batching amortizes the fixed per-command cost across the batch, which
+//! raises throughput under load. In exchange, listeners see the first event
+//! of a batch only after the whole batch has executed, so the publication
+//! latency of early events grows with the batch size, and all events of a
+//! batch share one timestamp. A `max_batch_size` of 1 is the unbatched
+//! path. Batches are never padded: at low load a drain returns whatever is
+//! queued, so latency is only traded when commands are actually backing up.
+//!
+//! The `OrderBook - Batch Execution` benchmark executes 10,000 add orders
+//! at batch sizes 1, 16 and 256. Without listeners, the three sizes land
+//! within measurement noise of each other (about 2 µs per command), because
+//! executing the command dominates a single clock read. The gain grows
+//! with the fixed cost per dispatch, i.e. with the number and weight of
+//! registered listeners.
+
+use super::executor::{contingent_events, execute_command};
+use super::listener::{DispatchOutcome, EventListeners};
+use super::metrics::SequencerMetrics;
+use super::priority::{CommandPriority, prioritize_cancels};
+use super::types::{SequencerCommand, SequencerEvent};
+use crate::orderbook::OrderBook;
+use std::collections::VecDeque;
+
+/// Nanoseconds since the Unix epoch, or 0 if the clock is before it.
+fn timestamp_ns() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// Executes queued commands in batches under one sequence.
+#[derive(Debug, Clone)]
+pub struct BatchExecutor {
+    max_batch_size: usize,
+    next_sequence: u64,
+    priority: CommandPriority,
+    metrics: Option<SequencerMetrics>,
+}
+
+impl BatchExecutor {
+    /// Create an executor draining at most `max_batch_size` commands per
+    /// batch (at least 1). The first event gets sequence number 0.
+    #[must_use]
+    pub fn new(max_batch_size: usize) -> Self {
+        Self {
+            max_batch_size: max_batch_size.max(1),
+            next_sequence: 0,
+            priority: CommandPriority::Fifo,
+            metrics: None,
+        }
+    }
+
+    /// Service pending commands under `priority` instead of in submission
+    /// order. See the [`priority`](super::priority) module for the
+    /// determinism implications.
+    #[must_use]
+    pub fn with_command_priority(mut self, priority: CommandPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Record every event this executor emits in `metrics`.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: SequencerMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the counters events are recorded in, if any.
+    #[must_use]
+    pub fn metrics(&self) -> Option<&SequencerMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Returns the order in which pending commands are serviced.
+    #[must_use]
+    pub fn command_priority(&self) -> CommandPriority {
+        self.priority
+    }
+
+    /// Returns the maximum number of commands executed per batch.
+    #[must_use]
+    pub fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+
+    /// Returns the sequence number the next event will receive.
+    #[must_use]
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Drain up to `max_batch_size` commands from the front of `pending`,
+    /// execute them against `book` and return their events.
+    ///
+    /// Each command's event is followed by one
+    /// [`SequencerCommand::ContingentTriggered`] event per contingent order
+    /// the command caused the book to submit. The clock is read once for
+    /// the whole batch. Under [`CommandPriority::CancelsFirst`] the whole
+    /// buffer is reordered before the batch is drained.
+    ///
+    /// Commands are applied one at a time in sequence order: a cancel sees
+    /// every fill of the commands sequenced before it and none of those
+    /// after it.
+    pub fn execute_batch<T>(
+        &mut self,
+        book: &OrderBook<T>,
+        pending: &mut VecDeque<SequencerCommand<T>>,
+    ) -> Vec<SequencerEvent<T>>
+    where
+        T: Clone + Send + Sync + Default + 'static,
+    {
+        if self.priority == CommandPriority::CancelsFirst {
+            prioritize_cancels(pending);
+        }
+        let count = pending.len().min(self.max_batch_size);
+        let timestamp_ns = timestamp_ns();
+
+        let mut events = Vec::with_capacity(count);
+        for command in pending.drain(..count) {
+            let result = execute_command(book, &command);
+            let triggered = contingent_events(book);
+            for (command, result) in std::iter::once((command, result)).chain(triggered) {
+                let sequence_num = self.next_sequence;
+                self.next_sequence = self.next_sequence.saturating_add(1);
+                let event = SequencerEvent {
+                    sequence_num,
+                    timestamp_ns,
+                    command,
+                    result,
+                };
+                if let Some(metrics) = &self.metrics {
+                    metrics.record(&event);
+                }
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Execute one batch, then deliver each of its events to `listeners`.
+    ///
+    /// Returns the events and [`DispatchOutcome::Shutdown`] if a listener
+    /// asked the loop to stop; events after that point are not delivered.
+    pub fn execute_and_dispatch<T>(
+        &mut self,
+        book: &OrderBook<T>,
+        pending: &mut VecDeque<SequencerCommand<T>>,
+        listeners: &EventListeners<T>,
+    ) -> (Vec<SequencerEvent<T>>, DispatchOutcome)
+    where
+        T: Clone + Send + Sync + Default + 'static,
+    {
+        let events = self.execute_batch(book, pending);
+        for event in &events {
+            if listeners.dispatch(event) == DispatchOutcome::Shutdown {
+                return (events, DispatchOutcome::Shutdown);
+            }
+        }
+        (events, DispatchOutcome::Continue)
+    }
+}
+
+impl Default for BatchExecutor {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::sequencer::types::SequencerResult;
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+    use std::sync::{Arc, Mutex};
+
+    fn commands() -> Vec<SequencerCommand<()>> {
+        let mut commands = Vec::new();
+        let mut ids = Vec::new();
+        for i in 0..20u64 {
+            let id = Id::new_uuid();
+            ids.push(id);
+            let (price, side) = if i % 2 == 0 {
+                (100 - (i % 5) as u128, Side::Buy)
+            } else {
+                (101 + (i % 5) as u128, Side::Sell)
+            };
+            commands.push(SequencerCommand::AddOrder(OrderType::Standard {
+                id,
+                price: Price::new(price),
+                quantity: Quantity::new(10),
+                side,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(i),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            }));
+        }
+        commands.push(SequencerCommand::CancelOrder(ids[3]));
+        commands.push(SequencerCommand::CancelOrder(ids[3]));
+        commands.push(SequencerCommand::MarketOrder {
+            id: Id::new_uuid(),
+            quantity: 25,
+            side: Side::Buy,
+        });
+        commands
+    }
+
+    fn outcome(result: &SequencerResult) -> String {
+        match result {
+            SequencerResult::TradeExecuted { trade_result } => format!(
+                "trade {}",
+                trade_result.match_result.executed_quantity().unwrap_or(0)
+            ),
+            other => format!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_batched_results_match_unbatched_path() {
+        let commands = commands();
+        let unbatched_book: OrderBook<()> = OrderBook::new("TEST");
+        let mut unbatched = BatchExecutor::default();
+        let mut pending: VecDeque<_> = commands.clone().into();
+        let mut expected = Vec::new();
+        while !pending.is_empty() {
+            expected.extend(unbatched.execute_batch(&unbatched_book, &mut pending));
+        }
+
+        let batched_book: OrderBook<()> = OrderBook::new("TEST");
+        let mut batched = BatchExecutor::new(8);
+        let mut pending: VecDeque<_> = commands.into();
+        let mut events = Vec::new();
+        let mut batches = 0;
+        while !pending.is_empty() {
+            let batch = batched.execute_batch(&batched_book, &mut pending);
+            assert!(batch.len() <= 8);
+            assert!(
+                batch
+                    .iter()
+                    .all(|e| e.timestamp_ns == batch[0].timestamp_ns)
+            );
+            events.extend(batch);
+            batches += 1;
+        }
+        assert_eq!(batches, 3);
+
+        assert_eq!(events.len(), expected.len());
+        for (actual, expected) in events.iter().zip(&expected) {
+            assert_eq!(actual.sequence_num, expected.sequence_num);
+            assert_eq!(outcome(&actual.result), outcome(&expected.result));
+        }
+        assert_eq!(batched.next_sequence(), unbatched.next_sequence());
+        assert_eq!(
+            batched_book.create_snapshot(usize::MAX).bids.len(),
+            unbatched_book.create_snapshot(usize::MAX).bids.len()
+        );
+        assert_eq!(batched_book.best_ask(), unbatched_book.best_ask());
+    }
+
+    #[test]
+    fn test_events_are_recorded_in_metrics() {
+        let metrics = SequencerMetrics::new();
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let mut executor = BatchExecutor::new(32).with_metrics(metrics.clone());
+        let mut pending: VecDeque<_> = commands().into();
+        let events = executor.execute_batch(&book, &mut pending);
+
+        assert_eq!(metrics.commands_processed(), events.len() as u64);
+        // The second cancel of the same order is rejected
+        assert_eq!(metrics.rejections(), 1);
+        assert_eq!(
+            metrics.current_sequence(),
+            Some(executor.next_sequence() - 1)
+        );
+    }
+
+    #[test]
+    fn test_listeners_receive_every_event_after_the_batch() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let mut listeners: EventListeners<()> = EventListeners::default();
+        listeners.add(Arc::new(move |event: &SequencerEvent<()>| {
+            sink.lock().unwrap().push(event.sequence_num);
+        }));
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let mut executor = BatchExecutor::new(16);
+        let mut pending: VecDeque<_> = commands().into();
+
+        let (events, outcome) = executor.execute_and_dispatch(&book, &mut pending, &listeners);
+        assert_eq!(outcome, DispatchOutcome::Continue);
+        assert_eq!(events.len(), 16);
+        assert_eq!(pending.len(), 7);
+        assert_eq!(*seen.lock().unwrap(), (0..16).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_cancel_is_ordered_after_preceding_match() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let maker = Id::new_uuid();
+        let mut pending: VecDeque<_> = vec![
+            SequencerCommand::AddOrder(OrderType::Standard {
+                id: maker,
+                price: Price::new(100),
+                quantity: Quantity::new(10),
+                side: Side::Sell,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            }),
+            SequencerCommand::MarketOrder {
+                id: Id::new_uuid(),
+                quantity: 4,
+                side: Side::Buy,
+            },
+            SequencerCommand::CancelOrder(maker),
+            SequencerCommand::MarketOrder {
+                id: Id::new_uuid(),
+                quantity: 4,
+                side: Side::Buy,
+            },
+        ]
+        .into();
+
+        let events = BatchExecutor::new(8).execute_batch(&book, &mut pending);
+        let outcomes: Vec<String> = events.iter().map(|event| outcome(&event.result)).collect();
+        assert_eq!(outcomes[1], "trade 4");
+        assert!(matches!(
+            events[2].result,
+            SequencerResult::OrderCancelled { order_id } if order_id == maker
+        ));
+        // The market order sequenced after the cancel finds nothing to fill
+        assert!(matches!(events[3].result, SequencerResult::Rejected { .. }));
+        let sequences: Vec<u64> = events.iter().map(|event| event.sequence_num).collect();
+        assert_eq!(sequences, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cancels_first_services_cancels_before_queued_adds() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let resting: Vec<Id> = (0..2)
+            .map(|_| {
+                let id = Id::new_uuid();
+                book.add_limit_order(id, 99, 10, Side::Buy, TimeInForce::Gtc, None)
+                    .unwrap();
+                id
+            })
+            .collect();
+
+        // add a, cancel r0, add b, cancel r1, in submission order
+        let adds: Vec<_> = commands().into_iter().take(2).collect();
+        let mut pending: VecDeque<_> = vec![
+            adds[0].clone(),
+            SequencerCommand::CancelOrder(resting[0]),
+            adds[1].clone(),
+            SequencerCommand::CancelOrder(resting[1]),
+        ]
+        .into();
+
+        let mut executor =
+            BatchExecutor::new(2).with_command_priority(CommandPriority::CancelsFirst);
+        assert_eq!(executor.command_priority(), CommandPriority::CancelsFirst);
+        let events = executor.execute_batch(&book, &mut pending);
+        let cancelled: Vec<_> = events
+            .iter()
+            .map(|event| match event.result {
+                SequencerResult::OrderCancelled { order_id } => Some(order_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cancelled, [Some(resting[0]), Some(resting[1])]);
+        assert_eq!(
+            events.iter().map(|e| e.sequence_num).collect::<Vec<_>>(),
+            [0, 1]
+        );
+        assert_eq!(pending.len(), 2);
+        assert!(
+            pending
+                .iter()
+                .all(|c| matches!(c, SequencerCommand::AddOrder(_)))
+        );
+
+        // The default services the same buffer in submission order
+        let mut pending: VecDeque<_> = vec![adds[0].clone(), adds[1].clone()].into();
+        pending.insert(1, SequencerCommand::CancelOrder(Id::new_uuid()));
+        let events = BatchExecutor::new(1).execute_batch(&book, &mut pending);
+        assert!(matches!(
+            events[0].result,
+            SequencerResult::OrderRested { .. }
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+//! Opt-in coalescing of queued modify/cancel commands.
+//!
+//! When a client modifies the same order several times before the sequencer
+//! loop catches up, applying each modification in turn wastes matching work.
+//! [`coalesce_commands`] collapses back-to-back [`SequencerCommand::UpdateOrder`]
+//! and cancel commands targeting the same order into the final intent.
+//!
+//! Coalescing changes observable semantics: intermediate prices never reach
+//! the book, so they can neither trade nor appear in market data. It is
+//! therefore never applied implicitly; a command loop must call
+//! [`coalesce_commands`] explicitly on its pending queue. Each
+//! [`CoalescedCommand`] records how many original commands it replaces so
+//! the audit trail can account for every submitted command.
+
+use super::types::SequencerCommand;
+use pricelevel::{Id, OrderUpdate};
+
+/// A command produced by [`coalesce_commands`].
+#[derive(Debug, Clone)]
+pub struct CoalescedCommand<T> {
+    /// The command to execute.
+    pub command: SequencerCommand<T>,
+    /// Number of original commands folded into `command` (at least 1).
+    pub coalesced_count: usize,
+}
+
+/// Collapse consecutive modify/cancel commands for the same order.
+///
+/// Only adjacent commands are merged, and only when both target the same
+/// order id and the earlier one is an [`SequencerCommand::UpdateOrder`].
+/// The merged command expresses the final intent:
+///
+/// - a cancel supersedes any preceding modification;
+/// - `Replace` and `UpdatePriceAndQuantity` supersede any preceding
+///   modification;
+/// - `UpdatePrice` and `UpdateQuantity` override only the field they set,
+///   keeping the other field from the preceding modification.
+///
+/// Commands after a cancel are never merged into it, and all other
+/// commands pass through unchanged. Relative order is preserved.
+#[must_use]
+pub fn coalesce_commands<T>(
+    commands: impl IntoIterator<Item = SequencerCommand<T>>,
+) -> Vec<CoalescedCommand<T>> {
+    let mut out: Vec<CoalescedCommand<T>> = Vec::new();
+
+    for command in commands {
+        if let Some(last) = out.last_mut()
+            && let SequencerCommand::UpdateOrder(previous) = &last.command
+            && let Some(merged) = merge(previous, &command)
+        {
+            last.command = merged;
+            last.coalesced_count += 1;
+            continue;
+        }
+        out.push(CoalescedCommand {
+            command,
+            coalesced_count: 1,
+        });
+    }
+
+    out
+}
+
+/// Merge `next` into a preceding update, or `None` if they cannot be merged.
+fn merge<T>(previous: &OrderUpdate, next: &SequencerCommand<T>) -> Option<SequencerCommand<T>> {
+    let previous_id = update_order_id(previous);
+    if matches!(previous, OrderUpdate::Cancel { .. }) {
+        return None;
+    }
+
+    let update = match next {
+        SequencerCommand::CancelOrder(id) if *id == previous_id => {
+            return Some(SequencerCommand::CancelOrder(*id));
+        }
+        SequencerCommand::CancelOrderIdempotent(id) if *id == previous_id => {
+            return Some(SequencerCommand::CancelOrderIdempotent(*id));
+        }
+        SequencerCommand::UpdateOrder(update) if update_order_id(update) == previous_id => update,
+        _ => return None,
+    };
+
+    let merged = match (previous, update) {
+        (_, OrderUpdate::Cancel { .. })
+        | (_, OrderUpdate::Replace { .. })
+        | (_, OrderUpdate::UpdatePriceAndQuantity { .. })
+        | (OrderUpdate::UpdatePrice { .. }, OrderUpdate::UpdatePrice { .. })
+        | (OrderUpdate::UpdateQuantity { .. }, OrderUpdate::UpdateQuantity { .. }) => *update,
+        (
+            OrderUpdate::UpdateQuantity { new_quantity, .. }
+            | OrderUpdate::UpdatePriceAndQuantity { new_quantity, .. },
+            OrderUpdate::UpdatePrice { new_price, .. },
+        ) => OrderUpdate::UpdatePriceAndQuantity {
+            order_id: previous_id,
+            new_price: *new_price,
+            new_quantity: *new_quantity,
+        },
+        (
+            OrderUpdate::UpdatePrice { new_price, .. }
+            | OrderUpdate::UpdatePriceAndQuantity { new_price, .. },
+            OrderUpdate::UpdateQuantity { new_quantity, .. },
+        ) => OrderUpdate::UpdatePriceAndQuantity {
+            order_id: previous_id,
+            new_price: *new_price,
+            new_quantity: *new_quantity,
+        },
+        (
+            OrderUpdate::Replace { quantity, side, .. },
+            OrderUpdate::UpdatePrice { new_price, .. },
+        ) => OrderUpdate::Replace {
+            order_id: previous_id,
+            price: *new_price,
+            quantity: *quantity,
+            side: *side,
+        },
+        (
+            OrderUpdate::Replace { price, side, .. },
+            OrderUpdate::UpdateQuantity { new_quantity, .. },
+        ) => OrderUpdate::Replace {
+            order_id: previous_id,
+            price: *price,
+            quantity: *new_quantity,
+            side: *side,
+        },
+        (OrderUpdate::Cancel { .. }, _) => return None,
+    };
+
+    Some(SequencerCommand::UpdateOrder(merged))
+}
+
+pub(super) fn update_order_id(update: &OrderUpdate) -> Id {
+    match update {
+        OrderUpdate::UpdatePrice { order_id, .. }
+        | OrderUpdate::UpdateQuantity { order_id, .. }
+        | OrderUpdate::UpdatePriceAndQuantity { order_id, .. }
+        | OrderUpdate::Cancel { order_id }
+        | OrderUpdate::Replace { order_id, .. } => *order_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderBook;
+    use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+    use pricelevel::{Price, Quantity, Side, TimeInForce};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn update_price(order_id: Id, price: u128) -> SequencerCommand<()> {
+        SequencerCommand::UpdateOrder(OrderUpdate::UpdatePrice {
+            order_id,
+            new_price: Price::new(price),
+        })
+    }
+
+    fn update_quantity(order_id: Id, quantity: u64) -> SequencerCommand<()> {
+        SequencerCommand::UpdateOrder(OrderUpdate::UpdateQuantity {
+            order_id,
+            new_quantity: Quantity::new(quantity),
+        })
+    }
+
+    #[test]
+    fn test_three_modifies_result_in_one_book_mutation() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let mutations = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&mutations);
+        book.set_price_level_listener(Arc::new(move |_: PriceLevelChangedEvent| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let queued = vec![
+            update_quantity(id, 8),
+            update_quantity(id, 6),
+            update_quantity(id, 4),
+        ];
+        let coalesced = coalesce_commands(queued);
+        assert_eq!(coalesced.len(), 1);
+        assert_eq!(coalesced[0].coalesced_count, 3);
+
+        for entry in coalesced {
+            if let SequencerCommand::UpdateOrder(update) = entry.command {
+                book.update_order(update).unwrap();
+            }
+        }
+
+        assert_eq!(mutations.load(Ordering::SeqCst), 1);
+        let order = book.get_order(id).expect("order");
+        assert_eq!(order.visible_quantity(), 4);
+    }
+
+    #[test]
+    fn test_price_and_quantity_merge() {
+        let id = Id::new_uuid();
+        let coalesced = coalesce_commands(vec![update_price(id, 101), update_quantity(id, 3)]);
+        assert_eq!(coalesced.len(), 1);
+        assert!(matches!(
+            coalesced[0].command,
+            SequencerCommand::UpdateOrder(OrderUpdate::UpdatePriceAndQuantity {
+                order_id,
+                new_price,
+                new_quantity,
+            }) if order_id == id && new_price == Price::new(101) && new_quantity == Quantity::new(3)
+        ));
+    }
+
+    #[test]
+    fn test_cancel_supersedes_modifies() {
+        let id = Id::new_uuid();
+        let coalesced = coalesce_commands(vec![
+            update_price(id, 101),
+            update_price(id, 102),
+            SequencerCommand::CancelOrder(id),
+        ]);
+        assert_eq!(coalesced.len(), 1);
+        assert_eq!(coalesced[0].coalesced_count, 3);
+        assert!(matches!(coalesced[0].command, SequencerCommand::CancelOrder(c) if c == id));
+    }
+
+    #[test]
+    fn test_non_adjacent_or_other_orders_are_kept() {
+        let a = Id::new_uuid();
+        let b = Id::new_uuid();
+        let coalesced = coalesce_commands(vec![
+            update_price(a, 101),
+            update_price(b, 201),
+            update_price(a, 102),
+            SequencerCommand::CancelOrder(a),
+            update_price(a, 103),
+        ]);
+        // a, b, (a + cancel), a-after-cancel
+        assert_eq!(coalesced.len(), 4);
+        let counts: Vec<usize> = coalesced.iter().map(|c| c.coalesced_count).collect();
+        assert_eq!(counts, vec![1, 1, 2, 1]);
+    }
+}
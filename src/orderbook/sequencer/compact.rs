@@ -0,0 +1,232 @@
+//! Compact journal events for memory-constrained deployments.
+//!
+//! A [`SequencerEvent`] keeps the full [`SequencerResult`] of its command,
+//! including trade details and snapshots, which replay never reads. A
+//! [`CompactEvent`] keeps only what deterministic replay needs: the
+//! sequence number, timestamp, command, whether the command was rejected,
+//! whether a market order was parked and what self-trade prevention
+//! cancelled. [`CompactJournal`] stores
+//! compact events and serves them back as full events with a
+//! [`SequencerResult::Unrecorded`] result, so it can be replayed with
+//! [`ReplayEngine`](super::ReplayEngine) like any other journal.
+
+use super::error::JournalError;
+use super::journal::{Journal, JournalEntry, JournalReadIter};
+use super::types::{SequencerCommand, SequencerEvent, SequencerResult};
+use crate::orderbook::stp::STPTriggered;
+use pricelevel::Id;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// The part of a [`SequencerResult`] needed for replay.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompactResult {
+    /// The command was applied.
+    Applied,
+    /// The command was rejected and is skipped on replay.
+    Rejected {
+        /// Human-readable reason for the rejection.
+        reason: Box<str>,
+    },
+    /// A market order was parked on an empty opposite side.
+    MarketOrderParked {
+        /// The identifier of the parked market order.
+        order_id: Id,
+    },
+    /// The command added an order and self-trade prevention triggered.
+    SelfTradePrevented {
+        /// The identifier of the added order.
+        order_id: Id,
+        /// What self-trade prevention cancelled.
+        outcome: STPTriggered,
+    },
+}
+
+impl CompactResult {
+    /// Compact the result of an applied command.
+    fn applied(result: &SequencerResult) -> Self {
+        let order_id = match result {
+            SequencerResult::MarketOrderParked { order_id } => {
+                return Self::MarketOrderParked {
+                    order_id: *order_id,
+                };
+            }
+            SequencerResult::STPTriggered { order_id, .. } => *order_id,
+            SequencerResult::TradeExecuted { trade_result }
+            | SequencerResult::PartiallyFilledResting { trade_result, .. }
+            | SequencerResult::ParkedOrdersFilled { trade_result, .. } => {
+                trade_result.match_result.order_id()
+            }
+            _ => return Self::Applied,
+        };
+        match result.stp_outcome() {
+            Some(outcome) => Self::SelfTradePrevented { order_id, outcome },
+            None => Self::Applied,
+        }
+    }
+}
+
+/// A journal event without the detailed result of its command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactEvent<T> {
+    /// Monotonically increasing sequence number.
+    pub sequence_num: u64,
+    /// Nanosecond timestamp assigned by the Sequencer.
+    pub timestamp_ns: u64,
+    /// The command that was executed.
+    pub command: SequencerCommand<T>,
+    /// Whether the command was applied or rejected.
+    pub result: CompactResult,
+}
+
+impl<T> From<SequencerEvent<T>> for CompactEvent<T> {
+    fn from(event: SequencerEvent<T>) -> Self {
+        let result = match event.result {
+            SequencerResult::Rejected { reason } => CompactResult::Rejected {
+                reason: reason.into_boxed_str(),
+            },
+            ref result => CompactResult::applied(result),
+        };
+        Self {
+            sequence_num: event.sequence_num,
+            timestamp_ns: event.timestamp_ns,
+            command: event.command,
+            result,
+        }
+    }
+}
+
+impl<T: Clone> From<&SequencerEvent<T>> for CompactEvent<T> {
+    fn from(event: &SequencerEvent<T>) -> Self {
+        let result = match &event.result {
+            SequencerResult::Rejected { reason } => CompactResult::Rejected {
+                reason: reason.as_str().into(),
+            },
+            result => CompactResult::applied(result),
+        };
+        Self {
+            sequence_num: event.sequence_num,
+            timestamp_ns: event.timestamp_ns,
+            command: event.command.clone(),
+            result,
+        }
+    }
+}
+
+impl<T> From<CompactEvent<T>> for SequencerEvent<T> {
+    /// Expand a compact event. Applied commands get a
+    /// [`SequencerResult::Unrecorded`] result, or a
+    /// [`SequencerResult::STPTriggered`] one if self-trade prevention
+    /// triggered, even when the order also traded.
+    fn from(event: CompactEvent<T>) -> Self {
+        let result = match event.result {
+            CompactResult::Applied => SequencerResult::Unrecorded,
+            CompactResult::MarketOrderParked { order_id } => {
+                SequencerResult::MarketOrderParked { order_id }
+            }
+            CompactResult::SelfTradePrevented { order_id, outcome } => {
+                SequencerResult::STPTriggered { order_id, outcome }
+            }
+            CompactResult::Rejected { reason } => SequencerResult::Rejected {
+                reason: reason.into_string(),
+            },
+        };
+        Self {
+            sequence_num: event.sequence_num,
+            timestamp_ns: event.timestamp_ns,
+            command: event.command,
+            result,
+        }
+    }
+}
+
+/// An in-memory journal that stores [`CompactEvent`]s.
+///
+/// Appends enforce strictly increasing sequence numbers like
+/// [`InMemoryJournal`](super::InMemoryJournal). Events are read back as
+/// full [`SequencerEvent`]s whose applied results are
+/// [`SequencerResult::Unrecorded`], so lookups that rely on result details,
+/// such as the maker ids of fills in
+/// [`Journal::events_for_order`], only see the command.
+#[derive(Debug)]
+pub struct CompactJournal<T> {
+    events: RwLock<Vec<CompactEvent<T>>>,
+}
+
+impl<T> Default for CompactJournal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CompactJournal<T> {
+    /// Creates a new empty compact journal.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of events in the journal.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.read().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Returns `true` if the journal contains no events.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.read().map(|e| e.is_empty()).unwrap_or(true)
+    }
+}
+
+impl<T> Journal<T> for CompactJournal<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+{
+    fn append(&self, event: &SequencerEvent<T>) -> Result<(), JournalError> {
+        let mut events = self.events.write().map_err(|_| JournalError::Io {
+            message: "failed to acquire write lock".to_string(),
+            path: None,
+        })?;
+        if let Some(last) = events.last()
+            && event.sequence_num <= last.sequence_num
+        {
+            return Err(JournalError::OutOfOrderAppend {
+                expected_gt: last.sequence_num,
+                found: event.sequence_num,
+            });
+        }
+        events.push(CompactEvent::from(event));
+        Ok(())
+    }
+
+    fn read_from(&self, sequence: u64) -> Result<JournalReadIter<T>, JournalError> {
+        let events = self.events.read().map_err(|_| JournalError::Io {
+            message: "failed to acquire read lock".to_string(),
+            path: None,
+        })?;
+
+        let filtered: Vec<_> = events
+            .iter()
+            .filter(|e| e.sequence_num >= sequence)
+            .map(|event| {
+                Ok(JournalEntry {
+                    event: SequencerEvent::from(event.clone()),
+                    stored_crc: 0,
+                })
+            })
+            .collect();
+
+        Ok(Box::new(filtered.into_iter()))
+    }
+
+    fn last_sequence(&self) -> Option<u64> {
+        self.events.read().ok()?.last().map(|e| e.sequence_num)
+    }
+
+    fn verify_integrity(&self) -> Result<(), JournalError> {
+        Ok(())
+    }
+}
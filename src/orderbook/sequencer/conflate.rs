@@ -0,0 +1,287 @@
+//! Conflation of level updates for slow market-data subscribers.
+//!
+//! A subscriber that cannot keep up with every incremental update should
+//! not fall arbitrarily behind. A [`DeltaConflator`] sits between the
+//! command loop and one subscriber: the loop pushes each sequenced event
+//! with the levels it changed, as returned by
+//! [`execute_command_with_changes`](super::execute_command_with_changes),
+//! and the subscriber calls [`DeltaConflator::flush`] whenever it is ready
+//! for more. Repeated updates to a level collapse into its latest quantity,
+//! so the pending state is bounded by the number of levels rather than the
+//! number of events.
+//!
+//! Trades are never conflated: every event that executed fills is kept, in
+//! sequence order, until the next flush.
+//!
+//! With a level granularity above `1`, levels are reported in buckets of
+//! that many price units, each at the lowest price of its bucket with the
+//! summed visible quantity of the levels in it. The conflator tracks the
+//! quantity of every level it has been told about, so it must see every
+//! event from an empty book, or from a book whose levels it was seeded with.
+
+use super::types::{SequencerEvent, SequencerResult};
+use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+use crate::orderbook::level_changes::LevelChangeSet;
+use crate::orderbook::trade::TradeResult;
+use pricelevel::Side;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Everything a subscriber missed since its previous flush.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflatedDeltas {
+    /// The latest visible quantity of each changed level or bucket, bids
+    /// first then asks, each in ascending price order. A quantity of `0`
+    /// means the level or bucket is now empty.
+    pub levels: Vec<PriceLevelChangedEvent>,
+    /// Every trade executed since the previous flush as
+    /// `(sequence_num, trade_result)`, in sequence order.
+    pub trades: Vec<(u64, TradeResult)>,
+    /// The sequence number of the latest event pushed before the flush, if
+    /// any event was pushed.
+    pub last_sequence_num: Option<u64>,
+}
+
+impl ConflatedDeltas {
+    /// Returns `true` if no level changed and no trade executed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty() && self.trades.is_empty()
+    }
+}
+
+/// Per-subscriber buffer that conflates level updates until flushed.
+#[derive(Debug, Clone)]
+pub struct DeltaConflator {
+    level_granularity: u128,
+    bids: BTreeMap<u128, u64>,
+    asks: BTreeMap<u128, u64>,
+    dirty_bids: BTreeSet<u128>,
+    dirty_asks: BTreeSet<u128>,
+    trades: Vec<(u64, TradeResult)>,
+    last_sequence_num: Option<u64>,
+}
+
+impl DeltaConflator {
+    /// Create a conflator reporting levels in buckets of `level_granularity`
+    /// price units. `0` and `1` both report every price level on its own.
+    #[must_use]
+    pub fn new(level_granularity: u128) -> Self {
+        Self {
+            level_granularity: level_granularity.max(1),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            dirty_bids: BTreeSet::new(),
+            dirty_asks: BTreeSet::new(),
+            trades: Vec::new(),
+            last_sequence_num: None,
+        }
+    }
+
+    /// Returns the bucket size in price units.
+    #[must_use]
+    pub fn level_granularity(&self) -> u128 {
+        self.level_granularity
+    }
+
+    /// Record the current quantity of a level without reporting it, e.g. to
+    /// seed the conflator with the book a subscriber already has.
+    pub fn seed_level(&mut self, side: Side, price: u128, quantity: u64) {
+        let levels = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        if quantity == 0 {
+            levels.remove(&price);
+        } else {
+            levels.insert(price, quantity);
+        }
+    }
+
+    /// Buffer one sequenced event and the levels it changed.
+    pub fn push<T>(&mut self, event: &SequencerEvent<T>, changes: &LevelChangeSet) {
+        for change in changes
+            .added
+            .iter()
+            .chain(&changes.updated)
+            .chain(&changes.removed)
+        {
+            self.seed_level(change.side, change.price, change.quantity);
+            let bucket = self.bucket(change.price);
+            match change.side {
+                Side::Buy => self.dirty_bids.insert(bucket),
+                Side::Sell => self.dirty_asks.insert(bucket),
+            };
+        }
+        if let SequencerResult::TradeExecuted { trade_result }
+        | SequencerResult::PartiallyFilledResting { trade_result, .. } = &event.result
+            && trade_result.summary.fill_count > 0
+        {
+            self.trades.push((event.sequence_num, trade_result.clone()));
+        }
+        self.last_sequence_num = Some(event.sequence_num);
+    }
+
+    /// Returns the number of levels or buckets with an unreported change.
+    #[must_use]
+    pub fn pending_levels(&self) -> usize {
+        self.dirty_bids.len() + self.dirty_asks.len()
+    }
+
+    /// Returns the number of unreported trades.
+    #[must_use]
+    pub fn pending_trades(&self) -> usize {
+        self.trades.len()
+    }
+
+    /// Returns `true` if there is nothing to report.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending_levels() == 0 && self.trades.is_empty()
+    }
+
+    /// Take everything buffered since the previous flush.
+    pub fn flush(&mut self) -> ConflatedDeltas {
+        let mut levels = Vec::with_capacity(self.pending_levels());
+        for (side, dirty, book) in [
+            (Side::Buy, &mut self.dirty_bids, &self.bids),
+            (Side::Sell, &mut self.dirty_asks, &self.asks),
+        ] {
+            for bucket in std::mem::take(dirty) {
+                let end = bucket.saturating_add(self.level_granularity);
+                let quantity = book
+                    .range(bucket..end)
+                    .fold(0u64, |total, (_, &quantity)| total.saturating_add(quantity));
+                levels.push(PriceLevelChangedEvent {
+                    side,
+                    price: bucket,
+                    quantity,
+                });
+            }
+        }
+        ConflatedDeltas {
+            levels,
+            trades: std::mem::take(&mut self.trades),
+            last_sequence_num: self.last_sequence_num,
+        }
+    }
+
+    fn bucket(&self, price: u128) -> u128 {
+        price - price % self.level_granularity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::OrderBook;
+    use crate::orderbook::sequencer::{SequencerCommand, execute_command_with_changes};
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, TimeInForce, TimestampMs};
+
+    fn limit(price: u128, quantity: u64, side: Side) -> SequencerCommand<()> {
+        SequencerCommand::AddOrder(OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+    }
+
+    fn run(
+        book: &OrderBook<()>,
+        conflator: &mut DeltaConflator,
+        sequence_num: u64,
+        command: SequencerCommand<()>,
+    ) {
+        let (result, changes) = execute_command_with_changes(book, &command);
+        let event = SequencerEvent {
+            sequence_num,
+            timestamp_ns: 0,
+            command,
+            result,
+        };
+        conflator.push(&event, &changes);
+    }
+
+    #[test]
+    fn test_slow_subscriber_gets_latest_levels_and_every_trade() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let mut conflator = DeltaConflator::new(1);
+        let mut sequence_num = 0;
+        let mut next = || {
+            sequence_num += 1;
+            sequence_num
+        };
+
+        for _ in 0..3 {
+            run(&book, &mut conflator, next(), limit(101, 10, Side::Sell));
+        }
+        run(&book, &mut conflator, next(), limit(99, 5, Side::Buy));
+        // Two trades against the ask, the second one partial
+        run(&book, &mut conflator, next(), limit(101, 10, Side::Buy));
+        run(&book, &mut conflator, next(), limit(101, 4, Side::Buy));
+        assert_eq!(conflator.pending_levels(), 2);
+        assert_eq!(conflator.pending_trades(), 2);
+
+        let deltas = conflator.flush();
+        assert_eq!(
+            deltas.levels,
+            vec![
+                PriceLevelChangedEvent {
+                    side: Side::Buy,
+                    price: 99,
+                    quantity: 5,
+                },
+                PriceLevelChangedEvent {
+                    side: Side::Sell,
+                    price: 101,
+                    quantity: 16,
+                },
+            ]
+        );
+        let trades: Vec<_> = deltas
+            .trades
+            .iter()
+            .map(|(sequence_num, trade)| (*sequence_num, trade.summary.executed_quantity))
+            .collect();
+        assert_eq!(trades, vec![(5, 10), (6, 4)]);
+        assert_eq!(deltas.last_sequence_num, Some(6));
+        assert!(conflator.is_empty());
+        assert!(conflator.flush().is_empty());
+
+        // A level emptied between flushes is reported at zero
+        run(&book, &mut conflator, next(), limit(99, 5, Side::Sell));
+        let deltas = conflator.flush();
+        assert_eq!(deltas.levels.len(), 1);
+        assert_eq!(deltas.levels[0].quantity, 0);
+        assert_eq!(deltas.trades.len(), 1);
+    }
+
+    #[test]
+    fn test_granularity_reports_bucket_totals() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let mut conflator = DeltaConflator::new(10);
+        assert_eq!(conflator.level_granularity(), 10);
+        run(&book, &mut conflator, 1, limit(101, 10, Side::Sell));
+        run(&book, &mut conflator, 2, limit(109, 5, Side::Sell));
+        run(&book, &mut conflator, 3, limit(110, 7, Side::Sell));
+        let deltas = conflator.flush();
+        let levels: Vec<_> = deltas
+            .levels
+            .iter()
+            .map(|level| (level.price, level.quantity))
+            .collect();
+        assert_eq!(levels, vec![(100, 15), (110, 7)]);
+
+        // An update to one level reports the whole bucket
+        run(&book, &mut conflator, 4, limit(101, 4, Side::Buy));
+        let deltas = conflator.flush();
+        assert_eq!(deltas.levels[0].price, 100);
+        assert_eq!(deltas.levels[0].quantity, 11);
+        assert_eq!(DeltaConflator::new(0).level_granularity(), 1);
+    }
+}
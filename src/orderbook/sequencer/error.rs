@@ -73,6 +73,30 @@ pub enum JournalError {
         /// Description of the header problem.
         message: String,
     },
+
+    /// A write was attempted on a read-only journal.
+    ReadOnly {
+        /// The file or directory backing the journal.
+        path: PathBuf,
+    },
+
+    /// An appended event's sequence number is not greater than the last
+    /// sequence number in the journal.
+    OutOfOrderAppend {
+        /// The last sequence number in the journal, which the appended
+        /// event had to exceed.
+        expected_gt: u64,
+        /// The sequence number of the rejected event.
+        found: u64,
+    },
+
+    /// A read started below the earliest event the journal still retains.
+    Trimmed {
+        /// The sequence number that was requested.
+        requested: u64,
+        /// The earliest sequence number still in the journal.
+        earliest_available: u64,
+    },
 }
 
 impl fmt::Display for JournalError {
@@ -130,6 +154,26 @@ impl fmt::Display for JournalError {
                     "invalid journal entry header at offset {offset}: {message}"
                 )
             }
+            JournalError::ReadOnly { path } => {
+                write!(f, "journal at {} is read-only", path.display())
+            }
+            JournalError::OutOfOrderAppend { expected_gt, found } => {
+                write!(
+                    f,
+                    "out-of-order journal append: sequence {found} is not \
+                     greater than last sequence {expected_gt}"
+                )
+            }
+            JournalError::Trimmed {
+                requested,
+                earliest_available,
+            } => {
+                write!(
+                    f,
+                    "sequence {requested} has been trimmed from the journal: \
+                     earliest available is {earliest_available}"
+                )
+            }
         }
     }
 }
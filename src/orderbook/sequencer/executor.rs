@@ -0,0 +1,643 @@
+//! Execution of sequencer commands against an order book.
+//!
+//! [`execute_command`] applies one [`SequencerCommand`] to an [`OrderBook`]
+//! and captures the outcome as the [`SequencerResult`] a command loop
+//! journals alongside it. Failures are reported as
+//! [`SequencerResult::Rejected`] rather than returned, so every command
+//! produces exactly one event. Contingent orders submitted by the book while
+//! executing a command are collected with [`contingent_events`] and
+//! journaled as events of their own right after it.
+//! [`execute_command_with_changes`] also reports the price levels the
+//! command changed, for incremental market data. After every command
+//! pending trailing stops are evaluated against the reference price, which
+//! an add or cancel can move under the mid policy (see
+//! [`OrderBook::evaluate_trailing_stops`]), and the book is checked for a
+//! crossed state; see [`OrderBook::check_crossed`].
+//!
+//! [`SequencerCommand::UpdateConfig`] changes settings that are not safe to
+//! modify through a shared reference, so it is only applied by
+//! [`execute_command_mut`]; [`execute_command`] rejects it.
+
+use super::coalesce::update_order_id;
+use super::types::{ConfigChange, SequencerCommand, SequencerResult};
+use crate::orderbook::stp::STPTriggered;
+use crate::orderbook::trade::TradeResult;
+use crate::orderbook::{
+    AddOrderResult, CancelOutcome, LevelChangeSet, MarketOrderEmptyBookPolicy, OrderBook,
+    OrderBookError, RemainderOutcome, RemainderPolicy,
+};
+
+/// Apply `command` to `book` and return its result.
+pub fn execute_command<T>(book: &OrderBook<T>, command: &SequencerCommand<T>) -> SequencerResult
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    let rejected = |e: OrderBookError| SequencerResult::Rejected {
+        reason: e.to_string(),
+    };
+
+    let result = match command {
+        SequencerCommand::AddOrder(order) => {
+            match book.add_order_with_remainder(order.clone(), RemainderPolicy::Rest) {
+                Ok(added) => add_order_result(book, added),
+                Err(e) => add_order_rejected(e),
+            }
+        }
+        SequencerCommand::CancelOrder(id) => match book.cancel_order(*id) {
+            Ok(Some(_)) => SequencerResult::OrderCancelled { order_id: *id },
+            Ok(None) => rejected(OrderBookError::OrderNotFound(id.to_string())),
+            Err(e) => rejected(e),
+        },
+        SequencerCommand::CancelOrderIdempotent(id) => match book.cancel_order_idempotent(*id) {
+            CancelOutcome::Cancelled(_) => SequencerResult::OrderCancelled { order_id: *id },
+            CancelOutcome::AlreadyGone => SequencerResult::CancelAlreadyGone { order_id: *id },
+            CancelOutcome::Error(e) => rejected(e),
+        },
+        SequencerCommand::CancelOrderAs { order_id, user_id } => {
+            match book.cancel_order_as(*order_id, *user_id) {
+                Ok(_) => SequencerResult::OrderCancelled {
+                    order_id: *order_id,
+                },
+                Err(e) => rejected(e),
+            }
+        }
+        SequencerCommand::AddContingent {
+            trigger_order_id,
+            order,
+        } => match book.add_contingent(*trigger_order_id, order.clone()) {
+            Ok(()) => SequencerResult::ContingentAdded {
+                trigger_order_id: *trigger_order_id,
+                order_id: order.id(),
+            },
+            Err(e) => rejected(e),
+        },
+        SequencerCommand::AddTrailingStop { order, trail } => {
+            match book.add_trailing_stop(order.clone(), *trail) {
+                Ok(trigger_price) => SequencerResult::TrailingStopAdded {
+                    order_id: order.id(),
+                    trigger_price,
+                },
+                Err(e) => rejected(e),
+            }
+        }
+        SequencerCommand::ContingentTriggered { .. } => {
+            rejected(OrderBookError::InvalidOperation {
+                message: "contingent submissions are emitted by the book, not executed".to_string(),
+            })
+        }
+        SequencerCommand::UpdateOrder(update) => match book.update_order(*update) {
+            Ok(_) => SequencerResult::OrderUpdated {
+                order_id: update_order_id(update),
+            },
+            Err(e) => rejected(e),
+        },
+        SequencerCommand::RequeueOrder(id) => match book.requeue_order(*id) {
+            Ok(new_order_id) => SequencerResult::OrderRequeued {
+                order_id: *id,
+                new_order_id,
+            },
+            Err(e) => rejected(e),
+        },
+        SequencerCommand::MarketOrder { id, quantity, side } => {
+            match book.submit_market_order(*id, *quantity, *side) {
+                Ok(match_result)
+                    if match_result.trades().as_vec().is_empty()
+                        && book.market_order_empty_book_policy()
+                            == MarketOrderEmptyBookPolicy::ParkAsPending =>
+                {
+                    SequencerResult::MarketOrderParked { order_id: *id }
+                }
+                Ok(match_result) => SequencerResult::TradeExecuted {
+                    trade_result: TradeResult::with_fees(
+                        book.symbol().to_string(),
+                        match_result,
+                        book.fee_schedule(),
+                    ),
+                },
+                Err(e) => rejected(e),
+            }
+        }
+        SequencerCommand::Snapshot { depth } => {
+            match book.create_snapshot(*depth).content_checksum() {
+                Ok(checksum) => SequencerResult::SnapshotTaken {
+                    depth: *depth,
+                    checksum,
+                },
+                Err(e) => rejected(e),
+            }
+        }
+        SequencerCommand::CancelAll => SequencerResult::MassCancelled {
+            result: book.cancel_all_orders(),
+        },
+        SequencerCommand::SetReferencePrice { price } => {
+            book.set_reference_price(*price);
+            SequencerResult::ReferencePriceSet { price: *price }
+        }
+        SequencerCommand::SetExternalBbo { bbo } => {
+            book.set_external_bbo(*bbo);
+            SequencerResult::ExternalBboSet { bbo: *bbo }
+        }
+        SequencerCommand::UpdateConfig(_) => rejected(OrderBookError::InvalidOperation {
+            message: "configuration changes need exclusive access to the book".to_string(),
+        }),
+        SequencerCommand::CancelBySide { side } => SequencerResult::MassCancelled {
+            result: book.cancel_orders_by_side(*side),
+        },
+        SequencerCommand::CancelByUser { user_id } => SequencerResult::MassCancelled {
+            result: book.cancel_orders_by_user(*user_id),
+        },
+        SequencerCommand::CancelByPriceRange {
+            side,
+            min_price,
+            max_price,
+        } => SequencerResult::MassCancelled {
+            result: book.cancel_orders_by_price_range(*side, *min_price, *max_price),
+        },
+    };
+    book.evaluate_trailing_stops();
+    book.check_crossed();
+    result
+}
+
+/// Apply `command` to `book` and return its result, including
+/// [`SequencerCommand::UpdateConfig`] changes.
+pub fn execute_command_mut<T>(
+    book: &mut OrderBook<T>,
+    command: &SequencerCommand<T>,
+) -> SequencerResult
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    match command {
+        SequencerCommand::UpdateConfig(change) => match apply_config_change(book, change) {
+            Ok(()) => SequencerResult::ConfigUpdated {
+                change: change.clone(),
+            },
+            Err(e) => SequencerResult::Rejected {
+                reason: e.to_string(),
+            },
+        },
+        _ => execute_command(book, command),
+    }
+}
+
+/// Apply `change` to `book` if the setting still has its expected value.
+///
+/// # Errors
+/// Returns [`OrderBookError::InvalidOperation`], leaving the book
+/// unchanged, if the current value differs from the expected one or the
+/// new tick size is zero.
+pub fn apply_config_change<T>(
+    book: &mut OrderBook<T>,
+    change: &ConfigChange,
+) -> Result<(), OrderBookError>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    let conflict = |setting: &str, current: String, expected: String| {
+        Err(OrderBookError::InvalidOperation {
+            message: format!("{setting} is {current}, expected {expected}"),
+        })
+    };
+
+    match change {
+        ConfigChange::TickSize {
+            expected,
+            tick_size,
+        } => {
+            let current = book.tick_schedule();
+            let matches = match expected {
+                Some(tick) => current == [(0, *tick)],
+                None => current.is_empty(),
+            };
+            if !matches {
+                return conflict(
+                    "tick schedule",
+                    format!("{current:?}"),
+                    format!("{expected:?}"),
+                );
+            }
+            book.set_tick_schedule(tick_size.map(|tick| vec![(0, tick)]).unwrap_or_default())
+        }
+        ConfigChange::FeeSchedule {
+            expected,
+            fee_schedule,
+        } => {
+            let current = book.fee_schedule();
+            if current != *expected {
+                return conflict(
+                    "fee schedule",
+                    format!("{current:?}"),
+                    format!("{expected:?}"),
+                );
+            }
+            book.set_fee_schedule(*fee_schedule);
+            Ok(())
+        }
+        ConfigChange::ReferencePricePolicy { expected, policy } => {
+            let current = book.reference_price_policy();
+            if current != *expected {
+                return conflict(
+                    "reference price policy",
+                    current.to_string(),
+                    expected.to_string(),
+                );
+            }
+            book.set_reference_price_policy(*policy);
+            book.evaluate_trailing_stops();
+            Ok(())
+        }
+        ConfigChange::MarketOrderEmptyBookPolicy { expected, policy } => {
+            let current = book.market_order_empty_book_policy();
+            if current != *expected {
+                return conflict(
+                    "market order empty book policy",
+                    current.to_string(),
+                    expected.to_string(),
+                );
+            }
+            book.set_market_order_empty_book_policy(*policy);
+            Ok(())
+        }
+    }
+}
+
+/// Apply `command` to `book` and return its result with the price levels
+/// it changed; see [`OrderBook::record_level_changes`].
+pub fn execute_command_with_changes<T>(
+    book: &OrderBook<T>,
+    command: &SequencerCommand<T>,
+) -> (SequencerResult, LevelChangeSet)
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    book.record_level_changes(|| execute_command(book, command))
+}
+
+/// Drain the contingent orders `book` submitted since the last call and
+/// return them as [`SequencerCommand::ContingentTriggered`] commands with
+/// their results, in submission order.
+pub fn contingent_events<T>(book: &OrderBook<T>) -> Vec<(SequencerCommand<T>, SequencerResult)>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    book.take_triggered_contingents()
+        .into_iter()
+        .map(|triggered| {
+            let result = match triggered.outcome {
+                Ok(added) => add_order_result(book, added),
+                Err(e) => add_order_rejected(e),
+            };
+            let command = SequencerCommand::ContingentTriggered {
+                trigger_order_id: triggered.trigger_order_id,
+                order: triggered.order,
+            };
+            (command, result)
+        })
+        .collect()
+}
+
+/// Classify an accepted add by whether it matched and whether it rests.
+fn add_order_result<T>(book: &OrderBook<T>, added: AddOrderResult<T>) -> SequencerResult
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    let order_id = added.order.id();
+    let matched = !added.match_result.trades().as_vec().is_empty();
+    let trade_result = || {
+        TradeResult::with_fees(
+            book.symbol().to_string(),
+            added.match_result.clone(),
+            book.fee_schedule(),
+        )
+        .with_stp(added.stp)
+    };
+
+    if !added.parked_fills.is_empty() {
+        let parked_fills = added
+            .parked_fills
+            .iter()
+            .map(|fill| {
+                TradeResult::with_fees(book.symbol().to_string(), fill.clone(), book.fee_schedule())
+            })
+            .collect();
+        return SequencerResult::ParkedOrdersFilled {
+            order_id,
+            trade_result: trade_result(),
+            parked_fills,
+        };
+    }
+    if !matched && let Some(outcome) = added.stp {
+        return SequencerResult::STPTriggered { order_id, outcome };
+    }
+    match added.remainder {
+        RemainderOutcome::Rested { .. } if !matched => SequencerResult::OrderRested { order_id },
+        RemainderOutcome::Rested { .. } => SequencerResult::PartiallyFilledResting {
+            order_id,
+            trade_result: trade_result(),
+        },
+        RemainderOutcome::Cancelled { quantity } if !matched => SequencerResult::OrderUnfilled {
+            order_id,
+            cancelled_quantity: quantity,
+        },
+        RemainderOutcome::Filled | RemainderOutcome::Cancelled { .. } => {
+            SequencerResult::TradeExecuted {
+                trade_result: trade_result(),
+            }
+        }
+    }
+}
+
+/// Report a failed add, keeping self-trade prevention apart from other
+/// rejections since it may have cancelled resting orders.
+fn add_order_rejected(e: OrderBookError) -> SequencerResult {
+    match e {
+        OrderBookError::SelfTradePrevented {
+            mode,
+            taker_order_id,
+            ..
+        } => SequencerResult::STPTriggered {
+            order_id: taker_order_id,
+            outcome: STPTriggered::rejected(mode),
+        },
+        e => SequencerResult::Rejected {
+            reason: e.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::{FeeSchedule, ReferencePricePolicy};
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn add(price: u128, quantity: u64, side: Side) -> SequencerCommand<()> {
+        SequencerCommand::AddOrder(OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+    }
+
+    fn executed(result: &SequencerResult) -> u64 {
+        match result {
+            SequencerResult::TradeExecuted { trade_result }
+            | SequencerResult::PartiallyFilledResting { trade_result, .. } => {
+                trade_result.summary().executed_quantity
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_execute_command_reports_outcomes() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let result = execute_command(&book, &SequencerCommand::CancelOrder(id));
+        assert!(matches!(result, SequencerResult::OrderCancelled { order_id } if order_id == id));
+
+        // Cancelling again is a rejection, the idempotent form is not
+        let result = execute_command(&book, &SequencerCommand::CancelOrder(id));
+        assert!(matches!(result, SequencerResult::Rejected { .. }));
+        let result = execute_command(&book, &SequencerCommand::CancelOrderIdempotent(id));
+        assert!(
+            matches!(result, SequencerResult::CancelAlreadyGone { order_id } if order_id == id)
+        );
+
+        // Cancels on behalf of a user are rejected unless the user owns the order
+        let owner = Hash32::new([1; 32]);
+        let id = Id::new_uuid();
+        book.add_limit_order_with_user(id, 100, 10, Side::Buy, TimeInForce::Gtc, owner, None)
+            .unwrap();
+        let cancel_as = |user_id| SequencerCommand::CancelOrderAs {
+            order_id: id,
+            user_id,
+        };
+        let result = execute_command(&book, &cancel_as(Hash32::new([2; 32])));
+        assert!(matches!(result, SequencerResult::Rejected { .. }));
+        let result = execute_command(&book, &cancel_as(owner));
+        assert!(matches!(result, SequencerResult::OrderCancelled { order_id } if order_id == id));
+
+        let result = execute_command(&book, &SequencerCommand::<()>::Snapshot { depth: 5 });
+        assert!(
+            matches!(result, SequencerResult::SnapshotTaken { depth: 5, ref checksum } if *checksum == book.create_snapshot(5).content_checksum().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_add_order_reports_rest_and_match_outcomes() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+
+        // Nothing to match against: the order rests untouched
+        let result = execute_command(&book, &add(100, 10, Side::Sell));
+        assert!(matches!(result, SequencerResult::OrderRested { .. }));
+
+        // Crosses part of the ask and rests the remainder
+        let command = add(100, 15, Side::Buy);
+        let SequencerCommand::AddOrder(ref order) = command else {
+            unreachable!()
+        };
+        let bid_id = order.id();
+        let result = execute_command(&book, &command);
+        assert!(
+            matches!(result, SequencerResult::PartiallyFilledResting { order_id, .. } if order_id == bid_id)
+        );
+        assert_eq!(executed(&result), 10);
+        assert_eq!(book.best_bid(), Some(100));
+
+        // Fully matched against the resting bid: nothing rests
+        let result = execute_command(&book, &add(100, 5, Side::Sell));
+        assert!(matches!(result, SequencerResult::TradeExecuted { .. }));
+        assert_eq!(executed(&result), 5);
+        assert_eq!(book.best_bid(), None);
+        assert_eq!(book.best_ask(), None);
+    }
+
+    #[test]
+    fn test_unfilled_one_shot_add_reports_cancel() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        execute_command(&book, &add(100, 10, Side::Sell));
+        let one_shot = |price| {
+            let id = Id::new_uuid();
+            let added = book
+                .add_limit_order_with_remainder(
+                    id,
+                    price,
+                    15,
+                    Side::Buy,
+                    TimeInForce::Gtc,
+                    Hash32::zero(),
+                    RemainderPolicy::Cancel,
+                    None,
+                )
+                .unwrap();
+            (id, add_order_result(&book, added))
+        };
+
+        // Below the ask nothing trades and the whole order is cancelled
+        let (id, result) = one_shot(99);
+        assert!(matches!(
+            result,
+            SequencerResult::OrderUnfilled { order_id, cancelled_quantity: 15 } if order_id == id
+        ));
+        assert_eq!(book.best_bid(), None);
+
+        // A partial fill still reports its trades
+        let (_, result) = one_shot(100);
+        assert!(matches!(result, SequencerResult::TradeExecuted { .. }));
+        assert_eq!(executed(&result), 10);
+    }
+
+    #[test]
+    fn test_ask_releasing_parked_order_reports_its_fills() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_market_order_empty_book_policy(MarketOrderEmptyBookPolicy::ParkAsPending);
+        let parked_id = Id::new_uuid();
+        let result = execute_command(
+            &book,
+            &SequencerCommand::MarketOrder {
+                id: parked_id,
+                quantity: 6,
+                side: Side::Buy,
+            },
+        );
+        assert!(matches!(result, SequencerResult::MarketOrderParked { .. }));
+
+        // The ask rests and is then partly consumed by the parked buy
+        let command = add(100, 10, Side::Sell);
+        let SequencerCommand::AddOrder(ref order) = command else {
+            unreachable!()
+        };
+        let ask_id = order.id();
+        let result = execute_command(&book, &command);
+        let SequencerResult::ParkedOrdersFilled {
+            order_id,
+            trade_result,
+            parked_fills,
+        } = result
+        else {
+            panic!("expected parked fills, got {result:?}");
+        };
+        assert_eq!(order_id, ask_id);
+        assert_eq!(trade_result.summary().executed_quantity, 0);
+        assert_eq!(parked_fills.len(), 1);
+        assert_eq!(parked_fills[0].match_result.order_id(), parked_id);
+        assert_eq!(parked_fills[0].summary().executed_quantity, 6);
+        assert_eq!(book.get_order(ask_id).unwrap().visible_quantity(), 4);
+    }
+
+    #[test]
+    fn test_execute_command_with_changes_reports_levels() {
+        use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+        let level = |side, price, quantity| PriceLevelChangedEvent {
+            side,
+            price,
+            quantity,
+        };
+        let book: OrderBook<()> = OrderBook::new("TEST");
+
+        // An add at a new price creates one level
+        let (_, changes) = execute_command_with_changes(&book, &add(100, 10, Side::Sell));
+        assert_eq!(changes.added, vec![level(Side::Sell, 100, 10)]);
+        assert_eq!(changes.len(), 1);
+
+        // Joining an existing level updates it
+        let (_, changes) = execute_command_with_changes(&book, &add(100, 5, Side::Sell));
+        assert_eq!(changes.updated, vec![level(Side::Sell, 100, 15)]);
+        assert_eq!(changes.len(), 1);
+
+        // A partial fill updates the maker level to its new quantity
+        let (_, changes) = execute_command_with_changes(
+            &book,
+            &SequencerCommand::MarketOrder {
+                id: Id::new_uuid(),
+                quantity: 4,
+                side: Side::Buy,
+            },
+        );
+        assert_eq!(changes.updated, vec![level(Side::Sell, 100, 11)]);
+        assert_eq!(changes.len(), 1);
+
+        // Cancelling the only order at a price removes its level
+        let command = add(90, 10, Side::Buy);
+        let SequencerCommand::AddOrder(ref order) = command else {
+            unreachable!()
+        };
+        let bid_id = order.id();
+        execute_command(&book, &command);
+        let (result, changes) =
+            execute_command_with_changes(&book, &SequencerCommand::CancelOrder(bid_id));
+        assert!(matches!(result, SequencerResult::OrderCancelled { .. }));
+        assert_eq!(changes.removed, vec![level(Side::Buy, 90, 0)]);
+        assert_eq!(changes.len(), 1);
+
+        // A rejected command changes nothing
+        let (_, changes) =
+            execute_command_with_changes(&book, &SequencerCommand::CancelOrder(bid_id));
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_config_change_is_compare_and_swap() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        let tick = |expected, tick_size| {
+            SequencerCommand::UpdateConfig(ConfigChange::TickSize {
+                expected,
+                tick_size,
+            })
+        };
+
+        // Shared access cannot reconfigure the book
+        let result = execute_command(&book, &tick(None, Some(5)));
+        assert!(matches!(result, SequencerResult::Rejected { .. }));
+        assert_eq!(book.tick_size(), None);
+
+        let result = execute_command_mut(&mut book, &tick(None, Some(5)));
+        assert!(matches!(result, SequencerResult::ConfigUpdated { .. }));
+        assert_eq!(book.tick_size(), Some(5));
+        // A stale expectation loses the race
+        let result = execute_command_mut(&mut book, &tick(None, Some(10)));
+        assert!(
+            matches!(result, SequencerResult::Rejected { reason } if reason.contains("expected None"))
+        );
+        assert_eq!(book.tick_size(), Some(5));
+        assert!(matches!(
+            apply_config_change(
+                &mut book,
+                &ConfigChange::TickSize {
+                    expected: Some(5),
+                    tick_size: Some(0),
+                }
+            ),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+        execute_command_mut(&mut book, &tick(Some(5), None));
+        assert_eq!(book.tick_size(), None);
+
+        let fees = Some(FeeSchedule::new(-1, 5));
+        let change = ConfigChange::FeeSchedule {
+            expected: None,
+            fee_schedule: fees,
+        };
+        assert!(apply_config_change(&mut book, &change).is_ok());
+        assert_eq!(book.fee_schedule(), fees);
+        assert!(apply_config_change(&mut book, &change).is_err());
+
+        let change = ConfigChange::ReferencePricePolicy {
+            expected: ReferencePricePolicy::Manual,
+            policy: ReferencePricePolicy::Mid,
+        };
+        assert!(apply_config_change(&mut book, &change).is_ok());
+        assert_eq!(book.reference_price_policy(), ReferencePricePolicy::Mid);
+        assert!(apply_config_change(&mut book, &change).is_err());
+    }
+}
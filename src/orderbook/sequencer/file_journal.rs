@@ -667,94 +667,9 @@ where
     /// Try to decode the next entry from the current mmap at `self.offset`.
     fn decode_next(&mut self) -> Option<Result<JournalEntry<T>, JournalError>> {
         let mmap = self.mmap.as_ref()?;
-        let data = &mmap[..];
-
-        if self.offset.checked_add(ENTRY_HEADER_SIZE).is_none()
-            || self.offset + ENTRY_HEADER_SIZE > data.len()
-        {
-            return None;
-        }
-
-        // Read entry_length
-        let el_bytes = data.get(self.offset..self.offset + 4)?;
-        let entry_length =
-            u32::from_le_bytes([el_bytes[0], el_bytes[1], el_bytes[2], el_bytes[3]]) as usize;
-
-        if entry_length == 0 {
-            return None; // End of written data
-        }
-
-        let entry_end = self.offset.checked_add(4)?.checked_add(entry_length)?;
-        if entry_end > data.len() {
-            return None; // Truncated
-        }
-
-        let payload_start = self.offset.checked_add(4)?;
-        let crc_start = entry_end.checked_sub(ENTRY_CRC_SIZE)?;
-
-        // Read stored CRC
-        let crc_bytes = data.get(crc_start..entry_end)?;
-        let stored_crc =
-            u32::from_le_bytes([crc_bytes[0], crc_bytes[1], crc_bytes[2], crc_bytes[3]]);
-
-        // Verify CRC
-        let checksummed_data = data.get(payload_start..crc_start)?;
-        let computed_crc = crc32fast::hash(checksummed_data);
-
-        if stored_crc != computed_crc {
-            let seq_bytes = data.get(payload_start..payload_start + 8)?;
-            let seq = u64::from_le_bytes([
-                seq_bytes[0],
-                seq_bytes[1],
-                seq_bytes[2],
-                seq_bytes[3],
-                seq_bytes[4],
-                seq_bytes[5],
-                seq_bytes[6],
-                seq_bytes[7],
-            ]);
-            self.offset = entry_end;
-            return Some(Err(JournalError::CorruptEntry {
-                sequence: seq,
-                expected_crc: stored_crc,
-                actual_crc: computed_crc,
-            }));
-        }
-
-        // Read sequence_num (first 8 bytes after entry_length)
-        let seq_bytes = data.get(payload_start..payload_start + 8)?;
-        let sequence_num = u64::from_le_bytes([
-            seq_bytes[0],
-            seq_bytes[1],
-            seq_bytes[2],
-            seq_bytes[3],
-            seq_bytes[4],
-            seq_bytes[5],
-            seq_bytes[6],
-            seq_bytes[7],
-        ]);
-
-        // Deserialize the payload (between timestamp_ns and CRC)
-        // The full payload region is: payload_start .. crc_start
-        // But we stored sequence_num + timestamp_ns + JSON payload
-        // The JSON payload starts at payload_start + 8 (seq) + 8 (ts)
-        let json_start = payload_start.checked_add(16)?;
-        let json_data = data.get(json_start..crc_start)?;
-
-        let event: SequencerEvent<T> = match serde_json::from_slice(json_data) {
-            Ok(ev) => ev,
-            Err(e) => {
-                self.offset = entry_end;
-                return Some(Err(JournalError::DeserializationError {
-                    sequence: sequence_num,
-                    message: e.to_string(),
-                }));
-            }
-        };
-
-        self.offset = entry_end;
-
-        Some(Ok(JournalEntry { event, stored_crc }))
+        let (result, next_offset) = decode_entry_at(&mmap[..], self.offset)?;
+        self.offset = next_offset;
+        Some(result)
     }
 }
 
@@ -800,12 +715,12 @@ where
 // ─── Helpers ────────────────────────────────────────────────────────────────
 
 /// Build the path for a segment file given its start sequence.
-fn segment_path(dir: &Path, start_sequence: u64) -> PathBuf {
+pub(super) fn segment_path(dir: &Path, start_sequence: u64) -> PathBuf {
     dir.join(format!("segment-{start_sequence:020}.journal"))
 }
 
 /// List all active (non-archived) segment start sequences in the directory.
-fn list_segments(dir: &Path) -> Result<Vec<u64>, JournalError> {
+pub(super) fn list_segments(dir: &Path) -> Result<Vec<u64>, JournalError> {
     let mut seqs = Vec::new();
 
     let entries = fs::read_dir(dir).map_err(|e| JournalError::Io {
@@ -834,9 +749,108 @@ fn list_segments(dir: &Path) -> Result<Vec<u64>, JournalError> {
     Ok(seqs)
 }
 
+/// Decode the entry starting at `offset` in a segment's bytes.
+///
+/// Returns the decoded entry (or its CRC/deserialization error) together
+/// with the offset of the next entry, or `None` at the end of written data
+/// or when the entry is truncated.
+pub(super) fn decode_entry_at<T>(
+    data: &[u8],
+    offset: usize,
+) -> Option<(Result<JournalEntry<T>, JournalError>, usize)>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    if offset.checked_add(ENTRY_HEADER_SIZE).is_none() || offset + ENTRY_HEADER_SIZE > data.len() {
+        return None;
+    }
+
+    // Read entry_length
+    let el_bytes = data.get(offset..offset + 4)?;
+    let entry_length =
+        u32::from_le_bytes([el_bytes[0], el_bytes[1], el_bytes[2], el_bytes[3]]) as usize;
+
+    if entry_length == 0 {
+        return None; // End of written data
+    }
+
+    let entry_end = offset.checked_add(4)?.checked_add(entry_length)?;
+    if entry_end > data.len() {
+        return None; // Truncated
+    }
+
+    let payload_start = offset.checked_add(4)?;
+    let crc_start = entry_end.checked_sub(ENTRY_CRC_SIZE)?;
+
+    // Read stored CRC
+    let crc_bytes = data.get(crc_start..entry_end)?;
+    let stored_crc = u32::from_le_bytes([crc_bytes[0], crc_bytes[1], crc_bytes[2], crc_bytes[3]]);
+
+    // Verify CRC
+    let checksummed_data = data.get(payload_start..crc_start)?;
+    let computed_crc = crc32fast::hash(checksummed_data);
+
+    if stored_crc != computed_crc {
+        let seq_bytes = data.get(payload_start..payload_start + 8)?;
+        let seq = u64::from_le_bytes([
+            seq_bytes[0],
+            seq_bytes[1],
+            seq_bytes[2],
+            seq_bytes[3],
+            seq_bytes[4],
+            seq_bytes[5],
+            seq_bytes[6],
+            seq_bytes[7],
+        ]);
+        return Some((
+            Err(JournalError::CorruptEntry {
+                sequence: seq,
+                expected_crc: stored_crc,
+                actual_crc: computed_crc,
+            }),
+            entry_end,
+        ));
+    }
+
+    // Read sequence_num (first 8 bytes after entry_length)
+    let seq_bytes = data.get(payload_start..payload_start + 8)?;
+    let sequence_num = u64::from_le_bytes([
+        seq_bytes[0],
+        seq_bytes[1],
+        seq_bytes[2],
+        seq_bytes[3],
+        seq_bytes[4],
+        seq_bytes[5],
+        seq_bytes[6],
+        seq_bytes[7],
+    ]);
+
+    // Deserialize the payload (between timestamp_ns and CRC)
+    // The full payload region is: payload_start .. crc_start
+    // But we stored sequence_num + timestamp_ns + JSON payload
+    // The JSON payload starts at payload_start + 8 (seq) + 8 (ts)
+    let json_start = payload_start.checked_add(16)?;
+    let json_data = data.get(json_start..crc_start)?;
+
+    let event: SequencerEvent<T> = match serde_json::from_slice(json_data) {
+        Ok(ev) => ev,
+        Err(e) => {
+            return Some((
+                Err(JournalError::DeserializationError {
+                    sequence: sequence_num,
+                    message: e.to_string(),
+                }),
+                entry_end,
+            ));
+        }
+    };
+
+    Some((Ok(JournalEntry { event, stored_crc }), entry_end))
+}
+
 /// Scan a memory-mapped segment to find the write position (byte offset of
 /// the first zero entry_length, i.e. end of written data).
-fn scan_write_position(data: &[u8], capacity: usize) -> usize {
+pub(super) fn scan_write_position(data: &[u8], capacity: usize) -> usize {
     let mut offset = 0usize;
 
     while let Some(end) = offset.checked_add(4) {
@@ -870,7 +884,7 @@ fn scan_write_position(data: &[u8], capacity: usize) -> usize {
 }
 
 /// Scan a segment to find the last sequence number written.
-fn scan_last_sequence(data: &[u8], write_pos: usize) -> Option<u64> {
+pub(super) fn scan_last_sequence(data: &[u8], write_pos: usize) -> Option<u64> {
     let mut offset = 0usize;
     let mut last_seq: Option<u64> = None;
 
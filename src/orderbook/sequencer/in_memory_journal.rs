@@ -3,13 +3,44 @@
 //! [`InMemoryJournal`] stores all events in a `Vec` in insertion order.
 //! Suitable for testing, benchmarking, and short-lived workloads where
 //! persistence is not required.
+//!
+//! Long-running tests and bounded-memory deployments can cap the journal
+//! with a [`RetentionPolicy`]. Retention only ever trims events at or below
+//! the last [`checkpoint`](InMemoryJournal::checkpoint), the sequence up to
+//! which the caller has captured the book in a snapshot, so the events
+//! needed to recover from that snapshot are always kept.
 
 use super::error::JournalError;
 use super::journal::{Journal, JournalEntry, JournalReadIter};
 use super::types::SequencerEvent;
+use pricelevel::Id;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::RwLock;
 
+/// Which events an [`InMemoryJournal`] retains once they are covered by a
+/// checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    /// Keep every event (default).
+    #[default]
+    KeepAll,
+    /// Keep the last `N` events.
+    LastEvents(usize),
+    /// Keep events whose sequence number is within this distance of the
+    /// last one: a window of `10` after sequence `99` keeps `90..=99`.
+    SequenceWindow(u64),
+}
+
+/// Retention configuration and the trimmed floor.
+#[derive(Debug, Default)]
+struct Retention {
+    policy: RetentionPolicy,
+    checkpoint: Option<u64>,
+    /// Earliest sequence still retained once anything has been trimmed.
+    floor: Option<u64>,
+}
+
 /// In-memory implementation of [`Journal`].
 ///
 /// Stores all events in a `Vec` in insertion order. Suitable for testing,
@@ -36,6 +67,10 @@ use std::sync::RwLock;
 #[derive(Debug)]
 pub struct InMemoryJournal<T> {
     events: RwLock<Vec<SequencerEvent<T>>>,
+    /// Positions in `events` of the events referencing each order id, if
+    /// the journal was created with [`InMemoryJournal::with_order_index`].
+    order_index: Option<RwLock<HashMap<Id, Vec<usize>>>>,
+    retention: RwLock<Retention>,
 }
 
 impl<T> Default for InMemoryJournal<T> {
@@ -50,6 +85,20 @@ impl<T> InMemoryJournal<T> {
     pub fn new() -> Self {
         Self {
             events: RwLock::new(Vec::new()),
+            order_index: None,
+            retention: RwLock::default(),
+        }
+    }
+
+    /// Creates a new empty in-memory journal that indexes events by order
+    /// id, so [`Journal::events_for_order`] costs O(events for that order)
+    /// instead of a scan of the whole journal.
+    #[must_use]
+    pub fn with_order_index() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+            order_index: Some(RwLock::new(HashMap::new())),
+            retention: RwLock::default(),
         }
     }
 
@@ -61,6 +110,8 @@ impl<T> InMemoryJournal<T> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             events: RwLock::new(Vec::with_capacity(capacity)),
+            order_index: None,
+            retention: RwLock::default(),
         }
     }
 
@@ -79,28 +130,210 @@ impl<T> InMemoryJournal<T> {
     pub fn is_empty(&self) -> bool {
         self.events.read().map(|e| e.is_empty()).unwrap_or(true)
     }
+
+    /// Returns `true` if the journal indexes events by order id.
+    #[must_use]
+    pub fn has_order_index(&self) -> bool {
+        self.order_index.is_some()
+    }
+
+    /// Set which events are retained and trim the journal to it.
+    ///
+    /// # Errors
+    /// [`JournalError::Io`] if an internal lock is poisoned.
+    pub fn set_retention(&self, policy: RetentionPolicy) -> Result<(), JournalError> {
+        self.write_retention()?.policy = policy;
+        self.trim().map(|_| ())
+    }
+
+    /// Returns which events are retained.
+    #[must_use]
+    pub fn retention(&self) -> RetentionPolicy {
+        self.retention
+            .read()
+            .map(|retention| retention.policy)
+            .unwrap_or_default()
+    }
+
+    /// Record that the book has been captured in a snapshot up to and
+    /// including `sequence`, allowing events up to it to be trimmed, and
+    /// trim the journal.
+    ///
+    /// # Errors
+    /// [`JournalError::Io`] if an internal lock is poisoned.
+    pub fn checkpoint(&self, sequence: u64) -> Result<(), JournalError> {
+        self.write_retention()?.checkpoint = Some(sequence);
+        self.trim().map(|_| ())
+    }
+
+    /// Returns the sequence of the last checkpoint, if any.
+    #[must_use]
+    pub fn last_checkpoint(&self) -> Option<u64> {
+        self.retention.read().ok()?.checkpoint
+    }
+
+    /// Returns the earliest sequence still retained if events have been
+    /// trimmed, otherwise `None`.
+    #[must_use]
+    pub fn trimmed_floor(&self) -> Option<u64> {
+        self.retention.read().ok()?.floor
+    }
+
+    /// Drop the events the retention policy no longer keeps and return how
+    /// many were dropped. Appends trim automatically; call this after
+    /// changing the checkpoint or policy through other means.
+    ///
+    /// # Errors
+    /// [`JournalError::Io`] if an internal lock is poisoned.
+    pub fn trim(&self) -> Result<usize, JournalError> {
+        let mut events = self.write_events()?;
+        self.trim_locked(&mut events)
+    }
+
+    /// Trim with the events write lock held.
+    fn trim_locked(&self, events: &mut Vec<SequencerEvent<T>>) -> Result<usize, JournalError> {
+        let mut retention = self.write_retention()?;
+        let Some(checkpoint) = retention.checkpoint else {
+            return Ok(0);
+        };
+        let Some(last) = events.last().map(|event| event.sequence_num) else {
+            return Ok(0);
+        };
+        let outside = match retention.policy {
+            RetentionPolicy::KeepAll => 0,
+            RetentionPolicy::LastEvents(count) => events.len().saturating_sub(count),
+            RetentionPolicy::SequenceWindow(window) => events
+                .iter()
+                .take_while(|event| event.sequence_num.saturating_add(window) <= last)
+                .count(),
+        };
+        // The last event is always kept so appends stay ordered
+        let covered = events
+            .iter()
+            .take(outside.min(events.len() - 1))
+            .take_while(|event| event.sequence_num <= checkpoint)
+            .count();
+        if covered == 0 {
+            return Ok(0);
+        }
+
+        events.drain(..covered);
+        retention.floor = events.first().map(|event| event.sequence_num);
+        if let Some(index) = &self.order_index {
+            let mut index = index.write().map_err(|_| JournalError::Io {
+                message: "failed to acquire order index write lock".to_string(),
+                path: None,
+            })?;
+            index.retain(|_, positions| {
+                positions.retain(|position| *position >= covered);
+                for position in positions.iter_mut() {
+                    *position -= covered;
+                }
+                !positions.is_empty()
+            });
+        }
+        Ok(covered)
+    }
+
+    fn write_retention(&self) -> Result<std::sync::RwLockWriteGuard<'_, Retention>, JournalError> {
+        self.retention.write().map_err(|_| JournalError::Io {
+            message: "failed to acquire retention write lock".to_string(),
+            path: None,
+        })
+    }
+
+    fn write_events(
+        &self,
+    ) -> Result<std::sync::RwLockWriteGuard<'_, Vec<SequencerEvent<T>>>, JournalError> {
+        self.events.write().map_err(|_| JournalError::Io {
+            message: "failed to acquire write lock".to_string(),
+            path: None,
+        })
+    }
+}
+
+impl<T: Clone> InMemoryJournal<T> {
+    /// Append `event` without checking its sequence number.
+    ///
+    /// Intended for bulk loads of events already known to be ordered. An
+    /// out-of-order event appended this way breaks the ordering that
+    /// [`Journal::read_from`] and replay rely on.
+    ///
+    /// # Errors
+    /// [`JournalError::Io`] if the internal lock is poisoned.
+    pub fn append_unchecked(&self, event: &SequencerEvent<T>) -> Result<(), JournalError> {
+        let mut events = self.write_events()?;
+        self.push(&mut events, event)?;
+        self.trim_locked(&mut events).map(|_| ())
+    }
+
+    /// Push `event` and record its position in the order index. Called with
+    /// the events write lock held, so positions and index stay in step.
+    fn push(
+        &self,
+        events: &mut Vec<SequencerEvent<T>>,
+        event: &SequencerEvent<T>,
+    ) -> Result<(), JournalError> {
+        if let Some(index) = &self.order_index {
+            let mut index = index.write().map_err(|_| JournalError::Io {
+                message: "failed to acquire order index write lock".to_string(),
+                path: None,
+            })?;
+            let position = events.len();
+            for order_id in event.order_ids() {
+                let positions = index.entry(order_id).or_default();
+                if positions.last() != Some(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+        events.push(event.clone());
+        Ok(())
+    }
 }
 
 impl<T> Journal<T> for InMemoryJournal<T>
 where
     T: Serialize + for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
 {
+    /// Append `event` after checking that its sequence number is greater
+    /// than [`last_sequence`](Journal::last_sequence).
+    ///
+    /// # Errors
+    /// [`JournalError::OutOfOrderAppend`] if the sequence number does not
+    /// advance; the journal is left unchanged.
     fn append(&self, event: &SequencerEvent<T>) -> Result<(), JournalError> {
-        self.events
-            .write()
-            .map_err(|_| JournalError::Io {
-                message: "failed to acquire write lock".to_string(),
-                path: None,
-            })?
-            .push(event.clone());
-        Ok(())
+        let mut events = self.write_events()?;
+        if let Some(last) = events.last()
+            && event.sequence_num <= last.sequence_num
+        {
+            return Err(JournalError::OutOfOrderAppend {
+                expected_gt: last.sequence_num,
+                found: event.sequence_num,
+            });
+        }
+        self.push(&mut events, event)?;
+        self.trim_locked(&mut events).map(|_| ())
     }
 
+    /// Read events from `sequence` on.
+    ///
+    /// # Errors
+    /// [`JournalError::Trimmed`] if `sequence` is below the earliest event
+    /// retained after trimming.
     fn read_from(&self, sequence: u64) -> Result<JournalReadIter<T>, JournalError> {
         let events = self.events.read().map_err(|_| JournalError::Io {
             message: "failed to acquire read lock".to_string(),
             path: None,
         })?;
+        if let Some(floor) = self.trimmed_floor()
+            && sequence < floor
+        {
+            return Err(JournalError::Trimmed {
+                requested: sequence,
+                earliest_available: floor,
+            });
+        }
 
         let filtered: Vec<_> = events
             .iter()
@@ -124,4 +357,39 @@ where
         // In-memory journal has no on-disk representation, so integrity is always valid
         Ok(())
     }
+
+    /// Move the retained events out of the journal without cloning them.
+    fn drain_owned(self) -> Result<Vec<SequencerEvent<T>>, JournalError> {
+        self.events.into_inner().map_err(|_| JournalError::Io {
+            message: "event lock poisoned".to_string(),
+            path: None,
+        })
+    }
+
+    fn events_for_order(&self, order_id: Id) -> Result<Vec<SequencerEvent<T>>, JournalError> {
+        let events = self.events.read().map_err(|_| JournalError::Io {
+            message: "failed to acquire read lock".to_string(),
+            path: None,
+        })?;
+
+        let Some(index) = &self.order_index else {
+            return Ok(events
+                .iter()
+                .filter(|event| event.references_order(order_id))
+                .cloned()
+                .collect());
+        };
+
+        let index = index.read().map_err(|_| JournalError::Io {
+            message: "failed to acquire order index read lock".to_string(),
+            path: None,
+        })?;
+        let mut positions = index.get(&order_id).cloned().unwrap_or_default();
+        // Unchecked appends may have stored events out of sequence order
+        positions.sort_by_key(|&position| events[position].sequence_num);
+        Ok(positions
+            .into_iter()
+            .map(|position| events[position].clone())
+            .collect())
+    }
 }
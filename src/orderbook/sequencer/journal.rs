@@ -11,6 +11,7 @@
 
 use super::error::JournalError;
 use super::types::SequencerEvent;
+use pricelevel::Id;
 use serde::{Deserialize, Serialize};
 
 /// Size of the fixed-size entry header in bytes.
@@ -101,4 +102,65 @@ where
     /// Returns the first [`JournalError::CorruptEntry`] encountered, or an
     /// I/O error if segment files cannot be read.
     fn verify_integrity(&self) -> Result<(), JournalError>;
+
+    /// Returns every event referencing `order_id`, in sequence order.
+    ///
+    /// An event references an order if its command addresses it or its
+    /// result names it (see [`SequencerEvent::order_ids`]), which covers the
+    /// add, modifications, cancels and fills of the order.
+    ///
+    /// The default implementation scans the whole journal. Implementations
+    /// that maintain an index by order id should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`JournalError`] encountered while reading.
+    fn events_for_order(&self, order_id: Id) -> Result<Vec<SequencerEvent<T>>, JournalError> {
+        let mut events = Vec::new();
+        for entry in self.read_from(0)? {
+            let entry = entry?;
+            if entry.event.references_order(order_id) {
+                events.push(entry.event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Returns owned copies of every retained event in read order, e.g. to
+    /// ship them to another thread or an external system.
+    ///
+    /// Journals that trim old events return the events from the earliest
+    /// one still available.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`JournalError`] encountered while reading.
+    fn clone_events(&self) -> Result<Vec<SequencerEvent<T>>, JournalError> {
+        let entries = match self.read_from(0) {
+            Err(JournalError::Trimmed {
+                earliest_available, ..
+            }) => self.read_from(earliest_available)?,
+            entries => entries?,
+        };
+        entries
+            .map(|entry| entry.map(|entry| entry.event))
+            .collect()
+    }
+
+    /// Consume the journal and return its events as
+    /// [`clone_events`](Self::clone_events) would.
+    ///
+    /// The default implementation clones the events before the journal is
+    /// dropped. Implementations holding events in memory should override it
+    /// to move them out instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`JournalError`] encountered while reading.
+    fn drain_owned(self) -> Result<Vec<SequencerEvent<T>>, JournalError>
+    where
+        Self: Sized,
+    {
+        self.clone_events()
+    }
 }
@@ -0,0 +1,271 @@
+//! Panic-isolated dispatch of sequenced events to listeners.
+//!
+//! A command loop publishes every [`SequencerEvent`] to its listeners after
+//! the command has been applied. A panicking listener must not unwind into
+//! the loop and take the critical path down with it, so
+//! [`EventListeners::dispatch`] runs each listener under
+//! [`std::panic::catch_unwind`] and applies the configured
+//! [`ListenerPanicPolicy`].
+
+use super::types::SequencerEvent;
+use serde::{Deserialize, Serialize};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+/// Callback invoked with every sequenced event.
+pub type SequencerListener<T> = Arc<dyn Fn(&SequencerEvent<T>) + Send + Sync>;
+
+/// What to do when a listener panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ListenerPanicPolicy {
+    /// Skip the panicking listener for this event, count the panic and keep
+    /// processing (default).
+    #[default]
+    Skip,
+    /// Stop dispatching and ask the command loop to shut down gracefully.
+    Shutdown,
+}
+
+impl std::fmt::Display for ListenerPanicPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenerPanicPolicy::Skip => write!(f, "Skip"),
+            ListenerPanicPolicy::Shutdown => write!(f, "Shutdown"),
+        }
+    }
+}
+
+/// Handle identifying a listener registered with [`EventListeners::add`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ListenerId(u64);
+
+impl std::fmt::Display for ListenerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Outcome of [`EventListeners::dispatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchOutcome {
+    /// All listeners ran, or panics were skipped; keep processing commands.
+    Continue,
+    /// A listener panicked under [`ListenerPanicPolicy::Shutdown`]; the
+    /// command loop should stop after the current event.
+    Shutdown,
+}
+
+/// A set of event listeners with panic isolation.
+pub struct EventListeners<T> {
+    listeners: Vec<(ListenerId, SequencerListener<T>)>,
+    next_id: u64,
+    policy: ListenerPanicPolicy,
+    panic_count: AtomicU64,
+}
+
+impl<T> EventListeners<T> {
+    /// Create an empty listener set with the given panic policy.
+    #[must_use]
+    pub fn new(policy: ListenerPanicPolicy) -> Self {
+        Self {
+            listeners: Vec::new(),
+            next_id: 0,
+            policy,
+            panic_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Register a listener. Listeners are invoked in registration order.
+    ///
+    /// Returns the id to pass to [`remove`](Self::remove) to detach it.
+    /// Registering the same closure twice yields two independent listeners.
+    pub fn add(&mut self, listener: SequencerListener<T>) -> ListenerId {
+        let id = ListenerId(self.next_id);
+        self.next_id = self.next_id.saturating_add(1);
+        self.listeners.push((id, listener));
+        id
+    }
+
+    /// Detach the listener registered under `id`, dropping its closure.
+    /// Returns `false` if no such listener is registered.
+    ///
+    /// The set is owned by the command loop, which removes listeners between
+    /// events; the removed listener receives no further events.
+    pub fn remove(&mut self, id: ListenerId) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.listeners.len() != before
+    }
+
+    /// Returns the number of registered listeners.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.listeners.len()
+    }
+
+    /// Returns `true` if no listener is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Returns the configured panic policy.
+    #[must_use]
+    pub fn policy(&self) -> ListenerPanicPolicy {
+        self.policy
+    }
+
+    /// Returns how many listener panics have been caught so far.
+    #[must_use]
+    pub fn panic_count(&self) -> u64 {
+        self.panic_count.load(Ordering::Relaxed)
+    }
+
+    /// Invoke every listener with `event`, catching panics.
+    ///
+    /// Under [`ListenerPanicPolicy::Skip`] the remaining listeners still
+    /// receive the event; under [`ListenerPanicPolicy::Shutdown`] dispatch
+    /// stops at the first panic.
+    pub fn dispatch(&self, event: &SequencerEvent<T>) -> DispatchOutcome {
+        for (id, listener) in &self.listeners {
+            if catch_unwind(AssertUnwindSafe(|| listener(event))).is_ok() {
+                continue;
+            }
+
+            let count = self.panic_count.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!(
+                "Sequencer listener {} panicked on event {} ({} panics so far, policy {})",
+                id, event.sequence_num, count, self.policy
+            );
+            if self.policy == ListenerPanicPolicy::Shutdown {
+                return DispatchOutcome::Shutdown;
+            }
+        }
+        DispatchOutcome::Continue
+    }
+}
+
+impl<T> Default for EventListeners<T> {
+    fn default() -> Self {
+        Self::new(ListenerPanicPolicy::default())
+    }
+}
+
+impl<T> std::fmt::Debug for EventListeners<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventListeners")
+            .field("listeners", &self.listeners.len())
+            .field("policy", &self.policy)
+            .field("panic_count", &self.panic_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderBook;
+    use crate::orderbook::sequencer::types::{SequencerCommand, SequencerResult};
+    use pricelevel::{Id, Side, TimeInForce};
+    use std::sync::atomic::AtomicUsize;
+
+    /// Apply `count` add-order commands to a book, dispatching each event.
+    /// Returns the number of commands processed before shutdown.
+    fn run(listeners: &EventListeners<()>, book: &OrderBook<()>, count: u64) -> u64 {
+        let mut processed = 0;
+        for seq in 0..count {
+            let id = Id::new_uuid();
+            let order = book
+                .add_limit_order(
+                    id,
+                    100 + u128::from(seq),
+                    1,
+                    Side::Sell,
+                    TimeInForce::Gtc,
+                    None,
+                )
+                .expect("add");
+            processed += 1;
+            let event = SequencerEvent {
+                sequence_num: seq,
+                timestamp_ns: seq,
+                command: SequencerCommand::AddOrder(*order),
+                result: SequencerResult::OrderAdded { order_id: id },
+            };
+            if listeners.dispatch(&event) == DispatchOutcome::Shutdown {
+                break;
+            }
+        }
+        processed
+    }
+
+    #[test]
+    fn test_panicking_listener_does_not_stop_processing() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+
+        let mut listeners: EventListeners<()> = EventListeners::default();
+        listeners.add(Arc::new(|event: &SequencerEvent<()>| {
+            if event.sequence_num == 1 {
+                panic!("listener failure");
+            }
+        }));
+        listeners.add(Arc::new(move |_: &SequencerEvent<()>| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(run(&listeners, &book, 5), 5);
+
+        // Later commands were still applied and every event reached the
+        // second listener.
+        assert_eq!(book.asks.len(), 5);
+        assert_eq!(seen.load(Ordering::SeqCst), 5);
+        assert_eq!(listeners.panic_count(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_policy_stops_after_panic() {
+        let mut listeners: EventListeners<()> = EventListeners::new(ListenerPanicPolicy::Shutdown);
+        listeners.add(Arc::new(|event: &SequencerEvent<()>| {
+            if event.sequence_num == 2 {
+                panic!("listener failure");
+            }
+        }));
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(run(&listeners, &book, 5), 3);
+        assert_eq!(listeners.panic_count(), 1);
+        assert_eq!(listeners.policy(), ListenerPanicPolicy::Shutdown);
+    }
+
+    #[test]
+    fn test_removed_listener_receives_no_further_events() {
+        let first = Arc::new(AtomicUsize::new(0));
+        let second = Arc::new(AtomicUsize::new(0));
+
+        let mut listeners: EventListeners<()> = EventListeners::default();
+        let counter = Arc::clone(&first);
+        let first_id = listeners.add(Arc::new(move |_: &SequencerEvent<()>| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        let counter = Arc::clone(&second);
+        let second_id = listeners.add(Arc::new(move |_: &SequencerEvent<()>| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        assert_ne!(first_id, second_id);
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        run(&listeners, &book, 2);
+
+        assert!(listeners.remove(first_id));
+        assert!(!listeners.remove(first_id));
+        assert_eq!(listeners.len(), 1);
+        run(&listeners, &book, 3);
+
+        assert_eq!(first.load(Ordering::SeqCst), 2);
+        assert_eq!(second.load(Ordering::SeqCst), 5);
+    }
+}
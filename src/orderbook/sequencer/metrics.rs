@@ -0,0 +1,157 @@
+//! Shared health counters for a sequencer command loop.
+//!
+//! The order book is owned by the command loop, so a monitoring task cannot
+//! inspect it directly. [`SequencerMetrics`] is a cheaply cloneable handle to
+//! a set of atomic counters: the loop calls [`SequencerMetrics::record`] for
+//! every event it emits, and any clone can read the totals concurrently
+//! without touching the book.
+//! [`BatchExecutor::with_metrics`](crate::orderbook::sequencer::BatchExecutor::with_metrics)
+//! and
+//! [`BookRegistry::with_metrics`](crate::orderbook::sequencer::BookRegistry::with_metrics)
+//! record their events this way.
+
+use super::types::{SequencerEvent, SequencerResult};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct MetricsInner {
+    started_at: Instant,
+    commands_processed: AtomicU64,
+    successes: AtomicU64,
+    rejections: AtomicU64,
+    /// Last recorded sequence number plus one; zero before the first event.
+    next_sequence: AtomicU64,
+}
+
+/// Cloneable handle to the counters of one command loop.
+///
+/// All counters are updated with relaxed atomics: each value is accurate on
+/// its own, but a reader may observe the counters of an event that is only
+/// partially recorded.
+#[derive(Debug, Clone)]
+pub struct SequencerMetrics {
+    inner: Arc<MetricsInner>,
+}
+
+impl SequencerMetrics {
+    /// Create a new set of counters. Uptime is measured from this call.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(MetricsInner {
+                started_at: Instant::now(),
+                commands_processed: AtomicU64::new(0),
+                successes: AtomicU64::new(0),
+                rejections: AtomicU64::new(0),
+                next_sequence: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Record a processed event.
+    ///
+    /// Events with a [`SequencerResult::Rejected`] result count as
+    /// rejections; every other result counts as a success.
+    pub fn record<T>(&self, event: &SequencerEvent<T>) {
+        let inner = &self.inner;
+        inner.commands_processed.fetch_add(1, Ordering::Relaxed);
+        if matches!(event.result, SequencerResult::Rejected { .. }) {
+            inner.rejections.fetch_add(1, Ordering::Relaxed);
+        } else {
+            inner.successes.fetch_add(1, Ordering::Relaxed);
+        }
+        inner
+            .next_sequence
+            .fetch_max(event.sequence_num.saturating_add(1), Ordering::Relaxed);
+    }
+
+    /// Returns the total number of commands processed.
+    #[must_use]
+    pub fn commands_processed(&self) -> u64 {
+        self.inner.commands_processed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of commands that were applied successfully.
+    #[must_use]
+    pub fn successes(&self) -> u64 {
+        self.inner.successes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of commands rejected by the order book.
+    #[must_use]
+    pub fn rejections(&self) -> u64 {
+        self.inner.rejections.load(Ordering::Relaxed)
+    }
+
+    /// Returns the highest sequence number recorded so far, or `None` before
+    /// the first event.
+    #[must_use]
+    pub fn current_sequence(&self) -> Option<u64> {
+        self.inner
+            .next_sequence
+            .load(Ordering::Relaxed)
+            .checked_sub(1)
+    }
+
+    /// Returns the time elapsed since the counters were created.
+    #[must_use]
+    pub fn uptime(&self) -> Duration {
+        self.inner.started_at.elapsed()
+    }
+}
+
+impl Default for SequencerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderBook;
+    use crate::orderbook::sequencer::types::SequencerCommand;
+    use crate::orderbook::trade::TradeResult;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    #[test]
+    fn test_metrics_count_successes_and_rejections() {
+        let metrics = SequencerMetrics::new();
+        let monitor = metrics.clone();
+        assert_eq!(monitor.current_sequence(), None);
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // Fills of 4, 4 and a partial 2 succeed; the rest find an empty book
+        for seq in 0..5u64 {
+            let id = Id::new_uuid();
+            let result = match book.submit_market_order(id, 4, Side::Buy) {
+                Ok(match_result) => SequencerResult::TradeExecuted {
+                    trade_result: TradeResult::new("TEST".to_string(), match_result),
+                },
+                Err(e) => SequencerResult::Rejected {
+                    reason: e.to_string(),
+                },
+            };
+            metrics.record(&SequencerEvent {
+                sequence_num: seq,
+                timestamp_ns: seq,
+                command: SequencerCommand::<()>::MarketOrder {
+                    id,
+                    quantity: 4,
+                    side: Side::Buy,
+                },
+                result,
+            });
+        }
+
+        assert_eq!(monitor.commands_processed(), 5);
+        assert_eq!(monitor.successes(), 3);
+        assert_eq!(monitor.rejections(), 2);
+        assert_eq!(monitor.current_sequence(), Some(4));
+    }
+}
@@ -0,0 +1,343 @@
+//! Read-only memory-mapped journal for fast cold replay.
+//!
+//! [`MmapJournal`] maps the segment files written by
+//! [`FileJournal`](super::FileJournal) once and decodes entries lazily,
+//! directly from the mapped bytes, as the [`Journal::read_from`] iterator
+//! advances. No entry is copied into an intermediate heap buffer, and
+//! entries before the requested start sequence are skipped by reading only
+//! their fixed-size header.
+//!
+//! # Safety Constraints
+//!
+//! Memory-mapped files are only sound while the underlying file is not
+//! truncated or modified by another process or thread. An `MmapJournal`
+//! must therefore only be opened on a journal that is no longer being
+//! written — typically at startup, before the sequencer (and its
+//! `FileJournal`) is started. Truncating a mapped file can raise `SIGBUS`
+//! on access; concurrent appends may be observed partially (they are then
+//! rejected by the CRC check, but are not guaranteed to be visible).
+
+use super::error::JournalError;
+use super::file_journal::{
+    decode_entry_at, list_segments, scan_last_sequence, scan_write_position, segment_path,
+};
+use super::journal::{Journal, JournalEntry, JournalReadIter};
+use super::types::SequencerEvent;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A single mapped segment and the end of its written data.
+struct MappedSegment {
+    mmap: Mmap,
+    write_pos: usize,
+}
+
+/// Read-only, memory-mapped view of a persisted journal.
+///
+/// Opens either a single segment file or a journal directory written by
+/// [`FileJournal`](super::FileJournal). [`Journal::append`] always fails
+/// with [`JournalError::ReadOnly`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use orderbook_rs::orderbook::sequencer::{MmapJournal, ReplayEngine};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let journal: MmapJournal<()> = MmapJournal::open("/tmp/journal")?;
+/// let (book, last_seq) = ReplayEngine::replay_from(&journal, 0, "BTC/USD")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MmapJournal<T> {
+    /// File or directory the journal was opened from.
+    path: PathBuf,
+    /// Mapped segments in sequence order.
+    segments: Arc<Vec<MappedSegment>>,
+    /// The last sequence number in the journal.
+    last_seq: Option<u64>,
+    /// Marker for the generic event payload type.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> MmapJournal<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+{
+    /// Map a journal segment file, or every active segment in a journal
+    /// directory, for reading.
+    ///
+    /// # Safety Constraints
+    ///
+    /// See the [module documentation](self): the files must not be
+    /// modified or truncated while the returned journal is alive.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JournalError::Io`] if a file cannot be opened or mapped,
+    /// or [`JournalError::InvalidDirectory`] if `path` does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, JournalError> {
+        let path = path.as_ref().to_path_buf();
+
+        let files: Vec<PathBuf> = if path.is_dir() {
+            let mut starts = list_segments(&path)?;
+            starts.sort();
+            starts
+                .into_iter()
+                .map(|start| segment_path(&path, start))
+                .collect()
+        } else if path.is_file() {
+            vec![path.clone()]
+        } else {
+            return Err(JournalError::InvalidDirectory { path });
+        };
+
+        let mut segments = Vec::with_capacity(files.len());
+        let mut last_seq = None;
+        for file_path in files {
+            let file = File::open(&file_path).map_err(|e| JournalError::Io {
+                message: e.to_string(),
+                path: Some(file_path.clone()),
+            })?;
+
+            // SAFETY: Read-only mapping. The caller guarantees (see module
+            // docs) that the file is not modified or truncated while mapped.
+            let mmap = unsafe {
+                Mmap::map(&file).map_err(|e| JournalError::Io {
+                    message: e.to_string(),
+                    path: Some(file_path.clone()),
+                })?
+            };
+            #[cfg(unix)]
+            let _ = mmap.advise(memmap2::Advice::Sequential);
+
+            let write_pos = scan_write_position(&mmap, mmap.len());
+            if let Some(seq) = scan_last_sequence(&mmap, write_pos) {
+                last_seq = Some(seq);
+            }
+            segments.push(MappedSegment { mmap, write_pos });
+        }
+
+        Ok(Self {
+            path,
+            segments: Arc::new(segments),
+            last_seq,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the number of mapped segments.
+    #[must_use]
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+}
+
+impl<T> Journal<T> for MmapJournal<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+{
+    fn append(&self, _event: &SequencerEvent<T>) -> Result<(), JournalError> {
+        Err(JournalError::ReadOnly {
+            path: self.path.clone(),
+        })
+    }
+
+    fn read_from(&self, sequence: u64) -> Result<JournalReadIter<T>, JournalError> {
+        Ok(Box::new(MmapIterator::<T> {
+            segments: Arc::clone(&self.segments),
+            segment_idx: 0,
+            offset: 0,
+            start_sequence: sequence,
+            _phantom: PhantomData,
+        }))
+    }
+
+    fn last_sequence(&self) -> Option<u64> {
+        self.last_seq
+    }
+
+    fn verify_integrity(&self) -> Result<(), JournalError> {
+        for segment in self.segments.iter() {
+            let data = &segment.mmap[..segment.write_pos];
+            let mut offset = 0usize;
+            while let Some((result, next_offset)) = decode_entry_at::<T>(data, offset) {
+                if let Err(e @ JournalError::CorruptEntry { .. }) = result {
+                    return Err(e);
+                }
+                offset = next_offset;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> std::fmt::Debug for MmapJournal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmapJournal")
+            .field("path", &self.path)
+            .field("segments", &self.segments.len())
+            .field("last_seq", &self.last_seq)
+            .finish()
+    }
+}
+
+/// Lazy iterator decoding entries directly from the mapped segments.
+struct MmapIterator<T> {
+    segments: Arc<Vec<MappedSegment>>,
+    segment_idx: usize,
+    offset: usize,
+    start_sequence: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> MmapIterator<T> {
+    /// Skip entries before `start_sequence` by reading only their headers.
+    fn skip_to_start(&mut self, data: &[u8]) {
+        while let Some(header) = data.get(self.offset..self.offset + 12) {
+            let entry_length =
+                u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let mut seq_bytes = [0u8; 8];
+            seq_bytes.copy_from_slice(&header[4..12]);
+            let sequence = u64::from_le_bytes(seq_bytes);
+
+            if entry_length == 0 || sequence >= self.start_sequence {
+                break;
+            }
+            match self.offset.checked_add(4 + entry_length) {
+                Some(next) if next <= data.len() => self.offset = next,
+                _ => break,
+            }
+        }
+    }
+}
+
+impl<T> Iterator for MmapIterator<T>
+where
+    T: for<'de> Deserialize<'de> + Clone + 'static,
+{
+    type Item = Result<JournalEntry<T>, JournalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let segments = Arc::clone(&self.segments);
+        while let Some(segment) = segments.get(self.segment_idx) {
+            let data = &segment.mmap[..segment.write_pos];
+            self.skip_to_start(data);
+
+            if let Some((result, next_offset)) = decode_entry_at::<T>(data, self.offset) {
+                self.offset = next_offset;
+                return Some(result);
+            }
+
+            // Segment exhausted — move to the next one
+            self.segment_idx = self.segment_idx.saturating_add(1);
+            self.offset = 0;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderBook;
+    use crate::orderbook::sequencer::types::{SequencerCommand, SequencerResult};
+    use crate::orderbook::sequencer::{FileJournal, ReplayEngine, snapshots_match};
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn add_event(seq: u64, id: Id, price: u128, qty: u64, side: Side) -> SequencerEvent<()> {
+        SequencerEvent {
+            sequence_num: seq,
+            timestamp_ns: seq,
+            command: SequencerCommand::AddOrder(OrderType::Standard {
+                id,
+                price: Price::new(price),
+                quantity: Quantity::new(qty),
+                side,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            }),
+            result: SequencerResult::OrderAdded { order_id: id },
+        }
+    }
+
+    /// Write the events to a `FileJournal` and apply them to a live book.
+    fn write_journal(dir: &Path, segment_size: usize) -> OrderBook<()> {
+        let journal: FileJournal<()> =
+            FileJournal::open_with_segment_size(dir, segment_size).expect("open");
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for i in 0..50u64 {
+            let id = Id::new_uuid();
+            let (price, side) = if i % 2 == 0 {
+                (1000 - (i % 10) as u128, Side::Buy)
+            } else {
+                (1100 + (i % 10) as u128, Side::Sell)
+            };
+            journal
+                .append(&add_event(i, id, price, 10, side))
+                .expect("append");
+            book.add_limit_order(id, price, 10, side, TimeInForce::Gtc, None)
+                .expect("add");
+        }
+        book
+    }
+
+    #[test]
+    fn test_replay_from_mmap_matches_live_book() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        // Small segments force rotation across several files
+        let live = write_journal(dir.path(), 4096);
+
+        let journal: MmapJournal<()> = MmapJournal::open(dir.path()).expect("open");
+        assert!(journal.segment_count() > 1);
+        assert_eq!(journal.last_sequence(), Some(49));
+        assert!(journal.verify_integrity().is_ok());
+
+        let (replayed, last_seq) =
+            ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+        assert_eq!(last_seq, 49);
+        assert!(snapshots_match(
+            &replayed.create_snapshot(usize::MAX),
+            &live.create_snapshot(usize::MAX)
+        ));
+    }
+
+    #[test]
+    fn test_read_from_skips_to_sequence_and_is_read_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_journal(dir.path(), 4096);
+        let journal: MmapJournal<()> = MmapJournal::open(dir.path()).expect("open");
+
+        let sequences: Vec<u64> = journal
+            .read_from(30)
+            .expect("read")
+            .map(|entry| entry.expect("entry").event.sequence_num)
+            .collect();
+        assert_eq!(sequences, (30..50).collect::<Vec<_>>());
+
+        let err = journal
+            .append(&add_event(50, Id::new_uuid(), 1, 1, Side::Buy))
+            .expect_err("read-only");
+        assert!(matches!(err, JournalError::ReadOnly { .. }));
+        assert!(err.to_string().contains("read-only"));
+    }
+
+    #[test]
+    fn test_open_single_segment_file_and_missing_path() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_journal(dir.path(), 1024 * 1024);
+
+        let journal: MmapJournal<()> =
+            MmapJournal::open(segment_path(dir.path(), 0)).expect("open file");
+        assert_eq!(journal.segment_count(), 1);
+        assert_eq!(journal.read_from(0).expect("read").count(), 50);
+
+        assert!(MmapJournal::<()>::open(dir.path().join("missing")).is_err());
+    }
+}
@@ -12,9 +12,21 @@
 //! - [`Journal`] — trait for append-only event journals
 //! - [`JournalEntry`] — a single entry read back from the journal
 //! - [`crate::orderbook::sequencer::InMemoryJournal`] — in-memory journal implementation for testing
+//! - [`crate::orderbook::sequencer::CompactJournal`] — in-memory journal keeping only what replay needs
 //! - [`crate::orderbook::sequencer::ReplayEngine`] — deterministic replay engine for event journals
 //! - [`crate::orderbook::sequencer::ReplayError`] — error type for replay operations
+//! - [`crate::orderbook::sequencer::EventListeners`] — panic-isolated dispatch of events to listeners
+//! - [`crate::orderbook::sequencer::SequencerMetrics`] — shared health counters for a command loop
+//! - [`crate::orderbook::sequencer::execute_command`] — applies a command to a book and captures its result
+//! - [`crate::orderbook::sequencer::execute_command_with_changes`] — as above, plus the price levels it changed
+//! - [`crate::orderbook::sequencer::execute_command_mut`] — as above, also applying [`ConfigChange`](crate::orderbook::sequencer::ConfigChange)s
+//! - [`crate::orderbook::sequencer::BatchExecutor`] — batched command execution under one timestamp read
+//! - [`crate::orderbook::sequencer::BookRegistry`] — routes symbol-tagged commands to many books under one sequence
+//! - [`crate::orderbook::sequencer::coalesce_commands`] — opt-in coalescing of queued modify/cancel commands
+//! - [`crate::orderbook::sequencer::DeltaConflator`] — per-subscriber conflation of level updates
+//! - [`crate::orderbook::sequencer::CommandPriority`] — opt-in servicing of cancels ahead of queued adds
 //! - `FileJournal` — memory-mapped file journal implementation (requires `journal` feature)
+//! - `MmapJournal` — read-only memory-mapped journal for fast cold replay (requires `journal` feature)
 //!
 //! # Feature Gate
 //!
@@ -27,22 +39,54 @@
 //!
 //! The sequencer types and [`Journal`] trait are always available.
 
+pub mod batch;
+pub mod coalesce;
+pub mod compact;
+pub mod conflate;
 pub mod error;
+pub mod executor;
 pub mod types;
 
 #[cfg(feature = "journal")]
 pub mod file_journal;
+#[cfg(feature = "journal")]
+pub mod mmap_journal;
 
 pub mod in_memory_journal;
 pub mod journal;
+pub mod listener;
+pub mod metrics;
+pub mod priority;
+pub mod registry;
 pub mod replay;
 
+pub use batch::BatchExecutor;
+pub use coalesce::{CoalescedCommand, coalesce_commands};
+pub use compact::{CompactEvent, CompactJournal, CompactResult};
+pub use conflate::{ConflatedDeltas, DeltaConflator};
 pub use error::JournalError;
+pub use executor::{
+    apply_config_change, contingent_events, execute_command, execute_command_mut,
+    execute_command_with_changes,
+};
 #[cfg(feature = "journal")]
 pub use file_journal::FileJournal;
-pub use in_memory_journal::InMemoryJournal;
+pub use in_memory_journal::{InMemoryJournal, RetentionPolicy};
 pub use journal::{
     ENTRY_CRC_SIZE, ENTRY_HEADER_SIZE, ENTRY_OVERHEAD, Journal, JournalEntry, JournalReadIter,
 };
-pub use replay::{ReplayEngine, ReplayError, snapshots_match};
-pub use types::{SequencerCommand, SequencerEvent, SequencerResult};
+pub use listener::{
+    DispatchOutcome, EventListeners, ListenerId, ListenerPanicPolicy, SequencerListener,
+};
+pub use metrics::SequencerMetrics;
+#[cfg(feature = "journal")]
+pub use mmap_journal::MmapJournal;
+pub use priority::{CommandPriority, prioritize_cancels};
+pub use registry::{BookRegistry, SymbolCommand, SymbolEvent};
+pub use replay::{
+    LevelDifference, ReplayEngine, ReplayError, ReplayedEvent, SnapshotComparison, snapshot_diff,
+    snapshots_match,
+};
+pub use types::{
+    CANONICAL_EVENT_VERSION, ConfigChange, SequencerCommand, SequencerEvent, SequencerResult,
+};
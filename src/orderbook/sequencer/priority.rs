@@ -0,0 +1,139 @@
+//! Opt-in servicing of cancels ahead of queued adds.
+//!
+//! In a fast market a cancel stuck behind a backlog of adds leaves a stale
+//! quote exposed for longer. Under [`CommandPriority::CancelsFirst`] a
+//! [`BatchExecutor`](super::BatchExecutor) moves every cancel in its pending
+//! buffer ahead of the adds queued before it, and assigns sequence numbers
+//! in that service order.
+//!
+//! # Determinism
+//! Reordering changes the total order of commands: which adds a cancel
+//! overtakes depends on how far the loop has fallen behind, so submitting
+//! the same command stream twice can produce different event sequences.
+//! The journal records the order commands were actually serviced in, so
+//! replaying a journal is still deterministic; re-executing the original
+//! submission order is not guaranteed to reproduce it. The policy is
+//! therefore never applied implicitly.
+//!
+//! A cancel only moves past [`SequencerCommand::AddOrder`] commands and
+//! never past the add of the order it cancels, so it cannot miss an order
+//! that was submitted before it. Relative order among cancels, and among
+//! all other commands, is preserved.
+
+use super::types::SequencerCommand;
+use pricelevel::Id;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Order in which a [`BatchExecutor`](super::BatchExecutor) services its
+/// pending commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CommandPriority {
+    /// Service commands in submission order (default).
+    #[default]
+    Fifo,
+    /// Service cancels ahead of adds queued before them.
+    CancelsFirst,
+}
+
+impl std::fmt::Display for CommandPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandPriority::Fifo => write!(f, "Fifo"),
+            CommandPriority::CancelsFirst => write!(f, "CancelsFirst"),
+        }
+    }
+}
+
+/// Move each cancel in `pending` ahead of the adds queued before it, except
+/// the add of the order it cancels.
+pub fn prioritize_cancels<T: Clone>(pending: &mut VecDeque<SequencerCommand<T>>) {
+    if !pending
+        .iter()
+        .any(|command| cancel_target(command).is_some())
+    {
+        return;
+    }
+
+    let mut ordered: Vec<SequencerCommand<T>> = Vec::with_capacity(pending.len());
+    for command in pending.drain(..) {
+        let Some(target) = cancel_target(&command) else {
+            ordered.push(command);
+            continue;
+        };
+        let position = ordered
+            .iter()
+            .rposition(|queued| match queued {
+                SequencerCommand::AddOrder(order) => order.id() == target,
+                _ => true,
+            })
+            .map_or(0, |index| index + 1);
+        ordered.insert(position, command);
+    }
+    pending.extend(ordered);
+}
+
+/// The order a cancel command targets, or `None` for other commands.
+fn cancel_target<T>(command: &SequencerCommand<T>) -> Option<Id> {
+    match command {
+        SequencerCommand::CancelOrder(id) | SequencerCommand::CancelOrderIdempotent(id) => {
+            Some(*id)
+        }
+        SequencerCommand::CancelOrderAs { order_id, .. } => Some(*order_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn add(id: Id) -> SequencerCommand<()> {
+        SequencerCommand::AddOrder(OrderType::Standard {
+            id,
+            price: Price::new(100),
+            quantity: Quantity::new(10),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+    }
+
+    fn labels(pending: &VecDeque<SequencerCommand<()>>, ids: &[Id]) -> Vec<String> {
+        let label = |id: &Id| ids.iter().position(|i| i == id).unwrap();
+        pending
+            .iter()
+            .map(|command| match command {
+                SequencerCommand::AddOrder(order) => format!("add{}", label(&order.id())),
+                SequencerCommand::CancelOrder(id) => format!("cancel{}", label(id)),
+                _ => "other".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cancels_move_ahead_of_adds_but_not_their_own() {
+        let ids: Vec<Id> = (0..4).map(|_| Id::new_uuid()).collect();
+        let mut pending: VecDeque<_> = vec![
+            add(ids[1]),
+            add(ids[2]),
+            SequencerCommand::CancelOrder(ids[0]),
+            add(ids[3]),
+            SequencerCommand::CancelOrder(ids[2]),
+            SequencerCommand::<()>::Snapshot { depth: 1 },
+            SequencerCommand::CancelOrder(ids[1]),
+        ]
+        .into();
+
+        prioritize_cancels(&mut pending);
+        assert_eq!(
+            labels(&pending, &ids),
+            [
+                "cancel0", "add1", "add2", "cancel2", "add3", "other", "cancel1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,571 @@
+//! Routing of symbol-tagged commands to many books under one sequence.
+//!
+//! Running one command loop per symbol does not scale to thousands of
+//! symbols. A [`BookRegistry`] owns the books of every symbol served by a
+//! single loop, routes each [`SymbolCommand`] to its book and stamps the
+//! resulting event with one global, gap-free sequence number shared by all
+//! symbols.
+//!
+//! [`BookRegistry::execute_transaction`] adds orders to several books as
+//! one unit, e.g. both legs of a spread: either every leg rests or no book
+//! is changed.
+
+use super::executor::execute_command_mut;
+use super::metrics::SequencerMetrics;
+use super::types::{SequencerCommand, SequencerEvent, SequencerResult};
+use crate::orderbook::order_state::CancelReason;
+use crate::orderbook::{OrderBook, OrderBookError, OrderRole};
+use pricelevel::Id;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A command addressed to the book of one symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolCommand<T> {
+    /// Symbol of the target book.
+    pub symbol: String,
+    /// The command to execute.
+    pub command: SequencerCommand<T>,
+}
+
+/// A sequenced event tagged with the symbol of the book it was applied to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolEvent<T> {
+    /// Symbol of the book the command was routed to.
+    pub symbol: String,
+    /// The sequenced event. Its `sequence_num` is global across symbols.
+    pub event: SequencerEvent<T>,
+}
+
+/// Order books of many symbols sharing one global sequence.
+pub struct BookRegistry<T> {
+    books: HashMap<String, OrderBook<T>>,
+    next_sequence: u64,
+    metrics: Option<SequencerMetrics>,
+}
+
+impl<T> BookRegistry<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Create an empty registry whose first event gets sequence number 0.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            books: HashMap::new(),
+            next_sequence: 0,
+            metrics: None,
+        }
+    }
+
+    /// Record every event this registry emits in `metrics`.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: SequencerMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Add an empty book for `symbol`. Returns `false` if the symbol is
+    /// already registered, in which case the existing book is kept.
+    pub fn add_book(&mut self, symbol: &str) -> bool {
+        if self.books.contains_key(symbol) {
+            return false;
+        }
+        self.books
+            .insert(symbol.to_string(), OrderBook::new(symbol));
+        true
+    }
+
+    /// Get the book of `symbol`.
+    #[must_use]
+    pub fn get_book(&self, symbol: &str) -> Option<&OrderBook<T>> {
+        self.books.get(symbol)
+    }
+
+    /// Get a mutable reference to the book of `symbol`, e.g. to configure
+    /// it before commands are routed to it.
+    pub fn get_book_mut(&mut self, symbol: &str) -> Option<&mut OrderBook<T>> {
+        self.books.get_mut(symbol)
+    }
+
+    /// Returns the registered symbols.
+    #[must_use]
+    pub fn symbols(&self) -> Vec<String> {
+        self.books.keys().cloned().collect()
+    }
+
+    /// Returns the number of registered books.
+    #[must_use]
+    pub fn book_count(&self) -> usize {
+        self.books.len()
+    }
+
+    /// Returns the sequence number the next event will receive.
+    #[must_use]
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Route `command` to its book, execute it and return the sequenced
+    /// event.
+    ///
+    /// A command for an unregistered symbol still consumes a sequence
+    /// number and produces a [`SequencerResult::Rejected`] event, so the
+    /// sequence stream stays gap-free.
+    pub fn execute(&mut self, command: SymbolCommand<T>, timestamp_ns: u64) -> SymbolEvent<T> {
+        let SymbolCommand { symbol, command } = command;
+        let result = match self.books.get_mut(&symbol) {
+            Some(book) => execute_command_mut(book, &command),
+            None => SequencerResult::Rejected {
+                reason: format!("unknown symbol: {symbol}"),
+            },
+        };
+
+        self.sequence(symbol, command, result, timestamp_ns)
+    }
+
+    /// Route `command` to its book and execute it only if it is assigned
+    /// `expected_sequence`, the [`next_sequence`](Self::next_sequence) the
+    /// client observed, for compare-and-submit patterns. `None` executes
+    /// unconditionally, as [`execute`](Self::execute) does.
+    ///
+    /// If other commands were sequenced in between, the book is not
+    /// changed and the command is sequenced as a
+    /// [`SequencerResult::Rejected`] event carrying
+    /// [`OrderBookError::SequenceConflict`].
+    pub fn execute_expecting(
+        &mut self,
+        command: SymbolCommand<T>,
+        expected_sequence: Option<u64>,
+        timestamp_ns: u64,
+    ) -> SymbolEvent<T> {
+        match expected_sequence {
+            Some(expected) if expected != self.next_sequence => {
+                let reason = OrderBookError::SequenceConflict {
+                    expected,
+                    actual: self.next_sequence,
+                }
+                .to_string();
+                let SymbolCommand { symbol, command } = command;
+                self.sequence(
+                    symbol,
+                    command,
+                    SequencerResult::Rejected { reason },
+                    timestamp_ns,
+                )
+            }
+            _ => self.execute(command, timestamp_ns),
+        }
+    }
+
+    /// Add the orders of `legs` to their books atomically: either every leg
+    /// rests or no book is changed.
+    ///
+    /// Legs are added in order. Each must be a
+    /// [`SequencerCommand::AddOrder`] that rests without matching, checked
+    /// against its book including the legs added before it, because a fill
+    /// cannot be undone. For the same reason a leg is refused while its
+    /// book has parked market orders on the opposite side. If a leg fails, the legs already added are
+    /// cancelled again in reverse order with
+    /// [`CancelReason::TransactionRolledBack`] and no sequence number is
+    /// consumed. If every leg rests, each is sequenced in order as an
+    /// [`SequencerResult::OrderRested`] event.
+    ///
+    /// Listeners of the books still see the add and cancel of rolled back
+    /// legs.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`] naming the failing leg
+    /// if its symbol is unknown, it is not an add, it would match or fill
+    /// parked market orders, or it would not rest, or the book's own error if the book rejects the order.
+    pub fn execute_transaction(
+        &mut self,
+        legs: Vec<SymbolCommand<T>>,
+        timestamp_ns: u64,
+    ) -> Result<Vec<SymbolEvent<T>>, OrderBookError> {
+        let mut added: Vec<(&OrderBook<T>, Id)> = Vec::with_capacity(legs.len());
+        for (index, leg) in legs.iter().enumerate() {
+            match self.add_leg(index, leg) {
+                Ok(staged) => added.push(staged),
+                Err(error) => {
+                    for (book, order_id) in added.into_iter().rev() {
+                        // Every staged leg rests, so the cancel cannot miss
+                        let _ = book.cancel_order_with_reason(
+                            order_id,
+                            CancelReason::TransactionRolledBack,
+                        );
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let order_ids: Vec<Id> = added.into_iter().map(|(_, order_id)| order_id).collect();
+        Ok(legs
+            .into_iter()
+            .zip(order_ids)
+            .map(|(leg, order_id)| {
+                self.sequence(
+                    leg.symbol,
+                    leg.command,
+                    SequencerResult::OrderRested { order_id },
+                    timestamp_ns,
+                )
+            })
+            .collect())
+    }
+
+    /// Add one transaction leg to its book and return the book and the id
+    /// of the resting order.
+    fn add_leg(
+        &self,
+        index: usize,
+        leg: &SymbolCommand<T>,
+    ) -> Result<(&OrderBook<T>, Id), OrderBookError> {
+        let invalid = |reason: &str| OrderBookError::InvalidOperation {
+            message: format!("transaction leg {index} on {}: {reason}", leg.symbol),
+        };
+        let book = self
+            .books
+            .get(&leg.symbol)
+            .ok_or_else(|| invalid("unknown symbol"))?;
+        let SequencerCommand::AddOrder(order) = &leg.command else {
+            return Err(invalid("only order adds can be part of a transaction"));
+        };
+        // Rolling back cancels by id, which must not hit an older order
+        if book.get_order(order.id()).is_some() {
+            return Err(invalid("order id already rests in the book"));
+        }
+        if book.classify_order(order.side(), order.price().as_u128()) == OrderRole::Taker {
+            return Err(invalid("order would match"));
+        }
+        // Resting would release parked market orders against the leg
+        let taker_side = order.side().opposite();
+        if book
+            .parked_market_orders()
+            .iter()
+            .any(|parked| parked.side == taker_side)
+        {
+            return Err(invalid("order would fill parked market orders"));
+        }
+
+        book.add_order(order.clone())?;
+        if book.get_order(order.id()).is_none() {
+            return Err(invalid("order did not rest"));
+        }
+        Ok((book, order.id()))
+    }
+
+    /// Stamp `result` with the next sequence number.
+    fn sequence(
+        &mut self,
+        symbol: String,
+        command: SequencerCommand<T>,
+        result: SequencerResult,
+        timestamp_ns: u64,
+    ) -> SymbolEvent<T> {
+        let sequence_num = self.next_sequence;
+        self.next_sequence = self.next_sequence.saturating_add(1);
+
+        let event = SequencerEvent {
+            sequence_num,
+            timestamp_ns,
+            command,
+            result,
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.record(&event);
+        }
+        SymbolEvent { symbol, event }
+    }
+}
+
+impl<T> Default for BookRegistry<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Debug for BookRegistry<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BookRegistry")
+            .field("books", &self.books.len())
+            .field("next_sequence", &self.next_sequence)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn add(symbol: &str, id: Id, price: u128, side: Side) -> SymbolCommand<()> {
+        SymbolCommand {
+            symbol: symbol.to_string(),
+            command: SequencerCommand::AddOrder(OrderType::Standard {
+                id,
+                price: Price::new(price),
+                quantity: Quantity::new(10),
+                side,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_commands_for_several_symbols_share_one_sequence() {
+        let metrics = SequencerMetrics::new();
+        let mut registry: BookRegistry<()> = BookRegistry::new().with_metrics(metrics.clone());
+        assert!(registry.add_book("BTC/USD"));
+        assert!(registry.add_book("ETH/USD"));
+        assert!(!registry.add_book("BTC/USD"));
+
+        let btc_bid = Id::new_uuid();
+        let eth_bid = Id::new_uuid();
+        let commands = vec![
+            add("BTC/USD", btc_bid, 100, Side::Buy),
+            add("ETH/USD", eth_bid, 50, Side::Buy),
+            add("ETH/USD", Id::new_uuid(), 55, Side::Sell),
+            SymbolCommand {
+                symbol: "BTC/USD".to_string(),
+                command: SequencerCommand::MarketOrder {
+                    id: Id::new_uuid(),
+                    quantity: 4,
+                    side: Side::Sell,
+                },
+            },
+            SymbolCommand {
+                symbol: "ETH/USD".to_string(),
+                command: SequencerCommand::CancelOrder(eth_bid),
+            },
+        ];
+
+        let events: Vec<SymbolEvent<()>> = commands
+            .into_iter()
+            .enumerate()
+            .map(|(i, command)| registry.execute(command, i as u64))
+            .collect();
+
+        let sequences: Vec<u64> = events.iter().map(|e| e.event.sequence_num).collect();
+        assert_eq!(sequences, vec![0, 1, 2, 3, 4]);
+        assert!(
+            events
+                .iter()
+                .all(|e| !matches!(e.event.result, SequencerResult::Rejected { .. }))
+        );
+        assert_eq!(registry.next_sequence(), 5);
+        assert_eq!(metrics.successes(), 5);
+        assert_eq!(metrics.current_sequence(), Some(4));
+
+        let btc = registry.get_book("BTC/USD").expect("btc");
+        assert_eq!(btc.get_order(btc_bid).unwrap().visible_quantity(), 6);
+        assert_eq!(btc.best_ask(), None);
+
+        let eth = registry.get_book("ETH/USD").expect("eth");
+        assert_eq!(eth.best_bid(), None);
+        assert_eq!(eth.best_ask(), Some(55));
+    }
+
+    fn spread_registry() -> BookRegistry<()> {
+        let mut registry: BookRegistry<()> = BookRegistry::new();
+        registry.add_book("BTC/USD");
+        registry.add_book("BTC/EUR");
+        registry
+    }
+
+    #[test]
+    fn test_transaction_commits_every_leg() {
+        let mut registry = spread_registry();
+        let usd_bid = Id::new_uuid();
+        let eur_ask = Id::new_uuid();
+
+        let events = registry
+            .execute_transaction(
+                vec![
+                    add("BTC/USD", usd_bid, 100, Side::Buy),
+                    add("BTC/EUR", eur_ask, 95, Side::Sell),
+                ],
+                7,
+            )
+            .unwrap();
+        let sequences: Vec<u64> = events.iter().map(|e| e.event.sequence_num).collect();
+        assert_eq!(sequences, vec![0, 1]);
+        assert!(matches!(
+            events[1].event.result,
+            SequencerResult::OrderRested { order_id } if order_id == eur_ask
+        ));
+        assert!(events.iter().all(|e| e.event.timestamp_ns == 7));
+        assert_eq!(registry.next_sequence(), 2);
+
+        let usd = registry.get_book("BTC/USD").expect("usd");
+        assert!(usd.get_order(usd_bid).is_some());
+        let eur = registry.get_book("BTC/EUR").expect("eur");
+        assert!(eur.get_order(eur_ask).is_some());
+    }
+
+    #[test]
+    fn test_failed_leg_rolls_back_the_transaction() {
+        let mut registry = spread_registry();
+        let resting = Id::new_uuid();
+        registry.execute(add("BTC/EUR", resting, 95, Side::Sell), 0);
+
+        let usd_bid = Id::new_uuid();
+        // The second leg would take the resting ask
+        let crossing = registry.execute_transaction(
+            vec![
+                add("BTC/USD", usd_bid, 100, Side::Buy),
+                add("BTC/EUR", Id::new_uuid(), 96, Side::Buy),
+            ],
+            1,
+        );
+        assert!(matches!(
+            crossing,
+            Err(OrderBookError::InvalidOperation { ref message }) if message.contains("leg 1")
+        ));
+
+        // A leg reusing the id of a resting order
+        let duplicate = registry.execute_transaction(
+            vec![
+                add("BTC/USD", usd_bid, 100, Side::Buy),
+                add("BTC/EUR", resting, 90, Side::Buy),
+            ],
+            2,
+        );
+        assert!(duplicate.is_err());
+
+        // A leg the book itself rejects
+        registry.get_book_mut("BTC/EUR").unwrap().set_tick_size(5);
+        let off_tick = registry.execute_transaction(
+            vec![
+                add("BTC/USD", usd_bid, 100, Side::Buy),
+                add("BTC/EUR", Id::new_uuid(), 91, Side::Buy),
+            ],
+            2,
+        );
+        assert!(matches!(
+            off_tick,
+            Err(OrderBookError::InvalidTickSize { .. })
+        ));
+
+        let unknown = registry.execute_transaction(
+            vec![
+                add("BTC/USD", usd_bid, 100, Side::Buy),
+                add("DOGE/USD", Id::new_uuid(), 1, Side::Buy),
+            ],
+            3,
+        );
+        assert!(unknown.is_err());
+
+        assert_eq!(registry.next_sequence(), 1);
+        let usd = registry.get_book("BTC/USD").expect("usd");
+        assert!(usd.get_order(usd_bid).is_none());
+        assert_eq!(usd.best_bid(), None);
+        let eur = registry.get_book("BTC/EUR").expect("eur");
+        assert_eq!(eur.best_ask_size(), Some((95, 10)));
+        assert_eq!(eur.best_bid(), None);
+    }
+
+    #[test]
+    fn test_leg_releasing_parked_market_orders_is_rejected() {
+        let mut registry = spread_registry();
+        let usd = registry.get_book_mut("BTC/USD").unwrap();
+        usd.set_market_order_empty_book_policy(
+            crate::orderbook::MarketOrderEmptyBookPolicy::ParkAsPending,
+        );
+        let parked = Id::new_uuid();
+        usd.submit_market_order(parked, 5, Side::Buy).unwrap();
+
+        // Resting the ask would fill the parked buy before the second leg fails
+        let result = registry.execute_transaction(
+            vec![
+                add("BTC/USD", Id::new_uuid(), 100, Side::Sell),
+                add("DOGE/USD", Id::new_uuid(), 1, Side::Buy),
+            ],
+            1,
+        );
+        assert!(matches!(
+            result,
+            Err(OrderBookError::InvalidOperation { ref message }) if message.contains("leg 0")
+        ));
+
+        assert_eq!(registry.next_sequence(), 0);
+        let usd = registry.get_book("BTC/USD").expect("usd");
+        assert_eq!(usd.last_trade_price(), None);
+        assert_eq!(usd.best_ask(), None);
+        let parked_orders = usd.parked_market_orders();
+        assert_eq!(parked_orders.len(), 1);
+        assert_eq!(parked_orders[0].order_id, parked);
+        assert_eq!(parked_orders[0].quantity, 5);
+
+        // A bid leg leaves the parked buy alone
+        let events = registry
+            .execute_transaction(vec![add("BTC/USD", Id::new_uuid(), 90, Side::Buy)], 2)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_symbol_is_rejected_without_a_gap() {
+        let mut registry: BookRegistry<()> = BookRegistry::default();
+        registry.add_book("BTC/USD");
+
+        let rejected = registry.execute(add("DOGE/USD", Id::new_uuid(), 1, Side::Buy), 0);
+        assert_eq!(rejected.event.sequence_num, 0);
+        assert!(matches!(
+            rejected.event.result,
+            SequencerResult::Rejected { ref reason } if reason.contains("DOGE/USD")
+        ));
+
+        let accepted = registry.execute(add("BTC/USD", Id::new_uuid(), 100, Side::Buy), 1);
+        assert_eq!(accepted.event.sequence_num, 1);
+        assert!(matches!(
+            accepted.event.result,
+            SequencerResult::OrderRested { .. }
+        ));
+        assert_eq!(registry.book_count(), 1);
+    }
+
+    #[test]
+    fn test_execute_expecting_rejects_stale_sequence() {
+        let mut registry: BookRegistry<()> = BookRegistry::new();
+        registry.add_book("BTC/USD");
+        let observed = registry.next_sequence();
+        registry.execute(add("BTC/USD", Id::new_uuid(), 100, Side::Buy), 0);
+
+        // Another command was sequenced since the client looked
+        let stale = Id::new_uuid();
+        let event =
+            registry.execute_expecting(add("BTC/USD", stale, 101, Side::Buy), Some(observed), 1);
+        assert_eq!(event.event.sequence_num, 1);
+        assert!(matches!(
+            &event.event.result,
+            SequencerResult::Rejected { reason } if reason == "sequence conflict: expected 0, actual 1"
+        ));
+        let book = registry.get_book("BTC/USD").unwrap();
+        assert!(book.get_order(stale).is_none());
+        assert_eq!(book.best_bid(), Some(100));
+
+        let current = Id::new_uuid();
+        let expected = registry.next_sequence();
+        let event =
+            registry.execute_expecting(add("BTC/USD", current, 101, Side::Buy), Some(expected), 2);
+        assert!(matches!(
+            event.event.result,
+            SequencerResult::OrderRested { .. }
+        ));
+        assert_eq!(registry.get_book("BTC/USD").unwrap().best_bid(), Some(101));
+        let event =
+            registry.execute_expecting(add("BTC/USD", Id::new_uuid(), 99, Side::Buy), None, 3);
+        assert!(matches!(
+            event.event.result,
+            SequencerResult::OrderRested { .. }
+        ));
+    }
+}
@@ -5,12 +5,20 @@
 //! identical final state. This enables disaster recovery, audit compliance,
 //! and state verification.
 
+use super::coalesce::update_order_id;
 use super::error::JournalError;
+use super::executor::apply_config_change;
 use super::journal::Journal;
 use super::types::{SequencerCommand, SequencerEvent, SequencerResult};
-use crate::orderbook::{OrderBook, OrderBookError, OrderBookSnapshot};
+use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+use crate::orderbook::stp::STPMode;
+use crate::orderbook::{CancelOutcome, OrderBook, OrderBookError, OrderBookSnapshot};
+use pricelevel::{Id, PriceLevelSnapshot, Side};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 /// Errors that can occur during journal replay.
@@ -55,6 +63,39 @@ pub enum ReplayError {
     /// Journal read error during replay.
     #[error("journal error during replay: {0}")]
     JournalError(#[from] JournalError),
+
+    /// The requested events have been trimmed from the journal.
+    #[error("journal trimmed: earliest available sequence is {earliest_available}")]
+    Trimmed {
+        /// The earliest sequence number still in the journal.
+        earliest_available: u64,
+    },
+
+    /// Two journals hold different commands under the same sequence number.
+    #[error("conflicting events at sequence {sequence}")]
+    ConflictingEvent {
+        /// The sequence number both journals use.
+        sequence: u64,
+    },
+
+    /// The receiving end of a replay channel was dropped.
+    #[error("replay channel closed at sequence {sequence_num}")]
+    ChannelClosed {
+        /// The sequence number of the event that could not be forwarded.
+        sequence_num: u64,
+    },
+}
+
+/// An event applied during [`ReplayEngine::replay_to_channel`], together
+/// with the price level changes it caused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayedEvent<T> {
+    /// The applied journal event.
+    pub event: SequencerEvent<T>,
+    /// Price level changes produced by applying the event, in emission
+    /// order. Each carries the new visible quantity of its level; a
+    /// quantity of zero means the level was removed.
+    pub changes: Vec<PriceLevelChangedEvent>,
 }
 
 /// Stateless replay engine that reconstructs [`OrderBook`] state from a [`Journal`].
@@ -85,6 +126,7 @@ where
     ///
     /// - [`ReplayError::EmptyJournal`] if the journal has no events
     /// - [`ReplayError::InvalidSequence`] if `from_sequence` > last journal sequence
+    /// - [`ReplayError::Trimmed`] if `from_sequence` has been trimmed from the journal
     /// - [`ReplayError::OrderBookError`] if a command fails unexpectedly during replay
     /// - [`ReplayError::JournalError`] if reading from the journal fails
     pub fn replay_from(
@@ -115,6 +157,202 @@ where
         from_sequence: u64,
         symbol: &str,
         progress: impl Fn(u64, u64),
+    ) -> Result<(OrderBook<T>, u64), ReplayError> {
+        Self::replay_with(journal, from_sequence, symbol, |_, event, count| {
+            progress(count, event.sequence_num);
+        })
+    }
+
+    /// Replays events like [`replay_from`](Self::replay_from), but halts at
+    /// the first event that succeeded when it was journaled and no longer
+    /// applies, a sign that matching logic has drifted since.
+    ///
+    /// Besides the commands that fail outright, strict replay reports
+    /// commands that would silently do nothing: a cancel, update or requeue
+    /// of an order that is not resting, and a mass cancel that removes a
+    /// different number of orders than the journal recorded. Events that
+    /// were rejected when journaled are still skipped.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`replay_from`](Self::replay_from). A drifted event is
+    /// reported as [`ReplayError::OrderBookError`] with its sequence number;
+    /// an order that is not resting as [`OrderBookError::OrderNotFound`],
+    /// a mass cancel count mismatch as
+    /// [`OrderBookError::InvalidOperation`].
+    pub fn replay_strict(
+        journal: &impl Journal<T>,
+        from_sequence: u64,
+        symbol: &str,
+    ) -> Result<(OrderBook<T>, u64), ReplayError> {
+        Self::replay_onto(
+            OrderBook::new(symbol),
+            journal,
+            from_sequence,
+            true,
+            |_, _, _| {},
+        )
+    }
+
+    /// Replays the full journal, servicing every [`SequencerCommand::Snapshot`]
+    /// at its exact sequence point.
+    ///
+    /// Returns, for each snapshot command in journal order, its sequence
+    /// number together with a snapshot of the top `depth` levels reflecting
+    /// exactly the events before it.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`replay_from`](Self::replay_from).
+    pub fn replay_snapshots(
+        journal: &impl Journal<T>,
+        symbol: &str,
+    ) -> Result<Vec<(u64, OrderBookSnapshot)>, ReplayError> {
+        let mut snapshots = Vec::new();
+        Self::replay_with(journal, 0, symbol, |book, event, _| {
+            if let SequencerCommand::Snapshot { depth } = event.command {
+                snapshots.push((event.sequence_num, book.create_snapshot(depth)));
+            }
+        })?;
+        Ok(snapshots)
+    }
+
+    /// Replays events from `from_sequence` onwards and forwards each applied
+    /// event, with the price level changes it caused, to `sender`.
+    ///
+    /// Rejected events are skipped and not forwarded. Downstream consumers,
+    /// e.g. read replicas, can rebuild the book from the forwarded events or
+    /// maintain aggregated depth from the level changes alone. `sender` is
+    /// dropped when replay finishes, which ends the consumers' receive loop.
+    ///
+    /// Returns the reconstructed book and the sequence number of the last
+    /// event read.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`replay_from`](Self::replay_from), plus
+    /// [`ReplayError::ChannelClosed`] if the receiver is dropped before
+    /// replay finishes.
+    pub fn replay_to_channel(
+        journal: &impl Journal<T>,
+        from_sequence: u64,
+        symbol: &str,
+        sender: Sender<ReplayedEvent<T>>,
+    ) -> Result<(OrderBook<T>, u64), ReplayError> {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let mut book = OrderBook::new(symbol);
+        let collected = Arc::clone(&changes);
+        book.set_price_level_listener(Arc::new(move |change| {
+            if let Ok(mut changes) = collected.lock() {
+                changes.push(change);
+            }
+        }));
+
+        let mut closed = None;
+        let (mut book, last_applied_seq) =
+            Self::replay_onto(book, journal, from_sequence, false, |_, event, _| {
+                let changes = changes
+                    .lock()
+                    .map(|mut changes| std::mem::take(&mut *changes))
+                    .unwrap_or_default();
+                if closed.is_some() || matches!(event.result, SequencerResult::Rejected { .. }) {
+                    return;
+                }
+                let replayed = ReplayedEvent {
+                    event: event.clone(),
+                    changes,
+                };
+                if sender.send(replayed).is_err() {
+                    closed = Some(event.sequence_num);
+                }
+            })?;
+
+        if let Some(sequence_num) = closed {
+            return Err(ReplayError::ChannelClosed { sequence_num });
+        }
+        book.remove_price_level_listener();
+        Ok((book, last_applied_seq))
+    }
+
+    /// Merges the events of two journal fragments, e.g. recovered from
+    /// different nodes, into one contiguous sequence ordered by sequence
+    /// number.
+    ///
+    /// A sequence number present in both fragments is kept once, from `a`,
+    /// if both hold the same command; their timestamps and results are not
+    /// compared. Trimmed journals contribute the events they still hold.
+    /// The merged events can be appended to a fresh journal and replayed.
+    ///
+    /// # Errors
+    ///
+    /// - [`ReplayError::EmptyJournal`] if neither fragment holds any events
+    /// - [`ReplayError::ConflictingEvent`] if the fragments hold different
+    ///   commands under the same sequence number
+    /// - [`ReplayError::SequenceGap`] if neither fragment covers a sequence
+    ///   number between the first and the last merged event
+    /// - [`ReplayError::JournalError`] if reading a fragment or serializing
+    ///   a command for comparison fails
+    pub fn merge_journals(
+        a: &impl Journal<T>,
+        b: &impl Journal<T>,
+    ) -> Result<Vec<SequencerEvent<T>>, ReplayError> {
+        let command_bytes = |event: &SequencerEvent<T>| {
+            serde_json::to_vec(&event.command).map_err(|e| JournalError::SerializationError {
+                message: e.to_string(),
+            })
+        };
+
+        let mut merged: BTreeMap<u64, SequencerEvent<T>> = BTreeMap::new();
+        for event in a.clone_events()?.into_iter().chain(b.clone_events()?) {
+            match merged.get(&event.sequence_num) {
+                Some(kept) if command_bytes(kept)? != command_bytes(&event)? => {
+                    return Err(ReplayError::ConflictingEvent {
+                        sequence: event.sequence_num,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    merged.insert(event.sequence_num, event);
+                }
+            }
+        }
+
+        let Some(&first) = merged.keys().next() else {
+            return Err(ReplayError::EmptyJournal);
+        };
+        for (expected, &found) in (first..).zip(merged.keys()) {
+            if found != expected {
+                return Err(ReplayError::SequenceGap { expected, found });
+            }
+        }
+        Ok(merged.into_values().collect())
+    }
+
+    /// Shared replay loop. `on_event` is invoked after each event has been
+    /// applied, with the number of events applied so far.
+    fn replay_with(
+        journal: &impl Journal<T>,
+        from_sequence: u64,
+        symbol: &str,
+        on_event: impl FnMut(&OrderBook<T>, &SequencerEvent<T>, u64),
+    ) -> Result<(OrderBook<T>, u64), ReplayError> {
+        Self::replay_onto(
+            OrderBook::new(symbol),
+            journal,
+            from_sequence,
+            false,
+            on_event,
+        )
+    }
+
+    /// Replay loop over a caller-provided fresh `book`. `strict` reports
+    /// events that no longer apply (see [`replay_strict`](Self::replay_strict)).
+    fn replay_onto(
+        mut book: OrderBook<T>,
+        journal: &impl Journal<T>,
+        from_sequence: u64,
+        strict: bool,
+        mut on_event: impl FnMut(&OrderBook<T>, &SequencerEvent<T>, u64),
     ) -> Result<(OrderBook<T>, u64), ReplayError> {
         let last_seq = match journal.last_sequence() {
             Some(seq) => seq,
@@ -128,12 +366,16 @@ where
             });
         }
 
-        let book = OrderBook::new(symbol);
         let mut last_applied_seq = 0u64;
         let mut count = 0u64;
         let mut expected_seq = from_sequence;
 
-        let iter = journal.read_from(from_sequence)?;
+        let iter = journal.read_from(from_sequence).map_err(|err| match err {
+            JournalError::Trimmed {
+                earliest_available, ..
+            } => ReplayError::Trimmed { earliest_available },
+            err => ReplayError::JournalError(err),
+        })?;
 
         for entry_result in iter {
             let entry = entry_result?;
@@ -147,11 +389,14 @@ where
                 });
             }
 
-            Self::apply_event(&book, event)?;
+            Self::apply_event(&mut book, event, strict)?;
+            book.evaluate_trailing_stops();
+            // Triggered submissions are journaled as their own events
+            drop(book.take_triggered_contingents());
             last_applied_seq = event.sequence_num;
             count = count.saturating_add(1);
             expected_seq = expected_seq.saturating_add(1);
-            progress(count, last_applied_seq);
+            on_event(&book, event, count);
         }
 
         Ok((book, last_applied_seq))
@@ -181,27 +426,93 @@ where
     ///
     /// Events with `Rejected` results are skipped — they represent commands
     /// that failed at write time and must not be re-applied during replay.
-    fn apply_event(book: &OrderBook<T>, event: &SequencerEvent<T>) -> Result<(), ReplayError> {
+    /// Adds are matched under the self-trade prevention mode recorded in
+    /// their result, so STP decisions replay as they were taken. Market
+    /// orders park under the policy the journal's
+    /// [`ConfigChange::MarketOrderEmptyBookPolicy`](super::ConfigChange::MarketOrderEmptyBookPolicy)
+    /// events set, and parked orders fill again on the replayed adds.
+    ///
+    /// With `strict`, commands that would silently do nothing are errors.
+    fn apply_event(
+        book: &mut OrderBook<T>,
+        event: &SequencerEvent<T>,
+        strict: bool,
+    ) -> Result<(), ReplayError> {
         // Skip events whose original execution was rejected.
         if matches!(event.result, SequencerResult::Rejected { .. }) {
             return Ok(());
         }
+        if strict
+            && let Some(order_id) = Self::addressed_order(event)
+            && book.get_order(order_id).is_none()
+        {
+            return Err(ReplayError::OrderBookError {
+                sequence_num: event.sequence_num,
+                source: OrderBookError::OrderNotFound(order_id.to_string()),
+            });
+        }
+        book.stp_mode = event
+            .result
+            .stp_outcome()
+            .map_or(STPMode::None, |outcome| outcome.mode);
 
         match &event.command {
-            SequencerCommand::AddOrder(order) => {
-                book.add_order(order.clone())
+            SequencerCommand::AddOrder(order) => match book.add_order(order.clone()) {
+                // The rejection is replayed for the resting orders it cancelled
+                Err(OrderBookError::SelfTradePrevented { .. })
+                    if matches!(event.result, SequencerResult::STPTriggered { .. }) => {}
+                Err(e) => {
+                    return Err(ReplayError::OrderBookError {
+                        sequence_num: event.sequence_num,
+                        source: e,
+                    });
+                }
+                Ok(_) => {}
+            },
+            SequencerCommand::CancelOrder(id) => {
+                book.cancel_order(*id)
                     .map_err(|e| ReplayError::OrderBookError {
                         sequence_num: event.sequence_num,
                         source: e,
                     })?;
             }
-            SequencerCommand::CancelOrder(id) => {
-                book.cancel_order(*id)
+            SequencerCommand::CancelOrderIdempotent(id) => {
+                if let CancelOutcome::Error(e) = book.cancel_order_idempotent(*id) {
+                    return Err(ReplayError::OrderBookError {
+                        sequence_num: event.sequence_num,
+                        source: e,
+                    });
+                }
+            }
+            SequencerCommand::CancelOrderAs { order_id, user_id } => {
+                book.cancel_order_as(*order_id, *user_id).map_err(|e| {
+                    ReplayError::OrderBookError {
+                        sequence_num: event.sequence_num,
+                        source: e,
+                    }
+                })?;
+            }
+            SequencerCommand::AddContingent {
+                trigger_order_id,
+                order,
+            } => {
+                book.add_contingent(*trigger_order_id, order.clone())
                     .map_err(|e| ReplayError::OrderBookError {
                         sequence_num: event.sequence_num,
                         source: e,
                     })?;
             }
+            SequencerCommand::AddTrailingStop { order, trail } => {
+                book.add_trailing_stop(order.clone(), *trail).map_err(|e| {
+                    ReplayError::OrderBookError {
+                        sequence_num: event.sequence_num,
+                        source: e,
+                    }
+                })?;
+            }
+            SequencerCommand::ContingentTriggered { .. } => {
+                // Audit only: the replayed fill already submitted the order.
+            }
             SequencerCommand::UpdateOrder(update) => {
                 book.update_order(*update)
                     .map_err(|e| ReplayError::OrderBookError {
@@ -209,6 +520,19 @@ where
                         source: e,
                     })?;
             }
+            SequencerCommand::RequeueOrder(id) => {
+                // Reuse the journaled id so later commands can address it
+                let requeued = match event.result {
+                    SequencerResult::OrderRequeued { new_order_id, .. } => {
+                        book.requeue_order_as(*id, new_order_id)
+                    }
+                    _ => book.requeue_order(*id).map(|_| ()),
+                };
+                requeued.map_err(|e| ReplayError::OrderBookError {
+                    sequence_num: event.sequence_num,
+                    source: e,
+                })?;
+            }
             SequencerCommand::MarketOrder { id, quantity, side } => {
                 book.submit_market_order(*id, *quantity, *side)
                     .map_err(|e| ReplayError::OrderBookError {
@@ -216,72 +540,240 @@ where
                         source: e,
                     })?;
             }
+            SequencerCommand::Snapshot { .. } => {
+                // Read-only: snapshots do not change book state.
+            }
             SequencerCommand::CancelAll => {
-                let _ = book.cancel_all_orders();
+                let result = book.cancel_all_orders();
+                Self::check_mass_cancel(strict, event, result.cancelled_count())?;
+            }
+            SequencerCommand::SetReferencePrice { price } => {
+                book.set_reference_price(*price);
+            }
+            SequencerCommand::SetExternalBbo { bbo } => {
+                book.set_external_bbo(*bbo);
+            }
+            SequencerCommand::UpdateConfig(change) => {
+                apply_config_change(book, change).map_err(|e| ReplayError::OrderBookError {
+                    sequence_num: event.sequence_num,
+                    source: e,
+                })?;
             }
             SequencerCommand::CancelBySide { side } => {
-                let _ = book.cancel_orders_by_side(*side);
+                let result = book.cancel_orders_by_side(*side);
+                Self::check_mass_cancel(strict, event, result.cancelled_count())?;
             }
             SequencerCommand::CancelByUser { user_id } => {
-                let _ = book.cancel_orders_by_user(*user_id);
+                let result = book.cancel_orders_by_user(*user_id);
+                Self::check_mass_cancel(strict, event, result.cancelled_count())?;
             }
             SequencerCommand::CancelByPriceRange {
                 side,
                 min_price,
                 max_price,
             } => {
-                let _ = book.cancel_orders_by_price_range(*side, *min_price, *max_price);
+                let result = book.cancel_orders_by_price_range(*side, *min_price, *max_price);
+                Self::check_mass_cancel(strict, event, result.cancelled_count())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the resting order a successful `event` required, if its
+    /// command addresses one.
+    fn addressed_order(event: &SequencerEvent<T>) -> Option<Id> {
+        match &event.command {
+            SequencerCommand::CancelOrder(id) | SequencerCommand::RequeueOrder(id) => Some(*id),
+            SequencerCommand::CancelOrderAs { order_id, .. } => Some(*order_id),
+            SequencerCommand::UpdateOrder(update) => Some(update_order_id(update)),
+            SequencerCommand::CancelOrderIdempotent(id)
+                if matches!(event.result, SequencerResult::OrderCancelled { .. }) =>
+            {
+                Some(*id)
             }
+            _ => None,
         }
+    }
 
+    /// With `strict`, report a mass cancel that removed `cancelled` orders
+    /// where the journal recorded a different count.
+    fn check_mass_cancel(
+        strict: bool,
+        event: &SequencerEvent<T>,
+        cancelled: usize,
+    ) -> Result<(), ReplayError> {
+        if let SequencerResult::MassCancelled { result } = &event.result
+            && strict
+            && result.cancelled_count() != cancelled
+        {
+            return Err(ReplayError::OrderBookError {
+                sequence_num: event.sequence_num,
+                source: OrderBookError::InvalidOperation {
+                    message: format!(
+                        "mass cancel removed {cancelled} orders, journal recorded {}",
+                        result.cancelled_count()
+                    ),
+                },
+            });
+        }
         Ok(())
     }
 }
 
-/// Compares two [`OrderBookSnapshot`]s for structural equality.
+/// A single price-level difference between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelDifference {
+    /// The level exists in the expected snapshot but not in the actual one.
+    MissingLevel {
+        /// Side of the level.
+        side: Side,
+        /// Price of the level.
+        price: u128,
+        /// Visible quantity in the expected snapshot.
+        expected_quantity: u64,
+    },
+    /// The level exists in the actual snapshot but not in the expected one.
+    ExtraLevel {
+        /// Side of the level.
+        side: Side,
+        /// Price of the level.
+        price: u128,
+        /// Visible quantity in the actual snapshot.
+        actual_quantity: u64,
+    },
+    /// The level exists in both snapshots with different visible quantities.
+    QuantityMismatch {
+        /// Side of the level.
+        side: Side,
+        /// Price of the level.
+        price: u128,
+        /// Visible quantity in the actual snapshot.
+        actual_quantity: u64,
+        /// Visible quantity in the expected snapshot.
+        expected_quantity: u64,
+    },
+}
+
+/// Structured result of comparing two [`OrderBookSnapshot`]s.
 ///
-/// Two snapshots are considered equal when:
-/// - `symbol` is identical
-/// - The sorted bid price levels match (by price, then visible quantity)
-/// - The sorted ask price levels match (by price, then visible quantity)
+/// Produced by [`snapshot_diff`]. An empty comparison means the snapshots
+/// match under the same rules as [`snapshots_match`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotComparison {
+    /// `(actual, expected)` symbols when they differ.
+    pub symbol_mismatch: Option<(String, String)>,
+    /// Bid level differences, best (highest) price first.
+    pub bid_differences: Vec<LevelDifference>,
+    /// Ask level differences, best (lowest) price first.
+    pub ask_differences: Vec<LevelDifference>,
+}
+
+impl SnapshotComparison {
+    /// Returns `true` if no differences were found.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.symbol_mismatch.is_none()
+            && self.bid_differences.is_empty()
+            && self.ask_differences.is_empty()
+    }
+
+    /// Total number of reported differences, including a symbol mismatch.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        usize::from(self.symbol_mismatch.is_some())
+            + self.bid_differences.len()
+            + self.ask_differences.len()
+    }
+}
+
+/// Compares two [`OrderBookSnapshot`]s and reports every difference.
 ///
-/// Timestamps are intentionally excluded from comparison because replayed
-/// books may be created at a different wall-clock time than the original.
+/// Levels are matched by price on each side and compared by visible
+/// quantity. Timestamps are intentionally excluded because replayed books
+/// may be created at a different wall-clock time than the original.
 #[must_use]
-pub fn snapshots_match(actual: &OrderBookSnapshot, expected: &OrderBookSnapshot) -> bool {
-    if actual.symbol != expected.symbol {
-        return false;
-    }
+pub fn snapshot_diff(
+    actual: &OrderBookSnapshot,
+    expected: &OrderBookSnapshot,
+) -> SnapshotComparison {
+    let symbol_mismatch = (actual.symbol != expected.symbol)
+        .then(|| (actual.symbol.clone(), expected.symbol.clone()));
 
-    // Compare bids sorted by price descending (highest bid first)
-    let mut actual_bids: Vec<_> = actual.bids.iter().collect();
-    let mut expected_bids: Vec<_> = expected.bids.iter().collect();
-    actual_bids.sort_by_key(|b| std::cmp::Reverse(b.price()));
-    expected_bids.sort_by_key(|b| std::cmp::Reverse(b.price()));
+    let mut bid_differences = diff_side(Side::Buy, &actual.bids, &expected.bids);
+    // Highest bid first
+    bid_differences.reverse();
+    let ask_differences = diff_side(Side::Sell, &actual.asks, &expected.asks);
 
-    if actual_bids.len() != expected_bids.len() {
-        return false;
-    }
-    for (a, b) in actual_bids.iter().zip(expected_bids.iter()) {
-        if a.price() != b.price() || a.visible_quantity() != b.visible_quantity() {
-            return false;
-        }
+    SnapshotComparison {
+        symbol_mismatch,
+        bid_differences,
+        ask_differences,
     }
+}
 
-    // Compare asks sorted by price ascending (lowest ask first)
-    let mut actual_asks: Vec<_> = actual.asks.iter().collect();
-    let mut expected_asks: Vec<_> = expected.asks.iter().collect();
-    actual_asks.sort_by_key(|l| l.price());
-    expected_asks.sort_by_key(|l| l.price());
+/// Diffs one side of two snapshots, returning differences in ascending price order.
+fn diff_side(
+    side: Side,
+    actual: &[PriceLevelSnapshot],
+    expected: &[PriceLevelSnapshot],
+) -> Vec<LevelDifference> {
+    let actual_levels: BTreeMap<u128, u64> = actual
+        .iter()
+        .map(|level| (level.price(), level.visible_quantity()))
+        .collect();
+    let expected_levels: BTreeMap<u128, u64> = expected
+        .iter()
+        .map(|level| (level.price(), level.visible_quantity()))
+        .collect();
 
-    if actual_asks.len() != expected_asks.len() {
-        return false;
-    }
-    for (a, b) in actual_asks.iter().zip(expected_asks.iter()) {
-        if a.price() != b.price() || a.visible_quantity() != b.visible_quantity() {
-            return false;
-        }
-    }
+    let prices: BTreeSet<u128> = actual_levels
+        .keys()
+        .chain(expected_levels.keys())
+        .copied()
+        .collect();
+
+    prices
+        .into_iter()
+        .filter_map(
+            |price| match (actual_levels.get(&price), expected_levels.get(&price)) {
+                (Some(&actual_quantity), Some(&expected_quantity))
+                    if actual_quantity != expected_quantity =>
+                {
+                    Some(LevelDifference::QuantityMismatch {
+                        side,
+                        price,
+                        actual_quantity,
+                        expected_quantity,
+                    })
+                }
+                (Some(&actual_quantity), None) => Some(LevelDifference::ExtraLevel {
+                    side,
+                    price,
+                    actual_quantity,
+                }),
+                (None, Some(&expected_quantity)) => Some(LevelDifference::MissingLevel {
+                    side,
+                    price,
+                    expected_quantity,
+                }),
+                _ => None,
+            },
+        )
+        .collect()
+}
 
-    true
+/// Compares two [`OrderBookSnapshot`]s for structural equality.
+///
+/// Two snapshots are considered equal when:
+/// - `symbol` is identical
+/// - The bid price levels match (by price, then visible quantity)
+/// - The ask price levels match (by price, then visible quantity)
+///
+/// Timestamps are intentionally excluded from comparison because replayed
+/// books may be created at a different wall-clock time than the original.
+/// Use [`snapshot_diff`] to find out what differs.
+#[must_use]
+pub fn snapshots_match(actual: &OrderBookSnapshot, expected: &OrderBookSnapshot) -> bool {
+    snapshot_diff(actual, expected).is_empty()
 }
@@ -5,8 +5,15 @@
 //! also used by the `Journal` trait for write-ahead
 //! logging and deterministic replay.
 
+use super::coalesce::update_order_id;
+use super::error::JournalError;
+use crate::orderbook::fees::FeeSchedule;
 use crate::orderbook::mass_cancel::MassCancelResult;
+use crate::orderbook::parked::MarketOrderEmptyBookPolicy;
+use crate::orderbook::reference_price::ReferencePricePolicy;
+use crate::orderbook::stp::STPTriggered;
 use crate::orderbook::trade::TradeResult;
+use crate::orderbook::trailing_stop::TrailAmount;
 use pricelevel::{Hash32, Id, OrderType, OrderUpdate, Side};
 use serde::{Deserialize, Serialize};
 
@@ -26,9 +33,63 @@ pub enum SequencerCommand<T> {
     /// Cancel an existing order by its identifier.
     CancelOrder(Id),
 
+    /// Cancel an order by its identifier, treating an absent order as success.
+    ///
+    /// Retried cancels for orders that are already gone are journaled as
+    /// [`SequencerResult::CancelAlreadyGone`] instead of a rejection.
+    CancelOrderIdempotent(Id),
+
+    /// Cancel an existing order on behalf of a user, rejecting the cancel
+    /// unless the user owns the order (see `OrderBook::cancel_order_as`).
+    CancelOrderAs {
+        /// The order identifier.
+        order_id: Id,
+        /// The user requesting the cancel.
+        user_id: Hash32,
+    },
+
     /// Update an existing order (price, quantity, or both).
     UpdateOrder(OrderUpdate),
 
+    /// Register an order to be submitted when a resting trigger order
+    /// fully fills (see `OrderBook::add_contingent`).
+    AddContingent {
+        /// The resting order whose full fill submits `order`.
+        trigger_order_id: Id,
+        /// The order to submit.
+        order: OrderType<T>,
+    },
+
+    /// Audit record of a contingent order the book submitted because its
+    /// trigger filled during the preceding command.
+    ///
+    /// Emitted by the command loop, never submitted by clients. Replay
+    /// skips it: re-applying the `AddContingent` and the fill submits the
+    /// order again.
+    ContingentTriggered {
+        /// The order whose fill triggered the submission.
+        trigger_order_id: Id,
+        /// The submitted contingent order.
+        order: OrderType<T>,
+    },
+
+    /// Hold an order off the book until the market trails back from its
+    /// best price (see `OrderBook::add_trailing_stop`). When the stop
+    /// fires, its submission is journaled as a
+    /// [`ContingentTriggered`](Self::ContingentTriggered) whose trigger is
+    /// the stop's own id.
+    AddTrailingStop {
+        /// The order to submit when the stop fires.
+        order: OrderType<T>,
+        /// Distance the trigger trails the best trade price by.
+        trail: TrailAmount,
+    },
+
+    /// Move a resting order to the back of its price level's queue,
+    /// keeping its price and quantity. The order rests under a new id
+    /// afterwards (see `OrderBook::requeue_order`).
+    RequeueOrder(Id),
+
     /// Submit an aggressive market order that sweeps available liquidity.
     MarketOrder {
         /// The order identifier.
@@ -39,9 +100,33 @@ pub enum SequencerCommand<T> {
         side: Side,
     },
 
+    /// Take a point-in-time snapshot of the top `depth` levels per side.
+    ///
+    /// Serviced in sequence like any other command, so the snapshot reflects
+    /// exactly the commands sequenced before it. Read-only: the book is not
+    /// changed and replay skips it (see
+    /// [`ReplayEngine::replay_snapshots`](super::ReplayEngine::replay_snapshots)).
+    Snapshot {
+        /// Maximum number of price levels per side.
+        depth: usize,
+    },
+
     /// Cancel all orders in the book.
     CancelAll,
 
+    /// Set the manual reference price used for price bands and stop triggers.
+    SetReferencePrice {
+        /// The new manual reference price.
+        price: u128,
+    },
+
+    /// Set or clear the external reference BBO used for locked/crossed
+    /// market prevention.
+    SetExternalBbo {
+        /// The external `(bid, ask)`, or `None` to clear it.
+        bbo: Option<(u128, u128)>,
+    },
+
     /// Cancel all orders on the specified side.
     CancelBySide {
         /// The side to cancel (Buy or Sell).
@@ -63,6 +148,57 @@ pub enum SequencerCommand<T> {
         /// Maximum price (inclusive).
         max_price: u128,
     },
+
+    /// Change a setting of the book, in sequence with the orders around it
+    /// so that replay applies it at the same point. Needs exclusive access
+    /// to the book; see
+    /// [`execute_command_mut`](super::execute_command_mut).
+    UpdateConfig(ConfigChange),
+}
+
+/// A compare-and-swap change of a book setting.
+///
+/// The change only applies if the setting still has its `expected` value,
+/// so two operators racing to reconfigure a book cannot silently overwrite
+/// each other: the later change is rejected instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigChange {
+    /// Replace the tick size. A tiered tick schedule never matches
+    /// `expected`.
+    TickSize {
+        /// The tick size the book must have, or `None` if tick validation
+        /// must be disabled.
+        expected: Option<u128>,
+        /// The new tick size, or `None` to disable tick validation.
+        tick_size: Option<u128>,
+    },
+
+    /// Replace the fee schedule.
+    FeeSchedule {
+        /// The fee schedule the book must have.
+        expected: Option<FeeSchedule>,
+        /// The new fee schedule, or `None` to charge no fees.
+        fee_schedule: Option<FeeSchedule>,
+    },
+
+    /// Replace the reference price policy, which moves price bands and
+    /// trailing stops.
+    ReferencePricePolicy {
+        /// The policy the book must have.
+        expected: ReferencePricePolicy,
+        /// The new policy.
+        policy: ReferencePricePolicy,
+    },
+
+    /// Replace how market orders against an empty opposite side are
+    /// handled. Replay only parks market orders once this change is
+    /// journaled.
+    MarketOrderEmptyBookPolicy {
+        /// The policy the book must have.
+        expected: MarketOrderEmptyBookPolicy,
+        /// The new policy.
+        policy: MarketOrderEmptyBookPolicy,
+    },
 }
 
 /// The outcome of executing a [`SequencerCommand`] against the order book.
@@ -72,40 +208,189 @@ pub enum SequencerCommand<T> {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SequencerResult {
     /// An order was successfully added to the book.
+    ///
+    /// This result does not say whether the order matched. Commands
+    /// executed with [`execute_command`](super::execute_command) report
+    /// [`OrderRested`](Self::OrderRested),
+    /// [`PartiallyFilledResting`](Self::PartiallyFilledResting),
+    /// [`ParkedOrdersFilled`](Self::ParkedOrdersFilled),
+    /// [`TradeExecuted`](Self::TradeExecuted) or
+    /// [`OrderUnfilled`](Self::OrderUnfilled) instead.
     OrderAdded {
         /// The identifier of the newly added order.
         order_id: Id,
     },
 
+    /// An added order rested in the book without matching.
+    OrderRested {
+        /// The identifier of the resting order.
+        order_id: Id,
+    },
+
+    /// An added order matched partially and its remainder rests in the book.
+    PartiallyFilledResting {
+        /// The identifier of the resting order.
+        order_id: Id,
+        /// The trades executed before the remainder rested.
+        trade_result: TradeResult,
+    },
+
+    /// An added order matched as far as it could and rested, and market
+    /// orders parked for liquidity then traded against it. Whatever they
+    /// left of it still rests.
+    ParkedOrdersFilled {
+        /// The identifier of the added order.
+        order_id: Id,
+        /// The trades the added order executed before it rested, possibly
+        /// none.
+        trade_result: TradeResult,
+        /// The fills of the parked market orders, in execution order.
+        parked_fills: Vec<TradeResult>,
+    },
+
+    /// A contingent order was registered.
+    ContingentAdded {
+        /// The order whose full fill submits the contingent order.
+        trigger_order_id: Id,
+        /// The identifier of the contingent order.
+        order_id: Id,
+    },
+
+    /// A trailing stop was registered.
+    TrailingStopAdded {
+        /// The identifier of the stop's order.
+        order_id: Id,
+        /// The initial trigger price.
+        trigger_price: u128,
+    },
+
     /// An order was successfully cancelled.
     OrderCancelled {
         /// The identifier of the cancelled order.
         order_id: Id,
     },
 
+    /// An idempotent cancel targeted an order that was no longer in the book.
+    CancelAlreadyGone {
+        /// The identifier of the order that was already gone.
+        order_id: Id,
+    },
+
     /// An order was successfully updated.
     OrderUpdated {
         /// The identifier of the updated order.
         order_id: Id,
     },
 
-    /// A trade was executed (possibly partially filled).
+    /// An order was moved to the back of its price level's queue.
+    OrderRequeued {
+        /// The identifier of the order before the requeue.
+        order_id: Id,
+        /// The identifier the order rests under after the requeue.
+        new_order_id: Id,
+    },
+
+    /// A trade was executed (possibly partially filled). For an added order
+    /// this means it was fully filled, or matched as far as its time in
+    /// force allowed, and nothing rests.
     TradeExecuted {
         /// The trade result containing match details, fees, and transactions.
         trade_result: TradeResult,
     },
 
+    /// An added order traded nothing and its time in force cancelled it
+    /// instead of resting, e.g. an immediate-or-cancel order that found no
+    /// crossing liquidity.
+    OrderUnfilled {
+        /// The identifier of the cancelled order.
+        order_id: Id,
+        /// The quantity that was cancelled.
+        cancelled_quantity: u64,
+    },
+
+    /// Self-trade prevention cancelled an added order before it traded, or
+    /// cancelled resting orders in its way while it rested without trading.
+    ///
+    /// When the order did trade, the outcome is reported on the
+    /// [`TradeResult`] of [`TradeExecuted`](Self::TradeExecuted) or
+    /// [`PartiallyFilledResting`](Self::PartiallyFilledResting) instead.
+    STPTriggered {
+        /// The identifier of the added (taker) order.
+        order_id: Id,
+        /// What self-trade prevention cancelled.
+        outcome: STPTriggered,
+    },
+
+    /// A market order found the opposite side empty and was parked until
+    /// liquidity arrives; see
+    /// [`MarketOrderEmptyBookPolicy`].
+    MarketOrderParked {
+        /// The identifier of the parked market order.
+        order_id: Id,
+    },
+
     /// A mass cancel operation was executed.
     MassCancelled {
         /// The result containing the count and IDs of cancelled orders.
         result: MassCancelResult,
     },
 
+    /// The manual reference price was set.
+    ReferencePriceSet {
+        /// The new manual reference price.
+        price: u128,
+    },
+
+    /// The external reference BBO was set or cleared.
+    ExternalBboSet {
+        /// The new external `(bid, ask)`, or `None` if cleared.
+        bbo: Option<(u128, u128)>,
+    },
+
+    /// A book setting was changed.
+    ConfigUpdated {
+        /// The change that was applied.
+        change: ConfigChange,
+    },
+
+    /// A point-in-time snapshot was taken.
+    ///
+    /// Only the checksum is journaled; the snapshot itself is rebuilt at the
+    /// event's sequence number by
+    /// [`ReplayEngine::replay_snapshots`](super::ReplayEngine::replay_snapshots).
+    SnapshotTaken {
+        /// Number of price levels per side the snapshot covered.
+        depth: usize,
+        /// [`OrderBookSnapshot::content_checksum`](crate::orderbook::snapshot::OrderBookSnapshot::content_checksum)
+        /// of the snapshot.
+        checksum: String,
+    },
+
     /// The command was rejected by the order book.
     Rejected {
         /// Human-readable reason for the rejection.
         reason: String,
     },
+
+    /// The command was applied but its detailed result was not retained,
+    /// e.g. for an event expanded from a
+    /// [`CompactEvent`](super::CompactEvent).
+    Unrecorded,
+}
+
+impl SequencerResult {
+    /// Returns what self-trade prevention cancelled, if it triggered while
+    /// executing the command.
+    #[must_use]
+    pub fn stp_outcome(&self) -> Option<STPTriggered> {
+        match self {
+            Self::STPTriggered { outcome, .. } => Some(*outcome),
+            Self::TradeExecuted { trade_result }
+            | Self::PartiallyFilledResting { trade_result, .. }
+            | Self::ParkedOrdersFilled { trade_result, .. } => trade_result.stp,
+            _ => None,
+        }
+    }
 }
 
 /// A sequenced event emitted by the Sequencer after processing a command.
@@ -133,3 +418,102 @@ pub struct SequencerEvent<T> {
     /// The result of executing the command.
     pub result: SequencerResult,
 }
+
+impl<T: Clone> SequencerEvent<T> {
+    /// Returns the ids of every order this event references: the order
+    /// addressed by the command, the id a requeued order rests under, the
+    /// orders removed by a mass cancel and the makers filled by a trade.
+    ///
+    /// Ids may repeat when an event references an order more than once.
+    #[must_use]
+    pub fn order_ids(&self) -> Vec<Id> {
+        let mut ids = Vec::new();
+        match &self.command {
+            SequencerCommand::AddOrder(order) | SequencerCommand::AddTrailingStop { order, .. } => {
+                ids.push(order.id())
+            }
+            SequencerCommand::CancelOrder(id)
+            | SequencerCommand::CancelOrderIdempotent(id)
+            | SequencerCommand::RequeueOrder(id)
+            | SequencerCommand::MarketOrder { id, .. } => ids.push(*id),
+            SequencerCommand::CancelOrderAs { order_id, .. } => ids.push(*order_id),
+            SequencerCommand::UpdateOrder(update) => ids.push(update_order_id(update)),
+            SequencerCommand::AddContingent {
+                trigger_order_id,
+                order,
+            }
+            | SequencerCommand::ContingentTriggered {
+                trigger_order_id,
+                order,
+            } => ids.extend([*trigger_order_id, order.id()]),
+            SequencerCommand::Snapshot { .. }
+            | SequencerCommand::CancelAll
+            | SequencerCommand::SetReferencePrice { .. }
+            | SequencerCommand::SetExternalBbo { .. }
+            | SequencerCommand::CancelBySide { .. }
+            | SequencerCommand::CancelByUser { .. }
+            | SequencerCommand::CancelByPriceRange { .. }
+            | SequencerCommand::UpdateConfig(_) => {}
+        }
+
+        match &self.result {
+            SequencerResult::OrderRequeued { new_order_id, .. } => ids.push(*new_order_id),
+            SequencerResult::MassCancelled { result } => {
+                ids.extend_from_slice(result.cancelled_order_ids());
+            }
+            SequencerResult::TradeExecuted { trade_result }
+            | SequencerResult::PartiallyFilledResting { trade_result, .. } => {
+                ids.extend(trade_result.fills().map(|fill| fill.maker_order_id));
+            }
+            _ => {}
+        }
+        ids
+    }
+
+    /// Returns `true` if this event references `order_id`; see
+    /// [`order_ids`](Self::order_ids).
+    #[must_use]
+    pub fn references_order(&self, order_id: Id) -> bool {
+        self.order_ids().contains(&order_id)
+    }
+}
+
+/// Version byte leading every [`SequencerEvent::canonical_bytes`] encoding.
+pub const CANONICAL_EVENT_VERSION: u8 = 1;
+
+impl<T: Serialize> SequencerEvent<T> {
+    /// Returns a canonical byte encoding of the event for hashing or signing.
+    ///
+    /// The layout is [`CANONICAL_EVENT_VERSION`], then `sequence_num` and
+    /// `timestamp_ns` as big-endian `u64`, then the command and the result,
+    /// each as a big-endian `u64` length followed by its compact JSON
+    /// encoding. JSON fields follow declaration order and the event types
+    /// hold no hash maps, so logically equal events encode to identical
+    /// bytes regardless of their in-memory layout, provided the `T` payload
+    /// serializes deterministically.
+    ///
+    /// # Errors
+    /// Returns [`JournalError::SerializationError`] if the command or the
+    /// result cannot be serialized.
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>, JournalError> {
+        let command = canonical_json(&self.command)?;
+        let result = canonical_json(&self.result)?;
+
+        let mut bytes = Vec::with_capacity(1 + 8 * 4 + command.len() + result.len());
+        bytes.push(CANONICAL_EVENT_VERSION);
+        bytes.extend_from_slice(&self.sequence_num.to_be_bytes());
+        bytes.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+        for part in [command, result] {
+            bytes.extend_from_slice(&(part.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(&part);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Compact JSON encoding of one part of a canonical event.
+fn canonical_json<V: Serialize>(value: &V) -> Result<Vec<u8>, JournalError> {
+    serde_json::to_vec(value).map_err(|e| JournalError::SerializationError {
+        message: e.to_string(),
+    })
+}
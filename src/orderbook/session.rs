@@ -0,0 +1,147 @@
+//! Cumulative traded volume and turnover for end-of-session reporting.
+//!
+//! Every fill executed by the matching engine or an auction uncross adds its
+//! quantity to [`OrderBook::session_volume`] and its notional (price ×
+//! quantity) to [`OrderBook::session_turnover`] until
+//! [`OrderBook::reset_session`] is called. Both totals saturate instead of
+//! overflowing.
+//!
+//! Snapshot packages carry the session totals, including the per-user
+//! volumes, which a full restore takes over like the fee ledger. Restoring
+//! only the orders, or from a plain snapshot, leaves them unchanged.
+
+use super::book::OrderBook;
+use pricelevel::{Hash32, MatchResult};
+use std::sync::atomic::Ordering;
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Returns the total quantity executed since the last
+    /// [`reset_session`](Self::reset_session).
+    #[must_use]
+    pub fn session_volume(&self) -> u64 {
+        self.session_volume.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total notional (price × quantity) executed since the
+    /// last [`reset_session`](Self::reset_session).
+    #[must_use]
+    pub fn session_turnover(&self) -> u128 {
+        self.session_turnover.load()
+    }
+
+    /// Reset the session volume and turnover to zero, e.g. at the open,
+    /// together with the per-user volumes that select fee tiers.
+    pub fn reset_session(&self) {
+        self.session_volume.store(0, Ordering::Relaxed);
+        self.session_turnover.store(0);
+        self.user_session_volume.clear();
+        trace!("Order book {}: Reset session totals", self.symbol);
+    }
+
+    /// Returns the per-user session volumes, for snapshots.
+    pub(super) fn raw_user_session_volume(&self) -> Vec<(Hash32, u64)> {
+        self.user_session_volume
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+
+    /// Replace the session totals with totals taken for a snapshot.
+    pub(super) fn restore_session_totals(
+        &self,
+        volume: u64,
+        turnover: u128,
+        user_volume: Vec<(Hash32, u64)>,
+    ) {
+        self.session_volume.store(volume, Ordering::Relaxed);
+        self.session_turnover.store(turnover);
+        self.user_session_volume.clear();
+        for (user_id, volume) in user_volume {
+            self.user_session_volume.insert(user_id, volume);
+        }
+    }
+
+    /// Add every fill of `match_result` to the session totals.
+    pub(super) fn record_session_trades(&self, match_result: &MatchResult) {
+        for trade in match_result.trades().as_vec() {
+            self.record_session_volume(trade.quantity().as_u64(), trade.price().as_u128());
+        }
+    }
+
+    /// Add an execution of `quantity` at `price` to the session totals.
+    pub(super) fn record_session_volume(&self, quantity: u64, price: u128) {
+        if quantity == 0 {
+            return;
+        }
+        let _ = self
+            .session_volume
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |volume| {
+                Some(volume.saturating_add(quantity))
+            });
+        let notional = price.saturating_mul(u128::from(quantity));
+        let _ = self
+            .session_turnover
+            .fetch_update(|turnover| Some(turnover.saturating_add(notional)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    #[test]
+    fn test_trades_accumulate_volume_and_turnover() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 101, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.session_volume(), 0);
+
+        // 10 @ 100 + 5 @ 101
+        book.submit_market_order(Id::new_uuid(), 15, Side::Buy)
+            .unwrap();
+        assert_eq!(book.session_volume(), 15);
+        assert_eq!(book.session_turnover(), 1_505);
+
+        // A crossing limit order: 5 @ 101
+        book.add_limit_order(Id::new_uuid(), 101, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.session_volume(), 20);
+        assert_eq!(book.session_turnover(), 2_010);
+
+        // Resting without a trade does not count
+        book.add_limit_order(Id::new_uuid(), 90, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(book.session_volume(), 20);
+
+        book.reset_session();
+        assert_eq!(book.session_volume(), 0);
+        assert_eq!(book.session_turnover(), 0);
+
+        book.submit_market_order(Id::new_uuid(), 5, Side::Sell)
+            .unwrap();
+        assert_eq!(book.session_volume(), 5);
+        assert_eq!(book.session_turnover(), 450);
+    }
+
+    #[test]
+    fn test_auction_uncross_counts_paired_volume_once() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_trading_state(crate::orderbook::TradingState::Auction);
+        book.add_limit_order(Id::new_uuid(), 102, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let result = book.run_auction_uncross().unwrap();
+        let clearing_price = result.clearing_price.unwrap();
+        assert_eq!(book.session_volume(), 10);
+        assert_eq!(book.session_turnover(), clearing_price * 10);
+    }
+}
@@ -0,0 +1,263 @@
+//! What-if execution of orders against a scratch copy of the book.
+//!
+//! [`OrderBook::simulate_market_order`] only walks the opposite side for a
+//! market order. [`OrderBook::simulate_add_order`] runs any order type
+//! (limit, iceberg, post-only, ...) through the full add path, including
+//! validation, self-trade prevention and resting of the remainder, on a
+//! scratch copy of the book, and reports the fills together with the
+//! resulting book. The live book is never touched.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::modifications::RemainderOutcome;
+use super::snapshot::OrderBookSnapshot;
+use super::trade::TradeFill;
+use pricelevel::OrderType;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+
+/// Fills an order would receive if it were added to the book.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillSimulation {
+    /// Fills against resting makers, in execution order.
+    pub fills: Vec<TradeFill>,
+    /// Total quantity that would execute.
+    pub executed_quantity: u64,
+    /// What would happen to the unfilled remainder.
+    pub remainder: RemainderOutcome,
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Simulate adding `order` and return its fills and the book snapshot
+    /// (at full depth) right after it.
+    ///
+    /// The order is applied to a scratch copy carrying this book's resting
+    /// orders, configuration and market state. Listeners, the order state
+    /// tracker and the match observer are not copied, so no side effects
+    /// are triggered. Within a price level the copy queues orders by
+    /// timestamp.
+    ///
+    /// # Errors
+    /// Returns the error the add would fail with on the live book, e.g.
+    /// [`OrderBookError::PriceCrossing`] for a crossing post-only order.
+    ///
+    /// # Performance
+    /// O(N) in the number of resting orders to build the copy, plus the
+    /// cost of the add.
+    pub fn simulate_add_order(
+        &self,
+        order: OrderType<T>,
+    ) -> Result<(FillSimulation, OrderBookSnapshot), OrderBookError> {
+        let scratch = self.scratch_copy()?;
+        let added = scratch.add_order_with_remainder(order, Default::default())?;
+
+        let simulation = FillSimulation {
+            fills: added
+                .match_result
+                .trades()
+                .as_vec()
+                .iter()
+                .map(|trade| TradeFill {
+                    maker_order_id: trade.maker_order_id(),
+                    price: trade.price().as_u128(),
+                    quantity: trade.quantity().as_u64(),
+                })
+                .collect(),
+            executed_quantity: added.match_result.executed_quantity().unwrap_or(0),
+            remainder: added.remainder,
+        };
+        Ok((simulation, scratch.create_snapshot(usize::MAX)))
+    }
+
+    /// Build an unobserved copy of this book: resting orders, validation and
+    /// matching configuration, and reference market state.
+    pub(super) fn scratch_copy(&self) -> Result<OrderBook<T>, OrderBookError> {
+        let mut scratch = OrderBook::new(&self.symbol);
+        scratch.tick_schedule = self.tick_schedule.clone();
+        scratch.lot_size = self.lot_size;
+        scratch.min_order_size = self.min_order_size;
+        scratch.max_order_size = self.max_order_size;
+        scratch.stp_mode = self.stp_mode;
+        scratch.stp_maker_scope = self.stp_maker_scope;
+        scratch.fee_schedule = self.fee_schedule;
+        scratch.reference_price_policy = self.reference_price_policy;
+        scratch.price_band_bps = self.price_band_bps;
+        scratch.external_bbo_policy = self.external_bbo_policy;
+        scratch.price_decimals = self.price_decimals;
+        scratch.tick_rounding = self.tick_rounding;
+        scratch.min_spread_ticks = self.min_spread_ticks;
+
+        scratch.restore_from_snapshot(self.create_snapshot(usize::MAX))?;
+
+        scratch.last_trade_price.store(self.last_trade_price.load());
+        scratch
+            .has_traded
+            .store(self.has_traded.load(Ordering::Relaxed), Ordering::Relaxed);
+        scratch.market_close_timestamp.store(
+            self.market_close_timestamp.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        scratch.has_market_close.store(
+            self.has_market_close.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        scratch
+            .manual_reference_price
+            .store(self.manual_reference_price.load());
+        scratch.has_manual_reference_price.store(
+            self.has_manual_reference_price.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        scratch.external_bbo.store(self.external_bbo.load());
+        scratch.trading_state.store(self.trading_state.load());
+        for limit in self.user_limits.iter() {
+            scratch.user_limits.insert(*limit.key(), *limit.value());
+        }
+        Ok(scratch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::sequencer::snapshots_match;
+    use pricelevel::{Hash32, Id, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn standard(price: u128, quantity: u64, side: Side, timestamp: u64) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    fn resting_orders() -> Vec<OrderType<()>> {
+        vec![
+            standard(101, 10, Side::Sell, 1),
+            standard(101, 5, Side::Sell, 2),
+            OrderType::IcebergOrder {
+                id: Id::new_uuid(),
+                price: Price::new(102),
+                visible_quantity: Quantity::new(5),
+                hidden_quantity: Quantity::new(15),
+                side: Side::Sell,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(3),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            },
+            standard(99, 10, Side::Buy, 4),
+            standard(98, 10, Side::Buy, 5),
+        ]
+    }
+
+    fn book(orders: &[OrderType<()>]) -> OrderBook<()> {
+        let book = OrderBook::new("TEST");
+        for order in orders {
+            book.add_order(*order).unwrap();
+        }
+        book
+    }
+
+    #[test]
+    fn test_simulated_post_state_matches_applied_order() {
+        let orders = resting_orders();
+        let live = book(&orders);
+        let before = live.create_snapshot(usize::MAX);
+
+        for aggressor in [
+            // Sweeps both ask levels, including the iceberg
+            standard(102, 30, Side::Buy, 10),
+            // Fully filled within the best bid
+            standard(99, 4, Side::Sell, 11),
+            // Rests without matching
+            OrderType::PostOnly {
+                id: Id::new_uuid(),
+                price: Price::new(100),
+                quantity: Quantity::new(7),
+                side: Side::Buy,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(12),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            },
+        ] {
+            let (simulation, simulated) = live.simulate_add_order(aggressor).unwrap();
+
+            // The live book is untouched
+            assert!(snapshots_match(&live.create_snapshot(usize::MAX), &before));
+
+            let applied = book(&orders);
+            let result = applied
+                .add_order_with_remainder(aggressor, Default::default())
+                .unwrap();
+            assert!(snapshots_match(
+                &simulated,
+                &applied.create_snapshot(usize::MAX)
+            ));
+            assert_eq!(
+                simulation.executed_quantity,
+                result.match_result.executed_quantity().unwrap_or(0)
+            );
+            assert_eq!(simulation.remainder, result.remainder);
+            let makers: Vec<Id> = result
+                .match_result
+                .trades()
+                .as_vec()
+                .iter()
+                .map(|t| t.maker_order_id())
+                .collect();
+            assert_eq!(
+                simulation
+                    .fills
+                    .iter()
+                    .map(|f| f.maker_order_id)
+                    .collect::<Vec<_>>(),
+                makers
+            );
+        }
+    }
+
+    #[test]
+    fn test_simulated_sweep_reports_fills() {
+        let orders = resting_orders();
+        let live = book(&orders);
+        let (simulation, simulated) = live
+            .simulate_add_order(standard(102, 30, Side::Buy, 10))
+            .unwrap();
+
+        assert_eq!(simulation.executed_quantity, 30);
+        assert_eq!(simulation.remainder, RemainderOutcome::Filled);
+        assert_eq!(simulation.fills[0].price, 101);
+        assert_eq!(simulation.fills.last().map(|f| f.price), Some(102));
+        assert_eq!(simulated.best_ask().map(|(price, _)| price), Some(102));
+        assert_eq!(live.best_ask(), Some(101));
+    }
+
+    #[test]
+    fn test_rejected_simulation_returns_error() {
+        let live = book(&resting_orders());
+        let crossing = OrderType::PostOnly {
+            id: Id::new_uuid(),
+            price: Price::new(101),
+            quantity: Quantity::new(1),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(20),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        };
+        assert!(matches!(
+            live.simulate_add_order(crossing),
+            Err(OrderBookError::PriceCrossing { .. })
+        ));
+    }
+}
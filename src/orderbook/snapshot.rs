@@ -1,14 +1,73 @@
 //! Order book snapshot for market data
 
 use bitflags::bitflags;
-use pricelevel::PriceLevelSnapshot;
+use pricelevel::{Hash32, Id, OrderType, PriceLevelSnapshot, Quantity, Side};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tracing::trace;
 
+use super::auction::TradingState;
 use super::error::OrderBookError;
+use super::fee_ledger::FeeRounding;
 use super::fees::FeeSchedule;
+use super::lifetime_counters::LifetimeCounters;
+use super::parked::ParkedMarketOrder;
 use super::stp::STPMode;
+use super::trailing_stop::PendingTrailingStop;
+
+/// Total (visible + hidden) quantity of a level, saturating at `u64::MAX`.
+#[inline]
+fn level_quantity(level: &PriceLevelSnapshot) -> u64 {
+    level
+        .visible_quantity()
+        .saturating_add(level.hidden_quantity())
+}
+
+/// Saturating sum of level quantities.
+fn saturating_volume<'a>(levels: impl IntoIterator<Item = &'a PriceLevelSnapshot>) -> u64 {
+    levels
+        .into_iter()
+        .fold(0u64, |acc, level| acc.saturating_add(level_quantity(level)))
+}
+
+/// Saturating sum of level notionals (price * quantity).
+fn saturating_value<'a>(levels: impl IntoIterator<Item = &'a PriceLevelSnapshot>) -> u128 {
+    levels.into_iter().fold(0u128, |acc, level| {
+        acc.saturating_add(
+            level
+                .price()
+                .saturating_mul(u128::from(level_quantity(level))),
+        )
+    })
+}
+
+/// Copy of `level` with the hidden quantity of every order set to zero.
+pub(super) fn without_hidden(level: PriceLevelSnapshot) -> PriceLevelSnapshot {
+    if level.hidden_quantity() == 0 {
+        return level;
+    }
+    let price = level.price();
+    let orders = level
+        .orders()
+        .iter()
+        .map(|order| {
+            let mut order = **order;
+            if let OrderType::IcebergOrder {
+                hidden_quantity, ..
+            }
+            | OrderType::ReserveOrder {
+                hidden_quantity, ..
+            } = &mut order
+            {
+                *hidden_quantity = Quantity::new(0);
+            }
+            Arc::new(order)
+        })
+        .collect();
+    PriceLevelSnapshot::with_orders(price, orders).unwrap_or(level)
+}
 
 /// A snapshot of the order book state at a specific point in time
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +86,23 @@ pub struct OrderBookSnapshot {
 }
 
 impl OrderBookSnapshot {
+    /// Returns the SHA-256 checksum of the symbol and price levels, leaving
+    /// out when the snapshot was taken, so two snapshots of the same book
+    /// state have the same checksum.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::SerializationError`] if the levels cannot
+    /// be serialized.
+    pub fn content_checksum(&self) -> Result<String, OrderBookError> {
+        let payload =
+            serde_json::to_vec(&(&self.symbol, &self.bids, &self.asks)).map_err(|error| {
+                OrderBookError::SerializationError {
+                    message: error.to_string(),
+                }
+            })?;
+        Ok(format!("{:x}", Sha256::digest(payload)))
+    }
+
     /// Recomputes aggregate values for all included price levels.
     pub fn refresh_aggregates(&mut self) {
         for level in &mut self.bids {
@@ -85,53 +161,58 @@ impl OrderBookSnapshot {
     }
 
     /// Calculate the total volume on the bid side
+    ///
+    /// Saturates at `u64::MAX`; use
+    /// [`checked_total_volume`](Self::checked_total_volume) when an exact
+    /// total is required.
     pub fn total_bid_volume(&self) -> u64 {
-        let volume = self
-            .bids
-            .iter()
-            .map(|level| level.total_quantity().unwrap_or(0))
-            .sum();
+        let volume = saturating_volume(&self.bids);
         trace!("total_bid_volume: {:?}", volume);
         volume
     }
 
     /// Calculate the total volume on the ask side
+    ///
+    /// Saturates at `u64::MAX`; use
+    /// [`checked_total_volume`](Self::checked_total_volume) when an exact
+    /// total is required.
     pub fn total_ask_volume(&self) -> u64 {
-        let volume = self
-            .asks
-            .iter()
-            .map(|level| level.total_quantity().unwrap_or(0))
-            .sum();
+        let volume = saturating_volume(&self.asks);
         trace!("total_ask_volume: {:?}", volume);
         volume
     }
 
-    /// Calculate the total value on the bid side (price * quantity)
-    pub fn total_bid_value(&self) -> u128 {
-        let value = self
-            .bids
+    /// Calculate the exact total volume on one side.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::QuantityOverflow`] if the total of any level,
+    /// or of the whole side, does not fit in a `u64`.
+    pub fn checked_total_volume(&self, side: Side) -> Result<u64, OrderBookError> {
+        let levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        levels
             .iter()
-            .map(|level| {
+            .try_fold(0u64, |total, level| {
                 level
-                    .price()
-                    .saturating_mul(level.total_quantity().unwrap_or(0) as u128)
+                    .total_quantity()
+                    .ok()
+                    .and_then(|quantity| total.checked_add(quantity))
             })
-            .sum();
+            .ok_or(OrderBookError::QuantityOverflow { side })
+    }
+
+    /// Calculate the total value on the bid side (price * quantity)
+    pub fn total_bid_value(&self) -> u128 {
+        let value = saturating_value(&self.bids);
         trace!("total_bid_value: {:?}", value);
         value
     }
 
     /// Calculate the total value on the ask side (price * quantity)
     pub fn total_ask_value(&self) -> u128 {
-        let value = self
-            .asks
-            .iter()
-            .map(|level| {
-                level
-                    .price()
-                    .saturating_mul(level.total_quantity().unwrap_or(0) as u128)
-            })
-            .sum();
+        let value = saturating_value(&self.asks);
         trace!("total_ask_value: {:?}", value);
         value
     }
@@ -147,7 +228,10 @@ pub const ORDERBOOK_SNAPSHOT_FORMAT_VERSION: u32 = 1;
 /// `tick_size`, `lot_size`, `min_order_size`, `max_order_size`) so that
 /// [`OrderBook::restore_from_snapshot_package`](super::book::OrderBook::restore_from_snapshot_package)
 /// can fully reconstruct the book's state, including validation rules and
-/// fee settings.
+/// fee settings. It also carries the state that goes with the resting
+/// orders, the last trade price and the orders held off the book (trailing
+/// stops, contingent and parked market orders), and the session totals.
+/// The trade tape behind fill probability estimates is not carried.
 ///
 /// All configuration fields use `#[serde(default)]` for backward
 /// compatibility — snapshots created before this version will deserialize
@@ -169,10 +253,17 @@ pub struct OrderBookSnapshotPackage {
     #[serde(default)]
     pub stp_mode: STPMode,
 
-    /// Tick size (minimum price increment) active at the time of the snapshot.
+    /// Tick size (minimum price increment) active at the time of the
+    /// snapshot; the tick of the lowest band of a tiered schedule.
     #[serde(default)]
     pub tick_size: Option<u128>,
 
+    /// Tick schedule as `(price_threshold, tick_size)` bands active at the
+    /// time of the snapshot. Empty in packages created before tick
+    /// schedules, which restore `tick_size` as a single band.
+    #[serde(default)]
+    pub tick_schedule: Vec<(u128, u128)>,
+
     /// Lot size (minimum quantity increment) active at the time of the snapshot.
     #[serde(default)]
     pub lot_size: Option<u64>,
@@ -184,6 +275,69 @@ pub struct OrderBookSnapshotPackage {
     /// Maximum order size active at the time of the snapshot.
     #[serde(default)]
     pub max_order_size: Option<u64>,
+
+    /// Fee rounding mode active at the time of the snapshot.
+    #[serde(default)]
+    pub fee_rounding: FeeRounding,
+
+    /// Fees accrued per user at the time of the snapshot, in 1/10,000 of a
+    /// price unit.
+    #[serde(default)]
+    pub fee_ledger: Vec<(Hash32, i128)>,
+
+    /// Lifetime order counters at the time of the snapshot.
+    #[serde(default)]
+    pub lifetime_counters: LifetimeCounters,
+
+    /// Trading state at the time of the snapshot. A book in an auction may
+    /// be crossed.
+    #[serde(default)]
+    pub trading_state: TradingState,
+
+    /// Price of the last trade at the time of the snapshot, `None` if the
+    /// book had not traded.
+    #[serde(default)]
+    pub last_trade_price: Option<u128>,
+
+    /// Trailing stops waiting to fire at the time of the snapshot, in the
+    /// order they were added.
+    #[serde(default)]
+    pub trailing_stops: Vec<PendingTrailingStop>,
+
+    /// Contingent orders waiting on each trigger order at the time of the
+    /// snapshot, in registration order.
+    #[serde(default)]
+    pub contingent_orders: Vec<(Id, Vec<OrderType<()>>)>,
+
+    /// Market orders parked for liquidity at the time of the snapshot, in
+    /// execution order.
+    #[serde(default)]
+    pub parked_market_orders: Vec<ParkedMarketOrder>,
+
+    /// Quantity executed in the session at the time of the snapshot.
+    #[serde(default)]
+    pub session_volume: u64,
+
+    /// Notional executed in the session at the time of the snapshot.
+    #[serde(default)]
+    pub session_turnover: u128,
+
+    /// Quantity each user traded in the session at the time of the
+    /// snapshot, which selects their fee tier.
+    #[serde(default)]
+    pub user_session_volume: Vec<(Hash32, u64)>,
+
+    /// Chain hash of the previous checkpoint, empty for the first
+    /// checkpoint of a chain.
+    #[serde(default)]
+    pub prev_hash: String,
+
+    /// Hex-encoded `SHA-256(prev_hash || package)`, where `package` is the
+    /// serialized package without its two hash fields, linking this
+    /// checkpoint to the previous one. Empty in packages created before
+    /// hash chaining.
+    #[serde(default)]
+    pub chain_hash: String,
 }
 
 impl OrderBookSnapshotPackage {
@@ -193,17 +347,33 @@ impl OrderBookSnapshotPackage {
 
         let checksum = Self::compute_checksum(&snapshot)?;
 
-        Ok(Self {
+        let mut package = Self {
             version: ORDERBOOK_SNAPSHOT_FORMAT_VERSION,
             snapshot,
             checksum,
             fee_schedule: None,
             stp_mode: STPMode::None,
             tick_size: None,
+            tick_schedule: Vec::new(),
             lot_size: None,
             min_order_size: None,
             max_order_size: None,
-        })
+            fee_rounding: FeeRounding::default(),
+            fee_ledger: Vec::new(),
+            lifetime_counters: LifetimeCounters::default(),
+            trading_state: TradingState::default(),
+            last_trade_price: None,
+            trailing_stops: Vec::new(),
+            contingent_orders: Vec::new(),
+            parked_market_orders: Vec::new(),
+            session_volume: 0,
+            session_turnover: 0,
+            user_session_volume: Vec::new(),
+            prev_hash: String::new(),
+            chain_hash: String::new(),
+        };
+        package.update_chain_hash()?;
+        Ok(package)
     }
 
     /// Serializes the package to JSON.
@@ -220,7 +390,8 @@ impl OrderBookSnapshotPackage {
         })
     }
 
-    /// Validates the checksum and version.
+    /// Validates the checksum and version, and the chain hash if present.
+    /// A package that names a previous checkpoint must carry a chain hash.
     pub fn validate(&self) -> Result<(), OrderBookError> {
         if self.version != ORDERBOOK_SNAPSHOT_FORMAT_VERSION {
             return Err(OrderBookError::InvalidOperation {
@@ -239,6 +410,67 @@ impl OrderBookSnapshotPackage {
             });
         }
 
+        if !self.chain_hash.is_empty() || !self.prev_hash.is_empty() {
+            self.validate_chain_hash()?;
+        }
+
+        Ok(())
+    }
+
+    /// Links this package to `previous` as the next checkpoint of its
+    /// chain, recomputing [`chain_hash`](Self::chain_hash).
+    pub fn chain_after(
+        &mut self,
+        previous: &OrderBookSnapshotPackage,
+    ) -> Result<(), OrderBookError> {
+        self.prev_hash = previous.chain_hash.clone();
+        self.update_chain_hash()
+    }
+
+    /// Recomputes [`chain_hash`](Self::chain_hash) over the package as it
+    /// stands, after any of its fields were set.
+    pub fn update_chain_hash(&mut self) -> Result<(), OrderBookError> {
+        self.chain_hash = self.compute_chain_hash()?;
+        Ok(())
+    }
+
+    /// Verifies that `packages` form one continuous chain of checkpoints.
+    ///
+    /// Every package must pass [`validate`](Self::validate) and carry a
+    /// correct chain hash, and every package after the first must name the
+    /// chain hash of its predecessor as its `prev_hash`. This detects
+    /// checkpoints that were altered, inserted, removed or reordered. The
+    /// first package may continue an earlier chain.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::ChecksumMismatch`] at the first package
+    /// that fails a check, or any error from [`validate`](Self::validate).
+    pub fn verify_chain(packages: &[OrderBookSnapshotPackage]) -> Result<(), OrderBookError> {
+        let mut previous: Option<&OrderBookSnapshotPackage> = None;
+        for package in packages {
+            package.validate()?;
+            package.validate_chain_hash()?;
+            if let Some(previous) = previous
+                && package.prev_hash != previous.chain_hash
+            {
+                return Err(OrderBookError::ChecksumMismatch {
+                    expected: previous.chain_hash.clone(),
+                    actual: package.prev_hash.clone(),
+                });
+            }
+            previous = Some(package);
+        }
+        Ok(())
+    }
+
+    fn validate_chain_hash(&self) -> Result<(), OrderBookError> {
+        let computed = self.compute_chain_hash()?;
+        if computed != self.chain_hash {
+            return Err(OrderBookError::ChecksumMismatch {
+                expected: self.chain_hash.clone(),
+                actual: computed,
+            });
+        }
         Ok(())
     }
 
@@ -248,18 +480,342 @@ impl OrderBookSnapshotPackage {
         Ok(self.snapshot)
     }
 
-    fn compute_checksum(snapshot: &OrderBookSnapshot) -> Result<String, OrderBookError> {
-        let payload =
-            serde_json::to_vec(snapshot).map_err(|error| OrderBookError::SerializationError {
-                message: error.to_string(),
-            })?;
+    /// Computes the changes needed to turn `previous` into `self`.
+    ///
+    /// The returned [`PackageDelta`] records the price levels that were added
+    /// or changed, the prices of levels that disappeared, and the
+    /// configuration, fee ledger, lifetime counters and off-book state of
+    /// `self`. It is chained to `previous` by checksum so
+    /// [`apply_delta`](Self::apply_delta) can reject out-of-order deltas.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`] if the two packages belong
+    /// to different symbols, or a validation error if either package fails
+    /// its checksum check.
+    pub fn delta_since(
+        &self,
+        previous: &OrderBookSnapshotPackage,
+    ) -> Result<PackageDelta, OrderBookError> {
+        previous.validate()?;
+        self.validate()?;
+        if self.snapshot.symbol != previous.snapshot.symbol {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!(
+                    "Cannot compute delta between symbols {} and {}",
+                    previous.snapshot.symbol, self.snapshot.symbol
+                ),
+            });
+        }
 
+        let (bids_upserted, bids_removed) =
+            Self::diff_levels(&previous.snapshot.bids, &self.snapshot.bids);
+        let (asks_upserted, asks_removed) =
+            Self::diff_levels(&previous.snapshot.asks, &self.snapshot.asks);
+
+        Ok(PackageDelta {
+            symbol: self.snapshot.symbol.clone(),
+            base_checksum: previous.checksum.clone(),
+            target_checksum: self.checksum.clone(),
+            timestamp: self.snapshot.timestamp,
+            bids_upserted,
+            bids_removed,
+            asks_upserted,
+            asks_removed,
+            fee_schedule: self.fee_schedule,
+            stp_mode: self.stp_mode,
+            tick_size: self.tick_size,
+            tick_schedule: self.tick_schedule.clone(),
+            lot_size: self.lot_size,
+            min_order_size: self.min_order_size,
+            max_order_size: self.max_order_size,
+            fee_rounding: self.fee_rounding,
+            fee_ledger: self.fee_ledger.clone(),
+            lifetime_counters: self.lifetime_counters,
+            trading_state: self.trading_state,
+            last_trade_price: self.last_trade_price,
+            trailing_stops: self.trailing_stops.clone(),
+            contingent_orders: self.contingent_orders.clone(),
+            parked_market_orders: self.parked_market_orders.clone(),
+            session_volume: self.session_volume,
+            session_turnover: self.session_turnover,
+            user_session_volume: self.user_session_volume.clone(),
+            prev_hash: self.prev_hash.clone(),
+            chain_hash: self.chain_hash.clone(),
+        })
+    }
+
+    /// Applies a delta produced by [`delta_since`](Self::delta_since) and
+    /// returns the resulting package.
+    ///
+    /// To restore from a base package plus a chain of deltas, apply each
+    /// delta in order to the result of the previous step. The result takes
+    /// the target's place in its checkpoint chain, so a chain rebuilt from
+    /// deltas still passes [`verify_chain`](Self::verify_chain).
+    ///
+    /// # Errors
+    /// - [`OrderBookError::InvalidOperation`] if the delta is for another symbol
+    /// - [`OrderBookError::ChecksumMismatch`] if the delta was not computed
+    ///   against this package, or if the reconstructed package does not
+    ///   match the delta's target checksum or, as a whole, its chain hash
+    pub fn apply_delta(
+        &self,
+        delta: &PackageDelta,
+    ) -> Result<OrderBookSnapshotPackage, OrderBookError> {
+        self.validate()?;
+        if delta.symbol != self.snapshot.symbol {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!(
+                    "Delta for symbol {} cannot be applied to package for {}",
+                    delta.symbol, self.snapshot.symbol
+                ),
+            });
+        }
+        if delta.base_checksum != self.checksum {
+            return Err(OrderBookError::ChecksumMismatch {
+                expected: delta.base_checksum.clone(),
+                actual: self.checksum.clone(),
+            });
+        }
+
+        let mut bids = Self::merge_levels(
+            &self.snapshot.bids,
+            &delta.bids_upserted,
+            &delta.bids_removed,
+        );
+        bids.sort_by_key(|level| std::cmp::Reverse(level.price()));
+        let mut asks = Self::merge_levels(
+            &self.snapshot.asks,
+            &delta.asks_upserted,
+            &delta.asks_removed,
+        );
+        asks.sort_by_key(|level| level.price());
+
+        let snapshot = OrderBookSnapshot {
+            symbol: self.snapshot.symbol.clone(),
+            timestamp: delta.timestamp,
+            bids,
+            asks,
+        };
+
+        let mut package = OrderBookSnapshotPackage::new(snapshot)?;
+        package.version = self.version;
+        package.fee_schedule = delta.fee_schedule;
+        package.stp_mode = delta.stp_mode;
+        package.tick_size = delta.tick_size;
+        package.tick_schedule = delta.tick_schedule.clone();
+        package.lot_size = delta.lot_size;
+        package.min_order_size = delta.min_order_size;
+        package.max_order_size = delta.max_order_size;
+        package.fee_rounding = delta.fee_rounding;
+        package.fee_ledger = delta.fee_ledger.clone();
+        package.lifetime_counters = delta.lifetime_counters;
+        package.trading_state = delta.trading_state;
+        package.last_trade_price = delta.last_trade_price;
+        package.trailing_stops = delta.trailing_stops.clone();
+        package.contingent_orders = delta.contingent_orders.clone();
+        package.parked_market_orders = delta.parked_market_orders.clone();
+        package.session_volume = delta.session_volume;
+        package.session_turnover = delta.session_turnover;
+        package.user_session_volume = delta.user_session_volume.clone();
+        package.prev_hash = delta.prev_hash.clone();
+        package.update_chain_hash()?;
+
+        if package.checksum != delta.target_checksum {
+            return Err(OrderBookError::ChecksumMismatch {
+                expected: delta.target_checksum.clone(),
+                actual: package.checksum,
+            });
+        }
+        if !delta.chain_hash.is_empty() && package.chain_hash != delta.chain_hash {
+            return Err(OrderBookError::ChecksumMismatch {
+                expected: delta.chain_hash.clone(),
+                actual: package.chain_hash,
+            });
+        }
+
+        Ok(package)
+    }
+
+    /// Returns the levels of `current` that are new or differ from
+    /// `previous`, and the prices present in `previous` but not in `current`.
+    fn diff_levels(
+        previous: &[PriceLevelSnapshot],
+        current: &[PriceLevelSnapshot],
+    ) -> (Vec<PriceLevelSnapshot>, Vec<u128>) {
+        let previous_by_price: HashMap<u128, &PriceLevelSnapshot> = previous
+            .iter()
+            .map(|level| (level.price(), level))
+            .collect();
+        let current_prices: HashSet<u128> = current.iter().map(|level| level.price()).collect();
+
+        let upserted = current
+            .iter()
+            .filter(|level| {
+                previous_by_price
+                    .get(&level.price())
+                    .is_none_or(|old| !Self::levels_equal(old, level))
+            })
+            .cloned()
+            .collect();
+        let removed = previous
+            .iter()
+            .map(|level| level.price())
+            .filter(|price| !current_prices.contains(price))
+            .collect();
+
+        (upserted, removed)
+    }
+
+    fn merge_levels(
+        base: &[PriceLevelSnapshot],
+        upserted: &[PriceLevelSnapshot],
+        removed: &[u128],
+    ) -> Vec<PriceLevelSnapshot> {
+        let mut by_price: HashMap<u128, PriceLevelSnapshot> = base
+            .iter()
+            .map(|level| (level.price(), level.clone()))
+            .collect();
+        for price in removed {
+            by_price.remove(price);
+        }
+        for level in upserted {
+            by_price.insert(level.price(), level.clone());
+        }
+        by_price.into_values().collect()
+    }
+
+    fn levels_equal(a: &PriceLevelSnapshot, b: &PriceLevelSnapshot) -> bool {
+        a.price() == b.price()
+            && a.visible_quantity() == b.visible_quantity()
+            && a.hidden_quantity() == b.hidden_quantity()
+            && a.orders() == b.orders()
+    }
+
+    fn compute_checksum(snapshot: &OrderBookSnapshot) -> Result<String, OrderBookError> {
         let mut hasher = Sha256::new();
-        hasher.update(payload);
+        hasher.update(Self::payload(snapshot)?);
 
         let checksum_bytes = hasher.finalize();
         Ok(format!("{:x}", checksum_bytes))
     }
+
+    fn compute_chain_hash(&self) -> Result<String, OrderBookError> {
+        let unhashed = Self {
+            prev_hash: String::new(),
+            chain_hash: String::new(),
+            ..self.clone()
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(self.prev_hash.as_bytes());
+        hasher.update(Self::payload(&unhashed)?);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    fn payload(value: &impl Serialize) -> Result<Vec<u8>, OrderBookError> {
+        serde_json::to_vec(value).map_err(|error| OrderBookError::SerializationError {
+            message: error.to_string(),
+        })
+    }
+}
+
+/// Incremental changes between two consecutive [`OrderBookSnapshotPackage`]s
+/// of the same book.
+///
+/// Produced by [`OrderBookSnapshotPackage::delta_since`] and consumed by
+/// [`OrderBookSnapshotPackage::apply_delta`]. A checkpoint pipeline can
+/// persist one full base package followed by a chain of deltas; each delta
+/// references the checksum of the package it was computed against, so a
+/// missing or reordered delta is detected on restore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageDelta {
+    /// Symbol of the book both packages belong to.
+    pub symbol: String,
+    /// Checksum of the package this delta was computed against.
+    pub base_checksum: String,
+    /// Checksum of the package this delta reconstructs.
+    pub target_checksum: String,
+    /// Timestamp of the target snapshot (milliseconds since epoch).
+    pub timestamp: u64,
+    /// Bid levels that were added or changed.
+    pub bids_upserted: Vec<PriceLevelSnapshot>,
+    /// Prices of bid levels that were removed.
+    pub bids_removed: Vec<u128>,
+    /// Ask levels that were added or changed.
+    pub asks_upserted: Vec<PriceLevelSnapshot>,
+    /// Prices of ask levels that were removed.
+    pub asks_removed: Vec<u128>,
+    /// Fee schedule of the target package.
+    #[serde(default)]
+    pub fee_schedule: Option<FeeSchedule>,
+    /// Self-trade prevention mode of the target package.
+    #[serde(default)]
+    pub stp_mode: STPMode,
+    /// Tick size of the target package.
+    #[serde(default)]
+    pub tick_size: Option<u128>,
+    /// Tick schedule of the target package.
+    #[serde(default)]
+    pub tick_schedule: Vec<(u128, u128)>,
+    /// Lot size of the target package.
+    #[serde(default)]
+    pub lot_size: Option<u64>,
+    /// Minimum order size of the target package.
+    #[serde(default)]
+    pub min_order_size: Option<u64>,
+    /// Maximum order size of the target package.
+    #[serde(default)]
+    pub max_order_size: Option<u64>,
+    /// Fee rounding mode of the target package.
+    #[serde(default)]
+    pub fee_rounding: FeeRounding,
+    /// Per-user fee ledger of the target package.
+    #[serde(default)]
+    pub fee_ledger: Vec<(Hash32, i128)>,
+    /// Lifetime order counters of the target package.
+    #[serde(default)]
+    pub lifetime_counters: LifetimeCounters,
+    /// Trading state of the target package.
+    #[serde(default)]
+    pub trading_state: TradingState,
+    /// Last trade price of the target package.
+    #[serde(default)]
+    pub last_trade_price: Option<u128>,
+    /// Pending trailing stops of the target package.
+    #[serde(default)]
+    pub trailing_stops: Vec<PendingTrailingStop>,
+    /// Contingent orders of the target package.
+    #[serde(default)]
+    pub contingent_orders: Vec<(Id, Vec<OrderType<()>>)>,
+    /// Parked market orders of the target package.
+    #[serde(default)]
+    pub parked_market_orders: Vec<ParkedMarketOrder>,
+    /// Session volume of the target package.
+    #[serde(default)]
+    pub session_volume: u64,
+    /// Session turnover of the target package.
+    #[serde(default)]
+    pub session_turnover: u128,
+    /// Per-user session volumes of the target package.
+    #[serde(default)]
+    pub user_session_volume: Vec<(Hash32, u64)>,
+    /// Chain hash of the checkpoint before the target package.
+    #[serde(default)]
+    pub prev_hash: String,
+    /// Chain hash of the target package, empty in deltas created before
+    /// hash chaining.
+    #[serde(default)]
+    pub chain_hash: String,
+}
+
+impl PackageDelta {
+    /// Returns `true` if the delta carries no level changes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bids_upserted.is_empty()
+            && self.bids_removed.is_empty()
+            && self.asks_upserted.is_empty()
+            && self.asks_removed.is_empty()
+    }
 }
 
 bitflags! {
@@ -496,7 +1052,7 @@ impl EnrichedSnapshot {
     }
 
     fn calculate_total_depth(levels: &[PriceLevelSnapshot]) -> u64 {
-        levels.iter().map(|l| l.total_quantity().unwrap_or(0)).sum()
+        saturating_volume(levels)
     }
 
     fn calculate_vwap(levels: &[PriceLevelSnapshot], max_levels: usize) -> Option<f64> {
@@ -506,7 +1062,7 @@ impl EnrichedSnapshot {
         let mut total_quantity = 0u64;
 
         for level in levels_to_use {
-            let quantity = level.total_quantity().unwrap_or(0);
+            let quantity = level_quantity(level);
             if quantity > 0 {
                 total_value =
                     total_value.saturating_add(level.price().saturating_mul(quantity as u128));
@@ -526,19 +1082,10 @@ impl EnrichedSnapshot {
         asks: &[PriceLevelSnapshot],
         max_levels: usize,
     ) -> f64 {
-        let bid_volume: u64 = bids
-            .iter()
-            .take(max_levels)
-            .map(|l| l.total_quantity().unwrap_or(0))
-            .sum();
-
-        let ask_volume: u64 = asks
-            .iter()
-            .take(max_levels)
-            .map(|l| l.total_quantity().unwrap_or(0))
-            .sum();
+        let bid_volume = saturating_volume(bids.iter().take(max_levels));
+        let ask_volume = saturating_volume(asks.iter().take(max_levels));
 
-        let total = bid_volume + ask_volume;
+        let total = bid_volume.saturating_add(ask_volume);
 
         if total == 0 {
             0.0
@@ -547,3 +1094,166 @@ impl EnrichedSnapshot {
         }
     }
 }
+
+/// A struct-of-arrays snapshot of the top of the book
+///
+/// Each side is represented by two parallel, contiguous vectors: index `i` of
+/// `bid_prices` pairs with index `i` of `bid_qtys` (and likewise for asks).
+/// Levels are ordered best-first, matching [`OrderBookSnapshot`]. Quantities
+/// are visible quantities. This layout avoids per-level structs and can be
+/// copied directly into wire buffers by market-data publishers.
+///
+/// # Examples
+/// ```
+/// use orderbook_rs::OrderBook;
+/// use pricelevel::{Id, Side, TimeInForce};
+///
+/// let book = OrderBook::<()>::new("BTC/USD");
+/// let _ = book.add_limit_order(Id::new(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+/// let _ = book.add_limit_order(Id::new(), 101, 5, Side::Sell, TimeInForce::Gtc, None);
+///
+/// let columnar = book.snapshot_columnar(10);
+/// assert_eq!(columnar.bid_prices, vec![100]);
+/// assert_eq!(columnar.ask_qtys, vec![5]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnarSnapshot {
+    /// The symbol or identifier for this order book
+    pub symbol: String,
+
+    /// Timestamp when the snapshot was created (milliseconds since epoch)
+    pub timestamp: u64,
+
+    /// Bid prices, best (highest) first
+    pub bid_prices: Vec<u128>,
+
+    /// Visible bid quantities, aligned with `bid_prices`
+    pub bid_qtys: Vec<u64>,
+
+    /// Ask prices, best (lowest) first
+    pub ask_prices: Vec<u128>,
+
+    /// Visible ask quantities, aligned with `ask_prices`
+    pub ask_qtys: Vec<u64>,
+}
+
+impl ColumnarSnapshot {
+    /// Number of bid levels in the snapshot
+    #[must_use]
+    #[inline]
+    pub fn bid_len(&self) -> usize {
+        self.bid_prices.len()
+    }
+
+    /// Number of ask levels in the snapshot
+    #[must_use]
+    #[inline]
+    pub fn ask_len(&self) -> usize {
+        self.ask_prices.len()
+    }
+}
+
+/// Top-of-book quote: the best `depth` levels per side as price/size pairs.
+///
+/// Lighter than [`OrderBookSnapshot`]: only the aggregated visible quantity
+/// of each level is included, with no per-order data and no timestamp.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Bid `(price, visible quantity)` pairs, best (highest) first
+    pub bids: Vec<(u128, u64)>,
+
+    /// Ask `(price, visible quantity)` pairs, best (lowest) first
+    pub asks: Vec<(u128, u64)>,
+}
+
+/// One price level of an [`AggregatedSnapshot`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregatedLevel {
+    /// Price of the level
+    pub price: u128,
+
+    /// Total visible quantity of the orders at this price
+    pub quantity: u64,
+
+    /// Number of orders resting at this price
+    pub order_count: usize,
+}
+
+/// Per-level view of the book for market-data distribution.
+///
+/// Each level carries its price, visible quantity and order count but no
+/// per-order data, so it is much smaller than an [`OrderBookSnapshot`] of a
+/// wide book. It cannot be used to restore a book.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregatedSnapshot {
+    /// The symbol or identifier for this order book
+    pub symbol: String,
+
+    /// Timestamp when the snapshot was created (milliseconds since epoch)
+    pub timestamp: u64,
+
+    /// Bid levels, best (highest) first
+    pub bids: Vec<AggregatedLevel>,
+
+    /// Ask levels, best (lowest) first
+    pub asks: Vec<AggregatedLevel>,
+}
+
+/// Options for [`OrderBook::create_snapshot_with`](crate::OrderBook::create_snapshot_with).
+///
+/// The default includes every level on both sides with hidden quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SnapshotOptions {
+    /// Maximum number of bid levels, best (highest) first
+    pub max_bid_levels: usize,
+
+    /// Maximum number of ask levels, best (lowest) first
+    pub max_ask_levels: usize,
+
+    /// Whether hidden iceberg and reserve quantity is included. When
+    /// `false`, hidden quantity is reported as zero on each level and order,
+    /// so the snapshot shows only public depth and no longer restores the
+    /// hidden part of those orders.
+    pub include_hidden: bool,
+}
+
+impl SnapshotOptions {
+    /// Options for the top `depth` levels on each side, including hidden
+    /// quantity, as used by `create_snapshot(depth)`.
+    #[must_use]
+    pub fn depth(depth: usize) -> Self {
+        Self {
+            max_bid_levels: depth,
+            max_ask_levels: depth,
+            include_hidden: true,
+        }
+    }
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self::depth(usize::MAX)
+    }
+}
+
+/// What [`OrderBook::restore_from_snapshot_package_with`](crate::OrderBook::restore_from_snapshot_package_with)
+/// takes from a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RestoreScope {
+    /// Restore the resting orders and keep the book's current
+    /// configuration and fee ledger.
+    OrdersOnly,
+    /// Restore the resting orders, the configuration and the fee ledger
+    /// (default).
+    #[default]
+    Full,
+}
+
+impl std::fmt::Display for RestoreScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestoreScope::OrdersOnly => write!(f, "OrdersOnly"),
+            RestoreScope::Full => write!(f, "Full"),
+        }
+    }
+}
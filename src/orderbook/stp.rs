@@ -11,6 +11,12 @@
 //! - `STPMode::CancelMaker` — Cancel the resting (maker) order and continue matching.
 //! - `STPMode::CancelBoth` — Cancel both taker and maker orders.
 //!
+//! Under `STPMode::CancelMaker`,
+//! [`STPMakerScope`](crate::orderbook::stp::STPMakerScope) decides which of
+//! the taker's own makers at a matched price are cancelled: all of them as
+//! soon as the level is reached, or only those the taker actually reaches in
+//! time priority.
+//!
 //! # Bypass
 //!
 //! Orders with `user_id == Hash32::zero()` (anonymous) always bypass STP checks,
@@ -40,8 +46,9 @@ pub enum STPMode {
     CancelTaker = 1,
 
     /// Cancel the resting (maker) order(s) from the same user and continue
-    /// matching the taker against remaining orders. All same-user resting
-    /// orders at each price level are removed before matching proceeds.
+    /// matching the taker against remaining orders. By default all
+    /// same-user resting orders at each price level are removed before
+    /// matching proceeds; see [`STPMakerScope`].
     CancelMaker = 2,
 
     /// Cancel both the incoming (taker) and the resting (maker) order.
@@ -70,6 +77,57 @@ impl STPMode {
     }
 }
 
+/// Which same-user makers [`STPMode::CancelMaker`] cancels at a matched
+/// price level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum STPMakerScope {
+    /// Cancel every resting order of the taker's user at the level before
+    /// matching there (default).
+    #[default]
+    AllAtLevel,
+    /// Walk the level in time priority and cancel only the same-user makers
+    /// the taker reaches. Makers queued behind the point where the taker is
+    /// filled keep resting.
+    HitOnly,
+}
+
+impl std::fmt::Display for STPMakerScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            STPMakerScope::AllAtLevel => write!(f, "AllAtLevel"),
+            STPMakerScope::HitOnly => write!(f, "HitOnly"),
+        }
+    }
+}
+
+/// What self-trade prevention did while matching an incoming order.
+///
+/// Reported on [`AddOrderResult`](crate::orderbook::AddOrderResult) and
+/// [`TradeResult`](crate::orderbook::trade::TradeResult) so callers can
+/// tell an STP cancellation apart from other outcomes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct STPTriggered {
+    /// The mode that was applied.
+    pub mode: STPMode,
+    /// Whether the incoming (taker) order was cancelled.
+    pub cancelled_taker: bool,
+    /// Whether one or more resting (maker) orders were cancelled.
+    pub cancelled_maker: bool,
+}
+
+impl STPTriggered {
+    /// The outcome of an STP rejection before any fill, as reported by
+    /// [`OrderBookError::SelfTradePrevented`](crate::orderbook::OrderBookError::SelfTradePrevented).
+    #[must_use]
+    pub fn rejected(mode: STPMode) -> Self {
+        Self {
+            mode,
+            cancelled_taker: true,
+            cancelled_maker: mode == STPMode::CancelBoth,
+        }
+    }
+}
+
 /// Result of an STP check against a single price level.
 ///
 /// Used internally by the matching engine to decide how to proceed
@@ -128,18 +186,10 @@ pub(crate) fn check_stp_at_level(
     match mode {
         STPMode::None => STPAction::NoConflict,
 
-        STPMode::CancelTaker => {
-            // Find the first same-user order and sum quantity before it
-            let mut safe_quantity: u64 = 0;
-            for order in orders {
-                if order.user_id() == taker_user_id {
-                    return STPAction::CancelTaker { safe_quantity };
-                }
-                // Sum visible quantity of non-same-user orders
-                safe_quantity = safe_quantity.saturating_add(order.visible_quantity());
-            }
-            STPAction::NoConflict
-        }
+        STPMode::CancelTaker => match first_same_user_maker(orders, taker_user_id) {
+            Some((safe_quantity, _)) => STPAction::CancelTaker { safe_quantity },
+            None => STPAction::NoConflict,
+        },
 
         STPMode::CancelMaker => {
             // Collect all same-user order IDs for cancellation
@@ -156,21 +206,35 @@ pub(crate) fn check_stp_at_level(
             }
         }
 
-        STPMode::CancelBoth => {
-            // Find the first same-user order and sum quantity before it
-            let mut safe_quantity: u64 = 0;
-            for order in orders {
-                if order.user_id() == taker_user_id {
-                    return STPAction::CancelBoth {
-                        safe_quantity,
-                        maker_order_id: order.id(),
-                    };
-                }
-                safe_quantity = safe_quantity.saturating_add(order.visible_quantity());
-            }
-            STPAction::NoConflict
+        STPMode::CancelBoth => match first_same_user_maker(orders, taker_user_id) {
+            Some((safe_quantity, maker_order_id)) => STPAction::CancelBoth {
+                safe_quantity,
+                maker_order_id,
+            },
+            None => STPAction::NoConflict,
+        },
+    }
+}
+
+/// Finds the first resting order of `taker_user_id` at a price level.
+///
+/// Returns the visible quantity of the orders ahead of it, which the taker
+/// can safely match, and its order ID; `None` if the user has no order at
+/// the level.
+#[inline]
+pub(crate) fn first_same_user_maker(
+    orders: &[std::sync::Arc<pricelevel::OrderType<()>>],
+    taker_user_id: Hash32,
+) -> Option<(u64, Id)> {
+    let mut safe_quantity: u64 = 0;
+    for order in orders {
+        if order.user_id() == taker_user_id {
+            return Some((safe_quantity, order.id()));
         }
+        // Sum visible quantity of non-same-user orders
+        safe_quantity = safe_quantity.saturating_add(order.visible_quantity());
     }
+    None
 }
 
 #[cfg(test)]
@@ -264,6 +264,27 @@ mod tests {
         assert_eq!(book.spread(), Some(100));
     }
 
+    #[test]
+    fn test_best_bid_ask_size() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+
+        // Empty sides have no touch
+        assert_eq!(book.best_bid_size(), None);
+        assert_eq!(book.best_ask_size(), None);
+
+        let _ = book.add_order(create_standard_order(1000, 10, Side::Buy));
+        let _ = book.add_order(create_standard_order(1000, 15, Side::Buy));
+        let _ = book.add_order(create_standard_order(990, 50, Side::Buy));
+        assert_eq!(book.best_bid_size(), Some((1000, 25)));
+        assert_eq!(book.best_ask_size(), None);
+
+        // Only the visible part of an iceberg counts
+        let _ = book.add_order(create_standard_order(1100, 5, Side::Sell));
+        let _ = book.add_order(create_iceberg_order(1100, 10, 90, Side::Sell));
+        let _ = book.add_order(create_standard_order(1200, 40, Side::Sell));
+        assert_eq!(book.best_ask_size(), Some((1100, 15)));
+    }
+
     #[test]
     fn test_market_order_match() {
         let book: OrderBook<()> = OrderBook::new("BTCUSD");
@@ -1075,4 +1096,309 @@ mod test_book_specific {
             _ => panic!("Expected InsufficientLiquidity error"),
         }
     }
+
+    #[test]
+    fn test_classify_order_crossing_buy_is_taker() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(
+            create_order_id(),
+            1000,
+            10,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            book.classify_order(Side::Buy, 1000),
+            crate::OrderRole::Taker
+        );
+        assert_eq!(
+            book.classify_order(Side::Buy, 1010),
+            crate::OrderRole::Taker
+        );
+        // Pure read: the resting ask is untouched
+        assert_eq!(book.best_ask(), Some(1000));
+    }
+
+    #[test]
+    fn test_classify_order_non_crossing_buy_is_maker() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(
+            create_order_id(),
+            1000,
+            10,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        book.add_limit_order(
+            create_order_id(),
+            990,
+            10,
+            Side::Buy,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(book.classify_order(Side::Buy, 999), crate::OrderRole::Maker);
+        assert_eq!(
+            book.classify_order(Side::Sell, 991),
+            crate::OrderRole::Maker
+        );
+        assert_eq!(
+            book.classify_order(Side::Sell, 990),
+            crate::OrderRole::Taker
+        );
+    }
+
+    #[test]
+    fn test_classify_order_empty_ask_side_is_maker() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(
+            create_order_id(),
+            990,
+            10,
+            Side::Buy,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            book.classify_order(Side::Buy, u128::MAX),
+            crate::OrderRole::Maker
+        );
+    }
+}
+
+#[cfg(test)]
+mod queue_position_tests {
+    use crate::OrderBook;
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn add_at(book: &OrderBook<()>, timestamp: u64) -> Id {
+        let id = Id::new_uuid();
+        book.add_order(OrderType::Standard {
+            id,
+            price: Price::new(1000),
+            quantity: Quantity::new(10),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_queue_position_shifts_after_cancel() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let a = add_at(&book, 1);
+        let b = add_at(&book, 2);
+        let c = add_at(&book, 3);
+
+        assert_eq!(book.queue_position(a), Some(0));
+        assert_eq!(book.queue_position(b), Some(1));
+        assert_eq!(book.queue_position(c), Some(2));
+
+        book.cancel_order(a).unwrap();
+        assert_eq!(book.queue_position(a), None);
+        assert_eq!(book.queue_position(b), Some(0));
+        assert_eq!(book.queue_position(c), Some(1));
+    }
+
+    #[test]
+    fn test_queue_position_shifts_after_fill() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let a = add_at(&book, 1);
+        let b = add_at(&book, 2);
+
+        book.submit_market_order(Id::new_uuid(), 10, Side::Sell)
+            .unwrap();
+        assert_eq!(book.queue_position(a), None);
+        assert_eq!(book.queue_position(b), Some(0));
+        assert_eq!(book.queue_position(Id::new_uuid()), None);
+    }
+
+    #[test]
+    fn test_queue_position_ranks_same_millisecond_adds() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let ids: Vec<Id> = (0..3)
+            .map(|_| {
+                let id = Id::new_uuid();
+                book.add_limit_order(id, 1000, 10, Side::Buy, TimeInForce::Gtc, None)
+                    .unwrap();
+                id
+            })
+            .collect();
+        for (position, id) in ids.iter().enumerate() {
+            assert_eq!(book.queue_position(*id), Some(position));
+        }
+
+        // A partial fill sends the front order to the back
+        book.submit_market_order(Id::new_uuid(), 4, Side::Sell)
+            .unwrap();
+        assert_eq!(book.queue_position(ids[1]), Some(0));
+        assert_eq!(book.queue_position(ids[2]), Some(1));
+        assert_eq!(book.queue_position(ids[0]), Some(2));
+    }
+}
+
+#[cfg(test)]
+mod match_order_tests {
+    use crate::OrderBook;
+    use pricelevel::{
+        Hash32, Id, OrderType, OrderUpdate, Price, Quantity, Side, TimeInForce, TimestampMs,
+    };
+
+    fn add_at(book: &OrderBook<()>, price: u128, side: Side, timestamp: u64) -> Id {
+        let id = Id::new_uuid();
+        book.add_order(OrderType::Standard {
+            id,
+            price: Price::new(price),
+            quantity: Quantity::new(10),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_orders_in_match_order_matches_sweep_makers() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        // Levels interleaved, worst price first
+        add_at(&book, 102, Side::Sell, 1);
+        add_at(&book, 100, Side::Sell, 2);
+        add_at(&book, 101, Side::Sell, 3);
+        add_at(&book, 100, Side::Sell, 4);
+        add_at(&book, 102, Side::Sell, 5);
+        add_at(&book, 100, Side::Sell, 6);
+
+        let expected: Vec<Id> = book
+            .orders_in_match_order(Side::Sell)
+            .map(|order| order.id())
+            .collect();
+        assert_eq!(expected.len(), 6);
+
+        let result = book
+            .submit_market_order(Id::new_uuid(), 60, Side::Buy)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect();
+        assert_eq!(makers, expected);
+    }
+
+    #[test]
+    fn test_orders_in_match_order_bids_best_first() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let low = add_at(&book, 99, Side::Buy, 1);
+        let late = add_at(&book, 101, Side::Buy, 3);
+        let early = add_at(&book, 101, Side::Buy, 2);
+
+        let order: Vec<Id> = book
+            .orders_in_match_order(Side::Buy)
+            .map(|order| order.id())
+            .collect();
+        // Within a level the queue runs in arrival order, not by timestamp
+        assert_eq!(order, vec![late, early, low]);
+        assert_eq!(book.orders_in_match_order(Side::Sell).count(), 0);
+
+        // A snapshot restore keeps the queue
+        let restored: OrderBook<()> = OrderBook::new("TEST");
+        restored
+            .restore_from_snapshot(book.create_snapshot(usize::MAX))
+            .unwrap();
+        let restored_order: Vec<Id> = restored
+            .orders_in_match_order(Side::Buy)
+            .map(|order| order.id())
+            .collect();
+        assert_eq!(restored_order, order);
+
+        let result = book
+            .submit_market_order(Id::new_uuid(), 30, Side::Sell)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect();
+        assert_eq!(makers, order);
+    }
+
+    #[test]
+    fn test_quantity_change_leaves_a_queue_entry() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let first = add_at(&book, 100, Side::Sell, 1);
+        let second = add_at(&book, 100, Side::Sell, 2);
+        book.update_order(OrderUpdate::UpdateQuantity {
+            order_id: first,
+            new_quantity: Quantity::new(8),
+        })
+        .unwrap();
+        let third = add_at(&book, 100, Side::Sell, 3);
+        assert_eq!(
+            book.level_order_ids(Side::Sell, 100),
+            vec![first, second, third]
+        );
+
+        // The partial fill takes the first entry, and the order falls back
+        // to the one the quantity change left ahead of the third order
+        book.submit_market_order(Id::new_uuid(), 4, Side::Buy)
+            .unwrap();
+        let order = book.level_order_ids(Side::Sell, 100);
+        assert_eq!(order, vec![second, first, third]);
+
+        let result = book
+            .submit_market_order(Id::new_uuid(), 24, Side::Buy)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect();
+        assert_eq!(makers, order);
+    }
+
+    #[test]
+    fn test_partially_filled_maker_rejoins_back_of_queue() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let first = add_at(&book, 100, Side::Sell, 1);
+        let second = add_at(&book, 100, Side::Sell, 2);
+        book.submit_market_order(Id::new_uuid(), 4, Side::Buy)
+            .unwrap();
+
+        let order: Vec<Id> = book
+            .orders_in_match_order(Side::Sell)
+            .map(|order| order.id())
+            .collect();
+        assert_eq!(order, vec![second, first]);
+        assert_eq!(book.level_order_ids(Side::Sell, 100), order);
+
+        let result = book
+            .submit_market_order(Id::new_uuid(), 12, Side::Buy)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect();
+        assert_eq!(makers, order);
+    }
 }
@@ -255,4 +255,109 @@ mod tests {
         assert_eq!(book.total_depth_at_levels(2, Side::Sell), 40); // 101, 102
         assert_eq!(book.total_depth_at_levels(3, Side::Sell), 75); // 101, 102, 103
     }
+
+    #[test]
+    fn test_resting_notional_per_side() {
+        let book = OrderBook::<()>::new("TEST");
+        book.add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 100, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 95, 20, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 110, 7, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_iceberg_order(
+            Id::new_uuid(),
+            120,
+            3,
+            9,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(book.resting_notional(Side::Buy), 100 * 15 + 95 * 20);
+        assert_eq!(book.resting_notional(Side::Sell), 110 * 7 + 120 * 3);
+        assert_eq!(
+            book.resting_notional_with_hidden(Side::Sell),
+            110 * 7 + 120 * 12
+        );
+        assert_eq!(
+            book.checked_resting_notional(Side::Buy, false),
+            Some(100 * 15 + 95 * 20)
+        );
+    }
+
+    #[test]
+    fn test_resting_notional_empty_side() {
+        let book = OrderBook::<()>::new("TEST");
+        assert_eq!(book.resting_notional(Side::Buy), 0);
+        assert_eq!(book.resting_notional(Side::Sell), 0);
+    }
+
+    #[test]
+    fn test_resting_notional_overflow_saturates() {
+        let book = OrderBook::<()>::new("TEST");
+        let huge = u128::MAX / 2;
+        book.add_limit_order(Id::new_uuid(), huge, 3, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        assert_eq!(book.checked_resting_notional(Side::Sell, false), None);
+        assert_eq!(book.resting_notional(Side::Sell), u128::MAX);
+
+        // Overflow in the running sum (not the product) is also caught
+        let book = OrderBook::<()>::new("TEST");
+        book.add_limit_order(Id::new_uuid(), huge, 1, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(
+            Id::new_uuid(),
+            huge - 1,
+            2,
+            Side::Buy,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        assert_eq!(book.checked_resting_notional(Side::Buy, false), None);
+        assert_eq!(book.resting_notional(Side::Buy), u128::MAX);
+    }
+
+    #[test]
+    fn test_depth_until_quantity_includes_straddling_level() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let _ = book.add_limit_order(Id::new(), 101, 15, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 102, 25, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 103, 35, Side::Sell, TimeInForce::Gtc, None);
+        // Hidden quantity neither counts nor shows a level
+        let _ = book.add_iceberg_order(Id::new(), 101, 0, 50, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_iceberg_order(Id::new(), 102, 5, 50, Side::Sell, TimeInForce::Gtc, None);
+
+        // 15 + 30 = 45 straddles the target of 20
+        assert_eq!(
+            book.depth_until_quantity(Side::Sell, 20),
+            vec![(101, 15), (102, 30)]
+        );
+        // An exact hit stops at that level
+        assert_eq!(
+            book.depth_until_quantity(Side::Sell, 45),
+            vec![(101, 15), (102, 30)]
+        );
+        assert_eq!(book.depth_until_quantity(Side::Sell, 0), vec![(101, 15)]);
+    }
+
+    #[test]
+    fn test_depth_until_quantity_exceeding_total_returns_all() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert!(book.depth_until_quantity(Side::Buy, 10).is_empty());
+
+        let _ = book.add_limit_order(Id::new(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 99, 20, Side::Buy, TimeInForce::Gtc, None);
+        let _ = book.add_iceberg_order(Id::new(), 98, 0, 40, Side::Buy, TimeInForce::Gtc, None);
+        assert_eq!(
+            book.depth_until_quantity(Side::Buy, 1_000),
+            vec![(100, 10), (99, 20)]
+        );
+    }
 }
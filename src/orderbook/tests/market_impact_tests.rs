@@ -265,4 +265,63 @@ mod tests {
         let liquidity = book.liquidity_in_range(101, 102, Side::Sell);
         assert_eq!(liquidity, 60); // 25 + 35
     }
+
+    #[test]
+    fn test_cost_curve_points() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let _ = book.add_limit_order(Id::new(), 100, 10, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 100, 5, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 105, 15, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 110, 20, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 95, 10, Side::Buy, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 90, 10, Side::Buy, TimeInForce::Gtc, None);
+
+        // One point per level, in price priority
+        assert_eq!(
+            book.cost_curve(Side::Buy, u64::MAX).unwrap(),
+            vec![(15, 1_500), (30, 3_075), (50, 5_275)]
+        );
+        assert_eq!(
+            book.cost_curve(Side::Sell, u64::MAX).unwrap(),
+            vec![(10, 950), (20, 1_850)]
+        );
+
+        // The last point matches simulate_market_order's cost
+        let simulation = book.simulate_market_order(30, Side::Buy);
+        let cost: u128 = simulation
+            .fills
+            .iter()
+            .map(|&(price, quantity)| price * u128::from(quantity))
+            .sum();
+        assert_eq!(
+            book.cost_curve(Side::Buy, 30).unwrap().last(),
+            Some(&(30, cost))
+        );
+    }
+
+    #[test]
+    fn test_cost_curve_stops_at_max_quantity() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let _ = book.add_limit_order(Id::new(), 100, 10, Side::Sell, TimeInForce::Gtc, None);
+        let _ = book.add_limit_order(Id::new(), 105, 15, Side::Sell, TimeInForce::Gtc, None);
+
+        assert_eq!(
+            book.cost_curve(Side::Buy, 12).unwrap(),
+            vec![(10, 1_000), (12, 1_210)]
+        );
+        assert_eq!(book.cost_curve(Side::Buy, 10).unwrap(), vec![(10, 1_000)]);
+        assert!(book.cost_curve(Side::Buy, 0).unwrap().is_empty());
+        assert!(book.cost_curve(Side::Sell, 10).unwrap().is_empty());
+
+        // Cost overflow is reported, not wrapped
+        let _ = book.add_limit_order(
+            Id::new(),
+            u128::MAX / 2,
+            10,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        );
+        assert_eq!(book.cost_curve(Side::Buy, u64::MAX), None);
+    }
 }
@@ -177,4 +177,262 @@ mod tests {
         let matched_quantity = book.peek_match(Side::Buy, 10, None);
         assert_eq!(matched_quantity, 0);
     }
+
+    /// Asks of an iceberg (5 shown, 10 hidden) and a standard 5 at 100, and
+    /// a standard 5 at 101. Returns the iceberg's id.
+    fn setup_hidden_book() -> (OrderBook<()>, Id) {
+        let book = setup_book();
+        let iceberg = Id::new();
+        book.add_iceberg_order(iceberg, 100, 5, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        add_limit_order(&book, Side::Sell, 100, 5);
+        add_limit_order(&book, Side::Sell, 101, 5);
+        (book, iceberg)
+    }
+
+    #[test]
+    fn test_market_order_consumes_hidden_liquidity() {
+        let (book, iceberg) = setup_hidden_book();
+        let result = book
+            .submit_market_order_with_hidden(Id::new(), 25, Side::Buy, Hash32::zero(), true)
+            .unwrap();
+        assert_eq!(result.remaining_quantity(), 0);
+        assert!(book.get_order(iceberg).is_none());
+        assert_eq!(book.best_ask(), None);
+    }
+
+    #[test]
+    fn test_lit_only_market_order_stops_at_displayed_depth() {
+        let (book, iceberg) = setup_hidden_book();
+        let result = book
+            .submit_market_order_with_hidden(Id::new(), 25, Side::Buy, Hash32::zero(), false)
+            .unwrap();
+        // 10 displayed at 100 and 5 at 101, the hidden 10 untouched
+        assert_eq!(result.executed_quantity().unwrap(), 15);
+        assert_eq!(result.remaining_quantity(), 10);
+        let order = book.get_order(iceberg).unwrap();
+        assert_eq!(order.visible_quantity(), 5);
+        assert_eq!(order.hidden_quantity(), 5);
+        assert_eq!(book.best_ask(), Some(100));
+        book.validate_invariants().unwrap();
+
+        // Only the refilled slice is displayed now
+        let result = book
+            .submit_market_order_with_hidden(Id::new(), 8, Side::Buy, Hash32::zero(), false)
+            .unwrap();
+        assert_eq!(result.remaining_quantity(), 3);
+        assert_eq!(book.get_order(iceberg).unwrap().hidden_quantity(), 0);
+    }
+}
+
+#[cfg(test)]
+mod test_match_against_levels {
+    use crate::orderbook::book_change_event::PriceLevelChangedEvent;
+    use crate::orderbook::matching::{IncomingOrder, MatchConfig, match_against_levels};
+    use crate::{IcebergRefillPolicy, STPMakerScope, STPMode};
+    use crossbeam_skiplist::SkipMap;
+    use pricelevel::{
+        Hash32, Id, OrderType, Price, PriceLevel, Quantity, Side, TimeInForce, TimestampMs,
+        UuidGenerator,
+    };
+    use std::cell::RefCell;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    type Levels = SkipMap<u128, Arc<PriceLevel>>;
+
+    /// Build levels from `(price, [(quantity, user)])`, resting in list order.
+    fn levels(side: Side, spec: &[(u128, &[(u64, u8)])]) -> (Levels, Vec<Id>) {
+        let levels = SkipMap::new();
+        let mut ids = Vec::new();
+        for &(price, orders) in spec {
+            let level = Arc::new(PriceLevel::new(price));
+            for &(quantity, user) in orders {
+                let id = Id::new_uuid();
+                level.add_order(OrderType::Standard {
+                    id,
+                    price: Price::new(price),
+                    quantity: Quantity::new(quantity),
+                    side,
+                    user_id: Hash32::new([user; 32]),
+                    timestamp: TimestampMs::new(0),
+                    time_in_force: TimeInForce::Gtc,
+                    extra_fields: (),
+                });
+                ids.push(id);
+            }
+            levels.insert(price, level);
+        }
+        (levels, ids)
+    }
+
+    fn incoming(side: Side, quantity: u64, limit_price: Option<u128>, user: u8) -> IncomingOrder {
+        IncomingOrder {
+            order_id: Id::new_uuid(),
+            side,
+            quantity,
+            limit_price,
+            user_id: Hash32::new([user; 32]),
+            interact_hidden: true,
+        }
+    }
+
+    #[test]
+    fn test_buy_sweeps_asks_up_to_limit() {
+        let generator = UuidGenerator::new(Uuid::new_v4());
+        let bids = SkipMap::new();
+        let (asks, ids) = levels(
+            Side::Sell,
+            &[(100, &[(3, 1), (2, 1)]), (101, &[(4, 1)]), (102, &[(5, 1)])],
+        );
+        let changes = RefCell::new(Vec::new());
+        let record = |event: PriceLevelChangedEvent| changes.borrow_mut().push(event);
+        let config = MatchConfig {
+            stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
+            transaction_id_generator: &generator,
+            on_level_change: Some(&record),
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            arrivals: None,
+        };
+
+        let outcome = match_against_levels(
+            &incoming(Side::Buy, 20, Some(101), 2),
+            &bids,
+            &asks,
+            &config,
+        );
+
+        assert!(outcome.failure.is_none());
+        assert_eq!(outcome.match_result.executed_quantity().unwrap(), 9);
+        assert_eq!(outcome.match_result.remaining_quantity(), 11);
+        assert_eq!(outcome.match_result.filled_order_ids(), &ids[..3]);
+        assert_eq!(outcome.last_trade_price, Some(101));
+        assert!(outcome.stp.is_none());
+        assert!(outcome.cancelled_makers.is_empty());
+        // Emptied levels are removed; the level past the limit is untouched
+        let remaining: Vec<u128> = asks.iter().map(|entry| *entry.key()).collect();
+        assert_eq!(remaining, vec![102]);
+        let changes: Vec<(u128, u64)> = changes
+            .borrow()
+            .iter()
+            .map(|event| (event.price, event.quantity))
+            .collect();
+        assert_eq!(changes, vec![(100, 0), (101, 0)]);
+    }
+
+    #[test]
+    fn test_stp_cancel_maker_reports_cancelled_orders() {
+        let generator = UuidGenerator::new(Uuid::new_v4());
+        let asks = SkipMap::new();
+        let (bids, ids) = levels(Side::Buy, &[(100, &[(2, 7), (3, 8)]), (99, &[(4, 7)])]);
+        let config = MatchConfig {
+            stp_mode: STPMode::CancelMaker,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
+            transaction_id_generator: &generator,
+            on_level_change: None,
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            arrivals: None,
+        };
+
+        let outcome =
+            match_against_levels(&incoming(Side::Sell, 5, None, 7), &bids, &asks, &config);
+
+        // Both of user 7's bids are cancelled; the sell fills against user 8
+        assert_eq!(outcome.match_result.executed_quantity().unwrap(), 3);
+        assert_eq!(outcome.match_result.filled_order_ids(), &[ids[1]]);
+        let cancelled: Vec<(Id, u128)> = outcome
+            .cancelled_makers
+            .iter()
+            .map(|maker| (maker.order_id, maker.price))
+            .collect();
+        assert_eq!(cancelled, vec![(ids[0], 100), (ids[2], 99)]);
+        assert!(
+            outcome
+                .cancelled_makers
+                .iter()
+                .all(|maker| maker.user_id == Hash32::new([7; 32]))
+        );
+        let stp = outcome.stp.unwrap();
+        assert!(stp.cancelled_maker && !stp.cancelled_taker);
+        assert!(bids.is_empty());
+    }
+
+    #[test]
+    fn test_empty_opposite_side_matches_nothing() {
+        let generator = UuidGenerator::new(Uuid::new_v4());
+        let (bids, _) = levels(Side::Buy, &[(100, &[(5, 1)])]);
+        let asks = SkipMap::new();
+        let config = MatchConfig {
+            stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
+            transaction_id_generator: &generator,
+            on_level_change: None,
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            arrivals: None,
+        };
+
+        let outcome = match_against_levels(&incoming(Side::Buy, 5, None, 1), &bids, &asks, &config);
+        assert!(outcome.match_result.trades().as_vec().is_empty());
+        assert_eq!(outcome.match_result.remaining_quantity(), 5);
+        assert_eq!(outcome.last_trade_price, None);
+        assert_eq!(bids.len(), 1);
+    }
+
+    #[test]
+    fn test_failed_walk_is_settled_before_the_error_returns() {
+        use crate::OrderBook;
+        use crate::orderbook::OrderBookError;
+        use pricelevel::PriceLevelError;
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let filled = Id::new_uuid();
+        let next = Id::new_uuid();
+        book.add_limit_order(filled, 100, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(next, 101, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_contingent(
+            filled,
+            OrderType::Standard {
+                id: Id::new_uuid(),
+                price: Price::new(90),
+                quantity: Quantity::new(1),
+                side: Side::Buy,
+                user_id: Hash32::zero(),
+                timestamp: TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            },
+        )
+        .unwrap();
+
+        // The first level fills, then the walk fails at the second
+        let generator = UuidGenerator::new(Uuid::new_v4());
+        let config = MatchConfig {
+            stp_mode: STPMode::None,
+            stp_maker_scope: STPMakerScope::AllAtLevel,
+            transaction_id_generator: &generator,
+            on_level_change: None,
+            iceberg_refill_policy: IcebergRefillPolicy::default(),
+            arrivals: None,
+        };
+        let order = incoming(Side::Buy, 10, Some(100), 1);
+        let mut outcome = match_against_levels(&order, &book.bids, &book.asks, &config);
+        assert_eq!(outcome.match_result.filled_order_ids(), &[filled]);
+        outcome.failure = Some(OrderBookError::PriceLevelError(
+            PriceLevelError::InvalidFormat,
+        ));
+
+        assert!(matches!(
+            book.settle_level_match(&order, outcome, true),
+            Err(OrderBookError::PriceLevelError(_))
+        ));
+        assert!(book.get_order(filled).is_none());
+        assert!(!book.order_locations.contains_key(&filled));
+        assert!(book.contingent_orders(filled).is_empty());
+        assert_eq!(book.last_trade_price(), Some(100));
+        assert_eq!(book.best_ask(), Some(101));
+        assert!(book.get_order(next).is_some());
+    }
 }
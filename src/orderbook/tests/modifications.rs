@@ -213,6 +213,109 @@ mod test_order_modifications {
             _ => panic!("Expected IcebergOrder"),
         }
     }
+
+    #[test]
+    fn test_amend_at_same_price_keeps_queue_place() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let first = Id::new_uuid();
+        let second = Id::new_uuid();
+        book.add_limit_order(first, 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(second, 1000, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        book.update_order(OrderUpdate::Replace {
+            order_id: first,
+            price: Price::new(1000),
+            quantity: Quantity::new(8),
+            side: Side::Sell,
+        })
+        .unwrap();
+        assert_eq!(book.level_order_ids(Side::Sell, 1000), vec![first, second]);
+
+        let result = book
+            .submit_market_order(Id::new_uuid(), 13, Side::Buy)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.maker_order_id())
+            .collect();
+        assert_eq!(makers, vec![first, second]);
+    }
+
+    #[test]
+    fn test_rejected_reprice_leaves_the_order_resting() {
+        use crate::orderbook::{LifetimeCounters, OrderStateTracker, OrderStatus};
+
+        let mut book: OrderBook<()> = OrderBook::with_tick_size("TEST", 10);
+        book.set_order_state_tracker(OrderStateTracker::new());
+        let first = create_order_id();
+        let second = create_order_id();
+        book.add_limit_order(first, 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(second, 100, 5, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        let contingent = create_order_id();
+        book.add_contingent(
+            first,
+            OrderType::Standard {
+                id: contingent,
+                price: Price::new(200),
+                quantity: Quantity::new(1),
+                side: Side::Sell,
+                user_id: pricelevel::Hash32::zero(),
+                timestamp: pricelevel::TimestampMs::new(0),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            },
+        )
+        .unwrap();
+
+        // Off tick: rejected before the order is touched
+        let result = book.update_order(OrderUpdate::UpdatePrice {
+            order_id: first,
+            new_price: Price::new(105),
+        });
+        assert!(matches!(
+            result,
+            Err(OrderBookError::InvalidTickSize { .. })
+        ));
+        assert_eq!(book.level_order_ids(Side::Buy, 100), vec![first, second]);
+        assert_eq!(book.contingent_orders(first).len(), 1);
+        assert_eq!(book.order_status(first), Some(OrderStatus::Open));
+        assert_eq!(
+            book.lifetime_counters(),
+            LifetimeCounters {
+                orders_added: 2,
+                ..LifetimeCounters::default()
+            }
+        );
+
+        // Too close to the ask: rejected by the re-add, the original is put back
+        book.add_limit_order(
+            create_order_id(),
+            130,
+            5,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        book.set_min_spread_ticks(Some(2));
+        let result = book.update_order(OrderUpdate::UpdatePrice {
+            order_id: first,
+            new_price: Price::new(120),
+        });
+        assert!(result.is_err());
+        let order = book.get_order(first).expect("original order rests");
+        assert_eq!(order.price().as_u128(), 100);
+        assert_eq!(order.visible_quantity(), 10);
+        assert_eq!(book.contingent_orders(first).len(), 1);
+        assert_eq!(book.order_status(first), Some(OrderStatus::Open));
+        assert_eq!(book.lifetime_counters().orders_cancelled, 0);
+    }
 }
 
 #[cfg(test)]
@@ -786,3 +889,321 @@ mod tests {
         }
     }
 }
+
+#[cfg(test)]
+mod test_cancel_idempotent {
+    use crate::{CancelOutcome, OrderBook};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    #[test]
+    fn test_cancel_idempotent_present_then_already_gone() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 1000, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let first = book.cancel_order_idempotent(id);
+        assert!(first.is_ok());
+        match first {
+            CancelOutcome::Cancelled(order) => assert_eq!(order.id(), id),
+            other => panic!("expected Cancelled, got {other:?}"),
+        }
+        assert!(book.get_order(id).is_none());
+
+        let retry = book.cancel_order_idempotent(id);
+        assert!(retry.is_ok());
+        assert!(matches!(retry, CancelOutcome::AlreadyGone));
+    }
+
+    #[test]
+    fn test_cancel_idempotent_unknown_order_is_already_gone() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let outcome = book.cancel_order_idempotent(Id::new_uuid());
+        assert!(matches!(outcome, CancelOutcome::AlreadyGone));
+    }
+
+    #[test]
+    fn test_cancel_idempotent_filled_order_is_already_gone() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let maker = Id::new_uuid();
+        book.add_limit_order(maker, 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(Id::new_uuid(), 1000, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        assert!(matches!(
+            book.cancel_order_idempotent(maker),
+            CancelOutcome::AlreadyGone
+        ));
+    }
+}
+
+#[cfg(test)]
+mod cancel_as_tests {
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Hash32, Id, Side, TimeInForce};
+
+    fn book_with_order(owner: Hash32) -> (OrderBook<()>, Id) {
+        let book = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order_with_user(id, 1000, 10, Side::Buy, TimeInForce::Gtc, owner, None)
+            .unwrap();
+        (book, id)
+    }
+
+    #[test]
+    fn test_cancel_as_owner_cancels() {
+        let owner = Hash32::new([1; 32]);
+        let (book, id) = book_with_order(owner);
+
+        let cancelled = book.cancel_order_as(id, owner).unwrap();
+        assert_eq!(cancelled.id(), id);
+        assert!(book.get_order(id).is_none());
+    }
+
+    #[test]
+    fn test_cancel_as_non_owner_is_unauthorized() {
+        let owner = Hash32::new([1; 32]);
+        let other = Hash32::new([2; 32]);
+        let (book, id) = book_with_order(owner);
+
+        assert!(matches!(
+            book.cancel_order_as(id, other),
+            Err(OrderBookError::Unauthorized { order_id, user_id })
+                if order_id == id && user_id == other
+        ));
+        assert!(book.get_order(id).is_some());
+    }
+
+    #[test]
+    fn test_cancel_as_unknown_order_is_not_found() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert!(matches!(
+            book.cancel_order_as(Id::new_uuid(), Hash32::new([1; 32])),
+            Err(OrderBookError::OrderNotFound(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod cancel_if_unfilled_tests {
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn book_with_order() -> (OrderBook<()>, Id) {
+        let book = OrderBook::new("TEST");
+        let id = Id::new_uuid();
+        book.add_limit_order(id, 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        (book, id)
+    }
+
+    #[test]
+    fn test_cancel_if_unfilled_cancels_untouched_order() {
+        let (book, id) = book_with_order();
+
+        let cancelled = book.try_cancel_if_unfilled(id, 10).unwrap();
+        assert_eq!(cancelled.id(), id);
+        assert!(book.get_order(id).is_none());
+        assert!(matches!(
+            book.try_cancel_if_unfilled(id, 10),
+            Err(OrderBookError::OrderNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_cancel_if_unfilled_after_match_is_stale() {
+        let (book, id) = book_with_order();
+        book.submit_market_order(Id::new_uuid(), 4, Side::Buy)
+            .unwrap();
+
+        assert!(matches!(
+            book.try_cancel_if_unfilled(id, 10),
+            Err(OrderBookError::QuantityChanged {
+                order_id,
+                expected: 10,
+                actual: 6,
+            }) if order_id == id
+        ));
+        assert_eq!(book.get_order(id).unwrap().visible_quantity(), 6);
+
+        // Retrying with the quantity now in the book succeeds
+        assert!(book.try_cancel_if_unfilled(id, 6).is_ok());
+        assert_eq!(book.best_ask(), None);
+    }
+}
+
+#[cfg(test)]
+mod requeue_tests {
+    use crate::orderbook::order_state::{CancelReason, OrderStateTracker, OrderStatus};
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Hash32, Id, Side, TimeInForce};
+
+    #[test]
+    fn test_requeue_moves_order_behind_later_arrival() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let first = Id::new_uuid();
+        let second = Id::new_uuid();
+        book.add_limit_order(first, 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(second, 1000, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let requeued = book.requeue_order(first).unwrap();
+        assert_ne!(requeued, first);
+        assert!(book.get_order(first).is_none());
+
+        // Price and quantity are unchanged
+        let order = book.get_order(requeued).expect("still resting");
+        assert_eq!(order.price().as_u128(), 1000);
+        assert_eq!(order.visible_quantity(), 10);
+        assert_eq!(book.get_orders_at_price(1000, Side::Sell).len(), 2);
+
+        // `second` now fills first
+        let result = book
+            .submit_market_order(Id::new_uuid(), 12, Side::Buy)
+            .unwrap();
+        let makers: Vec<Id> = result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|t| t.maker_order_id())
+            .collect();
+        assert_eq!(makers, vec![second, requeued]);
+        assert_eq!(book.get_order(requeued).unwrap().visible_quantity(), 3);
+    }
+
+    #[test]
+    fn test_requeue_rekeys_user_index_and_state() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_order_state_tracker(OrderStateTracker::new());
+        let user = Hash32::new([7; 32]);
+        let id = Id::new_uuid();
+        book.add_limit_order_with_user(id, 1000, 10, Side::Buy, TimeInForce::Gtc, user, None)
+            .unwrap();
+
+        let requeued = book.requeue_order(id).unwrap();
+        assert!(matches!(
+            book.order_status(id),
+            Some(OrderStatus::Cancelled {
+                reason: CancelReason::Requeued,
+                ..
+            })
+        ));
+        assert_eq!(book.order_status(requeued), Some(OrderStatus::Open));
+
+        // The user index follows the new id
+        let result = book.cancel_orders_by_user(user);
+        assert_eq!(result.cancelled_order_ids(), &[requeued]);
+    }
+
+    #[test]
+    fn test_requeue_unknown_order_fails() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert!(matches!(
+            book.requeue_order(Id::new_uuid()),
+            Err(OrderBookError::OrderNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_requeue_as_given_id() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let first = Id::new_uuid();
+        let second = Id::new_uuid();
+        book.add_limit_order(first, 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book.add_limit_order(second, 1000, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // The new id must not already be resting
+        assert!(matches!(
+            book.requeue_order_as(first, second),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+        assert!(book.get_order(first).is_some());
+
+        let requeued = Id::new_uuid();
+        book.requeue_order_as(first, requeued).unwrap();
+        assert!(book.get_order(first).is_none());
+        assert_eq!(book.get_order(requeued).unwrap().visible_quantity(), 10);
+    }
+}
+
+#[cfg(test)]
+mod iceberg_cancel_tests {
+    use crate::OrderBook;
+    use pricelevel::{Hash32, Id, Side, TimeInForce};
+
+    fn user() -> Hash32 {
+        Hash32::new([3; 32])
+    }
+
+    #[test]
+    fn test_cancel_iceberg_after_visible_slice_consumed() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let iceberg = Id::new_uuid();
+        book.add_iceberg_order_with_user(
+            iceberg,
+            100,
+            10,
+            30,
+            Side::Sell,
+            TimeInForce::Gtc,
+            user(),
+            None,
+        )
+        .unwrap();
+
+        // Consume the visible slice and part of the refill
+        book.submit_market_order(Id::new_uuid(), 15, Side::Buy)
+            .unwrap();
+        let order = book.get_order(iceberg).expect("iceberg still resting");
+        assert_eq!(order.visible_quantity() + order.hidden_quantity(), 25);
+
+        assert!(book.cancel_order(iceberg).unwrap().is_some());
+
+        assert!(book.get_order(iceberg).is_none());
+        assert_eq!(book.best_ask(), None);
+        assert!(book.asks.is_empty());
+        assert!(book.order_locations.is_empty());
+        assert!(book.user_orders.get(&user()).is_none());
+        assert_eq!(book.user_resting_quantity(user(), Side::Sell), 0);
+        assert_eq!(book.total_depth_at_levels(usize::MAX, Side::Sell), 0);
+        let snapshot = book.create_snapshot(usize::MAX);
+        assert!(snapshot.asks.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_refilled_iceberg_leaves_no_hidden_quantity_at_shared_level() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let iceberg = Id::new_uuid();
+        book.add_iceberg_order_with_user(
+            iceberg,
+            100,
+            10,
+            30,
+            Side::Sell,
+            TimeInForce::Gtc,
+            user(),
+            None,
+        )
+        .unwrap();
+        let other = Id::new_uuid();
+        book.add_limit_order(other, 100, 7, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        // Exactly the visible slice: the iceberg refills from hidden
+        book.submit_market_order(Id::new_uuid(), 10, Side::Buy)
+            .unwrap();
+        book.cancel_order(iceberg).unwrap();
+
+        let snapshot = book.create_snapshot(usize::MAX);
+        assert_eq!(snapshot.asks.len(), 1);
+        assert_eq!(snapshot.asks[0].order_count(), 1);
+        assert_eq!(snapshot.asks[0].hidden_quantity(), 0);
+        assert_eq!(snapshot.asks[0].visible_quantity(), 7);
+        assert_eq!(book.user_resting_quantity(user(), Side::Sell), 0);
+        assert!(book.get_order(other).is_some());
+    }
+}
@@ -780,3 +780,216 @@ mod test_operations_specific {
         assert_eq!(remaining_sell.unwrap().visible_quantity(), 5);
     }
 }
+
+#[cfg(test)]
+mod test_remainder_policy {
+    use crate::{OrderBook, RemainderOutcome, RemainderPolicy};
+    use pricelevel::{Hash32, Id, Side, TimeInForce};
+
+    fn new_order_id() -> Id {
+        Id::new_uuid()
+    }
+
+    fn create_test_order_book() -> OrderBook<()> {
+        OrderBook::new("TEST-SYMBOL")
+    }
+
+    #[test]
+    fn test_limit_remainder_rests_by_default_policy() {
+        let order_book = create_test_order_book();
+        order_book
+            .add_limit_order(new_order_id(), 1000, 4, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let id = new_order_id();
+        let result = order_book
+            .add_limit_order_with_remainder(
+                id,
+                1000,
+                10,
+                Side::Buy,
+                TimeInForce::Gtc,
+                Hash32::zero(),
+                RemainderPolicy::Rest,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.match_result.executed_quantity().unwrap(), 4);
+        assert_eq!(result.remainder, RemainderOutcome::Rested { quantity: 6 });
+        assert_eq!(result.order.visible_quantity(), 6);
+        assert_eq!(order_book.best_bid(), Some(1000));
+        assert_eq!(order_book.best_ask(), None);
+        assert!(order_book.get_order(id).is_some());
+    }
+
+    #[test]
+    fn test_limit_remainder_cancelled_under_policy() {
+        let order_book = create_test_order_book();
+        order_book
+            .add_limit_order(new_order_id(), 1000, 4, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let id = new_order_id();
+        let result = order_book
+            .add_limit_order_with_remainder(
+                id,
+                1000,
+                10,
+                Side::Buy,
+                TimeInForce::Gtc,
+                Hash32::zero(),
+                RemainderPolicy::Cancel,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.match_result.executed_quantity().unwrap(), 4);
+        assert_eq!(
+            result.remainder,
+            RemainderOutcome::Cancelled { quantity: 6 }
+        );
+        assert_eq!(order_book.best_bid(), None);
+        assert_eq!(order_book.best_ask(), None);
+        assert!(order_book.get_order(id).is_none());
+    }
+
+    #[test]
+    fn test_limit_remainder_fully_filled_reports_filled() {
+        let order_book = create_test_order_book();
+        order_book
+            .add_limit_order(new_order_id(), 1000, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let result = order_book
+            .add_limit_order_with_remainder(
+                new_order_id(),
+                1000,
+                10,
+                Side::Buy,
+                TimeInForce::Gtc,
+                Hash32::zero(),
+                RemainderPolicy::Cancel,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(result.remainder, RemainderOutcome::Filled);
+        assert!(result.match_result.is_complete());
+    }
+
+    #[test]
+    fn test_submit_ioc_limit_partial_fill_cancels_remainder() {
+        let order_book = create_test_order_book();
+        let maker = new_order_id();
+        order_book
+            .add_limit_order(maker, 1000, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        order_book
+            .add_limit_order(new_order_id(), 1010, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let id = new_order_id();
+        let result = order_book
+            .submit_ioc_limit(id, 1000, 8, Side::Buy, Hash32::zero())
+            .unwrap();
+
+        assert_eq!(result.match_result.executed_quantity().unwrap(), 5);
+        assert_eq!(result.match_result.remaining_quantity(), 3);
+        assert_eq!(result.match_result.filled_order_ids(), &[maker]);
+        // The level beyond the limit is untouched and nothing rests
+        assert!(order_book.get_order(id).is_none());
+        assert_eq!(order_book.best_bid(), None);
+        assert_eq!(order_book.best_ask(), Some(1010));
+    }
+
+    #[test]
+    fn test_submit_ioc_limit_full_fill() {
+        let order_book = create_test_order_book();
+        order_book
+            .add_limit_order(new_order_id(), 1000, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let id = new_order_id();
+        let result = order_book
+            .submit_ioc_limit(id, 990, 10, Side::Sell, Hash32::zero())
+            .unwrap();
+
+        assert!(result.match_result.is_complete());
+        assert_eq!(result.symbol, "TEST-SYMBOL");
+        assert!(order_book.get_order(id).is_none());
+        assert_eq!(order_book.best_bid(), None);
+        assert_eq!(order_book.best_ask(), None);
+    }
+}
+
+#[cfg(test)]
+mod test_add_order_detailed {
+    use crate::{OrderBook, STPMode};
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn order(price: u128, quantity: u64, side: Side, user: u8) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::new([user; 32]),
+            timestamp: TimestampMs::new(0),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    #[test]
+    fn test_detailed_add_reports_rested_and_filled_quantity() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+
+        // Cleanly rested
+        let ask = order(100, 4, Side::Sell, 1);
+        let rested = book.add_order_detailed(ask).unwrap();
+        assert_eq!(rested.order_id, ask.id());
+        assert_eq!(rested.rested_quantity, 4);
+        assert_eq!(rested.filled_quantity, 0);
+        assert!(rested.fills.is_empty());
+
+        // Partially matched, remainder rests
+        let partial = book
+            .add_order_detailed(order(100, 3, Side::Buy, 2))
+            .unwrap();
+        assert_eq!(partial.filled_quantity, 3);
+        assert_eq!(partial.rested_quantity, 0);
+        let bid = order(100, 5, Side::Buy, 2);
+        let partial = book.add_order_detailed(bid).unwrap();
+        assert_eq!(partial.filled_quantity, 1);
+        assert_eq!(partial.rested_quantity, 4);
+        assert_eq!(partial.fills.len(), 1);
+        assert_eq!(partial.fills[0].maker_order_id(), ask.id());
+        assert!(book.get_order(bid.id()).is_some());
+
+        // Fully matched: nothing rests, and the order is not in the book
+        let taker = order(100, 4, Side::Sell, 1);
+        let filled = book.add_order_detailed(taker).unwrap();
+        assert_eq!(filled.filled_quantity, 4);
+        assert_eq!(filled.rested_quantity, 0);
+        assert_eq!(filled.cancelled_quantity, 0);
+        assert!(book.get_order(taker.id()).is_none());
+    }
+
+    #[test]
+    fn test_detailed_add_reports_stp_cancelled_remainder() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_stp_mode(STPMode::CancelTaker);
+        book.add_order(order(100, 2, Side::Sell, 2)).unwrap();
+        book.add_order(order(101, 2, Side::Sell, 1)).unwrap();
+
+        // Not in the book after the add, but not fully filled either
+        let taker = order(101, 5, Side::Buy, 1);
+        let outcome = book.add_order_detailed(taker).unwrap();
+        assert_eq!(outcome.filled_quantity, 2);
+        assert_eq!(outcome.rested_quantity, 0);
+        assert_eq!(outcome.cancelled_quantity, 3);
+        assert!(outcome.stp.is_some_and(|stp| stp.cancelled_taker));
+        assert!(book.get_order(taker.id()).is_none());
+    }
+}
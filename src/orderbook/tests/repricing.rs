@@ -374,4 +374,64 @@ mod tests {
         assert_eq!(book.pegged_order_count(), 0);
         assert_eq!(book.trailing_stop_count(), 0);
     }
+
+    #[test]
+    fn test_special_orders_survive_snapshot_restore() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_order(OrderType::Standard {
+            id: create_order_id(),
+            price: Price::new(100),
+            quantity: Quantity::new(100),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(current_time_millis()),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+
+        let pegged_id = create_order_id();
+        book.add_order(OrderType::PeggedOrder {
+            id: pegged_id,
+            price: Price::new(90),
+            quantity: Quantity::new(10),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(current_time_millis()),
+            time_in_force: TimeInForce::Gtc,
+            reference_price_offset: 5,
+            reference_price_type: PegReferenceType::BestBid,
+            extra_fields: (),
+        })
+        .unwrap();
+
+        let trailing_id = create_order_id();
+        book.add_order(OrderType::TrailingStop {
+            id: trailing_id,
+            price: Price::new(110),
+            quantity: Quantity::new(10),
+            side: Side::Sell,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(current_time_millis()),
+            time_in_force: TimeInForce::Gtc,
+            trail_amount: Quantity::new(5),
+            last_reference_price: Price::new(115),
+            extra_fields: (),
+        })
+        .unwrap();
+
+        let package = book.create_snapshot_package(usize::MAX).unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_package(package).unwrap();
+
+        assert_eq!(restored.pegged_order_ids(), vec![pegged_id]);
+        assert_eq!(restored.trailing_stop_ids(), vec![trailing_id]);
+
+        // Re-pricing still applies to the restored pegged order
+        assert_eq!(restored.reprice_pegged_orders().unwrap(), 1);
+        assert_eq!(
+            restored.get_order(pegged_id).unwrap().price().as_u128(),
+            105
+        );
+    }
 }
@@ -642,3 +642,759 @@ mod test_snapshot_specific {
         assert_eq!(best_ask, Some((1010, 15)));
     }
 }
+
+#[cfg(test)]
+mod columnar_snapshot_tests {
+    use crate::OrderBook;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn populated_book() -> OrderBook<()> {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for (price, qty) in [(1000, 10), (990, 20), (980, 30), (970, 40)] {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                qty,
+                Side::Buy,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+        for (price, qty) in [(1010, 15), (1020, 25), (1030, 35)] {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                qty,
+                Side::Sell,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+        // Second order at an existing level aggregates into it
+        book.add_limit_order(Id::new_uuid(), 1010, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_columnar_aligns_with_regular_snapshot() {
+        let book = populated_book();
+
+        for depth in [0, 1, 3, 10] {
+            let regular = book.create_snapshot(depth);
+            let columnar = book.snapshot_columnar(depth);
+
+            assert_eq!(columnar.symbol, regular.symbol);
+            assert_eq!(columnar.bid_len(), regular.bids.len());
+            assert_eq!(columnar.bid_qtys.len(), regular.bids.len());
+            assert_eq!(columnar.ask_len(), regular.asks.len());
+            assert_eq!(columnar.ask_qtys.len(), regular.asks.len());
+
+            for (i, level) in regular.bids.iter().enumerate() {
+                assert_eq!(columnar.bid_prices[i], level.price());
+                assert_eq!(columnar.bid_qtys[i], level.visible_quantity());
+            }
+            for (i, level) in regular.asks.iter().enumerate() {
+                assert_eq!(columnar.ask_prices[i], level.price());
+                assert_eq!(columnar.ask_qtys[i], level.visible_quantity());
+            }
+        }
+    }
+
+    #[test]
+    fn test_columnar_best_first_ordering() {
+        let book = populated_book();
+        let columnar = book.snapshot_columnar(2);
+
+        assert_eq!(columnar.bid_prices, vec![1000, 990]);
+        assert_eq!(columnar.bid_qtys, vec![10, 20]);
+        assert_eq!(columnar.ask_prices, vec![1010, 1020]);
+        assert_eq!(columnar.ask_qtys, vec![20, 25]);
+    }
+
+    #[test]
+    fn test_aggregated_snapshot_collapses_orders_per_level() {
+        let book = populated_book();
+        let aggregated = book.create_aggregated_snapshot(10);
+        assert_eq!(aggregated.symbol, book.symbol());
+
+        let asks: Vec<(u128, u64, usize)> = aggregated
+            .asks
+            .iter()
+            .map(|level| (level.price, level.quantity, level.order_count))
+            .collect();
+        assert_eq!(asks, vec![(1010, 20, 2), (1020, 25, 1), (1030, 35, 1)]);
+        assert_eq!(aggregated.bids.len(), book.create_snapshot(10).bids.len());
+        assert_eq!(aggregated.bids[0].order_count, 1);
+    }
+
+    #[test]
+    fn test_aggregated_snapshot_truncates_each_side() {
+        let book = populated_book();
+        let aggregated = book.create_aggregated_snapshot(2);
+        let prices = |levels: &[crate::AggregatedLevel]| -> Vec<u128> {
+            levels.iter().map(|level| level.price).collect()
+        };
+        assert_eq!(prices(&aggregated.bids), vec![1000, 990]);
+        assert_eq!(prices(&aggregated.asks), vec![1010, 1020]);
+
+        let empty = book.create_aggregated_snapshot(0);
+        assert!(empty.bids.is_empty() && empty.asks.is_empty());
+    }
+
+    #[test]
+    fn test_columnar_empty_book() {
+        let book: OrderBook<()> = OrderBook::new("EMPTY");
+        let columnar = book.snapshot_columnar(5);
+
+        assert!(columnar.bid_prices.is_empty());
+        assert!(columnar.ask_prices.is_empty());
+        assert_eq!(columnar.symbol, "EMPTY");
+    }
+}
+
+#[cfg(test)]
+mod package_delta_tests {
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn add(book: &OrderBook<()>, id: Id, price: u128, qty: u64, side: Side) {
+        book.add_limit_order(id, price, qty, side, TimeInForce::Gtc, None)
+            .unwrap();
+    }
+
+    fn base_book() -> (OrderBook<()>, Id, Id) {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let removed_bid = Id::new_uuid();
+        let changed_ask = Id::new_uuid();
+        add(&book, Id::new_uuid(), 1000, 10, Side::Buy);
+        add(&book, removed_bid, 990, 20, Side::Buy);
+        add(&book, changed_ask, 1010, 15, Side::Sell);
+        add(&book, Id::new_uuid(), 1020, 25, Side::Sell);
+        (book, removed_bid, changed_ask)
+    }
+
+    #[test]
+    fn test_base_plus_delta_matches_full_package() {
+        let (mut book, removed_bid, changed_ask) = base_book();
+        let base = book.create_snapshot_package(usize::MAX).unwrap();
+
+        book.cancel_order(removed_bid).unwrap();
+        book.update_order(pricelevel::OrderUpdate::UpdateQuantity {
+            order_id: changed_ask,
+            new_quantity: pricelevel::Quantity::new(5),
+        })
+        .unwrap();
+        add(&book, Id::new_uuid(), 980, 30, Side::Buy);
+        book.set_tick_size(10);
+        book.set_trading_state(crate::orderbook::auction::TradingState::Auction);
+        let full = book.create_snapshot_package(usize::MAX).unwrap();
+
+        let delta = full.delta_since(&base).unwrap();
+        assert_eq!(delta.bids_removed, vec![990]);
+        assert_eq!(delta.bids_upserted.len(), 1);
+        assert_eq!(delta.bids_upserted[0].price(), 980);
+        assert_eq!(delta.asks_upserted.len(), 1);
+        assert_eq!(delta.asks_upserted[0].price(), 1010);
+        assert!(delta.asks_removed.is_empty());
+
+        let rebuilt = base.apply_delta(&delta).unwrap();
+        assert_eq!(rebuilt.checksum, full.checksum);
+        assert_eq!(rebuilt.tick_size, Some(10));
+        assert_eq!(rebuilt.trading_state, full.trading_state);
+
+        let mut from_delta: OrderBook<()> = OrderBook::new("TEST");
+        from_delta.restore_from_snapshot_package(rebuilt).unwrap();
+        assert!(from_delta.is_auction());
+        let mut from_full: OrderBook<()> = OrderBook::new("TEST");
+        from_full.restore_from_snapshot_package(full).unwrap();
+
+        let a = from_delta.create_snapshot(usize::MAX);
+        let b = from_full.create_snapshot(usize::MAX);
+        assert_eq!(a.bids.len(), b.bids.len());
+        assert_eq!(a.asks.len(), b.asks.len());
+        for (x, y) in a
+            .bids
+            .iter()
+            .chain(&a.asks)
+            .zip(b.bids.iter().chain(&b.asks))
+        {
+            assert_eq!(x.price(), y.price());
+            assert_eq!(x.visible_quantity(), y.visible_quantity());
+            assert_eq!(x.order_count(), y.order_count());
+        }
+        assert_eq!(from_delta.tick_size(), Some(10));
+    }
+
+    #[test]
+    fn test_delta_chain_restores_latest_package() {
+        let (book, removed_bid, _) = base_book();
+        let p0 = book.create_snapshot_package(usize::MAX).unwrap();
+        add(&book, Id::new_uuid(), 1030, 5, Side::Sell);
+        let p1 = book.create_snapshot_package(usize::MAX).unwrap();
+        book.cancel_order(removed_bid).unwrap();
+        let p2 = book.create_snapshot_package(usize::MAX).unwrap();
+
+        let d1 = p1.delta_since(&p0).unwrap();
+        let d2 = p2.delta_since(&p1).unwrap();
+
+        let restored = p0.apply_delta(&d1).unwrap().apply_delta(&d2).unwrap();
+        assert_eq!(restored.checksum, p2.checksum);
+
+        // Skipping a delta breaks the chain
+        let err = p0.apply_delta(&d2).unwrap_err();
+        assert!(matches!(err, OrderBookError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn test_delta_of_unchanged_book_is_empty() {
+        let (book, _, _) = base_book();
+        let p0 = book.create_snapshot_package(usize::MAX).unwrap();
+        let delta = p0.delta_since(&p0).unwrap();
+        assert!(delta.is_empty());
+        assert_eq!(p0.apply_delta(&delta).unwrap().checksum, p0.checksum);
+    }
+
+    #[test]
+    fn test_delta_rejects_symbol_mismatch() {
+        let (book, _, _) = base_book();
+        let p0 = book.create_snapshot_package(usize::MAX).unwrap();
+        let other: OrderBook<()> = OrderBook::new("OTHER");
+        let p_other = other.create_snapshot_package(usize::MAX).unwrap();
+
+        let err = p_other.delta_since(&p0).unwrap_err();
+        assert!(matches!(err, OrderBookError::InvalidOperation { .. }));
+
+        let delta = p0.delta_since(&p0).unwrap();
+        let err = p_other.apply_delta(&delta).unwrap_err();
+        assert!(matches!(err, OrderBookError::InvalidOperation { .. }));
+    }
+}
+
+#[cfg(test)]
+mod package_chain_tests {
+    use crate::orderbook::OrderBookSnapshotPackage;
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn add(book: &OrderBook<()>, price: u128, qty: u64, side: Side) {
+        book.add_limit_order(Id::new_uuid(), price, qty, side, TimeInForce::Gtc, None)
+            .unwrap();
+    }
+
+    fn three_checkpoints() -> Vec<OrderBookSnapshotPackage> {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        add(&book, 1000, 10, Side::Buy);
+        let p0 = book.create_snapshot_package(usize::MAX).unwrap();
+        add(&book, 1010, 5, Side::Sell);
+        let p1 = book
+            .create_chained_snapshot_package(usize::MAX, &p0)
+            .unwrap();
+        add(&book, 990, 7, Side::Buy);
+        let p2 = book
+            .create_chained_snapshot_package(usize::MAX, &p1)
+            .unwrap();
+        vec![p0, p1, p2]
+    }
+
+    #[test]
+    fn test_three_checkpoint_chain_verifies() {
+        let chain = three_checkpoints();
+        assert!(chain[0].prev_hash.is_empty());
+        assert_eq!(chain[1].prev_hash, chain[0].chain_hash);
+        assert_eq!(chain[2].prev_hash, chain[1].chain_hash);
+        OrderBookSnapshotPackage::verify_chain(&chain).unwrap();
+
+        // The chain survives a JSON round trip
+        let restored: Vec<_> = chain
+            .iter()
+            .map(|p| OrderBookSnapshotPackage::from_json(&p.to_json().unwrap()).unwrap())
+            .collect();
+        OrderBookSnapshotPackage::verify_chain(&restored).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_middle_checkpoint_is_detected() {
+        let mut chain = three_checkpoints();
+        chain[1].snapshot.asks.clear();
+        let err = OrderBookSnapshotPackage::verify_chain(&chain).unwrap_err();
+        assert!(matches!(err, OrderBookError::ChecksumMismatch { .. }));
+
+        // Re-sealing the altered checkpoint still breaks the link to the next
+        let mut chain = three_checkpoints();
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        add(&book, 1000, 99, Side::Buy);
+        let forged = book
+            .create_chained_snapshot_package(usize::MAX, &chain[0])
+            .unwrap();
+        chain[1] = forged;
+        chain[1].validate().unwrap();
+        let err = OrderBookSnapshotPackage::verify_chain(&chain).unwrap_err();
+        assert!(matches!(
+            err,
+            OrderBookError::ChecksumMismatch { ref actual, .. } if *actual == chain[2].prev_hash
+        ));
+    }
+
+    #[test]
+    fn test_removed_checkpoint_breaks_chain() {
+        let mut chain = three_checkpoints();
+        chain.remove(1);
+        let err = OrderBookSnapshotPackage::verify_chain(&chain).unwrap_err();
+        assert!(matches!(err, OrderBookError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn test_chain_hash_covers_fields_outside_the_snapshot() {
+        let mut chain = three_checkpoints();
+        chain[1].session_volume += 1;
+        assert!(matches!(
+            chain[1].validate(),
+            Err(OrderBookError::ChecksumMismatch { .. })
+        ));
+
+        // A checkpoint naming its predecessor must carry its own hash
+        let mut chain = three_checkpoints();
+        chain[1].chain_hash.clear();
+        assert!(matches!(
+            chain[1].validate(),
+            Err(OrderBookError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_chain_rebuilt_from_deltas_verifies() {
+        let chain = three_checkpoints();
+        let d1 = chain[1].delta_since(&chain[0]).unwrap();
+        let d2 = chain[2].delta_since(&chain[1]).unwrap();
+        let p1 = chain[0].apply_delta(&d1).unwrap();
+        let p2 = p1.apply_delta(&d2).unwrap();
+        assert_eq!(p2.chain_hash, chain[2].chain_hash);
+        OrderBookSnapshotPackage::verify_chain(&[chain[0].clone(), p1, p2]).unwrap();
+
+        // A delta that does not rebuild its target package is rejected
+        let mut forged = d2.clone();
+        forged.session_volume += 1;
+        assert!(matches!(
+            chain[1].apply_delta(&forged),
+            Err(OrderBookError::ChecksumMismatch { .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod quote_tests {
+    use crate::OrderBook;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn add(book: &OrderBook<()>, price: u128, qty: u64, side: Side) {
+        book.add_limit_order(Id::new_uuid(), price, qty, side, TimeInForce::Gtc, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_quote_orders_and_aggregates_levels() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        add(&book, 990, 10, Side::Buy);
+        add(&book, 1000, 5, Side::Buy);
+        add(&book, 1000, 7, Side::Buy);
+        add(&book, 1020, 3, Side::Sell);
+        add(&book, 1010, 4, Side::Sell);
+        add(&book, 1010, 6, Side::Sell);
+
+        let quote = book.quote(5);
+        assert_eq!(quote.bids, vec![(1000, 12), (990, 10)]);
+        assert_eq!(quote.asks, vec![(1010, 10), (1020, 3)]);
+    }
+
+    #[test]
+    fn test_quote_truncates_to_depth() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for i in 0..10 {
+            add(&book, 900 - i, 1, Side::Buy);
+            add(&book, 1000 + i, 1, Side::Sell);
+        }
+
+        let quote = book.quote(3);
+        assert_eq!(quote.bids, vec![(900, 1), (899, 1), (898, 1)]);
+        assert_eq!(quote.asks, vec![(1000, 1), (1001, 1), (1002, 1)]);
+
+        let empty = book.quote(0);
+        assert!(empty.bids.is_empty() && empty.asks.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod crossed_restore_tests {
+    use crate::orderbook::auction::TradingState;
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    #[test]
+    fn test_restore_non_crossed_package_succeeds() {
+        let source: OrderBook<()> = OrderBook::new("TEST");
+        source
+            .add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        source
+            .add_limit_order(Id::new_uuid(), 101, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        let package = source.create_snapshot_package(usize::MAX).unwrap();
+
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        assert!(restored.restore_from_snapshot_package(package).is_ok());
+        assert_eq!(restored.best_bid(), Some(100));
+        assert_eq!(restored.best_ask(), Some(101));
+    }
+
+    /// A book crossed during an auction.
+    fn auction_book() -> OrderBook<()> {
+        let source: OrderBook<()> = OrderBook::new("TEST");
+        source.set_trading_state(TradingState::Auction);
+        source
+            .add_limit_order(Id::new_uuid(), 105, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        source
+            .add_limit_order(Id::new_uuid(), 100, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        source
+    }
+
+    #[test]
+    fn test_auction_package_round_trips_crossed() {
+        let package = auction_book().create_snapshot_package(usize::MAX).unwrap();
+        assert_eq!(package.trading_state, TradingState::Auction);
+        let package =
+            crate::orderbook::OrderBookSnapshotPackage::from_json(&package.to_json().unwrap())
+                .unwrap();
+
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(restored.trading_state(), TradingState::Auction);
+        assert_eq!(
+            (restored.best_bid(), restored.best_ask()),
+            (Some(105), Some(100))
+        );
+
+        let result = restored.run_auction_uncross().unwrap();
+        assert_eq!(result.executed_quantity, 10);
+        assert_eq!(restored.trading_state(), TradingState::Continuous);
+    }
+
+    #[test]
+    fn test_restore_crossed_package_is_rejected() {
+        // Outside an auction a crossed package is corrupt
+        let mut package = auction_book().create_snapshot_package(usize::MAX).unwrap();
+        package.trading_state = TradingState::Continuous;
+        package.update_chain_hash().unwrap();
+
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        let keep = Id::new_uuid();
+        restored
+            .add_limit_order(keep, 90, 1, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        let err = restored
+            .restore_from_snapshot_package(package)
+            .expect_err("crossed snapshot");
+        assert!(matches!(err, OrderBookError::InvalidSnapshot { .. }));
+        assert!(err.to_string().contains("105"));
+        // The existing state is untouched
+        assert!(restored.get_order(keep).is_some());
+        assert_eq!(restored.best_ask(), None);
+    }
+}
+
+#[cfg(test)]
+mod snapshot_options_tests {
+    use crate::{OrderBook, SnapshotOptions};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    fn book() -> OrderBook<()> {
+        let book = OrderBook::new("TEST");
+        for price in [97, 98, 99, 100] {
+            book.add_limit_order(Id::new_uuid(), price, 10, Side::Buy, TimeInForce::Gtc, None)
+                .unwrap();
+        }
+        for price in [101, 102] {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                10,
+                Side::Sell,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+        book.add_iceberg_order(
+            Id::new_uuid(),
+            101,
+            5,
+            20,
+            Side::Sell,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_asymmetric_depths() {
+        let book = book();
+        let snapshot = book.create_snapshot_with(SnapshotOptions {
+            max_bid_levels: 3,
+            max_ask_levels: 1,
+            include_hidden: true,
+        });
+
+        let bids: Vec<u128> = snapshot.bids.iter().map(|level| level.price()).collect();
+        assert_eq!(bids, vec![100, 99, 98]);
+        assert_eq!(snapshot.asks.len(), 1);
+        assert_eq!(snapshot.asks[0].price(), 101);
+
+        // The depth argument of create_snapshot applies per side
+        let symmetric = book.create_snapshot(2);
+        assert_eq!(symmetric.bids.len(), 2);
+        assert_eq!(symmetric.asks.len(), 2);
+        let default = book.create_snapshot_with(SnapshotOptions::default());
+        assert_eq!(default.bids.len(), 4);
+    }
+
+    #[test]
+    fn test_include_hidden_controls_iceberg_quantity() {
+        let book = book();
+        let with_hidden = book.create_snapshot_with(SnapshotOptions::depth(1));
+        assert_eq!(with_hidden.asks[0].visible_quantity(), 15);
+        assert_eq!(with_hidden.asks[0].hidden_quantity(), 20);
+
+        let public = book.create_snapshot_with(SnapshotOptions {
+            include_hidden: false,
+            ..SnapshotOptions::depth(1)
+        });
+        let level = &public.asks[0];
+        assert_eq!(level.visible_quantity(), 15);
+        assert_eq!(level.hidden_quantity(), 0);
+        assert_eq!(level.order_count(), 2);
+        assert!(
+            level
+                .orders()
+                .iter()
+                .all(|order| order.hidden_quantity() == 0)
+        );
+
+        // The book itself is unchanged
+        assert_eq!(
+            book.create_snapshot(1).asks[0].hidden_quantity(),
+            with_hidden.asks[0].hidden_quantity()
+        );
+    }
+}
+
+#[cfg(test)]
+mod off_book_state_tests {
+    use crate::orderbook::OrderBookSnapshotPackage;
+    use crate::orderbook::reference_price::ReferencePricePolicy;
+    use crate::{MarketOrderEmptyBookPolicy, OrderBook, RestoreScope, TrailAmount};
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn standard(price: u128, quantity: u64, side: Side, tif: TimeInForce) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: tif,
+            extra_fields: (),
+        }
+    }
+
+    /// Print a trade of 1 at `price` between two fresh orders.
+    fn trade_at(book: &OrderBook<()>, price: u128) {
+        book.add_order(standard(price, 1, Side::Sell, TimeInForce::Gtc))
+            .unwrap();
+        book.add_order(standard(price, 1, Side::Buy, TimeInForce::Ioc))
+            .unwrap();
+    }
+
+    /// Restore `book`'s package, through JSON, into a fresh book.
+    fn round_trip(book: &OrderBook<()>) -> OrderBook<()> {
+        let json = book.snapshot_to_json(usize::MAX).unwrap();
+        let mut restored = OrderBook::new("TEST");
+        restored.set_reference_price_policy(book.reference_price_policy());
+        restored.restore_from_snapshot_json(&json).unwrap();
+        restored
+    }
+
+    #[test]
+    fn test_last_trade_price_round_trips() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(round_trip(&book).last_trade_price(), None);
+
+        trade_at(&book, 100);
+        let restored = round_trip(&book);
+        assert_eq!(restored.last_trade_price(), Some(100));
+
+        // A plain snapshot does not know the last trade
+        restored
+            .restore_from_snapshot(book.create_snapshot(usize::MAX))
+            .unwrap();
+        assert_eq!(restored.last_trade_price(), None);
+    }
+
+    #[test]
+    fn test_trailing_stop_round_trips_and_fires() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        trade_at(&book, 100);
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Ioc);
+        book.add_trailing_stop(stop, TrailAmount::Offset(5))
+            .unwrap();
+        trade_at(&book, 110);
+
+        let restored = round_trip(&book);
+        assert_eq!(
+            restored.pending_trailing_stops(),
+            book.pending_trailing_stops()
+        );
+        assert_eq!(restored.trailing_stop_trigger(stop.id()), Some(105));
+
+        // The restored stop keeps trailing, then fires into the bid
+        let bid = standard(104, 5, Side::Buy, TimeInForce::Gtc);
+        restored.add_order(bid).unwrap();
+        trade_at(&restored, 105);
+        assert!(restored.pending_trailing_stops().is_empty());
+        assert!(restored.get_order(bid.id()).is_none());
+        let triggered = restored.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, stop.id());
+    }
+
+    #[test]
+    fn test_contingent_order_round_trips_and_triggers() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let entry = standard(100, 5, Side::Sell, TimeInForce::Gtc);
+        book.add_order(entry).unwrap();
+        let hedge = standard(90, 5, Side::Buy, TimeInForce::Gtc);
+        book.add_contingent(entry.id(), hedge).unwrap();
+
+        let restored = round_trip(&book);
+        assert_eq!(restored.contingent_orders(entry.id()), vec![hedge]);
+
+        restored
+            .submit_market_order(Id::new_uuid(), 5, Side::Buy)
+            .unwrap();
+        let triggered = restored.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, entry.id());
+        assert!(restored.get_order(hedge.id()).is_some());
+    }
+
+    #[test]
+    fn test_contingent_of_missing_trigger_is_dropped() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let near = standard(100, 5, Side::Sell, TimeInForce::Gtc);
+        let far = standard(200, 5, Side::Sell, TimeInForce::Gtc);
+        book.add_order(near).unwrap();
+        book.add_order(far).unwrap();
+        let hedge = standard(90, 5, Side::Buy, TimeInForce::Gtc);
+        book.add_contingent(near.id(), hedge).unwrap();
+        book.add_contingent(far.id(), hedge).unwrap();
+
+        let mut restored = OrderBook::new("TEST");
+        restored
+            .restore_from_snapshot_package(book.create_snapshot_package(1).unwrap())
+            .unwrap();
+        assert_eq!(restored.contingent_orders(near.id()), vec![hedge]);
+        assert!(restored.contingent_orders(far.id()).is_empty());
+    }
+
+    #[test]
+    fn test_parked_market_order_round_trips_and_executes() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_market_order_empty_book_policy(MarketOrderEmptyBookPolicy::ParkAsPending);
+        let parked = Id::new_uuid();
+        book.submit_market_order(parked, 5, Side::Buy).unwrap();
+
+        let restored = round_trip(&book);
+        assert_eq!(restored.parked_market_orders(), book.parked_market_orders());
+
+        let ask = standard(100, 5, Side::Sell, TimeInForce::Gtc);
+        restored.add_order(ask).unwrap();
+        assert!(restored.parked_market_orders().is_empty());
+        assert!(restored.get_order(ask.id()).is_none());
+    }
+
+    #[test]
+    fn test_plain_restore_drops_off_book_orders() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        book.set_market_order_empty_book_policy(MarketOrderEmptyBookPolicy::ParkAsPending);
+        trade_at(&book, 100);
+        book.submit_market_order(Id::new_uuid(), 5, Side::Sell)
+            .unwrap();
+        book.add_trailing_stop(
+            standard(1, 5, Side::Sell, TimeInForce::Ioc),
+            TrailAmount::Offset(5),
+        )
+        .unwrap();
+        let entry = standard(100, 5, Side::Sell, TimeInForce::Gtc);
+        book.add_order(entry).unwrap();
+        book.add_contingent(entry.id(), standard(90, 5, Side::Buy, TimeInForce::Gtc))
+            .unwrap();
+
+        book.restore_from_snapshot(book.create_snapshot(usize::MAX))
+            .unwrap();
+        assert!(book.get_order(entry.id()).is_some());
+        assert!(book.pending_trailing_stops().is_empty());
+        assert!(book.contingent_orders(entry.id()).is_empty());
+        assert!(book.parked_market_orders().is_empty());
+    }
+
+    #[test]
+    fn test_session_totals_follow_restore_scope() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        trade_at(&book, 100);
+        trade_at(&book, 110);
+        let package = book.create_snapshot_package(usize::MAX).unwrap();
+        assert_eq!((package.session_volume, package.session_turnover), (2, 210));
+
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored
+            .restore_from_snapshot_package_with(package.clone(), RestoreScope::OrdersOnly)
+            .unwrap();
+        assert_eq!(restored.session_volume(), 0);
+        restored.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(
+            (restored.session_volume(), restored.session_turnover()),
+            (2, 210)
+        );
+    }
+
+    #[test]
+    fn test_delta_carries_off_book_state() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        let base = book.create_snapshot_package(usize::MAX).unwrap();
+        trade_at(&book, 100);
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Ioc);
+        book.add_trailing_stop(stop, TrailAmount::Offset(5))
+            .unwrap();
+        let target = book.create_snapshot_package(usize::MAX).unwrap();
+
+        let delta = target.delta_since(&base).unwrap();
+        let applied: OrderBookSnapshotPackage = base.apply_delta(&delta).unwrap();
+        assert_eq!(applied.last_trade_price, Some(100));
+        assert_eq!(applied.session_volume, 1);
+
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        restored.restore_from_snapshot_package(applied).unwrap();
+        assert_eq!(restored.trailing_stop_trigger(stop.id()), Some(95));
+    }
+}
@@ -326,3 +326,137 @@ mod tests {
         assert!(distribution.last().unwrap().max_price > 100);
     }
 }
+
+#[cfg(test)]
+mod quantity_overflow_tests {
+    use crate::{OrderBook, OrderBookError};
+    use pricelevel::{Id, Side, TimeInForce};
+
+    const HALF: u64 = u64::MAX / 2 + 1;
+
+    /// Two bid levels whose combined quantity exceeds `u64::MAX`, plus a
+    /// small ask side.
+    fn overflowing_book() -> OrderBook<()> {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for price in [100, 99] {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                HALF,
+                Side::Buy,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+        book.add_limit_order(Id::new_uuid(), 101, 5, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_exact_totals_report_overflow() {
+        let book = overflowing_book();
+        assert!(matches!(
+            book.checked_total_quantity(Side::Buy),
+            Err(OrderBookError::QuantityOverflow { side: Side::Buy })
+        ));
+        assert_eq!(book.checked_total_quantity(Side::Sell).unwrap(), 5);
+
+        let snapshot = book.create_snapshot(usize::MAX);
+        assert!(matches!(
+            snapshot.checked_total_volume(Side::Buy),
+            Err(OrderBookError::QuantityOverflow { side: Side::Buy })
+        ));
+        assert_eq!(snapshot.checked_total_volume(Side::Sell).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_display_totals_saturate() {
+        let book = overflowing_book();
+        let (buy_pressure, sell_pressure) = book.buy_sell_pressure();
+        assert_eq!(buy_pressure, u64::MAX);
+        assert_eq!(sell_pressure, 5);
+        assert_eq!(book.total_depth_at_levels(10, Side::Buy), u64::MAX);
+
+        let snapshot = book.create_snapshot(usize::MAX);
+        assert_eq!(snapshot.total_bid_volume(), u64::MAX);
+        assert_eq!(snapshot.total_ask_volume(), 5);
+        // u128 notional does not overflow here
+        assert_eq!(snapshot.total_bid_value(), 199 * u128::from(HALF));
+    }
+
+    #[test]
+    fn test_exact_total_just_below_limit() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.add_limit_order(
+            Id::new_uuid(),
+            100,
+            u64::MAX - 1,
+            Side::Buy,
+            TimeInForce::Gtc,
+            None,
+        )
+        .unwrap();
+        book.add_limit_order(Id::new_uuid(), 99, 1, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+
+        assert_eq!(book.checked_total_quantity(Side::Buy).unwrap(), u64::MAX);
+        assert_eq!(book.buy_sell_pressure().0, u64::MAX);
+        assert_eq!(
+            book.create_snapshot(usize::MAX)
+                .checked_total_volume(Side::Buy)
+                .unwrap(),
+            u64::MAX
+        );
+    }
+}
+
+#[cfg(test)]
+mod order_age_tests {
+    use crate::OrderBook;
+    use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn rest_at(book: &OrderBook<()>, side: Side, price: u128, timestamp: u64) {
+        book.add_order(OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(10),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_order_age_histogram_buckets() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let now = 100_000;
+        // Ages 0, 500, 1_000, 1_500, 10_000 and 90_000 across both sides
+        rest_at(&book, Side::Buy, 100, now);
+        rest_at(&book, Side::Buy, 100, now - 500);
+        rest_at(&book, Side::Buy, 99, now - 1_000);
+        rest_at(&book, Side::Sell, 101, now - 1_500);
+        rest_at(&book, Side::Sell, 102, now - 10_000);
+        rest_at(&book, Side::Sell, 102, now - 90_000);
+
+        let buckets = [1_000, 10_000, 60_000];
+        // Ages on a bound count in the bucket above it
+        assert_eq!(book.order_age_histogram(now, &buckets), vec![2, 2, 1, 1]);
+        // Orders stamped after `now` count as age zero
+        assert_eq!(
+            book.order_age_histogram(now - 1_000, &buckets),
+            vec![4, 1, 0, 1]
+        );
+        assert_eq!(book.order_age_histogram(now, &[]), vec![6]);
+    }
+
+    #[test]
+    fn test_order_age_histogram_empty_book() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(book.order_age_histogram(1_000, &[10, 100]), vec![0, 0, 0]);
+    }
+}
@@ -4,7 +4,7 @@
 mod tests {
     use crate::orderbook::book::OrderBook;
     use crate::orderbook::error::OrderBookError;
-    use crate::orderbook::stp::STPMode;
+    use crate::orderbook::stp::{STPMakerScope, STPMode};
     use pricelevel::{Hash32, Id, OrderType, Price, Quantity, Side, TimeInForce, TimestampMs};
 
     /// Helper: create a non-zero user hash from a single byte value.
@@ -247,6 +247,71 @@ mod tests {
         assert!(book.get_order(maker1).is_none());
     }
 
+    /// A level at 100 holding, in time priority, 5 from user 1, 5 from user
+    /// 2, 5 from user 1 and 5 from user 2.
+    fn interleaved_level(scope: STPMakerScope) -> (OrderBook<()>, [Id; 4]) {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_stp_mode(STPMode::CancelMaker);
+        book.set_stp_maker_scope(scope);
+        let ids = [(); 4].map(|()| Id::new());
+        for (timestamp, (&id, owner)) in ids.iter().zip([1u8, 2, 1, 2]).enumerate() {
+            book.add_order(OrderType::Standard {
+                id,
+                price: Price::new(100),
+                quantity: Quantity::new(5),
+                side: Side::Sell,
+                user_id: user(owner),
+                timestamp: TimestampMs::new(timestamp as u64 + 1),
+                time_in_force: TimeInForce::Gtc,
+                extra_fields: (),
+            })
+            .unwrap();
+        }
+        (book, ids)
+    }
+
+    #[test]
+    fn test_cancel_maker_all_at_level_cancels_every_own_maker() {
+        let (book, [own_first, other_first, own_second, other_second]) =
+            interleaved_level(STPMakerScope::default());
+        assert_eq!(book.stp_maker_scope(), STPMakerScope::AllAtLevel);
+
+        let mr = book
+            .match_market_order_with_user(Id::new(), 5, Side::Buy, user(1))
+            .unwrap();
+        assert_eq!(mr.filled_order_ids(), &[other_first]);
+        // Both of user 1's makers are gone, though the taker never reached
+        // the second one
+        assert!(book.get_order(own_first).is_none());
+        assert!(book.get_order(own_second).is_none());
+        assert!(book.get_order(other_second).is_some());
+        assert_eq!(book.user_resting_quantity(user(1), Side::Sell), 0);
+    }
+
+    #[test]
+    fn test_cancel_maker_hit_only_cancels_reached_makers() {
+        let (book, [own_first, other_first, own_second, other_second]) =
+            interleaved_level(STPMakerScope::HitOnly);
+
+        let mr = book
+            .match_market_order_with_user(Id::new(), 5, Side::Buy, user(1))
+            .unwrap();
+        assert_eq!(mr.filled_order_ids(), &[other_first]);
+        // Only the maker ahead of the fill is cancelled
+        assert!(book.get_order(own_first).is_none());
+        assert!(book.get_order(own_second).is_some());
+        assert_eq!(book.user_resting_quantity(user(1), Side::Sell), 5);
+        assert!(book.get_order(other_second).is_some());
+
+        // A taker reaching past the second own maker cancels it as well
+        let mr = book
+            .match_market_order_with_user(Id::new(), 3, Side::Buy, user(1))
+            .unwrap();
+        assert_eq!(mr.executed_quantity().unwrap(), 3);
+        assert!(book.get_order(own_second).is_none());
+        assert_eq!(book.get_order(other_second).unwrap().visible_quantity(), 2);
+    }
+
     // -----------------------------------------------------------------------
     // STPMode::CancelBoth
     // -----------------------------------------------------------------------
@@ -814,4 +879,126 @@ mod tests {
         assert!(msg.contains("missing user_id"));
         assert!(msg.contains("STP"));
     }
+
+    // -----------------------------------------------------------------------
+    // STP outcome reporting
+    // -----------------------------------------------------------------------
+
+    /// Helper: book with a same-user ask at 100 (qty 5) behind another
+    /// user's ask at 99 (qty 5), and the result of the same user buying 8 at
+    /// 100 under `mode`.
+    fn buy_into_own_ask(
+        mode: STPMode,
+    ) -> (
+        OrderBook<()>,
+        Id,
+        Result<crate::orderbook::AddOrderResult<()>, OrderBookError>,
+    ) {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_stp_mode(mode);
+        add_sell_order_with_user(&book, 99, 5, user(2));
+        let own_ask = add_sell_order_with_user(&book, 100, 5, user(1));
+        let order = OrderType::Standard {
+            id: Id::new(),
+            price: Price::new(100),
+            quantity: Quantity::new(8),
+            side: Side::Buy,
+            user_id: user(1),
+            timestamp: TimestampMs::new(crate::utils::current_time_millis()),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        };
+        let result = book.add_order_with_remainder(order, Default::default());
+        (book, own_ask, result)
+    }
+
+    #[test]
+    fn test_stp_outcome_flags_per_mode() {
+        use crate::orderbook::RemainderOutcome;
+        use crate::orderbook::stp::STPTriggered;
+
+        let (_, _, result) = buy_into_own_ask(STPMode::None);
+        assert_eq!(result.unwrap().stp, None);
+
+        // CancelTaker: the fill at 99 is kept, the remainder does not rest
+        let (book, own_ask, result) = buy_into_own_ask(STPMode::CancelTaker);
+        let added = result.unwrap();
+        assert_eq!(
+            added.stp,
+            Some(STPTriggered {
+                mode: STPMode::CancelTaker,
+                cancelled_taker: true,
+                cancelled_maker: false,
+            })
+        );
+        assert_eq!(added.remainder, RemainderOutcome::Cancelled { quantity: 3 });
+        assert!(book.get_order(own_ask).is_some());
+        assert_eq!(book.best_bid(), None);
+
+        // CancelMaker: the own ask is removed and the remainder rests
+        let (book, own_ask, result) = buy_into_own_ask(STPMode::CancelMaker);
+        let added = result.unwrap();
+        assert_eq!(
+            added.stp,
+            Some(STPTriggered {
+                mode: STPMode::CancelMaker,
+                cancelled_taker: false,
+                cancelled_maker: true,
+            })
+        );
+        assert_eq!(added.remainder, RemainderOutcome::Rested { quantity: 3 });
+        assert!(book.get_order(own_ask).is_none());
+
+        // CancelBoth: the own ask and the remainder are both cancelled
+        let (book, own_ask, result) = buy_into_own_ask(STPMode::CancelBoth);
+        let added = result.unwrap();
+        assert_eq!(
+            added.stp,
+            Some(STPTriggered {
+                mode: STPMode::CancelBoth,
+                cancelled_taker: true,
+                cancelled_maker: true,
+            })
+        );
+        assert_eq!(added.remainder, RemainderOutcome::Cancelled { quantity: 3 });
+        assert!(book.get_order(own_ask).is_none());
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn test_stp_outcome_reported_on_trade_result() {
+        use crate::orderbook::trade::TradeResult;
+        use std::sync::{Arc, Mutex};
+
+        let seen: Arc<Mutex<Vec<TradeResult>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let mut book: OrderBook<()> = OrderBook::with_trade_listener(
+            "TEST",
+            Arc::new(move |trade: &TradeResult| {
+                if let Ok(mut seen) = sink.lock() {
+                    seen.push(trade.clone());
+                }
+            }),
+        );
+        book.set_stp_mode(STPMode::CancelMaker);
+        add_sell_order_with_user(&book, 100, 5, user(1));
+        add_sell_order_with_user(&book, 101, 5, user(2));
+        book.match_market_order_with_user(Id::new(), 5, Side::Buy, user(1))
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        let stp = seen[0].stp.unwrap();
+        assert!(stp.cancelled_maker && !stp.cancelled_taker);
+    }
+
+    #[test]
+    fn test_stp_rejection_outcome_from_mode() {
+        use crate::orderbook::stp::STPTriggered;
+
+        let outcome = STPTriggered::rejected(STPMode::CancelTaker);
+        assert!(outcome.cancelled_taker && !outcome.cancelled_maker);
+        let outcome = STPTriggered::rejected(STPMode::CancelBoth);
+        assert!(outcome.cancelled_taker && outcome.cancelled_maker);
+    }
 }
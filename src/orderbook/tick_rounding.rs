@@ -0,0 +1,256 @@
+//! Rounding of off-tick prices to the configured tick size.
+//!
+//! By default an order whose price is not a multiple of the tick size is
+//! rejected with [`OrderBookError::InvalidTickSize`]. With a
+//! [`TickRounding`] configured through [`OrderBook::set_tick_rounding`],
+//! the price is rounded to a valid tick on entry instead. The direction is
+//! chosen per side; the default rounds buys down and sells up, so an order
+//! never ends up priced more aggressively than the client asked for.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use pricelevel::{OrderType, Side};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// Direction in which a price is rounded to a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Round down to the highest tick at or below the price (default).
+    #[default]
+    Down,
+    /// Round up to the lowest tick at or above the price.
+    Up,
+    /// Round to the closest tick; a price halfway between two ticks rounds
+    /// up.
+    Nearest,
+}
+
+impl std::fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoundingMode::Down => write!(f, "Down"),
+            RoundingMode::Up => write!(f, "Up"),
+            RoundingMode::Nearest => write!(f, "Nearest"),
+        }
+    }
+}
+
+/// Per-side rounding applied to off-tick prices on order entry.
+///
+/// The default is conservative: buys round down and sells round up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TickRounding {
+    /// Rounding applied to buy prices.
+    pub buy: RoundingMode,
+    /// Rounding applied to sell prices.
+    pub sell: RoundingMode,
+}
+
+impl TickRounding {
+    /// Returns the rounding applied to prices on `side`.
+    #[must_use]
+    pub fn mode_for(&self, side: Side) -> RoundingMode {
+        match side {
+            Side::Buy => self.buy,
+            Side::Sell => self.sell,
+        }
+    }
+}
+
+impl Default for TickRounding {
+    fn default() -> Self {
+        Self {
+            buy: RoundingMode::Down,
+            sell: RoundingMode::Up,
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Round `price` to a multiple of the tick size of its price band.
+    ///
+    /// Returns `price` unchanged when no tick size is configured. If
+    /// rounding up would overflow, the price is rounded down instead. A
+    /// price that rounds into a neighbouring band of a tick schedule is
+    /// rounded again with that band's tick.
+    #[must_use]
+    pub fn round_to_tick(&self, price: u128, mode: RoundingMode) -> u128 {
+        let mut price = price;
+        for _ in 0..self.tick_schedule.len() {
+            let rounded = self.round_within_band(price, mode);
+            if rounded == price {
+                break;
+            }
+            price = rounded;
+        }
+        price
+    }
+
+    /// Round `price` to a multiple of the tick at `price`.
+    fn round_within_band(&self, price: u128, mode: RoundingMode) -> u128 {
+        let Some(tick) = self.tick_size_at(price) else {
+            return price;
+        };
+        let remainder = price % tick;
+        let down = price - remainder;
+        if remainder == 0 {
+            return price;
+        }
+        let round_up = match mode {
+            RoundingMode::Down => false,
+            RoundingMode::Up => true,
+            RoundingMode::Nearest => remainder >= tick - remainder,
+        };
+        if round_up {
+            down.checked_add(tick).unwrap_or(down)
+        } else {
+            down
+        }
+    }
+
+    /// Set how off-tick prices are handled on entry: rounded per side, or
+    /// rejected with [`OrderBookError::InvalidTickSize`] when `None`
+    /// (default).
+    pub fn set_tick_rounding(&mut self, rounding: Option<TickRounding>) {
+        self.tick_rounding = rounding;
+        trace!(
+            "Order book {}: Set tick rounding to {:?}",
+            self.symbol, rounding
+        );
+    }
+
+    /// Returns the rounding applied to off-tick prices on entry, if any.
+    #[must_use]
+    pub fn tick_rounding(&self) -> Option<TickRounding> {
+        self.tick_rounding
+    }
+
+    /// Round the price of an incoming order to a valid tick in place when
+    /// tick rounding is configured.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidTickSize`] when the price would
+    /// round to zero.
+    pub(super) fn apply_tick_rounding(
+        &self,
+        order: &mut OrderType<T>,
+    ) -> Result<(), OrderBookError> {
+        let Some(rounding) = self.tick_rounding else {
+            return Ok(());
+        };
+        let Some(tick_size) = self.tick_size_at(order.price().as_u128()) else {
+            return Ok(());
+        };
+        let price = order.price().as_u128();
+        if price.is_multiple_of(tick_size) {
+            return Ok(());
+        }
+
+        let rounded = self.round_to_tick(price, rounding.mode_for(order.side()));
+        if rounded == 0 {
+            return self.reject(
+                order.id(),
+                OrderBookError::InvalidTickSize { price, tick_size },
+            );
+        }
+
+        trace!(
+            "Order book {}: Rounding order {} price {} to tick {}",
+            self.symbol,
+            order.id(),
+            price,
+            rounded
+        );
+        Self::set_order_price(order, rounded);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn book(rounding: Option<TickRounding>) -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_tick_size(100);
+        book.set_tick_rounding(rounding);
+        book
+    }
+
+    #[test]
+    fn test_round_to_tick_modes() {
+        let book = book(None);
+        assert_eq!(book.round_to_tick(150, RoundingMode::Down), 100);
+        assert_eq!(book.round_to_tick(150, RoundingMode::Up), 200);
+        assert_eq!(book.round_to_tick(150, RoundingMode::Nearest), 200);
+        assert_eq!(book.round_to_tick(149, RoundingMode::Nearest), 100);
+        assert_eq!(book.round_to_tick(200, RoundingMode::Up), 200);
+
+        let untick: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(untick.round_to_tick(150, RoundingMode::Up), 150);
+    }
+
+    #[test]
+    fn test_default_rounding_is_conservative_per_side() {
+        let book = book(Some(TickRounding::default()));
+
+        let buy = book
+            .add_limit_order(Id::new_uuid(), 150, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(buy.price().as_u128(), 100);
+
+        let sell = book
+            .add_limit_order(Id::new_uuid(), 150, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(sell.price().as_u128(), 200);
+
+        assert_eq!(book.best_bid(), Some(100));
+        assert_eq!(book.best_ask(), Some(200));
+    }
+
+    #[test]
+    fn test_explicit_rounding_per_side() {
+        let book = book(Some(TickRounding {
+            buy: RoundingMode::Up,
+            sell: RoundingMode::Down,
+        }));
+
+        let buy = book
+            .add_limit_order(Id::new_uuid(), 150, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(buy.price().as_u128(), 200);
+
+        let sell = book
+            .add_limit_order(Id::new_uuid(), 350, 10, Side::Sell, TimeInForce::Gtc, None)
+            .unwrap();
+        assert_eq!(sell.price().as_u128(), 300);
+    }
+
+    #[test]
+    fn test_off_tick_rejected_without_rounding() {
+        let book = book(None);
+        let err = book
+            .add_limit_order(Id::new_uuid(), 150, 10, Side::Buy, TimeInForce::Gtc, None)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OrderBookError::InvalidTickSize {
+                price: 150,
+                tick_size: 100
+            }
+        ));
+
+        // Rounding a buy down to zero is still rejected
+        let rounding = self::book(Some(TickRounding::default()));
+        assert!(
+            rounding
+                .add_limit_order(Id::new_uuid(), 50, 10, Side::Buy, TimeInForce::Gtc, None)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Tick sizes that vary by price band.
+//!
+//! Venues often quote higher prices in coarser increments. A tick schedule
+//! is a list of `(price_threshold, tick_size)` bands: the tick of a price is
+//! the tick of the band with the highest threshold at or below it, so a
+//! price exactly at a threshold already uses the coarser tick. Prices below
+//! the lowest threshold use the lowest band. [`OrderBook::set_tick_size`]
+//! configures a schedule of a single band, and every tick check on entry
+//! (validation, rounding, minimum spread and external BBO repricing) looks
+//! up the tick at the price in question.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set the tick size per price band as `(price_threshold, tick_size)`
+    /// pairs, in any order. An empty schedule disables tick validation.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`], leaving the schedule
+    /// unchanged, if a tick size is zero or two bands share a threshold.
+    pub fn set_tick_schedule(
+        &mut self,
+        mut schedule: Vec<(u128, u128)>,
+    ) -> Result<(), OrderBookError> {
+        schedule.sort_unstable_by_key(|&(threshold, _)| threshold);
+        if let Some(&(threshold, _)) = schedule.iter().find(|&&(_, tick)| tick == 0) {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!("tick size of the band at {threshold} is zero"),
+            });
+        }
+        if let Some(pair) = schedule.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!("tick schedule has two bands at {}", pair[0].0),
+            });
+        }
+        trace!(
+            "Order book {}: Setting tick schedule to {:?}",
+            self.symbol, schedule
+        );
+        self.tick_schedule = schedule;
+        Ok(())
+    }
+
+    /// Returns the tick schedule as `(price_threshold, tick_size)` pairs in
+    /// ascending threshold order; empty if tick validation is disabled.
+    #[must_use]
+    pub fn tick_schedule(&self) -> &[(u128, u128)] {
+        &self.tick_schedule
+    }
+
+    /// Returns the tick size that applies to `price`, if any.
+    #[must_use]
+    pub fn tick_size_at(&self, price: u128) -> Option<u128> {
+        let bands_at_or_below = self
+            .tick_schedule
+            .partition_point(|&(threshold, _)| threshold <= price);
+        self.tick_schedule
+            .get(bands_at_or_below.saturating_sub(1))
+            .map(|&(_, tick)| tick)
+            .filter(|&tick| tick > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::RoundingMode;
+    use pricelevel::{Id, Side, TimeInForce};
+
+    /// Ticks of 1 below 100 and of 5 from 100 up.
+    fn book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_tick_schedule(vec![(100, 5), (0, 1)]).unwrap();
+        book
+    }
+
+    fn add(book: &OrderBook<()>, price: u128) -> Result<(), OrderBookError> {
+        book.add_limit_order(Id::new_uuid(), price, 1, Side::Buy, TimeInForce::Gtc, None)
+            .map(|_| ())
+    }
+
+    #[test]
+    fn test_two_tier_schedule_validates_by_band() {
+        let book = book();
+        assert_eq!(book.tick_schedule(), &[(0, 1), (100, 5)]);
+        assert_eq!(book.tick_size_at(99), Some(1));
+        assert_eq!(book.tick_size_at(100), Some(5));
+
+        // Fine ticks below the threshold
+        assert!(add(&book, 97).is_ok());
+        assert!(add(&book, 99).is_ok());
+        // The threshold itself uses the coarse tick
+        assert!(add(&book, 100).is_ok());
+        assert!(add(&book, 105).is_ok());
+        for price in [101, 104, 106] {
+            assert!(matches!(
+                add(&book, price),
+                Err(OrderBookError::InvalidTickSize { tick_size: 5, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_single_tick_is_a_one_band_schedule() {
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        assert_eq!(book.tick_size_at(7), None);
+        book.set_tick_size(5);
+        assert_eq!(book.tick_schedule(), &[(0, 5)]);
+        assert_eq!(book.tick_size(), Some(5));
+        assert_eq!(book.tick_size_at(1_000_000), Some(5));
+
+        // A lowest band above zero also covers the prices below it
+        book.set_tick_schedule(vec![(50, 10)]).unwrap();
+        assert_eq!(book.tick_size_at(20), Some(10));
+        assert!(add(&book, 25).is_err());
+
+        assert!(matches!(
+            book.set_tick_schedule(vec![(0, 1), (100, 0)]),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+        assert!(matches!(
+            book.set_tick_schedule(vec![(100, 1), (100, 5)]),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+        assert_eq!(book.tick_schedule(), &[(50, 10)]);
+        book.set_tick_schedule(Vec::new()).unwrap();
+        assert_eq!(book.tick_size(), None);
+    }
+
+    #[test]
+    fn test_rounding_respects_band_of_rounded_price() {
+        let mut book = book();
+        assert_eq!(book.round_to_tick(102, RoundingMode::Up), 105);
+        assert_eq!(book.round_to_tick(101, RoundingMode::Down), 100);
+        assert_eq!(book.round_to_tick(98, RoundingMode::Up), 98);
+
+        book.set_tick_schedule(vec![(0, 3), (102, 5)]).unwrap();
+        // 103 rounds down to 100 in the coarse band, then to 99 in the fine one
+        assert_eq!(book.round_to_tick(103, RoundingMode::Down), 99);
+    }
+
+    #[test]
+    fn test_schedule_survives_snapshot_restore() {
+        let book = book();
+        let json = book
+            .create_snapshot_package(usize::MAX)
+            .unwrap()
+            .to_json()
+            .unwrap();
+        let mut restored: OrderBook<()> = OrderBook::new("TEST");
+        restored.restore_from_snapshot_json(&json).unwrap();
+        assert_eq!(restored.tick_schedule(), &[(0, 1), (100, 5)]);
+
+        // Packages without a schedule restore their tick size as one band
+        let mut package = OrderBook::<()>::with_tick_size("TEST", 5)
+            .create_snapshot_package(usize::MAX)
+            .unwrap();
+        package.tick_schedule.clear();
+        package.update_chain_hash().unwrap();
+        restored.restore_from_snapshot_package(package).unwrap();
+        assert_eq!(restored.tick_schedule(), &[(0, 5)]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Handling of out-of-band client timestamps on order entry.
+//!
+//! Orders carry the timestamp their client assigned. Matching within a price
+//! level always follows arrival order, so a skewed client clock does not let
+//! an order jump the queue, but the timestamp is kept on the resting order
+//! and order listings such as [`OrderBook::get_orders_at_price`] sort by it.
+//! [`TimestampPolicy`] controls what the book accepts: timestamps as given
+//! (default), future timestamps clamped to the book's clock, or rejection of
+//! any order timestamped before the last accepted one.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use crate::utils::current_time_millis;
+use pricelevel::OrderType;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use tracing::trace;
+
+/// How the book treats the client timestamp of an incoming order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum TimestampPolicy {
+    /// Keep the client timestamp unchanged (default).
+    #[default]
+    TrustClient,
+    /// Replace a timestamp later than the book's clock with the current
+    /// time.
+    ClampToNow,
+    /// Reject an order timestamped before the last accepted order with
+    /// [`OrderBookError::TimestampOutOfOrder`].
+    RejectOutOfOrder,
+}
+
+impl std::fmt::Display for TimestampPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampPolicy::TrustClient => write!(f, "TrustClient"),
+            TimestampPolicy::ClampToNow => write!(f, "ClampToNow"),
+            TimestampPolicy::RejectOutOfOrder => write!(f, "RejectOutOfOrder"),
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Set how client timestamps are treated on order entry.
+    pub fn set_timestamp_policy(&mut self, policy: TimestampPolicy) {
+        trace!(
+            "Order book {}: Setting timestamp policy to {}",
+            self.symbol, policy
+        );
+        self.timestamp_policy = policy;
+    }
+
+    /// Returns how client timestamps are treated on order entry.
+    #[must_use]
+    pub fn timestamp_policy(&self) -> TimestampPolicy {
+        self.timestamp_policy
+    }
+
+    /// Returns the timestamp of the last order accepted for matching, in
+    /// milliseconds, or `0` if none has been accepted yet.
+    #[must_use]
+    pub fn last_order_timestamp(&self) -> u64 {
+        self.last_order_timestamp.load(Ordering::Acquire)
+    }
+
+    /// Apply the timestamp policy to an incoming order in place.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::TimestampOutOfOrder`] under
+    /// [`TimestampPolicy::RejectOutOfOrder`] when the order is timestamped
+    /// before the last accepted order.
+    pub(super) fn apply_timestamp_policy(
+        &self,
+        order: &mut OrderType<T>,
+    ) -> Result<(), OrderBookError> {
+        let timestamp = order.timestamp();
+        match self.timestamp_policy {
+            TimestampPolicy::TrustClient => Ok(()),
+            TimestampPolicy::ClampToNow => {
+                let now = current_time_millis();
+                if timestamp > now {
+                    trace!(
+                        "Order book {}: Clamping order {} timestamp {} to {}",
+                        self.symbol,
+                        order.id(),
+                        timestamp,
+                        now
+                    );
+                    Self::set_order_timestamp(order, now);
+                }
+                Ok(())
+            }
+            TimestampPolicy::RejectOutOfOrder => {
+                let last_timestamp = self.last_order_timestamp();
+                if timestamp >= last_timestamp {
+                    return Ok(());
+                }
+                self.reject(
+                    order.id(),
+                    OrderBookError::TimestampOutOfOrder {
+                        order_id: order.id(),
+                        timestamp,
+                        last_timestamp,
+                    },
+                )
+            }
+        }
+    }
+
+    /// Record the timestamp of an order accepted for matching.
+    pub(super) fn record_order_timestamp(&self, order: &OrderType<T>) {
+        self.last_order_timestamp
+            .fetch_max(order.timestamp(), Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Id, Price, Quantity, Side, TimeInForce, TimestampMs};
+
+    fn bid_at(timestamp: u64) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(100),
+            quantity: Quantity::new(10),
+            side: Side::Buy,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(timestamp),
+            time_in_force: TimeInForce::Gtc,
+            extra_fields: (),
+        }
+    }
+
+    fn book(policy: TimestampPolicy) -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_timestamp_policy(policy);
+        book
+    }
+
+    /// Sell into the bids one order's worth and return the order that filled.
+    fn first_filled(book: &OrderBook<()>) -> Id {
+        let result = book
+            .submit_market_order(Id::new_uuid(), 10, Side::Sell)
+            .unwrap();
+        *result.filled_order_ids().first().unwrap()
+    }
+
+    #[test]
+    fn test_trust_client_keeps_arrival_priority() {
+        let book = book(TimestampPolicy::default());
+        assert_eq!(book.timestamp_policy(), TimestampPolicy::TrustClient);
+        let first = bid_at(2_000);
+        let late = bid_at(1_000);
+        book.add_order(first).unwrap();
+        book.add_order(late).unwrap();
+
+        // The earlier timestamp is kept but does not jump the queue
+        assert_eq!(book.get_order(late.id()).unwrap().timestamp(), 1_000);
+        assert_eq!(book.last_order_timestamp(), 2_000);
+        assert_eq!(first_filled(&book), first.id());
+    }
+
+    #[test]
+    fn test_clamp_to_now_replaces_future_timestamps() {
+        let book = book(TimestampPolicy::ClampToNow);
+        let past = bid_at(1_000);
+        let future = bid_at(u64::MAX);
+        book.add_order(future).unwrap();
+        book.add_order(past).unwrap();
+
+        let clamped = book.get_order(future.id()).unwrap().timestamp();
+        assert!(clamped > 1_000 && clamped <= current_time_millis());
+        assert_eq!(book.get_order(past.id()).unwrap().timestamp(), 1_000);
+        assert_eq!(first_filled(&book), future.id());
+    }
+
+    #[test]
+    fn test_reject_out_of_order_keeps_queue_front() {
+        let book = book(TimestampPolicy::RejectOutOfOrder);
+        let first = bid_at(2_000);
+        book.add_order(first).unwrap();
+
+        let late = bid_at(1_999);
+        assert!(matches!(
+            book.add_order(late),
+            Err(OrderBookError::TimestampOutOfOrder {
+                timestamp: 1_999,
+                last_timestamp: 2_000,
+                ..
+            })
+        ));
+        assert!(book.get_order(late.id()).is_none());
+
+        // An equal timestamp is in order and queues behind the first order
+        let tied = bid_at(2_000);
+        book.add_order(tied).unwrap();
+        assert_eq!(first_filled(&book), first.id());
+        assert_eq!(first_filled(&book), tied.id());
+    }
+}
@@ -4,7 +4,9 @@
    Date: 2/10/25
 ******************************************************************************/
 use crate::orderbook::fees::FeeSchedule;
-use pricelevel::MatchResult;
+use crate::orderbook::stp::STPTriggered;
+use crate::orderbook::tick_rounding::RoundingMode;
+use pricelevel::{Id, MatchResult};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -23,6 +25,140 @@ pub struct TradeResult {
     /// unit as the notional (price × quantity). Zero when no `FeeSchedule`
     /// is configured.
     pub total_taker_fees: i128,
+    /// Aggregate totals of the match, computed once at construction.
+    #[serde(default)]
+    pub summary: TradeSummary,
+    /// What self-trade prevention did while matching, if it triggered.
+    #[serde(default)]
+    pub stp: Option<STPTriggered>,
+}
+
+/// Aggregate totals of a [`TradeResult`].
+///
+/// Use [`TradeResult::summary`] when only totals are needed, e.g. for
+/// high fan-out publishing, and [`TradeResult::fills`] for per-maker detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TradeSummary {
+    /// Number of individual fills.
+    pub fill_count: usize,
+    /// Total quantity executed across all fills.
+    pub executed_quantity: u64,
+    /// Total notional (price × quantity) executed across all fills.
+    pub executed_value: u128,
+    /// Quantity of the taker order left unfilled.
+    pub remaining_quantity: u64,
+    /// Number of distinct price levels the taker traded at.
+    #[serde(default)]
+    pub levels_touched: usize,
+}
+
+impl TradeSummary {
+    /// Compute the totals of `match_result`. Sums saturate on overflow.
+    #[must_use]
+    pub fn from_match_result(match_result: &MatchResult) -> Self {
+        let trades = match_result.trades().as_vec();
+        let (executed_quantity, executed_value) =
+            trades
+                .iter()
+                .fold((0u64, 0u128), |(quantity, value), trade| {
+                    let fill_quantity = trade.quantity().as_u64();
+                    (
+                        quantity.saturating_add(fill_quantity),
+                        value.saturating_add(
+                            trade
+                                .price()
+                                .as_u128()
+                                .saturating_mul(u128::from(fill_quantity)),
+                        ),
+                    )
+                });
+
+        // Fills at one level are contiguous, so each price change starts a
+        // new level
+        let levels_touched = trades
+            .iter()
+            .enumerate()
+            .filter(|(i, trade)| *i == 0 || trades[i - 1].price() != trade.price())
+            .count();
+
+        Self {
+            fill_count: trades.len(),
+            executed_quantity,
+            executed_value,
+            remaining_quantity: match_result.remaining_quantity(),
+            levels_touched,
+        }
+    }
+
+    /// Returns the exact average execution price, or `None` if nothing
+    /// executed.
+    ///
+    /// Computed in integer arithmetic from `executed_value` and
+    /// `executed_quantity`, so it is deterministic across platforms and
+    /// replays. It is only exact while `executed_value` has not saturated.
+    #[must_use]
+    pub fn average_price(&self) -> Option<AveragePrice> {
+        let quantity = u128::from(self.executed_quantity);
+        if quantity == 0 {
+            return None;
+        }
+        Some(AveragePrice {
+            quotient: self.executed_value / quantity,
+            remainder: self.executed_value % quantity,
+            quantity: self.executed_quantity,
+        })
+    }
+}
+
+/// Exact average execution price of a trade, as the integer quotient and
+/// remainder of the executed value divided by the executed quantity.
+///
+/// The exact average is `quotient + remainder / quantity` price units, with
+/// `remainder < quantity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AveragePrice {
+    /// Whole price units of the average, rounded down.
+    pub quotient: u128,
+    /// Executed value left over after the division.
+    pub remainder: u128,
+    /// Executed quantity the value was divided by.
+    pub quantity: u64,
+}
+
+impl AveragePrice {
+    /// Returns `true` if the average is a whole number of price units.
+    #[must_use]
+    #[inline]
+    pub fn is_exact(&self) -> bool {
+        self.remainder == 0
+    }
+
+    /// Returns the average rounded to a whole price unit with `mode`;
+    /// [`RoundingMode::Nearest`] rounds a halfway average up.
+    #[must_use]
+    pub fn rounded(&self, mode: RoundingMode) -> u128 {
+        let round_up = match mode {
+            RoundingMode::Down => false,
+            RoundingMode::Up => self.remainder > 0,
+            RoundingMode::Nearest => self.remainder >= u128::from(self.quantity) - self.remainder,
+        };
+        if round_up {
+            self.quotient.saturating_add(1)
+        } else {
+            self.quotient
+        }
+    }
+}
+
+/// A single fill of the taker order against one maker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeFill {
+    /// Order ID of the maker (passive) side.
+    pub maker_order_id: Id,
+    /// Execution price.
+    pub price: u128,
+    /// Executed quantity.
+    pub quantity: u64,
 }
 
 impl TradeResult {
@@ -31,11 +167,14 @@ impl TradeResult {
     /// Use this constructor when no `FeeSchedule` is configured.
     /// Fees default to zero for backward compatibility.
     pub fn new(symbol: String, match_result: MatchResult) -> Self {
+        let summary = TradeSummary::from_match_result(&match_result);
         Self {
             symbol,
             match_result,
             total_maker_fees: 0,
             total_taker_fees: 0,
+            summary,
+            stp: None,
         }
     }
 
@@ -76,14 +215,24 @@ impl TradeResult {
             _ => (0, 0),
         };
 
+        let summary = TradeSummary::from_match_result(&match_result);
         Self {
             symbol,
             match_result,
             total_maker_fees,
             total_taker_fees,
+            summary,
+            stp: None,
         }
     }
 
+    /// Attach the self-trade prevention outcome of the match.
+    #[must_use]
+    pub fn with_stp(mut self, stp: Option<STPTriggered>) -> Self {
+        self.stp = stp;
+        self
+    }
+
     /// Returns the sum of all fees (maker + taker) for this trade
     ///
     /// A positive value means net fees charged; a negative value means
@@ -95,6 +244,41 @@ impl TradeResult {
             .checked_add(self.total_taker_fees)
             .unwrap_or(i128::MAX)
     }
+
+    /// Returns the aggregate totals without iterating the fills.
+    #[must_use]
+    #[inline]
+    pub fn summary(&self) -> TradeSummary {
+        self.summary
+    }
+
+    /// Returns the number of distinct price levels the taker traded at.
+    #[must_use]
+    #[inline]
+    pub fn levels_touched(&self) -> usize {
+        self.summary.levels_touched
+    }
+
+    /// Returns the exact average execution price; see
+    /// [`TradeSummary::average_price`].
+    #[must_use]
+    #[inline]
+    pub fn average_price(&self) -> Option<AveragePrice> {
+        self.summary.average_price()
+    }
+
+    /// Returns the individual fills in execution order.
+    pub fn fills(&self) -> impl Iterator<Item = TradeFill> + '_ {
+        self.match_result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| TradeFill {
+                maker_order_id: trade.maker_order_id(),
+                price: trade.price().as_u128(),
+                quantity: trade.quantity().as_u64(),
+            })
+    }
 }
 
 /// Trade listener specification using Arc for shared ownership
@@ -155,7 +339,7 @@ pub struct TransactionInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pricelevel::{Id, MatchResult, Price, Quantity, Trade};
+    use pricelevel::{Price, Quantity, Trade};
 
     fn make_match_result_with_trades(trades: Vec<Trade>) -> MatchResult {
         let order_id = Id::new_uuid();
@@ -290,4 +474,146 @@ mod tests {
         assert_eq!(info.maker_fee, -25);
         assert_eq!(info.taker_fee, 50);
     }
+
+    #[test]
+    fn test_three_maker_sweep_fills_sum_to_summary() {
+        use crate::OrderBook;
+        use pricelevel::{Side, TimeInForce};
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let makers = [Id::new_uuid(), Id::new_uuid(), Id::new_uuid()];
+        for (maker, price) in makers.iter().zip([100u128, 101, 102]) {
+            book.add_limit_order(*maker, price, 10, Side::Sell, TimeInForce::Gtc, None)
+                .unwrap();
+        }
+
+        let match_result = book
+            .submit_market_order(Id::new_uuid(), 25, Side::Buy)
+            .unwrap();
+        let tr = TradeResult::new("TEST".to_string(), match_result);
+
+        let fills: Vec<TradeFill> = tr.fills().collect();
+        assert_eq!(
+            fills.iter().map(|f| f.maker_order_id).collect::<Vec<_>>(),
+            makers.to_vec()
+        );
+        assert_eq!(fills[2].quantity, 5);
+
+        let summary = tr.summary();
+        assert_eq!(summary.fill_count, 3);
+        assert_eq!(
+            summary.executed_quantity,
+            fills.iter().map(|f| f.quantity).sum::<u64>()
+        );
+        assert_eq!(
+            summary.executed_value,
+            fills
+                .iter()
+                .map(|f| f.price * u128::from(f.quantity))
+                .sum::<u128>()
+        );
+        assert_eq!(summary.executed_quantity, 25);
+        assert_eq!(summary.executed_value, 1000 + 1010 + 510);
+        assert_eq!(summary.remaining_quantity, 0);
+        assert_eq!(
+            u128::from(summary.executed_quantity),
+            u128::from(tr.match_result.executed_quantity().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_limit_sweep_skips_price_gaps_and_counts_levels() {
+        use crate::OrderBook;
+        use pricelevel::{Hash32, OrderType, Side, TimeInForce, TimestampMs};
+
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        for price in [100u128, 100, 102, 105] {
+            book.add_limit_order(
+                Id::new_uuid(),
+                price,
+                10,
+                Side::Sell,
+                TimeInForce::Gtc,
+                None,
+            )
+            .unwrap();
+        }
+
+        let taker = Id::new_uuid();
+        let added = book
+            .add_order_with_remainder(
+                OrderType::Standard {
+                    id: taker,
+                    price: Price::new(103),
+                    quantity: Quantity::new(50),
+                    side: Side::Buy,
+                    user_id: Hash32::zero(),
+                    timestamp: TimestampMs::new(0),
+                    time_in_force: TimeInForce::Gtc,
+                    extra_fields: (),
+                },
+                Default::default(),
+            )
+            .unwrap();
+        let tr = TradeResult::new("TEST".to_string(), added.match_result);
+
+        let prices: Vec<u128> = tr.fills().map(|f| f.price).collect();
+        assert_eq!(prices, vec![100, 100, 102]);
+        assert_eq!(tr.levels_touched(), 2);
+        assert_eq!(tr.summary().executed_quantity, 30);
+
+        // 105 is beyond the limit and untouched; the remainder rests at 103
+        assert_eq!(book.best_ask(), Some(105));
+        assert_eq!(
+            book.get_orders_at_price(105, Side::Sell)[0].visible_quantity(),
+            10
+        );
+        assert_eq!(book.best_bid(), Some(103));
+        assert_eq!(
+            book.get_order(taker).map(|o| o.visible_quantity()),
+            Some(20)
+        );
+
+        let empty = TradeResult::new("TEST".to_string(), make_match_result_with_trades(vec![]));
+        assert_eq!(empty.levels_touched(), 0);
+    }
+
+    #[test]
+    fn test_average_price_is_exact_integer_quotient() {
+        // 3 @ 100 + 4 @ 102 = 708 over 7: average 101 1/7
+        let mr = make_match_result_with_trades(vec![make_trade(100, 3), make_trade(102, 4)]);
+        let tr = TradeResult::new("TEST".to_string(), mr);
+
+        let average = tr.average_price().unwrap();
+        assert_eq!(
+            average,
+            AveragePrice {
+                quotient: 101,
+                remainder: 1,
+                quantity: 7,
+            }
+        );
+        assert!(!average.is_exact());
+        assert_eq!(average.rounded(RoundingMode::Down), 101);
+        assert_eq!(average.rounded(RoundingMode::Up), 102);
+        assert_eq!(average.rounded(RoundingMode::Nearest), 101);
+
+        // 1 @ 100 + 1 @ 102 averages exactly 101; 1 @ 100 + 3 @ 102 is
+        // 101.5 and rounds up when nearest
+        let even = make_match_result_with_trades(vec![make_trade(100, 1), make_trade(102, 1)]);
+        let even = TradeResult::new("TEST".to_string(), even)
+            .average_price()
+            .unwrap();
+        assert!(even.is_exact());
+        assert_eq!(even.rounded(RoundingMode::Up), 101);
+        let half = make_match_result_with_trades(vec![make_trade(100, 1), make_trade(102, 3)]);
+        let half = TradeResult::new("TEST".to_string(), half)
+            .average_price()
+            .unwrap();
+        assert_eq!((half.quotient, half.remainder, half.quantity), (101, 2, 4));
+        assert_eq!(half.rounded(RoundingMode::Nearest), 102);
+
+        let empty = TradeResult::new("TEST".to_string(), make_match_result_with_trades(vec![]));
+        assert_eq!(empty.average_price(), None);
+    }
 }
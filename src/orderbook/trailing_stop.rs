@@ -0,0 +1,562 @@
+//! Off-book trailing stops that follow the reference price.
+//!
+//! [`OrderBook::add_trailing_stop`] holds an order off the book together
+//! with a trigger price that trails the market by a [`TrailAmount`]. The
+//! market is the active reference price chosen by the book's
+//! [`ReferencePricePolicy`](crate::orderbook::reference_price::ReferencePricePolicy).
+//! A sell stop's trigger ratchets up as the reference rises, never down, and
+//! fires when the reference falls to or below it; a buy stop mirrors this
+//! below the market.
+//!
+//! Under [`ReferencePricePolicy::LastTrade`](crate::orderbook::reference_price::ReferencePricePolicy::LastTrade),
+//! stops are evaluated against every trade of continuous matching, in
+//! execution order, so a single sweep can both ratchet and fire a stop.
+//! Under the other policies they are evaluated against the reference after
+//! each match, after each manual reference change, and after each command
+//! run through [`execute_command`](crate::orderbook::sequencer::execute_command),
+//! which catches a mid price moved by an add or a cancel. Callers driving
+//! the book directly under the mid policy call
+//! [`OrderBook::evaluate_trailing_stops`] themselves.
+//!
+//! A fired stop submits its order through the regular add path once the
+//! operation whose trade fired it has completed, as a contingent order is,
+//! and is recorded as a
+//! [`TriggeredContingent`](crate::orderbook::contingent::TriggeredContingent)
+//! whose `trigger_order_id` is the stop's own id, so a command loop
+//! journals it the same way as a contingent order. An auction uncross
+//! moves stops once, with its clearing price. Unlike
+//! [`OrderType::TrailingStop`](pricelevel::OrderType::TrailingStop), which
+//! rests in the book and is re-priced by the special order tracker, these
+//! stops never rest until they fire, and mass cancels leave them in place;
+//! remove them with
+//! [`OrderBook::cancel_trailing_stop`]. Snapshot packages carry pending
+//! stops with their current trigger, and a plain
+//! [`OrderBook::restore_from_snapshot`] drops them.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::reference_price::ReferencePricePolicy;
+use pricelevel::{Id, MatchResult, OrderType, Side};
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+/// Basis points per unit (1 bps = 0.01%).
+const BASIS_POINTS_PER_UNIT: u128 = 10_000;
+
+/// Distance a trailing stop's trigger keeps from the best price seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrailAmount {
+    /// A fixed number of price units.
+    Offset(u128),
+    /// A fraction of the best price seen, in basis points.
+    Bps(u32),
+}
+
+impl TrailAmount {
+    /// Returns the trail distance from `reference`, rounded down.
+    #[must_use]
+    pub fn distance(&self, reference: u128) -> u128 {
+        match self {
+            TrailAmount::Offset(offset) => *offset,
+            TrailAmount::Bps(bps) => {
+                reference.saturating_mul(u128::from(*bps)) / BASIS_POINTS_PER_UNIT
+            }
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        matches!(self, TrailAmount::Offset(0) | TrailAmount::Bps(0))
+    }
+}
+
+impl std::fmt::Display for TrailAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrailAmount::Offset(offset) => write!(f, "{offset}"),
+            TrailAmount::Bps(bps) => write!(f, "{bps}bps"),
+        }
+    }
+}
+
+/// The current state of a pending trailing stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrailingStopStatus {
+    /// Identifier of the order submitted when the stop fires.
+    pub stop_id: Id,
+    /// Side of that order; a sell stop trails below the market.
+    pub side: Side,
+    /// Distance the trigger trails the reference price by.
+    pub trail: TrailAmount,
+    /// Best reference price seen since the stop was added: the highest for
+    /// a sell stop, the lowest for a buy stop.
+    pub reference_price: u128,
+    /// Reference price at or through which the stop fires.
+    pub trigger_price: u128,
+}
+
+/// A trailing stop waiting to fire, as carried by a snapshot package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTrailingStop<T = ()> {
+    order: OrderType<T>,
+    trail: TrailAmount,
+    reference_price: u128,
+    trigger_price: u128,
+}
+
+impl<T: Clone> PendingTrailingStop<T> {
+    fn new(order: OrderType<T>, trail: TrailAmount, reference_price: u128) -> Self {
+        let mut stop = Self {
+            order,
+            trail,
+            reference_price,
+            trigger_price: 0,
+        };
+        stop.trigger_price = stop.trigger_for(reference_price);
+        stop
+    }
+
+    fn trigger_for(&self, reference: u128) -> u128 {
+        let distance = self.trail.distance(reference);
+        match self.order.side() {
+            Side::Sell => reference.saturating_sub(distance),
+            Side::Buy => reference.saturating_add(distance),
+        }
+    }
+
+    /// Apply a reference price; returns `true` if the stop fires.
+    fn observe(&mut self, price: u128) -> bool {
+        let (fires, favorable) = match self.order.side() {
+            Side::Sell => (price <= self.trigger_price, price > self.reference_price),
+            Side::Buy => (price >= self.trigger_price, price < self.reference_price),
+        };
+        if fires {
+            return true;
+        }
+        if favorable {
+            self.reference_price = price;
+            self.trigger_price = self.trigger_for(price);
+        }
+        false
+    }
+
+    fn status(&self) -> TrailingStopStatus {
+        TrailingStopStatus {
+            stop_id: self.order.id(),
+            side: self.order.side(),
+            trail: self.trail,
+            reference_price: self.reference_price,
+            trigger_price: self.trigger_price,
+        }
+    }
+}
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Hold `order` off the book until the market moves against its side by
+    /// `trail` from the best reference price seen, then submit it. Returns
+    /// the initial trigger price.
+    ///
+    /// The trail starts from the active
+    /// [`reference_price`](Self::reference_price). The order's side decides
+    /// the direction: a sell stop fires on a fall, a buy stop on a rise.
+    ///
+    /// # Errors
+    /// Returns [`OrderBookError::InvalidOperation`] if no reference price is
+    /// available or `trail` is zero.
+    pub fn add_trailing_stop(
+        &self,
+        order: OrderType<T>,
+        trail: TrailAmount,
+    ) -> Result<u128, OrderBookError> {
+        if trail.is_zero() {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!("trailing stop {} has a zero trail", order.id()),
+            });
+        }
+        let Some(reference) = self.reference_price() else {
+            return Err(OrderBookError::InvalidOperation {
+                message: format!(
+                    "trailing stop {} needs a reference price to trail",
+                    order.id()
+                ),
+            });
+        };
+        let stop = PendingTrailingStop::new(order, trail, reference);
+        let trigger = stop.trigger_price;
+        trace!(
+            "Order book {}: Trailing stop {} trails {} by {}, trigger {}",
+            self.symbol,
+            stop.order.id(),
+            reference,
+            trail,
+            trigger
+        );
+        if let Ok(mut stops) = self.trailing_stops.lock() {
+            stops.push(stop);
+        }
+        Ok(trigger)
+    }
+
+    /// Returns the current trigger price of the pending stop `stop_id`.
+    #[must_use]
+    pub fn trailing_stop_trigger(&self, stop_id: Id) -> Option<u128> {
+        self.trailing_stop_status(stop_id)
+            .map(|status| status.trigger_price)
+    }
+
+    /// Returns the state of the pending stop `stop_id`.
+    #[must_use]
+    pub fn trailing_stop_status(&self, stop_id: Id) -> Option<TrailingStopStatus> {
+        let stops = self.trailing_stops.lock().ok()?;
+        stops
+            .iter()
+            .find(|stop| stop.order.id() == stop_id)
+            .map(PendingTrailingStop::status)
+    }
+
+    /// Returns the state of every pending stop, in the order they were
+    /// added.
+    #[must_use]
+    pub fn pending_trailing_stops(&self) -> Vec<TrailingStopStatus> {
+        self.trailing_stops
+            .lock()
+            .map(|stops| stops.iter().map(PendingTrailingStop::status).collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove a pending stop, returning its order if it was pending.
+    pub fn cancel_trailing_stop(&self, stop_id: Id) -> Option<OrderType<T>> {
+        let mut stops = self.trailing_stops.lock().ok()?;
+        let index = stops.iter().position(|stop| stop.order.id() == stop_id)?;
+        Some(stops.remove(index).order)
+    }
+
+    /// Returns the pending stops with unit extra fields, for snapshots.
+    pub(super) fn raw_trailing_stops(&self) -> Vec<PendingTrailingStop> {
+        self.trailing_stops
+            .lock()
+            .map(|stops| {
+                stops
+                    .iter()
+                    .map(|stop| PendingTrailingStop {
+                        order: self.convert_to_unit_type(&stop.order),
+                        trail: stop.trail,
+                        reference_price: stop.reference_price,
+                        trigger_price: stop.trigger_price,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Replace the pending stops with stops taken by
+    /// [`raw_trailing_stops`](Self::raw_trailing_stops), in their order.
+    /// Their orders get default extra fields.
+    pub(super) fn restore_trailing_stops(&self, raw: Vec<PendingTrailingStop>) {
+        if let Ok(mut stops) = self.trailing_stops.lock() {
+            *stops = raw
+                .into_iter()
+                .map(|stop| PendingTrailingStop {
+                    order: self.convert_from_unit_type(&stop.order),
+                    trail: stop.trail,
+                    reference_price: stop.reference_price,
+                    trigger_price: stop.trigger_price,
+                })
+                .collect();
+        }
+    }
+
+    /// Move pending stops with the active reference price and submit the
+    /// orders of those that fire, in the order the stops were added.
+    ///
+    /// Returns the number of stops that fired.
+    pub fn evaluate_trailing_stops(&self) -> usize {
+        match self.reference_price() {
+            Some(reference) => self.fire_trailing_stops(&[reference]),
+            None => 0,
+        }
+    }
+
+    /// Move pending stops after `match_result`: with every trade price under
+    /// the last trade policy, otherwise with the reference price.
+    pub(super) fn update_trailing_stops(&self, match_result: &MatchResult) {
+        let prices: Vec<u128> = match_result
+            .trades()
+            .as_vec()
+            .iter()
+            .map(|trade| trade.price().as_u128())
+            .collect();
+        self.observe_trade_prices(&prices);
+    }
+
+    /// Move pending stops after trades at `prices`, in order: with every
+    /// price under the last trade policy, otherwise with the reference
+    /// price.
+    pub(super) fn observe_trade_prices(&self, prices: &[u128]) {
+        if prices.is_empty() {
+            return;
+        }
+        if self.reference_price_policy() == ReferencePricePolicy::LastTrade {
+            self.fire_trailing_stops(prices);
+        } else {
+            self.evaluate_trailing_stops();
+        }
+    }
+
+    /// Observe `prices` in order on every pending stop and submit the
+    /// orders of the stops that fire. Returns the number fired.
+    fn fire_trailing_stops(&self, prices: &[u128]) -> usize {
+        let fired: Vec<PendingTrailingStop<T>> = {
+            let Ok(mut stops) = self.trailing_stops.lock() else {
+                return 0;
+            };
+            let mut fired = Vec::new();
+            let mut index = 0;
+            while index < stops.len() {
+                let stop = &mut stops[index];
+                if prices.iter().any(|price| stop.observe(*price)) {
+                    fired.push(stops.remove(index));
+                } else {
+                    index += 1;
+                }
+            }
+            fired
+        };
+
+        let count = fired.len();
+        for stop in fired {
+            trace!(
+                "Order book {}: Trailing stop {} fired at trigger {}",
+                self.symbol,
+                stop.order.id(),
+                stop.trigger_price
+            );
+            self.submit_triggered(stop.order.id(), stop.order);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Hash32, Price, Quantity, TimeInForce, TimestampMs};
+
+    fn standard(price: u128, quantity: u64, side: Side, tif: TimeInForce) -> OrderType<()> {
+        OrderType::Standard {
+            id: Id::new_uuid(),
+            price: Price::new(price),
+            quantity: Quantity::new(quantity),
+            side,
+            user_id: Hash32::zero(),
+            timestamp: TimestampMs::new(0),
+            time_in_force: tif,
+            extra_fields: (),
+        }
+    }
+
+    /// A book whose stops follow the last trade price.
+    fn last_trade_book() -> OrderBook<()> {
+        let mut book = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::LastTrade);
+        book
+    }
+
+    /// Print a trade of 1 at `price` between two fresh orders.
+    fn trade_at(book: &OrderBook<()>, price: u128) {
+        book.add_order(standard(price, 1, Side::Sell, TimeInForce::Gtc))
+            .unwrap();
+        book.add_order(standard(price, 1, Side::Buy, TimeInForce::Ioc))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sell_stop_ratchets_up_and_fires_on_pullback() {
+        let book = last_trade_book();
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Ioc);
+        assert!(matches!(
+            book.add_trailing_stop(stop, TrailAmount::Offset(5)),
+            Err(OrderBookError::InvalidOperation { .. })
+        ));
+
+        trade_at(&book, 100);
+        assert_eq!(
+            book.add_trailing_stop(stop, TrailAmount::Offset(5))
+                .unwrap(),
+            95
+        );
+
+        // Rises move the trigger up; a dip above it leaves it in place
+        trade_at(&book, 104);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), Some(99));
+        trade_at(&book, 110);
+        trade_at(&book, 107);
+        let status = book.trailing_stop_status(stop.id()).unwrap();
+        assert_eq!((status.reference_price, status.trigger_price), (110, 105));
+
+        // The pullback to the trigger fires the stop into the resting bid
+        let bid = standard(103, 5, Side::Buy, TimeInForce::Gtc);
+        book.add_order(bid).unwrap();
+        trade_at(&book, 105);
+        assert!(book.pending_trailing_stops().is_empty());
+        assert!(book.get_order(bid.id()).is_none());
+
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, stop.id());
+        assert_eq!(
+            triggered[0]
+                .outcome
+                .as_ref()
+                .unwrap()
+                .match_result
+                .filled_order_ids(),
+            &[bid.id()]
+        );
+    }
+
+    #[test]
+    fn test_fired_stop_waits_for_the_aggressor_to_rest() {
+        let book = last_trade_book();
+        trade_at(&book, 110);
+        let stop = standard(103, 5, Side::Sell, TimeInForce::Gtc);
+        book.add_trailing_stop(stop, TrailAmount::Offset(5))
+            .unwrap();
+
+        // The sweep's trade at 100 fires the stop, whose sell then trades
+        // with the bid the buy rests instead of crossing it
+        book.add_order(standard(100, 10, Side::Sell, TimeInForce::Gtc))
+            .unwrap();
+        let buy = standard(105, 15, Side::Buy, TimeInForce::Gtc);
+        book.add_order(buy).unwrap();
+        assert!(!book.check_crossed());
+        assert!(book.pending_trailing_stops().is_empty());
+        assert_eq!((book.best_bid(), book.best_ask()), (None, None));
+
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(
+            triggered[0]
+                .outcome
+                .as_ref()
+                .unwrap()
+                .match_result
+                .filled_order_ids(),
+            &[buy.id()]
+        );
+    }
+
+    #[test]
+    fn test_auction_uncross_moves_stops_with_the_clearing_price() {
+        use crate::orderbook::TradingState;
+
+        let book = last_trade_book();
+        trade_at(&book, 110);
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Gtc);
+        book.add_trailing_stop(stop, TrailAmount::Offset(5))
+            .unwrap();
+
+        book.set_trading_state(TradingState::Auction);
+        book.add_order(standard(100, 5, Side::Buy, TimeInForce::Gtc))
+            .unwrap();
+        book.add_order(standard(100, 5, Side::Sell, TimeInForce::Gtc))
+            .unwrap();
+        let result = book.run_auction_uncross().unwrap();
+        assert_eq!(result.clearing_price, Some(100));
+
+        assert!(book.pending_trailing_stops().is_empty());
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, stop.id());
+        assert_eq!(book.best_ask(), Some(1));
+    }
+
+    #[test]
+    fn test_buy_stop_trails_in_basis_points() {
+        let book = last_trade_book();
+        trade_at(&book, 1_000);
+        let stop = standard(2_000, 5, Side::Buy, TimeInForce::Gtc);
+        // 2% of 1,000
+        assert_eq!(
+            book.add_trailing_stop(stop, TrailAmount::Bps(200)).unwrap(),
+            1_020
+        );
+
+        trade_at(&book, 900);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), Some(918));
+        trade_at(&book, 910);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), Some(918));
+        trade_at(&book, 918);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), None);
+        assert_eq!(book.best_bid(), Some(2_000));
+    }
+
+    #[test]
+    fn test_cancel_trailing_stop() {
+        let book = last_trade_book();
+        trade_at(&book, 100);
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Ioc);
+        book.add_trailing_stop(stop, TrailAmount::Offset(5))
+            .unwrap();
+
+        assert_eq!(
+            book.cancel_trailing_stop(stop.id()).map(|o| o.id()),
+            Some(stop.id())
+        );
+        assert!(book.cancel_trailing_stop(stop.id()).is_none());
+        trade_at(&book, 90);
+        assert!(book.take_triggered_contingents().is_empty());
+    }
+
+    #[test]
+    fn test_stop_trails_manual_reference() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let stop = standard(1, 5, Side::Sell, TimeInForce::Ioc);
+        book.set_reference_price(100);
+        assert_eq!(
+            book.add_trailing_stop(stop, TrailAmount::Offset(5))
+                .unwrap(),
+            95
+        );
+
+        // Trades do not move a stop that follows the manual reference
+        trade_at(&book, 90);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), Some(95));
+
+        book.set_reference_price(110);
+        assert_eq!(book.trailing_stop_trigger(stop.id()), Some(105));
+        book.set_reference_price(105);
+        assert!(book.pending_trailing_stops().is_empty());
+        let triggered = book.take_triggered_contingents();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].trigger_order_id, stop.id());
+    }
+
+    #[test]
+    fn test_stop_trails_mid_price() {
+        use crate::orderbook::sequencer::{SequencerCommand, execute_command};
+
+        let mut book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_reference_price_policy(ReferencePricePolicy::Mid);
+        book.add_order(standard(98, 1, Side::Buy, TimeInForce::Gtc))
+            .unwrap();
+        book.add_order(standard(110, 1, Side::Sell, TimeInForce::Gtc))
+            .unwrap();
+        let stop = standard(200, 5, Side::Buy, TimeInForce::Ioc);
+        assert_eq!(
+            book.add_trailing_stop(stop, TrailAmount::Offset(3))
+                .unwrap(),
+            107
+        );
+
+        // A higher bid lifts the mid to 109 without a trade
+        execute_command(
+            &book,
+            &SequencerCommand::AddOrder(standard(108, 1, Side::Buy, TimeInForce::Gtc)),
+        );
+        assert!(book.pending_trailing_stops().is_empty());
+        assert_eq!(book.take_triggered_contingents().len(), 1);
+    }
+}
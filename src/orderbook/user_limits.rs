@@ -0,0 +1,229 @@
+//! Per-user resting quantity limits.
+//!
+//! Risk management can cap how much quantity a user may have resting on
+//! each side of the book with [`OrderBook::set_user_limit`]. The budget is
+//! computed from the user's live orders, so cancels and fills free it
+//! immediately. [`OrderBook::active_users`] lists the users that currently
+//! have orders resting.
+
+use super::book::OrderBook;
+use super::error::OrderBookError;
+use super::modifications::OrderQuantity;
+use pricelevel::{Hash32, OrderType, Side};
+use tracing::trace;
+
+impl<T> OrderBook<T>
+where
+    T: Clone + Send + Sync + Default + 'static,
+{
+    /// Cap the quantity `user_id` may have resting on each side at
+    /// `max_resting_quantity`.
+    ///
+    /// The limit applies per side and is enforced when orders are added;
+    /// orders already resting are not affected.
+    pub fn set_user_limit(&self, user_id: Hash32, max_resting_quantity: u64) {
+        self.user_limits.insert(user_id, max_resting_quantity);
+        trace!(
+            "Order book {}: Set resting limit {} for user {}",
+            self.symbol, max_resting_quantity, user_id
+        );
+    }
+
+    /// Remove the resting quantity limit of `user_id`. Returns the removed
+    /// limit, if any.
+    pub fn clear_user_limit(&self, user_id: Hash32) -> Option<u64> {
+        self.user_limits.remove(&user_id).map(|(_, limit)| limit)
+    }
+
+    /// Returns the resting quantity limit of `user_id`, if set.
+    #[must_use]
+    pub fn user_limit(&self, user_id: Hash32) -> Option<u64> {
+        self.user_limits.get(&user_id).map(|limit| *limit)
+    }
+
+    /// Returns the total quantity (visible and hidden) `user_id` has resting
+    /// on `side`.
+    ///
+    /// # Performance
+    /// O(N × M) where N is the number of the user's orders and M the number
+    /// of orders at each of their price levels.
+    #[must_use]
+    pub fn user_resting_quantity(&self, user_id: Hash32, side: Side) -> u64 {
+        let Some(order_ids) = self.user_orders.get(&user_id) else {
+            return 0;
+        };
+
+        let price_levels = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+
+        order_ids
+            .iter()
+            .filter_map(|id| {
+                let (price, order_side) = *self.order_locations.get(id)?;
+                if order_side != side {
+                    return None;
+                }
+                let level = price_levels.get(&price)?;
+                level
+                    .value()
+                    .iter_orders()
+                    .find(|order| order.id() == *id)
+                    .map(|order| order.total_quantity())
+            })
+            .fold(0u64, |acc, quantity| acc.saturating_add(quantity))
+    }
+
+    /// Returns the distinct users with at least one resting order, ordered
+    /// by id.
+    ///
+    /// Orders without an owner rest under `Hash32::zero()`; that user is
+    /// listed only when `include_anonymous` is set.
+    #[must_use]
+    pub fn active_users(&self, include_anonymous: bool) -> Vec<Hash32> {
+        let mut users: Vec<Hash32> = self
+            .user_orders
+            .iter()
+            .filter(|entry| !entry.value().is_empty())
+            .map(|entry| *entry.key())
+            .filter(|user_id| include_anonymous || *user_id != Hash32::zero())
+            .collect();
+        users.sort_unstable_by_key(|user_id| user_id.0);
+        users
+    }
+
+    /// Reject `order` if it would push its user's resting quantity on its
+    /// side over the user's limit.
+    ///
+    /// The remainder that would rest is not known before matching, so the
+    /// full order quantity counts against the budget.
+    pub(super) fn check_user_limit(&self, order: &OrderType<T>) -> Result<(), OrderBookError> {
+        self.check_user_limit_replacing(order, None)
+    }
+
+    /// Like [`Self::check_user_limit`], but for an order replacing resting
+    /// order `replaced`, whose quantity no longer counts once replaced.
+    pub(super) fn check_user_limit_replacing(
+        &self,
+        order: &OrderType<T>,
+        replaced: Option<&OrderType<T>>,
+    ) -> Result<(), OrderBookError> {
+        let user_id = order.user_id();
+        let Some(limit) = self.user_limit(user_id) else {
+            return Ok(());
+        };
+
+        let side = order.side();
+        let mut resting = self.user_resting_quantity(user_id, side);
+        if let Some(replaced) = replaced
+            && replaced.user_id() == user_id
+            && replaced.side() == side
+        {
+            resting = resting.saturating_sub(replaced.total_quantity());
+        }
+        let requested = order.total_quantity();
+        if resting.saturating_add(requested) > limit {
+            return Err(OrderBookError::UserLimitExceeded {
+                user_id,
+                side,
+                limit,
+                resting,
+                requested,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricelevel::{Id, TimeInForce};
+
+    fn user() -> Hash32 {
+        Hash32::new([7; 32])
+    }
+
+    fn add(
+        book: &OrderBook<()>,
+        price: u128,
+        quantity: u64,
+        side: Side,
+    ) -> Result<Id, OrderBookError> {
+        let id = Id::new_uuid();
+        book.add_limit_order_with_user(id, price, quantity, side, TimeInForce::Gtc, user(), None)
+            .map(|_| id)
+    }
+
+    #[test]
+    fn test_user_limit_rejects_and_frees_on_cancel() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_user_limit(user(), 100);
+
+        let first = add(&book, 100, 60, Side::Buy).unwrap();
+        add(&book, 99, 40, Side::Buy).unwrap();
+        assert_eq!(book.user_resting_quantity(user(), Side::Buy), 100);
+
+        let err = add(&book, 98, 1, Side::Buy).unwrap_err();
+        assert!(matches!(
+            err,
+            OrderBookError::UserLimitExceeded {
+                side: Side::Buy,
+                limit: 100,
+                resting: 100,
+                requested: 1,
+                ..
+            }
+        ));
+
+        // The limit is per side
+        add(&book, 110, 100, Side::Sell).unwrap();
+
+        book.cancel_order(first).unwrap();
+        assert_eq!(book.user_resting_quantity(user(), Side::Buy), 40);
+        add(&book, 98, 60, Side::Buy).unwrap();
+        assert_eq!(book.user_resting_quantity(user(), Side::Buy), 100);
+    }
+
+    #[test]
+    fn test_clear_user_limit() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        book.set_user_limit(user(), 10);
+        assert_eq!(book.user_limit(user()), Some(10));
+        assert!(add(&book, 100, 11, Side::Buy).is_err());
+
+        assert_eq!(book.clear_user_limit(user()), Some(10));
+        assert_eq!(book.user_limit(user()), None);
+        assert!(add(&book, 100, 11, Side::Buy).is_ok());
+    }
+
+    #[test]
+    fn test_active_users_with_and_without_anonymous() {
+        let book: OrderBook<()> = OrderBook::new("TEST");
+        let alice = Hash32::new([1; 32]);
+        let bob = Hash32::new([2; 32]);
+        for (user_id, price) in [(bob, 100), (alice, 99), (Hash32::zero(), 98), (bob, 97)] {
+            book.add_limit_order_with_user(
+                Id::new_uuid(),
+                price,
+                10,
+                Side::Buy,
+                TimeInForce::Gtc,
+                user_id,
+                None,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(book.active_users(false), vec![alice, bob]);
+        assert_eq!(book.active_users(true), vec![Hash32::zero(), alice, bob]);
+
+        // Bob's first order fills and Alice's is taken as well; Bob still
+        // rests at 97
+        book.submit_market_order(Id::new_uuid(), 20, Side::Sell)
+            .unwrap();
+        assert_eq!(book.active_users(false), vec![bob]);
+        assert_eq!(book.active_users(true), vec![Hash32::zero(), bob]);
+    }
+}
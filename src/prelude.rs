@@ -17,9 +17,9 @@
 //! This will import all the essential types needed for working with the order book.
 
 // Core order book types
-pub use crate::orderbook::OrderBook;
 pub use crate::orderbook::manager::{BookManager, BookManagerStd, BookManagerTokio};
 pub use crate::orderbook::{ManagerError, OrderBookError};
+pub use crate::orderbook::{OrderBook, OrderBookBuilder};
 
 // Iterator types
 pub use crate::orderbook::iterators::LevelInfo;
@@ -28,14 +28,16 @@ pub use crate::orderbook::iterators::LevelInfo;
 pub use crate::orderbook::market_impact::{MarketImpact, OrderSimulation};
 
 // Snapshot types
-pub use crate::orderbook::snapshot::{EnrichedSnapshot, MetricFlags, OrderBookSnapshot};
+pub use crate::orderbook::snapshot::{
+    ColumnarSnapshot, EnrichedSnapshot, MetricFlags, OrderBookSnapshot, Quote, SnapshotOptions,
+};
 
 // Statistics types
 pub use crate::orderbook::statistics::{DepthStats, DistributionBin};
 
 // Trade-related types
 pub use crate::orderbook::trade::{
-    TradeEvent, TradeInfo, TradeListener, TradeResult, TransactionInfo,
+    TradeEvent, TradeFill, TradeInfo, TradeListener, TradeResult, TradeSummary, TransactionInfo,
 };
 
 // Order types and enums from pricelevel
@@ -65,12 +67,16 @@ pub use crate::orderbook::nats_book_change::{
 };
 
 // Sequencer and journal types
-#[cfg(feature = "journal")]
-pub use crate::orderbook::sequencer::FileJournal;
 pub use crate::orderbook::sequencer::{
-    InMemoryJournal, Journal, JournalEntry, JournalError, JournalReadIter, ReplayEngine,
-    ReplayError, SequencerCommand, SequencerEvent, SequencerResult, snapshots_match,
+    BatchExecutor, BookRegistry, CompactEvent, CompactJournal, CompactResult, DispatchOutcome,
+    EventListeners, InMemoryJournal, Journal, JournalEntry, JournalError, JournalReadIter,
+    LevelDifference, ListenerId, ListenerPanicPolicy, ReplayEngine, ReplayError, ReplayedEvent,
+    SequencerCommand, SequencerEvent, SequencerListener, SequencerMetrics, SequencerResult,
+    SnapshotComparison, SymbolCommand, SymbolEvent, contingent_events, execute_command,
+    snapshot_diff, snapshots_match,
 };
+#[cfg(feature = "journal")]
+pub use crate::orderbook::sequencer::{FileJournal, MmapJournal};
 
 // Utility functions
 pub use crate::utils::current_time_millis;
@@ -1,13 +1,14 @@
 /******************************************************************************
    Unit tests for ReplayEngine and InMemoryJournal coverage gaps.
-   Covers: replay error paths, snapshots_match, JournalError Display,
+   Covers: replay error paths, snapshots_match, snapshot_diff, JournalError Display,
    InMemoryJournal edge cases.
 ******************************************************************************/
 
 use orderbook_rs::orderbook::mass_cancel::MassCancelResult;
 use orderbook_rs::orderbook::sequencer::{
-    InMemoryJournal, Journal, ReplayEngine, ReplayError, SequencerCommand, SequencerEvent,
-    SequencerResult, snapshots_match,
+    CompactEvent, CompactJournal, CompactResult, InMemoryJournal, Journal, LevelDifference,
+    ReplayEngine, ReplayError, ReplayedEvent, SequencerCommand, SequencerEvent, SequencerResult,
+    snapshot_diff, snapshots_match,
 };
 use pricelevel::{Hash32, Id, Price, Quantity, Side, TimeInForce, TimestampMs};
 
@@ -175,6 +176,89 @@ fn replay_skips_rejected_events() {
     assert_eq!(snap.bids.len(), 1);
 }
 
+#[test]
+fn replay_strict_reports_first_drifted_event() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let resting = Id::new_uuid();
+    let never_added = Id::new_uuid();
+    let events = [
+        make_add_event(0, resting, 100, 10, Side::Buy),
+        // Rejected when journaled: skipped, not a drift
+        SequencerEvent {
+            sequence_num: 1,
+            timestamp_ns: 0,
+            command: SequencerCommand::CancelOrder(never_added),
+            result: SequencerResult::Rejected {
+                reason: "order not found".to_string(),
+            },
+        },
+        make_rejected_event(2),
+        // Journaled as cancelled, but the order never rests on replay
+        make_cancel_event(3, never_added),
+        make_cancel_event(4, resting),
+    ];
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+
+    // Lenient replay silently cancels nothing at sequence 3
+    let (book, last_seq) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").unwrap();
+    assert_eq!(last_seq, 4);
+    assert!(book.create_snapshot(usize::MAX).bids.is_empty());
+
+    let err = ReplayEngine::<()>::replay_strict(&journal, 0, "TEST")
+        .err()
+        .expect("expected drift");
+    assert!(matches!(
+        err,
+        ReplayError::OrderBookError {
+            sequence_num: 3,
+            source: orderbook_rs::OrderBookError::OrderNotFound(_),
+        }
+    ));
+}
+
+#[test]
+fn replay_strict_reports_mass_cancel_count_drift() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let id = Id::new_uuid();
+    assert!(
+        journal
+            .append(&make_add_event(0, id, 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(journal.append(&make_cancel_event(1, id)).is_ok());
+    let (book, _) = ReplayEngine::<()>::replay_strict(&journal, 0, "TEST").unwrap();
+    assert!(book.get_order(id).is_none());
+
+    // The journal says the mass cancel removed nothing
+    assert!(
+        journal
+            .append(&make_add_event(2, Id::new_uuid(), 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 3,
+                timestamp_ns: 0,
+                command: SequencerCommand::CancelAll,
+                result: SequencerResult::MassCancelled {
+                    result: MassCancelResult::default(),
+                },
+            })
+            .is_ok()
+    );
+    assert!(ReplayEngine::<()>::replay_from(&journal, 0, "TEST").is_ok());
+    assert!(matches!(
+        ReplayEngine::<()>::replay_strict(&journal, 0, "TEST"),
+        Err(ReplayError::OrderBookError {
+            sequence_num: 3,
+            source: orderbook_rs::OrderBookError::InvalidOperation { .. },
+        })
+    ));
+}
+
 #[test]
 fn replay_with_progress_callback() {
     let journal: InMemoryJournal<()> = InMemoryJournal::new();
@@ -444,8 +528,159 @@ fn snapshots_match_different_ask_count_returns_false() {
     assert!(!snapshots_match(&s1, &s2));
 }
 
+// ─── snapshot_diff ──────────────────────────────────────────────────────────
+
+#[test]
+fn snapshot_diff_identical_books_is_empty() {
+    let book = orderbook_rs::OrderBook::<()>::new("TEST");
+    let _ = book.add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    let s1 = book.create_snapshot(usize::MAX);
+    let s2 = book.create_snapshot(usize::MAX);
+    let diff = snapshot_diff(&s1, &s2);
+    assert!(diff.is_empty());
+    assert_eq!(diff.len(), 0);
+}
+
+#[test]
+fn snapshot_diff_reports_quantity_difference() {
+    let actual_book = orderbook_rs::OrderBook::<()>::new("TEST");
+    let _ = actual_book.add_limit_order(Id::new_uuid(), 110, 7, Side::Sell, TimeInForce::Gtc, None);
+    let expected_book = orderbook_rs::OrderBook::<()>::new("TEST");
+    let _ =
+        expected_book.add_limit_order(Id::new_uuid(), 110, 5, Side::Sell, TimeInForce::Gtc, None);
+
+    let diff = snapshot_diff(
+        &actual_book.create_snapshot(usize::MAX),
+        &expected_book.create_snapshot(usize::MAX),
+    );
+    assert!(diff.bid_differences.is_empty());
+    assert_eq!(
+        diff.ask_differences,
+        vec![LevelDifference::QuantityMismatch {
+            side: Side::Sell,
+            price: 110,
+            actual_quantity: 7,
+            expected_quantity: 5,
+        }]
+    );
+}
+
+#[test]
+fn snapshot_diff_reports_extra_and_missing_levels() {
+    let actual_book = orderbook_rs::OrderBook::<()>::new("TEST");
+    let _ = actual_book.add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    let _ = actual_book.add_limit_order(Id::new_uuid(), 99, 4, Side::Buy, TimeInForce::Gtc, None);
+    let expected_book = orderbook_rs::OrderBook::<()>::new("TEST");
+    let _ =
+        expected_book.add_limit_order(Id::new_uuid(), 100, 10, Side::Buy, TimeInForce::Gtc, None);
+    let _ = expected_book.add_limit_order(Id::new_uuid(), 98, 3, Side::Buy, TimeInForce::Gtc, None);
+
+    let actual = actual_book.create_snapshot(usize::MAX);
+    let expected = expected_book.create_snapshot(usize::MAX);
+    let diff = snapshot_diff(&actual, &expected);
+
+    assert_eq!(
+        diff.bid_differences,
+        vec![
+            LevelDifference::ExtraLevel {
+                side: Side::Buy,
+                price: 99,
+                actual_quantity: 4,
+            },
+            LevelDifference::MissingLevel {
+                side: Side::Buy,
+                price: 98,
+                expected_quantity: 3,
+            },
+        ]
+    );
+    assert!(diff.ask_differences.is_empty());
+    assert_eq!(diff.len(), 2);
+    assert!(!snapshots_match(&actual, &expected));
+}
+
+#[test]
+fn snapshot_diff_reports_symbol_mismatch() {
+    let s1 = orderbook_rs::OrderBook::<()>::new("BTC").create_snapshot(usize::MAX);
+    let s2 = orderbook_rs::OrderBook::<()>::new("ETH").create_snapshot(usize::MAX);
+    let diff = snapshot_diff(&s1, &s2);
+    assert_eq!(
+        diff.symbol_mismatch,
+        Some(("BTC".to_string(), "ETH".to_string()))
+    );
+    assert_eq!(diff.len(), 1);
+}
+
 // ─── InMemoryJournal ────────────────────────────────────────────────────────
 
+#[test]
+fn cloned_journal_events_replay_identically() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let bid = Id::new_uuid();
+    let events = [
+        make_add_event(0, bid, 100, 10, Side::Buy),
+        make_add_event(1, Id::new_uuid(), 101, 10, Side::Buy),
+        make_add_event(2, Id::new_uuid(), 105, 7, Side::Sell),
+        make_cancel_event(3, bid),
+        make_rejected_event(4),
+    ];
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+
+    // Owned copies can be shipped to another thread
+    let cloned = journal.clone_events().unwrap();
+    let cloned = std::thread::spawn(move || cloned).join().unwrap();
+    let sequences: Vec<u64> = cloned.iter().map(|e| e.sequence_num).collect();
+    assert_eq!(sequences, vec![0, 1, 2, 3, 4]);
+
+    let copy: InMemoryJournal<()> = InMemoryJournal::new();
+    for event in &cloned {
+        assert!(copy.append(event).is_ok());
+    }
+    let (original, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").unwrap();
+    let (replica, _) = ReplayEngine::<()>::replay_from(&copy, 0, "TEST").unwrap();
+    assert!(snapshots_match(
+        &replica.create_snapshot(usize::MAX),
+        &original.create_snapshot(usize::MAX)
+    ));
+
+    let drained = journal.drain_owned().unwrap();
+    assert_eq!(drained.len(), cloned.len());
+    assert!(
+        drained
+            .iter()
+            .zip(&cloned)
+            .all(|(a, b)| a.sequence_num == b.sequence_num && a.order_ids() == b.order_ids())
+    );
+}
+
+#[test]
+fn clone_events_starts_at_the_trimmed_floor() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for seq in 0..5 {
+        assert!(
+            journal
+                .append(&make_add_event(seq, Id::new_uuid(), 100, 1, Side::Buy))
+                .is_ok()
+        );
+    }
+    assert!(
+        journal
+            .set_retention(orderbook_rs::orderbook::sequencer::RetentionPolicy::LastEvents(2))
+            .is_ok()
+    );
+    assert!(journal.checkpoint(4).is_ok());
+
+    let sequences: Vec<u64> = journal
+        .clone_events()
+        .unwrap()
+        .iter()
+        .map(|e| e.sequence_num)
+        .collect();
+    assert_eq!(sequences, vec![3, 4]);
+}
+
 #[test]
 fn in_memory_journal_new_is_empty() {
     let journal: InMemoryJournal<()> = InMemoryJournal::new();
@@ -507,12 +742,182 @@ fn in_memory_journal_read_from_filters_correctly() {
     assert_eq!(entries.len(), 2);
 }
 
+#[test]
+fn in_memory_journal_append_in_order_is_ok() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for seq in [0, 1, 5, 7] {
+        let event = make_add_event(seq, Id::new_uuid(), 100, 10, Side::Buy);
+        assert!(journal.append(&event).is_ok());
+    }
+    assert_eq!(journal.len(), 4);
+    assert_eq!(journal.last_sequence(), Some(7));
+}
+
+#[test]
+fn in_memory_journal_append_rejects_lower_sequence() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    assert!(journal.append(&make_rejected_event(7)).is_ok());
+
+    for seq in [5, 7] {
+        let err = journal.append(&make_rejected_event(seq)).unwrap_err();
+        assert!(matches!(
+            err,
+            orderbook_rs::JournalError::OutOfOrderAppend { expected_gt: 7, found } if found == seq
+        ));
+    }
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal.last_sequence(), Some(7));
+}
+
+#[test]
+fn in_memory_journal_append_unchecked_skips_validation() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    assert!(journal.append_unchecked(&make_rejected_event(7)).is_ok());
+    assert!(journal.append_unchecked(&make_rejected_event(5)).is_ok());
+    assert_eq!(journal.len(), 2);
+    assert_eq!(journal.last_sequence(), Some(5));
+}
+
+#[test]
+fn in_memory_journal_events_for_order_amid_other_traffic() {
+    let target = Id::new_uuid();
+    let other = Id::new_uuid();
+    let events = vec![
+        make_add_event(0, other, 100, 10, Side::Buy),
+        make_add_event(1, target, 105, 5, Side::Sell),
+        make_add_event(2, Id::new_uuid(), 99, 7, Side::Buy),
+        make_rejected_event(3),
+        make_cancel_event(4, other),
+        make_cancel_event(5, target),
+        make_add_event(6, Id::new_uuid(), 106, 3, Side::Sell),
+    ];
+
+    for journal in [InMemoryJournal::new(), InMemoryJournal::with_order_index()] {
+        for event in &events {
+            assert!(journal.append(event).is_ok());
+        }
+
+        let found = journal.events_for_order(target).unwrap();
+        let sequences: Vec<u64> = found.iter().map(|e| e.sequence_num).collect();
+        assert_eq!(sequences, vec![1, 5]);
+        assert!(matches!(found[0].command, SequencerCommand::AddOrder(_)));
+        assert!(matches!(
+            found[1].command,
+            SequencerCommand::CancelOrder(id) if id == target
+        ));
+        assert!(journal.events_for_order(Id::new_uuid()).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn in_memory_journal_order_index_sorts_unchecked_appends() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::with_order_index();
+    assert!(journal.has_order_index());
+    let id = Id::new_uuid();
+    assert!(journal.append_unchecked(&make_cancel_event(9, id)).is_ok());
+    assert!(
+        journal
+            .append_unchecked(&make_add_event(3, id, 100, 10, Side::Buy))
+            .is_ok()
+    );
+
+    let sequences: Vec<u64> = journal
+        .events_for_order(id)
+        .unwrap()
+        .iter()
+        .map(|e| e.sequence_num)
+        .collect();
+    assert_eq!(sequences, vec![3, 9]);
+}
+
 #[test]
 fn in_memory_journal_verify_integrity_always_ok() {
     let journal: InMemoryJournal<()> = InMemoryJournal::new();
     assert!(journal.verify_integrity().is_ok());
 }
 
+#[test]
+fn in_memory_journal_retention_trims_after_checkpoint() {
+    use orderbook_rs::RetentionPolicy;
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::with_order_index();
+    journal
+        .set_retention(RetentionPolicy::LastEvents(3))
+        .unwrap();
+    let ids: Vec<Id> = (0..8).map(|_| Id::new_uuid()).collect();
+    for (seq, id) in ids.iter().enumerate().take(5) {
+        let event = make_add_event(seq as u64, *id, 100, 1, Side::Buy);
+        assert!(journal.append(&event).is_ok());
+    }
+    // Nothing is trimmed before a checkpoint covers it
+    assert_eq!(journal.len(), 5);
+    assert_eq!(journal.trimmed_floor(), None);
+
+    journal.checkpoint(4).unwrap();
+    assert_eq!(journal.len(), 3);
+    assert_eq!(journal.trimmed_floor(), Some(2));
+
+    // Appending beyond the window trims covered events, but not past the
+    // checkpoint
+    for (seq, id) in ids.iter().enumerate().skip(5) {
+        let event = make_add_event(seq as u64, *id, 100, 1, Side::Buy);
+        assert!(journal.append(&event).is_ok());
+    }
+    assert_eq!(journal.trimmed_floor(), Some(5));
+    assert_eq!(journal.len(), 3);
+    assert_eq!(journal.trim().unwrap(), 0);
+
+    // The order index follows the trimmed positions
+    assert!(journal.events_for_order(ids[1]).unwrap().is_empty());
+    let events = journal.events_for_order(ids[6]).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].sequence_num, 6);
+
+    journal
+        .set_retention(RetentionPolicy::SequenceWindow(1))
+        .unwrap();
+    journal.checkpoint(7).unwrap();
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal.last_sequence(), Some(7));
+}
+
+#[test]
+fn in_memory_journal_read_below_trimmed_floor_errors() {
+    use orderbook_rs::RetentionPolicy;
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for seq in 0..10 {
+        let event = make_add_event(seq, Id::new_uuid(), 100, 1, Side::Buy);
+        assert!(journal.append(&event).is_ok());
+    }
+    journal
+        .set_retention(RetentionPolicy::SequenceWindow(4))
+        .unwrap();
+    journal.checkpoint(9).unwrap();
+    assert_eq!(journal.len(), 4);
+
+    let err = journal.read_from(5).err().expect("trimmed read");
+    assert!(matches!(
+        err,
+        orderbook_rs::JournalError::Trimmed {
+            requested: 5,
+            earliest_available: 6,
+        }
+    ));
+    assert!(err.to_string().contains("earliest available is 6"));
+    assert_eq!(journal.read_from(6).unwrap().count(), 4);
+
+    assert!(matches!(
+        ReplayEngine::<()>::replay_from(&journal, 0, "TEST"),
+        Err(ReplayError::Trimmed {
+            earliest_available: 6
+        })
+    ));
+    let (book, last) = ReplayEngine::<()>::replay_from(&journal, 6, "TEST").expect("replay");
+    assert_eq!(last, 9);
+    assert_eq!(book.create_snapshot(usize::MAX).total_bid_volume(), 4);
+}
+
 // ─── JournalError Display ───────────────────────────────────────────────────
 
 #[test]
@@ -604,6 +1009,17 @@ fn journal_error_display_sequence_not_found() {
     assert!(display.contains("999"));
 }
 
+#[test]
+fn journal_error_display_out_of_order_append() {
+    let err = orderbook_rs::JournalError::OutOfOrderAppend {
+        expected_gt: 7,
+        found: 5,
+    };
+    let display = err.to_string();
+    assert!(display.contains("sequence 5"));
+    assert!(display.contains("last sequence 7"));
+}
+
 #[test]
 fn journal_error_display_invalid_entry_header() {
     let err = orderbook_rs::JournalError::InvalidEntryHeader {
@@ -630,3 +1046,917 @@ fn replay_error_snapshot_mismatch_display() {
     let err = ReplayError::SnapshotMismatch;
     assert!(err.to_string().contains("mismatch"));
 }
+
+// ─── Custom extra_fields ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct MyMeta {
+    strategy: String,
+    tag: u32,
+}
+
+fn meta_order(
+    id: Id,
+    price: u128,
+    qty: u64,
+    side: Side,
+    tag: u32,
+) -> pricelevel::OrderType<MyMeta> {
+    pricelevel::OrderType::Standard {
+        id,
+        price: Price::new(price),
+        quantity: Quantity::new(qty),
+        side,
+        time_in_force: TimeInForce::Gtc,
+        user_id: Hash32::zero(),
+        timestamp: TimestampMs::new(0),
+        extra_fields: MyMeta {
+            strategy: "mm-alpha".to_string(),
+            tag,
+        },
+    }
+}
+
+#[test]
+fn replay_preserves_custom_extra_fields() {
+    use orderbook_rs::OrderBook;
+
+    let live: OrderBook<MyMeta> = OrderBook::new("META");
+    let journal: InMemoryJournal<MyMeta> = InMemoryJournal::new();
+
+    let bid_id = Id::new_uuid();
+    let ask_id = Id::new_uuid();
+    let taker_id = Id::new_uuid();
+    let orders = vec![
+        meta_order(bid_id, 100, 10, Side::Buy, 1),
+        meta_order(ask_id, 110, 10, Side::Sell, 2),
+        // Partially fills the resting ask; its metadata must survive.
+        meta_order(taker_id, 110, 4, Side::Buy, 3),
+    ];
+
+    // Execute each command against the live book and journal the event,
+    // going through JSON to mimic durable storage of the generic payload.
+    for (seq, order) in orders.into_iter().enumerate() {
+        let order_id = order.id();
+        let result = match live.add_order(order.clone()) {
+            Ok(_) => SequencerResult::OrderAdded { order_id },
+            Err(e) => SequencerResult::Rejected {
+                reason: e.to_string(),
+            },
+        };
+        let event = SequencerEvent {
+            sequence_num: seq as u64,
+            timestamp_ns: 0,
+            command: SequencerCommand::AddOrder(order),
+            result,
+        };
+        let json = serde_json::to_string(&event).expect("serialize event");
+        let stored: SequencerEvent<MyMeta> = serde_json::from_str(&json).expect("deserialize");
+        assert!(journal.append(&stored).is_ok());
+    }
+
+    let (replayed, last_seq) =
+        ReplayEngine::<MyMeta>::replay_from(&journal, 0, "META").expect("replay");
+    assert_eq!(last_seq, 2);
+
+    for (id, tag) in [(bid_id, 1), (ask_id, 2)] {
+        let original = live.get_order(id).expect("live order");
+        let restored = replayed.get_order(id).expect("replayed order");
+        assert_eq!(restored.extra_fields(), original.extra_fields());
+        assert_eq!(restored.extra_fields().tag, tag);
+        assert_eq!(restored.extra_fields().strategy, "mm-alpha");
+    }
+    assert!(replayed.get_order(taker_id).is_none());
+
+    let expected = live.create_snapshot(usize::MAX);
+    let actual = replayed.create_snapshot(usize::MAX);
+    assert!(snapshots_match(&actual, &expected));
+    assert_eq!(actual.best_ask(), Some((110, 6)));
+}
+
+#[test]
+fn cancelled_order_returns_custom_extra_fields() {
+    use orderbook_rs::OrderBook;
+
+    let book: OrderBook<MyMeta> = OrderBook::new("META");
+    let id = Id::new_uuid();
+    book.add_order(meta_order(id, 100, 10, Side::Buy, 7))
+        .expect("add");
+
+    let cancelled = book.cancel_order(id).expect("cancel").expect("present");
+    assert_eq!(cancelled.extra_fields().tag, 7);
+    assert!(book.get_order(id).is_none());
+}
+
+// ─── Idempotent cancel ──────────────────────────────────────────────────────
+
+#[test]
+fn replay_duplicate_idempotent_cancel_is_not_an_error() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let id = Id::new_uuid();
+    let keep = Id::new_uuid();
+    assert!(
+        journal
+            .append(&make_add_event(0, id, 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&make_add_event(1, keep, 99, 5, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 2,
+                timestamp_ns: 0,
+                command: SequencerCommand::CancelOrderIdempotent(id),
+                result: SequencerResult::OrderCancelled { order_id: id },
+            })
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 3,
+                timestamp_ns: 0,
+                command: SequencerCommand::CancelOrderIdempotent(id),
+                result: SequencerResult::CancelAlreadyGone { order_id: id },
+            })
+            .is_ok()
+    );
+
+    let (book, last_seq) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(last_seq, 3);
+    assert!(book.get_order(id).is_none());
+    assert!(book.get_order(keep).is_some());
+}
+
+// ─── Reference price ────────────────────────────────────────────────────────
+
+#[test]
+fn replay_applies_manual_reference_price() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let id = Id::new_uuid();
+    assert!(
+        journal
+            .append(&make_add_event(0, id, 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 1,
+                timestamp_ns: 0,
+                command: SequencerCommand::SetReferencePrice { price: 105 },
+                result: SequencerResult::ReferencePriceSet { price: 105 },
+            })
+            .is_ok()
+    );
+
+    let (book, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(book.reference_price(), Some(105));
+}
+
+// ─── External BBO ───────────────────────────────────────────────────────────
+
+#[test]
+fn replay_applies_external_bbo() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 0,
+                timestamp_ns: 0,
+                command: SequencerCommand::SetExternalBbo {
+                    bbo: Some((100, 105)),
+                },
+                result: SequencerResult::ExternalBboSet {
+                    bbo: Some((100, 105)),
+                },
+            })
+            .is_ok()
+    );
+
+    let (book, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(book.external_bbo(), Some((100, 105)));
+    assert!(book.would_lock_external_bbo(105, Side::Buy));
+}
+
+// ─── Point-in-time snapshots ────────────────────────────────────────────────
+
+#[test]
+fn replay_services_snapshot_commands_in_sequence() {
+    use orderbook_rs::OrderBook;
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let live: OrderBook<()> = OrderBook::new("TEST");
+    let mut journaled = Vec::new();
+
+    // add, add, snapshot, add, cancel, snapshot
+    let ids: Vec<Id> = (0..3).map(|_| Id::new_uuid()).collect();
+    let mut seq = 0u64;
+    let push = |event: SequencerEvent<()>| {
+        assert!(journal.append(&event).is_ok());
+    };
+    for (i, &id) in ids.iter().enumerate() {
+        let price = 100 + i as u128;
+        let event = make_add_event(seq, id, price, 10, Side::Buy);
+        if let SequencerCommand::AddOrder(order) = &event.command {
+            live.add_order(*order).expect("add");
+        }
+        push(event);
+        seq += 1;
+
+        if i == 1 {
+            let checksum = live.create_snapshot(usize::MAX).content_checksum().unwrap();
+            journaled.push(checksum.clone());
+            push(SequencerEvent {
+                sequence_num: seq,
+                timestamp_ns: 0,
+                command: SequencerCommand::Snapshot { depth: usize::MAX },
+                result: SequencerResult::SnapshotTaken {
+                    depth: usize::MAX,
+                    checksum,
+                },
+            });
+            seq += 1;
+        }
+    }
+    live.cancel_order(ids[0]).expect("cancel");
+    push(make_cancel_event(seq, ids[0]));
+    seq += 1;
+    let checksum = live.create_snapshot(usize::MAX).content_checksum().unwrap();
+    journaled.push(checksum.clone());
+    push(SequencerEvent {
+        sequence_num: seq,
+        timestamp_ns: 0,
+        command: SequencerCommand::Snapshot { depth: usize::MAX },
+        result: SequencerResult::SnapshotTaken {
+            depth: usize::MAX,
+            checksum,
+        },
+    });
+
+    let snapshots = ReplayEngine::<()>::replay_snapshots(&journal, "TEST").expect("replay");
+    let sequences: Vec<u64> = snapshots.iter().map(|(s, _)| *s).collect();
+    assert_eq!(sequences, vec![2, 5]);
+
+    // First snapshot: only the two adds before it
+    assert_eq!(snapshots[0].1.bids.len(), 2);
+    assert_eq!(snapshots[0].1.best_bid().map(|(p, _)| p), Some(101));
+    // Second snapshot: third add and the cancel applied
+    assert_eq!(snapshots[1].1.bids.len(), 2);
+    assert_eq!(snapshots[1].1.best_bid().map(|(p, _)| p), Some(102));
+
+    // Each rebuilt snapshot matches the checksum journaled live
+    for ((_, replayed), expected) in snapshots.iter().zip(&journaled) {
+        assert_eq!(&replayed.content_checksum().unwrap(), expected);
+    }
+}
+
+// ─── Requeue ────────────────────────────────────────────────────────────────
+
+#[test]
+fn replay_applies_requeue_order() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let first = Id::new_uuid();
+    let second = Id::new_uuid();
+    let requeued = Id::new_uuid();
+    assert!(
+        journal
+            .append(&make_add_event(0, first, 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&make_add_event(1, second, 100, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(
+        journal
+            .append(&SequencerEvent {
+                sequence_num: 2,
+                timestamp_ns: 2,
+                command: SequencerCommand::RequeueOrder(first),
+                result: SequencerResult::OrderRequeued {
+                    order_id: first,
+                    new_order_id: requeued,
+                },
+            })
+            .is_ok()
+    );
+    // The requeued order is addressed by its journaled id
+    assert!(journal.append(&make_cancel_event(3, requeued)).is_ok());
+
+    let (book, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert!(book.get_order(first).is_none());
+    assert!(book.get_order(requeued).is_none());
+    assert_eq!(book.get_orders_at_price(100, Side::Buy).len(), 1);
+    assert!(book.get_order(second).is_some());
+}
+
+#[test]
+fn replay_requeue_keeps_priority_order() {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let first = Id::new_uuid();
+    let second = Id::new_uuid();
+    let events = [
+        make_add_event(0, first, 100, 10, Side::Buy),
+        make_add_event(1, second, 100, 10, Side::Buy),
+        SequencerEvent {
+            sequence_num: 2,
+            timestamp_ns: 2,
+            command: SequencerCommand::RequeueOrder(first),
+            result: SequencerResult::OrderRequeued {
+                order_id: first,
+                new_order_id: Id::new_uuid(),
+            },
+        },
+    ];
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+
+    let (book, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    // The requeued order lost priority to `second`
+    let result = book
+        .submit_market_order(Id::new_uuid(), 10, Side::Sell)
+        .expect("market order");
+    assert_eq!(result.trades().as_vec()[0].maker_order_id(), second);
+}
+
+// ─── Replay to channel ──────────────────────────────────────────────────────
+
+fn channel_journal() -> InMemoryJournal<()> {
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let resting = Id::new_uuid();
+    let events = vec![
+        make_add_event(0, Id::new_uuid(), 100, 10, Side::Buy),
+        make_add_event(1, resting, 99, 5, Side::Buy),
+        make_add_event(2, Id::new_uuid(), 101, 10, Side::Sell),
+        make_add_event(3, Id::new_uuid(), 102, 7, Side::Sell),
+        make_rejected_event(4),
+        // Crosses the best bid and partially fills it
+        make_add_event(5, Id::new_uuid(), 100, 4, Side::Sell),
+        make_cancel_event(6, resting),
+    ];
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+    journal
+}
+
+#[test]
+fn replay_to_channel_consumer_matches_direct_replay() {
+    let journal = channel_journal();
+    let (sender, receiver) = std::sync::mpsc::channel::<ReplayedEvent<()>>();
+
+    let consumer = std::thread::spawn(move || {
+        let mut sequences = Vec::new();
+        // Keyed by (is_bid, price)
+        let mut depth = std::collections::BTreeMap::new();
+        for replayed in receiver {
+            sequences.push(replayed.event.sequence_num);
+            for change in replayed.changes {
+                if change.quantity == 0 {
+                    depth.remove(&(change.side == Side::Buy, change.price));
+                } else {
+                    depth.insert((change.side == Side::Buy, change.price), change.quantity);
+                }
+            }
+        }
+        (sequences, depth)
+    });
+
+    let (book, last) =
+        ReplayEngine::<()>::replay_to_channel(&journal, 0, "TEST", sender).expect("replay");
+    let (sequences, depth) = consumer.join().expect("consumer");
+
+    // The rejected event is not forwarded
+    assert_eq!(sequences, vec![0, 1, 2, 3, 5, 6]);
+    assert_eq!(last, 6);
+
+    let (direct, _) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    let expected = direct.create_snapshot(usize::MAX);
+    assert!(snapshots_match(
+        &book.create_snapshot(usize::MAX),
+        &expected
+    ));
+
+    let mut levels = std::collections::BTreeMap::new();
+    for level in &expected.bids {
+        levels.insert((true, level.price()), level.visible_quantity());
+    }
+    for level in &expected.asks {
+        levels.insert((false, level.price()), level.visible_quantity());
+    }
+    assert_eq!(depth, levels);
+}
+
+#[test]
+fn replay_to_channel_dropped_receiver_returns_error() {
+    let journal = channel_journal();
+    let (sender, receiver) = std::sync::mpsc::channel::<ReplayedEvent<()>>();
+    drop(receiver);
+
+    let result = ReplayEngine::<()>::replay_to_channel(&journal, 0, "TEST", sender);
+    assert!(matches!(
+        result,
+        Err(ReplayError::ChannelClosed { sequence_num: 0 })
+    ));
+}
+
+// ─── Compact journal ────────────────────────────────────────────────────────
+
+#[test]
+fn replay_from_compact_journal_matches_full_journal() {
+    use orderbook_rs::OrderBook;
+    use orderbook_rs::orderbook::sequencer::execute_command;
+    use pricelevel::OrderType;
+
+    let order = |price: u128, quantity: u64, side: Side| OrderType::Standard {
+        id: Id::new_uuid(),
+        price: Price::new(price),
+        quantity: Quantity::new(quantity),
+        side,
+        time_in_force: TimeInForce::Gtc,
+        user_id: Hash32::zero(),
+        timestamp: TimestampMs::new(0),
+        extra_fields: (),
+    };
+    let resting = order(99, 5, Side::Buy);
+    let commands = vec![
+        SequencerCommand::AddOrder(order(100, 10, Side::Buy)),
+        SequencerCommand::AddOrder(resting),
+        SequencerCommand::AddOrder(order(101, 10, Side::Sell)),
+        // Trades against the best bid
+        SequencerCommand::AddOrder(order(100, 4, Side::Sell)),
+        SequencerCommand::CancelOrder(Id::new_uuid()),
+        SequencerCommand::CancelOrder(resting.id()),
+        SequencerCommand::MarketOrder {
+            id: Id::new_uuid(),
+            quantity: 3,
+            side: Side::Buy,
+        },
+    ];
+
+    let live: OrderBook<()> = OrderBook::new("TEST");
+    let full: InMemoryJournal<()> = InMemoryJournal::new();
+    let compact: CompactJournal<()> = CompactJournal::new();
+    for (seq, command) in commands.into_iter().enumerate() {
+        let result = execute_command(&live, &command);
+        let event = SequencerEvent {
+            sequence_num: seq as u64,
+            timestamp_ns: seq as u64,
+            command,
+            result,
+        };
+        assert!(full.append(&event).is_ok());
+        assert!(compact.append(&event).is_ok());
+    }
+    assert_eq!(compact.len(), 7);
+
+    let (from_full, last_full) = ReplayEngine::<()>::replay_from(&full, 0, "TEST").expect("full");
+    let (from_compact, last_compact) =
+        ReplayEngine::<()>::replay_from(&compact, 0, "TEST").expect("compact");
+    assert_eq!(last_full, last_compact);
+    let expected = from_full.create_snapshot(usize::MAX);
+    assert!(snapshots_match(
+        &from_compact.create_snapshot(usize::MAX),
+        &expected
+    ));
+    assert!(snapshots_match(
+        &live.create_snapshot(usize::MAX),
+        &expected
+    ));
+
+    // The unknown-order cancel keeps its rejection, everything else is unrecorded
+    let events: Vec<SequencerEvent<()>> = compact
+        .read_from(0)
+        .expect("read")
+        .map(|entry| entry.expect("entry").event)
+        .collect();
+    assert!(matches!(events[4].result, SequencerResult::Rejected { .. }));
+    assert!(matches!(events[3].result, SequencerResult::Unrecorded));
+}
+
+#[test]
+fn compact_event_round_trip_keeps_replay_fields() {
+    let id = Id::new_uuid();
+    let full = make_add_event(7, id, 100, 10, Side::Buy);
+    let compact = CompactEvent::from(&full);
+    assert_eq!(compact.result, CompactResult::Applied);
+    assert!(std::mem::size_of::<CompactEvent<()>>() < std::mem::size_of::<SequencerEvent<()>>());
+
+    let expanded = SequencerEvent::from(compact);
+    assert_eq!(expanded.sequence_num, 7);
+    assert!(matches!(expanded.command, SequencerCommand::AddOrder(order) if order.id() == id));
+    assert!(matches!(expanded.result, SequencerResult::Unrecorded));
+
+    let rejected = CompactEvent::from(make_rejected_event(8));
+    assert!(matches!(
+        rejected.result,
+        CompactResult::Rejected { ref reason } if &**reason == "test rejection"
+    ));
+    let journal: CompactJournal<()> = CompactJournal::new();
+    assert!(journal.append(&make_rejected_event(8)).is_ok());
+    assert!(matches!(
+        journal.append(&make_rejected_event(8)),
+        Err(orderbook_rs::JournalError::OutOfOrderAppend { .. })
+    ));
+}
+
+// ─── Contingent orders ──────────────────────────────────────────────────────
+
+#[test]
+fn triggered_contingent_is_journaled_and_replayed() {
+    use orderbook_rs::OrderBook;
+    use orderbook_rs::orderbook::sequencer::BatchExecutor;
+    use pricelevel::OrderType;
+    use std::collections::VecDeque;
+
+    let order = |price: u128, quantity: u64, side: Side| OrderType::Standard {
+        id: Id::new_uuid(),
+        price: Price::new(price),
+        quantity: Quantity::new(quantity),
+        side,
+        time_in_force: TimeInForce::Gtc,
+        user_id: Hash32::zero(),
+        timestamp: TimestampMs::new(0),
+        extra_fields: (),
+    };
+    let entry = order(100, 10, Side::Buy);
+    let take_profit = order(110, 10, Side::Sell);
+    let mut pending: VecDeque<SequencerCommand<()>> = VecDeque::from(vec![
+        SequencerCommand::AddOrder(entry),
+        SequencerCommand::AddContingent {
+            trigger_order_id: entry.id(),
+            order: take_profit,
+        },
+        SequencerCommand::MarketOrder {
+            id: Id::new_uuid(),
+            quantity: 10,
+            side: Side::Sell,
+        },
+    ]);
+
+    let live: OrderBook<()> = OrderBook::new("TEST");
+    let mut executor = BatchExecutor::new(16);
+    let events = executor.execute_batch(&live, &mut pending);
+
+    // The fill of the entry is followed by the submission it triggered
+    assert_eq!(events.len(), 4);
+    assert!(matches!(
+        events[1].result,
+        SequencerResult::ContingentAdded { order_id, .. } if order_id == take_profit.id()
+    ));
+    assert!(matches!(
+        events[3].command,
+        SequencerCommand::ContingentTriggered { trigger_order_id, order }
+            if trigger_order_id == entry.id() && order.id() == take_profit.id()
+    ));
+    assert!(matches!(
+        events[3].result,
+        SequencerResult::OrderRested { order_id } if order_id == take_profit.id()
+    ));
+    assert_eq!(events[3].sequence_num, 3);
+    assert_eq!(executor.next_sequence(), 4);
+    assert!(live.get_order(take_profit.id()).is_some());
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+    let (replayed, last) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(last, 3);
+    assert!(snapshots_match(
+        &replayed.create_snapshot(usize::MAX),
+        &live.create_snapshot(usize::MAX)
+    ));
+    assert!(replayed.take_triggered_contingents().is_empty());
+}
+
+#[test]
+fn trailing_stop_is_journaled_and_replayed() {
+    use orderbook_rs::orderbook::sequencer::BatchExecutor;
+    use orderbook_rs::{OrderBook, TrailAmount};
+    use pricelevel::OrderType;
+    use std::collections::VecDeque;
+
+    let order = |price: u128, quantity: u64, side: Side| OrderType::Standard {
+        id: Id::new_uuid(),
+        price: Price::new(price),
+        quantity: Quantity::new(quantity),
+        side,
+        time_in_force: TimeInForce::Gtc,
+        user_id: Hash32::zero(),
+        timestamp: TimestampMs::new(0),
+        extra_fields: (),
+    };
+    let reference = |price: u128| SequencerCommand::SetReferencePrice { price };
+    let stop = order(90, 1, Side::Sell);
+    let mut pending: VecDeque<SequencerCommand<()>> = VecDeque::from(vec![
+        SequencerCommand::AddOrder(order(99, 1, Side::Buy)),
+        reference(100),
+        SequencerCommand::AddTrailingStop {
+            order: stop,
+            trail: TrailAmount::Offset(5),
+        },
+        // Ratchets the trigger to 99, then the fall to 99 fires it
+        reference(104),
+        reference(99),
+    ]);
+
+    let live: OrderBook<()> = OrderBook::new("TEST");
+    let mut executor = BatchExecutor::new(16);
+    let events = executor.execute_batch(&live, &mut pending);
+
+    assert_eq!(events.len(), 6);
+    assert!(matches!(
+        events[2].result,
+        SequencerResult::TrailingStopAdded { order_id, trigger_price: 95 } if order_id == stop.id()
+    ));
+    assert!(matches!(
+        events[5].command,
+        SequencerCommand::ContingentTriggered { trigger_order_id, .. } if trigger_order_id == stop.id()
+    ));
+    assert!(matches!(
+        events[5].result,
+        SequencerResult::TradeExecuted { .. }
+    ));
+    assert!(live.pending_trailing_stops().is_empty());
+    assert_eq!(live.best_bid(), None);
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for event in &events {
+        assert!(journal.append(event).is_ok());
+    }
+    let (replayed, last) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(last, 5);
+    assert!(snapshots_match(
+        &replayed.create_snapshot(usize::MAX),
+        &live.create_snapshot(usize::MAX)
+    ));
+    assert!(replayed.pending_trailing_stops().is_empty());
+    assert!(replayed.take_triggered_contingents().is_empty());
+}
+
+// ─── Self-trade prevention ──────────────────────────────────────────────────
+
+#[test]
+fn stp_decisions_are_journaled_and_replayed() {
+    use orderbook_rs::orderbook::sequencer::execute_command;
+    use orderbook_rs::{OrderBook, STPMode, STPTriggered};
+    use pricelevel::OrderType;
+
+    let order = |price: u128, quantity: u64, side: Side, user: u8| OrderType::Standard {
+        id: Id::new_uuid(),
+        price: Price::new(price),
+        quantity: Quantity::new(quantity),
+        side,
+        time_in_force: TimeInForce::Gtc,
+        user_id: Hash32::new([user; 32]),
+        timestamp: TimestampMs::new(0),
+        extra_fields: (),
+    };
+    let own_ask = order(100, 5, Side::Sell, 1);
+    let commands = vec![
+        SequencerCommand::AddOrder(order(101, 5, Side::Sell, 2)),
+        SequencerCommand::AddOrder(own_ask),
+        // Rejected before trading, but cancels the own ask
+        SequencerCommand::AddOrder(order(100, 5, Side::Buy, 1)),
+        SequencerCommand::AddOrder(order(102, 5, Side::Sell, 1)),
+        // Trades at 101, then stops at the own ask at 102
+        SequencerCommand::AddOrder(order(102, 8, Side::Buy, 1)),
+    ];
+
+    let mut live: OrderBook<()> = OrderBook::new("TEST");
+    live.set_stp_mode(STPMode::CancelBoth);
+    let full: InMemoryJournal<()> = InMemoryJournal::new();
+    let compact: CompactJournal<()> = CompactJournal::new();
+    let mut results = Vec::new();
+    for (seq, command) in commands.into_iter().enumerate() {
+        let result = execute_command(&live, &command);
+        results.push(result.clone());
+        let event = SequencerEvent {
+            sequence_num: seq as u64,
+            timestamp_ns: 0,
+            command,
+            result,
+        };
+        assert!(full.append(&event).is_ok());
+        assert!(compact.append(&event).is_ok());
+    }
+
+    let both = STPTriggered {
+        mode: STPMode::CancelBoth,
+        cancelled_taker: true,
+        cancelled_maker: true,
+    };
+    assert!(matches!(
+        results[2],
+        SequencerResult::STPTriggered { outcome, .. } if outcome == both
+    ));
+    assert!(matches!(
+        results[4],
+        SequencerResult::TradeExecuted { ref trade_result } if trade_result.stp == Some(both)
+    ));
+    assert!(live.get_order(own_ask.id()).is_none());
+
+    let expected = live.create_snapshot(usize::MAX);
+    for replayed in [
+        ReplayEngine::<()>::replay_from(&full, 0, "TEST").expect("full"),
+        ReplayEngine::<()>::replay_from(&compact, 0, "TEST").expect("compact"),
+    ] {
+        assert!(snapshots_match(
+            &replayed.0.create_snapshot(usize::MAX),
+            &expected
+        ));
+    }
+}
+
+// ─── Parked market orders ───────────────────────────────────────────────────
+
+#[test]
+fn parked_market_order_is_journaled_and_replayed() {
+    use orderbook_rs::orderbook::sequencer::{ConfigChange, execute_command_mut};
+    use orderbook_rs::{MarketOrderEmptyBookPolicy, OrderBook};
+
+    let market_id = Id::new_uuid();
+    let ask_id = Id::new_uuid();
+    let commands: Vec<SequencerCommand<()>> = vec![
+        SequencerCommand::UpdateConfig(ConfigChange::MarketOrderEmptyBookPolicy {
+            expected: MarketOrderEmptyBookPolicy::Reject,
+            policy: MarketOrderEmptyBookPolicy::ParkAsPending,
+        }),
+        SequencerCommand::MarketOrder {
+            id: market_id,
+            quantity: 6,
+            side: Side::Buy,
+        },
+        make_add_event(2, ask_id, 100, 10, Side::Sell).command,
+    ];
+
+    let mut live: OrderBook<()> = OrderBook::new("TEST");
+    let full: InMemoryJournal<()> = InMemoryJournal::new();
+    let compact: CompactJournal<()> = CompactJournal::new();
+    for (seq, command) in commands.into_iter().enumerate() {
+        let result = execute_command_mut(&mut live, &command);
+        match seq {
+            1 => assert!(matches!(
+                result,
+                SequencerResult::MarketOrderParked { order_id } if order_id == market_id
+            )),
+            2 => assert!(matches!(
+                result,
+                SequencerResult::ParkedOrdersFilled { order_id, ref parked_fills, .. }
+                    if order_id == ask_id
+                        && parked_fills.len() == 1
+                        && parked_fills[0].match_result.order_id() == market_id
+                        && parked_fills[0].summary().executed_quantity == 6
+            )),
+            _ => {}
+        }
+        let event = SequencerEvent {
+            sequence_num: seq as u64,
+            timestamp_ns: 0,
+            command,
+            result,
+        };
+        assert!(full.append(&event).is_ok());
+        assert!(compact.append(&event).is_ok());
+    }
+    assert!(live.parked_market_orders().is_empty());
+    let expected = live.create_snapshot(usize::MAX);
+    assert_eq!(expected.best_ask(), Some((100, 4)));
+
+    for replayed in [
+        ReplayEngine::<()>::replay_from(&full, 0, "TEST").expect("full"),
+        ReplayEngine::<()>::replay_from(&compact, 0, "TEST").expect("compact"),
+    ] {
+        assert!(snapshots_match(
+            &replayed.0.create_snapshot(usize::MAX),
+            &expected
+        ));
+    }
+}
+
+#[test]
+fn test_replay_applies_config_change_at_its_sequence() {
+    use orderbook_rs::OrderBook;
+    use orderbook_rs::orderbook::sequencer::{ConfigChange, execute_command_mut};
+
+    let before = Id::new_uuid();
+    let after = Id::new_uuid();
+    let commands = vec![
+        make_add_event(0, before, 105, 10, Side::Buy).command,
+        SequencerCommand::UpdateConfig(ConfigChange::TickSize {
+            expected: None,
+            tick_size: Some(10),
+        }),
+        make_add_event(2, Id::new_uuid(), 95, 10, Side::Buy).command,
+        make_add_event(3, after, 90, 10, Side::Buy).command,
+    ];
+
+    let mut live: OrderBook<()> = OrderBook::new("TEST");
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    let mut results = Vec::new();
+    for (seq, command) in commands.into_iter().enumerate() {
+        let result = execute_command_mut(&mut live, &command);
+        results.push(matches!(result, SequencerResult::Rejected { .. }));
+        let event = SequencerEvent {
+            sequence_num: seq as u64,
+            timestamp_ns: 0,
+            command,
+            result,
+        };
+        assert!(journal.append(&event).is_ok());
+    }
+    // 105 rests before the change, 95 is off the new tick, 90 is on it
+    assert_eq!(results, vec![false, false, true, false]);
+
+    let (replayed, last) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(last, 3);
+    assert_eq!(replayed.tick_size(), Some(10));
+    assert!(replayed.get_order(before).is_some());
+    assert!(replayed.get_order(after).is_some());
+    assert!(snapshots_match(
+        &replayed.create_snapshot(usize::MAX),
+        &live.create_snapshot(usize::MAX)
+    ));
+    // The replayed book validates against the new tick size
+    assert!(
+        replayed
+            .add_limit_order(Id::new_uuid(), 105, 1, Side::Buy, TimeInForce::Gtc, None)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_merge_journals_dedupes_overlapping_fragments() {
+    let ids: Vec<Id> = (0..6).map(|_| Id::new_uuid()).collect();
+    let events: Vec<SequencerEvent<()>> = ids
+        .iter()
+        .enumerate()
+        .map(|(seq, id)| make_add_event(seq as u64, *id, 100 + seq as u128, 10, Side::Sell))
+        .collect();
+    let a: InMemoryJournal<()> = InMemoryJournal::new();
+    let b: InMemoryJournal<()> = InMemoryJournal::new();
+    for event in &events[..4] {
+        assert!(a.append(event).is_ok());
+    }
+    for event in &events[2..] {
+        assert!(b.append(event).is_ok());
+    }
+
+    let merged = ReplayEngine::<()>::merge_journals(&b, &a).expect("merge");
+    let sequences: Vec<u64> = merged.iter().map(|event| event.sequence_num).collect();
+    assert_eq!(sequences, vec![0, 1, 2, 3, 4, 5]);
+
+    let journal: InMemoryJournal<()> = InMemoryJournal::new();
+    for event in &merged {
+        assert!(journal.append(event).is_ok());
+    }
+    let (book, last) = ReplayEngine::<()>::replay_from(&journal, 0, "TEST").expect("replay");
+    assert_eq!(last, 5);
+    assert!(ids.iter().all(|id| book.get_order(*id).is_some()));
+}
+
+#[test]
+fn test_merge_journals_rejects_conflicts_and_gaps() {
+    let a: InMemoryJournal<()> = InMemoryJournal::new();
+    let b: InMemoryJournal<()> = InMemoryJournal::new();
+    assert!(matches!(
+        ReplayEngine::<()>::merge_journals(&a, &b),
+        Err(ReplayError::EmptyJournal)
+    ));
+
+    let id = Id::new_uuid();
+    assert!(a.append(&make_add_event(0, id, 100, 10, Side::Buy)).is_ok());
+    assert!(
+        a.append(&make_add_event(1, Id::new_uuid(), 99, 10, Side::Buy))
+            .is_ok()
+    );
+    assert!(b.append(&make_add_event(0, id, 100, 10, Side::Buy)).is_ok());
+    assert!(b.append(&make_cancel_event(1, id)).is_ok());
+    let result = ReplayEngine::<()>::merge_journals(&a, &b);
+    assert!(matches!(
+        result,
+        Err(ReplayError::ConflictingEvent { sequence: 1 })
+    ));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "conflicting events at sequence 1"
+    );
+
+    let c: InMemoryJournal<()> = InMemoryJournal::new();
+    assert!(c.append(&make_cancel_event(3, id)).is_ok());
+    assert!(matches!(
+        ReplayEngine::<()>::merge_journals(&a, &c),
+        Err(ReplayError::SequenceGap {
+            expected: 2,
+            found: 3
+        })
+    ));
+}
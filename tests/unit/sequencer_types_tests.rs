@@ -570,4 +570,57 @@ mod tests_sequencer_types {
             assert_eq!(book.best_bid(), Some(200));
         }
     }
+
+    // ── Canonical encoding ──────────────────────────────────────────────
+
+    mod canonical_bytes {
+        use super::*;
+        use orderbook_rs::orderbook::sequencer::CANONICAL_EVENT_VERSION;
+
+        fn add_event(seq: u64, price: u128) -> SequencerEvent<()> {
+            let order_id = Id::from_u64(7);
+            make_event(
+                seq,
+                SequencerCommand::AddOrder(OrderType::Standard {
+                    id: order_id,
+                    price: Price::new(price),
+                    quantity: Quantity::new(10),
+                    side: Side::Buy,
+                    user_id: Hash32::zero(),
+                    timestamp: TimestampMs::new(1_000),
+                    time_in_force: TimeInForce::Gtc,
+                    extra_fields: (),
+                }),
+                SequencerResult::OrderRested { order_id },
+            )
+        }
+
+        #[test]
+        fn cloned_and_decoded_events_encode_identically() {
+            let event = add_event(1, 100);
+            let bytes = event.canonical_bytes().expect("encode");
+            assert_eq!(bytes[0], CANONICAL_EVENT_VERSION);
+            assert_eq!(&bytes[1..9], &1u64.to_be_bytes());
+            assert_eq!(&bytes[9..17], &1_000_000_000u64.to_be_bytes());
+
+            assert_eq!(event.clone().canonical_bytes().expect("encode"), bytes);
+            let json = serde_json::to_string(&event).expect("serialize");
+            let decoded: SequencerEvent<()> = serde_json::from_str(&json).expect("deserialize");
+            assert_eq!(decoded.canonical_bytes().expect("encode"), bytes);
+        }
+
+        #[test]
+        fn differing_events_encode_differently() {
+            let base = add_event(1, 100).canonical_bytes().expect("encode");
+            let mut later = add_event(1, 100);
+            later.timestamp_ns += 1;
+            let mut rejected = add_event(1, 100);
+            rejected.result = SequencerResult::Rejected {
+                reason: "test".to_string(),
+            };
+            for other in [add_event(2, 100), add_event(1, 101), later, rejected] {
+                assert_ne!(other.canonical_bytes().expect("encode"), base);
+            }
+        }
+    }
 }
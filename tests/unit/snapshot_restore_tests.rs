@@ -301,4 +301,88 @@ mod tests_snapshot_restore {
         assert_eq!(restored.min_order_size(), Some(1));
         assert_eq!(restored.max_order_size(), Some(999));
     }
+
+    fn scoped_restore(scope: orderbook_rs::RestoreScope) -> DefaultOrderBook {
+        use orderbook_rs::FeeSchedule;
+
+        let mut original = DefaultOrderBook::new("SCOPE");
+        populate_order_book(&original);
+        original.set_fee_schedule(Some(FeeSchedule::new(0, 4)));
+        original.set_tick_size(25);
+        let package = original.create_snapshot_package(10).expect("package");
+
+        let mut live = DefaultOrderBook::new("SCOPE");
+        live.set_fee_schedule(Some(FeeSchedule::new(-1, 2)));
+        live.set_tick_size(5);
+        live.add_limit_order(Id::from_u64(9), 9_000, 3, Side::Buy, TimeInForce::Gtc, None)
+            .expect("add bid");
+        live.restore_from_snapshot_package_with(package, scope)
+            .expect("restore");
+        live
+    }
+
+    #[test]
+    fn orders_only_restore_keeps_live_config() {
+        use orderbook_rs::{FeeSchedule, RestoreScope};
+
+        let restored = scoped_restore(RestoreScope::OrdersOnly);
+        assert_eq!(restored.fee_schedule(), Some(FeeSchedule::new(-1, 2)));
+        assert_eq!(restored.tick_size(), Some(5));
+        // The package's orders replace the live ones
+        assert!(restored.get_order(Id::from_u64(9)).is_none());
+        assert!(restored.get_order(Id::from_u64(1)).is_some());
+        assert_eq!(restored.best_bid(), Some(10_000));
+        assert_eq!(restored.best_ask(), Some(10_100));
+    }
+
+    #[test]
+    fn full_restore_overwrites_live_config() {
+        use orderbook_rs::{FeeSchedule, RestoreScope};
+
+        assert_eq!(RestoreScope::default(), RestoreScope::Full);
+        let restored = scoped_restore(RestoreScope::Full);
+        assert_eq!(restored.fee_schedule(), Some(FeeSchedule::new(0, 4)));
+        assert_eq!(restored.tick_size(), Some(25));
+        assert!(restored.get_order(Id::from_u64(9)).is_none());
+        assert_eq!(restored.best_bid(), Some(10_000));
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct MyMeta {
+        schema: u32,
+        client_tag: String,
+    }
+
+    #[test]
+    fn mapped_restore_converts_extra_fields() {
+        let original = DefaultOrderBook::new("META");
+        let order_ids = populate_order_book(&original);
+        let package = original.create_snapshot_package(10).expect("package");
+
+        let mut restored: OrderBook<MyMeta> = OrderBook::new("META");
+        let mut mapped = 0;
+        restored
+            .restore_from_snapshot_package_mapped(package, |order| {
+                mapped += 1;
+                MyMeta {
+                    schema: 2,
+                    client_tag: format!("migrated-{}", order.id()),
+                }
+            })
+            .expect("mapped restore");
+
+        assert_eq!(mapped, order_ids.len());
+        for id in order_ids {
+            let order = restored.get_order(id).expect("restored order");
+            assert_eq!(
+                *order.extra_fields(),
+                MyMeta {
+                    schema: 2,
+                    client_tag: format!("migrated-{id}"),
+                }
+            );
+        }
+        assert_eq!(restored.best_bid(), Some(10_000));
+        assert_eq!(restored.best_ask(), Some(10_100));
+    }
 }
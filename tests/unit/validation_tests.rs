@@ -784,4 +784,101 @@ mod tests {
         let msg = format!("{}", result.unwrap_err());
         assert!(msg.contains("lot size"), "Should fail on lot: {msg}");
     }
+
+    // --- Clearing constraints ---
+
+    #[test]
+    fn test_clearing_each_constraint_accepts_rejected_orders() {
+        let mut book: OrderBook<()> = OrderBook::new("BTC/USD");
+
+        book.set_tick_size(100);
+        assert!(
+            book.add_order(make_standard_order(150, 10, Side::Buy))
+                .is_err()
+        );
+        book.clear_tick_size();
+        assert_eq!(book.tick_size(), None);
+        assert!(
+            book.add_order(make_standard_order(150, 10, Side::Buy))
+                .is_ok()
+        );
+
+        book.set_lot_size(10);
+        assert!(
+            book.add_order(make_standard_order(150, 7, Side::Buy))
+                .is_err()
+        );
+        book.clear_lot_size();
+        assert_eq!(book.lot_size(), None);
+        assert!(
+            book.add_order(make_standard_order(150, 7, Side::Buy))
+                .is_ok()
+        );
+
+        book.set_min_order_size(10);
+        assert!(
+            book.add_order(make_standard_order(150, 5, Side::Buy))
+                .is_err()
+        );
+        book.clear_min_order_size();
+        assert_eq!(book.min_order_size(), None);
+        assert!(
+            book.add_order(make_standard_order(150, 5, Side::Buy))
+                .is_ok()
+        );
+
+        book.set_max_order_size(100);
+        assert!(
+            book.add_order(make_standard_order(150, 500, Side::Buy))
+                .is_err()
+        );
+        book.clear_max_order_size();
+        assert_eq!(book.max_order_size(), None);
+        assert!(
+            book.add_order(make_standard_order(150, 500, Side::Buy))
+                .is_ok()
+        );
+
+        book.set_reference_price(150);
+        book.set_price_band_bps(Some(100));
+        assert!(
+            book.add_order(make_standard_order(200, 10, Side::Buy))
+                .is_err()
+        );
+        book.set_price_band_bps(None);
+        assert!(
+            book.add_order(make_standard_order(200, 10, Side::Buy))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_zero_tick_and_lot_disable_validation() {
+        let mut book: OrderBook<()> = OrderBook::with_tick_size("BTC/USD", 0);
+        assert_eq!(book.tick_size(), None);
+        book.set_lot_size(10);
+        book.set_lot_size(0);
+        assert_eq!(book.lot_size(), None);
+        assert!(
+            book.add_order(make_standard_order(1001, 7, Side::Buy))
+                .is_ok()
+        );
+
+        // Tick without lot, and lot without tick
+        book.set_tick_size(100);
+        assert!(
+            book.add_order(make_standard_order(1000, 7, Side::Buy))
+                .is_ok()
+        );
+        book.set_tick_size(0);
+        book.set_lot_size(10);
+        assert!(
+            book.add_order(make_standard_order(1001, 10, Side::Buy))
+                .is_ok()
+        );
+        assert!(
+            book.add_order(make_standard_order(1001, 7, Side::Buy))
+                .is_err()
+        );
+    }
 }